@@ -24,5 +24,74 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .rust_protobuf_customize(protobuf_customized.clone())
         .run()?;
 
+    #[cfg(feature = "grpc")]
+    grpc_codegen("src/protocols/protos/uksmd_ctl.proto")?;
+
+    Ok(())
+}
+
+// The rust type of a message of uksmd_ctl.proto.
+#[cfg(feature = "grpc")]
+fn grpc_type(name: &str) -> String {
+    match name {
+        "google.protobuf.Empty" => "uksmd::protocols::empty::Empty".to_string(),
+        _ => format!("uksmd::protocols::uksmd_ctl::{}", name),
+    }
+}
+
+#[cfg(feature = "grpc")]
+fn snake_case(name: &str) -> String {
+    let mut s = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                s.push('_');
+            }
+            s.push(c.to_ascii_lowercase());
+        } else {
+            s.push(c);
+        }
+    }
+    s
+}
+
+// Generate the grpc server of the Control service.
+// The messages are the ones of rust-protobuf, which are encoded by
+// grpc::ProtobufCodec, so the agent can handle them as the ones of ttrpc.
+#[cfg(feature = "grpc")]
+fn grpc_codegen(proto: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let content = std::fs::read_to_string(proto)?;
+    let mut service = Service::builder().name("Control").package("MemAgent");
+
+    // rpc Name(Request) returns (Response);
+    for line in content.lines().map(str::trim) {
+        let Some(rpc) = line.strip_prefix("rpc ") else {
+            continue;
+        };
+        let parts: Vec<&str> = rpc
+            .split(|c: char| c == '(' || c == ')' || c.is_whitespace())
+            .filter(|s| !s.is_empty() && *s != "returns" && *s != ";")
+            .collect();
+        if parts.len() != 3 {
+            return Err(format!("rpc {} of {} is not right", line, proto).into());
+        }
+
+        service = service.method(
+            Method::builder()
+                .name(snake_case(parts[0]))
+                .route_name(parts[0])
+                .input_type(grpc_type(parts[1]))
+                .output_type(grpc_type(parts[2]))
+                .codec_path("crate::grpc::ProtobufCodec")
+                .build(),
+        );
+    }
+
+    Builder::new()
+        .build_client(false)
+        .compile(&[service.build()]);
+
     Ok(())
 }
@@ -0,0 +1,46 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// Embed the engine of uksmd to merge the pages of a process.
+//
+// cargo run --example embed -- PID
+
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+use structopt::StructOpt;
+use uksmd::protocols::uksmd_ctl;
+use uksmd::{Config, Engine};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let pid: u64 = std::env::args()
+        .nth(1)
+        .ok_or(anyhow!("usage: embed PID"))?
+        .parse()
+        .map_err(|e| anyhow!("pid is not right: {}", e))?;
+
+    let config = Config::from_iter(["embed", "--reaper"]);
+    let engine = Engine::new(config)?;
+
+    engine
+        .add_task(uksmd_ctl::AddRequest {
+            pid,
+            ..Default::default()
+        })
+        .await?;
+    engine.merge(uksmd_ctl::MergeRequest::default()).await?;
+
+    let status = engine.status().await?;
+    println!("{}", status);
+
+    engine
+        .del_task(uksmd_ctl::DelRequest {
+            pid,
+            ..Default::default()
+        })
+        .await?;
+    engine.shutdown(Duration::from_secs(10)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,16 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::env;
+
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    cbindgen::generate(&crate_dir)
+        .expect("generate the header of uksmd client fail")
+        .write_to_file(format!("{}/include/uksmd_client.h", crate_dir));
+}
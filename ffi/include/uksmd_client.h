@@ -0,0 +1,141 @@
+/*
+ * Copyright (C) 2024 Ant group. All rights reserved.
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * The C client of uksmd.
+ *
+ * Memory ownership:
+ * - The client that uksmd_connect returns is owned by the caller and must
+ *   be released with uksmd_disconnect.
+ * - The strings that the functions return are owned by the caller and must
+ *   be released with uksmd_free_string, not free.
+ * - The strings that the caller passes are only borrowed during the call.
+ *
+ * Return values:
+ * - UKSMD_OK if the call succeeds.
+ * - A positive gRPC status code if uksmd returns an error, for example 3
+ *   (INVALID_ARGUMENT), 7 (PERMISSION_DENIED) or 8 (RESOURCE_EXHAUSTED).
+ * - A negative UKSMD_E* if the call cannot reach uksmd.
+ */
+
+#ifndef UKSMD_CLIENT_H
+#define UKSMD_CLIENT_H
+
+/* Generated by cbindgen from ffi/src/lib.rs, do not edit. */
+
+#include <stdbool.h>
+#include <stdint.h>
+
+/**
+ * The call succeeds.
+ */
+#define UKSMD_OK 0
+
+/**
+ * An argument of the call is not right.
+ */
+#define UKSMD_EINVAL -1
+
+/**
+ * The ttrpc request fails before uksmd handles it.
+ */
+#define UKSMD_ERPC -2
+
+/**
+ * The connection to uksmd.
+ */
+typedef struct uksmd_client uksmd_client;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Connect to uksmd at addr, for example "unix:///var/run/uksmd.sock".
+ *
+ * Return NULL if the connection fails.
+ * The client must be released with uksmd_disconnect.
+ *
+ * # Safety
+ *
+ * addr must be a valid nul-terminated string.
+ */
+struct uksmd_client *uksmd_connect(const char *addr);
+
+/**
+ * Release the client that uksmd_connect returns.
+ *
+ * # Safety
+ *
+ * client must be NULL or returned by uksmd_connect, and must not be used
+ * after the call.
+ */
+void uksmd_disconnect(struct uksmd_client *client);
+
+/**
+ * Add the task pid to uksmd.
+ *
+ * # Safety
+ *
+ * client must be returned by uksmd_connect.
+ */
+int32_t uksmd_add(const struct uksmd_client *client, uint64_t pid);
+
+/**
+ * Del the task pid from uksmd and unmerge its pages.
+ *
+ * # Safety
+ *
+ * client must be returned by uksmd_connect.
+ */
+int32_t uksmd_del(const struct uksmd_client *client, uint64_t pid);
+
+/**
+ * Refresh the pages of the tasks.
+ *
+ * # Safety
+ *
+ * client must be returned by uksmd_connect.
+ */
+int32_t uksmd_refresh(const struct uksmd_client *client);
+
+/**
+ * Merge the pages of the tasks, force merges them out of the merge
+ * windows.
+ *
+ * # Safety
+ *
+ * client must be returned by uksmd_connect.
+ */
+int32_t uksmd_merge(const struct uksmd_client *client, bool force);
+
+/**
+ * Get the status of uksmd as a json object, the keys are the names of
+ * the fields of StatusResponse.
+ *
+ * The json is stored to *json, it must be released with
+ * uksmd_free_string.
+ * *json is NULL if the call fails.
+ *
+ * # Safety
+ *
+ * client must be returned by uksmd_connect, json must be a valid pointer.
+ */
+int32_t uksmd_status_json(const struct uksmd_client *client, char **json);
+
+/**
+ * Release a string that the functions of the client return.
+ *
+ * # Safety
+ *
+ * s must be NULL or returned by the functions of the client, and must not
+ * be used after the call.
+ */
+void uksmd_free_string(char *s);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* UKSMD_CLIENT_H */
@@ -0,0 +1,233 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The C client of uksmd.
+//!
+//! The memory ownership rules and the return values are documented in the
+//! header, see cbindgen.toml.
+
+use std::ffi::{c_char, CStr, CString};
+use tokio::runtime::{Builder, Runtime};
+use ttrpc::r#async::Client;
+use uksmd::protocols::{empty, json, uksmd_ctl, uksmd_ctl_ttrpc};
+
+/// The call succeeds.
+pub const UKSMD_OK: i32 = 0;
+/// An argument of the call is not right.
+pub const UKSMD_EINVAL: i32 = -1;
+/// The ttrpc request fails before uksmd handles it.
+pub const UKSMD_ERPC: i32 = -2;
+
+/// The connection to uksmd.
+pub struct UksmdClient {
+    rt: Runtime,
+    client: uksmd_ctl_ttrpc::ControlClient,
+}
+
+fn ttrpc_error_code(e: ttrpc::error::Error) -> i32 {
+    match e {
+        ttrpc::error::Error::RpcStatus(status) => status.code() as i32,
+        _ => UKSMD_ERPC,
+    }
+}
+
+/// Connect to uksmd at addr, for example "unix:///var/run/uksmd.sock".
+///
+/// Return NULL if the connection fails.
+/// The client must be released with uksmd_disconnect.
+///
+/// # Safety
+///
+/// addr must be a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn uksmd_connect(addr: *const c_char) -> *mut UksmdClient {
+    if addr.is_null() {
+        return std::ptr::null_mut();
+    }
+    let addr = match CStr::from_ptr(addr).to_str() {
+        Ok(addr) => addr,
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    let rt = match Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+    {
+        Ok(rt) => rt,
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    let c = {
+        let _guard = rt.enter();
+        match Client::connect(addr) {
+            Ok(c) => c,
+            Err(_) => return std::ptr::null_mut(),
+        }
+    };
+    let client = uksmd_ctl_ttrpc::ControlClient::new(c);
+
+    Box::into_raw(Box::new(UksmdClient { rt, client }))
+}
+
+/// Release the client that uksmd_connect returns.
+///
+/// # Safety
+///
+/// client must be NULL or returned by uksmd_connect, and must not be used
+/// after the call.
+#[no_mangle]
+pub unsafe extern "C" fn uksmd_disconnect(client: *mut UksmdClient) {
+    if !client.is_null() {
+        let client = Box::from_raw(client);
+        client.rt.shutdown_background();
+    }
+}
+
+unsafe fn client_ref<'a>(client: *const UksmdClient) -> Option<&'a UksmdClient> {
+    client.as_ref()
+}
+
+/// Add the task pid to uksmd.
+///
+/// # Safety
+///
+/// client must be returned by uksmd_connect.
+#[no_mangle]
+pub unsafe extern "C" fn uksmd_add(client: *const UksmdClient, pid: u64) -> i32 {
+    let Some(c) = client_ref(client) else {
+        return UKSMD_EINVAL;
+    };
+    let req = uksmd_ctl::AddRequest {
+        pid,
+        ..Default::default()
+    };
+    match c
+        .rt
+        .block_on(c.client.add(ttrpc::context::with_timeout(0), &req))
+    {
+        Ok(_) => UKSMD_OK,
+        Err(e) => ttrpc_error_code(e),
+    }
+}
+
+/// Del the task pid from uksmd and unmerge its pages.
+///
+/// # Safety
+///
+/// client must be returned by uksmd_connect.
+#[no_mangle]
+pub unsafe extern "C" fn uksmd_del(client: *const UksmdClient, pid: u64) -> i32 {
+    let Some(c) = client_ref(client) else {
+        return UKSMD_EINVAL;
+    };
+    let req = uksmd_ctl::DelRequest {
+        pid,
+        ..Default::default()
+    };
+    match c
+        .rt
+        .block_on(c.client.del(ttrpc::context::with_timeout(0), &req))
+    {
+        Ok(_) => UKSMD_OK,
+        Err(e) => ttrpc_error_code(e),
+    }
+}
+
+/// Refresh the pages of the tasks.
+///
+/// # Safety
+///
+/// client must be returned by uksmd_connect.
+#[no_mangle]
+pub unsafe extern "C" fn uksmd_refresh(client: *const UksmdClient) -> i32 {
+    let Some(c) = client_ref(client) else {
+        return UKSMD_EINVAL;
+    };
+    match c.rt.block_on(
+        c.client
+            .refresh(ttrpc::context::with_timeout(0), &empty::Empty::new()),
+    ) {
+        Ok(_) => UKSMD_OK,
+        Err(e) => ttrpc_error_code(e),
+    }
+}
+
+/// Merge the pages of the tasks, force merges them out of the merge
+/// windows.
+///
+/// # Safety
+///
+/// client must be returned by uksmd_connect.
+#[no_mangle]
+pub unsafe extern "C" fn uksmd_merge(client: *const UksmdClient, force: bool) -> i32 {
+    let Some(c) = client_ref(client) else {
+        return UKSMD_EINVAL;
+    };
+    let req = uksmd_ctl::MergeRequest {
+        force,
+        ..Default::default()
+    };
+    match c
+        .rt
+        .block_on(c.client.merge(ttrpc::context::with_timeout(0), &req))
+    {
+        Ok(_) => UKSMD_OK,
+        Err(e) => ttrpc_error_code(e),
+    }
+}
+
+/// Get the status of uksmd as a json object, the keys are the names of
+/// the fields of StatusResponse.
+///
+/// The json is stored to *json, it must be released with
+/// uksmd_free_string.
+/// *json is NULL if the call fails.
+///
+/// # Safety
+///
+/// client must be returned by uksmd_connect, json must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn uksmd_status_json(
+    client: *const UksmdClient,
+    json: *mut *mut c_char,
+) -> i32 {
+    if json.is_null() {
+        return UKSMD_EINVAL;
+    }
+    *json = std::ptr::null_mut();
+    let Some(c) = client_ref(client) else {
+        return UKSMD_EINVAL;
+    };
+
+    let resp = match c.rt.block_on(
+        c.client
+            .status(ttrpc::context::with_timeout(0), &empty::Empty::new()),
+    ) {
+        Ok(resp) => resp,
+        Err(e) => return ttrpc_error_code(e),
+    };
+
+    // serde_json does not put nul in the strings, it escapes them.
+    match CString::new(json::message_json(&resp).to_string()) {
+        Ok(s) => {
+            *json = s.into_raw();
+            UKSMD_OK
+        }
+        Err(_) => UKSMD_ERPC,
+    }
+}
+
+/// Release a string that the functions of the client return.
+///
+/// # Safety
+///
+/// s must be NULL or returned by the functions of the client, and must not
+/// be used after the call.
+#[no_mangle]
+pub unsafe extern "C" fn uksmd_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
@@ -0,0 +1,87 @@
+/*
+ * Copyright (C) 2024 Ant group. All rights reserved.
+ *
+ * SPDX-License-Identifier: Apache-2.0
+ *
+ * Drive the C client by dlopen, the library and the address of a socket
+ * that closes the connections are the arguments.
+ * Exit with the number of the checks that fail.
+ */
+
+#include <dlfcn.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "uksmd_client.h"
+
+static int failed;
+
+#define CHECK(cond)                                                    \
+	do {                                                           \
+		if (!(cond)) {                                         \
+			fprintf(stderr, "%s:%d: %s\n", __FILE__, __LINE__, #cond); \
+			failed++;                                      \
+		}                                                      \
+	} while (0)
+
+#define SYM(lib, name) __typeof__(name) *p_##name = dlsym(lib, #name)
+
+int main(int argc, char **argv)
+{
+	void *lib;
+	struct uksmd_client *client;
+	char *json = (char *)1;
+
+	if (argc != 3) {
+		fprintf(stderr, "usage: %s <library> <addr>\n", argv[0]);
+		return 1;
+	}
+	lib = dlopen(argv[1], RTLD_NOW);
+	if (!lib) {
+		fprintf(stderr, "dlopen %s failed: %s\n", argv[1], dlerror());
+		return 1;
+	}
+
+	SYM(lib, uksmd_connect);
+	SYM(lib, uksmd_disconnect);
+	SYM(lib, uksmd_add);
+	SYM(lib, uksmd_del);
+	SYM(lib, uksmd_refresh);
+	SYM(lib, uksmd_merge);
+	SYM(lib, uksmd_status_json);
+	SYM(lib, uksmd_free_string);
+	CHECK(p_uksmd_connect && p_uksmd_disconnect && p_uksmd_add &&
+	      p_uksmd_del && p_uksmd_refresh && p_uksmd_merge &&
+	      p_uksmd_status_json && p_uksmd_free_string);
+	if (failed)
+		return failed;
+
+	/* The arguments are checked before uksmd is called. */
+	CHECK(p_uksmd_connect(NULL) == NULL);
+	CHECK(p_uksmd_connect("unix:///nonexistent/uksmd.sock") == NULL);
+	CHECK(p_uksmd_add(NULL, 1) == UKSMD_EINVAL);
+	CHECK(p_uksmd_del(NULL, 1) == UKSMD_EINVAL);
+	CHECK(p_uksmd_refresh(NULL) == UKSMD_EINVAL);
+	CHECK(p_uksmd_merge(NULL, true) == UKSMD_EINVAL);
+	CHECK(p_uksmd_status_json(NULL, NULL) == UKSMD_EINVAL);
+	CHECK(p_uksmd_status_json(NULL, &json) == UKSMD_EINVAL);
+	CHECK(json == NULL);
+	/* NULL can be released. */
+	p_uksmd_free_string(NULL);
+	p_uksmd_disconnect(NULL);
+
+	/* The peer closes the connection before it answers. */
+	client = p_uksmd_connect(argv[2]);
+	CHECK(client != NULL);
+	if (client) {
+		CHECK(p_uksmd_add(client, 1) == UKSMD_ERPC);
+		json = (char *)1;
+		CHECK(p_uksmd_status_json(client, &json) == UKSMD_ERPC);
+		CHECK(json == NULL);
+		p_uksmd_free_string(json);
+		p_uksmd_disconnect(client);
+	}
+
+	dlclose(lib);
+	return failed;
+}
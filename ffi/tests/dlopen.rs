@@ -0,0 +1,71 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// Build tests/client.c with the header of the library and run it against
+// the library that cargo builds.
+
+use std::os::unix::net::UnixListener;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// The library is in the target dir, the test is in its deps dir.
+fn library() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    [deps, deps.parent().unwrap()]
+        .iter()
+        .map(|dir| dir.join("libuksmd_client.so"))
+        .find(|lib| lib.exists())
+        .expect("libuksmd_client.so is not built")
+}
+
+#[test]
+fn test_c_client() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = std::env::temp_dir().join(format!("uksmd-ffi-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let client = dir.join("client");
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = match Command::new(&cc)
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg(manifest.join("tests/client.c"))
+        .arg("-o")
+        .arg(&client)
+        .arg("-ldl")
+        .status()
+    {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("skip the C client test, run {} failed: {}", cc, e);
+            return;
+        }
+    };
+    assert!(status.success(), "build tests/client.c failed");
+
+    // A peer that closes the connections without an answer.
+    let sock = dir.join("uksmd.sock");
+    let listener = UnixListener::bind(&sock).unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            drop(stream);
+        }
+    });
+
+    let output = Command::new(&client)
+        .arg(library())
+        .arg(format!("unix://{}", sock.display()))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
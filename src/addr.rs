@@ -0,0 +1,64 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The address of the control socket that uksmd and uksmd-ctl use.
+
+use std::path::Path;
+
+/// The environment variable of the address, which is used if --addr is not
+/// given.
+pub const ADDR_ENV: &str = "UKSMD_ADDR";
+
+/// The address that is used if nothing else is set.
+pub const DEFAULT_ADDR: &str = "unix:///var/run/uksmd.sock";
+
+/// The name of the socket in XDG_RUNTIME_DIR.
+pub const RUNTIME_SOCK: &str = "uksmd.sock";
+
+/// Resolve the address, the precedence is:
+/// addr from --addr or UKSMD_ADDR, the socket in runtime_dir if it exists,
+/// and DEFAULT_ADDR.
+pub fn resolve(addr: Option<String>, runtime_dir: Option<&str>) -> String {
+    if let Some(addr) = addr {
+        return addr;
+    }
+
+    if let Some(dir) = runtime_dir {
+        let sock = Path::new(dir).join(RUNTIME_SOCK);
+        if sock.exists() {
+            return format!("unix://{}", sock.display());
+        }
+    }
+
+    DEFAULT_ADDR.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let dir = std::env::temp_dir().join(format!("uksmd-addr-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let runtime_dir = dir.to_str();
+        let flag = || Some("unix:///tmp/flag.sock".to_string());
+
+        // The socket in the runtime dir is only used if it exists.
+        assert_eq!(resolve(None, None), DEFAULT_ADDR);
+        assert_eq!(resolve(None, runtime_dir), DEFAULT_ADDR);
+        assert_eq!(resolve(flag(), runtime_dir), "unix:///tmp/flag.sock");
+
+        std::fs::write(dir.join(RUNTIME_SOCK), "").unwrap();
+        assert_eq!(
+            resolve(None, runtime_dir),
+            format!("unix://{}/uksmd.sock", dir.display())
+        );
+        assert_eq!(resolve(flag(), runtime_dir), "unix:///tmp/flag.sock");
+        assert_eq!(resolve(None, None), DEFAULT_ADDR);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -2,20 +2,59 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::cmdqueue::{CmdQueue, CmdReceiver, QueuedCmd};
 use crate::protocols::uksmd_ctl;
-use crate::task;
+use crate::{config, discovery, events, page, proc, reaper, task, window};
 use anyhow::{anyhow, Result};
-use tokio::runtime::{Builder, Runtime};
+use chrono::Local;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Handle;
 use tokio::select;
 use tokio::sync::mpsc;
 use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{self, timeout};
+
+// The top crcs that CrcHistogram returns if the request doesn't set it.
+const CRC_HISTOGRAM_TOP: usize = 10;
+
+// Seconds between the checks of the merge windows.
+const WINDOW_CHECK_SECS: u64 = 10;
+
+// Seconds between the checks of the load average.
+const LOAD_CHECK_SECS: u64 = 5;
 
 #[derive(Debug)]
 pub enum AgentCmd {
     Add(uksmd_ctl::AddRequest),
+    AddByContainer(uksmd_ctl::AddByContainerRequest),
     Del(uksmd_ctl::DelRequest),
     Refresh,
-    Merge,
+    Merge(uksmd_ctl::MergeRequest),
+    Status,
+    Stats,
+    SetConfig(uksmd_ctl::SetConfigRequest),
+    PauseTask(uksmd_ctl::PauseTaskRequest),
+    ResumeTask(uksmd_ctl::ResumeTaskRequest),
+    Update(uksmd_ctl::UpdateRequest),
+    RecentEvents(uksmd_ctl::RecentEventsRequest),
+    GetMaps(uksmd_ctl::GetMapsRequest),
+    WatchCgroup(uksmd_ctl::WatchCgroupRequest),
+    UnwatchCgroup(uksmd_ctl::UnwatchCgroupRequest),
+    // reload the discovery rules
+    ReloadDiscovery,
+    CrcHistogram(uksmd_ctl::CrcHistogramRequest),
+    History(uksmd_ctl::HistoryRequest),
+    UnMerge(uksmd_ctl::UnmergeRequest),
+    UnmergeAll,
+    Drain,
+    // del the task that exited
+    Reap(u64),
+    // del all the tasks that exited
+    ReapGone,
+    Shutdown,
 }
 
 #[allow(dead_code)]
@@ -23,89 +62,1077 @@ pub enum AgentCmd {
 pub enum AgentReturn {
     Ok,
     Err(anyhow::Error),
+    Add(uksmd_ctl::AddResponse),
+    Status(Box<uksmd_ctl::StatusResponse>),
+    Stats(Box<uksmd_ctl::StatsResponse>),
+    History(uksmd_ctl::HistoryResponse),
+    RecentEvents(uksmd_ctl::RecentEventsResponse),
+    GetMaps(uksmd_ctl::GetMapsResponse),
+    CrcHistogram(uksmd_ctl::CrcHistogramResponse),
+    Drain(uksmd_ctl::DrainResponse),
+    UnmergeAll(uksmd_ctl::UnmergeAllResponse),
+}
+
+// Start the refresh and merge of all the tasks when the memory pressure is
+// high, stop them when the pressure is lower than the low threshold.
+#[derive(Debug)]
+struct PsiTrigger {
+    enabled: bool,
+    metric: proc::PsiMetric,
+    high: f64,
+    low: f64,
+    samples: u32,
+    // number of the consecutive samples that are higher than high
+    high_count: u32,
+    // if the merge cycles are started by the pressure
+    active: bool,
+    pressure: f64,
+    // unmerge the pages merged last when the cpu pressure is higher than it
+    cpu_threshold: Option<f64>,
+    cpu_pressure: f64,
+}
+
+impl PsiTrigger {
+    fn new(config: &config::Config) -> Self {
+        let mut enabled = config.psi_trigger;
+        if enabled {
+            if let Err(e) = proc::read_pressure("memory", &config.psi_metric) {
+                warn!("disable psi_trigger because PSI is not available: {}", e);
+                enabled = false;
+            }
+        }
+
+        let mut cpu_threshold = config.cpu_psi_unmerge;
+        if cpu_threshold.is_some() {
+            if let Err(e) = proc::read_pressure("cpu", &config.psi_metric) {
+                warn!(
+                    "disable cpu_psi_unmerge because PSI is not available: {}",
+                    e
+                );
+                cpu_threshold = None;
+            }
+        }
+
+        Self {
+            enabled,
+            metric: config.psi_metric.clone(),
+            high: config.psi_high,
+            low: config.psi_low,
+            samples: config.psi_samples.max(1),
+            high_count: 0,
+            active: false,
+            pressure: 0.0,
+            cpu_threshold,
+            cpu_pressure: 0.0,
+        }
+    }
+
+    // Sample the cpu pressure and return true if the pages should be
+    // unmerged.
+    fn sample_cpu(&mut self) -> bool {
+        let threshold = match self.cpu_threshold {
+            Some(threshold) => threshold,
+            None => return false,
+        };
+
+        match proc::read_pressure("cpu", &self.metric) {
+            Ok(pressure) => self.cpu_pressure = pressure,
+            Err(e) => {
+                error!("proc::read_pressure cpu failed: {}", e);
+                return false;
+            }
+        }
+
+        self.cpu_pressure > threshold
+    }
+
+    // Sample the pressure and return true if a merge cycle should be started.
+    fn sample(&mut self) -> bool {
+        match proc::read_pressure("memory", &self.metric) {
+            Ok(pressure) => self.pressure = pressure,
+            Err(e) => {
+                error!("proc::read_pressure memory failed: {}", e);
+                return false;
+            }
+        }
+
+        if self.pressure > self.high {
+            self.high_count += 1;
+        } else {
+            self.high_count = 0;
+        }
+
+        if !self.active && self.high_count >= self.samples {
+            info!(
+                "memory pressure {} {} is high, start merging",
+                self.metric, self.pressure
+            );
+            self.active = true;
+        } else if self.active && self.pressure < self.low {
+            info!(
+                "memory pressure {} {} is low, stop merging",
+                self.metric, self.pressure
+            );
+            self.active = false;
+        }
+
+        self.active
+    }
+}
+
+// Start the refresh and merge of all the tasks when MemAvailable is lower
+// than the low watermark, stop them when it is higher than the high one.
+#[derive(Debug)]
+struct MemTrigger {
+    enabled: bool,
+    low: proc::MemWatermark,
+    high: proc::MemWatermark,
+    // if the merge cycles are started by the watermark
+    active: bool,
+    available: u64,
+}
+
+impl MemTrigger {
+    fn new(config: &config::Config) -> Self {
+        let (enabled, low, high) = match (config.free_mem_low, config.free_mem_high) {
+            (Some(low), Some(high)) => (true, low, high),
+            // The low watermark is also the high one without hysteresis.
+            (Some(low), None) => (true, low, low),
+            _ => (
+                false,
+                proc::MemWatermark::Bytes(0),
+                proc::MemWatermark::Bytes(0),
+            ),
+        };
+
+        Self {
+            enabled,
+            low,
+            high,
+            active: false,
+            available: 0,
+        }
+    }
+
+    // Update the state by a sample and return true if a merge cycle should
+    // be started.
+    fn update(&mut self, total: u64, available: u64) -> bool {
+        self.available = available;
+
+        if !self.active && available < self.low.bytes(total) {
+            info!("MemAvailable {} is low, start merging", available);
+            self.active = true;
+        } else if self.active && available > self.high.bytes(total) {
+            info!("MemAvailable {} is high, stop merging", available);
+            self.active = false;
+        }
+
+        self.active
+    }
+
+    fn sample(&mut self) -> bool {
+        match proc::read_mem_available() {
+            Ok((total, available)) => self.update(total, available),
+            Err(e) => {
+                error!("proc::read_mem_available failed: {}", e);
+                false
+            }
+        }
+    }
+}
+
+// Get the init pid of the container.
+#[cfg(feature = "cri")]
+async fn container_pid(config: &config::Config, container_id: &str) -> Result<u64> {
+    let endpoint = config.cri_endpoint.as_deref().ok_or_else(|| {
+        anyhow::Error::new(task::TaskError::Unavailable(
+            "cri_endpoint is not set".to_string(),
+        ))
+    })?;
+
+    crate::integrations::cri::container_pid(endpoint, container_id).await
+}
+
+#[cfg(not(feature = "cri"))]
+async fn container_pid(_config: &config::Config, _container_id: &str) -> Result<u64> {
+    Err(anyhow::Error::new(task::TaskError::Unavailable(
+        "uksmd is built without the cri feature".to_string(),
+    )))
+}
+
+// The rules to add the processes automatically.
+#[derive(Debug, Default)]
+struct Discovery {
+    path: Option<String>,
+    rules: Vec<discovery::DiscoveryRule>,
+    // the pids that cannot be added, they are not tried again until they
+    // exit or the rules are reloaded
+    rejected: HashSet<u64>,
+}
+
+impl Discovery {
+    fn new(config: &config::Config) -> Self {
+        let mut d = Self {
+            path: config.discovery_rules.clone(),
+            ..Default::default()
+        };
+        if let Err(e) = d.reload() {
+            error!("load discovery rules failed: {}", e);
+        }
+
+        d
+    }
+
+    fn reload(&mut self) -> Result<()> {
+        if let Some(path) = &self.path {
+            self.rules = discovery::load_rules(path)
+                .map_err(|e| anyhow!("discovery::load_rules {} failed: {}", path, e))?;
+            self.rejected.clear();
+            info!("load {} discovery rules from {}", self.rules.len(), path);
+        }
+
+        Ok(())
+    }
+}
+
+// Add the processes that match the discovery rules and del the discovered
+// tasks that exited.
+async fn discover(tasks: &mut task::Tasks, d: &mut Discovery) {
+    // The scan reads all the processes, keep it off the async threads.
+    let procs = match tokio::task::spawn_blocking(discovery::scan).await {
+        Ok(Ok(procs)) => procs,
+        Ok(Err(e)) => {
+            error!("discovery::scan failed: {}", e);
+            return;
+        }
+        Err(e) => {
+            error!("spawn_blocking discovery::scan failed: {}", e);
+            return;
+        }
+    };
+    let alive: HashSet<u64> = procs.iter().map(|p| p.pid).collect();
+    d.rejected.retain(|pid| alive.contains(pid));
+
+    for pid in tasks.discovered_pids().await {
+        if !alive.contains(&pid) {
+            tasks.reap(pid).await;
+        }
+    }
+
+    let tracked = tasks.pids().await;
+    for info in procs.iter().filter(|p| !tracked.contains(&p.pid)) {
+        if d.rejected.contains(&info.pid) {
+            continue;
+        }
+        let rule = match discovery::find_rule(&d.rules, info) {
+            Some(rule) => rule,
+            None => continue,
+        };
+        let req = uksmd_ctl::AddRequest {
+            pid: info.pid,
+            OptAddr: rule.addr.map(|(start, end)| {
+                uksmd_ctl::add_request::OptAddr::Addr(uksmd_ctl::Addr {
+                    start,
+                    end,
+                    ..Default::default()
+                })
+            }),
+            include_vma: rule.include_vma.clone(),
+            exclude_vma: rule.exclude_vma.clone(),
+            ..Default::default()
+        };
+        match tasks.add(req).await {
+            Ok(_) => {
+                tasks.set_discovered(info.pid, &rule.labels).await;
+                tasks.events().push(
+                    events::Severity::Info,
+                    format!("discover task {} ({})", info.pid, info.comm),
+                );
+            }
+            Err(e) => {
+                debug!("add discovered task {} failed: {}", info.pid, e);
+                d.rejected.insert(info.pid);
+            }
+        }
+    }
+}
+
+// A cgroup whose member processes are added and deleted automatically.
+#[derive(Debug)]
+struct CgroupWatch {
+    labels: Vec<String>,
+    include_vma: Vec<String>,
+    exclude_vma: Vec<String>,
+    // the pids that cannot be added, they are not tried again until they
+    // leave the cgroup
+    rejected: HashSet<u64>,
+}
+
+// cgroup path -> CgroupWatch
+type CgroupWatches = HashMap<String, CgroupWatch>;
+
+// Add the new member processes of the watched cgroups and del the tasks
+// that left them. The watch of a removed cgroup is dropped.
+async fn sync_cgroups(tasks: &mut task::Tasks, watches: &mut CgroupWatches) {
+    let watched = tasks.watched_pids().await;
+    let mut removed = Vec::new();
+
+    for (cgroup, watch) in watches.iter_mut() {
+        let pids: HashSet<u64> = match proc::read_cgroup_procs(cgroup) {
+            Ok(pids) => pids.into_iter().collect(),
+            Err(e) => {
+                if !proc::cgroup_exists(cgroup) {
+                    removed.push(cgroup.clone());
+                } else {
+                    error!("proc::read_cgroup_procs {} failed: {}", cgroup, e);
+                }
+                continue;
+            }
+        };
+        watch.rejected.retain(|pid| pids.contains(pid));
+
+        let empty = HashSet::new();
+        let tracked = watched.get(cgroup).unwrap_or(&empty);
+        for pid in tracked.difference(&pids) {
+            if tasks
+                .del(uksmd_ctl::DelRequest {
+                    pid: *pid,
+                    ..Default::default()
+                })
+                .await
+                .is_ok()
+            {
+                tasks.events().push(
+                    events::Severity::Info,
+                    format!("del task {} that left cgroup {}", pid, cgroup),
+                );
+            }
+        }
+
+        for pid in pids.difference(tracked) {
+            if watch.rejected.contains(pid) {
+                continue;
+            }
+            let req = uksmd_ctl::AddRequest {
+                pid: *pid,
+                include_vma: watch.include_vma.clone(),
+                exclude_vma: watch.exclude_vma.clone(),
+                ..Default::default()
+            };
+            match tasks.add(req).await {
+                Ok(_) => {
+                    tasks.set_watch(*pid, cgroup, &watch.labels).await;
+                    tasks.events().push(
+                        events::Severity::Info,
+                        format!("add task {} of cgroup {}", pid, cgroup),
+                    );
+                }
+                Err(e) => {
+                    debug!("add task {} of cgroup {} failed: {}", pid, cgroup, e);
+                    watch.rejected.insert(*pid);
+                }
+            }
+        }
+    }
+
+    for cgroup in removed {
+        unwatch_cgroup(tasks, watches, &cgroup).await;
+        warn!("cgroup {} is removed, unwatch it", cgroup);
+        tasks.events().push(
+            events::Severity::Warn,
+            format!("cgroup {} is removed, unwatch it", cgroup),
+        );
+    }
+}
+
+// Drop the watch of cgroup and del the tasks that are added from it.
+async fn unwatch_cgroup(tasks: &mut task::Tasks, watches: &mut CgroupWatches, cgroup: &str) {
+    watches.remove(cgroup);
+
+    let pids = tasks
+        .watched_pids()
+        .await
+        .remove(cgroup)
+        .unwrap_or_default();
+    for pid in pids {
+        let _ = tasks
+            .del(uksmd_ctl::DelRequest {
+                pid,
+                ..Default::default()
+            })
+            .await;
+    }
+}
+
+// The state of the tasks that are added automatically.
+#[derive(Debug)]
+struct AutoTasks {
+    watches: CgroupWatches,
+    discovery: Discovery,
+}
+
+fn crc_buckets(histogram: &page::CrcHistogram) -> Vec<uksmd_ctl::CrcBucket> {
+    histogram
+        .buckets
+        .iter()
+        .map(|(range, crcs, pages)| uksmd_ctl::CrcBucket {
+            range: range.to_string(),
+            crcs: *crcs,
+            pages: *pages,
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn crc_top(histogram: &page::CrcHistogram) -> Vec<uksmd_ctl::CrcCount> {
+    histogram
+        .top
+        .iter()
+        .map(|(crc, pages)| uksmd_ctl::CrcCount {
+            crc: *crc,
+            pages: *pages,
+            ..Default::default()
+        })
+        .collect()
+}
+
+// The response of an added task, the options of config are filled in for
+// the ones that the task doesn't set.
+fn add_response(config: &config::Config, scope: task::AddScope) -> uksmd_ctl::AddResponse {
+    let task = scope.task;
+    let (include_vma, exclude_vma) = if task.include_vma.is_empty() && task.exclude_vma.is_empty() {
+        (config.include_vma.clone(), config.exclude_vma.clone())
+    } else {
+        (task.include_vma, task.exclude_vma)
+    };
+
+    uksmd_ctl::AddResponse {
+        OptAddr: task.addr.map(|(start, end)| {
+            uksmd_ctl::add_response::OptAddr::Addr(uksmd_ctl::Addr {
+                start,
+                end,
+                ..Default::default()
+            })
+        }),
+        vmas: scope.vmas,
+        bytes: scope.bytes,
+        thp: task.thp.unwrap_or(config.thp).to_string(),
+        include_vma,
+        exclude_vma,
+        min_vma_size: task.min_vma_size.unwrap_or(config.min_vma_size),
+        priority: task.priority,
+        follow_children: task.follow_children,
+        comm: task.comm,
+        start_time: task.start_time,
+        pid: task.pid,
+        include_shmem: task.include_shmem.unwrap_or(config.include_shmem),
+        ..Default::default()
+    }
+}
+
+// Reply the Del waiters whose pids are unmerged and deleted, and drop the
+// ones whose callers are gone because their deadlines passed.
+async fn reply_del_waiters(
+    tasks: &task::Tasks,
+    waiters: &mut Vec<(u64, oneshot::Sender<AgentReturn>)>,
+) {
+    let mut pending = Vec::with_capacity(waiters.len());
+    for (pid, ret_tx) in waiters.drain(..) {
+        if ret_tx.is_closed() {
+            warn!("the caller of del {} is gone, discard its return", pid);
+        } else if tasks.del_pending(pid).await {
+            pending.push((pid, ret_tx));
+        } else if ret_tx.send(AgentReturn::Ok).is_err() {
+            warn!("the caller of del {} is gone, discard its return", pid);
+        }
+    }
+    *waiters = pending;
+}
+
+async fn handle_cmd(
+    tasks: &mut task::Tasks,
+    config: &config::Config,
+    psi: &PsiTrigger,
+    mem: &MemTrigger,
+    auto: &mut AutoTasks,
+    cmd: AgentCmd,
+    stopping: bool,
+) -> AgentReturn {
+    let mut ret_msg = AgentReturn::Ok;
+
+    match cmd {
+        AgentCmd::Status => {
+            let info_status = tasks.info_status().await;
+            let task_names = tasks.task_names().await;
+            let (last_merge_drained, last_merge_drain_us) = tasks.last_merge_drain();
+            ret_msg = AgentReturn::Status(Box::new(uksmd_ctl::StatusResponse {
+                worker_nice: config.worker_nice.unwrap_or(0),
+                worker_sched_idle: config.worker_sched_idle,
+                worker_ioprio_idle: config.worker_ioprio_idle,
+                skipped_pages: tasks.skipped_pages(),
+                merge_batched: tasks.merge_batched().await,
+                backend: tasks.backend_name().to_string(),
+                kernel_caps: tasks.kernel_caps(),
+                kernel_version: tasks.kernel_version(),
+                zero_pages: info_status
+                    .iter()
+                    .map(|(pid, s)| (*pid, s.zero_count))
+                    .collect(),
+                stale_pages: info_status
+                    .iter()
+                    .map(|(pid, s)| (*pid, s.stale_count))
+                    .collect(),
+                foreign_ksm_pages: info_status
+                    .iter()
+                    .map(|(pid, s)| (*pid, s.foreign_count))
+                    .collect(),
+                thp_pages: info_status
+                    .iter()
+                    .map(|(pid, s)| (*pid, s.thp_count))
+                    .collect(),
+                comm: task_names
+                    .iter()
+                    .map(|(pid, (comm, _))| (*pid, comm.clone()))
+                    .collect(),
+                cmdline: task_names
+                    .into_iter()
+                    .map(|(pid, (_, cmdline))| (pid, cmdline))
+                    .collect(),
+                paused_pids: tasks.paused_pids().await,
+                merge_window_open: tasks.merge_window_open(),
+                next_merge_window: window::next_window(
+                    &config.merge_window,
+                    Local::now().naive_local(),
+                )
+                .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+                merge_deferred: tasks.merge_deferred().await,
+                load_throttle: tasks.load_throttle().state(),
+                priority: tasks.priorities().await,
+                cross_uid_blocked: tasks.cross_uid_blocked().await,
+                parent: tasks.parents().await,
+                watch_cgroup: tasks.watches().await,
+                labels: tasks.labels().await,
+                watched_cgroups: auto.watches.keys().cloned().collect(),
+                discovered: tasks.discovered_pids().await.into_iter().collect(),
+                task_state: tasks.task_states().await,
+                kernel_lost: tasks.kernel_lost(),
+                merge_remaining: tasks.merge_remaining().await,
+                last_merge_drained,
+                last_merge_drain_us,
+                ..Default::default()
+            }));
+        }
+        AgentCmd::Stats => {
+            let mut stats = tasks.stats().await;
+            stats.memory_pressure = psi.pressure;
+            stats.pressure_merging = psi.active;
+            stats.cpu_pressure = psi.cpu_pressure;
+            stats.mem_available = mem.available;
+            stats.free_mem_merging = mem.active;
+            stats.op_timings = tasks.op_timings();
+            ret_msg = AgentReturn::Stats(Box::new(stats));
+        }
+        AgentCmd::History(req) => match tasks.history(req.pid).await {
+            Ok(samples) => {
+                ret_msg = AgentReturn::History(uksmd_ctl::HistoryResponse {
+                    samples: samples
+                        .into_iter()
+                        .map(|s| uksmd_ctl::HistorySample {
+                            time: s.time,
+                            uksm_count: s.uksm_count,
+                            saved_bytes: s.saved_bytes,
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                });
+            }
+            Err(e) => ret_msg = AgentReturn::Err(e),
+        },
+        AgentCmd::CrcHistogram(req) => {
+            let top = match req.top {
+                0 => CRC_HISTOGRAM_TOP,
+                top => top as usize,
+            };
+            let (merged, candidates) = tasks.crc_histogram(top).await;
+            ret_msg = AgentReturn::CrcHistogram(uksmd_ctl::CrcHistogramResponse {
+                merged: crc_buckets(&merged),
+                candidates: crc_buckets(&candidates),
+                top_merged: crc_top(&merged),
+                top_candidates: crc_top(&candidates),
+                ..Default::default()
+            });
+        }
+        AgentCmd::Drain => match tasks.drain().await {
+            Ok(elapsed) => {
+                ret_msg = AgentReturn::Drain(uksmd_ctl::DrainResponse {
+                    elapsed_us: elapsed.as_micros() as u64,
+                    ..Default::default()
+                });
+            }
+            Err(e) => ret_msg = AgentReturn::Err(e),
+        },
+        AgentCmd::GetMaps(req) => match tasks.get_maps(req.pid).await {
+            Ok((maps, rejects)) => {
+                ret_msg = AgentReturn::GetMaps(uksmd_ctl::GetMapsResponse {
+                    maps: maps
+                        .into_iter()
+                        .map(|m| uksmd_ctl::Addr {
+                            start: m.start,
+                            end: m.end,
+                            ..Default::default()
+                        })
+                        .collect(),
+                    rejected: rejects
+                        .into_iter()
+                        .map(|r| uksmd_ctl::RejectedMap {
+                            start: r.start,
+                            end: r.end,
+                            reason: r.reason,
+                            ..Default::default()
+                        })
+                        .collect(),
+                    ..Default::default()
+                });
+            }
+            Err(e) => ret_msg = AgentReturn::Err(e),
+        },
+        AgentCmd::RecentEvents(req) => {
+            ret_msg = AgentReturn::RecentEvents(uksmd_ctl::RecentEventsResponse {
+                events: tasks
+                    .events()
+                    .recent(req.count as usize)
+                    .into_iter()
+                    .map(|e| uksmd_ctl::RecentEvent {
+                        time: e.time.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+                        severity: e.severity.to_string(),
+                        message: e.message,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            });
+        }
+        AgentCmd::SetConfig(req) => {
+            if let Err(e) = tasks.set_config(&req).await {
+                ret_msg = AgentReturn::Err(e);
+            }
+        }
+        AgentCmd::Del(req) => {
+            let pid = req.pid;
+            match tasks.del(req).await {
+                Ok(()) => tasks
+                    .events()
+                    .push(events::Severity::Info, format!("del task {}", pid)),
+                Err(e) => ret_msg = AgentReturn::Err(e),
+            }
+        }
+        AgentCmd::Reap(pid) => {
+            tasks.reap(pid).await;
+        }
+        AgentCmd::ReapGone => {
+            tasks.reap_gone().await;
+        }
+        _ if stopping => {
+            ret_msg = AgentReturn::Err(anyhow!("uKSM agent is shutting down"));
+        }
+        AgentCmd::Add(req) => {
+            let pid = req.pid;
+            match tasks.add(req).await {
+                Ok(scope) => {
+                    tasks
+                        .events()
+                        .push(events::Severity::Info, format!("add task {}", pid));
+                    ret_msg = AgentReturn::Add(add_response(config, scope));
+                }
+                Err(e) => {
+                    tasks.events().push(
+                        events::Severity::Warn,
+                        format!("add task {} failed: {}", pid, e),
+                    );
+                    ret_msg = AgentReturn::Err(e);
+                }
+            }
+        }
+        AgentCmd::AddByContainer(req) => match container_pid(config, &req.container_id).await {
+            Ok(pid) => {
+                let mut add = req.add.into_option().unwrap_or_default();
+                add.pid = pid;
+                match tasks.add(add).await {
+                    Ok(scope) => {
+                        tasks.events().push(
+                            events::Severity::Info,
+                            format!("add task {} of container {}", pid, req.container_id),
+                        );
+                        ret_msg = AgentReturn::Add(add_response(config, scope));
+                    }
+                    Err(e) => ret_msg = AgentReturn::Err(e),
+                }
+            }
+            Err(e) => ret_msg = AgentReturn::Err(e),
+        },
+        AgentCmd::PauseTask(req) => {
+            if let Err(e) = tasks.set_paused(req.pid, true).await {
+                ret_msg = AgentReturn::Err(e);
+            }
+        }
+        AgentCmd::ResumeTask(req) => {
+            if let Err(e) = tasks.set_paused(req.pid, false).await {
+                ret_msg = AgentReturn::Err(e);
+            }
+        }
+        AgentCmd::Update(req) => {
+            if let Err(e) = tasks.update(&req).await {
+                ret_msg = AgentReturn::Err(e);
+            }
+        }
+        AgentCmd::UnMerge(req) => {
+            let addr = match req.OptAddr {
+                Some(uksmd_ctl::unmerge_request::OptAddr::Addr(addr)) => {
+                    Some((addr.start, addr.end))
+                }
+                None => None,
+            };
+            if req.pid != 0 {
+                if let Err(e) = tasks.add_range(req.pid, addr, true).await {
+                    ret_msg = AgentReturn::Err(e);
+                }
+            } else if addr.is_some() {
+                ret_msg = AgentReturn::Err(anyhow::Error::new(task::TaskError::InvalidArg(
+                    "addr is set without pid".to_string(),
+                )));
+            } else {
+                tasks.add_unmerge_recent(req.budget, true);
+            }
+        }
+        AgentCmd::UnmergeAll => {
+            let count = tasks.add_unmerge_all().await;
+            tasks.events().push(
+                events::Severity::Info,
+                format!("unmerge all {} tasks", count),
+            );
+            ret_msg = AgentReturn::UnmergeAll(uksmd_ctl::UnmergeAllResponse {
+                tasks: count as u64,
+                ..Default::default()
+            });
+        }
+        AgentCmd::Refresh => {
+            tasks.add_refresh_all().await;
+        }
+        AgentCmd::Merge(req) => {
+            let addr = match req.OptAddr {
+                Some(uksmd_ctl::merge_request::OptAddr::Addr(addr)) => Some((addr.start, addr.end)),
+                None => None,
+            };
+            if req.pid != 0 {
+                if let Err(e) = tasks.add_range(req.pid, addr, false).await {
+                    ret_msg = AgentReturn::Err(e);
+                }
+            } else if addr.is_some() {
+                ret_msg = AgentReturn::Err(anyhow::Error::new(task::TaskError::InvalidArg(
+                    "addr is set without pid".to_string(),
+                )));
+            } else {
+                if req.force {
+                    tasks.force_merge();
+                }
+                tasks.add_refresh_all().await;
+                tasks.add_merge_all().await;
+            }
+        }
+        AgentCmd::WatchCgroup(req) => {
+            let cgroup = req.path.trim_matches('/').to_string();
+            if auto.watches.contains_key(&cgroup) {
+                ret_msg = AgentReturn::Err(anyhow!("cgroup {} is watched", cgroup));
+            } else if let Err(e) = proc::VmaFilter::new(&req.include_vma, &req.exclude_vma)
+                .and_then(|_| proc::read_cgroup_procs(&cgroup))
+            {
+                ret_msg = AgentReturn::Err(anyhow::Error::new(task::TaskError::InvalidArg(
+                    format!("watch cgroup {} failed: {}", cgroup, e),
+                )));
+            } else {
+                auto.watches.insert(
+                    cgroup.clone(),
+                    CgroupWatch {
+                        labels: req.labels,
+                        include_vma: req.include_vma,
+                        exclude_vma: req.exclude_vma,
+                        rejected: HashSet::new(),
+                    },
+                );
+                tasks
+                    .events()
+                    .push(events::Severity::Info, format!("watch cgroup {}", cgroup));
+                sync_cgroups(tasks, &mut auto.watches).await;
+            }
+        }
+        AgentCmd::UnwatchCgroup(req) => {
+            let cgroup = req.path.trim_matches('/');
+            if auto.watches.contains_key(cgroup) {
+                unwatch_cgroup(tasks, &mut auto.watches, cgroup).await;
+                tasks
+                    .events()
+                    .push(events::Severity::Info, format!("unwatch cgroup {}", cgroup));
+            } else {
+                ret_msg = AgentReturn::Err(anyhow!("cgroup {} is not watched", cgroup));
+            }
+        }
+        AgentCmd::ReloadDiscovery => {
+            if let Err(e) = auto.discovery.reload() {
+                ret_msg = AgentReturn::Err(e);
+            }
+        }
+        AgentCmd::Shutdown => {}
+    }
+
+    ret_msg
+}
+
+async fn save_state(tasks: &task::Tasks, config: &config::Config) {
+    if let Some(path) = &config.state_file {
+        if let Err(e) = tasks.save_state(path).await {
+            error!("tasks.save_state {} failed: {}", path, e);
+        }
+    }
 }
 
 async fn agent_loop(
-    mut cmd_rx: mpsc::Receiver<(AgentCmd, oneshot::Sender<AgentReturn>)>,
+    mut cmd_rx: CmdReceiver,
+    queue: CmdQueue,
+    config: config::Config,
 ) -> Result<()> {
-    let mut tasks = task::Tasks::new();
+    let events = Arc::new(events::Events::new(config.events_capacity));
+    let mut tasks = task::Tasks::new(&config, events.clone());
+
+    if let Some(path) = &config.state_file {
+        tasks
+            .load_state(path)
+            .await
+            .map_err(|e| anyhow!("tasks.load_state {} failed: {}", path, e))?;
+    }
 
     let (work_ret_tx, mut work_ret_rx) = mpsc::channel(2);
+    let worker = tasks
+        .start_worker(work_ret_tx, &config)
+        .map_err(|e| anyhow!("tasks.start_worker failed: {}", e))?;
     let mut work_is_running = false;
 
+    let verify_period = Duration::from_secs(config.verify_interval.max(1));
+    let mut verify_tick = time::interval_at(time::Instant::now() + verify_period, verify_period);
+
+    let mut psi = PsiTrigger::new(&config);
+    let psi_period = Duration::from_secs(config.psi_interval.max(1));
+    let mut psi_tick = time::interval_at(time::Instant::now() + psi_period, psi_period);
+
+    let window_period = Duration::from_secs(WINDOW_CHECK_SECS);
+    let mut window_tick = time::interval(window_period);
+
+    let load_period = Duration::from_secs(LOAD_CHECK_SECS);
+    let mut load_tick = time::interval(load_period);
+
+    let mut mem = MemTrigger::new(&config);
+    let mem_period = Duration::from_secs(config.free_mem_interval.max(1));
+    let mut mem_tick = time::interval_at(time::Instant::now() + mem_period, mem_period);
+
+    let mut auto = AutoTasks {
+        watches: CgroupWatches::new(),
+        discovery: Discovery::new(&config),
+    };
+    let cgroup_period = Duration::from_secs(config.cgroup_watch_interval.max(1));
+    let mut cgroup_tick = time::interval_at(time::Instant::now() + cgroup_period, cgroup_period);
+
+    let mut discovery_tick = time::interval(Duration::from_secs(config.discovery_interval.max(1)));
+
+    let mut kernel_tick = time::interval(Duration::from_secs(config.kernel_probe_interval.max(1)));
+
+    // The state file is saved when the work cycles that changed it are done.
+    // The saves are at least state_period apart, state_tick saves the change
+    // that came too soon after the last save.
+    let state_period = Duration::from_secs(config.state_save_interval.max(1));
+    let mut state_tick = time::interval_at(time::Instant::now() + state_period, state_period);
+    let mut state_changed = false;
+    let mut state_saved = time::Instant::now()
+        .checked_sub(state_period)
+        .unwrap_or_else(time::Instant::now);
+
+    // Set when got AgentCmd::Shutdown, reply it when all the work is done.
+    let mut shutdown_tx: Option<oneshot::Sender<AgentReturn>> = None;
+
+    // The Del with wait, reply them when the unmerge and del of their pids
+    // are done.
+    let mut del_waiters: Vec<(u64, oneshot::Sender<AgentReturn>)> = Vec::new();
+
     loop {
         select! {
-            Some((cmd, ret_tx)) = cmd_rx.recv() => {
-                let mut ret_msg = AgentReturn::Ok;
-                match cmd {
-                    AgentCmd::Add(req) => {
-                        if let Err(e) = tasks.add(req).await {
-                            ret_msg = AgentReturn::Err(e);
-                        }
-                    }
-                    AgentCmd::Del(req) => {
-                        if let Err(e) = tasks.del(req).await {
-                            ret_msg = AgentReturn::Err(e);
-                        }
+            Some(QueuedCmd { cmd, ret_tx, .. }) = cmd_rx.recv() => {
+                if let AgentCmd::Shutdown = cmd {
+                    info!("uKSM agent shutdown, wait for the remaining work");
+                    tasks.stop().await;
+                    if config.unmerge_on_exit {
+                        let count = tasks.add_unmerge_all().await;
+                        info!("unmerge the pages of {} tasks before exit", count);
                     }
-                    AgentCmd::Refresh => {
-                        tasks.add_refresh_all().await;
+                    shutdown_tx = Some(ret_tx);
+                } else {
+                    let wait_pid = match &cmd {
+                        AgentCmd::Del(req) if req.wait => Some(req.pid),
+                        _ => None,
+                    };
+                    let mut ret_msg = handle_cmd(&mut tasks, &config, &psi, &mem, &mut auto, cmd, shutdown_tx.is_some()).await;
+                    if let AgentReturn::Status(status) = &mut ret_msg {
+                        status.cmd_queue_depth = queue.depth();
+                        status.cmd_queue_oldest_ms = queue.oldest_age().as_millis() as u64;
                     }
-                    AgentCmd::Merge => {
-                        tasks.add_refresh_all().await;
-                        tasks.add_merge_all().await;
+                    if let (Some(pid), AgentReturn::Ok) = (wait_pid, &ret_msg) {
+                        del_waiters.push((pid, ret_tx));
+                    } else if ret_tx.send(ret_msg).is_err() {
+                        // The caller is gone if its deadline passed, the
+                        // return is discarded and the agent keeps running.
+                        warn!("the caller of the command is gone, discard its return");
                     }
                 }
-                ret_tx.send(ret_msg).map_err(|e| anyhow!("ret_tx.send failed: {:?}", e))?;
             }
-            Some(work_ret) = work_ret_rx.recv() => {
+            _ = psi_tick.tick(), if (psi.enabled || psi.cpu_threshold.is_some()) && shutdown_tx.is_none() => {
+                if psi.enabled && psi.sample() && !tasks.load_throttle().is_throttled() {
+                    tasks.add_refresh_all().await;
+                    tasks.add_merge_all().await;
+                }
+                if psi.sample_cpu() {
+                    tasks.add_unmerge_recent(config.cpu_unmerge_pages, false);
+                }
+            }
+            _ = cgroup_tick.tick(), if !auto.watches.is_empty() && shutdown_tx.is_none() => {
+                sync_cgroups(&mut tasks, &mut auto.watches).await;
+            }
+            _ = discovery_tick.tick(), if !auto.discovery.rules.is_empty() && shutdown_tx.is_none() => {
+                discover(&mut tasks, &mut auto.discovery).await;
+            }
+            _ = kernel_tick.tick(), if tasks.kernel_lost() && shutdown_tx.is_none() => {
+                tasks.recover_kernel().await;
+            }
+            _ = load_tick.tick() => {
+                tasks.load_throttle().update();
+            }
+            _ = window_tick.tick(), if !config.merge_window.is_empty() => {
+                tasks.set_merge_window_open(window::in_windows(
+                    &config.merge_window,
+                    Local::now().naive_local(),
+                ));
+            }
+            _ = mem_tick.tick(), if mem.enabled && shutdown_tx.is_none() => {
+                if mem.sample() && !tasks.load_throttle().is_throttled() {
+                    tasks.add_refresh_all().await;
+                    tasks.add_merge_all().await;
+                }
+            }
+            _ = verify_tick.tick(), if config.verify_interval > 0 && shutdown_tx.is_none() => {
+                tasks.add_verify_all().await;
+            }
+            _ = state_tick.tick(), if state_changed => {
+                save_state(&tasks, &config).await;
+                state_changed = false;
+                state_saved = time::Instant::now();
+            }
+            work_ret = work_ret_rx.recv(), if work_is_running => {
                 work_is_running = false;
-                if let Err(e) = work_ret {
-                    error!("work task error {}", e);
+                match work_ret {
+                    Some(Ok(report)) => {
+                        info!("{}", report);
+                        events.push(events::Severity::Info, report.to_string());
+                        tasks.record_report(&report);
+                        state_changed |= config.state_file.is_some() && report.changes_state();
+                    }
+                    Some(Err(e)) => {
+                        error!("work task error {}", e);
+                        events.push(events::Severity::Error, format!("work task error {}", e));
+                    }
+                    None => error!("work_ret_rx is closed"),
                 }
             }
         }
 
+        // The queues are not changed by the worker when it is idle.
+        if !work_is_running && !del_waiters.is_empty() {
+            reply_del_waiters(&tasks, &mut del_waiters).await;
+        }
+
+        if !work_is_running && state_changed && state_saved.elapsed() >= state_period {
+            save_state(&tasks, &config).await;
+            state_changed = false;
+            state_saved = time::Instant::now();
+        }
+
         if !work_is_running {
-            work_is_running = tasks.async_work(work_ret_tx.clone()).await;
+            work_is_running = tasks.async_work(&worker).await;
+        }
+
+        if !work_is_running {
+            if let Some(ret_tx) = shutdown_tx.take() {
+                if config.unmerge_on_exit {
+                    let merged: u64 = tasks
+                        .info_status()
+                        .await
+                        .values()
+                        .map(|s| s.uksm_count)
+                        .sum();
+                    info!("uKSM agent exits, {} merged pages are not unmerged", merged);
+                }
+                save_state(&tasks, &config).await;
+                tokio::task::spawn_blocking(move || worker.join())
+                    .await
+                    .map_err(|e| anyhow!("worker.join failed: {}", e))?;
+                if ret_tx.send(AgentReturn::Ok).is_err() {
+                    warn!("the caller of Shutdown is gone, discard its return");
+                }
+                return Ok(());
+            }
         }
     }
 }
 
+// The agent runs agent_loop as a task of the runtime that creates it.
 #[derive(Debug)]
 pub struct Agent {
-    _rt: Runtime,
-    cmd_tx: mpsc::Sender<(AgentCmd, oneshot::Sender<AgentReturn>)>,
+    handle: std::sync::Mutex<Option<JoinHandle<()>>>,
+    queue: CmdQueue,
 }
 
 impl Agent {
-    pub fn new() -> Result<Self> {
-        let (cmd_tx, cmd_rx) = mpsc::channel(10);
+    // It must be called in a tokio runtime.
+    pub fn new(config: config::Config) -> Result<Self> {
+        let rt = Handle::try_current()
+            .map_err(|e| anyhow!("Agent::new is not called in a tokio runtime: {}", e))?;
+        let (queue, cmd_rx) = CmdQueue::new(
+            config.cmd_queue_depth,
+            Duration::from_millis(config.cmd_queue_wait_ms),
+        );
 
-        let rt = Builder::new_multi_thread()
-            .worker_threads(1)
-            .enable_all()
-            .build()
-            .map_err(|e| anyhow!("Builder::new_multi_thread failed: {}", e))?;
+        if config.reaper {
+            reaper::start(
+                queue.clone(),
+                Duration::from_secs(config.reaper_interval.max(1)),
+            )?;
+        }
 
-        rt.spawn(async move {
+        let loop_queue = queue.clone();
+        let handle = rt.spawn(async move {
             info!("uKSM agent start");
-            match agent_loop(cmd_rx).await {
+            match agent_loop(cmd_rx, loop_queue, config).await {
                 Err(e) => error!("uKSM agent error {}", e),
                 Ok(()) => info!("uKSM agent stop"),
             }
         });
 
-        Ok(Self { cmd_tx, _rt: rt })
+        Ok(Self {
+            queue,
+            handle: std::sync::Mutex::new(Some(handle)),
+        })
     }
 
+    // Return TaskError::Unavailable if the command queue is full.
     pub async fn send_cmd_async(&self, cmd: AgentCmd) -> Result<AgentReturn> {
-        let (ret_tx, ret_rx) = oneshot::channel();
-
-        self.cmd_tx
-            .send((cmd, ret_tx))
-            .await
-            .map_err(|e| anyhow!("cmd_tx.send cmd failed: {}", e))?;
+        let ret_rx = self.queue.send(cmd).await?;
 
         let ret = ret_rx
             .await
@@ -113,4 +1140,198 @@ impl Agent {
 
         Ok(ret)
     }
+
+    // Return TaskError::Deadline if the agent does not return in deadline.
+    // The receiver of the return is dropped then, so the command is
+    // abandoned and the agent discards its return.
+    pub async fn send_cmd_deadline(
+        &self,
+        cmd: AgentCmd,
+        deadline: Option<Duration>,
+    ) -> Result<AgentReturn> {
+        let deadline = match deadline {
+            Some(d) => d,
+            None => return self.send_cmd_async(cmd).await,
+        };
+
+        let cmd_str = format!("{:?}", cmd);
+        timeout(deadline, self.send_cmd_async(cmd))
+            .await
+            .map_err(|_| {
+                anyhow::Error::new(task::TaskError::Deadline(format!(
+                    "agent does not return {} in {:?}",
+                    cmd_str, deadline
+                )))
+            })?
+    }
+
+    // Stop the agent after the current work item and the queued unmerge
+    // and del work are done, and wait for agent_loop to return.
+    pub async fn shutdown(&self, grace_timeout: Duration) -> Result<()> {
+        let handle = self.handle.lock().unwrap().take();
+        let stop = async {
+            self.send_cmd_async(AgentCmd::Shutdown)
+                .await
+                .map_err(|e| anyhow!("send_cmd_async Shutdown failed: {}", e))?;
+            if let Some(handle) = handle {
+                handle
+                    .await
+                    .map_err(|e| anyhow!("join agent_loop failed: {}", e))?;
+            }
+            Ok(())
+        };
+
+        match timeout(grace_timeout, stop).await {
+            Ok(ret) => ret,
+            Err(_) => Err(anyhow!("agent shutdown is not done in {:?}", grace_timeout)),
+        }
+    }
+}
+
+impl Drop for Agent {
+    // agent_loop is stopped if the agent is dropped without shutdown.
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::FakeKernel;
+    use crate::task::AsyncWork;
+    use crate::{paths, proc::fake};
+    use structopt::StructOpt;
+
+    const GB: u64 = 1 << 30;
+
+    fn mem_trigger(args: &[&str]) -> MemTrigger {
+        let config = config::Config::from_iter(["uksmd"].iter().chain(args));
+        MemTrigger::new(&config)
+    }
+
+    #[test]
+    fn test_mem_trigger_hysteresis() {
+        let mut mem = mem_trigger(&["--free-mem-low", "10%", "--free-mem-high", "2147483648"]);
+        assert!(mem.enabled);
+
+        // (MemAvailable of a 10 GB host, if merging)
+        for (available, active) in [
+            (5 * GB, false),
+            (GB - 1, true),
+            // hovering between the watermarks doesn't flap
+            (GB + GB / 2, true),
+            (GB / 2, true),
+            (2 * GB, true),
+            (2 * GB + 1, false),
+            (GB + GB / 2, false),
+            (GB, false),
+            (GB - 1, true),
+        ] {
+            assert_eq!(mem.update(10 * GB, available), active, "{}", available);
+            assert_eq!(mem.available, available);
+        }
+    }
+
+    #[test]
+    fn test_mem_trigger_without_high() {
+        // The low watermark is also the high one.
+        let mut mem = mem_trigger(&["--free-mem-low", "1073741824"]);
+        assert!(mem.enabled);
+        assert!(mem.update(10 * GB, GB - 1));
+        assert!(mem.update(10 * GB, GB));
+        assert!(!mem.update(10 * GB, GB + 1));
+
+        assert!(!mem_trigger(&[]).enabled);
+        assert!(!mem_trigger(&["--free-mem-high", "20%"]).enabled);
+    }
+
+    #[test]
+    fn test_mem_trigger_sample() {
+        fake::root();
+        let meminfo = paths::proc("meminfo");
+        let write = |available_kb: u64| {
+            std::fs::write(
+                &meminfo,
+                format!(
+                    "MemTotal:       10485760 kB\nMemFree:          102400 kB\n\
+                     MemAvailable:   {:>8} kB\nBuffers:            1024 kB\n",
+                    available_kb
+                ),
+            )
+            .unwrap();
+        };
+        let mut mem = mem_trigger(&["--free-mem-low", "10%", "--free-mem-high", "20%"]);
+
+        write(5 << 20);
+        assert!(!mem.sample());
+        assert_eq!(mem.available, 5 * GB);
+        write(512 << 10);
+        assert!(mem.sample());
+        write(1536 << 10);
+        assert!(mem.sample());
+        write(3 << 20);
+        assert!(!mem.sample());
+
+        // A broken meminfo doesn't start merging.
+        write(512 << 10);
+        std::fs::write(&meminfo, "MemTotal:       10485760 kB\n").unwrap();
+        assert!(!mem.sample());
+    }
+
+    #[test]
+    fn test_del_waiter_after_unmerge_and_del() {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = task::Tasks::with_fake_kernel(&[], &kernel);
+        let mut pids = Vec::new();
+        for _ in 0..2 {
+            let pid = kernel.spawn(&[1, 2]);
+            rt.block_on(tasks.add(uksmd_ctl::AddRequest {
+                pid,
+                ..Default::default()
+            }))
+            .unwrap();
+            pids.push(pid);
+        }
+        for _ in 0..2 {
+            rt.block_on(tasks.add_refresh_all());
+            tasks.run_work(AsyncWork::Refresh).unwrap();
+        }
+        for _ in 0..2 {
+            rt.block_on(tasks.add_merge_all());
+            tasks.run_work(AsyncWork::Merge).unwrap();
+        }
+        assert!(kernel.is_ksm(pids[1], fake::MAP_START));
+
+        let (tx, mut rx) = oneshot::channel();
+        let (gone_tx, gone_rx) = oneshot::channel();
+        let mut waiters = vec![(pids[1], tx), (pids[1], gone_tx)];
+        drop(gone_rx);
+        rt.block_on(tasks.del(uksmd_ctl::DelRequest {
+            pid: pids[1],
+            wait: true,
+            ..Default::default()
+        }))
+        .unwrap();
+
+        // The del is replied after the unmerge and the del are done, the
+        // waiter whose caller is gone is dropped.
+        rt.block_on(reply_del_waiters(&tasks, &mut waiters));
+        assert_eq!(waiters.len(), 1);
+        assert!(rx.try_recv().is_err());
+        tasks.run_work(AsyncWork::UnMerge).unwrap();
+        assert!(!kernel.is_ksm(pids[1], fake::MAP_START));
+        rt.block_on(reply_del_waiters(&tasks, &mut waiters));
+        assert!(rx.try_recv().is_err());
+        tasks.run_work(AsyncWork::Del).unwrap();
+        rt.block_on(reply_del_waiters(&tasks, &mut waiters));
+        assert!(matches!(rx.try_recv(), Ok(AgentReturn::Ok)));
+        assert!(waiters.is_empty());
+    }
 }
@@ -0,0 +1,148 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+use std::os::unix::io::RawFd;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCred {
+    pub pid: i32,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+pub trait CredSource: std::fmt::Debug + Send + Sync {
+    fn peer_cred(&self, fd: RawFd) -> Result<PeerCred>;
+}
+
+// Get the credentials of the caller through SO_PEERCRED of the connection.
+#[derive(Debug, Default)]
+pub struct SockCredSource;
+
+impl CredSource for SockCredSource {
+    fn peer_cred(&self, fd: RawFd) -> Result<PeerCred> {
+        let mut cred = libc::ucred {
+            pid: 0,
+            uid: 0,
+            gid: 0,
+        };
+        let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+        let ret = unsafe {
+            libc::getsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                &mut cred as *mut libc::ucred as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret != 0 {
+            return Err(anyhow!(
+                "getsockopt SO_PEERCRED fd {} failed: {}",
+                fd,
+                std::io::Error::last_os_error()
+            ));
+        }
+
+        Ok(PeerCred {
+            pid: cred.pid,
+            uid: cred.uid,
+            gid: cred.gid,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuthPolicy {
+    // gids that can call the read-only methods
+    pub read_gids: HashSet<u32>,
+}
+
+impl AuthPolicy {
+    pub fn new(read_gids: Vec<u32>) -> Self {
+        Self {
+            read_gids: read_gids.into_iter().collect(),
+        }
+    }
+
+    // Load the policy from the config file path and add read_gids to it.
+    // The file has a "key=value" per line, the empty lines and the lines
+    // start with '#' are skipped. The key is read_gid, it can be set more
+    // than once.
+    pub fn load(path: Option<&str>, read_gids: &[u32]) -> Result<Self> {
+        let mut policy = Self::new(read_gids.to_vec());
+        let Some(path) = path else {
+            return Ok(policy);
+        };
+
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("read file {} failed: {}", path, e))?;
+        for line in content
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("line \"{}\" of {} is not key=value", line, path))?;
+            match key.trim() {
+                "read_gid" => {
+                    let gid = value.trim().parse::<u32>().map_err(|e| {
+                        anyhow!("read_gid {} of {} is not right: {}", value, path, e)
+                    })?;
+                    policy.read_gids.insert(gid);
+                }
+                key => return Err(anyhow!("key {} of {} is not supported", key, path)),
+            }
+        }
+
+        Ok(policy)
+    }
+
+    // Only root can call the mutating methods.
+    pub fn allow(&self, cred: &PeerCred, mutating: bool) -> bool {
+        if cred.uid == 0 {
+            return true;
+        }
+
+        !mutating && self.read_gids.contains(&cred.gid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_policy() {
+        let path = std::env::temp_dir().join(format!("uksmd-auth-test-{}", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        let cred = |uid, gid| PeerCred { pid: 1, uid, gid };
+
+        std::fs::write(&path, "# the monitors\nread_gid=50\n\nread_gid = 60\n").unwrap();
+        let policy = AuthPolicy::load(Some(path_str), &[70]).unwrap();
+        assert_eq!(policy.read_gids, HashSet::from([50, 60, 70]));
+        assert!(policy.allow(&cred(1000, 60), false));
+        assert!(!policy.allow(&cred(1000, 60), true));
+        assert!(!policy.allow(&cred(1000, 80), false));
+        assert!(policy.allow(&cred(0, 80), true));
+
+        // Without the file only the gids of the flags are allowed.
+        let policy = AuthPolicy::load(None, &[70]).unwrap();
+        assert_eq!(policy.read_gids, HashSet::from([70]));
+
+        for content in ["read_gid=abc\n", "write_gid=50\n", "read_gid\n"] {
+            std::fs::write(&path, content).unwrap();
+            assert!(
+                AuthPolicy::load(Some(path_str), &[]).is_err(),
+                "{}",
+                content
+            );
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert!(AuthPolicy::load(Some(path_str), &[]).is_err());
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::uksm::{PidAddr, UKSMPagemapEntry};
+use crate::{config, ksm, uksm};
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(test)]
+pub mod fake;
+
+// The function that gets the address and the entries of a chunk of pages.
+pub type PagemapChunkFn<'a> = dyn FnMut(u64, &mut [Option<UKSMPagemapEntry>]) -> Result<()> + 'a;
+
+// The operations of the kernel interface that reads the pages and merges
+// them, the uksm and ksm backends implement it.
+pub trait KernelOps: std::fmt::Debug + Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Read the entries of the pages in [start, end) of pid chunk by chunk,
+    // the memory used doesn't grow with the size of the range.
+    fn read_pagemap_chunked(
+        &self,
+        pid: u64,
+        start: u64,
+        end: u64,
+        f: &mut PagemapChunkFn,
+    ) -> Result<()>;
+
+    // Read the entries of the pages in [start, end) of pid.
+    fn read_pagemap(
+        &self,
+        pid: u64,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<Option<UKSMPagemapEntry>>> {
+        let mut entries = Vec::new();
+        self.read_pagemap_chunked(pid, start, end, &mut |_, chunk| {
+            entries.extend_from_slice(chunk);
+            Ok(())
+        })?;
+
+        Ok(entries)
+    }
+
+    // Merge pa2 to pa1, return false if they are not the same.
+    fn cmp_merge(&self, pa1: &PidAddr, pa2: &PidAddr) -> Result<bool>;
+
+    // If cmp_merge_batch is supported.
+    fn probe_batch(&self) -> bool {
+        false
+    }
+
+    // Merge each pa2 to pa1 of pairs, return if each pair is merged.
+    fn cmp_merge_batch(&self, pairs: &[(PidAddr, PidAddr)]) -> Result<Vec<bool>> {
+        pairs
+            .iter()
+            .map(|(pa1, pa2)| self.cmp_merge(pa1, pa2))
+            .collect()
+    }
+
+    fn unmerge(&self, pa: &PidAddr) -> Result<()>;
+
+    // Mark the page MADV_NOHUGEPAGE, so the huge page is split when the
+    // page is merged and khugepaged doesn't collapse it again.
+    fn split_thp(&self, pa: &PidAddr) -> Result<()> {
+        ksm::madvise_page(pa, libc::MADV_NOHUGEPAGE)
+    }
+
+    fn lru_drain(&self) -> Result<()> {
+        Ok(())
+    }
+
+    // Whether lru_drain drains the lru lists.
+    fn can_lru_drain(&self) -> bool {
+        false
+    }
+
+    // The crc of the zero page if the backend knows how the crc is computed.
+    fn zero_crc(&self) -> Option<u32> {
+        None
+    }
+
+    // The names of the kernel interfaces that the backend found.
+    fn caps(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn version(&self) -> Option<String> {
+        None
+    }
+
+    // If the kernel interfaces can be used, it is probed again after they
+    // are lost.
+    fn available(&self) -> bool {
+        true
+    }
+
+    // number of the retried kernel operations
+    fn retried(&self) -> u64 {
+        0
+    }
+}
+
+// Check the uksm interfaces, skip_kernel_check only checks that they exist.
+fn check_uksm(config: &config::Config) -> Result<()> {
+    if config.skip_kernel_check {
+        uksm::check_kernel()?;
+    } else {
+        uksm::probe_kernel()?;
+    }
+
+    Ok(())
+}
+
+// Check the backend that config asks, or pick one that the kernel supports.
+// Return its name.
+pub fn check(config: &config::Config) -> Result<&'static str> {
+    match config.backend.as_deref() {
+        Some("uksm") => {
+            check_uksm(config).map_err(|e| anyhow!("uksm::check_kernel fail: {}", e))?;
+            Ok("uksm")
+        }
+        Some("ksm") => {
+            ksm::check_kernel().map_err(|e| anyhow!("ksm::check_kernel fail: {}", e))?;
+            Ok("ksm")
+        }
+        Some(name) => Err(anyhow!("backend {} is not supported", name)),
+        None => {
+            if let Err(e) = check_uksm(config) {
+                ksm::check_kernel().map_err(|ke| {
+                    anyhow!(
+                        "uksm::check_kernel fail: {}, ksm::check_kernel fail: {}",
+                        e,
+                        ke
+                    )
+                })?;
+                warn!("uksm is not available, use ksm: {}", e);
+                Ok("ksm")
+            } else {
+                Ok("uksm")
+            }
+        }
+    }
+}
+
+// Create the backend that config.backend names, it should be set by check.
+pub fn new(config: &config::Config) -> Arc<dyn KernelOps> {
+    match config.backend.as_deref() {
+        Some("ksm") => Arc::new(ksm::KsmBackend::new(config.pagemap_chunk_pages)),
+        _ => Arc::new(uksm::UksmBackend::new(
+            uksm::RetryPolicy {
+                retries: config.kernel_retries,
+                delay: Duration::from_millis(config.kernel_retry_delay_ms),
+            },
+            config.pagemap_chunk_pages,
+        )),
+    }
+}
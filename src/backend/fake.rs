@@ -0,0 +1,454 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// An in-memory kernel for the tests.
+// Each page of a fake task is in a frame that keeps the content of the page.
+// The merged pages share a ksm frame as the pages that the kernel merged,
+// the pages of a forked task share the frames of its parent, and a write to
+// a page of a shared frame copies it to a new frame.
+// The crc of a page is the low 32 bits of its content, so two contents that
+// differ in the high bits have the same crc but cannot be merged.
+
+use crate::backend::{KernelOps, PagemapChunkFn};
+use crate::page;
+use crate::proc::fake::{self as fake_proc, FakeTask};
+use crate::uksm::{KernelError, PidAddr, UKSMPagemapEntry};
+use anyhow::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+
+// The pages read from a fake task in a chunk.
+const CHUNK_PAGES: u64 = 64;
+
+// A failure that the next write of a merge or unmerge gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    NoMem,
+    Rejected,
+    // The kernel interfaces are lost until set_lost(false).
+    Lost,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    data: u64,
+    ksm: bool,
+    thp: bool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    tasks: HashSet<u64>,
+    // (pid, addr) -> frame
+    pages: HashMap<(u64, u64), u64>,
+    frames: HashMap<u64, Frame>,
+    // frame -> number of the pages in it
+    users: HashMap<u64, usize>,
+    last_frame: u64,
+    // number of the merge and unmerge writes
+    merges: u64,
+    unmerges: u64,
+    // number of the pages read from pagemap
+    read_pages: u64,
+    faults: VecDeque<Fault>,
+    lost: bool,
+    // the pagemap reads wait until the hold is released
+    hold: bool,
+    held: usize,
+}
+
+impl State {
+    fn new_frame(&mut self, data: u64) -> u64 {
+        self.last_frame += 1;
+        self.frames.insert(
+            self.last_frame,
+            Frame {
+                data,
+                ksm: false,
+                thp: false,
+            },
+        );
+        self.last_frame
+    }
+
+    fn frame_users(&self, frame: u64) -> usize {
+        self.users.get(&frame).copied().unwrap_or(0)
+    }
+
+    // Map the page to frame, the old frame of it loses a user.
+    fn set_page(&mut self, page: (u64, u64), frame: u64) {
+        if let Some(old) = self.pages.insert(page, frame) {
+            self.put_frame(old);
+        }
+        *self.users.entry(frame).or_default() += 1;
+    }
+
+    fn remove_page(&mut self, page: (u64, u64)) {
+        if let Some(old) = self.pages.remove(&page) {
+            self.put_frame(old);
+        }
+    }
+
+    fn put_frame(&mut self, frame: u64) {
+        if let Some(users) = self.users.get_mut(&frame) {
+            *users -= 1;
+            if *users == 0 {
+                self.users.remove(&frame);
+            }
+        }
+    }
+
+    fn check(&mut self, pas: &[&PidAddr]) -> Result<()> {
+        if let Some(fault) = self.faults.pop_front() {
+            return Err(anyhow::Error::new(match fault {
+                Fault::NoMem => KernelError::NoMem("fake kernel is short of memory".to_string()),
+                Fault::Rejected => {
+                    let pa = pas[pas.len() - 1];
+                    KernelError::Rejected(pa.pid, pa.addr)
+                }
+                Fault::Lost => {
+                    self.lost = true;
+                    KernelError::Lost("fake kernel is lost".to_string())
+                }
+            }));
+        }
+        if self.lost {
+            return Err(anyhow::Error::new(KernelError::Lost(
+                "fake kernel is lost".to_string(),
+            )));
+        }
+        // The kernel reports the last task that is gone and the last page.
+        if let Some(pa) = pas.iter().rev().find(|pa| !self.tasks.contains(&pa.pid)) {
+            return Err(anyhow::Error::new(KernelError::TaskGone(pa.pid)));
+        }
+        if pas
+            .iter()
+            .any(|pa| !self.pages.contains_key(&(pa.pid, pa.addr)))
+        {
+            let pa = pas[pas.len() - 1];
+            return Err(anyhow::Error::new(KernelError::PageGone(pa.pid, pa.addr)));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FakeKernel {
+    state: Mutex<State>,
+    // notified when a read is held or the hold is released
+    cond: Condvar,
+    // if the merge commands can be written in batch
+    batch: bool,
+    // the next merge write panics
+    panic: AtomicBool,
+}
+
+impl FakeKernel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_batch() -> Self {
+        Self {
+            batch: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn add_task(&self, pid: u64) {
+        self.state.lock().unwrap().tasks.insert(pid);
+    }
+
+    // The task exits, its pages are gone.
+    pub fn exit(&self, pid: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.tasks.remove(&pid);
+        let pages: Vec<(u64, u64)> = state
+            .pages
+            .keys()
+            .filter(|(p, _)| *p == pid)
+            .copied()
+            .collect();
+        for page in pages {
+            state.remove_page(page);
+        }
+    }
+
+    // Write data to the page, the frame is copied if it is shared or it is
+    // a ksm page.
+    pub fn write(&self, pid: u64, addr: u64, data: u64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(frame) = state.pages.get(&(pid, addr)).copied() {
+            if !state.frames[&frame].ksm && state.frame_users(frame) == 1 {
+                if let Some(f) = state.frames.get_mut(&frame) {
+                    f.data = data;
+                }
+                return;
+            }
+        }
+        let frame = state.new_frame(data);
+        state.set_page((pid, addr), frame);
+    }
+
+    // Write the contents to the pages from start.
+    pub fn write_pages(&self, pid: u64, start: u64, contents: &[u64]) {
+        for (i, data) in contents.iter().enumerate() {
+            self.write(pid, start + i as u64 * *page::PAGE_SIZE, *data);
+        }
+    }
+
+    // Start a fake task that has a map of the pages of contents, in the
+    // fake procfs and in the kernel. Return its pid.
+    pub fn spawn(&self, contents: &[u64]) -> u64 {
+        let pid = fake_proc::new_pid();
+        self.spawn_pid(pid, contents);
+        pid
+    }
+
+    // Start the fake task of spawn with pid.
+    pub fn spawn_pid(&self, pid: u64, contents: &[u64]) {
+        FakeTask::new(pid)
+            .map(fake_proc::MAP_START, contents.len() as u64)
+            .write();
+        self.add_task(pid);
+        self.write_pages(pid, fake_proc::MAP_START, contents);
+    }
+
+    // The child shares the frames of all the pages of parent.
+    pub fn fork(&self, parent: u64, child: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.tasks.insert(child);
+        let pages: Vec<((u64, u64), u64)> = state
+            .pages
+            .iter()
+            .filter(|((pid, _), _)| *pid == parent)
+            .map(|((_, addr), frame)| ((child, *addr), *frame))
+            .collect();
+        for (page, frame) in pages {
+            state.set_page(page, frame);
+        }
+    }
+
+    // The page is unmapped or swapped out.
+    pub fn drop_page(&self, pid: u64, addr: u64) {
+        self.state.lock().unwrap().remove_page((pid, addr));
+    }
+
+    // Mark the frame of the page a part of a transparent huge page.
+    pub fn set_thp(&self, pid: u64, addr: u64, thp: bool) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(frame) = state.pages.get(&(pid, addr)).copied() {
+            if let Some(f) = state.frames.get_mut(&frame) {
+                f.thp = thp;
+            }
+        }
+    }
+
+    pub fn frame(&self, pid: u64, addr: u64) -> Option<u64> {
+        self.state.lock().unwrap().pages.get(&(pid, addr)).copied()
+    }
+
+    pub fn is_ksm(&self, pid: u64, addr: u64) -> bool {
+        let state = self.state.lock().unwrap();
+        state
+            .pages
+            .get(&(pid, addr))
+            .is_some_and(|frame| state.frames[frame].ksm)
+    }
+
+    // Merge pa2 to pa1 as another merger, for example ksmd, does.
+    pub fn merge_behind(&self, pa1: &PidAddr, pa2: &PidAddr) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(frame) = state.pages.get(&(pa1.pid, pa1.addr)).copied() {
+            if let Some(f) = state.frames.get_mut(&frame) {
+                f.ksm = true;
+            }
+            state.set_page((pa2.pid, pa2.addr), frame);
+        }
+    }
+
+    // Unmerge all the pages as the kernel does when uksm is unloaded.
+    pub fn unmerge_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        let pages: Vec<((u64, u64), u64)> = state
+            .pages
+            .iter()
+            .filter(|(_, frame)| state.frames[frame].ksm)
+            .map(|(page, frame)| (*page, *frame))
+            .collect();
+        for (page, frame) in pages {
+            let data = state.frames[&frame].data;
+            let frame = state.new_frame(data);
+            state.set_page(page, frame);
+        }
+    }
+
+    // number of the merge and unmerge writes
+    pub fn merges(&self) -> u64 {
+        self.state.lock().unwrap().merges
+    }
+
+    pub fn unmerges(&self) -> u64 {
+        self.state.lock().unwrap().unmerges
+    }
+
+    pub fn read_pages(&self) -> u64 {
+        self.state.lock().unwrap().read_pages
+    }
+
+    // The next merge or unmerge write fails with fault.
+    pub fn inject(&self, fault: Fault) {
+        self.state.lock().unwrap().faults.push_back(fault);
+    }
+
+    // The next merge write panics as a bug of the worker does, the panic
+    // doesn't poison the state.
+    pub fn inject_panic(&self) {
+        self.panic.store(true, Ordering::SeqCst);
+    }
+
+    pub fn set_lost(&self, lost: bool) {
+        self.state.lock().unwrap().lost = lost;
+    }
+
+    // Hold the next pagemap reads, to keep their tasks in the middle of a
+    // refresh.
+    pub fn hold_reads(&self) {
+        self.state.lock().unwrap().hold = true;
+    }
+
+    // Wait until a pagemap read is held.
+    pub fn wait_held(&self) {
+        let state = self.state.lock().unwrap();
+        drop(
+            self.cond
+                .wait_while(state, |state| state.held == 0)
+                .unwrap(),
+        );
+    }
+
+    pub fn release_reads(&self) {
+        self.state.lock().unwrap().hold = false;
+        self.cond.notify_all();
+    }
+}
+
+impl KernelOps for FakeKernel {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn read_pagemap_chunked(
+        &self,
+        pid: u64,
+        start: u64,
+        end: u64,
+        f: &mut PagemapChunkFn,
+    ) -> Result<()> {
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.hold {
+                state.held += 1;
+                self.cond.notify_all();
+                state = self.cond.wait_while(state, |state| state.hold).unwrap();
+                state.held -= 1;
+            }
+        }
+
+        let mut addr = start;
+        while addr < end {
+            let count = std::cmp::min(CHUNK_PAGES, (end - addr) / *page::PAGE_SIZE);
+            // Don't hold the lock in f.
+            let mut entries = {
+                let mut state = self.state.lock().unwrap();
+                if state.lost {
+                    return Err(anyhow::Error::new(KernelError::Lost(
+                        "fake kernel is lost".to_string(),
+                    )));
+                }
+                if !state.tasks.contains(&pid) {
+                    return Err(anyhow::Error::new(KernelError::TaskGone(pid)));
+                }
+                state.read_pages += count;
+                (0..count)
+                    .map(|i| {
+                        let frame = *state.pages.get(&(pid, addr + i * *page::PAGE_SIZE))?;
+                        let f = state.frames[&frame];
+                        Some(UKSMPagemapEntry {
+                            pfn: frame,
+                            crc: f.data as u32,
+                            is_thp: f.thp,
+                            is_ksm: f.ksm,
+                            idle: None,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            };
+            f(addr, &mut entries)?;
+            addr += count * *page::PAGE_SIZE;
+        }
+
+        Ok(())
+    }
+
+    fn cmp_merge(&self, pa1: &PidAddr, pa2: &PidAddr) -> Result<bool> {
+        if self.panic.swap(false, Ordering::SeqCst) {
+            panic!("fake kernel panics in merge {:?} {:?}", pa1, pa2);
+        }
+        let mut state = self.state.lock().unwrap();
+        state.merges += 1;
+        state.check(&[pa1, pa2])?;
+
+        let mut frame1 = state.pages[&(pa1.pid, pa1.addr)];
+        let frame2 = state.pages[&(pa2.pid, pa2.addr)];
+        let data = state.frames[&frame1].data;
+        if data != state.frames[&frame2].data {
+            return Ok(false);
+        }
+        // The other pages of a frame that is not a ksm page keep it.
+        if !state.frames[&frame1].ksm && state.frame_users(frame1) > 1 {
+            frame1 = state.new_frame(data);
+            state.set_page((pa1.pid, pa1.addr), frame1);
+        }
+        if let Some(f) = state.frames.get_mut(&frame1) {
+            f.ksm = true;
+            f.thp = false;
+        }
+        state.set_page((pa2.pid, pa2.addr), frame1);
+
+        Ok(true)
+    }
+
+    fn probe_batch(&self) -> bool {
+        self.batch
+    }
+
+    fn unmerge(&self, pa: &PidAddr) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.unmerges += 1;
+        state.check(&[pa])?;
+
+        let frame = state.pages[&(pa.pid, pa.addr)];
+        if state.frames[&frame].ksm {
+            let data = state.frames[&frame].data;
+            let frame = state.new_frame(data);
+            state.set_page((pa.pid, pa.addr), frame);
+        }
+
+        Ok(())
+    }
+
+    fn split_thp(&self, pa: &PidAddr) -> Result<()> {
+        self.state.lock().unwrap().check(&[pa])?;
+        self.set_thp(pa.pid, pa.addr, false);
+
+        Ok(())
+    }
+
+    fn available(&self) -> bool {
+        !self.state.lock().unwrap().lost
+    }
+}
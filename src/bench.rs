@@ -0,0 +1,217 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! Measure the refresh and merge throughput against a task.
+//!
+//! It runs the same work as the engine with the timings enabled, but in
+//! the current runtime without the agent, and reports the numbers of the
+//! work.
+
+use crate::protocols::uksmd_ctl;
+use crate::task::{AsyncWork, Tasks, WorkReport};
+use crate::{config, events, proc, Engine};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The task to measure.
+#[derive(Debug, Default, Clone)]
+pub struct BenchRequest {
+    pub pid: u64,
+    /// Only measure the pages in [start, end).
+    pub addr: Option<(u64, u64)>,
+    /// Keep the pages merged, they are unmerged at the end by default.
+    pub keep: bool,
+}
+
+/// The durations of an operation in the bench.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct BenchTiming {
+    pub name: String,
+    pub calls: u64,
+    pub total_us: u64,
+    pub max_us: u64,
+}
+
+/// The result of a bench.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct BenchReport {
+    pub pid: u64,
+    /// The refreshes that are needed to make the stable pages mergeable.
+    pub refresh_passes: u64,
+    pub scanned_pages: u64,
+    pub refresh_secs: f64,
+    pub scanned_pages_per_sec: f64,
+    /// The pages that the merge tried.
+    pub merge_candidates: u64,
+    pub merged_pages: u64,
+    pub merge_secs: f64,
+    /// The pairs of pages that are written to the kernel to merge.
+    pub cmp_writes: u64,
+    pub cmp_writes_per_sec: f64,
+    /// The ratio of the written pairs that are merged.
+    pub merge_success_ratio: f64,
+    pub unmerged_pages: u64,
+    pub kept: bool,
+    pub peak_rss_bytes: u64,
+    pub timings: Vec<BenchTiming>,
+}
+
+impl std::fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "pid {}", self.pid)?;
+        writeln!(
+            f,
+            "refresh {} passes {} pages {:.3}s {:.0} pages/s",
+            self.refresh_passes, self.scanned_pages, self.refresh_secs, self.scanned_pages_per_sec
+        )?;
+        writeln!(
+            f,
+            "merge {} of {} pages {:.3}s",
+            self.merged_pages, self.merge_candidates, self.merge_secs
+        )?;
+        writeln!(
+            f,
+            "cmp writes {} {:.0}/s success {:.1}%",
+            self.cmp_writes,
+            self.cmp_writes_per_sec,
+            self.merge_success_ratio * 100.0
+        )?;
+        if self.kept {
+            writeln!(f, "merged pages are kept")?;
+        } else {
+            writeln!(f, "unmerged {} pages", self.unmerged_pages)?;
+        }
+        writeln!(f, "peak rss {} bytes", self.peak_rss_bytes)?;
+        for t in self.timings.iter() {
+            writeln!(
+                f,
+                "timing {} calls {} total {}us max {}us",
+                t.name, t.calls, t.total_us, t.max_us
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn per_sec(count: u64, secs: f64) -> f64 {
+    if secs > 0.0 {
+        count as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+// Run work of tasks in a blocking thread because it locks tasks_pages with
+// blocking_lock.
+async fn run_work(tasks: &Tasks, work: AsyncWork) -> Result<Vec<WorkReport>> {
+    let mut tasks = tasks.clone();
+    tokio::task::spawn_blocking(move || tasks.run_work(work))
+        .await
+        .map_err(|e| anyhow!("join {:?} work failed: {}", work, e))?
+}
+
+fn add_timings(timings: &mut BTreeMap<&'static str, BenchTiming>, reports: &[WorkReport]) {
+    for t in reports.iter().flat_map(|r| r.timings.iter()) {
+        let timing = timings.entry(t.name).or_insert_with(|| BenchTiming {
+            name: t.name.to_string(),
+            ..Default::default()
+        });
+        timing.calls += t.calls;
+        timing.total_us += t.total.as_micros() as u64;
+        timing.max_us = timing.max_us.max(t.max.as_micros() as u64);
+    }
+}
+
+/// Refresh the task until its stable pages are mergeable, merge them once
+/// and unmerge them unless req.keep is set.
+///
+/// It must be called in a tokio runtime.
+pub async fn run(mut config: config::Config, req: BenchRequest) -> Result<BenchReport> {
+    Engine::check_config(&mut config)?;
+    config.timings = true;
+    // Make the numbers of the runs comparable.
+    config.deterministic_order = true;
+
+    let events = Arc::new(events::Events::new(config.events_capacity));
+    let mut tasks = Tasks::new(&config, events);
+    tasks
+        .add(uksmd_ctl::AddRequest {
+            pid: req.pid,
+            OptAddr: req.addr.map(|(start, end)| {
+                uksmd_ctl::add_request::OptAddr::Addr(uksmd_ctl::Addr {
+                    start,
+                    end,
+                    ..Default::default()
+                })
+            }),
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| anyhow!("add task {} failed: {}", req.pid, e))?;
+
+    let mut report = BenchReport {
+        pid: req.pid,
+        // A New page becomes Old after it is unchanged in stable_cycles
+        // refreshes.
+        refresh_passes: config.stable_cycles.max(1) as u64 + 1,
+        kept: req.keep,
+        ..Default::default()
+    };
+    let mut timings = BTreeMap::new();
+
+    let scanned = tasks.refreshed_pages();
+    let mut refresh_time = Duration::ZERO;
+    for _ in 0..report.refresh_passes {
+        tasks.add_refresh_all().await;
+        let now = Instant::now();
+        let reports = run_work(&tasks, AsyncWork::Refresh).await?;
+        refresh_time += now.elapsed();
+        add_timings(&mut timings, &reports);
+    }
+    report.scanned_pages = tasks.refreshed_pages() - scanned;
+    report.refresh_secs = refresh_time.as_secs_f64();
+    report.scanned_pages_per_sec = per_sec(report.scanned_pages, report.refresh_secs);
+
+    report.merge_candidates = tasks
+        .info_status()
+        .await
+        .get(&req.pid)
+        .map_or(0, |s| s.old_count);
+    let (compares, compares_merged) = tasks.merge_compares().await;
+    tasks.add_merge_all().await;
+    let now = Instant::now();
+    let reports = run_work(&tasks, AsyncWork::Merge).await?;
+    report.merge_secs = now.elapsed().as_secs_f64();
+    add_timings(&mut timings, &reports);
+    report.merged_pages = reports.iter().map(|r| r.merged_pages).sum();
+    let (compares_after, compares_merged_after) = tasks.merge_compares().await;
+    report.cmp_writes = compares_after - compares;
+    report.cmp_writes_per_sec = per_sec(report.cmp_writes, report.merge_secs);
+    if report.cmp_writes > 0 {
+        report.merge_success_ratio =
+            (compares_merged_after - compares_merged) as f64 / report.cmp_writes as f64;
+    }
+
+    if !req.keep {
+        tasks
+            .del(uksmd_ctl::DelRequest {
+                pid: req.pid,
+                force: true,
+                ..Default::default()
+            })
+            .await?;
+        let reports = run_work(&tasks, AsyncWork::UnMerge).await?;
+        report.unmerged_pages = reports.iter().map(|r| r.unmerged_pages).sum();
+        run_work(&tasks, AsyncWork::Del).await?;
+    }
+
+    report.peak_rss_bytes = proc::self_peak_rss().unwrap_or_default();
+    report.timings = timings.into_values().collect();
+
+    Ok(report)
+}
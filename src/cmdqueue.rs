@@ -0,0 +1,219 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The queue of the commands of agent_loop.
+// The senders wait for a free slot for a bounded time, so the rpcs get a
+// busy error instead of waiting forever if agent_loop is stuck.
+
+use crate::agent::{AgentCmd, AgentReturn};
+use crate::task::TaskError;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::error::SendTimeoutError;
+use tokio::sync::{mpsc, oneshot};
+
+#[derive(Debug)]
+pub struct QueuedCmd {
+    seq: u64,
+    pub cmd: AgentCmd,
+    pub ret_tx: oneshot::Sender<AgentReturn>,
+}
+
+#[derive(Debug, Default)]
+struct Queued {
+    next_seq: AtomicU64,
+    // The seq of the commands in the queue and the time they are queued.
+    times: Mutex<BTreeMap<u64, Instant>>,
+}
+
+impl Queued {
+    fn push(&self) -> u64 {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        self.times.lock().unwrap().insert(seq, Instant::now());
+        seq
+    }
+
+    fn pop(&self, seq: u64) {
+        self.times.lock().unwrap().remove(&seq);
+    }
+}
+
+// Pop seq when it is dropped, unless the command is sent. The send is
+// dropped while it waits if the caller gives up, for example when the
+// deadline of the rpc passes.
+struct PopGuard<'a> {
+    queued: &'a Queued,
+    seq: u64,
+    sent: bool,
+}
+
+impl Drop for PopGuard<'_> {
+    fn drop(&mut self) {
+        if !self.sent {
+            self.queued.pop(self.seq);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CmdQueue {
+    tx: mpsc::Sender<QueuedCmd>,
+    queued: Arc<Queued>,
+    wait: Duration,
+}
+
+#[derive(Debug)]
+pub struct CmdReceiver {
+    rx: mpsc::Receiver<QueuedCmd>,
+    queued: Arc<Queued>,
+}
+
+impl CmdReceiver {
+    pub async fn recv(&mut self) -> Option<QueuedCmd> {
+        let cmd = self.rx.recv().await?;
+        self.queued.pop(cmd.seq);
+        Some(cmd)
+    }
+}
+
+impl CmdQueue {
+    pub fn new(depth: usize, wait: Duration) -> (Self, CmdReceiver) {
+        let (tx, rx) = mpsc::channel(depth.max(1));
+        let queued = Arc::new(Queued::default());
+
+        (
+            Self {
+                tx,
+                queued: queued.clone(),
+                wait,
+            },
+            CmdReceiver { rx, queued },
+        )
+    }
+
+    // Queue cmd and return the receiver of its return.
+    // Return TaskError::Unavailable if the queue is still full after the wait.
+    pub async fn send(&self, cmd: AgentCmd) -> Result<oneshot::Receiver<AgentReturn>> {
+        let (ret_tx, ret_rx) = oneshot::channel();
+        let seq = self.queued.push();
+        let mut guard = PopGuard {
+            queued: &self.queued,
+            seq,
+            sent: false,
+        };
+
+        match self
+            .tx
+            .send_timeout(QueuedCmd { seq, cmd, ret_tx }, self.wait)
+            .await
+        {
+            Ok(()) => {
+                guard.sent = true;
+                Ok(ret_rx)
+            }
+            Err(SendTimeoutError::Timeout(c)) => {
+                Err(anyhow::Error::new(TaskError::Unavailable(format!(
+                    "agent is busy, the command queue is full for {:?}, drop {:?}",
+                    self.wait, c.cmd
+                ))))
+            }
+            Err(SendTimeoutError::Closed(_)) => Err(anyhow!("agent is stopped")),
+        }
+    }
+
+    // Queue cmd from a thread out of the runtime, it waits until there
+    // is a free slot.
+    pub fn blocking_send(&self, cmd: AgentCmd) -> Result<oneshot::Receiver<AgentReturn>> {
+        let (ret_tx, ret_rx) = oneshot::channel();
+        let seq = self.queued.push();
+
+        self.tx
+            .blocking_send(QueuedCmd { seq, cmd, ret_tx })
+            .map_err(|_| {
+                self.queued.pop(seq);
+                anyhow!("agent is stopped")
+            })?;
+
+        Ok(ret_rx)
+    }
+
+    // The number of the commands in the queue.
+    pub fn depth(&self) -> u64 {
+        self.queued.times.lock().unwrap().len() as u64
+    }
+
+    // How long the oldest command in the queue has waited.
+    pub fn oldest_age(&self) -> Duration {
+        self.queued
+            .times
+            .lock()
+            .unwrap()
+            .values()
+            .next()
+            .map(|t| t.elapsed())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_full_queue_is_busy() {
+        // The receiver isn't polled as the one of a stuck agent_loop.
+        let (queue, mut rx) = CmdQueue::new(2, Duration::from_millis(100));
+        let _r1 = queue.send(AgentCmd::Refresh).await.unwrap();
+        let _r2 = queue.send(AgentCmd::Status).await.unwrap();
+        assert_eq!(queue.depth(), 2);
+
+        let now = Instant::now();
+        let e = queue.send(AgentCmd::Stats).await.unwrap_err();
+        let elapsed = now.elapsed();
+        assert!(matches!(
+            e.downcast_ref::<TaskError>(),
+            Some(TaskError::Unavailable(_))
+        ));
+        assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+        // The dropped command is not counted.
+        assert_eq!(queue.depth(), 2);
+        assert!(queue.oldest_age() >= elapsed);
+
+        // The commands are received in order and free their slots.
+        assert!(matches!(rx.recv().await.unwrap().cmd, AgentCmd::Refresh));
+        assert_eq!(queue.depth(), 1);
+        queue.send(AgentCmd::Stats).await.unwrap();
+        assert!(matches!(rx.recv().await.unwrap().cmd, AgentCmd::Status));
+        assert!(matches!(rx.recv().await.unwrap().cmd, AgentCmd::Stats));
+        assert_eq!(queue.depth(), 0);
+        assert_eq!(queue.oldest_age(), Duration::ZERO);
+
+        // The agent is stopped.
+        drop(rx);
+        let e = queue.send(AgentCmd::Stats).await.unwrap_err();
+        assert!(e.to_string().contains("agent is stopped"));
+        assert_eq!(queue.depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_send_is_not_counted() {
+        let (queue, mut rx) = CmdQueue::new(1, Duration::from_secs(60));
+        let _r1 = queue.send(AgentCmd::Refresh).await.unwrap();
+
+        // The send that waits for a slot is given up as the one of an rpc
+        // whose deadline passes.
+        let ret =
+            tokio::time::timeout(Duration::from_millis(50), queue.send(AgentCmd::Stats)).await;
+        assert!(ret.is_err());
+        assert_eq!(queue.depth(), 1);
+
+        assert!(matches!(rx.recv().await.unwrap().cmd, AgentCmd::Refresh));
+        assert_eq!(queue.depth(), 0);
+        assert_eq!(queue.oldest_age(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,332 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{page, proc, uksm, window};
+use structopt::StructOpt;
+
+/// The config of uksmd.
+///
+/// The defaults are the ones of the options of the daemon, get them with
+/// `Config::from_iter(["uksmd"])` of [`StructOpt`].
+#[derive(StructOpt, Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Config {
+    /// gids that can call the read-only methods, they are added to the
+    /// ones in the auth-policy file
+    #[structopt(long)]
+    pub read_gid: Vec<u32>,
+
+    /// file of the permission policy of the control socket, a line
+    /// "read_gid=<gid>" lets the gid call the read-only methods
+    #[structopt(long)]
+    pub auth_policy: Option<String>,
+
+    /// seconds to wait for the remaining work when shutdown
+    #[structopt(long, default_value = "30")]
+    pub shutdown_timeout: u64,
+
+    /// unmerge the pages of all the tasks when shutdown, it waits for them
+    /// in shutdown_timeout
+    #[structopt(long)]
+    pub unmerge_on_exit: bool,
+
+    /// max number of the commands that wait for the agent
+    #[structopt(long, default_value = "10")]
+    pub cmd_queue_depth: usize,
+
+    /// milliseconds that a command waits for a free slot of the full
+    /// command queue before the rpc fails as busy
+    #[structopt(long, default_value = "1000")]
+    pub cmd_queue_wait_ms: u64,
+
+    /// pages read from the pagemap of a task in a read
+    #[structopt(long, default_value = "256")]
+    pub pagemap_chunk_pages: u64,
+
+    /// skip the maps that have one of these VmFlags of smaps
+    #[structopt(long, use_delimiter = true, default_value = "lo,io,pf,dd")]
+    pub skip_vm_flags: Vec<String>,
+
+    /// only refresh the maps whose pathnames match one of these regexes
+    #[structopt(long)]
+    pub include_vma: Vec<String>,
+
+    /// don't refresh the maps whose pathnames match one of these regexes
+    #[structopt(long)]
+    pub exclude_vma: Vec<String>,
+
+    /// get the anonymous maps from maps instead of smaps, it is faster but the VmFlags are not checked
+    #[structopt(long)]
+    pub fast_maps: bool,
+
+    /// min size of the maps to refresh
+    #[structopt(long, default_value = "0")]
+    pub min_vma_size: u64,
+
+    /// merge the pages of the shared anonymous maps
+    #[structopt(long)]
+    pub include_shared_anon: bool,
+
+    /// merge the resident pages of the memfd and /dev/shm maps, like the guest memory of qemu with memory-backend-memfd, the hugetlb maps are still skipped
+    #[structopt(long)]
+    pub include_shmem: bool,
+
+    /// kernel interface to merge the pages, uksm or ksm, default is uksm if it is available
+    #[structopt(long)]
+    pub backend: Option<String>,
+
+    /// only check that the uksm interfaces exist at startup, don't probe their writes
+    #[structopt(long)]
+    pub skip_kernel_check: bool,
+
+    /// root of procfs, it can be the host /proc that is mounted in a container
+    #[structopt(long, default_value = "/proc")]
+    pub procfs_root: String,
+
+    /// dir of the uksm kernel interface
+    #[structopt(long, default_value = "/proc/uksm")]
+    pub uksmfs_root: String,
+
+    /// endpoint of the CRI runtime to add the tasks by container IDs, it needs the cri feature
+    #[structopt(long)]
+    pub cri_endpoint: Option<String>,
+
+    /// file of the rules to add the matched processes automatically, it is reloaded by SIGHUP
+    #[structopt(long)]
+    pub discovery_rules: Option<String>,
+
+    /// seconds between the scans of the processes by the discovery rules
+    #[structopt(long, default_value = "30")]
+    pub discovery_interval: u64,
+
+    /// root of the cgroup filesystem, the watched cgroups are relative to it
+    #[structopt(long, default_value = "/sys/fs/cgroup")]
+    pub cgroupfs_root: String,
+
+    /// seconds between the syncs of the tasks with the watched cgroups
+    #[structopt(long, default_value = "5")]
+    pub cgroup_watch_interval: u64,
+
+    /// file to keep the tracked tasks and merged pages across restarts
+    #[structopt(long)]
+    pub state_file: Option<String>,
+
+    /// the state file is saved after the merge and refresh cycles that
+    /// change the merged pages, at least these seconds apart, it is saved
+    /// at exit too
+    #[structopt(long, default_value = "10")]
+    pub state_save_interval: u64,
+
+    /// nice value of the worker thread
+    #[structopt(long, allow_hyphen_values = true)]
+    pub worker_nice: Option<i32>,
+
+    /// run the worker thread with SCHED_IDLE
+    #[structopt(long)]
+    pub worker_sched_idle: bool,
+
+    /// run the worker thread with the idle io priority
+    #[structopt(long)]
+    pub worker_ioprio_idle: bool,
+
+    /// max pages refreshed per second, 0 means unlimited
+    #[structopt(long, default_value = "0")]
+    pub refresh_pages_per_sec: u64,
+
+    /// max pages merged per second, 0 means unlimited
+    #[structopt(long, default_value = "0")]
+    pub merge_pages_per_sec: u64,
+
+    /// number of the tasks that are read concurrently in refresh
+    #[structopt(long, default_value = "1")]
+    pub refresh_workers: usize,
+
+    /// max number of the tracked tasks
+    #[structopt(long, default_value = "1024")]
+    pub max_tasks: usize,
+
+    /// max bytes of the address range of a task, 0 means unlimited
+    #[structopt(long, default_value = "274877906944")]
+    pub max_addr_range: u64,
+
+    /// max number of the children that are added automatically for a task
+    #[structopt(long, default_value = "64")]
+    pub max_children: usize,
+
+    /// allow to add the init
+    #[structopt(long)]
+    pub allow_dangerous_pids: bool,
+
+    /// allow to merge the pages of the tasks that belong to different uids
+    #[structopt(long)]
+    pub allow_cross_uid_merge: bool,
+
+    /// which pages can be merged, any, cross_task_only or same_task_only
+    #[structopt(long, default_value = "any")]
+    pub merge_scope: uksm::MergeScope,
+
+    /// max number of the pages tracked by all the tasks
+    #[structopt(long, default_value = "67108864")]
+    pub max_tracked_pages: u64,
+
+    /// max pages of a task that a merge handles before it lets the other
+    /// tasks and work go, the merge of the task continues after them.
+    /// 0 means the whole task
+    #[structopt(long, default_value = "100000")]
+    pub merge_chunk_pages: usize,
+
+    /// merge the tasks by the ascending pids instead of the descending page
+    /// counts in each priority, so that the groups of the merged pages are
+    /// the same across the runs. The pages of a task are always merged by
+    /// the ascending addresses. It costs a sort of the pids per merge cycle
+    #[structopt(long)]
+    pub deterministic_order: bool,
+
+    /// max groups of the same crc that are tried to merge a page, 0 means unlimited
+    #[structopt(long, default_value = "8")]
+    pub merge_attempts: usize,
+
+    /// refreshes that the crc of a page must be unchanged before it is merged
+    #[structopt(long, default_value = "1")]
+    pub stable_cycles: u8,
+
+    /// how to handle the pages of the transparent huge pages, skip, split or merge
+    #[structopt(long, default_value = "merge")]
+    pub thp: page::ThpPolicy,
+
+    /// track the pages that the kernel merged without uksmd, for example
+    /// by ksmd, as the other pages, so uksmd merges and unmerges them
+    #[structopt(long)]
+    pub adopt_foreign_ksm: bool,
+
+    /// merge the zero pages as the other pages
+    #[structopt(long)]
+    pub merge_zero_pages: bool,
+
+    /// crc of the zero page, default is computed by the backend if it can
+    #[structopt(long)]
+    pub zero_page_crc: Option<u32>,
+
+    /// only merge the pages that are idle since the last refresh, it needs CAP_SYS_ADMIN
+    #[structopt(long)]
+    pub idle_filter: bool,
+
+    /// only read the pages that are soft-dirty since the last refresh, it clears the soft-dirty bits of the tasks
+    #[structopt(long)]
+    pub soft_dirty: bool,
+
+    /// min number of the tracked pages with the same crc to merge them
+    #[structopt(long, default_value = "2")]
+    pub min_dup_count: u32,
+
+    /// max merge commands in a write if the kernel supports it, 1 means one write per pair
+    #[structopt(long, default_value = "128")]
+    pub merge_batch: usize,
+
+    /// park a page after it is not the same as the pages of its crc in this
+    /// many merges in a row, it is not merged again until its crc changes,
+    /// 0 means never
+    #[structopt(long, default_value = "8")]
+    pub max_merge_failures: u8,
+
+    /// drain the per-cpu lru lists with lru_add_drain_all before each merge
+    /// cycle so that the pages in them can be merged, it is skipped if the
+    /// kernel doesn't have it
+    #[structopt(long, parse(try_from_str), default_value = "true")]
+    pub lru_drain_before_merge: bool,
+
+    /// refresh and merge all the tasks when the memory pressure is high
+    #[structopt(long)]
+    pub psi_trigger: bool,
+
+    /// the metric of /proc/pressure/memory that psi_trigger checks, some_avg10, full_avg60 and so on
+    #[structopt(long, default_value = "some_avg10")]
+    pub psi_metric: proc::PsiMetric,
+
+    /// start merging when the pressure is higher than it in psi_samples samples
+    #[structopt(long, default_value = "10")]
+    pub psi_high: f64,
+
+    /// stop merging when the pressure is lower than it
+    #[structopt(long, default_value = "5")]
+    pub psi_low: f64,
+
+    /// number of the consecutive samples that the pressure is high
+    #[structopt(long, default_value = "3")]
+    pub psi_samples: u32,
+
+    /// seconds between the samples of the memory pressure
+    #[structopt(long, default_value = "5")]
+    pub psi_interval: u64,
+
+    /// refresh and merge all the tasks when MemAvailable is lower than it, in bytes or percent like 10%
+    #[structopt(long)]
+    pub free_mem_low: Option<proc::MemWatermark>,
+
+    /// stop merging when MemAvailable is higher than it, in bytes or percent
+    #[structopt(long)]
+    pub free_mem_high: Option<proc::MemWatermark>,
+
+    /// seconds between the samples of MemAvailable
+    #[structopt(long, default_value = "5")]
+    pub free_mem_interval: u64,
+
+    /// unmerge the pages that are merged last when the cpu pressure of psi_metric is higher than it
+    #[structopt(long)]
+    pub cpu_psi_unmerge: Option<f64>,
+
+    /// max pages unmerged in a sample of cpu_psi_unmerge
+    #[structopt(long, default_value = "1024")]
+    pub cpu_unmerge_pages: u64,
+
+    /// the windows of the local time in which the merges run, like 01:00-05:00 or sat,sun@00:00-08:00
+    #[structopt(long)]
+    pub merge_window: Vec<window::MergeWindow>,
+
+    /// number of the recent events that are kept for the RecentEvents RPC
+    #[structopt(long, default_value = "1024")]
+    pub events_capacity: usize,
+
+    /// record the durations of the major operations of each work cycle
+    #[structopt(long)]
+    pub timings: bool,
+
+    /// samples of the merge savings that are kept for each task, 0 means disabled
+    #[structopt(long, default_value = "288")]
+    pub history_samples: usize,
+
+    /// don't start the merge cycles and pause the work when the load average per cpu is higher than it, 0 means disabled
+    #[structopt(long, default_value = "0")]
+    pub max_load: f64,
+
+    /// max seconds that the work is paused by max_load
+    #[structopt(long, default_value = "60")]
+    pub max_load_delay: u64,
+
+    /// seconds between the probes of the kernel interfaces after they are
+    /// lost, for example the uksm module is being reloaded
+    #[structopt(long, default_value = "5")]
+    pub kernel_probe_interval: u64,
+
+    /// seconds between the checks of the merged pages that the kernel broke, 0 means disabled
+    #[structopt(long, default_value = "0")]
+    pub verify_interval: u64,
+
+    /// del the tasks that exited by the exit events of the proc connector,
+    /// or by polling them if the proc connector cannot be used
+    #[structopt(long)]
+    pub reaper: bool,
+
+    /// seconds between the polls of the reaper
+    #[structopt(long, default_value = "10")]
+    pub reaper_interval: u64,
+
+    /// max retries of a write to /proc/uksm that fails with EAGAIN or EBUSY
+    #[structopt(long, default_value = "3")]
+    pub kernel_retries: u32,
+
+    /// milliseconds before the first retry, doubled for each retry
+    #[structopt(long, default_value = "1")]
+    pub kernel_retry_delay_ms: u64,
+}
@@ -3,15 +3,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use anyhow::{anyhow, Result};
+use chrono::{Local, TimeZone};
 use structopt::StructOpt;
+use ttrpc::proto::Code;
 use ttrpc::r#async::Client;
 use uksmd::protocols::{empty, uksmd_ctl, uksmd_ctl_ttrpc};
+use uksmd::version;
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "uksmd-ctl", about = "uKSM daemon controler")]
 struct Opt {
-    #[structopt(long, default_value = "unix:///var/run/uksmd.sock")]
-    addr: String,
+    /// address of uKSM daemon, if it is not given, $XDG_RUNTIME_DIR/uksmd.sock
+    /// is used if it exists, then unix:///var/run/uksmd.sock
+    #[structopt(long, env = "UKSMD_ADDR")]
+    addr: Option<String>,
 
     #[structopt(subcommand)]
     command: Command,
@@ -29,23 +34,300 @@ enum Command {
     Refresh,
 
     #[structopt(name = "merge", about = "Merge the pages of all tasks")]
-    Merge,
+    Merge(CommandMerge),
+
+    #[structopt(name = "status", about = "Show the status of uKSM daemon")]
+    Status,
+
+    #[structopt(name = "stats", about = "Show the statistics of uKSM daemon")]
+    Stats,
+
+    #[structopt(name = "set-config", about = "Change the config of uKSM daemon")]
+    SetConfig(CommandSetConfig),
+
+    #[structopt(name = "unmerge", about = "Unmerge the pages that are merged last")]
+    Unmerge(CommandUnmerge),
+
+    #[structopt(
+        name = "pause-task",
+        about = "Pause the refresh and merge of task by pid"
+    )]
+    PauseTask(CommandPauseTask),
+
+    #[structopt(
+        name = "resume-task",
+        about = "Resume the refresh and merge of task by pid"
+    )]
+    ResumeTask(CommandResumeTask),
+
+    #[structopt(
+        name = "update",
+        about = "Change the options of task by pid, the ones that are not set are kept"
+    )]
+    Update(CommandUpdate),
+
+    #[structopt(name = "history", about = "Show the merge savings of task by pid")]
+    History(CommandHistory),
+
+    #[structopt(name = "events", about = "Show the recent events newest first")]
+    Events(CommandEvents),
+
+    #[structopt(name = "maps", about = "Show the maps of pid that uKSM daemon selects")]
+    Maps(CommandMaps),
+
+    #[structopt(name = "histogram", about = "Show the distribution of the page crcs")]
+    Histogram(CommandHistogram),
+
+    #[structopt(
+        name = "watch-cgroup",
+        about = "Add and del the processes of cgroup automatically"
+    )]
+    WatchCgroup(CommandWatchCgroup),
+
+    #[structopt(name = "unwatch-cgroup", about = "Stop watching cgroup")]
+    UnwatchCgroup(CommandUnwatchCgroup),
+
+    #[structopt(
+        name = "version",
+        about = "Show the versions of uksmd-ctl and uKSM daemon"
+    )]
+    Version,
+
+    #[structopt(
+        name = "unmerge-all",
+        about = "Unmerge the pages of all tasks, they are still tracked"
+    )]
+    UnmergeAll,
+
+    #[structopt(
+        name = "drain",
+        about = "Drain the per-cpu lru lists with lru_add_drain_all"
+    )]
+    Drain,
+}
+
+impl Command {
+    // The rpc that the command calls.
+    fn rpc(&self) -> &'static str {
+        match self {
+            Command::Add(cmdadd) if cmdadd.container.is_some() => "AddByContainer",
+            Command::Add(_) => "Add",
+            Command::Del(_) => "Del",
+            Command::Refresh => "Refresh",
+            Command::Merge(_) => "Merge",
+            Command::Status => "Status",
+            Command::Stats => "Stats",
+            Command::SetConfig(_) => "SetConfig",
+            Command::Unmerge(_) => "Unmerge",
+            Command::PauseTask(_) => "PauseTask",
+            Command::ResumeTask(_) => "ResumeTask",
+            Command::Update(_) => "Update",
+            Command::History(_) => "History",
+            Command::Events(_) => "RecentEvents",
+            Command::Maps(_) => "GetMaps",
+            Command::Histogram(_) => "CrcHistogram",
+            Command::WatchCgroup(_) => "WatchCgroup",
+            Command::UnwatchCgroup(_) => "UnwatchCgroup",
+            Command::Version => "GetVersion",
+            Command::Drain => "Drain",
+            Command::UnmergeAll => "UnmergeAll",
+        }
+    }
 }
 
 #[derive(StructOpt, Debug)]
 struct CommandAdd {
+    #[structopt(long, required_unless = "container", conflicts_with = "container")]
+    pid: Option<u64>,
     #[structopt(long)]
-    pid: u64,
+    container: Option<String>,
     #[structopt(long)]
     start: Option<u64>,
     #[structopt(long)]
     end: Option<u64>,
+    #[structopt(long)]
+    thp: Option<String>,
+    #[structopt(long)]
+    include_vma: Vec<String>,
+    #[structopt(long)]
+    exclude_vma: Vec<String>,
+    #[structopt(long)]
+    min_vma_size: Option<u64>,
+    #[structopt(long)]
+    priority: Option<i32>,
+    #[structopt(long)]
+    follow_children: bool,
+    /// round start down and end up to the page
+    #[structopt(long)]
+    align: bool,
+    /// accept a range that is larger than max_addr_range of uksmd
+    #[structopt(long)]
+    force: bool,
+    /// merge the resident pages of the memfd and /dev/shm maps, true or false
+    #[structopt(long)]
+    include_shmem: Option<bool>,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandMerge {
+    #[structopt(long)]
+    force: bool,
+    #[structopt(long)]
+    pid: Option<u64>,
+    #[structopt(long, requires_all = &["pid", "end"])]
+    start: Option<u64>,
+    #[structopt(long, requires = "start")]
+    end: Option<u64>,
 }
 
 #[derive(StructOpt, Debug)]
 struct CommandDel {
     #[structopt(long)]
     pid: u64,
+    #[structopt(long)]
+    force: bool,
+    /// return after the pages of the task are unmerged and it is deleted
+    #[structopt(long)]
+    wait: bool,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandUnmerge {
+    #[structopt(long, required_unless = "pid", conflicts_with = "pid")]
+    budget: Option<u64>,
+    #[structopt(long)]
+    pid: Option<u64>,
+    #[structopt(long, requires_all = &["pid", "end"])]
+    start: Option<u64>,
+    #[structopt(long, requires = "start")]
+    end: Option<u64>,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandPauseTask {
+    #[structopt(long)]
+    pid: u64,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandResumeTask {
+    #[structopt(long)]
+    pid: u64,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandUpdate {
+    #[structopt(long)]
+    pid: u64,
+    /// the task with a higher priority is merged first
+    #[structopt(long, allow_hyphen_values = true)]
+    priority: Option<i32>,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandHistory {
+    #[structopt(long)]
+    pid: u64,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandEvents {
+    #[structopt(long, default_value = "0")]
+    count: u64,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandMaps {
+    #[structopt(long)]
+    pid: u64,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandHistogram {
+    #[structopt(long, default_value = "0")]
+    top: u64,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandWatchCgroup {
+    path: String,
+    #[structopt(long)]
+    label: Vec<String>,
+    #[structopt(long)]
+    include_vma: Vec<String>,
+    #[structopt(long)]
+    exclude_vma: Vec<String>,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandUnwatchCgroup {
+    path: String,
+}
+
+#[derive(StructOpt, Debug)]
+struct CommandSetConfig {
+    #[structopt(long)]
+    refresh_pages_per_sec: Option<u64>,
+    #[structopt(long)]
+    merge_pages_per_sec: Option<u64>,
+    #[structopt(long)]
+    min_dup_count: Option<u32>,
+    #[structopt(long)]
+    merge_scope: Option<String>,
+    #[structopt(long)]
+    max_load: Option<f64>,
+}
+
+// Get the version of the daemon, None if the daemon is older than
+// GetVersion. The ttrpc server returns UNIMPLEMENTED for a method that it
+// doesn't have, and the default handler of the service returns NOT_FOUND.
+async fn get_version(
+    client: &uksmd_ctl_ttrpc::ControlClient,
+) -> Result<Option<uksmd_ctl::VersionResponse>> {
+    match client
+        .get_version(ttrpc::context::with_timeout(0), &empty::Empty::new())
+        .await
+    {
+        Ok(resp) => Ok(Some(resp)),
+        Err(ttrpc::error::Error::RpcStatus(s))
+            if s.code() == Code::UNIMPLEMENTED || s.code() == Code::NOT_FOUND =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(anyhow!("client.get_version fail: {}", e)),
+    }
+}
+
+// Warn if the versions do not match and refuse rpc if the daemon does not
+// support it.
+fn check_version(server: Option<&uksmd_ctl::VersionResponse>, rpc: &str) -> Result<()> {
+    let Some(server) = server else {
+        eprintln!("warning: uKSM daemon does not report its version, it is older than api level 1");
+        return Ok(());
+    };
+
+    if server.version != version::VERSION || server.api_level != version::API_LEVEL {
+        eprintln!(
+            "warning: uksmd-ctl {} (api level {}) does not match uKSM daemon {} (api level {})",
+            version::VERSION,
+            version::API_LEVEL,
+            server.version,
+            server.api_level
+        );
+    }
+
+    let level = version::rpc_level(rpc).unwrap_or(version::API_LEVEL);
+    if server.api_level < level {
+        return Err(anyhow!(
+            "uKSM daemon {} (api level {}) does not support {}, which needs api level {}",
+            server.version,
+            server.api_level,
+            rpc,
+            level
+        ));
+    }
+
+    Ok(())
 }
 
 #[tokio::main]
@@ -53,20 +335,25 @@ async fn main() -> Result<()> {
     let opt = Opt::from_args();
 
     // setup client
-    let c = Client::connect(&opt.addr).unwrap();
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR").ok();
+    let addr = uksmd::addr::resolve(opt.addr, runtime_dir.as_deref());
+    let c = Client::connect(&addr).unwrap();
     let client = uksmd_ctl_ttrpc::ControlClient::new(c.clone());
 
+    let server = get_version(&client).await?;
+    check_version(server.as_ref(), opt.command.rpc())?;
+
     match opt.command {
         Command::Add(cmdadd) => {
-            if (cmdadd.start.is_none() && !cmdadd.end.is_none())
-                || (!cmdadd.start.is_none() && cmdadd.end.is_none())
+            if (cmdadd.start.is_none() && cmdadd.end.is_some())
+                || (cmdadd.start.is_some() && cmdadd.end.is_none())
             {
                 return Err(anyhow!(
                     "start and end should be set together or not set together"
                 ));
             }
             let req = uksmd_ctl::AddRequest {
-                pid: cmdadd.pid,
+                pid: cmdadd.pid.unwrap_or(0),
                 OptAddr: if cmdadd.start.is_none() {
                     None
                 } else {
@@ -76,17 +363,80 @@ async fn main() -> Result<()> {
                         ..Default::default()
                     }))
                 },
+                OptThp: cmdadd.thp.map(uksmd_ctl::add_request::OptThp::Thp),
+                include_vma: cmdadd.include_vma,
+                exclude_vma: cmdadd.exclude_vma,
+                OptMinVmaSize: cmdadd
+                    .min_vma_size
+                    .map(uksmd_ctl::add_request::OptMinVmaSize::MinVmaSize),
+                OptPriority: cmdadd
+                    .priority
+                    .map(uksmd_ctl::add_request::OptPriority::Priority),
+                follow_children: cmdadd.follow_children,
+                align: cmdadd.align,
+                force: cmdadd.force,
+                OptIncludeShmem: cmdadd
+                    .include_shmem
+                    .map(uksmd_ctl::add_request::OptIncludeShmem::IncludeShmem),
                 ..Default::default()
             };
-            client
-                .add(ttrpc::context::with_timeout(0), &req)
-                .await
-                .map_err(|e| anyhow!("client.add fail: {}", e))?;
+            let resp = if let Some(container_id) = cmdadd.container {
+                let req = uksmd_ctl::AddByContainerRequest {
+                    container_id,
+                    add: protobuf::MessageField::some(req),
+                    ..Default::default()
+                };
+                client
+                    .add_by_container(ttrpc::context::with_timeout(0), &req)
+                    .await
+                    .map_err(|e| anyhow!("client.add_by_container fail: {}", e))?
+            } else {
+                client
+                    .add(ttrpc::context::with_timeout(0), &req)
+                    .await
+                    .map_err(|e| anyhow!("client.add fail: {}", e))?
+            };
+            let summary = server
+                .as_ref()
+                .is_some_and(|s| s.api_level >= version::ADD_SUMMARY_LEVEL);
+            if summary {
+                println!(
+                    "pid {} comm {} start_time {}",
+                    resp.pid, resp.comm, resp.start_time
+                );
+                println!(
+                    "thp {} priority {} follow_children {}",
+                    resp.thp, resp.priority, resp.follow_children
+                );
+                println!(
+                    "include_vma {:?} exclude_vma {:?} min_vma_size {} include_shmem {}",
+                    resp.include_vma, resp.exclude_vma, resp.min_vma_size, resp.include_shmem
+                );
+            }
+            if cmdadd.align || summary {
+                if let Some(uksmd_ctl::add_response::OptAddr::Addr(addr)) = resp.OptAddr {
+                    println!("start 0x{:x} end 0x{:x}", addr.start, addr.end);
+                }
+            }
+            println!("vmas {} bytes {}", resp.vmas, resp.bytes);
         }
 
         Command::Del(cmdadd) => {
+            // Option::is_none_or needs rust 1.82, keep building with the older ones.
+            #[allow(clippy::unnecessary_map_or)]
+            let old_server = server
+                .as_ref()
+                .map_or(true, |s| s.api_level < version::DEL_WAIT_LEVEL);
+            if cmdadd.wait && old_server {
+                return Err(anyhow!(
+                    "uKSM daemon does not support del with wait, which needs api level {}",
+                    version::DEL_WAIT_LEVEL
+                ));
+            }
             let req: uksmd_ctl::DelRequest = uksmd_ctl::DelRequest {
                 pid: cmdadd.pid,
+                force: cmdadd.force,
+                wait: cmdadd.wait,
                 ..Default::default()
             };
             client
@@ -102,13 +452,322 @@ async fn main() -> Result<()> {
                 .map_err(|e| anyhow!("client.refresh fail: {}", e))?;
         }
 
-        Command::Merge => {
+        Command::Merge(cmdmerge) => {
+            let req = uksmd_ctl::MergeRequest {
+                force: cmdmerge.force,
+                pid: cmdmerge.pid.unwrap_or(0),
+                OptAddr: cmdmerge.start.zip(cmdmerge.end).map(|(start, end)| {
+                    uksmd_ctl::merge_request::OptAddr::Addr(uksmd_ctl::Addr {
+                        start,
+                        end,
+                        ..Default::default()
+                    })
+                }),
+                ..Default::default()
+            };
             client
-                .merge(ttrpc::context::with_timeout(0), &empty::Empty::new())
+                .merge(ttrpc::context::with_timeout(0), &req)
                 .await
                 .map_err(|e| anyhow!("client.merge fail: {}", e))?;
         }
+
+        Command::Status => {
+            let resp = client
+                .status(ttrpc::context::with_timeout(0), &empty::Empty::new())
+                .await
+                .map_err(|e| anyhow!("client.status fail: {}", e))?;
+            println!("{}", resp);
+        }
+
+        Command::Stats => {
+            let resp = client
+                .stats(ttrpc::context::with_timeout(0), &empty::Empty::new())
+                .await
+                .map_err(|e| anyhow!("client.stats fail: {}", e))?;
+            println!("{}", resp);
+        }
+
+        Command::SetConfig(cmdset) => {
+            let req = uksmd_ctl::SetConfigRequest {
+                OptRefreshRate: cmdset
+                    .refresh_pages_per_sec
+                    .map(uksmd_ctl::set_config_request::OptRefreshRate::RefreshPagesPerSec),
+                OptMergeRate: cmdset
+                    .merge_pages_per_sec
+                    .map(uksmd_ctl::set_config_request::OptMergeRate::MergePagesPerSec),
+                OptMinDupCount: cmdset
+                    .min_dup_count
+                    .map(uksmd_ctl::set_config_request::OptMinDupCount::MinDupCount),
+                OptMergeScope: cmdset
+                    .merge_scope
+                    .map(uksmd_ctl::set_config_request::OptMergeScope::MergeScope),
+                OptMaxLoad: cmdset
+                    .max_load
+                    .map(uksmd_ctl::set_config_request::OptMaxLoad::MaxLoad),
+                ..Default::default()
+            };
+            client
+                .set_config(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.set_config fail: {}", e))?;
+        }
+
+        Command::Unmerge(cmdunmerge) => {
+            let req = uksmd_ctl::UnmergeRequest {
+                budget: cmdunmerge.budget.unwrap_or(0),
+                pid: cmdunmerge.pid.unwrap_or(0),
+                OptAddr: cmdunmerge.start.zip(cmdunmerge.end).map(|(start, end)| {
+                    uksmd_ctl::unmerge_request::OptAddr::Addr(uksmd_ctl::Addr {
+                        start,
+                        end,
+                        ..Default::default()
+                    })
+                }),
+                ..Default::default()
+            };
+            client
+                .unmerge(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.unmerge fail: {}", e))?;
+        }
+
+        Command::PauseTask(cmdpause) => {
+            let req = uksmd_ctl::PauseTaskRequest {
+                pid: cmdpause.pid,
+                ..Default::default()
+            };
+            client
+                .pause_task(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.pause_task fail: {}", e))?;
+        }
+
+        Command::ResumeTask(cmdresume) => {
+            let req = uksmd_ctl::ResumeTaskRequest {
+                pid: cmdresume.pid,
+                ..Default::default()
+            };
+            client
+                .resume_task(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.resume_task fail: {}", e))?;
+        }
+
+        Command::Update(cmdupdate) => {
+            let req = uksmd_ctl::UpdateRequest {
+                pid: cmdupdate.pid,
+                OptPriority: cmdupdate
+                    .priority
+                    .map(uksmd_ctl::update_request::OptPriority::Priority),
+                ..Default::default()
+            };
+            client
+                .update(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.update fail: {}", e))?;
+        }
+
+        Command::History(cmdhistory) => {
+            let req = uksmd_ctl::HistoryRequest {
+                pid: cmdhistory.pid,
+                ..Default::default()
+            };
+            let resp = client
+                .history(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.history fail: {}", e))?;
+            println!("{:<20} {:>12} {:>16}", "TIME", "UKSM_PAGES", "SAVED_BYTES");
+            for s in resp.samples {
+                let time = Local
+                    .timestamp_opt(s.time as i64, 0)
+                    .single()
+                    .map_or(s.time.to_string(), |t| {
+                        t.format("%Y-%m-%d %H:%M:%S").to_string()
+                    });
+                println!("{:<20} {:>12} {:>16}", time, s.uksm_count, s.saved_bytes);
+            }
+        }
+
+        Command::Events(cmdevents) => {
+            let req = uksmd_ctl::RecentEventsRequest {
+                count: cmdevents.count,
+                ..Default::default()
+            };
+            let resp = client
+                .recent_events(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.recent_events fail: {}", e))?;
+            for e in resp.events {
+                println!("{} [{}] {}", e.time, e.severity, e.message);
+            }
+        }
+
+        Command::Maps(cmdmaps) => {
+            let req = uksmd_ctl::GetMapsRequest {
+                pid: cmdmaps.pid,
+                ..Default::default()
+            };
+            let resp = client
+                .get_maps(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.get_maps fail: {}", e))?;
+            println!("selected:");
+            for m in resp.maps {
+                println!("  {:x}-{:x}", m.start, m.end);
+            }
+            println!("skipped:");
+            for r in resp.rejected {
+                println!("  {:x}-{:x} {}", r.start, r.end, r.reason);
+            }
+        }
+
+        Command::WatchCgroup(cmdwatch) => {
+            let req = uksmd_ctl::WatchCgroupRequest {
+                path: cmdwatch.path,
+                labels: cmdwatch.label,
+                include_vma: cmdwatch.include_vma,
+                exclude_vma: cmdwatch.exclude_vma,
+                ..Default::default()
+            };
+            client
+                .watch_cgroup(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.watch_cgroup fail: {}", e))?;
+        }
+
+        Command::UnwatchCgroup(cmdunwatch) => {
+            let req = uksmd_ctl::UnwatchCgroupRequest {
+                path: cmdunwatch.path,
+                ..Default::default()
+            };
+            client
+                .unwatch_cgroup(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.unwatch_cgroup fail: {}", e))?;
+        }
+
+        Command::Histogram(cmdhistogram) => {
+            let req = uksmd_ctl::CrcHistogramRequest {
+                top: cmdhistogram.top,
+                ..Default::default()
+            };
+            let resp = client
+                .crc_histogram(ttrpc::context::with_timeout(0), &req)
+                .await
+                .map_err(|e| anyhow!("client.crc_histogram fail: {}", e))?;
+            for (name, buckets, top) in [
+                ("merged", &resp.merged, &resp.top_merged),
+                ("candidates", &resp.candidates, &resp.top_candidates),
+            ] {
+                println!("{}:", name);
+                println!("  {:<10} {:>12} {:>12}", "PAGES", "CRCS", "TOTAL_PAGES");
+                for b in buckets.iter() {
+                    println!("  {:<10} {:>12} {:>12}", b.range, b.crcs, b.pages);
+                }
+                println!("  top:");
+                for c in top.iter() {
+                    println!("    {:08x} {:>12}", c.crc, c.pages);
+                }
+            }
+        }
+
+        Command::UnmergeAll => {
+            let resp = client
+                .unmerge_all(ttrpc::context::with_timeout(0), &empty::Empty::new())
+                .await
+                .map_err(|e| anyhow!("client.unmerge_all fail: {}", e))?;
+            println!("unmerge the pages of {} tasks", resp.tasks);
+        }
+
+        Command::Drain => {
+            let resp = client
+                .drain(ttrpc::context::with_timeout(0), &empty::Empty::new())
+                .await
+                .map_err(|e| anyhow!("client.drain fail: {}", e))?;
+            println!("drained in {}us", resp.elapsed_us);
+        }
+
+        Command::Version => {
+            println!(
+                "uksmd-ctl: {} (api level {})",
+                version::VERSION,
+                version::API_LEVEL
+            );
+            if let Some(server) = server {
+                println!(
+                    "uKSM daemon: {} (api level {})",
+                    server.version, server.api_level
+                );
+            }
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::io::IntoRawFd;
+    use std::sync::Arc;
+
+    // A daemon that is older than GetVersion.
+    struct OldControl;
+
+    impl uksmd_ctl_ttrpc::Control for OldControl {}
+
+    #[tokio::test]
+    async fn test_get_version_of_old_daemon() {
+        let dir = std::env::temp_dir().join(format!("uksmd-ctl-version-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // The server that doesn't have the method, and the one that has the
+        // default handler of it.
+        for (i, registered) in [false, true].into_iter().enumerate() {
+            let path = dir.join(format!("uksmd-{}.sock", i));
+            let mut service = uksmd_ctl_ttrpc::create_control(Arc::new(Box::new(OldControl)));
+            if !registered {
+                for s in service.values_mut() {
+                    assert!(s.methods.remove("GetVersion").is_some());
+                }
+            }
+            let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+            let mut server = ttrpc::r#async::Server::new()
+                .set_domain_unix()
+                .add_listener(listener.into_raw_fd())
+                .unwrap()
+                .register_service(service);
+            server.start().await.unwrap();
+
+            let c = Client::connect(&format!("unix://{}", path.to_str().unwrap())).unwrap();
+            let client = uksmd_ctl_ttrpc::ControlClient::new(c);
+            assert!(get_version(&client).await.unwrap().is_none());
+            server.shutdown().await.unwrap();
+        }
+    }
+
+    #[test]
+    fn test_addr_precedence() {
+        let dir = std::env::temp_dir().join(format!("uksmd-ctl-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(uksmd::addr::RUNTIME_SOCK), "").unwrap();
+        let runtime_dir = dir.to_str();
+        let addr = |args: &[&str]| {
+            let opt = Opt::from_iter(["uksmd-ctl"].iter().chain(args).chain(&["version"]));
+            uksmd::addr::resolve(opt.addr, runtime_dir)
+        };
+
+        // flag > env > runtime dir
+        std::env::set_var(uksmd::addr::ADDR_ENV, "unix:///tmp/env.sock");
+        assert_eq!(
+            addr(&["--addr", "unix:///tmp/flag.sock"]),
+            "unix:///tmp/flag.sock"
+        );
+        assert_eq!(addr(&[]), "unix:///tmp/env.sock");
+        std::env::remove_var(uksmd::addr::ADDR_ENV);
+        assert_eq!(addr(&[]), format!("unix://{}/uksmd.sock", dir.display()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,277 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The rules to find the processes that are added automatically, for
+// example the VM processes. The rules file has a rule per line like
+// "comm=^qemu;min_rss=1073741824;uid=107;label=vm", the empty lines and the
+// lines start with '#' are skipped.
+// The keys are comm, cmdline (regexes), min_rss (bytes), uid, start and end
+// (the addr of the task), include_vma, exclude_vma and label, a key can be
+// set more than once for include_vma, exclude_vma and label.
+
+use crate::{paths, proc};
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryRule {
+    comm: Option<Regex>,
+    cmdline: Option<Regex>,
+    min_rss: u64,
+    uid: Option<u32>,
+    pub addr: Option<(u64, u64)>,
+    pub include_vma: Vec<String>,
+    pub exclude_vma: Vec<String>,
+    pub labels: Vec<String>,
+}
+
+impl FromStr for DiscoveryRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut rule = DiscoveryRule::default();
+        let (mut start, mut end) = (None, None);
+
+        for item in s
+            .split(';')
+            .map(|item| item.trim())
+            .filter(|i| !i.is_empty())
+        {
+            let (key, value) = item.split_once('=').ok_or(anyhow!(
+                "item {} of rule {} is not key=value",
+                item,
+                s
+            ))?;
+            let regex = |value: &str| {
+                Regex::new(value).map_err(|e| anyhow!("Regex::new {} failed: {}", value, e))
+            };
+            let number = |value: &str| {
+                value
+                    .parse::<u64>()
+                    .map_err(|e| anyhow!("parse {} of rule {} failed: {}", key, s, e))
+            };
+            match key.trim() {
+                "comm" => rule.comm = Some(regex(value)?),
+                "cmdline" => rule.cmdline = Some(regex(value)?),
+                "min_rss" => rule.min_rss = number(value)?,
+                "uid" => rule.uid = Some(number(value)? as u32),
+                "start" => start = Some(number(value)?),
+                "end" => end = Some(number(value)?),
+                "include_vma" => rule.include_vma.push(value.to_string()),
+                "exclude_vma" => rule.exclude_vma.push(value.to_string()),
+                "label" => rule.labels.push(value.to_string()),
+                key => return Err(anyhow!("key {} of rule {} is not supported", key, s)),
+            }
+        }
+
+        rule.addr = match (start, end) {
+            (Some(start), Some(end)) => Some((start, end)),
+            (None, None) => None,
+            _ => return Err(anyhow!("start and end of rule {} are not set together", s)),
+        };
+        if rule.comm.is_none() && rule.cmdline.is_none() {
+            return Err(anyhow!("rule {} has neither comm nor cmdline", s));
+        }
+        proc::VmaFilter::new(&rule.include_vma, &rule.exclude_vma)?;
+
+        Ok(rule)
+    }
+}
+
+// The information of a process that the rules check.
+#[derive(Debug, Clone, Default)]
+pub struct ProcInfo {
+    pub pid: u64,
+    pub comm: String,
+    pub cmdline: String,
+    // bytes
+    pub rss: u64,
+    pub uid: Option<u32>,
+}
+
+impl DiscoveryRule {
+    pub fn matches(&self, info: &ProcInfo) -> bool {
+        self.comm.as_ref().is_none_or(|re| re.is_match(&info.comm))
+            && self
+                .cmdline
+                .as_ref()
+                .is_none_or(|re| re.is_match(&info.cmdline))
+            && info.rss >= self.min_rss
+            && self.uid.is_none_or(|uid| info.uid == Some(uid))
+    }
+}
+
+// Load the rules from path.
+pub fn load_rules(path: &str) -> Result<Vec<DiscoveryRule>> {
+    let rules =
+        std::fs::read_to_string(path).map_err(|e| anyhow!("read file {} failed: {}", path, e))?;
+
+    rules
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.parse::<DiscoveryRule>())
+        .collect()
+}
+
+// The first rule that matches info.
+pub fn find_rule<'a>(rules: &'a [DiscoveryRule], info: &ProcInfo) -> Option<&'a DiscoveryRule> {
+    rules.iter().find(|rule| rule.matches(info))
+}
+
+// Get the information of all the processes, the kernel threads are skipped.
+pub fn scan() -> Result<Vec<ProcInfo>> {
+    let proc_dir = paths::proc("");
+    let dir =
+        std::fs::read_dir(&proc_dir).map_err(|e| anyhow!("read dir {} failed: {}", proc_dir, e))?;
+
+    Ok(dir
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u64>().ok())
+        .filter_map(|pid| {
+            // The process might exit during the scan.
+            let rss = proc::pid_rss(pid).ok()?;
+            Some(ProcInfo {
+                pid,
+                comm: proc::pid_comm(pid).ok()?,
+                cmdline: proc::pid_cmdline(pid).ok()?,
+                rss,
+                uid: proc::pid_uid(pid).ok(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proc::fake::{self, FakeTask};
+
+    fn rule(s: &str) -> DiscoveryRule {
+        s.parse().unwrap()
+    }
+
+    fn info(comm: &str, cmdline: &str, rss: u64, uid: Option<u32>) -> ProcInfo {
+        ProcInfo {
+            pid: 100,
+            comm: comm.to_string(),
+            cmdline: cmdline.to_string(),
+            rss,
+            uid,
+        }
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let r = rule(
+            "comm=^qemu; min_rss=1073741824;uid=107;start=4096;end=8192;\
+             exclude_vma=\\[stack\\];label=vm;label=prod",
+        );
+        assert_eq!(r.min_rss, 1 << 30);
+        assert_eq!(r.uid, Some(107));
+        assert_eq!(r.addr, Some((4096, 8192)));
+        assert_eq!(r.exclude_vma, vec!["\\[stack\\]"]);
+        assert_eq!(r.labels, vec!["vm", "prod"]);
+
+        for s in [
+            "",
+            "min_rss=1024",
+            "comm=(qemu",
+            "comm=qemu;min_rss=1G",
+            "comm=qemu;start=4096",
+            "comm=qemu;foo=bar",
+            "comm=qemu;exclude_vma=(",
+            "comm",
+        ] {
+            assert!(s.parse::<DiscoveryRule>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let gb = 1 << 30;
+        let qemu = rule("comm=^qemu;min_rss=1073741824;uid=107");
+        assert!(qemu.matches(&info("qemu-kvm", "", gb, Some(107))));
+        assert!(!qemu.matches(&info("qemu-kvm", "", gb - 1, Some(107))));
+        assert!(!qemu.matches(&info("qemu-kvm", "", gb, Some(0))));
+        assert!(!qemu.matches(&info("qemu-kvm", "", gb, None)));
+        assert!(!qemu.matches(&info("kvm-qemu", "", gb, Some(107))));
+
+        let ch = rule("cmdline=cloud-hypervisor .*--memory");
+        assert!(ch.matches(&info(
+            "vmm",
+            "/usr/bin/cloud-hypervisor --memory size=1G",
+            0,
+            None
+        )));
+        assert!(!ch.matches(&info("cloud-hypervisor", "cloud-hypervisor", 0, None)));
+
+        // The first rule that matches is used.
+        let rules = [
+            rule("comm=^qemu;label=first"),
+            rule("comm=qemu;label=second"),
+        ];
+        let find = |comm| find_rule(&rules, &info(comm, "", 0, None)).map(|r| r.labels[0].as_str());
+        assert_eq!(find("qemu-kvm"), Some("first"));
+        assert_eq!(find("kvm-qemu"), Some("second"));
+        assert_eq!(find("bash"), None);
+    }
+
+    #[test]
+    fn test_scan_and_match_fake_proc() {
+        let mut qemu = FakeTask::new(fake::new_pid());
+        qemu.comm = "qemu-kvm".to_string();
+        qemu.uid = 107;
+        qemu.write();
+        let bash = FakeTask::new(fake::new_pid());
+        bash.write();
+        // A kernel thread has no memory.
+        let kthread = fake::new_pid();
+        fake::write_file(kthread, "status", "Name:\tkworker/0:1\nUid:\t0\t0\t0\t0\n");
+        fake::write_file(kthread, "comm", "kworker/0:1\n");
+        fake::write_file(kthread, "cmdline", "");
+
+        let pids = [qemu.pid, bash.pid, kthread];
+        let procs: Vec<ProcInfo> = scan()
+            .unwrap()
+            .into_iter()
+            .filter(|info| pids.contains(&info.pid))
+            .collect();
+        assert_eq!(procs.len(), 2);
+        let p = procs.iter().find(|info| info.pid == qemu.pid).unwrap();
+        assert_eq!(
+            (p.comm.as_str(), p.cmdline.as_str(), p.rss, p.uid),
+            ("qemu-kvm", "qemu-kvm --fake", 4096 * 1024, Some(107))
+        );
+
+        let rules = [rule("comm=^qemu;cmdline=--fake;min_rss=4194304;uid=107")];
+        let found: Vec<u64> = procs
+            .iter()
+            .filter(|info| find_rule(&rules, info).is_some())
+            .map(|info| info.pid)
+            .collect();
+        assert_eq!(found, vec![qemu.pid]);
+
+        for pid in pids {
+            FakeTask::exit(pid);
+        }
+    }
+
+    #[test]
+    fn test_load_rules() {
+        let path = fake::root().join("discovery.rules");
+        std::fs::write(
+            &path,
+            "# the VMs\n\ncomm=^qemu;label=vm\n  cmdline=cloud-hypervisor  \n",
+        )
+        .unwrap();
+        let rules = load_rules(path.to_str().unwrap()).unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].labels, vec!["vm"]);
+
+        std::fs::write(&path, "comm=^qemu\nmin_rss=1\n").unwrap();
+        assert!(load_rules(path.to_str().unwrap()).is_err());
+        assert!(load_rules(fake::root().join("no.rules").to_str().unwrap()).is_err());
+    }
+}
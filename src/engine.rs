@@ -0,0 +1,127 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::agent::{Agent, AgentCmd, AgentReturn};
+use crate::protocols::uksmd_ctl;
+use crate::{backend, config, discovery, paths, proc, sched};
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+
+/// The merge engine of uksmd.
+///
+/// The agent that manages the tasks runs as a task of the tokio runtime
+/// that creates the engine, call shutdown to stop and join it.
+/// The errors that come from the tasks can be downcast to
+/// [`TaskError`](crate::TaskError).
+#[derive(Debug)]
+pub struct Engine {
+    agent: Agent,
+}
+
+impl Engine {
+    /// Check the config and fill the backend that is used if it is not set.
+    pub fn check_config(config: &mut config::Config) -> Result<()> {
+        paths::init(config);
+
+        let backend = backend::check(config).map_err(|e| anyhow!("backend::check fail: {}", e))?;
+        config.backend = Some(backend.to_string());
+
+        sched::check_worker_settings(config)
+            .map_err(|e| anyhow!("sched::check_worker_settings fail: {}", e))?;
+
+        proc::VmaFilter::new(&config.include_vma, &config.exclude_vma)
+            .map_err(|e| anyhow!("proc::VmaFilter::new fail: {}", e))?;
+
+        if let Some(path) = &config.discovery_rules {
+            discovery::load_rules(path)
+                .map_err(|e| anyhow!("discovery::load_rules {} fail: {}", path, e))?;
+        }
+
+        #[cfg(not(feature = "cri"))]
+        if config.cri_endpoint.is_some() {
+            return Err(anyhow!(
+                "cri_endpoint is set but uksmd is built without the cri feature"
+            ));
+        }
+
+        if config.free_mem_high.is_some() && config.free_mem_low.is_none() {
+            return Err(anyhow!("free_mem_high is set without free_mem_low"));
+        }
+
+        Ok(())
+    }
+
+    /// Check the config and start the engine.
+    ///
+    /// It must be called in a tokio runtime.
+    pub fn new(mut config: config::Config) -> Result<Self> {
+        Self::check_config(&mut config)?;
+
+        let agent = Agent::new(config).map_err(|e| anyhow!("Agent::new fail: {}", e))?;
+
+        Ok(Self { agent })
+    }
+
+    #[doc(hidden)]
+    pub async fn send_cmd(&self, cmd: AgentCmd) -> Result<AgentReturn> {
+        self.agent.send_cmd_async(cmd).await
+    }
+
+    #[doc(hidden)]
+    pub async fn send_cmd_deadline(
+        &self,
+        cmd: AgentCmd,
+        deadline: Option<Duration>,
+    ) -> Result<AgentReturn> {
+        self.agent.send_cmd_deadline(cmd, deadline).await
+    }
+
+    async fn run(&self, cmd: AgentCmd) -> Result<AgentReturn> {
+        match self.send_cmd(cmd).await? {
+            AgentReturn::Err(e) => Err(e),
+            ret => Ok(ret),
+        }
+    }
+
+    /// Add a task, the pages of it are merged by the next merge.
+    /// The response has the range of the task after it is aligned.
+    pub async fn add_task(&self, req: uksmd_ctl::AddRequest) -> Result<uksmd_ctl::AddResponse> {
+        match self.run(AgentCmd::Add(req)).await? {
+            AgentReturn::Add(resp) => Ok(resp),
+            _ => Err(anyhow!("agent return for Add is not right")),
+        }
+    }
+
+    /// Del a task and unmerge its pages.
+    pub async fn del_task(&self, req: uksmd_ctl::DelRequest) -> Result<()> {
+        self.run(AgentCmd::Del(req)).await?;
+        Ok(())
+    }
+
+    /// Refresh the pages of all the tasks.
+    pub async fn refresh(&self) -> Result<()> {
+        self.run(AgentCmd::Refresh).await?;
+        Ok(())
+    }
+
+    /// Merge the pages of the tasks.
+    pub async fn merge(&self, req: uksmd_ctl::MergeRequest) -> Result<()> {
+        self.run(AgentCmd::Merge(req)).await?;
+        Ok(())
+    }
+
+    /// Get the status of the engine and its tasks.
+    pub async fn status(&self) -> Result<uksmd_ctl::StatusResponse> {
+        match self.run(AgentCmd::Status).await? {
+            AgentReturn::Status(resp) => Ok(*resp),
+            _ => Err(anyhow!("agent return for Status is not right")),
+        }
+    }
+
+    /// Stop the engine after the current work item and the queued unmerge
+    /// and del work are done, and wait for the agent to stop.
+    pub async fn shutdown(&self, grace_timeout: Duration) -> Result<()> {
+        self.agent.shutdown(grace_timeout).await
+    }
+}
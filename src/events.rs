@@ -0,0 +1,75 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The recent significant events of uksmd, they can be read by RPC after
+// the log is rotated.
+
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub time: DateTime<Local>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+// A ring buffer of the events, the oldest event is dropped when it is full.
+#[derive(Debug)]
+pub struct Events {
+    capacity: usize,
+    events: Mutex<VecDeque<Event>>,
+}
+
+impl Events {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn push(&self, severity: Severity, message: String) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut events = self.events.lock().unwrap();
+        if events.len() >= self.capacity {
+            events.pop_front();
+        }
+        events.push_back(Event {
+            time: Local::now(),
+            severity,
+            message,
+        });
+    }
+
+    // Return the last count events newest first, 0 means all.
+    pub fn recent(&self, count: usize) -> Vec<Event> {
+        let events = self.events.lock().unwrap();
+        let count = if count == 0 { events.len() } else { count };
+
+        events.iter().rev().take(count).cloned().collect()
+    }
+}
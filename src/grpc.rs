@@ -0,0 +1,531 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// Serve the Control service over grpc with mTLS, for the controllers that
+// cannot use ttrpc.
+// The messages are the ones of rust-protobuf that ttrpc uses, they are
+// encoded by ProtobufCodec instead of prost.
+
+use crate::rpc::GrpcOpt;
+use anyhow::{anyhow, Result};
+use bytes::{Buf, BufMut};
+use std::fs;
+use std::marker::PhantomData;
+use std::net::ToSocketAddrs;
+use std::sync::Arc;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+use uksmd::agent::{AgentCmd, AgentReturn};
+use uksmd::protocols::{empty, uksmd_ctl};
+use uksmd::version;
+use uksmd::{Engine, TaskError};
+
+mod control {
+    include!(concat!(env!("OUT_DIR"), "/MemAgent.Control.rs"));
+}
+
+use control::control_server::{Control, ControlServer};
+
+#[derive(Debug)]
+pub struct ProtobufCodec<E, D>(PhantomData<(E, D)>);
+
+impl<E, D> Default for ProtobufCodec<E, D> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E, D> Codec for ProtobufCodec<E, D>
+where
+    E: protobuf::Message + Send + 'static,
+    D: protobuf::Message + Send + 'static,
+{
+    type Encode = E;
+    type Decode = D;
+    type Encoder = ProtobufEncoder<E>;
+    type Decoder = ProtobufDecoder<D>;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        ProtobufEncoder(PhantomData)
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        ProtobufDecoder(PhantomData)
+    }
+}
+
+#[derive(Debug)]
+pub struct ProtobufEncoder<E>(PhantomData<E>);
+
+impl<E: protobuf::Message> Encoder for ProtobufEncoder<E> {
+    type Item = E;
+    type Error = Status;
+
+    fn encode(&mut self, item: E, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        let buf = item
+            .write_to_bytes()
+            .map_err(|e| Status::internal(format!("encode {} fail: {}", E::NAME, e)))?;
+        dst.put_slice(&buf);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ProtobufDecoder<D>(PhantomData<D>);
+
+impl<D: protobuf::Message> Decoder for ProtobufDecoder<D> {
+    type Item = D;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<D>, Status> {
+        let buf = src.copy_to_bytes(src.remaining());
+        let msg = D::parse_from_bytes(&buf)
+            .map_err(|e| Status::invalid_argument(format!("decode {} fail: {}", D::NAME, e)))?;
+
+        Ok(Some(msg))
+    }
+}
+
+fn agent_error_status(e: anyhow::Error) -> Status {
+    let estr = format!("{}", e);
+    error!("agent return error: {}", estr);
+
+    match e.downcast_ref::<TaskError>() {
+        Some(TaskError::Limit(_)) => Status::resource_exhausted(estr),
+        Some(TaskError::InvalidArg(_)) => Status::invalid_argument(estr),
+        Some(TaskError::Unavailable(_)) => Status::unavailable(estr),
+        Some(TaskError::FailedPrecondition(_)) => Status::failed_precondition(estr),
+        _ => Status::internal(estr),
+    }
+}
+
+fn unexpected_return(method: &str, ret: AgentReturn) -> Status {
+    Status::internal(format!("{} get unexpected return {:?}", method, ret))
+}
+
+// The clients are authenticated by mTLS, so all of them can call all the
+// methods.
+#[derive(Debug)]
+struct GrpcControl {
+    engine: Arc<Engine>,
+}
+
+impl GrpcControl {
+    async fn send(&self, cmd: AgentCmd) -> Result<AgentReturn, Status> {
+        let cmd_str = format!("{:?}", cmd);
+        let ret = self.engine.send_cmd(cmd).await.map_err(|e| {
+            let estr = format!("engine.send_cmd {} fail: {}", cmd_str, e);
+            error!("{}", estr);
+            // The queue of the agent is full.
+            if matches!(
+                e.downcast_ref::<TaskError>(),
+                Some(TaskError::Unavailable(_))
+            ) {
+                Status::unavailable(estr)
+            } else {
+                Status::internal(estr)
+            }
+        })?;
+
+        match ret {
+            AgentReturn::Err(e) => Err(agent_error_status(e)),
+            ret => Ok(ret),
+        }
+    }
+
+    async fn send_empty(&self, cmd: AgentCmd) -> Result<Response<empty::Empty>, Status> {
+        self.send(cmd).await?;
+        Ok(Response::new(empty::Empty::new()))
+    }
+}
+
+#[tonic::async_trait]
+impl Control for GrpcControl {
+    async fn add(
+        &self,
+        req: Request<uksmd_ctl::AddRequest>,
+    ) -> Result<Response<uksmd_ctl::AddResponse>, Status> {
+        match self.send(AgentCmd::Add(req.into_inner())).await? {
+            AgentReturn::Add(resp) => Ok(Response::new(resp)),
+            ret => Err(unexpected_return("add", ret)),
+        }
+    }
+
+    async fn add_by_container(
+        &self,
+        req: Request<uksmd_ctl::AddByContainerRequest>,
+    ) -> Result<Response<uksmd_ctl::AddResponse>, Status> {
+        match self
+            .send(AgentCmd::AddByContainer(req.into_inner()))
+            .await?
+        {
+            AgentReturn::Add(resp) => Ok(Response::new(resp)),
+            ret => Err(unexpected_return("add_by_container", ret)),
+        }
+    }
+
+    async fn del(
+        &self,
+        req: Request<uksmd_ctl::DelRequest>,
+    ) -> Result<Response<empty::Empty>, Status> {
+        self.send_empty(AgentCmd::Del(req.into_inner())).await
+    }
+
+    async fn refresh(&self, _: Request<empty::Empty>) -> Result<Response<empty::Empty>, Status> {
+        self.send_empty(AgentCmd::Refresh).await
+    }
+
+    async fn merge(
+        &self,
+        req: Request<uksmd_ctl::MergeRequest>,
+    ) -> Result<Response<empty::Empty>, Status> {
+        self.send_empty(AgentCmd::Merge(req.into_inner())).await
+    }
+
+    async fn status(
+        &self,
+        _: Request<empty::Empty>,
+    ) -> Result<Response<uksmd_ctl::StatusResponse>, Status> {
+        match self.send(AgentCmd::Status).await? {
+            AgentReturn::Status(resp) => Ok(Response::new(*resp)),
+            ret => Err(unexpected_return("status", ret)),
+        }
+    }
+
+    async fn stats(
+        &self,
+        _: Request<empty::Empty>,
+    ) -> Result<Response<uksmd_ctl::StatsResponse>, Status> {
+        match self.send(AgentCmd::Stats).await? {
+            AgentReturn::Stats(resp) => Ok(Response::new(*resp)),
+            ret => Err(unexpected_return("stats", ret)),
+        }
+    }
+
+    async fn set_config(
+        &self,
+        req: Request<uksmd_ctl::SetConfigRequest>,
+    ) -> Result<Response<empty::Empty>, Status> {
+        self.send_empty(AgentCmd::SetConfig(req.into_inner())).await
+    }
+
+    async fn pause_task(
+        &self,
+        req: Request<uksmd_ctl::PauseTaskRequest>,
+    ) -> Result<Response<empty::Empty>, Status> {
+        self.send_empty(AgentCmd::PauseTask(req.into_inner())).await
+    }
+
+    async fn resume_task(
+        &self,
+        req: Request<uksmd_ctl::ResumeTaskRequest>,
+    ) -> Result<Response<empty::Empty>, Status> {
+        self.send_empty(AgentCmd::ResumeTask(req.into_inner()))
+            .await
+    }
+
+    async fn update(
+        &self,
+        req: Request<uksmd_ctl::UpdateRequest>,
+    ) -> Result<Response<empty::Empty>, Status> {
+        self.send_empty(AgentCmd::Update(req.into_inner())).await
+    }
+
+    async fn unmerge(
+        &self,
+        req: Request<uksmd_ctl::UnmergeRequest>,
+    ) -> Result<Response<empty::Empty>, Status> {
+        self.send_empty(AgentCmd::UnMerge(req.into_inner())).await
+    }
+
+    async fn history(
+        &self,
+        req: Request<uksmd_ctl::HistoryRequest>,
+    ) -> Result<Response<uksmd_ctl::HistoryResponse>, Status> {
+        match self.send(AgentCmd::History(req.into_inner())).await? {
+            AgentReturn::History(resp) => Ok(Response::new(resp)),
+            ret => Err(unexpected_return("history", ret)),
+        }
+    }
+
+    async fn recent_events(
+        &self,
+        req: Request<uksmd_ctl::RecentEventsRequest>,
+    ) -> Result<Response<uksmd_ctl::RecentEventsResponse>, Status> {
+        match self.send(AgentCmd::RecentEvents(req.into_inner())).await? {
+            AgentReturn::RecentEvents(resp) => Ok(Response::new(resp)),
+            ret => Err(unexpected_return("recent_events", ret)),
+        }
+    }
+
+    async fn get_maps(
+        &self,
+        req: Request<uksmd_ctl::GetMapsRequest>,
+    ) -> Result<Response<uksmd_ctl::GetMapsResponse>, Status> {
+        match self.send(AgentCmd::GetMaps(req.into_inner())).await? {
+            AgentReturn::GetMaps(resp) => Ok(Response::new(resp)),
+            ret => Err(unexpected_return("get_maps", ret)),
+        }
+    }
+
+    async fn crc_histogram(
+        &self,
+        req: Request<uksmd_ctl::CrcHistogramRequest>,
+    ) -> Result<Response<uksmd_ctl::CrcHistogramResponse>, Status> {
+        match self.send(AgentCmd::CrcHistogram(req.into_inner())).await? {
+            AgentReturn::CrcHistogram(resp) => Ok(Response::new(resp)),
+            ret => Err(unexpected_return("crc_histogram", ret)),
+        }
+    }
+
+    async fn watch_cgroup(
+        &self,
+        req: Request<uksmd_ctl::WatchCgroupRequest>,
+    ) -> Result<Response<empty::Empty>, Status> {
+        self.send_empty(AgentCmd::WatchCgroup(req.into_inner()))
+            .await
+    }
+
+    async fn unwatch_cgroup(
+        &self,
+        req: Request<uksmd_ctl::UnwatchCgroupRequest>,
+    ) -> Result<Response<empty::Empty>, Status> {
+        self.send_empty(AgentCmd::UnwatchCgroup(req.into_inner()))
+            .await
+    }
+
+    async fn unmerge_all(
+        &self,
+        _: Request<empty::Empty>,
+    ) -> Result<Response<uksmd_ctl::UnmergeAllResponse>, Status> {
+        match self.send(AgentCmd::UnmergeAll).await? {
+            AgentReturn::UnmergeAll(resp) => Ok(Response::new(resp)),
+            ret => Err(unexpected_return("unmerge_all", ret)),
+        }
+    }
+
+    async fn drain(
+        &self,
+        _: Request<empty::Empty>,
+    ) -> Result<Response<uksmd_ctl::DrainResponse>, Status> {
+        match self.send(AgentCmd::Drain).await? {
+            AgentReturn::Drain(resp) => Ok(Response::new(resp)),
+            ret => Err(unexpected_return("drain", ret)),
+        }
+    }
+
+    async fn get_version(
+        &self,
+        _: Request<empty::Empty>,
+    ) -> Result<Response<uksmd_ctl::VersionResponse>, Status> {
+        Ok(Response::new(uksmd_ctl::VersionResponse {
+            version: version::VERSION.to_string(),
+            api_level: version::API_LEVEL,
+            ..Default::default()
+        }))
+    }
+}
+
+fn read_pem(path: &Option<String>, name: &str) -> Result<Vec<u8>> {
+    let path = path
+        .as_ref()
+        .ok_or(anyhow!("grpc_tls_{} is not set", name))?;
+    fs::read(path).map_err(|e| anyhow!("read grpc_tls_{} {} fail: {}", name, path, e))
+}
+
+// The grpc server that runs until stop is called.
+pub struct GrpcServer {
+    stop_tx: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl GrpcServer {
+    pub fn start(opt: &GrpcOpt, engine: Arc<Engine>) -> Result<Self> {
+        let addr_str = opt
+            .grpc_addr
+            .as_ref()
+            .ok_or(anyhow!("grpc_addr is not set"))?;
+        let addr = addr_str
+            .to_socket_addrs()
+            .map_err(|e| anyhow!("resolve grpc_addr {} fail: {}", addr_str, e))?
+            .next()
+            .ok_or(anyhow!("grpc_addr {} has no address", addr_str))?;
+
+        let identity = Identity::from_pem(
+            read_pem(&opt.grpc_tls_cert, "cert")?,
+            read_pem(&opt.grpc_tls_key, "key")?,
+        );
+        let ca = Certificate::from_pem(read_pem(&opt.grpc_tls_ca, "ca")?);
+        let tls = ServerTlsConfig::new().identity(identity).client_ca_root(ca);
+
+        let server = Server::builder()
+            .tls_config(tls)
+            .map_err(|e| anyhow!("grpc tls_config fail: {}", e))?
+            .add_service(ControlServer::new(GrpcControl { engine }));
+
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let handle = tokio::spawn(async move {
+            info!("uksmd: grpc server listens on {}", addr);
+            if let Err(e) = server
+                .serve_with_shutdown(addr, async {
+                    stop_rx.await.ok();
+                })
+                .await
+            {
+                error!("uksmd: grpc server fail: {}", e);
+            }
+        });
+
+        Ok(Self { stop_tx, handle })
+    }
+
+    pub async fn stop(self) {
+        self.stop_tx.send(()).ok();
+        if let Err(e) = self.handle.await {
+            error!("uksmd: grpc server join fail: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::time::{Duration, Instant};
+    use tonic::codegen::http::uri::PathAndQuery;
+    use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
+    use tonic::Code;
+
+    use crate::common::{fake_config, fake_root, stats, wait_for, write_task, MAP_START};
+
+    fn pem(name: &str) -> String {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/testdata/grpc")
+            .join(name)
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    // Connect to the server when it is up.
+    async fn connect(port: u16, identity: bool) -> Result<Channel> {
+        let mut tls = ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(fs::read(pem("ca.pem"))?))
+            .domain_name("localhost");
+        if identity {
+            tls = tls.identity(Identity::from_pem(
+                fs::read(pem("client.pem"))?,
+                fs::read(pem("client.key"))?,
+            ));
+        }
+        let endpoint = Endpoint::from_shared(format!("https://127.0.0.1:{}", port))?
+            .tls_config(tls)?
+            .timeout(Duration::from_secs(5));
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            match endpoint.connect().await {
+                Ok(channel) => return Ok(channel),
+                Err(e) if Instant::now() > deadline => return Err(e.into()),
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        }
+    }
+
+    async fn call<E, D>(channel: &Channel, method: &'static str, req: E) -> Result<D, Status>
+    where
+        E: protobuf::Message + Send + Sync + 'static,
+        D: protobuf::Message + Send + Sync + 'static,
+    {
+        let mut grpc = tonic::client::Grpc::new(channel.clone());
+        grpc.ready()
+            .await
+            .map_err(|e| Status::unavailable(e.to_string()))?;
+        let path = PathAndQuery::try_from(format!("/MemAgent.Control/{}", method)).unwrap();
+        grpc.unary(Request::new(req), path, ProtobufCodec::<E, D>::default())
+            .await
+            .map(|resp| resp.into_inner())
+    }
+
+    async fn add(channel: &Channel, pid: u64) -> Result<uksmd_ctl::AddResponse, Status> {
+        let req = uksmd_ctl::AddRequest {
+            pid,
+            ..Default::default()
+        };
+        call(channel, "Add", req).await
+    }
+
+    #[tokio::test]
+    async fn test_grpc_add_refresh_merge() {
+        let root = fake_root();
+        let config = fake_config(&["--min-dup-count", "1"]);
+        let (proc, uksm) = (root.join("proc"), root.join("uksm"));
+        let (a, b) = (1 << 23, (1 << 23) + 1);
+        write_task(&proc, a, &[7, 8]);
+        write_task(&proc, b, &[7, 9]);
+
+        let engine = Arc::new(Engine::new(config).unwrap());
+        let port = free_port();
+        let opt = GrpcOpt {
+            grpc_addr: Some(format!("127.0.0.1:{}", port)),
+            grpc_tls_cert: Some(pem("server.pem")),
+            grpc_tls_key: Some(pem("server.key")),
+            grpc_tls_ca: Some(pem("ca.pem")),
+        };
+        let server = GrpcServer::start(&opt, engine.clone()).unwrap();
+        let channel = connect(port, true).await.unwrap();
+
+        for pid in [a, b] {
+            add(&channel, pid).await.unwrap();
+        }
+        // The errors of the agent keep their codes.
+        let err = add(&channel, std::process::id() as u64).await.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument, "{}", err);
+
+        let refresh = || async {
+            call::<_, empty::Empty>(&channel, "Refresh", empty::Empty::new())
+                .await
+                .unwrap();
+        };
+        refresh().await;
+        wait_for(|| async { stats(&engine).await.tracked_pages == 4 }).await;
+        refresh().await;
+        call::<_, empty::Empty>(&channel, "Merge", uksmd_ctl::MergeRequest::default())
+            .await
+            .unwrap();
+        let cmds = [(a, b), (b, a)]
+            .map(|(p1, p2)| format!("{} 0x{:x} {} 0x{:x}", p1, MAP_START, p2, MAP_START));
+        wait_for(|| async {
+            let merge = fs::read_to_string(uksm.join("merge")).unwrap();
+            cmds.iter().any(|cmd| merge.contains(cmd))
+        })
+        .await;
+
+        // The clients without a certificate of the CA are refused.
+        let anonymous = match connect(port, false).await {
+            Ok(channel) => {
+                call::<_, uksmd_ctl::VersionResponse>(&channel, "GetVersion", empty::Empty::new())
+                    .await
+                    .is_err()
+            }
+            Err(_) => true,
+        };
+        assert!(anonymous);
+
+        server.stop().await;
+        engine.shutdown(Duration::from_secs(5)).await.unwrap();
+    }
+}
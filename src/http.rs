@@ -0,0 +1,366 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The read-only http endpoints of the status, for the monitors that do not
+// have uksmd-ctl.
+// The json is built from the same messages as the ones of the rpcs.
+
+use crate::rpc::HttpOpt;
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{timeout, Duration};
+use uksmd::agent::{AgentCmd, AgentReturn};
+use uksmd::protocols::{json, uksmd_ctl};
+use uksmd::{Engine, TaskError};
+
+const MAX_REQUEST_LEN: usize = 8192;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_EVENTS_COUNT: u64 = 100;
+
+// The lists of the pids in StatusResponse that are flags of the tasks.
+const TASK_FLAGS: [(&str, &str); 2] = [("paused_pids", "paused"), ("discovered", "discovered")];
+
+// The tasks of the status, the maps of the pids are split into the tasks.
+fn tasks_json(status: &Value) -> Value {
+    let mut tasks: BTreeMap<u64, Map<String, Value>> = BTreeMap::new();
+
+    if let Some(status) = status.as_object() {
+        for (name, value) in status {
+            if let Some(map) = value.as_object() {
+                for (pid, v) in map {
+                    if let Ok(pid) = pid.parse::<u64>() {
+                        tasks
+                            .entry(pid)
+                            .or_default()
+                            .insert(name.clone(), v.clone());
+                    }
+                }
+            }
+        }
+
+        for (list, flag) in TASK_FLAGS {
+            let pids: Vec<u64> = status
+                .get(list)
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|v| v.as_u64()).collect())
+                .unwrap_or_default();
+            for (pid, task) in tasks.iter_mut() {
+                task.insert(flag.to_string(), Value::from(pids.contains(pid)));
+            }
+        }
+    }
+
+    Value::Array(
+        tasks
+            .into_iter()
+            .map(|(pid, mut task)| {
+                task.insert("pid".to_string(), Value::from(pid));
+                Value::Object(task)
+            })
+            .collect(),
+    )
+}
+
+fn query_u64(query: &str, key: &str) -> Option<u64> {
+    query
+        .split('&')
+        .filter_map(|kv| kv.split_once('='))
+        .find(|(k, _)| *k == key)
+        .and_then(|(_, v)| v.parse().ok())
+}
+
+async fn query(engine: &Engine, cmd: AgentCmd) -> Result<AgentReturn> {
+    match engine.send_cmd(cmd).await? {
+        AgentReturn::Err(e) => Err(e),
+        ret => Ok(ret),
+    }
+}
+
+// Return the status code and the body of path.
+async fn handle_get(engine: &Engine, target: &str) -> (&'static str, Value) {
+    let (path, query_str) = target.split_once('?').unwrap_or((target, ""));
+
+    let ret = match path {
+        "/status" => match query(engine, AgentCmd::Status).await {
+            Ok(AgentReturn::Status(resp)) => Ok(json::message_json(&*resp)),
+            Ok(ret) => Err(anyhow!("unexpected return {:?}", ret)),
+            Err(e) => Err(e),
+        },
+        "/tasks" => match query(engine, AgentCmd::Status).await {
+            Ok(AgentReturn::Status(resp)) => Ok(tasks_json(&json::message_json(&*resp))),
+            Ok(ret) => Err(anyhow!("unexpected return {:?}", ret)),
+            Err(e) => Err(e),
+        },
+        "/stats" => match query(engine, AgentCmd::Stats).await {
+            Ok(AgentReturn::Stats(resp)) => Ok(json::message_json(&*resp)),
+            Ok(ret) => Err(anyhow!("unexpected return {:?}", ret)),
+            Err(e) => Err(e),
+        },
+        "/events" => {
+            let req = uksmd_ctl::RecentEventsRequest {
+                count: query_u64(query_str, "count").unwrap_or(DEFAULT_EVENTS_COUNT),
+                ..Default::default()
+            };
+            match query(engine, AgentCmd::RecentEvents(req)).await {
+                Ok(AgentReturn::RecentEvents(resp)) => Ok(json::message_json(&resp)),
+                Ok(ret) => Err(anyhow!("unexpected return {:?}", ret)),
+                Err(e) => Err(e),
+            }
+        }
+        _ => return ("404 Not Found", error_json("not found")),
+    };
+
+    match ret {
+        Ok(body) => ("200 OK", body),
+        Err(e) => {
+            error!("uksmd: http get {} fail: {}", path, e);
+            if matches!(
+                e.downcast_ref::<TaskError>(),
+                Some(TaskError::Unavailable(_))
+            ) {
+                ("503 Service Unavailable", error_json(&e.to_string()))
+            } else {
+                ("500 Internal Server Error", error_json(&e.to_string()))
+            }
+        }
+    }
+}
+
+fn error_json(error: &str) -> Value {
+    let mut obj = Map::new();
+    obj.insert("error".to_string(), Value::from(error));
+    Value::Object(obj)
+}
+
+// Read the head of the request, the body is ignored because there is no
+// method with a body.
+async fn read_head(stream: &mut TcpStream) -> Result<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() >= MAX_REQUEST_LEN {
+            return Err(anyhow!("request is longer than {}", MAX_REQUEST_LEN));
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(anyhow!("connection is closed before the request is done"));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    String::from_utf8(buf).map_err(|e| anyhow!("request is not utf8: {}", e))
+}
+
+async fn handle_conn(engine: &Engine, mut stream: TcpStream) -> Result<()> {
+    let head = timeout(REQUEST_TIMEOUT, read_head(&mut stream))
+        .await
+        .map_err(|_| anyhow!("read request timeout"))??;
+
+    // GET /status HTTP/1.1
+    let mut parts = head.lines().next().unwrap_or("").split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => handle_get(engine, target).await,
+        (Some(_), Some(_)) => ("405 Method Not Allowed", error_json("only GET is allowed")),
+        _ => ("400 Bad Request", error_json("bad request")),
+    };
+
+    let body = body.to_string();
+    let resp = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(resp.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+async fn resolve(opt: &HttpOpt, port: u16) -> Result<SocketAddr> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((opt.http_host.as_str(), port))
+        .await
+        .map_err(|e| anyhow!("resolve http_host {} fail: {}", opt.http_host, e))?
+        .collect();
+
+    if !opt.http_allow_remote {
+        if let Some(addr) = addrs.iter().find(|a| !a.ip().is_loopback()) {
+            return Err(anyhow!(
+                "http_host {} is not loopback, set http_allow_remote to listen on it",
+                addr.ip()
+            ));
+        }
+    }
+
+    addrs
+        .into_iter()
+        .next()
+        .ok_or(anyhow!("http_host {} has no address", opt.http_host))
+}
+
+// The http server that runs until stop is called.
+pub struct HttpServer {
+    stop_tx: oneshot::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl HttpServer {
+    pub async fn start(opt: &HttpOpt, engine: Arc<Engine>) -> Result<Self> {
+        let port = opt.http_port.ok_or(anyhow!("http_port is not set"))?;
+        let addr = resolve(opt, port).await?;
+        let listener = TcpListener::bind(addr)
+            .await
+            .map_err(|e| anyhow!("TcpListener::bind {} fail: {}", addr, e))?;
+        info!("uksmd: http server listens on {}", addr);
+
+        let (stop_tx, stop_rx) = oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            tokio::pin!(stop_rx);
+            loop {
+                tokio::select! {
+                    _ = &mut stop_rx => break,
+                    ret = listener.accept() => match ret {
+                        Ok((stream, peer)) => {
+                            let engine = engine.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = handle_conn(&engine, stream).await {
+                                    warn!("uksmd: http request from {} fail: {}", peer, e);
+                                }
+                            });
+                        }
+                        Err(e) => error!("uksmd: http accept fail: {}", e),
+                    },
+                }
+            }
+        });
+
+        Ok(Self { stop_tx, handle })
+    }
+
+    pub async fn stop(self) {
+        self.stop_tx.send(()).ok();
+        if let Err(e) = self.handle.await {
+            error!("uksmd: http server join fail: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{fake_config, fake_root, write_task};
+
+    fn opt(host: &str, allow_remote: bool) -> HttpOpt {
+        HttpOpt {
+            http_port: Some(0),
+            http_host: host.to_string(),
+            http_allow_remote: allow_remote,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_refuses_remote() {
+        let addr = resolve(&opt("127.0.0.1", false), 8080).await.unwrap();
+        assert_eq!(addr, "127.0.0.1:8080".parse().unwrap());
+        let addr = resolve(&opt("::1", false), 8080).await.unwrap();
+        assert!(addr.ip().is_loopback());
+
+        let e = resolve(&opt("10.1.2.3", false), 8080).await.unwrap_err();
+        assert!(e.to_string().contains("set http_allow_remote"), "{}", e);
+        let e = resolve(&opt("0.0.0.0", false), 8080).await.unwrap_err();
+        assert!(e.to_string().contains("is not loopback"), "{}", e);
+
+        let addr = resolve(&opt("10.1.2.3", true), 8080).await.unwrap();
+        assert_eq!(addr, "10.1.2.3:8080".parse().unwrap());
+    }
+
+    // Get path from the server and return the status line and the body.
+    async fn get(addr: SocketAddr, path: &str) -> (String, Value) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+            .await
+            .unwrap();
+        let mut resp = String::new();
+        stream.read_to_string(&mut resp).await.unwrap();
+        let (head, body) = resp.split_once("\r\n\r\n").unwrap();
+        let status = head.lines().next().unwrap().to_string();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_status_json() {
+        let proc = fake_root().join("proc");
+        let (a, b) = ((1 << 23) + 200, (1 << 23) + 201);
+        write_task(&proc, a, &[7, 8]);
+        write_task(&proc, b, &[7, 9]);
+        let engine = Arc::new(Engine::new(fake_config(&[])).unwrap());
+        for pid in [a, b] {
+            let req = uksmd_ctl::AddRequest {
+                pid,
+                ..Default::default()
+            };
+            assert!(matches!(
+                engine.send_cmd(AgentCmd::Add(req)).await.unwrap(),
+                AgentReturn::Add(_)
+            ));
+        }
+        let req = uksmd_ctl::PauseTaskRequest {
+            pid: b,
+            ..Default::default()
+        };
+        assert!(matches!(
+            engine.send_cmd(AgentCmd::PauseTask(req)).await.unwrap(),
+            AgentReturn::Ok
+        ));
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut opt = opt("127.0.0.1", false);
+        opt.http_port = Some(port);
+        let server = HttpServer::start(&opt, engine.clone()).await.unwrap();
+        let addr = resolve(&opt, port).await.unwrap();
+
+        // The status is the json of StatusResponse, the maps are keyed by
+        // the pids.
+        let (status, body) = get(addr, "/status").await;
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body["comm"][a.to_string()], "fake");
+        assert_eq!(body["priority"][b.to_string()], 0);
+        assert_eq!(body["paused_pids"], serde_json::json!([b]));
+        assert_eq!(body["cmd_queue_depth"], 0);
+
+        // The tasks are the entries of the maps of each pid, with the flags
+        // of the pid lists.
+        let (status, body) = get(addr, "/tasks").await;
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let tasks = body.as_array().unwrap();
+        assert_eq!(tasks.len(), 2);
+        for (task, pid) in tasks.iter().zip([a, b]) {
+            assert_eq!(task["pid"], pid);
+            assert_eq!(task["comm"], "fake");
+            assert_eq!(task["task_state"], "idle");
+            assert_eq!(task["paused"], pid == b);
+            assert_eq!(task["discovered"], false);
+        }
+
+        let (status, body) = get(addr, "/nothing").await;
+        assert_eq!(status, "HTTP/1.1 404 Not Found");
+        assert_eq!(body["error"], "not found");
+
+        server.stop().await;
+        engine.shutdown(Duration::from_secs(10)).await.unwrap();
+    }
+}
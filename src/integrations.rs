@@ -0,0 +1,9 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The optional integrations with the other systems, each of them is
+// enabled by a feature.
+
+#[cfg(feature = "cri")]
+pub mod cri;
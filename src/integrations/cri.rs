@@ -0,0 +1,55 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// Resolve a container ID to its init pid by the CRI ContainerStatus call.
+// The call is done by crictl, so uksmd doesn't need a gRPC client.
+
+use crate::task::TaskError;
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+const CRICTL: &str = "crictl";
+const CRICTL_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Get the init pid of container from the CRI runtime at endpoint.
+pub async fn container_pid(endpoint: &str, container_id: &str) -> Result<u64> {
+    let unavailable = |msg: String| anyhow::Error::new(TaskError::Unavailable(msg));
+
+    let output = timeout(
+        CRICTL_TIMEOUT,
+        Command::new(CRICTL)
+            .args(["--runtime-endpoint", endpoint, "inspect", "-o", "json"])
+            .arg(container_id)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| {
+        unavailable(format!(
+            "{} inspect {} is not done in {:?}",
+            CRICTL, container_id, CRICTL_TIMEOUT
+        ))
+    })?
+    .map_err(|e| unavailable(format!("run {} failed: {}", CRICTL, e)))?;
+    if !output.status.success() {
+        return Err(unavailable(format!(
+            "ContainerStatus {} from {} failed: {}",
+            container_id,
+            endpoint,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| anyhow!("parse ContainerStatus of {} failed: {}", container_id, e))?;
+    match status["info"]["pid"].as_u64() {
+        Some(pid) if pid > 0 => Ok(pid),
+        _ => Err(anyhow!(
+            "ContainerStatus of {} doesn't have the pid, is it running?",
+            container_id
+        )),
+    }
+}
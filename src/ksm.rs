@@ -0,0 +1,225 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::uksm::{KernelError, PidAddr, UKSMPagemapEntry};
+use crate::{backend, page, paths};
+use anyhow::{anyhow, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+
+const KSM_RUN_PATH: &str = "/sys/kernel/mm/ksm/run";
+lazy_static! {
+    static ref KPAGEFLAGS_PATH: String = paths::proc("kpageflags");
+}
+
+const PM_ENTRY_SIZE: u64 = 8;
+const PM_PFRAME_MASK: u64 = (1 << 55) - 1;
+const PM_PRESENT: u64 = 1 << 63;
+const KPF_KSM: u64 = 1 << 21;
+const KPF_THP: u64 = 1 << 22;
+
+pub fn check_kernel() -> Result<()> {
+    OpenOptions::new()
+        .write(true)
+        .open(KSM_RUN_PATH)
+        .map_err(|e| anyhow!("open file {} failed: {}", KSM_RUN_PATH, e))?;
+
+    Ok(())
+}
+
+// The backend that uses the standard KSM of the kernel.
+// The pages are marked MADV_MERGEABLE and ksmd merges them later, so a
+// page might be found not merged by the next refresh and be merged again.
+#[derive(Debug)]
+pub struct KsmBackend {
+    // pages read by a process_vm_readv
+    chunk_pages: u64,
+}
+
+impl KsmBackend {
+    pub fn new(chunk_pages: u64) -> Self {
+        // Start ksmd.
+        if let Err(e) = write_run("1") {
+            warn!("{}", e);
+        }
+
+        Self {
+            chunk_pages: chunk_pages.max(1),
+        }
+    }
+}
+
+fn write_run(value: &str) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(KSM_RUN_PATH)
+        .map_err(|e| anyhow!("open file {} failed: {}", KSM_RUN_PATH, e))?;
+    file.write_all(value.as_bytes())
+        .map_err(|e| anyhow!("write file {} failed: {}", KSM_RUN_PATH, e))?;
+
+    Ok(())
+}
+
+fn page_crc(data: &[u8]) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    let h = hasher.finish();
+
+    (h ^ (h >> 32)) as u32
+}
+
+fn pidfd_open(pid: u64) -> Result<OwnedFd> {
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+    if fd < 0 {
+        let e = std::io::Error::last_os_error();
+        if e.raw_os_error() == Some(libc::ESRCH) {
+            return Err(anyhow::Error::new(KernelError::TaskGone(pid)));
+        }
+        return Err(anyhow!("pidfd_open {} failed: {}", pid, e));
+    }
+
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+}
+
+// Call madvise for the page of pa in its process.
+pub fn madvise_page(pa: &PidAddr, advice: libc::c_int) -> Result<()> {
+    let pidfd = pidfd_open(pa.pid)?;
+    let iov = libc::iovec {
+        iov_base: pa.addr as *mut libc::c_void,
+        iov_len: *page::PAGE_SIZE as usize,
+    };
+
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_process_madvise,
+            pidfd.as_raw_fd(),
+            &iov as *const libc::iovec,
+            1,
+            advice,
+            0,
+        )
+    };
+    if ret < 0 {
+        let e = std::io::Error::last_os_error();
+        return Err(match e.raw_os_error() {
+            Some(libc::ESRCH) => anyhow::Error::new(KernelError::TaskGone(pa.pid)),
+            Some(libc::ENOMEM) | Some(libc::EFAULT) => {
+                anyhow::Error::new(KernelError::PageGone(pa.pid, pa.addr))
+            }
+            _ => anyhow!(
+                "process_madvise {} 0x{:x} {} failed: {}",
+                pa.pid,
+                pa.addr,
+                advice,
+                e
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+impl backend::KernelOps for KsmBackend {
+    fn name(&self) -> &'static str {
+        "ksm"
+    }
+
+    // Get the pfn from pagemap, the KSM flag from kpageflags and the crc
+    // from the content of the page.
+    fn read_pagemap_chunked(
+        &self,
+        pid: u64,
+        start: u64,
+        end: u64,
+        f: &mut backend::PagemapChunkFn,
+    ) -> Result<()> {
+        let pagemap_path = paths::proc(&format!("{}/pagemap", pid));
+        let pagemap = File::open(&pagemap_path).map_err(|e| match e.raw_os_error() {
+            Some(libc::ENOENT) | Some(libc::ESRCH) => {
+                anyhow::Error::new(KernelError::TaskGone(pid))
+            }
+            _ => anyhow!("open file {} failed: {}", pagemap_path, e),
+        })?;
+        let kpageflags = File::open(&*KPAGEFLAGS_PATH)
+            .map_err(|e| anyhow!("open file {} failed: {}", *KPAGEFLAGS_PATH, e))?;
+
+        let page_size = *page::PAGE_SIZE;
+        let mut entries = Vec::with_capacity(self.chunk_pages as usize);
+        let mut data = vec![0u8; (self.chunk_pages * page_size) as usize];
+        let mut pms = vec![0u8; (self.chunk_pages * PM_ENTRY_SIZE) as usize];
+        let mut flags = [0u8; PM_ENTRY_SIZE as usize];
+
+        let mut addr = start;
+        while addr < end {
+            let count = std::cmp::min(self.chunk_pages, (end - addr) / page_size);
+            let pms = &mut pms[..(count * PM_ENTRY_SIZE) as usize];
+            pagemap
+                .read_exact_at(pms, addr / page_size * PM_ENTRY_SIZE)
+                .map_err(|e| anyhow!("read file {} 0x{:x} failed: {}", pagemap_path, addr, e))?;
+
+            let local = libc::iovec {
+                iov_base: data.as_mut_ptr() as *mut libc::c_void,
+                iov_len: (count * page_size) as usize,
+            };
+            let remote = libc::iovec {
+                iov_base: addr as *mut libc::c_void,
+                iov_len: (count * page_size) as usize,
+            };
+            let read =
+                unsafe { libc::process_vm_readv(pid as libc::pid_t, &local, 1, &remote, 1, 0) };
+            let read_pages = if read < 0 { 0 } else { read as u64 / page_size };
+
+            entries.clear();
+            for i in 0..count {
+                let off = (i * PM_ENTRY_SIZE) as usize;
+                let pme =
+                    u64::from_ne_bytes(pms[off..off + 8].try_into().expect("Expected 8 bytes"));
+                if pme & PM_PRESENT == 0 || i >= read_pages {
+                    entries.push(None);
+                    continue;
+                }
+
+                let pfn = pme & PM_PFRAME_MASK;
+                kpageflags
+                    .read_exact_at(&mut flags, pfn * PM_ENTRY_SIZE)
+                    .map_err(|e| anyhow!("read file {} {} failed: {}", *KPAGEFLAGS_PATH, pfn, e))?;
+                let kflags = u64::from_ne_bytes(flags);
+
+                let off = (i * page_size) as usize;
+                entries.push(Some(UKSMPagemapEntry {
+                    pfn,
+                    crc: page_crc(&data[off..off + page_size as usize]),
+                    is_thp: kflags & KPF_THP != 0,
+                    is_ksm: kflags & KPF_KSM != 0,
+                    idle: None,
+                }));
+            }
+
+            f(addr, &mut entries)?;
+            addr += count * page_size;
+        }
+
+        Ok(())
+    }
+
+    fn zero_crc(&self) -> Option<u32> {
+        Some(page_crc(&vec![0u8; *page::PAGE_SIZE as usize]))
+    }
+
+    // KSM merges the same pages itself, just let it scan the both pages.
+    fn cmp_merge(&self, pa1: &PidAddr, pa2: &PidAddr) -> Result<bool> {
+        madvise_page(pa1, libc::MADV_MERGEABLE)?;
+        madvise_page(pa2, libc::MADV_MERGEABLE)?;
+
+        Ok(true)
+    }
+
+    fn unmerge(&self, pa: &PidAddr) -> Result<()> {
+        madvise_page(pa, libc::MADV_UNMERGEABLE)
+    }
+}
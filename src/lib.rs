@@ -1,5 +1,44 @@
-// Copyright (C) 2023 Ant group. All rights reserved.
+// Copyright (C) 2023, 2024 Ant group. All rights reserved.
 //
 // SPDX-License-Identifier: Apache-2.0
 
+//! The engine of uksmd that merges the anonymous pages of the tasks.
+//!
+//! [`Engine`] is what the uksmd daemon runs under its ttrpc service, other
+//! daemons can embed it to manage the tasks without the service.
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate lazy_static;
+
+pub mod addr;
+#[doc(hidden)]
+pub mod agent;
+mod backend;
+pub mod bench;
+mod cmdqueue;
+pub mod config;
+mod discovery;
+mod engine;
+mod events;
+mod integrations;
+mod ksm;
+mod page;
+mod paths;
+mod proc;
 pub mod protocols;
+mod queue;
+mod ratelimit;
+mod reaper;
+mod sched;
+mod state;
+mod task;
+mod timing;
+mod uksm;
+pub mod version;
+mod window;
+
+pub use config::Config;
+pub use engine::Engine;
+pub use task::TaskError;
@@ -0,0 +1,117 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The formats of the log records.
+// The json format writes a record as an object in a line, the newlines in
+// the message are escaped so a multi-line error stays in one record.
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+use log::Record;
+use log4rs::encode::{self, Encode};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(anyhow!("log format {} is not supported", s)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct JsonEncoder {
+    // distinguish the records of the different runs of uksmd
+    instance: String,
+}
+
+impl JsonEncoder {
+    pub fn new(instance: &str) -> Self {
+        Self {
+            instance: instance.to_string(),
+        }
+    }
+}
+
+impl Encode for JsonEncoder {
+    fn encode(&self, w: &mut dyn encode::Write, record: &Record) -> anyhow::Result<()> {
+        let obj = serde_json::json!({
+            "timestamp": Local::now().to_rfc3339(),
+            "level": record.level().to_string(),
+            "target": record.target(),
+            "file": record.file(),
+            "line": record.line(),
+            "message": record.args().to_string(),
+            "instance": self.instance,
+        });
+        serde_json::to_writer(&mut *w, &obj)?;
+        w.write_all(b"\n")?;
+
+        Ok(())
+    }
+}
+
+// The id of this run of uksmd.
+pub fn instance_id() -> String {
+    let start = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+
+    format!("{:x}-{}", start, std::process::id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+    use log4rs::encode::writer::simple::SimpleWriter;
+
+    #[test]
+    fn test_json_encoder() {
+        let mut buf = SimpleWriter(Vec::new());
+        let record = Record::builder()
+            .level(Level::Error)
+            .target("uksmd::task")
+            .file(Some("src/task.rs"))
+            .line(Some(42))
+            .args(format_args!(
+                "merge 100 failed: write merge failed\n\nCaused by:\n    \"EIO\""
+            ))
+            .build();
+        JsonEncoder::new("66a0b1c2-100")
+            .encode(&mut buf, &record)
+            .unwrap();
+
+        // The record is in one line even if the message has newlines.
+        let out = String::from_utf8(buf.0).unwrap();
+        assert_eq!(out.matches('\n').count(), 1);
+        assert!(out.ends_with('\n'));
+
+        let mut obj: serde_json::Value = serde_json::from_str(&out).unwrap();
+        let timestamp = obj["timestamp"].take();
+        chrono::DateTime::parse_from_rfc3339(timestamp.as_str().unwrap()).unwrap();
+        assert_eq!(
+            obj.to_string(),
+            r#"{"file":"src/task.rs","instance":"66a0b1c2-100","level":"ERROR","line":42,"message":"merge 100 failed: write merge failed\n\nCaused by:\n    \"EIO\"","target":"uksmd::task","timestamp":null}"#
+        );
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("text".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!("yaml".parse::<LogFormat>().is_err());
+    }
+}
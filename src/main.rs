@@ -11,35 +11,83 @@ use log4rs::{
     append::console::ConsoleAppender,
     append::file::FileAppender,
     config::{Appender, Config, Root},
-    encode::pattern::PatternEncoder,
+    encode::{pattern::PatternEncoder, Encode},
 };
 use structopt::StructOpt;
 
-mod agent;
-mod page;
-mod proc;
-mod protocols;
+mod auth;
+// The fake procfs and uksm interface of the integration tests, the tests of
+// the rpc servers use a part of its helpers.
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "../tests/common/mod.rs"]
+mod common;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod http;
+mod logfmt;
 mod rpc;
-mod task;
-mod uksm;
+
+#[derive(StructOpt, Debug)]
+struct BenchOpt {
+    /// run one refresh and merge cycle against --bench-pid, print the
+    /// report and exit
+    #[structopt(long, requires = "bench-pid")]
+    bench: bool,
+    #[structopt(long)]
+    bench_pid: Option<u64>,
+    /// only measure the pages in [bench_start, bench_end)
+    #[structopt(long, requires = "bench-end")]
+    bench_start: Option<u64>,
+    #[structopt(long, requires = "bench-start")]
+    bench_end: Option<u64>,
+    /// keep the pages merged when the bench exits
+    #[structopt(long)]
+    bench_keep: bool,
+    /// text or json
+    #[structopt(long, default_value = "text")]
+    bench_output: logfmt::LogFormat,
+}
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "uksmd", about = "uKSM daemon")]
 struct Opt {
-    #[structopt(long, default_value = "unix:///var/run/uksmd.sock")]
-    addr: String,
+    /// address to listen on, unix:///var/run/uksmd.sock if it is not given
+    #[structopt(long, env = "UKSMD_ADDR")]
+    addr: Option<String>,
     #[structopt(long)]
     log_file: Option<String>,
     #[structopt(long, default_value = "Trace")]
     log_level: log::LevelFilter,
+    #[structopt(long, default_value = "text")]
+    log_format: logfmt::LogFormat,
+    #[structopt(flatten)]
+    grpc: rpc::GrpcOpt,
+    #[structopt(flatten)]
+    http: rpc::HttpOpt,
+    #[structopt(flatten)]
+    bench: BenchOpt,
+    #[structopt(flatten)]
+    config: uksmd::Config,
 }
 
 pub const LOG_FORMAT: &str = "{d} [{l}] {f}:{L} - {m}{n}";
 
+lazy_static! {
+    static ref INSTANCE_ID: String = logfmt::instance_id();
+}
+
+fn log_encoder(format: logfmt::LogFormat) -> Box<dyn Encode> {
+    match format {
+        logfmt::LogFormat::Text => Box::new(PatternEncoder::new(LOG_FORMAT)),
+        logfmt::LogFormat::Json => Box::new(logfmt::JsonEncoder::new(&INSTANCE_ID)),
+    }
+}
+
 fn setup_logging(opt: &Opt) -> Result<()> {
     let config = if let Some(f) = &opt.log_file {
         let file_appender = FileAppender::builder()
-            .encoder(Box::new(PatternEncoder::new(LOG_FORMAT)))
+            .encoder(log_encoder(opt.log_format))
             .build(f)
             .map_err(|e| anyhow!("FileAppender::builder() file {} fail: {}", f, e))?;
 
@@ -49,7 +97,7 @@ fn setup_logging(opt: &Opt) -> Result<()> {
             .map_err(|e| anyhow!("Config::builder file_appender fail: {}", e))?
     } else {
         let stderr_appender = ConsoleAppender::builder()
-            .encoder(Box::new(PatternEncoder::new(LOG_FORMAT)))
+            .encoder(log_encoder(opt.log_format))
             .build();
 
         Config::builder()
@@ -63,21 +111,64 @@ fn setup_logging(opt: &Opt) -> Result<()> {
     Ok(())
 }
 
+// Run the bench in a runtime of its own and print the report.
+fn bench(opt: BenchOpt, config: uksmd::Config) -> Result<()> {
+    let req = uksmd::bench::BenchRequest {
+        pid: opt.bench_pid.ok_or(anyhow!("bench_pid is not set"))?,
+        addr: opt.bench_start.zip(opt.bench_end),
+        keep: opt.bench_keep,
+    };
+
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| anyhow!("Builder::new_multi_thread fail: {}", e))?;
+    let report = rt
+        .block_on(uksmd::bench::run(config, req))
+        .map_err(|e| anyhow!("bench::run fail: {}", e))?;
+
+    match opt.bench_output {
+        logfmt::LogFormat::Text => print!("{}", report),
+        logfmt::LogFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report)
+                .map_err(|e| anyhow!("serde_json::to_string_pretty fail: {}", e))?
+        ),
+    }
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
     // Check opt
-    let opt = Opt::from_args();
+    let mut opt = Opt::from_args();
 
     setup_logging(&opt).map_err(|e| anyhow!("setup_logging fail: {}", e))?;
 
-    uksm::check_kernel().map_err(|e| anyhow!("uksm::check_kernel fail: {}", e))?;
-
-    info!("uKSM daemon start");
-
-    rpc::rpc_loop(opt.addr).map_err(|e| {
-        let estr = format!("rpc::grpc_loop fail: {}", e);
-        error!("{}", estr);
-        anyhow!("{}", estr)
-    })?;
+    uksmd::Engine::check_config(&mut opt.config)?;
+    info!(
+        "use backend {}",
+        opt.config.backend.as_deref().unwrap_or_default()
+    );
+
+    if opt.bench.bench {
+        return bench(opt.bench, opt.config);
+    }
+
+    info!("uKSM daemon start, instance {}", *INSTANCE_ID);
+
+    // The rpc servers, the agent and the signal handlers share the runtime.
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| anyhow!("Builder::new_multi_thread fail: {}", e))?;
+    let addr = uksmd::addr::resolve(opt.addr, None);
+    rt.block_on(rpc::rpc_loop(addr, opt.grpc, opt.http, opt.config))
+        .map_err(|e| {
+            let estr = format!("rpc::rpc_loop fail: {}", e);
+            error!("{}", estr);
+            anyhow!("{}", estr)
+        })?;
 
     info!("uKSM daemon stop");
 
@@ -3,186 +3,1475 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::proc::MapRange;
-use crate::{proc, task, uksm};
+use crate::{backend, config, proc, ratelimit, task, timing, uksm};
 use anyhow::{anyhow, Result};
-use page_size;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 lazy_static! {
     pub static ref PAGE_SIZE: u64 = page_size::get() as u64;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PageEntry {
     pub crc: u32,
+    // the frame of the page if the kernel reports it as a ksm page or the
+    // page may share its frame with a forked task, 0 if unknown. It is read
+    // again by the refresh, so it is not saved.
+    #[serde(skip)]
+    pub pfn: u64,
+    // the frame is not a ksm page
+    #[serde(skip)]
+    pub cow: bool,
 }
 
-#[derive(Default, Debug)]
+#[allow(dead_code)]
+#[derive(Default, Debug, Clone, Copy)]
 pub struct InfoStatus {
     pub new_count: u64,
     pub old_count: u64,
     pub uksm_count: u64,
+    pub zero_count: u64,
+    pub thp_count: u64,
+    pub stale_count: u64,
+    pub foreign_count: u64,
+    // the outcomes of the adds of the merges of the task
+    pub probes: uksm::ProbeCounts,
 }
 
-#[derive(Debug, Clone)]
-pub struct Info {
-    pid: u64,
+// The change of the page counts of a task.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct InfoDelta {
+    pub new_count: i64,
+    pub old_count: i64,
+    pub uksm_count: i64,
+}
+
+// A sample of the merge savings of a task after a merge or verify.
+#[derive(Debug, Clone, Copy)]
+pub struct HistorySample {
+    // seconds since the epoch
+    pub time: u64,
+    pub uksm_count: u64,
+    pub saved_bytes: u64,
+}
+
+impl InfoStatus {
+    pub fn delta(&self, before: &InfoStatus) -> InfoDelta {
+        InfoDelta {
+            new_count: self.new_count as i64 - before.new_count as i64,
+            old_count: self.old_count as i64 - before.old_count as i64,
+            uksm_count: self.uksm_count as i64 - before.uksm_count as i64,
+        }
+    }
+}
+
+// How to handle the pages of a transparent huge page.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ThpPolicy {
+    // Don't track the pages.
+    Skip,
+    // Disable the huge page of the pages before merging them.
+    Split,
+    // Merge the pages as the others, the kernel splits the huge page.
+    #[default]
+    Merge,
+}
+
+impl FromStr for ThpPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "skip" => Ok(ThpPolicy::Skip),
+            "split" => Ok(ThpPolicy::Split),
+            "merge" => Ok(ThpPolicy::Merge),
+            _ => Err(anyhow!("thp policy {} is not supported", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for ThpPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            ThpPolicy::Skip => "skip",
+            ThpPolicy::Split => "split",
+            ThpPolicy::Merge => "merge",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// The part of Info that is kept in the state file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InfoState {
+    pub pid: u64,
+    start_time: u64,
     maps: Vec<proc::MapRange>,
-    new_pages: HashMap<u64, PageEntry>,
-    old_pages: HashMap<u64, PageEntry>,
     uksm_pages: HashMap<u64, PageEntry>,
+    #[serde(default)]
+    merge_cursor: Option<u64>,
+}
+
+// The global budget of the pages tracked by all the Info.
+#[derive(Debug)]
+pub struct PageBudget {
+    limit: u64,
+    used: AtomicU64,
+    // pages that are not tracked because the budget is exceeded
+    skipped: AtomicU64,
+}
+
+impl PageBudget {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            used: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+        }
+    }
+
+    fn try_acquire(&self) -> bool {
+        let ret = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                if used < self.limit {
+                    Some(used + 1)
+                } else {
+                    None
+                }
+            });
+        if ret.is_err() {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        ret.is_ok()
+    }
+
+    // Pages loaded from the state file are always tracked.
+    fn force_acquire(&self, count: u64) {
+        self.used.fetch_add(count, Ordering::Relaxed);
+    }
+
+    fn release(&self, count: u64) {
+        self.used.fetch_sub(count, Ordering::Relaxed);
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    pub fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    pub fn skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+}
+
+// The global count of each crc of the Old and Uksm pages of all the Info.
+// A crc that only has one page cannot be merged.
+#[derive(Debug)]
+pub struct CrcCounts {
+    counts: Mutex<HashMap<u32, u32>>,
+    // the pages of a crc with a smaller count are not merged
+    min_dup_count: AtomicU32,
+    // pages that are not merged because of min_dup_count
+    skipped: AtomicU64,
+    zero_crc: Option<u32>,
+    merge_zero_pages: bool,
+}
+
+impl CrcCounts {
+    pub fn new(config: &config::Config, zero_crc: Option<u32>) -> Self {
+        Self {
+            counts: Mutex::new(HashMap::new()),
+            min_dup_count: AtomicU32::new(config.min_dup_count),
+            skipped: AtomicU64::new(0),
+            zero_crc,
+            merge_zero_pages: config.merge_zero_pages,
+        }
+    }
+
+    fn is_zero(&self, crc: u32) -> bool {
+        self.zero_crc == Some(crc)
+    }
+
+    pub fn min_dup_count(&self) -> u32 {
+        self.min_dup_count.load(Ordering::Relaxed)
+    }
+
+    pub fn set_min_dup_count(&self, count: u32) {
+        self.min_dup_count.store(count, Ordering::Relaxed);
+    }
+
+    // The approximate bytes of the counts.
+    pub fn mem_bytes(&self) -> u64 {
+        (self.counts.lock().unwrap().capacity() * 2 * std::mem::size_of::<u32>()) as u64
+    }
+
+    // Get the skipped pages and reset it.
+    pub fn take_skipped(&self) -> u64 {
+        self.skipped.swap(0, Ordering::Relaxed)
+    }
+
+    fn inc(&self, crc: u32) {
+        *self.counts.lock().unwrap().entry(crc).or_insert(0) += 1;
+    }
+
+    fn dec(&self, crc: u32) {
+        let mut counts = self.counts.lock().unwrap();
+        if let Some(count) = counts.get_mut(&crc) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&crc);
+            }
+        }
+    }
+
+    pub fn get(&self, crc: u32) -> u32 {
+        self.counts.lock().unwrap().get(&crc).copied().unwrap_or(0)
+    }
+
+    // Copy the counts to not block the others when they are handled.
+    pub fn snapshot(&self) -> Vec<(u32, u64)> {
+        self.counts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(crc, count)| (*crc, *count as u64))
+            .collect()
+    }
+}
+
+// The ranges of the page counts of the buckets of CrcHistogram.
+const CRC_BUCKETS: [(&str, u64, u64); 5] = [
+    ("1", 1, 1),
+    ("2", 2, 2),
+    ("3-10", 3, 10),
+    ("11-100", 11, 100),
+    (">100", 101, u64::MAX),
+];
+
+// The distribution of the page counts of the crcs.
+#[derive(Debug, Default)]
+pub struct CrcHistogram {
+    // the range, crcs and pages of each bucket
+    pub buckets: Vec<(&'static str, u64, u64)>,
+    // the crcs that have the most pages and their page counts
+    pub top: Vec<(u32, u64)>,
+}
+
+impl CrcHistogram {
+    pub fn new(mut counts: Vec<(u32, u64)>, top: usize) -> Self {
+        let buckets = CRC_BUCKETS
+            .iter()
+            .map(|(name, min, max)| {
+                counts
+                    .iter()
+                    .filter(|(_, count)| count >= min && count <= max)
+                    .fold((*name, 0, 0), |(name, crcs, pages), (_, count)| {
+                        (name, crcs + 1, pages + count)
+                    })
+            })
+            .collect();
+
+        counts.sort_unstable_by_key(|(crc, count)| (std::cmp::Reverse(*count), *crc));
+        counts.truncate(top);
+
+        Self {
+            buckets,
+            top: counts,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageState {
+    None = 0,
+    New,
+    Old,
+    Uksm,
+    // An Old page that failed to merge with the pages of its crc too many
+    // times in a row, it is not merged until its crc changes.
+    Stale,
+    // A page that the kernel merged without uksm, for example by ksmd. It
+    // is neither merged nor unmerged until the kernel unmerges it.
+    Foreign,
+}
+
+// Compact record of a page, 8 bytes.
+#[derive(Debug, Clone, Copy)]
+struct PageRec {
+    crc: u32,
+    state: PageState,
+    // refreshes that the crc of a New page is unchanged
+    age: u8,
+    // the page is a part of a transparent huge page
+    thp: bool,
+    // merges in a row that the Old page is not the same as the pages of
+    // its crc
+    fails: u8,
+}
+
+impl PageRec {
+    // If the crc of the page is counted in CrcCounts.
+    fn counted(&self) -> bool {
+        self.state == PageState::Old || self.state == PageState::Uksm
+    }
+
+    const NONE: PageRec = PageRec {
+        crc: 0,
+        state: PageState::None,
+        age: 0,
+        thp: false,
+        fails: 0,
+    };
+}
+
+// The pages of a chunk of a map.
+const CHUNK_PAGES: usize = 512;
+
+#[derive(Debug)]
+struct PageChunk {
+    pages: [PageRec; CHUNK_PAGES],
+    // number of the pages that are not None
+    used: usize,
+}
+
+// The pages of a map indexed by (addr - range.start) / PAGE_SIZE.
+// They are kept in the chunks of CHUNK_PAGES pages, a chunk is only
+// allocated when it has a page that is not None, so a large map that has
+// a few pages costs a few chunks.
+#[derive(Debug)]
+struct MapPages {
+    range: proc::MapRange,
+    count: usize,
+    chunks: Vec<Option<Box<PageChunk>>>,
+}
+
+impl MapPages {
+    fn new(range: proc::MapRange) -> Self {
+        let count = ((range.end - range.start) / *PAGE_SIZE) as usize;
+        let mut chunks = Vec::new();
+        chunks.resize_with(count.div_ceil(CHUNK_PAGES), || None);
+        Self {
+            range,
+            count,
+            chunks,
+        }
+    }
+
+    fn index(&self, addr: u64) -> usize {
+        ((addr - self.range.start) / *PAGE_SIZE) as usize
+    }
+
+    fn addr(&self, index: usize) -> u64 {
+        self.range.start + index as u64 * *PAGE_SIZE
+    }
+
+    fn get(&self, index: usize) -> Option<&PageRec> {
+        if index >= self.count {
+            return None;
+        }
+        match &self.chunks[index / CHUNK_PAGES] {
+            Some(chunk) => Some(&chunk.pages[index % CHUNK_PAGES]),
+            None => Some(&PageRec::NONE),
+        }
+    }
+
+    // The page in a chunk that is not allocated is None, it is only changed
+    // by set.
+    fn get_mut(&mut self, index: usize) -> Option<&mut PageRec> {
+        if index >= self.count {
+            return None;
+        }
+        self.chunks[index / CHUNK_PAGES]
+            .as_mut()
+            .map(|chunk| &mut chunk.pages[index % CHUNK_PAGES])
+    }
+
+    fn set(&mut self, index: usize, rec: PageRec) {
+        if index >= self.count {
+            return;
+        }
+        let slot = &mut self.chunks[index / CHUNK_PAGES];
+        let chunk = match slot {
+            Some(chunk) => chunk,
+            None if rec.state == PageState::None => return,
+            None => slot.insert(Box::new(PageChunk {
+                pages: [PageRec::NONE; CHUNK_PAGES],
+                used: 0,
+            })),
+        };
+        let page = &mut chunk.pages[index % CHUNK_PAGES];
+        match (page.state == PageState::None, rec.state == PageState::None) {
+            (true, false) => chunk.used += 1,
+            (false, true) => chunk.used -= 1,
+            _ => {}
+        }
+        *page = rec;
+        if chunk.used == 0 {
+            *slot = None;
+        }
+    }
+
+    // The pages that are not None in [first, last) and their indexes.
+    fn range(&self, first: usize, last: usize) -> impl Iterator<Item = (usize, &PageRec)> {
+        let last = last.min(self.count);
+        let first = first.min(last);
+        let chunks = first / CHUNK_PAGES..last.div_ceil(CHUNK_PAGES);
+        self.chunks[chunks.clone()]
+            .iter()
+            .zip(chunks)
+            .filter_map(|(chunk, c)| Some((chunk.as_ref()?, c * CHUNK_PAGES)))
+            .flat_map(move |(chunk, base)| {
+                let from = first.max(base) - base;
+                let to = last.min(base + CHUNK_PAGES) - base;
+                chunk.pages[from..to]
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, rec)| (base + from + i, rec))
+            })
+            .filter(|(_, rec)| rec.state != PageState::None)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (usize, &PageRec)> {
+        self.range(0, self.count)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chunks.iter().all(|chunk| chunk.is_none())
+    }
+
+    fn mem_bytes(&self) -> usize {
+        self.chunks.iter().flatten().count() * std::mem::size_of::<PageChunk>()
+            + self.chunks.capacity() * std::mem::size_of::<Option<Box<PageChunk>>>()
+    }
+}
+
+#[derive(Debug)]
+pub struct Info {
+    pid: u64,
+    // start time of the task to distinguish the tasks with the same pid
+    start_time: u64,
+    // sorted by the start of range and not overlapping
+    maps: Vec<MapPages>,
+    // pages count of each PageState, PageState::None is not counted
+    counts: [u64; 6],
+    // Old and Uksm pages that are zero pages
+    zero_count: u64,
+    // pages of the transparent huge pages that the last refresh found
+    thp_count: u64,
+    thp: ThpPolicy,
+    // track the pages that the kernel merged without uksm as the others
+    adopt_foreign_ksm: bool,
+    // the frames of the New and Old pages that the kernel reports as ksm
+    // pages, they join the groups in the same frames without a merge
+    ksm_pfns: HashMap<u64, u64>,
+    // the frames of the other New and Old pages if the task forked or is
+    // forked by another tracked task, their pages might share the frames
+    track_cow: bool,
+    cow_pfns: HashMap<u64, u64>,
+    // the starts of the maps whose pages the kernel rejected to merge, to
+    // warn once for each map
+    rejected_maps: HashSet<u64>,
+    // the exec id of the task when it is refreshed
+    exec_id: Option<(u64, u64)>,
+    budget: Arc<PageBudget>,
+    crcs: Arc<CrcCounts>,
+    // the last samples of the merge savings, they are kept when the maps
+    // are refreshed or cleared
+    history: VecDeque<HistorySample>,
+    // the merge of the task is split into chunks, the next one starts from
+    // the cursor and about merge_remaining Old pages are left
+    merge_cursor: Option<u64>,
+    merge_remaining: u64,
+    // the outcomes of the adds of the merges, they are kept when the maps
+    // are cleared
+    probes: uksm::ProbeCounts,
+}
+
+impl Drop for Info {
+    fn drop(&mut self) {
+        self.release_crcs();
+        self.budget.release(self.page_count());
+    }
 }
 
 impl Info {
-    pub fn new(pid: u64) -> Self {
+    pub fn new(pid: u64, start_time: u64, budget: Arc<PageBudget>, crcs: Arc<CrcCounts>) -> Self {
         Self {
             pid,
+            start_time,
             maps: Vec::new(),
-            new_pages: HashMap::new(),
-            old_pages: HashMap::new(),
-            uksm_pages: HashMap::new(),
+            counts: [0; 6],
+            zero_count: 0,
+            thp_count: 0,
+            thp: ThpPolicy::default(),
+            adopt_foreign_ksm: false,
+            ksm_pfns: HashMap::new(),
+            track_cow: false,
+            cow_pfns: HashMap::new(),
+            rejected_maps: HashSet::new(),
+            exec_id: None,
+            budget,
+            crcs,
+            history: VecDeque::new(),
+            merge_cursor: None,
+            merge_remaining: 0,
+            probes: uksm::ProbeCounts::default(),
+        }
+    }
+
+    pub fn from_state(state: InfoState, budget: Arc<PageBudget>, crcs: Arc<CrcCounts>) -> Self {
+        let mut info = Self::new(state.pid, state.start_time, budget, crcs);
+        info.maps = state.maps.into_iter().map(MapPages::new).collect();
+        info.maps.sort_by_key(|m| m.range.start);
+        info.merge_cursor = state.merge_cursor;
+
+        for (addr, entry) in state.uksm_pages {
+            if info.get(addr).is_some() {
+                info.set(
+                    addr,
+                    PageRec {
+                        crc: entry.crc,
+                        state: PageState::Uksm,
+                        age: 0,
+                        thp: false,
+                        fails: 0,
+                    },
+                );
+                info.counts[PageState::Uksm as usize] += 1;
+                info.crcs.inc(entry.crc);
+                if info.crcs.is_zero(entry.crc) {
+                    info.zero_count += 1;
+                }
+            }
+        }
+        info.budget.force_acquire(info.page_count());
+
+        info
+    }
+
+    pub fn to_state(&self) -> InfoState {
+        InfoState {
+            pid: self.pid,
+            start_time: self.start_time,
+            maps: self.maps.iter().map(|m| m.range.clone()).collect(),
+            uksm_pages: self
+                .addrs(PageState::Uksm)
+                .into_iter()
+                .filter_map(|addr| {
+                    self.get(addr).map(|rec| {
+                        (
+                            addr,
+                            PageEntry {
+                                crc: rec.crc,
+                                pfn: 0,
+                                cow: false,
+                            },
+                        )
+                    })
+                })
+                .collect(),
+            merge_cursor: self.merge_cursor,
+        }
+    }
+
+    fn page_count(&self) -> u64 {
+        self.counts[PageState::New as usize]
+            + self.counts[PageState::Old as usize]
+            + self.counts[PageState::Uksm as usize]
+            + self.counts[PageState::Stale as usize]
+            + self.counts[PageState::Foreign as usize]
+    }
+
+    fn map_index(&self, addr: u64) -> Option<usize> {
+        let i = self.maps.partition_point(|m| m.range.end <= addr);
+        if i < self.maps.len() && self.maps[i].range.start <= addr {
+            Some(i)
+        } else {
+            None
+        }
+    }
+
+    fn get(&self, addr: u64) -> Option<&PageRec> {
+        let m = &self.maps[self.map_index(addr)?];
+        m.get(m.index(addr))
+    }
+
+    // None if the page is None, see MapPages::get_mut.
+    fn get_mut(&mut self, addr: u64) -> Option<&mut PageRec> {
+        let i = self.map_index(addr)?;
+        let m = &mut self.maps[i];
+        let index = m.index(addr);
+        m.get_mut(index)
+    }
+
+    fn set(&mut self, addr: u64, rec: PageRec) {
+        if let Some(i) = self.map_index(addr) {
+            let m = &mut self.maps[i];
+            let index = m.index(addr);
+            m.set(index, rec);
+        }
+    }
+
+    fn addrs(&self, state: PageState) -> Vec<u64> {
+        let mut addrs = Vec::with_capacity(self.counts[state as usize] as usize);
+        for m in self.maps.iter() {
+            for (i, rec) in m.iter() {
+                if rec.state == state {
+                    addrs.push(m.addr(i));
+                }
+            }
+        }
+        addrs
+    }
+
+    // The addrs of the pages in [start, end) that f accepts, the pages that
+    // are not in it are skipped without looking up the maps for each of
+    // them.
+    fn range_addrs<F: Fn(&PageRec) -> bool>(&self, start: u64, end: u64, f: F) -> Vec<u64> {
+        let mut addrs = Vec::new();
+        let mut i = self.maps.partition_point(|m| m.range.end <= start);
+        while i < self.maps.len() && self.maps[i].range.start < end {
+            let m = &self.maps[i];
+            let first = m.index(start.max(m.range.start));
+            let last = m.index(end.min(m.range.end));
+            for (index, rec) in m.range(first, last) {
+                if f(rec) {
+                    addrs.push(m.addr(index));
+                }
+            }
+            i += 1;
+        }
+        addrs
+    }
+
+    fn set_state(&mut self, addr: u64, state: PageState, crc: u32) {
+        if let Some(old_rec) = self.get(addr).copied() {
+            let new_rec = PageRec {
+                crc,
+                state,
+                age: 0,
+                thp: false,
+                fails: 0,
+            };
+            self.set(addr, new_rec);
+            if old_rec.counted() {
+                self.crcs.dec(old_rec.crc);
+                if self.crcs.is_zero(old_rec.crc) {
+                    self.zero_count -= 1;
+                }
+            }
+            if new_rec.counted() {
+                self.crcs.inc(new_rec.crc);
+                if self.crcs.is_zero(new_rec.crc) {
+                    self.zero_count += 1;
+                }
+            }
+            let old_state = old_rec.state;
+            if old_state != PageState::None {
+                self.counts[old_state as usize] -= 1;
+            }
+            if state != PageState::None {
+                self.counts[state as usize] += 1;
+            }
+        }
+    }
+
+    pub fn start_time(&self) -> u64 {
+        self.start_time
+    }
+
+    pub fn exec_id(&self) -> Option<(u64, u64)> {
+        self.exec_id
+    }
+
+    pub fn set_exec_id(&mut self, exec_id: Option<(u64, u64)>) {
+        self.exec_id = exec_id;
+    }
+
+    pub fn set_thp(&mut self, thp: ThpPolicy) {
+        self.thp = thp;
+    }
+
+    pub fn set_adopt_foreign_ksm(&mut self, adopt: bool) {
+        self.adopt_foreign_ksm = adopt;
+    }
+
+    pub fn set_track_cow(&mut self, track: bool) {
+        if !track {
+            self.cow_pfns.clear();
         }
+        self.track_cow = track;
+    }
+
+    fn release_crcs(&self) {
+        for m in self.maps.iter() {
+            for (_, rec) in m.iter().filter(|(_, rec)| rec.counted()) {
+                self.crcs.dec(rec.crc);
+            }
+        }
+    }
+
+    // Remove all the pages, the merged pages are removed from uksm too.
+    pub fn clear(&mut self, uksm: &mut uksm::Uksm) {
+        uksm.remove_pid(self.pid);
+        self.release_crcs();
+        self.budget.release(self.page_count());
+        self.counts = [0; 6];
+        self.zero_count = 0;
+        self.maps.clear();
+        self.ksm_pfns.clear();
+        self.cow_pfns.clear();
+        self.merge_cursor = None;
+        self.merge_remaining = 0;
     }
 
     fn remove(&mut self, uksm: &mut uksm::Uksm, addr: u64) {
-        if let Some(_) = self.new_pages.remove(&addr) {
-            return;
+        let rec = match self.get(addr) {
+            Some(rec) if rec.state != PageState::None => *rec,
+            _ => return,
+        };
+
+        // An Old page might be in uksm waiting for the same pages.
+        if rec.state == PageState::Uksm || uksm.contains(self.pid, addr) {
+            uksm.remove(self.pid, addr);
         }
+        self.budget.release(1);
+        self.set_state(addr, PageState::None, 0);
+        self.remove_pfn(addr);
+    }
 
-        if let Some(_) = self.old_pages.remove(&addr) {
-            return;
+    fn remove_pfn(&mut self, addr: u64) {
+        if !self.ksm_pfns.is_empty() {
+            self.ksm_pfns.remove(&addr);
         }
+        if !self.cow_pfns.is_empty() {
+            self.cow_pfns.remove(&addr);
+        }
+    }
 
-        if let Some(e) = self.uksm_pages.remove(&addr) {
-            uksm.remove(self.pid, addr, e.crc);
+    // Remove the pages in [start, end).
+    // The merged pages are unmerged if unmerge is set.
+    fn remove_range(&mut self, uksm: &mut uksm::Uksm, start: u64, end: u64, unmerge: bool) {
+        let addrs = self.range_addrs(start, end, |rec| rec.state != PageState::None);
+        for addr in addrs {
+            if unmerge
+                && self
+                    .get(addr)
+                    .is_some_and(|rec| rec.state == PageState::Uksm)
+            {
+                if let Err(e) = uksm.unmerge(self.pid, addr) {
+                    trace!("unmerge {} 0x{:x} failed: {}", self.pid, addr, e);
+                }
+            }
+            self.remove(uksm, addr);
         }
     }
 
+    // The maps might be still there but filtered out, unmerge their pages.
     fn remove_maps(&mut self, uksm: &mut uksm::Uksm, maps: Vec<MapRange>) {
         for map in maps {
-            for addr in (map.start..map.end).step_by(*PAGE_SIZE as usize) {
+            self.remove_range(uksm, map.start, map.end, true);
+        }
+    }
+
+    // A New page becomes Old after its crc is unchanged in stable_cycles
+    // refreshes and it is idle if the idle of the page is known.
+    // A page that the kernel merged without uksm becomes Foreign unless
+    // adopt_foreign_ksm is set.
+    // The pages of the transparent huge pages are not tracked if the thp
+    // policy is skip.
+    fn update(
+        &mut self,
+        uksm: &mut uksm::Uksm,
+        addr: u64,
+        entry: uksm::UKSMPagemapEntry,
+        stable_cycles: u8,
+    ) {
+        let rec = match self.get(addr) {
+            Some(rec) => *rec,
+            None => return,
+        };
+
+        if entry.is_thp {
+            self.thp_count += 1;
+            if self.thp == ThpPolicy::Skip {
                 self.remove(uksm, addr);
+                return;
             }
         }
-    }
 
-    fn update(&mut self, uksm: &mut uksm::Uksm, addr: u64, entry: uksm::UKSMPagemapEntry) {
-        if let Some(e) = self.new_pages.get_mut(&addr) {
-            if e.crc != entry.crc {
-                e.crc = entry.crc;
-            } else if let Some(value) = self.new_pages.remove(&addr) {
-                self.old_pages.insert(addr, value);
+        // The kernel merged the page but uksm didn't, the Old page that
+        // waits in uksm is merged when a later page is merged with it.
+        let foreign = !self.adopt_foreign_ksm
+            && entry.is_ksm
+            && rec.state != PageState::Uksm
+            && !uksm.contains(self.pid, addr);
+
+        match rec.state {
+            PageState::Foreign if foreign => {}
+            PageState::None if foreign => {
+                if self.budget.try_acquire() {
+                    self.set_state(addr, PageState::Foreign, entry.crc);
+                }
+            }
+            _ if foreign => self.set_state(addr, PageState::Foreign, entry.crc),
+            // The kernel unmerged it, or it is adopted.
+            PageState::Foreign => self.set_state(addr, PageState::New, entry.crc),
+            PageState::New => {
+                if rec.crc != entry.crc {
+                    self.set_state(addr, PageState::New, entry.crc);
+                } else if entry.idle == Some(false) {
+                    // The page is accessed, age it again.
+                    if let Some(rec) = self.get_mut(addr) {
+                        rec.age = 0;
+                    }
+                } else if rec.age.saturating_add(1) >= stable_cycles {
+                    self.set_state(addr, PageState::Old, rec.crc);
+                } else if let Some(rec) = self.get_mut(addr) {
+                    rec.age += 1;
+                }
+            }
+            PageState::Old => {
+                if rec.crc != entry.crc {
+                    if uksm.contains(self.pid, addr) {
+                        uksm.remove(self.pid, addr);
+                    }
+                    self.set_state(addr, PageState::New, entry.crc);
+                }
+            }
+            // The page that shares the frame of its group with a forked task
+            // is not a ksm page.
+            PageState::Uksm => {
+                let shared = entry.is_ksm || uksm.frame_shared(self.pid, addr, entry.pfn);
+                if !shared || rec.crc != entry.crc {
+                    uksm.remove(self.pid, addr);
+                    self.set_state(addr, PageState::New, entry.crc);
+                }
+            }
+            PageState::Stale => {
+                if rec.crc != entry.crc {
+                    self.set_state(addr, PageState::New, entry.crc);
+                }
+            }
+            PageState::None => {
+                if self.budget.try_acquire() {
+                    self.set_state(addr, PageState::New, entry.crc);
+                }
             }
-            return;
         }
 
-        if let Some(e) = self.old_pages.get_mut(&addr) {
-            if e.crc != entry.crc {
-                e.crc = entry.crc;
-                if let Some(value) = self.old_pages.remove(&addr) {
-                    self.new_pages.insert(addr, value);
+        let state = match self.get(addr) {
+            Some(rec) => rec.state,
+            None => return,
+        };
+        if let Some(rec) = self.get_mut(addr) {
+            rec.thp = entry.is_thp;
+        }
+        // Keep the frames of the ksm pages and the pages of the forked
+        // tasks to find the groups that they share the frames with already.
+        match state {
+            PageState::New | PageState::Old if entry.is_ksm => {
+                self.ksm_pfns.insert(addr, entry.pfn);
+            }
+            PageState::New | PageState::Old if self.track_cow && entry.pfn != 0 => {
+                if !self.ksm_pfns.is_empty() {
+                    self.ksm_pfns.remove(&addr);
                 }
+                self.cow_pfns.insert(addr, entry.pfn);
             }
-            return;
+            _ => self.remove_pfn(addr),
         }
+        if matches!(state, PageState::Old | PageState::Uksm) {
+            let pfn = if entry.is_ksm || self.track_cow {
+                entry.pfn
+            } else {
+                0
+            };
+            uksm.set_pfn(self.pid, addr, pfn, entry.is_ksm);
+        }
+    }
+
+    // Remove the pages of the maps that are gone and move the pages
+    // to the layout of maps.
+    pub fn refresh_remove_maps(&mut self, uksm: &mut uksm::Uksm, maps: &[proc::MapRange]) {
+        self.thp_count = 0;
 
-        if let Some(e) = self.uksm_pages.get_mut(&addr) {
-            if !entry.is_ksm || e.crc != entry.crc {
-                uksm.remove(self.pid, addr, e.crc);
+        let old_ranges: Vec<_> = self.maps.iter().map(|m| m.range.clone()).collect();
+        let should_remove_maps = find_non_overlapping_ranges(&old_ranges, maps);
 
-                e.crc = entry.crc;
-                if let Some(value) = self.uksm_pages.remove(&addr) {
-                    self.new_pages.insert(addr, value);
+        self.remove_maps(uksm, should_remove_maps);
+
+        let mut new_maps: Vec<MapPages> = maps.iter().cloned().map(MapPages::new).collect();
+        new_maps.sort_by_key(|m| m.range.start);
+        for old in self.maps.iter() {
+            for (i, rec) in old.iter() {
+                let addr = old.addr(i);
+                let j = new_maps.partition_point(|m| m.range.end <= addr);
+                if j < new_maps.len() && new_maps[j].range.start <= addr {
+                    let index = new_maps[j].index(addr);
+                    new_maps[j].set(index, *rec);
                 }
             }
+        }
+        self.maps = new_maps;
+    }
 
-            return;
+    // Apply the entries read from start.
+    pub fn refresh_pages(
+        &mut self,
+        uksm: &mut uksm::Uksm,
+        start: u64,
+        entries: &[Option<uksm::UKSMPagemapEntry>],
+        stable_cycles: u8,
+    ) {
+        let _timer = timing::start(timing::Op::RefreshPages);
+        let mut addr = start;
+        let mut i = 0;
+        while i < entries.len() {
+            if let Some(entry) = entries[i] {
+                self.update(uksm, addr, entry, stable_cycles);
+                addr += *PAGE_SIZE;
+                i += 1;
+            } else {
+                // Remove the hole at once.
+                let count = entries[i..].iter().take_while(|e| e.is_none()).count();
+                let end = addr + count as u64 * *PAGE_SIZE;
+                self.remove_range(uksm, addr, end, false);
+                addr = end;
+                i += count;
+            }
+        }
+    }
+
+    // Check the merged pages with the entries read from start, the pages
+    // that the kernel broke are handled as refresh does.
+    // Return the number of the broken pages.
+    pub fn verify(
+        &mut self,
+        uksm: &mut uksm::Uksm,
+        start: u64,
+        entries: &[Option<uksm::UKSMPagemapEntry>],
+    ) -> u64 {
+        let mut broken = 0;
+        let mut addr = start;
+        for e in entries {
+            if let Some(rec) = self.get(addr) {
+                if rec.state == PageState::Uksm {
+                    match e {
+                        Some(entry) if entry.is_ksm && entry.crc == rec.crc => {
+                            uksm.set_pfn(self.pid, addr, entry.pfn, true);
+                        }
+                        Some(entry)
+                            if entry.crc == rec.crc
+                                && uksm.frame_shared(self.pid, addr, entry.pfn) => {}
+                        Some(entry) => {
+                            // The page is not New, stable_cycles is not used.
+                            self.update(uksm, addr, *entry, 0);
+                            broken += 1;
+                        }
+                        None => {
+                            self.remove(uksm, addr);
+                            broken += 1;
+                        }
+                    }
+                }
+            }
+            addr += *PAGE_SIZE;
         }
 
-        self.new_pages.insert(addr, PageEntry { crc: entry.crc });
+        broken
     }
 
-    pub fn refresh(&mut self, uksm: &mut uksm::Uksm, task: task::TaskInfo) -> Result<()> {
-        let maps = proc::parse_task_smaps(&task)
-            .map_err(|e| anyhow!("proc::parse_task_smaps failed: {}", e))?;
+    // The kernel unmerged all the pages, make the merged pages New to merge
+    // them again. Return the number of them.
+    pub fn reset_merged(&mut self) -> u64 {
+        let addrs = self.uksm_addrs();
+        for addr in addrs.iter() {
+            if let Some(rec) = self.get(*addr) {
+                let crc = rec.crc;
+                self.set_state(*addr, PageState::New, crc);
+            }
+        }
 
-        let should_remove_maps = find_non_overlapping_ranges(&self.maps, &maps);
+        addrs.len() as u64
+    }
 
-        self.remove_maps(uksm, should_remove_maps);
+    // Drop the maps that don't have any page.
+    pub fn drop_empty_maps(&mut self) {
+        self.maps.retain(|m| !m.is_empty());
+    }
 
-        let mut new_maps = Vec::new();
-        for r in maps {
-            let entries = uksm::read_uksm_pagemap(task.pid, r.start, r.end).map_err(|e| {
-                anyhow!("uksm::read_uksm_pagemap {} {:?} failed: {}", task.pid, r, e)
-            })?;
-
-            let mut addr = r.start;
-            let mut current_map_is_empty = true;
-            for e in entries {
-                if let Some(entry) = e {
-                    current_map_is_empty = false;
-                    self.update(uksm, addr, entry);
-                } else {
-                    self.remove(uksm, addr);
+    pub fn uksm_addrs(&self) -> Vec<u64> {
+        self.addrs(PageState::Uksm)
+    }
+
+    // At most limit Old pages from the merge cursor, 0 means no limit.
+    // The maps are sorted, so the addrs are ascending.
+    pub fn merge_chunk_addrs(&self, limit: usize) -> Vec<u64> {
+        let cursor = self.merge_cursor.unwrap_or(0);
+        let mut addrs = Vec::new();
+        let first = self.maps.partition_point(|m| m.range.end <= cursor);
+        for m in self.maps[first..].iter() {
+            let skip = (cursor.saturating_sub(m.range.start) / *PAGE_SIZE) as usize;
+            for (i, rec) in m.range(skip, m.count) {
+                if rec.state == PageState::Old {
+                    if limit != 0 && addrs.len() >= limit {
+                        return addrs;
+                    }
+                    addrs.push(m.addr(i));
                 }
-                addr += *PAGE_SIZE;
             }
+        }
+        addrs
+    }
 
-            if !current_map_is_empty {
-                new_maps.push(r);
+    // Move the merge cursor after the chunk of addrs that is merged.
+    // last is the last addr of the chunk if there might be more pages
+    // after it, or None if the merge of the task is done.
+    // old is the number of the Old pages before the chunk is merged, the
+    // merged pages are not Old any more.
+    pub fn advance_merge_cursor(&mut self, last: Option<u64>, merged: u64, old: u64) {
+        if self.merge_cursor.is_none() {
+            self.merge_remaining = old;
+        }
+        match last {
+            Some(addr) => {
+                self.merge_cursor = Some(addr + *PAGE_SIZE);
+                self.merge_remaining = self.merge_remaining.saturating_sub(merged);
+            }
+            None => {
+                self.merge_cursor = None;
+                self.merge_remaining = 0;
             }
         }
+    }
 
-        self.maps = new_maps;
+    // The Old pages that are left to the next chunks of the merge.
+    pub fn merge_remaining(&self) -> Option<u64> {
+        self.merge_cursor.map(|_| self.merge_remaining)
+    }
+
+    // The pages that are not merged with any other page are kept Old and
+    // they become Uksm when a later page is merged with them.
+    // The pages of a crc that has less than min_dup_count pages are kept
+    // Old too, so are the zero pages if merge_zero_pages is not set.
+    pub fn merge(&mut self, uksm: &mut uksm::Uksm, addrs: &[u64]) -> Result<()> {
+        let _timer = timing::start(timing::Op::Merge);
+        let mut pages: Vec<(u64, u32)> = addrs
+            .iter()
+            .filter_map(|addr| match self.get(*addr) {
+                Some(rec) if rec.state == PageState::Old => Some((*addr, rec.crc)),
+                _ => None,
+            })
+            .collect();
+        if !self.crcs.merge_zero_pages {
+            pages.retain(|(_, crc)| !self.crcs.is_zero(*crc));
+        }
+        let min_dup_count = self.crcs.min_dup_count();
+        let count = pages.len();
+        pages.retain(|(_, crc)| self.crcs.get(*crc) >= min_dup_count);
+        self.crcs
+            .skipped
+            .fetch_add((count - pages.len()) as u64, Ordering::Relaxed);
+        // The pages whose crc has the other pages first.
+        pages.sort_by_key(|(_, crc)| self.crcs.get(*crc) < 2);
+
+        let mut gone = Vec::new();
+        if self.thp == ThpPolicy::Split {
+            for (addr, _) in pages.iter() {
+                if !self.get(*addr).is_some_and(|rec| rec.thp) {
+                    continue;
+                }
+                match uksm.split_thp(self.pid, *addr) {
+                    Ok(()) => {}
+                    Err(e) if uksm::is_page_gone(&e) => gone.push(*addr),
+                    Err(e) if uksm::is_task_gone(&e).is_some() => return Err(e),
+                    Err(e) => warn!("split thp of task {} failed: {}", self.pid, e),
+                }
+            }
+            pages.retain(|(addr, _)| !gone.contains(addr));
+        }
+
+        let pages: Vec<(u64, PageEntry)> = pages
+            .into_iter()
+            .map(|(addr, crc)| {
+                let entry = match (self.ksm_pfns.get(&addr), self.cow_pfns.get(&addr)) {
+                    (Some(pfn), _) => PageEntry {
+                        crc,
+                        pfn: *pfn,
+                        cow: false,
+                    },
+                    (None, Some(pfn)) => PageEntry {
+                        crc,
+                        pfn: *pfn,
+                        cow: true,
+                    },
+                    (None, None) => PageEntry {
+                        crc,
+                        pfn: 0,
+                        cow: false,
+                    },
+                };
+                (addr, entry)
+            })
+            .collect();
+        let ret = uksm.add_batch(self.pid, &pages, &mut self.probes);
+
+        // Part of the pages might be added even if add_batch failed.
+        for (addr, entry) in pages {
+            if uksm.is_merged(self.pid, addr) {
+                self.set_state(addr, PageState::Uksm, entry.crc);
+            }
+        }
+
+        for addr in gone.into_iter().chain(ret?) {
+            self.remove(uksm, addr);
+        }
 
         Ok(())
     }
 
-    pub fn merge(&mut self, uksm: &mut uksm::Uksm) -> Result<()> {
-        let addrs: Vec<_> = self.old_pages.keys().cloned().collect();
+    // The Old page addr of crc is not the same as a page of crc that it is
+    // compared with. Park it as Stale after max failures in a row, it is
+    // removed from uksm so the later pages of crc are not compared with it.
+    // Return true if the page is parked.
+    pub fn merge_failed(&mut self, uksm: &mut uksm::Uksm, addr: u64, crc: u32, max: u8) -> bool {
+        let fails = match self.get_mut(addr) {
+            Some(rec) if rec.state == PageState::Old && rec.crc == crc => {
+                rec.fails = rec.fails.saturating_add(1);
+                rec.fails
+            }
+            _ => return false,
+        };
+        if fails < max || uksm.is_merged(self.pid, addr) {
+            return false;
+        }
+
+        if uksm.contains(self.pid, addr) {
+            uksm.remove(self.pid, addr);
+        }
+        self.set_state(addr, PageState::Stale, crc);
+
+        true
+    }
+
+    // The kernel rejected to merge the Old page addr, the other pages of its
+    // map are rejected too, for example they are shmem pages. Park the Old
+    // pages of the map that are not merged as Stale, they are tried again
+    // when they are changed. Return the number of the parked pages.
+    pub fn merge_rejected(&mut self, uksm: &mut uksm::Uksm, addr: u64) -> u64 {
+        let range = match self.map_index(addr) {
+            Some(i) => self.maps[i].range.clone(),
+            None => return 0,
+        };
+        if self.rejected_maps.insert(range.start) {
+            warn!(
+                "kernel rejects to merge the pages of map 0x{:x}-0x{:x} of task {}, skip them",
+                range.start, range.end, self.pid
+            );
+        }
 
+        let addrs = self.range_addrs(range.start, range.end, |rec| rec.state == PageState::Old);
+        let mut parked = 0;
         for addr in addrs {
-            if let Some(entry) = self.old_pages.get(&addr) {
-                uksm.add(self.pid, addr, entry)?;
+            if uksm.is_merged(self.pid, addr) {
+                continue;
             }
-
-            if let Some(entry) = self.old_pages.remove(&addr) {
-                self.uksm_pages.insert(addr, entry);
+            if uksm.contains(self.pid, addr) {
+                uksm.remove(self.pid, addr);
+            }
+            if let Some(rec) = self.get(addr) {
+                let crc = rec.crc;
+                self.set_state(addr, PageState::Stale, crc);
+                parked += 1;
             }
         }
 
-        Ok(())
+        parked
     }
 
-    pub fn unmerge(&mut self, uksm: &mut uksm::Uksm) -> Result<()> {
-        let addrs: Vec<_> = self.uksm_pages.keys().cloned().collect();
+    // Merge the Old pages in [start, end) as merge does, the pages out of it
+    // are not touched. Return the number of the Old pages in it.
+    pub fn merge_range(&mut self, uksm: &mut uksm::Uksm, start: u64, end: u64) -> Result<usize> {
+        let addrs = self.range_addrs(start, end, |rec| rec.state == PageState::Old);
+        self.merge(uksm, &addrs)?;
 
+        Ok(addrs.len())
+    }
+
+    // Unmerge the merged pages in [start, end) and return the number of the
+    // unmerged pages.
+    pub fn unmerge_range(&mut self, uksm: &mut uksm::Uksm, start: u64, end: u64) -> Result<usize> {
+        let addrs = self.range_addrs(start, end, |rec| rec.state == PageState::Uksm);
+        self.unmerge(uksm, &addrs, None)
+    }
+
+    // Unmerge at most limit pages of addrs and return the number of the
+    // unmerged pages.
+    pub fn unmerge(
+        &mut self,
+        uksm: &mut uksm::Uksm,
+        addrs: &[u64],
+        limit: Option<usize>,
+    ) -> Result<usize> {
+        let mut count = 0;
         for addr in addrs {
-            if let Some(entry) = self.uksm_pages.get(&addr) {
-                uksm.unmerge(self.pid, addr, entry)?;
+            if limit.is_some_and(|limit| count >= limit) {
+                break;
             }
+            let rec = match self.get(*addr) {
+                Some(rec) if rec.state == PageState::Uksm => *rec,
+                _ => continue,
+            };
 
-            if let Some(entry) = self.uksm_pages.remove(&addr) {
-                self.old_pages.insert(addr, entry);
+            match uksm.unmerge(self.pid, *addr) {
+                Ok(()) => {
+                    self.set_state(*addr, PageState::Old, rec.crc);
+                    count += 1;
+                }
+                Err(e) if uksm::is_page_gone(&e) => self.remove(uksm, *addr),
+                Err(e) => return Err(e),
             }
         }
 
-        Ok(())
+        Ok(count)
+    }
+
+    // Append a sample of the merge savings, drop the oldest samples that
+    // exceed capacity.
+    pub fn record_history(&mut self, saved_bytes: u64, capacity: usize) {
+        let time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.history.push_back(HistorySample {
+            time,
+            uksm_count: self.counts[PageState::Uksm as usize],
+            saved_bytes,
+        });
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+
+    // The approximate bytes of the records of the pages of the task.
+    pub fn mem_bytes(&self) -> u64 {
+        let recs: usize = self.maps.iter().map(|m| m.mem_bytes()).sum();
+        (recs
+            + self.maps.capacity() * std::mem::size_of::<MapPages>()
+            + (self.ksm_pfns.len() + self.cow_pfns.len()) * 2 * std::mem::size_of::<u64>())
+            as u64
+    }
+
+    pub fn history(&self) -> Vec<HistorySample> {
+        self.history.iter().copied().collect()
     }
 
     pub fn get_status(&self) -> InfoStatus {
         InfoStatus {
-            new_count: self.new_pages.len() as u64,
-            old_count: self.old_pages.len() as u64,
-            uksm_count: self.uksm_pages.len() as u64,
+            new_count: self.counts[PageState::New as usize],
+            old_count: self.counts[PageState::Old as usize],
+            uksm_count: self.counts[PageState::Uksm as usize],
+            zero_count: self.zero_count,
+            thp_count: self.thp_count,
+            stale_count: self.counts[PageState::Stale as usize],
+            foreign_count: self.counts[PageState::Foreign as usize],
+            probes: self.probes,
         }
     }
 }
 
-fn find_non_overlapping_ranges(
-    a: &Vec<proc::MapRange>,
-    b: &Vec<proc::MapRange>,
-) -> Vec<proc::MapRange> {
+// The maps and pages of a task read from proc.
+// Reading doesn't need Uksm, so the tasks can be read concurrently.
+pub struct RefreshData {
+    pub maps: Vec<proc::MapRange>,
+    // the runs of the pages to refresh, the pages that are not in them are
+    // not changed since the last refresh
+    pub runs: Vec<(u64, u64, PageChange)>,
+    // the soft-dirty bits of the task are cleared
+    pub cleared: bool,
+    // the exec id of the task before its maps are got
+    pub exec_id: Option<(u64, u64)>,
+}
+
+// How read_refresh_data reads the pages.
+#[derive(Debug, Clone, Default)]
+pub struct RefreshOpts {
+    pub skip_vm_flags: Arc<Vec<String>>,
+    pub vma_filter: Arc<proc::VmaFilter>,
+    pub min_vma_size: u64,
+    pub include_shmem: bool,
+    // get the maps from maps instead of smaps
+    pub fast_maps: bool,
+    // keep the frames of the pages that might be shared with a forked task
+    pub track_cow: bool,
+    pub idle_filter: bool,
+    // clear the soft-dirty bits to find the changed pages in the next refresh
+    pub soft_dirty: bool,
+    // only read the pages that are soft-dirty, the bits must be cleared by
+    // the last refresh
+    pub incremental: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageChange {
+    Clean,
+    Absent,
+    Dirty,
+}
+
+// Split the pages from start to the runs of the same change and add them
+// to the runs of a map, a run that has the same change as the last one of
+// runs is joined to it.
+// All the present pages are Dirty if not incremental.
+fn add_change_runs(
+    runs: &mut Vec<(u64, u64, PageChange)>,
+    start: u64,
+    flags: &[proc::PagemapFlags],
+    incremental: bool,
+) {
+    let mut addr = start;
+    for f in flags {
+        let change = if !f.present {
+            PageChange::Absent
+        } else if incremental && !f.soft_dirty {
+            PageChange::Clean
+        } else {
+            PageChange::Dirty
+        };
+        match runs.last_mut() {
+            Some(run) if run.2 == change => run.1 += *PAGE_SIZE,
+            _ => runs.push((addr, addr + *PAGE_SIZE, change)),
+        }
+        addr += *PAGE_SIZE;
+    }
+}
+
+// Get if the pages of entries are idle, then mark them idle for the next
+// refresh.
+// The idle of the entries is kept None if it cannot be got.
+fn read_idle(pid: u64, entries: &mut [Option<uksm::UKSMPagemapEntry>]) {
+    let pfns: Vec<u64> = entries.iter().flatten().map(|e| e.pfn).collect();
+
+    match proc::page_idle::is_idle(&pfns) {
+        Ok(idle) => {
+            for (entry, idle) in entries.iter_mut().flatten().zip(idle) {
+                entry.idle = Some(idle);
+            }
+        }
+        Err(e) => {
+            debug!("proc::page_idle::is_idle {} failed: {}", pid, e);
+            return;
+        }
+    }
+
+    if let Err(e) = proc::page_idle::set_idle(&pfns) {
+        debug!("proc::page_idle::set_idle {} failed: {}", pid, e);
+    }
+}
+
+// Read the entries of the Dirty run [start, end) chunk by chunk and call f
+// with each chunk.
+pub fn read_run(
+    task: &task::TaskInfo,
+    backend: &dyn backend::KernelOps,
+    limit: &ratelimit::TokenBucket,
+    opts: &RefreshOpts,
+    start: u64,
+    end: u64,
+    f: &mut backend::PagemapChunkFn,
+) -> Result<()> {
+    let _timer = timing::start(timing::Op::ReadPagemap);
+    backend
+        .read_pagemap_chunked(task.pid, start, end, &mut |addr, entries| {
+            limit.acquire(entries.len() as u64);
+            if opts.idle_filter {
+                read_idle(task.pid, entries);
+            }
+            f(addr, entries)
+        })
+        .map_err(|e| {
+            if uksm::is_task_gone(&e).is_some() {
+                return e;
+            }
+            anyhow!(
+                "backend.read_pagemap_chunked {} 0x{:x}-0x{:x} failed: {}",
+                task.pid,
+                start,
+                end,
+                e
+            )
+        })
+}
+
+// Get the maps of task and the runs of the pages to refresh.
+pub fn read_refresh_data(task: &task::TaskInfo, opts: &RefreshOpts) -> Result<RefreshData> {
+    let exec_id = proc::pid_exec_id(task.pid).ok();
+    let maps = if opts.fast_maps {
+        proc::parse_task_maps(task, &opts.vma_filter, opts.min_vma_size)
+            .map_err(|e| anyhow!("proc::parse_task_maps failed: {}", e))?
+    } else {
+        proc::parse_task_smaps(
+            task,
+            &opts.skip_vm_flags,
+            &opts.vma_filter,
+            opts.min_vma_size,
+            opts.include_shmem,
+        )
+        .map_err(|e| anyhow!("proc::parse_task_smaps failed: {}", e))?
+    };
+
+    // Read the present bits to skip the holes without reading them from
+    // the backend, and the soft-dirty bits before clearing them.
+    // All the pages are Dirty if the bits cannot be read.
+    let mut runs = Vec::new();
+    for r in maps.iter() {
+        let mut map_runs = Vec::new();
+        if let Err(e) = proc::read_pagemap_flags(task.pid, r.start, r.end, &mut |addr, flags| {
+            add_change_runs(&mut map_runs, addr, flags, opts.incremental);
+            Ok(())
+        }) {
+            debug!("proc::read_pagemap_flags {} failed: {}", task.pid, e);
+            runs = maps
+                .iter()
+                .map(|r| (r.start, r.end, PageChange::Dirty))
+                .collect();
+            break;
+        }
+        runs.extend(
+            map_runs
+                .into_iter()
+                .filter(|run| run.2 != PageChange::Clean),
+        );
+    }
+
+    // Clear the bits before reading the pages, so the pages that are
+    // changed after the read are found by the next refresh.
+    let cleared = opts.soft_dirty
+        && match proc::clear_soft_dirty(task.pid) {
+            Ok(()) => true,
+            Err(e) => {
+                debug!("proc::clear_soft_dirty {} failed: {}", task.pid, e);
+                false
+            }
+        };
+
+    Ok(RefreshData {
+        maps,
+        runs,
+        cleared,
+        exec_id,
+    })
+}
+
+fn find_non_overlapping_ranges(a: &[proc::MapRange], b: &[proc::MapRange]) -> Vec<proc::MapRange> {
     let mut c: Vec<proc::MapRange> = Vec::new();
 
     for range_a in a.iter() {
@@ -220,3 +1509,480 @@ fn find_non_overlapping_ranges(
 
     c
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{fake::FakeKernel, KernelOps};
+    use structopt::StructOpt;
+
+    // The xorshift64 generator, the tests are the same in each run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    const STATES: [PageState; 6] = [
+        PageState::None,
+        PageState::New,
+        PageState::Old,
+        PageState::Uksm,
+        PageState::Stale,
+        PageState::Foreign,
+    ];
+
+    // The crc and age of a None page are not used.
+    fn key(rec: &PageRec) -> (u32, PageState, u8) {
+        match rec.state {
+            PageState::None => (0, PageState::None, 0),
+            _ => (rec.crc, rec.state, rec.age),
+        }
+    }
+
+    fn flags(bits: &str) -> Vec<proc::PagemapFlags> {
+        bits.chars()
+            .map(|c| proc::PagemapFlags {
+                present: c != '.',
+                soft_dirty: c == 'd',
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_add_change_runs() {
+        let size = *PAGE_SIZE;
+        let mut runs = Vec::new();
+        add_change_runs(&mut runs, 0, &flags("dd.c"), true);
+        // The next chunk joins the last run.
+        add_change_runs(&mut runs, 4 * size, &flags("cd"), true);
+        assert_eq!(
+            runs,
+            vec![
+                (0, 2 * size, PageChange::Dirty),
+                (2 * size, 3 * size, PageChange::Absent),
+                (3 * size, 5 * size, PageChange::Clean),
+                (5 * size, 6 * size, PageChange::Dirty),
+            ]
+        );
+
+        let mut runs = Vec::new();
+        add_change_runs(&mut runs, 0, &flags("dc."), false);
+        assert_eq!(
+            runs,
+            vec![
+                (0, 2 * size, PageChange::Dirty),
+                (2 * size, 3 * size, PageChange::Absent),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_map_pages_against_hashmap() {
+        let count = 3 * CHUNK_PAGES + 17;
+        let mut m = MapPages::new(proc::MapRange {
+            start: 0x1000_0000,
+            end: 0x1000_0000 + count as u64 * *PAGE_SIZE,
+        });
+        let mut want: HashMap<usize, PageRec> = HashMap::new();
+        let mut rng = Rng(0x2545f4914f6cdd1d);
+
+        for _ in 0..5000 {
+            // Most of the pages are in the first and the last chunks.
+            let index = match rng.below(3) {
+                0 => rng.below(count as u64) as usize,
+                1 => rng.below(64) as usize,
+                _ => count - 1 - rng.below(64) as usize,
+            };
+            match rng.below(3) {
+                0 | 1 => {
+                    let rec = PageRec {
+                        crc: rng.below(8) as u32,
+                        state: STATES[rng.below(STATES.len() as u64) as usize],
+                        ..PageRec::NONE
+                    };
+                    m.set(index, rec);
+                    if rec.state == PageState::None {
+                        want.remove(&index);
+                    } else {
+                        want.insert(index, rec);
+                    }
+                }
+                _ => {
+                    let age = rng.below(4) as u8;
+                    match (m.get_mut(index), want.get_mut(&index)) {
+                        (Some(rec), Some(w)) => {
+                            rec.age = age;
+                            w.age = age;
+                        }
+                        (Some(rec), None) => assert_eq!(rec.state, PageState::None),
+                        (None, w) => assert!(w.is_none()),
+                    }
+                }
+            }
+            let rec = m.get(index).unwrap();
+            assert_eq!(key(rec), key(want.get(&index).unwrap_or(&PageRec::NONE)));
+
+            let first = rng.below(count as u64 + 1) as usize;
+            let last = first + rng.below((count - first) as u64 + 1) as usize;
+            let got: Vec<(usize, (u32, PageState, u8))> =
+                m.range(first, last).map(|(i, rec)| (i, key(rec))).collect();
+            let mut w: Vec<(usize, (u32, PageState, u8))> = want
+                .iter()
+                .filter(|(i, _)| (first..last).contains(*i))
+                .map(|(i, rec)| (*i, key(rec)))
+                .collect();
+            w.sort_unstable_by_key(|(i, _)| *i);
+            assert_eq!(got, w);
+        }
+
+        assert_eq!(m.iter().count(), want.len());
+        assert!(m.get(count).is_none());
+        // Only the chunks that have pages are allocated.
+        let chunks: HashSet<usize> = want.keys().map(|i| i / CHUNK_PAGES).collect();
+        assert_eq!(m.chunks.iter().flatten().count(), chunks.len());
+        for index in want.keys().copied().collect::<Vec<_>>() {
+            m.set(index, PageRec::NONE);
+        }
+        assert!(m.is_empty());
+    }
+
+    // Check the counts and the addrs of the states of info with the ones
+    // that are counted page by page.
+    fn check_info(info: &Info, uksm: &uksm::Uksm) {
+        let mut counts = [0u64; 6];
+        let mut addrs: Vec<Vec<u64>> = vec![Vec::new(); 6];
+        for m in info.maps.iter() {
+            for i in 0..m.count {
+                let addr = m.addr(i);
+                let rec = info.get(addr).unwrap();
+                if rec.state != PageState::None {
+                    counts[rec.state as usize] += 1;
+                    addrs[rec.state as usize].push(addr);
+                }
+                // The pages in uksm are Old or Uksm, a page that another
+                // page joined is Uksm after the next merge of its task, and
+                // a Uksm page whose group lost the others is left alone.
+                if uksm.contains(info.pid, addr) {
+                    assert!(matches!(rec.state, PageState::Old | PageState::Uksm));
+                } else {
+                    assert_ne!(rec.state, PageState::Uksm);
+                }
+            }
+        }
+        let status = info.get_status();
+        assert_eq!(
+            (
+                status.new_count,
+                status.old_count,
+                status.uksm_count,
+                status.stale_count,
+                status.foreign_count
+            ),
+            (
+                counts[PageState::New as usize],
+                counts[PageState::Old as usize],
+                counts[PageState::Uksm as usize],
+                counts[PageState::Stale as usize],
+                counts[PageState::Foreign as usize]
+            )
+        );
+        for state in STATES[1..].iter() {
+            assert_eq!(info.addrs(*state), addrs[*state as usize], "{:?}", state);
+        }
+    }
+
+    #[test]
+    fn test_info_against_pages() {
+        let config = config::Config::from_iter(["uksmd"]);
+        let kernel = Arc::new(FakeKernel::new());
+        let mut uksm = uksm::Uksm::new(&config, kernel.clone());
+        let budget = Arc::new(PageBudget::new(1500));
+        let crcs = Arc::new(CrcCounts::new(&config, None));
+        let (pid1, pid2) = ((1 << 23) + 100, (1 << 23) + 101);
+        let mut infos = [
+            Info::new(pid1, 0, budget.clone(), crcs.clone()),
+            Info::new(pid2, 0, budget.clone(), crcs.clone()),
+        ];
+        kernel.add_task(pid1);
+        kernel.add_task(pid2);
+
+        // The maps that the tasks get, some of them overlap.
+        let start = 0x1000_0000;
+        let size = *PAGE_SIZE;
+        let ranges = [
+            (start, start + 700 * size),
+            (start + 900 * size, start + 1000 * size),
+            (start + 600 * size, start + 800 * size),
+        ];
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        let mut layouts = [Vec::new(), Vec::new()];
+        for _ in 0..60 {
+            let i = rng.below(2) as usize;
+            let info = &mut infos[i];
+            let pid = info.pid;
+            if rng.below(3) == 0 {
+                let mut maps: Vec<proc::MapRange> = ranges
+                    .iter()
+                    .filter(|_| rng.below(3) != 0)
+                    .map(|(start, end)| proc::MapRange {
+                        start: *start,
+                        end: *end,
+                    })
+                    .collect();
+                maps.sort_by_key(|m| m.start);
+                maps.dedup_by(|b, a| {
+                    let overlap = b.start < a.end;
+                    if overlap {
+                        a.end = a.end.max(b.end);
+                    }
+                    overlap
+                });
+                layouts[i] = maps;
+            }
+            if rng.below(3) == 0 {
+                let addrs = info.merge_chunk_addrs(0);
+                info.merge(&mut uksm, &addrs).unwrap();
+            } else {
+                for _ in 0..200 {
+                    let addr = start + rng.below(1000) * size;
+                    match rng.below(5) {
+                        0 => kernel.drop_page(pid, addr),
+                        _ => kernel.write(pid, addr, rng.below(6)),
+                    }
+                }
+                info.refresh_remove_maps(&mut uksm, &layouts[i]);
+                for m in layouts[i].iter() {
+                    let entries = kernel.read_pagemap(pid, m.start, m.end).unwrap();
+                    info.refresh_pages(&mut uksm, m.start, &entries, 1);
+                }
+                info.drop_empty_maps();
+            }
+            check_info(&infos[0], &uksm);
+            check_info(&infos[1], &uksm);
+            assert_eq!(infos[0].page_count() + infos[1].page_count(), budget.used());
+        }
+
+        for info in infos.iter_mut() {
+            info.clear(&mut uksm);
+            check_info(info, &uksm);
+        }
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[test]
+    fn test_age_with_scripted_crcs() {
+        let config = config::Config::from_iter(["uksmd"]);
+        let kernel = Arc::new(FakeKernel::new());
+        let mut uksm = uksm::Uksm::new(&config, kernel.clone());
+        let crcs = Arc::new(CrcCounts::new(&config, None));
+        let pid = (1 << 23) + 100;
+        let mut info = Info::new(pid, 0, Arc::new(PageBudget::new(100)), crcs);
+        let start = 0x1000_0000;
+        let map = proc::MapRange {
+            start,
+            end: start + 2 * *PAGE_SIZE,
+        };
+        info.refresh_remove_maps(&mut uksm, &[map]);
+        let entry = |crc| {
+            Some(uksm::UKSMPagemapEntry {
+                pfn: 0,
+                crc,
+                is_thp: false,
+                is_ksm: false,
+                idle: None,
+            })
+        };
+
+        // The crc of each refresh and the state and the age after it with
+        // stable_cycles 3.
+        let script = [
+            (1, PageState::New, 0),
+            (1, PageState::New, 1),
+            (1, PageState::New, 2),
+            // The crc is changed, age it again.
+            (2, PageState::New, 0),
+            (2, PageState::New, 1),
+            (2, PageState::New, 2),
+            (2, PageState::Old, 0),
+            (2, PageState::Old, 0),
+            // An Old page that is changed is New again.
+            (3, PageState::New, 0),
+            (3, PageState::New, 1),
+        ];
+        for (i, (crc, state, age)) in script.iter().enumerate() {
+            info.refresh_pages(&mut uksm, start, &[entry(*crc)], 3);
+            let rec = info.get(start).unwrap();
+            assert_eq!((rec.crc, rec.state, rec.age), (*crc, *state, *age), "{}", i);
+        }
+
+        // A New page is Old after the first refresh that its crc is
+        // unchanged with the default stable_cycles.
+        let addr = start + *PAGE_SIZE;
+        info.refresh_pages(&mut uksm, addr, &[entry(4)], config.stable_cycles);
+        assert_eq!(info.get(addr).unwrap().state, PageState::New);
+        info.refresh_pages(&mut uksm, addr, &[entry(4)], config.stable_cycles);
+        assert_eq!(info.get(addr).unwrap().state, PageState::Old);
+    }
+
+    #[test]
+    fn test_age_is_reset_when_ksm_is_lost() {
+        let config = config::Config::from_iter(["uksmd"]);
+        let kernel = Arc::new(FakeKernel::new());
+        let mut uksm = uksm::Uksm::new(&config, kernel.clone());
+        let crcs = Arc::new(CrcCounts::new(&config, None));
+        let pid = (1 << 23) + 100;
+        let mut info = Info::new(pid, 0, Arc::new(PageBudget::new(100)), crcs);
+        let start = 0x1000_0000;
+        let map = proc::MapRange {
+            start,
+            end: start + 2 * *PAGE_SIZE,
+        };
+        info.refresh_remove_maps(&mut uksm, &[map]);
+        kernel.add_task(pid);
+        kernel.write_pages(pid, start, &[5, 5]);
+        let read = || kernel.read_pagemap(pid, start, start + 2 * *PAGE_SIZE);
+
+        // Merge the pages of the same crc.
+        for _ in 0..3 {
+            info.refresh_pages(&mut uksm, start, &read().unwrap(), 2);
+        }
+        for _ in 0..2 {
+            let addrs = info.merge_chunk_addrs(0);
+            info.merge(&mut uksm, &addrs).unwrap();
+        }
+        info.refresh_pages(&mut uksm, start, &read().unwrap(), 2);
+        // The page that joined the other one.
+        let addr = start + *PAGE_SIZE;
+        assert_eq!(info.get(addr).unwrap().state, PageState::Uksm);
+        assert!(kernel.is_ksm(pid, addr));
+
+        // The kernel unmerged the page without changing its crc, it is New
+        // and is aged from 0 again.
+        let mut entries = read().unwrap();
+        for entry in entries.iter_mut().flatten() {
+            entry.is_ksm = false;
+        }
+        info.refresh_pages(&mut uksm, start, &entries, 2);
+        let rec = info.get(addr).unwrap();
+        assert_eq!((rec.crc, rec.state, rec.age), (5, PageState::New, 0));
+        assert!(!uksm.contains(pid, addr));
+        info.refresh_pages(&mut uksm, start, &entries, 2);
+        assert_eq!(info.get(addr).unwrap().age, 1);
+        info.refresh_pages(&mut uksm, start, &entries, 2);
+        assert_eq!(info.get(addr).unwrap().state, PageState::Old);
+    }
+
+    // The vmas of a random layout of 64 pages, some of them are adjacent.
+    fn random_vmas(rng: &mut Rng, start: u64) -> Vec<(u64, u64)> {
+        let mut vmas = Vec::new();
+        let mut page = rng.below(3);
+        while page < 64 {
+            let count = (1 + rng.below(4)).min(64 - page);
+            vmas.push((start + page * *PAGE_SIZE, count));
+            page += count + [0, 0, 1, 2][rng.below(4) as usize];
+        }
+        vmas
+    }
+
+    fn pages_of(ranges: &[MapRange]) -> HashSet<u64> {
+        ranges
+            .iter()
+            .flat_map(|r| (r.start..r.end).step_by(*PAGE_SIZE as usize))
+            .collect()
+    }
+
+    // Parse the smaps of a fake task that has vmas.
+    fn parse_vmas(vmas: &[(u64, u64)]) -> Vec<MapRange> {
+        let pid = proc::fake::new_pid();
+        let mut task = proc::fake::FakeTask::new(pid);
+        for (start, count) in vmas {
+            task = task.map(*start, *count);
+        }
+        task.write();
+        let task = task::TaskInfo::new(pid, None, 0, None);
+        let maps =
+            proc::parse_task_smaps(&task, &[], &proc::VmaFilter::default(), 0, false).unwrap();
+        proc::fake::FakeTask::exit(pid);
+        maps
+    }
+
+    #[test]
+    fn test_coalesced_maps_against_pages() {
+        let config = config::Config::from_iter(["uksmd"]);
+        let kernel = Arc::new(FakeKernel::new());
+        let mut uksm = uksm::Uksm::new(&config, kernel);
+        let crcs = Arc::new(CrcCounts::new(&config, None));
+        let budget = Arc::new(PageBudget::new(1000));
+        let start = 0x1000_0000;
+        let mut rng = Rng(0x853c49e6748fea9b);
+
+        for _ in 0..50 {
+            let (vmas_a, vmas_b) = (random_vmas(&mut rng, start), random_vmas(&mut rng, start));
+            let (a, b) = (parse_vmas(&vmas_a), parse_vmas(&vmas_b));
+            for (vmas, maps) in [(&vmas_a, &a), (&vmas_b, &b)] {
+                // The maps are sorted and no two of them are adjacent.
+                assert!(maps.windows(2).all(|w| w[0].end < w[1].start), "{:?}", maps);
+                let ranges: Vec<MapRange> = vmas
+                    .iter()
+                    .map(|(start, count)| MapRange {
+                        start: *start,
+                        end: start + count * *PAGE_SIZE,
+                    })
+                    .collect();
+                assert_eq!(pages_of(maps), pages_of(&ranges));
+            }
+
+            // The holes of a that b doesn't have are the pages of a that
+            // are not in b.
+            let holes = find_non_overlapping_ranges(&a, &b);
+            let want: HashSet<u64> = pages_of(&a).difference(&pages_of(&b)).copied().collect();
+            assert_eq!(pages_of(&holes), want);
+            assert_eq!(
+                holes
+                    .iter()
+                    .map(|r| (r.end - r.start) / *PAGE_SIZE)
+                    .sum::<u64>(),
+                want.len() as u64
+            );
+
+            // All the pages of a are tracked, only the ones that are in b
+            // too are kept after the maps are changed to b.
+            let mut info = Info::new(1, 0, budget.clone(), crcs.clone());
+            info.refresh_remove_maps(&mut uksm, &a);
+            for m in a.iter() {
+                let count = ((m.end - m.start) / *PAGE_SIZE) as usize;
+                let entries = vec![
+                    Some(uksm::UKSMPagemapEntry {
+                        pfn: 0,
+                        crc: 1,
+                        is_thp: false,
+                        is_ksm: false,
+                        idle: None,
+                    });
+                    count
+                ];
+                info.refresh_pages(&mut uksm, m.start, &entries, 1);
+            }
+            info.refresh_remove_maps(&mut uksm, &b);
+            let kept: HashSet<u64> = pages_of(&a).intersection(&pages_of(&b)).copied().collect();
+            for page in (0..64).map(|i| start + i * *PAGE_SIZE) {
+                let tracked = info
+                    .get(page)
+                    .is_some_and(|rec| rec.state == PageState::New);
+                assert_eq!(tracked, kept.contains(&page), "0x{:x}", page);
+            }
+            assert_eq!(budget.used(), kept.len() as u64);
+            info.clear(&mut uksm);
+        }
+    }
+}
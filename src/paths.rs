@@ -0,0 +1,50 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::config;
+use std::sync::OnceLock;
+
+static PROCFS_ROOT: OnceLock<String> = OnceLock::new();
+static UKSMFS_ROOT: OnceLock<String> = OnceLock::new();
+static CGROUPFS_ROOT: OnceLock<String> = OnceLock::new();
+
+// Set the roots before any path is used.
+pub fn init(config: &config::Config) {
+    let _ = PROCFS_ROOT.set(config.procfs_root.trim_end_matches('/').to_string());
+    let _ = UKSMFS_ROOT.set(config.uksmfs_root.trim_end_matches('/').to_string());
+    let _ = CGROUPFS_ROOT.set(config.cgroupfs_root.trim_end_matches('/').to_string());
+}
+
+// The path of name under procfs, for example proc("1/smaps").
+pub fn proc(name: &str) -> String {
+    format!(
+        "{}/{}",
+        PROCFS_ROOT.get().map(|s| s.as_str()).unwrap_or("/proc"),
+        name
+    )
+}
+
+// The path of name under the dir of the uksm kernel interface.
+pub fn uksm(name: &str) -> String {
+    format!(
+        "{}/{}",
+        UKSMFS_ROOT
+            .get()
+            .map(|s| s.as_str())
+            .unwrap_or("/proc/uksm"),
+        name
+    )
+}
+
+// The path of name under the cgroup filesystem.
+pub fn cgroup(name: &str) -> String {
+    format!(
+        "{}/{}",
+        CGROUPFS_ROOT
+            .get()
+            .map(|s| s.as_str())
+            .unwrap_or("/sys/fs/cgroup"),
+        name.trim_start_matches('/')
+    )
+}
@@ -2,37 +2,546 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::task;
+use crate::{page, paths, task, timing};
 use anyhow::{anyhow, Result};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
+use std::os::unix::fs::FileExt;
+use std::str::FromStr;
+use std::time::Duration;
 
+#[cfg(test)]
+pub mod fake;
+pub mod page_idle;
+
+const CMDLINE_MAX_LEN: usize = 100;
+
+// A metric of the pressure stall information, for example some_avg10.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PsiMetric {
+    kind: String,
+    avg: String,
+}
+
+impl Default for PsiMetric {
+    fn default() -> Self {
+        Self {
+            kind: "some".to_string(),
+            avg: "avg10".to_string(),
+        }
+    }
+}
+
+impl FromStr for PsiMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('_') {
+            Some((kind @ ("some" | "full"), avg @ ("avg10" | "avg60" | "avg300"))) => Ok(Self {
+                kind: kind.to_string(),
+                avg: avg.to_string(),
+            }),
+            _ => Err(anyhow!("psi metric {} is not supported", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for PsiMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}_{}", self.kind, self.avg)
+    }
+}
+
+// A watermark of the available memory in bytes or in percent of the total
+// memory, for example 1073741824 or 10%.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemWatermark {
+    Bytes(u64),
+    Percent(f64),
+}
+
+impl FromStr for MemWatermark {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(percent) = s.strip_suffix('%') {
+            let percent = percent
+                .parse::<f64>()
+                .map_err(|e| anyhow!("parse watermark {} failed: {}", s, e))?;
+            if !(0.0..=100.0).contains(&percent) {
+                return Err(anyhow!("watermark {} is not right", s));
+            }
+            Ok(MemWatermark::Percent(percent))
+        } else {
+            Ok(MemWatermark::Bytes(s.parse::<u64>().map_err(|e| {
+                anyhow!("parse watermark {} failed: {}", s, e)
+            })?))
+        }
+    }
+}
+
+impl MemWatermark {
+    // Get the watermark in bytes.
+    pub fn bytes(&self, total: u64) -> u64 {
+        match self {
+            MemWatermark::Bytes(bytes) => *bytes,
+            MemWatermark::Percent(percent) => (total as f64 * percent / 100.0) as u64,
+        }
+    }
+}
+
+// Read MemTotal and MemAvailable of meminfo in bytes.
+pub fn read_mem_available() -> Result<(u64, u64)> {
+    let meminfo_file = paths::proc("meminfo");
+    let meminfo = std::fs::read_to_string(&meminfo_file)
+        .map_err(|e| anyhow!("read file {} failed: {}", meminfo_file, e))?;
+
+    let field = |name: &str| -> Result<u64> {
+        let value = meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.split_whitespace().next())
+            .ok_or(anyhow!("cannot get {} from {}", name, meminfo_file))?;
+        let kb = value
+            .parse::<u64>()
+            .map_err(|e| anyhow!("parse {} {} failed: {}", name, value, e))?;
+        Ok(kb * 1024)
+    };
+
+    Ok((field("MemTotal:")?, field("MemAvailable:")?))
+}
+
+// Read the load average of the last minute.
+pub fn read_loadavg() -> Result<f64> {
+    let loadavg_file = paths::proc("loadavg");
+    let loadavg = std::fs::read_to_string(&loadavg_file)
+        .map_err(|e| anyhow!("read file {} failed: {}", loadavg_file, e))?;
+
+    let load = loadavg
+        .split_whitespace()
+        .next()
+        .ok_or(anyhow!("format of {} is not right", loadavg_file))?;
+    load.parse::<f64>()
+        .map_err(|e| anyhow!("parse load {} failed: {}", load, e))
+}
+
+// Read the metric of the pressure of resource, memory or cpu.
+pub fn read_pressure(resource: &str, metric: &PsiMetric) -> Result<f64> {
+    let psi_file = paths::proc(&format!("pressure/{}", resource));
+    let psi = std::fs::read_to_string(&psi_file)
+        .map_err(|e| anyhow!("read file {} failed: {}", psi_file, e))?;
+
+    // some avg10=0.00 avg60=0.00 avg300=0.00 total=0
+    let value = psi
+        .lines()
+        .find_map(|line| line.strip_prefix(metric.kind.as_str()))
+        .and_then(|line| {
+            line.split_whitespace()
+                .find_map(|field| field.strip_prefix(metric.avg.as_str())?.strip_prefix('='))
+        })
+        .ok_or(anyhow!("cannot get {} from {}", metric, psi_file))?;
+
+    value
+        .parse::<f64>()
+        .map_err(|e| anyhow!("parse {} {} failed: {}", metric, value, e))
+}
+
+// Check if the pages of pid can be merged, the zombies and the kernel
+// threads don't have any page.
+// The comm name of pid is in the error.
 pub fn pid_is_available(pid: u64) -> Result<()> {
-    let maps_file = format!("/proc/{}/smaps", pid);
+    let maps_file = paths::proc(&format!("{}/smaps", pid));
     File::open(maps_file.clone()).map_err(|e| anyhow!("open file {} failed: {}", maps_file, e))?;
 
+    let status_file = paths::proc(&format!("{}/status", pid));
+    let status = std::fs::read_to_string(&status_file)
+        .map_err(|e| anyhow!("read file {} failed: {}", status_file, e))?;
+    let field = |name: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .map(|value| value.trim())
+    };
+
+    let comm = field("Name:").unwrap_or("");
+    if field("State:").is_some_and(|state| state.starts_with('Z')) {
+        return Err(anyhow!("pid {} ({}) is a zombie", pid, comm));
+    }
+    // The tasks without mm don't have VmSize.
+    if field("VmSize:").is_none() {
+        return Err(anyhow!("pid {} ({}) is a kernel thread", pid, comm));
+    }
+
     Ok(())
 }
 
-#[derive(Debug, Clone, PartialEq)]
+// Get the comm name of pid.
+pub fn pid_comm(pid: u64) -> Result<String> {
+    let comm_file = paths::proc(&format!("{}/comm", pid));
+    let comm = std::fs::read_to_string(&comm_file)
+        .map_err(|e| anyhow!("read file {} failed: {}", comm_file, e))?;
+
+    Ok(comm.trim_end().to_string())
+}
+
+// Get the head of the cmdline of pid, the arguments are separated by spaces.
+pub fn pid_cmdline(pid: u64) -> Result<String> {
+    let cmdline_file = paths::proc(&format!("{}/cmdline", pid));
+    let mut cmdline = std::fs::read(&cmdline_file)
+        .map_err(|e| anyhow!("read file {} failed: {}", cmdline_file, e))?;
+    cmdline.truncate(CMDLINE_MAX_LEN);
+    for b in cmdline.iter_mut() {
+        if *b == 0 {
+            *b = b' ';
+        }
+    }
+
+    Ok(String::from_utf8_lossy(&cmdline).trim_end().to_string())
+}
+
+// Get the real uid of pid.
+pub fn pid_uid(pid: u64) -> Result<u32> {
+    let status_file = paths::proc(&format!("{}/status", pid));
+    let status = std::fs::read_to_string(&status_file)
+        .map_err(|e| anyhow!("read file {} failed: {}", status_file, e))?;
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|uids| uids.split_whitespace().next())
+        .and_then(|uid| uid.parse().ok())
+        .ok_or_else(|| anyhow!("file {} has no Uid", status_file))
+}
+
+// Get the value of key in kB of a status file in bytes.
+fn status_bytes(status_file: &str, key: &str) -> Result<u64> {
+    let status = std::fs::read_to_string(status_file)
+        .map_err(|e| anyhow!("read file {} failed: {}", status_file, e))?;
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .and_then(|rss| rss.split_whitespace().next())
+        .and_then(|rss| rss.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+        .ok_or_else(|| anyhow!("file {} has no {}", status_file, key))
+}
+
+// Get the resident memory of pid in bytes, the kernel threads don't have it.
+pub fn pid_rss(pid: u64) -> Result<u64> {
+    status_bytes(&paths::proc(&format!("{}/status", pid)), "VmRSS")
+}
+
+// Get the resident memory of uksmd in bytes.
+pub fn self_rss() -> Result<u64> {
+    status_bytes(&paths::proc("self/status"), "VmRSS")
+}
+
+// Get the peak resident memory of uksmd in bytes.
+pub fn self_peak_rss() -> Result<u64> {
+    status_bytes(&paths::proc("self/status"), "VmHWM")
+}
+
+// Get the cpu time that the calling thread used.
+pub fn thread_cpu_time() -> Result<Duration> {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut usage) } != 0 {
+        return Err(anyhow!(
+            "getrusage failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    let time = |tv: libc::timeval| {
+        Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+    };
+    Ok(time(usage.ru_utime) + time(usage.ru_stime))
+}
+
+// The end of the user address space, the largest one of the paging modes
+// of the arch.
+#[cfg(target_arch = "x86_64")]
+pub const USER_ADDR_END: u64 = 1 << 56;
+#[cfg(target_arch = "aarch64")]
+pub const USER_ADDR_END: u64 = 1 << 52;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+pub const USER_ADDR_END: u64 = 1 << 47;
+
+// The init should not be merged.
+pub fn pid_is_dangerous(pid: u64) -> bool {
+    pid == 1
+}
+
+// Check if pid is uksmd itself or one of its threads.
+pub fn pid_is_self(pid: u64) -> bool {
+    let self_pid = std::process::id() as u64;
+    if pid == self_pid {
+        return true;
+    }
+
+    let status_file = paths::proc(&format!("{}/status", pid));
+    std::fs::read_to_string(status_file).is_ok_and(|status| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("Tgid:"))
+            .and_then(|tgid| tgid.trim().parse::<u64>().ok())
+            == Some(self_pid)
+    })
+}
+
+// Get the fields of /proc/<pid>/stat from the 3rd field.
+fn pid_stat_fields(pid: u64) -> Result<Vec<String>> {
+    let stat_file = paths::proc(&format!("{}/stat", pid));
+    let stat = std::fs::read_to_string(&stat_file)
+        .map_err(|e| anyhow!("read file {} failed: {}", stat_file, e))?;
+
+    // The comm field is in parentheses and might include spaces.
+    Ok(stat
+        .rsplit_once(')')
+        .ok_or(anyhow!("format of {} is not right", stat_file))?
+        .1
+        .split_whitespace()
+        .map(String::from)
+        .collect())
+}
+
+// Get the nth field of /proc/<pid>/stat.
+fn pid_stat_field(fields: &[String], nth: usize, name: &str) -> Result<u64> {
+    // fields starts from the 3rd field.
+    let field = fields
+        .get(nth - 3)
+        .ok_or(anyhow!("cannot get {} from stat", name))?;
+
+    field
+        .parse::<u64>()
+        .map_err(|e| anyhow!("parse {} {} failed: {}", name, field, e))
+}
+
+// Get the start time of pid in clock ticks after system boot.
+// It is used to check if the pid is reused by another process.
+pub fn pid_start_time(pid: u64) -> Result<u64> {
+    pid_stat_field(&pid_stat_fields(pid)?, 22, "starttime")
+}
+
+pub fn pid_ppid(pid: u64) -> Result<u64> {
+    pid_stat_field(&pid_stat_fields(pid)?, 4, "ppid")
+}
+
+// Get the member processes of cgroup.
+pub fn read_cgroup_procs(cgroup: &str) -> Result<Vec<u64>> {
+    let procs_file = paths::cgroup(&format!("{}/cgroup.procs", cgroup));
+    let procs = std::fs::read_to_string(&procs_file)
+        .map_err(|e| anyhow!("read file {} failed: {}", procs_file, e))?;
+
+    Ok(procs
+        .lines()
+        .filter_map(|line| line.trim().parse::<u64>().ok())
+        .collect())
+}
+
+pub fn cgroup_exists(cgroup: &str) -> bool {
+    std::path::Path::new(&paths::cgroup(cgroup)).is_dir()
+}
+
+// Get the child processes of pid by the children files of its threads.
+pub fn pid_children(pid: u64) -> Result<Vec<u64>> {
+    let task_dir = paths::proc(&format!("{}/task", pid));
+    let entries =
+        std::fs::read_dir(&task_dir).map_err(|e| anyhow!("read dir {} failed: {}", task_dir, e))?;
+
+    let mut children = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| anyhow!("read dir {} failed: {}", task_dir, e))?;
+        let children_file = entry.path().join("children");
+        match std::fs::read_to_string(&children_file) {
+            Ok(s) => children.extend(s.split_whitespace().filter_map(|p| p.parse::<u64>().ok())),
+            // The kernel doesn't have CONFIG_PROC_CHILDREN.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return pid_children_by_ppid(pid);
+            }
+            // The thread exited.
+            Err(_) => {}
+        }
+    }
+
+    Ok(children)
+}
+
+// Get the child processes of pid by scanning the ppid of all the processes.
+fn pid_children_by_ppid(pid: u64) -> Result<Vec<u64>> {
+    let proc_dir = paths::proc("");
+    let entries =
+        std::fs::read_dir(&proc_dir).map_err(|e| anyhow!("read dir {} failed: {}", proc_dir, e))?;
+
+    Ok(entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u64>().ok())
+        .filter(|child| {
+            pid_stat_fields(*child)
+                .and_then(|fields| pid_stat_field(&fields, 4, "ppid"))
+                .is_ok_and(|ppid| ppid == pid)
+        })
+        .collect())
+}
+
+// Get the start of the code and the stack of pid.
+// They are changed when pid execs a new program.
+pub fn pid_exec_id(pid: u64) -> Result<(u64, u64)> {
+    let fields = pid_stat_fields(pid)?;
+
+    Ok((
+        pid_stat_field(&fields, 26, "startcode")?,
+        pid_stat_field(&fields, 28, "startstack")?,
+    ))
+}
+
+const PM_ENTRY_SIZE: u64 = 8;
+const PM_SOFT_DIRTY: u64 = 1 << 55;
+const PM_PRESENT: u64 = 1 << 63;
+// The pages whose flags are read in a chunk.
+const PM_CHUNK_PAGES: u64 = 4096;
+
+// The flags of a page in /proc/<pid>/pagemap.
+#[derive(Debug, Clone, Copy)]
+pub struct PagemapFlags {
+    pub present: bool,
+    pub soft_dirty: bool,
+}
+
+// Read the flags of the pages in [start, end) of pid chunk by chunk and
+// call f with the address and the flags of each chunk.
+// The pages after the end of the file are not present.
+pub fn read_pagemap_flags(
+    pid: u64,
+    start: u64,
+    end: u64,
+    f: &mut dyn FnMut(u64, &[PagemapFlags]) -> Result<()>,
+) -> Result<()> {
+    let pagemap_path = paths::proc(&format!("{}/pagemap", pid));
+    let pagemap = File::open(&pagemap_path)
+        .map_err(|e| anyhow!("open file {} failed: {}", pagemap_path, e))?;
+
+    let page_size = *page::PAGE_SIZE;
+    let mut buf = vec![0u8; (PM_CHUNK_PAGES * PM_ENTRY_SIZE) as usize];
+    let mut flags = Vec::with_capacity(PM_CHUNK_PAGES as usize);
+    let mut addr = start;
+    while addr < end {
+        let count = std::cmp::min(PM_CHUNK_PAGES, (end - addr) / page_size);
+        let buf = &mut buf[..(count * PM_ENTRY_SIZE) as usize];
+        let offset = addr / page_size * PM_ENTRY_SIZE;
+        let mut len = 0;
+        while len < buf.len() {
+            match pagemap.read_at(&mut buf[len..], offset + len as u64) {
+                Ok(0) => break,
+                Ok(n) => len += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    return Err(anyhow!(
+                        "read file {} 0x{:x} failed: {}",
+                        pagemap_path,
+                        addr,
+                        e
+                    ))
+                }
+            }
+        }
+        buf[len..].fill(0);
+
+        flags.clear();
+        flags.extend(buf.chunks_exact(PM_ENTRY_SIZE as usize).map(|b| {
+            let pme = u64::from_ne_bytes(b.try_into().expect("Expected 8 bytes"));
+            PagemapFlags {
+                present: pme & PM_PRESENT != 0,
+                soft_dirty: pme & PM_SOFT_DIRTY != 0,
+            }
+        }));
+        f(addr, &flags)?;
+        addr += count * page_size;
+    }
+
+    Ok(())
+}
+
+// Clear the soft-dirty bits of all the pages of pid.
+// It affects the other users of the soft-dirty bits of pid.
+pub fn clear_soft_dirty(pid: u64) -> Result<()> {
+    let clear_refs_path = paths::proc(&format!("{}/clear_refs", pid));
+    std::fs::write(&clear_refs_path, "4")
+        .map_err(|e| anyhow!("write file {} failed: {}", clear_refs_path, e))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MapRange {
     pub start: u64,
     pub end: u64,
 }
 
+// A vma that parse_task_smaps skipped and the reason.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapReject {
+    pub start: u64,
+    pub end: u64,
+    pub reason: String,
+}
+
 struct ParseSmapsRec {
+    // the vma before it is clipped
+    vma_start: u64,
+    vma_end: u64,
     start: u64,
     end: u64,
     anon_size: u64,
+    // the vma is a memfd or /dev/shm map that is selected by its resident
+    // pages instead of the anonymous ones
+    shmem: bool,
+    rss: u64,
+    // why the vma is skipped
+    reason: Option<String>,
 }
 
 impl ParseSmapsRec {
     pub fn new() -> Self {
         Self {
+            vma_start: 0,
+            vma_end: 0,
             start: 0,
             end: 0,
             anon_size: 0,
+            shmem: false,
+            rss: 0,
+            reason: None,
+        }
+    }
+
+    fn begin(&mut self, vma_start: u64, vma_end: u64) {
+        self.invalid();
+        self.vma_start = vma_start;
+        self.vma_end = vma_end;
+        self.shmem = false;
+        self.reason = None;
+    }
+
+    fn reject(&mut self, reason: String) {
+        self.invalid();
+        self.reason = Some(reason);
+    }
+
+    // Handle the vma when its lines are done.
+    fn finish(&self, vec: &mut Vec<MapRange>, rejects: &mut Option<&mut Vec<MapReject>>) {
+        if self.is_valid() {
+            vec.push(self.to_map_range());
+        } else if let Some(rejects) = rejects.as_deref_mut() {
+            if self.vma_start < self.vma_end {
+                rejects.push(MapReject {
+                    start: self.vma_start,
+                    end: self.vma_end,
+                    reason: self
+                        .reason
+                        .clone()
+                        .unwrap_or_else(|| "no anonymous pages".to_string()),
+                });
+            }
         }
     }
 
@@ -40,10 +549,11 @@ impl ParseSmapsRec {
         self.start = 0;
         self.end = 0;
         self.anon_size = 0;
+        self.rss = 0;
     }
 
     fn is_valid(&self) -> bool {
-        self.anon_size != 0 && self.start != self.end
+        (self.anon_size != 0 || (self.shmem && self.rss != 0)) && self.start != self.end
     }
 
     fn addr_ok(&self) -> bool {
@@ -58,58 +568,208 @@ impl ParseSmapsRec {
     }
 }
 
-pub fn parse_task_smaps(task: &task::TaskInfo) -> Result<Vec<MapRange>> {
-    let maps_file = format!("/proc/{}/smaps", task.pid);
+// Filter the maps by their pathnames, the anonymous maps are named by
+// PR_SET_VMA_ANON_NAME like "[anon:name]".
+#[derive(Debug, Clone, Default)]
+pub struct VmaFilter {
+    // keep only the maps that match one of them if it is not empty
+    include: Vec<Regex>,
+    // drop the maps that match one of them
+    exclude: Vec<Regex>,
+}
+
+impl VmaFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let compile = |patterns: &[String]| -> Result<Vec<Regex>> {
+            patterns
+                .iter()
+                .map(|p| Regex::new(p).map_err(|e| anyhow!("Regex::new {} failed: {}", p, e)))
+                .collect()
+        };
+
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    fn is_match(&self, name: &str) -> bool {
+        if self.exclude.iter().any(|re| re.is_match(name)) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(|re| re.is_match(name))
+    }
+}
+
+// If the pathname is of a memfd or a file in /dev/shm, the kernel appends
+// " (deleted)" to the memfds and the removed files.
+fn is_shmem_name(name: &str) -> bool {
+    name.starts_with("/memfd:") || name.starts_with("/dev/shm/")
+}
+
+// Parse the header line of a vma like
+// "7f0000000000-7f0000021000 rw-p 00000000 00:00 0    [anon:name]".
+// It is the format of the lines of maps too.
+// Return the start, the end, the perms and the pathname.
+fn parse_smaps_header(line: &str) -> Option<(u64, u64, &str, &str)> {
+    let mut fields = line.splitn(6, ' ');
+    let (start, end) = fields.next()?.split_once('-')?;
+    let start = u64::from_str_radix(start, 16).ok()?;
+    let end = u64::from_str_radix(end, 16).ok()?;
+    let perms = fields.next()?;
+    // offset, dev and inode
+    for _ in 0..3 {
+        fields.next()?;
+    }
+
+    Some((start, end, perms, fields.next().unwrap_or("").trim_start()))
+}
+
+// Clip [start, end) by the addr of task.
+// Return the reason if nothing is left or it is smaller than min_vma_size.
+fn clip_range(
+    task: &task::TaskInfo,
+    start: u64,
+    end: u64,
+    min_vma_size: u64,
+) -> std::result::Result<(u64, u64), &'static str> {
+    let (mut start, mut end) = (start, end);
+    if let Some((tstart, tend)) = task.addr {
+        if start >= tend || end <= tstart {
+            return Err("out of the addr of task");
+        }
+
+        if start < tstart {
+            start = tstart;
+        }
+
+        if end > tend {
+            end = tend;
+        }
+    }
+    if end - start < min_vma_size {
+        return Err("smaller than min_vma_size");
+    }
+
+    Ok((start, end))
+}
+
+// Get the private writable anonymous maps of task from maps, it is faster
+// than smaps because the kernel doesn't walk the page tables for it.
+// The anonymous maps are the ones without a pathname or named like
+// "[heap]", "[stack]" and "[anon:name]", the VmFlags are not checked.
+pub fn parse_task_maps(
+    task: &task::TaskInfo,
+    filter: &VmaFilter,
+    min_vma_size: u64,
+) -> Result<Vec<MapRange>> {
+    let maps_file = paths::proc(&format!("{}/maps", task.pid));
     let file = File::open(maps_file.clone())
         .map_err(|e| anyhow!("open file {} failed: {}", maps_file, e))?;
 
     let reader = BufReader::new(file);
-    let re = Regex::new(r"^(?P<start>[a-f0-9]+)-(?P<end>[a-f0-9]+) .*")
-        .map_err(|e| anyhow!("Regex::new failed: {}", e))?;
+    let mut vec: Vec<MapRange> = Vec::new();
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| anyhow!("read file {} failed: {}", maps_file, e))?;
+        let (start, end, perms, name) = match parse_smaps_header(&line) {
+            Some(header) => header,
+            None => continue,
+        };
+        if start >= end || perms != "rw-p" || !filter.is_match(name) {
+            continue;
+        }
+        if !(name.is_empty() || name == "[heap]" || name == "[stack]" || name.starts_with("[anon:"))
+        {
+            continue;
+        }
+
+        if let Ok((start, end)) = clip_range(task, start, end, min_vma_size) {
+            vec.push(MapRange { start, end });
+        }
+    }
+
+    Ok(coalesce_ranges(vec))
+}
+
+// Get the anonymous maps of task, the maps that have a flag of
+// skip_vm_flags in VmFlags, are dropped by filter or are smaller than
+// min_vma_size after clipped by the addr of task are skipped.
+// If include_shmem is set, the memfd and /dev/shm maps that have resident
+// pages are got too, the "sh" of skip_vm_flags doesn't skip them. The
+// hugetlb maps are always skipped.
+pub fn parse_task_smaps(
+    task: &task::TaskInfo,
+    skip_vm_flags: &[String],
+    filter: &VmaFilter,
+    min_vma_size: u64,
+    include_shmem: bool,
+) -> Result<Vec<MapRange>> {
+    parse_task_smaps_rejects(
+        task,
+        skip_vm_flags,
+        filter,
+        min_vma_size,
+        include_shmem,
+        None,
+    )
+}
+
+// parse_task_smaps that records the skipped vmas to rejects if it is set.
+pub fn parse_task_smaps_rejects(
+    task: &task::TaskInfo,
+    skip_vm_flags: &[String],
+    filter: &VmaFilter,
+    min_vma_size: u64,
+    include_shmem: bool,
+    mut rejects: Option<&mut Vec<MapReject>>,
+) -> Result<Vec<MapRange>> {
+    let _timer = timing::start(timing::Op::ParseSmaps);
+    let maps_file = paths::proc(&format!("{}/smaps", task.pid));
+    let file = File::open(maps_file.clone())
+        .map_err(|e| anyhow!("open file {} failed: {}", maps_file, e))?;
 
+    let reader = BufReader::new(file);
     let mut vec: Vec<MapRange> = Vec::new();
 
     let mut rec = ParseSmapsRec::new();
     for line in reader.lines() {
         let line = line.map_err(|e| anyhow!("read file {} failed: {}", maps_file, e))?;
-        if let Some(captures) = re.captures(&line) {
+        if let Some((start, end, _, name)) = parse_smaps_header(&line) {
             // Got a new vma.
             // handle the old vma rec.
-            if rec.is_valid() {
-                vec.push(rec.to_map_range());
-            }
+            rec.finish(&mut vec, &mut rejects);
 
-            rec.invalid();
+            rec.begin(start, end);
+            rec.shmem = include_shmem && is_shmem_name(name);
 
-            let mut start = u64::from_str_radix(&captures["start"], 16)
-                .map_err(|e| anyhow!("u64::from_str_radix {} failed: {}", &captures["start"], e))?;
-            let mut end = u64::from_str_radix(&captures["end"], 16)
-                .map_err(|e| anyhow!("u64::from_str_radix {} failed: {}", &captures["end"], e))?;
             if start >= end {
                 continue;
             }
+            if !filter.is_match(name) {
+                rec.reason = Some(format!("filtered by name \"{}\"", name));
+                continue;
+            }
 
-            if let Some((tstart, tend)) = task.addr {
-                if start >= tend || end <= tstart {
-                    continue;
-                }
-
-                if start < tstart {
-                    start = tstart;
-                }
-
-                if end > tend {
-                    end = tend;
+            match clip_range(task, start, end, min_vma_size) {
+                Ok((start, end)) => {
+                    rec.start = start;
+                    rec.end = end;
                 }
+                Err(reason) => rec.reason = Some(reason.to_string()),
             }
-            rec.start = start;
-            rec.end = end;
         } else if rec.addr_ok() && line.starts_with("Anonymous:") {
             let parts: Vec<&str> = line.split_whitespace().collect();
             if parts.len() < 3 {
                 continue;
             }
             rec.anon_size = parts[1].parse::<u64>().unwrap_or(0);
+        } else if rec.addr_ok() && rec.shmem && line.starts_with("Rss:") {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 3 {
+                continue;
+            }
+            rec.rss = parts[1].parse::<u64>().unwrap_or(0);
         } else if rec.addr_ok()
             && (line.starts_with("Shared_Hugetlb:") || line.starts_with("Private_Hugetlb:"))
         {
@@ -119,14 +779,478 @@ pub fn parse_task_smaps(task: &task::TaskInfo) -> Result<Vec<MapRange>> {
                 continue;
             }
             if parts[1].parse::<u64>().unwrap_or(0) > 0 {
-                rec.invalid();
+                rec.reject("hugetlb".to_string());
+            }
+        } else if rec.addr_ok() && line.starts_with("VmFlags:") {
+            if let Some(flag) = line
+                .split_whitespace()
+                .skip(1)
+                .filter(|flag| !(rec.shmem && *flag == "sh"))
+                .find(|flag| skip_vm_flags.iter().any(|f| f == flag))
+            {
+                rec.reject(format!("vm flag {}", flag));
             }
         }
     }
     // Handle the last vma
-    if rec.is_valid() {
-        vec.push(rec.to_map_range());
+    rec.finish(&mut vec, &mut rejects);
+
+    Ok(coalesce_ranges(vec))
+}
+
+// Merge the adjacent ranges, the ranges are sorted by start.
+fn coalesce_ranges(ranges: Vec<MapRange>) -> Vec<MapRange> {
+    let mut merged: Vec<MapRange> = Vec::with_capacity(ranges.len());
+
+    for r in ranges {
+        match merged.last_mut() {
+            Some(last) if last.end == r.start => last.end = r.end,
+            _ => merged.push(r),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::proc::fake;
+    use std::time::Instant;
+    use structopt::StructOpt;
+
+    #[test]
+    fn test_read_pagemap_flags_in_chunks() {
+        let pid = fake::new_pid();
+        // The pages after the file are not present.
+        let pages = PM_CHUNK_PAGES + 10;
+        let pagemap: Vec<u8> = (0..pages)
+            .flat_map(|i| {
+                let mut pme = 0;
+                if i % 3 != 0 {
+                    pme |= PM_PRESENT;
+                }
+                if i % 2 == 0 {
+                    pme |= PM_SOFT_DIRTY;
+                }
+                pme.to_ne_bytes()
+            })
+            .collect();
+        let path = fake::root().join(pid.to_string()).join("pagemap");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, pagemap).unwrap();
+
+        let page_size = *page::PAGE_SIZE;
+        let start = page_size;
+        let end = (pages + 20) * page_size;
+        let mut chunks = Vec::new();
+        let mut flags = Vec::new();
+        read_pagemap_flags(pid, start, end, &mut |addr, f| {
+            chunks.push((addr, f.len() as u64));
+            flags.extend_from_slice(f);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(
+            chunks,
+            vec![
+                (start, PM_CHUNK_PAGES),
+                (start + PM_CHUNK_PAGES * page_size, 29)
+            ]
+        );
+        for (i, f) in (1..).zip(flags) {
+            let present = i < pages && i % 3 != 0;
+            let soft_dirty = i < pages && i % 2 == 0;
+            assert_eq!(
+                (f.present, f.soft_dirty),
+                (present, soft_dirty),
+                "page {}",
+                i
+            );
+        }
+
+        assert!(read_pagemap_flags(fake::new_pid(), start, end, &mut |_, _| Ok(())).is_err());
+    }
+
+    // The smaps of a qemu whose guest ram is a memory-backend-memfd, with a
+    // dimm of memfd with hugetlb=on and a memory-backend-file in /dev/shm.
+    const QEMU_MEMFD_SMAPS: &str = include_str!("proc/testdata/qemu-memfd.smaps");
+
+    fn range(start: u64, end: u64) -> MapRange {
+        MapRange { start, end }
+    }
+
+    fn parse_qemu_memfd(skip_vm_flags: &[&str], include_shmem: bool) -> Vec<MapRange> {
+        let pid = fake::new_pid();
+        fake::write_file(pid, "smaps", QEMU_MEMFD_SMAPS);
+        let task = task::TaskInfo::new(pid, None, 0, None);
+        let skip_vm_flags: Vec<String> = skip_vm_flags.iter().map(|f| f.to_string()).collect();
+        parse_task_smaps(
+            &task,
+            &skip_vm_flags,
+            &VmaFilter::default(),
+            0,
+            include_shmem,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_smaps_qemu_memfd() {
+        let anon = vec![
+            range(0x55d0c1e00000, 0x55d0c1e40000),
+            range(0x55d0c3200000, 0x55d0c3a00000),
+            range(0x7f3e30000000, 0x7f3e30800000),
+            range(0x7ffc5a100000, 0x7ffc5a122000),
+        ];
+        // The guest ram has no anonymous pages.
+        assert_eq!(parse_qemu_memfd(&["lo", "io", "pf", "dd"], false), anon);
+
+        // The resident memfd and /dev/shm maps are got, the ring that has no
+        // resident pages and the shared file map are not.
+        let mut all = anon.clone();
+        all.insert(2, range(0x7f3c00000000, 0x7f3c80000000));
+        all.insert(3, range(0x7f3e00000000, 0x7f3e10000000));
+        assert_eq!(parse_qemu_memfd(&["lo", "io", "pf", "dd"], true), all);
+
+        // The "sh" of skip_vm_flags only skips the shared maps that are not
+        // shmem.
+        assert_eq!(parse_qemu_memfd(&["sh"], true), all);
+        assert_eq!(parse_qemu_memfd(&["sh"], false), anon);
+    }
+
+    #[test]
+    fn test_parse_smaps_shmem_hugetlb() {
+        let pid = fake::new_pid();
+        fake::write_file(pid, "smaps", QEMU_MEMFD_SMAPS);
+        let task = task::TaskInfo::new(pid, None, 0, None);
+
+        // The memfd dimm of hugetlb is skipped with include_shmem.
+        let mut rejects = Vec::new();
+        let maps = parse_task_smaps_rejects(
+            &task,
+            &[],
+            &VmaFilter::default(),
+            0,
+            true,
+            Some(&mut rejects),
+        )
+        .unwrap();
+        let dimm = range(0x7f3d00000000, 0x7f3d40000000);
+        assert!(!maps
+            .iter()
+            .any(|m| m.start < dimm.end && dimm.start < m.end));
+        let reasons: Vec<(u64, &str)> = rejects
+            .iter()
+            .map(|r| (r.start, r.reason.as_str()))
+            .collect();
+        assert!(reasons.contains(&(dimm.start, "hugetlb")));
+        assert!(reasons.contains(&(0x7f3d80000000, "no anonymous pages")));
+
+        // A hugetlb map that has anonymous pages is skipped too.
+        let smaps = "7f0000000000-7f0000400000 rw-p 00000000 00:0f 0\n\
+                     Size:               4096 kB\n\
+                     Rss:                4096 kB\n\
+                     Anonymous:          4096 kB\n\
+                     Shared_Hugetlb:        0 kB\n\
+                     Private_Hugetlb:    4096 kB\n\
+                     VmFlags: rd wr mr mw me de ht\n";
+        fake::write_file(pid, "smaps", smaps);
+        for include_shmem in [false, true] {
+            assert!(
+                parse_task_smaps(&task, &[], &VmaFilter::default(), 0, include_shmem)
+                    .unwrap()
+                    .is_empty()
+            );
+        }
     }
 
-    Ok(vec)
+    // The smaps of a task that has a map of each VmFlags to skip, a heap and
+    // a stack.
+    const VM_FLAGS_SMAPS: &str = include_str!("proc/testdata/vm-flags.smaps");
+
+    #[test]
+    fn test_parse_smaps_vm_flags() {
+        let pid = fake::new_pid();
+        fake::write_file(pid, "smaps", VM_FLAGS_SMAPS);
+        let task = task::TaskInfo::new(pid, None, 0, None);
+        let parse = |skip_vm_flags: &[String]| {
+            let mut rejects = Vec::new();
+            let maps = parse_task_smaps_rejects(
+                &task,
+                skip_vm_flags,
+                &VmaFilter::default(),
+                0,
+                false,
+                Some(&mut rejects),
+            )
+            .unwrap();
+            let rejects: Vec<(u64, String)> =
+                rejects.into_iter().map(|r| (r.start, r.reason)).collect();
+            (maps, rejects)
+        };
+        let heap = range(0x55f0a0000000, 0x55f0a0100000);
+        let stack = range(0x7ffd10000000, 0x7ffd10021000);
+        let flagged = [
+            ("lo", range(0x7f1000000000, 0x7f1000200000)),
+            ("io", range(0x7f1100000000, 0x7f1100100000)),
+            ("pf", range(0x7f1200000000, 0x7f1200100000)),
+            ("dd", range(0x7f1300000000, 0x7f1300100000)),
+            ("sh", range(0x7f1400000000, 0x7f1400100000)),
+        ];
+
+        // The default flags and the "sh" that is skipped without
+        // include_shared_anon.
+        let config = config::Config::from_iter(["uksmd"]);
+        assert_eq!(config.skip_vm_flags, ["lo", "io", "pf", "dd"]);
+        let mut skip_vm_flags = config.skip_vm_flags.clone();
+        skip_vm_flags.push("sh".to_string());
+        let (maps, rejects) = parse(&skip_vm_flags);
+        assert_eq!(maps, vec![heap.clone(), stack.clone()]);
+        let want: Vec<(u64, String)> = flagged
+            .iter()
+            .map(|(flag, m)| (m.start, format!("vm flag {}", flag)))
+            .collect();
+        assert_eq!(rejects, want);
+
+        // Each map is got when its flag is not skipped.
+        for (flag, m) in flagged.iter() {
+            let others: Vec<String> = skip_vm_flags
+                .iter()
+                .filter(|f| f != flag)
+                .cloned()
+                .collect();
+            let (maps, rejects) = parse(&others);
+            assert_eq!(
+                maps,
+                vec![heap.clone(), m.clone(), stack.clone()],
+                "{}",
+                flag
+            );
+            assert_eq!(rejects.len(), flagged.len() - 1);
+        }
+        assert_eq!(parse(&[]).0.len(), flagged.len() + 2);
+    }
+
+    #[test]
+    fn test_parse_smaps_header() {
+        assert_eq!(
+            parse_smaps_header(
+                "7f9c40000000-7f9c80000000 rw-p 00000000 00:00 0                          [anon:guest-ram]"
+            ),
+            Some((0x7f9c40000000, 0x7f9c80000000, "rw-p", "[anon:guest-ram]"))
+        );
+        assert_eq!(
+            parse_smaps_header("7f9ca0000000-7f9ca0800000 rw-p 00000000 00:00 0"),
+            Some((0x7f9ca0000000, 0x7f9ca0800000, "rw-p", ""))
+        );
+        // The pathname might have spaces.
+        assert_eq!(
+            parse_smaps_header(
+                "7f3c00000000-7f3c80000000 rw-s 00000000 00:01 2049                       /memfd:pc.ram (deleted)"
+            ),
+            Some((0x7f3c00000000, 0x7f3c80000000, "rw-s", "/memfd:pc.ram (deleted)"))
+        );
+        assert_eq!(parse_smaps_header("Anonymous:          4096 kB"), None);
+        assert_eq!(parse_smaps_header("VmFlags: rd wr mr mw me ac"), None);
+    }
+
+    // The smaps of a process that names its guest ram with PR_SET_VMA.
+    const PLAIN_SMAPS: &str = include_str!("proc/testdata/plain.smaps");
+
+    fn parse_filtered(
+        smaps: &str,
+        include: &[&str],
+        exclude: &[&str],
+        include_shmem: bool,
+    ) -> Vec<MapRange> {
+        let pid = fake::new_pid();
+        fake::write_file(pid, "smaps", smaps);
+        let task = task::TaskInfo::new(pid, None, 0, None);
+        let strings =
+            |patterns: &[&str]| -> Vec<String> { patterns.iter().map(|p| p.to_string()).collect() };
+        let filter = VmaFilter::new(&strings(include), &strings(exclude)).unwrap();
+        parse_task_smaps(&task, &[], &filter, 0, include_shmem).unwrap()
+    }
+
+    #[test]
+    fn test_parse_smaps_vma_filter() {
+        // Only the guest ram of qemu, not its heap and stack.
+        assert_eq!(
+            parse_filtered(QEMU_MEMFD_SMAPS, &["^/memfd:pc\\.ram"], &[], true),
+            vec![range(0x7f3c00000000, 0x7f3c80000000)]
+        );
+        assert_eq!(
+            parse_filtered(QEMU_MEMFD_SMAPS, &[], &["^\\[(heap|stack)\\]$"], false),
+            vec![
+                range(0x55d0c1e00000, 0x55d0c1e40000),
+                range(0x7f3e30000000, 0x7f3e30800000),
+            ]
+        );
+
+        let guest_ram = range(0x7f9c40000000, 0x7f9c80000000);
+        let guest_rom = range(0x7f9c90000000, 0x7f9c90800000);
+        assert_eq!(
+            parse_filtered(PLAIN_SMAPS, &["^\\[anon:guest"], &[], false),
+            vec![guest_ram.clone(), guest_rom.clone()]
+        );
+        // A map that matches an exclude pattern is dropped even if it
+        // matches an include one.
+        assert_eq!(
+            parse_filtered(PLAIN_SMAPS, &["^\\[anon:guest"], &["-rom\\]$"], false),
+            vec![guest_ram.clone()]
+        );
+        // The maps without a name are matched by "^$".
+        assert_eq!(
+            parse_filtered(PLAIN_SMAPS, &["^$"], &[], false),
+            vec![range(0x7f9ca0000000, 0x7f9ca0800000)]
+        );
+        // The data of the program and libc, the heap, the anonymous maps
+        // and the stack.
+        assert_eq!(parse_filtered(PLAIN_SMAPS, &[], &[], false).len(), 7);
+        assert_eq!(
+            parse_filtered(PLAIN_SMAPS, &[], &["^/", "^\\[anon:"], false),
+            vec![
+                range(0x5612a6000000, 0x5612a6400000),
+                range(0x7f9ca0000000, 0x7f9ca0800000),
+                range(0x7ffe8e300000, 0x7ffe8e321000),
+            ]
+        );
+
+        assert!(VmaFilter::new(&["[anon".to_string()], &[]).is_err());
+    }
+
+    // The regex that the header lines of smaps were parsed with.
+    fn regex_header(re: &Regex, line: &str) -> Option<(u64, u64, String)> {
+        let captures = re.captures(line)?;
+        Some((
+            u64::from_str_radix(&captures["start"], 16).ok()?,
+            u64::from_str_radix(&captures["end"], 16).ok()?,
+            captures["name"].to_string(),
+        ))
+    }
+
+    #[test]
+    fn test_parse_smaps_header_against_regex() {
+        let re =
+            Regex::new(r"^(?P<start>[a-f0-9]+)-(?P<end>[a-f0-9]+) \S+ \S+ \S+ \S+\s*(?P<name>.*)$")
+                .unwrap();
+        let smaps = [QEMU_MEMFD_SMAPS, PLAIN_SMAPS, VM_FLAGS_SMAPS].concat();
+        let mut headers = 0;
+        for line in smaps.lines() {
+            let got = parse_smaps_header(line)
+                .map(|(start, end, _, name)| (start, end, name.to_string()));
+            assert_eq!(got, regex_header(&re, line), "{}", line);
+            headers += got.is_some() as usize;
+        }
+        assert_eq!(headers, 26);
+
+        // The parser is faster than the regex on the lines of a task that
+        // has thousands of vmas.
+        let lines: Vec<&str> = smaps
+            .lines()
+            .cycle()
+            .take(100 * smaps.lines().count())
+            .collect();
+        let now = Instant::now();
+        let parsed = lines
+            .iter()
+            .filter(|line| parse_smaps_header(line).is_some())
+            .count();
+        let parser = now.elapsed();
+        let now = Instant::now();
+        let matched = lines
+            .iter()
+            .filter(|line| regex_header(&re, line).is_some())
+            .count();
+        let regex = now.elapsed();
+        assert_eq!(parsed, matched);
+        assert!(parser < regex / 2, "{:?} {:?}", parser, regex);
+    }
+
+    #[test]
+    fn test_parse_maps_against_smaps() {
+        let fixtures = [
+            (
+                QEMU_MEMFD_SMAPS,
+                include_str!("proc/testdata/qemu-memfd.maps"),
+            ),
+            (PLAIN_SMAPS, include_str!("proc/testdata/plain.maps")),
+        ];
+        let skip_vm_flags: Vec<String> = ["lo", "io", "pf", "dd", "sh"]
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        // The maps of the files that have anonymous pages are only got
+        // from smaps.
+        let no_files = VmaFilter::new(&[], &["^/".to_string()]).unwrap();
+        for (smaps, maps) in fixtures {
+            let pid = fake::new_pid();
+            fake::write_file(pid, "smaps", smaps);
+            fake::write_file(pid, "maps", maps);
+            for addr in [None, Some((0x7f9c60000000, 0x7ffe8e310000))] {
+                for min_vma_size in [0, 4 << 20] {
+                    let task = task::TaskInfo::new(pid, addr, 0, None);
+                    let want =
+                        parse_task_smaps(&task, &skip_vm_flags, &no_files, min_vma_size, false)
+                            .unwrap();
+                    let got = parse_task_maps(&task, &VmaFilter::default(), min_vma_size).unwrap();
+                    assert!(addr.is_some() || min_vma_size != 0 || !want.is_empty());
+                    assert_eq!(got, want, "{:?} {}", addr, min_vma_size);
+                }
+            }
+        }
+    }
+
+    fn write_status(pid: u64, name: &str, state: &str, tgid: u64, vm: bool) {
+        let vm = if vm {
+            "VmSize:\t  102400 kB\nVmRSS:\t    4096 kB\n"
+        } else {
+            ""
+        };
+        fake::write_file(
+            pid,
+            "status",
+            &format!(
+                "Name:\t{}\nUmask:\t0022\nState:\t{}\nTgid:\t{}\nNgid:\t0\nPid:\t{}\nPPid:\t1\n\
+                 Uid:\t0\t0\t0\t0\nGid:\t0\t0\t0\t0\n{}Threads:\t1\n",
+                name, state, tgid, pid, vm
+            ),
+        );
+    }
+
+    #[test]
+    fn test_pid_is_available() {
+        let pid = fake::new_pid();
+        // smaps is needed.
+        write_status(pid, "worker", "S (sleeping)", pid, true);
+        assert!(pid_is_available(pid).is_err());
+        fake::write_file(pid, "smaps", "");
+        pid_is_available(pid).unwrap();
+
+        write_status(pid, "worker", "Z (zombie)", pid, false);
+        let e = pid_is_available(pid).unwrap_err().to_string();
+        assert_eq!(e, format!("pid {} (worker) is a zombie", pid));
+
+        write_status(pid, "kworker/0:1", "I (idle)", pid, false);
+        let e = pid_is_available(pid).unwrap_err().to_string();
+        assert_eq!(e, format!("pid {} (kworker/0:1) is a kernel thread", pid));
+
+        assert!(pid_is_dangerous(1));
+        assert!(!pid_is_dangerous(pid));
+        // A thread of uksmd has its tgid.
+        assert!(pid_is_self(std::process::id() as u64));
+        assert!(!pid_is_self(pid));
+        write_status(
+            pid,
+            "uksmd",
+            "S (sleeping)",
+            std::process::id() as u64,
+            true,
+        );
+        assert!(pid_is_self(pid));
+        fake::FakeTask::exit(pid);
+    }
 }
@@ -0,0 +1,155 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The fake procfs of the tests.
+// The root of procfs is set once, so all the tests share it and each of
+// them gets its own pids from new_pid. The pids are larger than the max pid
+// of the kernel, so they are never the real tasks.
+
+use crate::{config, page, paths};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use structopt::StructOpt;
+
+// PID_MAX_LIMIT of the kernel is 1 << 22.
+const PID_BASE: u64 = 1 << 23;
+
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+static LAST_PID: AtomicU64 = AtomicU64::new(PID_BASE);
+
+// The start of the first map of the fake tasks.
+pub const MAP_START: u64 = 0x1000_0000;
+
+// The root of the fake procfs, it is created and set as the root of procfs
+// the first time.
+pub fn root() -> &'static Path {
+    ROOT.get_or_init(|| {
+        let root = std::env::temp_dir().join(format!("uksmd-test-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let config = config::Config::from_iter(["uksmd", "--procfs-root", root.to_str().unwrap()]);
+        paths::init(&config);
+        root
+    })
+}
+
+pub fn new_pid() -> u64 {
+    LAST_PID.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+// Write a file of pid in the fake procfs, for example a fixture of smaps.
+pub fn write_file(pid: u64, name: &str, content: &str) {
+    let path = root().join(pid.to_string()).join(name);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+// The files of a fake task.
+#[derive(Debug, Clone)]
+pub struct FakeTask {
+    pub pid: u64,
+    pub ppid: u64,
+    pub start_time: u64,
+    pub uid: u32,
+    pub comm: String,
+    // the start of the code and the stack, they are changed by an exec
+    pub exec_id: (u64, u64),
+    // the private anonymous maps of the task
+    pub maps: Vec<(u64, u64)>,
+    pub children: Vec<u64>,
+}
+
+impl FakeTask {
+    pub fn new(pid: u64) -> Self {
+        Self {
+            pid,
+            ppid: 1,
+            start_time: 100,
+            uid: 1000,
+            comm: "fake".to_string(),
+            exec_id: (0x400000, 0x7ff000000000),
+            maps: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    // Add a map of pages from start.
+    pub fn map(mut self, start: u64, pages: u64) -> Self {
+        self.maps.push((start, start + pages * *page::PAGE_SIZE));
+        self
+    }
+
+    pub fn smaps(&self) -> String {
+        self.maps
+            .iter()
+            .map(|(start, end)| {
+                let kb = (end - start) / 1024;
+                format!(
+                    "{:x}-{:x} rw-p 00000000 00:00 0\n\
+                     Size:           {} kB\n\
+                     Rss:            {} kB\n\
+                     Anonymous:      {} kB\n\
+                     Shared_Hugetlb:        0 kB\n\
+                     Private_Hugetlb:       0 kB\n\
+                     VmFlags: rd wr mr mw me ac\n",
+                    start, end, kb, kb, kb
+                )
+            })
+            .collect()
+    }
+
+    // Write the files of the task, the smaps is made of the maps.
+    pub fn write(&self) {
+        self.write_files(&self.smaps());
+    }
+
+    // Write the files of the task with the fixture of smaps.
+    pub fn write_files(&self, smaps: &str) {
+        let pid = self.pid;
+        write_file(
+            pid,
+            "status",
+            &format!(
+                "Name:\t{}\nState:\tS (sleeping)\nTgid:\t{}\nPPid:\t{}\n\
+                 Uid:\t{}\t{}\t{}\t{}\nVmSize:\t  102400 kB\nVmRSS:\t    4096 kB\n",
+                self.comm, pid, self.ppid, self.uid, self.uid, self.uid, self.uid
+            ),
+        );
+        write_file(pid, "comm", &format!("{}\n", self.comm));
+        write_file(pid, "cmdline", &format!("{}\0--fake\0", self.comm));
+
+        // The fields from the 3rd one, ppid is the 4th, starttime is the
+        // 22nd, startcode is the 26th and startstack is the 28th.
+        let mut fields = vec!["0".to_string(); 26];
+        fields[0] = "S".to_string();
+        fields[4 - 3] = self.ppid.to_string();
+        fields[22 - 3] = self.start_time.to_string();
+        fields[26 - 3] = self.exec_id.0.to_string();
+        fields[28 - 3] = self.exec_id.1.to_string();
+        write_file(
+            pid,
+            "stat",
+            &format!("{} ({}) {}\n", pid, self.comm, fields.join(" ")),
+        );
+
+        write_file(pid, "smaps", smaps);
+        let maps: String = smaps
+            .lines()
+            .filter(|line| line.split(' ').next().is_some_and(|r| r.contains('-')))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        write_file(pid, "maps", &maps);
+        let children: Vec<String> = self.children.iter().map(|c| c.to_string()).collect();
+        write_file(
+            pid,
+            &format!("task/{}/children", pid),
+            &format!("{} ", children.join(" ")),
+        );
+    }
+
+    // The task exits, its files are gone.
+    pub fn exit(pid: u64) {
+        let _ = std::fs::remove_dir_all(root().join(pid.to_string()));
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The idle page tracking of the kernel.
+// Each bit of the bitmap is the idle flag of a pfn, it is read and written
+// in 64-bit words.
+
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::FileExt;
+
+const PAGE_IDLE_BITMAP_PATH: &str = "/sys/kernel/mm/page_idle/bitmap";
+
+const WORD_BYTES: u64 = 8;
+const WORD_BITS: u64 = 64;
+
+fn open(write: bool) -> Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(write)
+        .open(PAGE_IDLE_BITMAP_PATH)
+        .map_err(|e| anyhow!("open file {} failed: {}", PAGE_IDLE_BITMAP_PATH, e))
+}
+
+// Group the pfns by the word of the bitmap.
+fn words(pfns: &[u64]) -> BTreeMap<u64, u64> {
+    let mut words = BTreeMap::new();
+    for pfn in pfns {
+        *words.entry(pfn / WORD_BITS).or_insert(0) |= 1 << (pfn % WORD_BITS);
+    }
+    words
+}
+
+// Return if each page of pfns is idle.
+pub fn is_idle(pfns: &[u64]) -> Result<Vec<bool>> {
+    let file = open(false)?;
+
+    let mut idle_words = BTreeMap::new();
+    for index in words(pfns).into_keys() {
+        let mut buf = [0u8; WORD_BYTES as usize];
+        file.read_exact_at(&mut buf, index * WORD_BYTES)
+            .map_err(|e| anyhow!("read file {} failed: {}", PAGE_IDLE_BITMAP_PATH, e))?;
+        idle_words.insert(index, u64::from_ne_bytes(buf));
+    }
+
+    Ok(pfns
+        .iter()
+        .map(|pfn| idle_words[&(pfn / WORD_BITS)] & (1 << (pfn % WORD_BITS)) != 0)
+        .collect())
+}
+
+// Mark the pages of pfns idle, the kernel clears the flag when the page
+// is accessed.
+pub fn set_idle(pfns: &[u64]) -> Result<()> {
+    let file = open(true)?;
+
+    for (index, bits) in words(pfns) {
+        file.write_all_at(&bits.to_ne_bytes(), index * WORD_BYTES)
+            .map_err(|e| anyhow!("write file {} failed: {}", PAGE_IDLE_BITMAP_PATH, e))?;
+    }
+
+    Ok(())
+}
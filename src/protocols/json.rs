@@ -0,0 +1,76 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The json of the messages of uksmd_ctl.proto, which is built from the
+//! reflection of the messages so it follows the proto.
+
+use protobuf::reflect::{ReflectValueRef, RuntimeFieldType};
+use protobuf::MessageDyn;
+use serde_json::{Map, Value};
+
+fn reflect_json(value: ReflectValueRef) -> Value {
+    match value {
+        ReflectValueRef::U32(v) => Value::from(v),
+        ReflectValueRef::U64(v) => Value::from(v),
+        ReflectValueRef::I32(v) => Value::from(v),
+        ReflectValueRef::I64(v) => Value::from(v),
+        ReflectValueRef::F32(v) => Value::from(v),
+        ReflectValueRef::F64(v) => Value::from(v),
+        ReflectValueRef::Bool(v) => Value::from(v),
+        ReflectValueRef::String(v) => Value::from(v),
+        ReflectValueRef::Bytes(v) => Value::from(v),
+        ReflectValueRef::Enum(desc, v) => match desc.value_by_number(v) {
+            Some(v) => Value::from(v.name()),
+            None => Value::from(v),
+        },
+        ReflectValueRef::Message(m) => message_json(&*m),
+    }
+}
+
+// The json keys are strings, the integer keys of the maps are converted.
+fn reflect_key(value: ReflectValueRef) -> String {
+    match reflect_json(value) {
+        Value::String(s) => s,
+        v => v.to_string(),
+    }
+}
+
+/// Convert msg to a json object, the keys are the names of the fields.
+/// The fields of the oneofs are only set if they are set in msg.
+pub fn message_json(msg: &dyn MessageDyn) -> Value {
+    let mut obj = Map::new();
+
+    for field in msg.descriptor_dyn().fields() {
+        let value = match field.runtime_field_type() {
+            RuntimeFieldType::Singular(_) => {
+                if field.containing_oneof().is_some() {
+                    // Only the fields of the oneofs that are set.
+                    match field.get_singular(msg) {
+                        Some(v) => reflect_json(v),
+                        None => continue,
+                    }
+                } else {
+                    reflect_json(field.get_singular_field_or_default(msg))
+                }
+            }
+            RuntimeFieldType::Repeated(_) => Value::Array(
+                field
+                    .get_repeated(msg)
+                    .into_iter()
+                    .map(reflect_json)
+                    .collect(),
+            ),
+            RuntimeFieldType::Map(..) => Value::Object(
+                field
+                    .get_map(msg)
+                    .into_iter()
+                    .map(|(k, v)| (reflect_key(k), reflect_json(v)))
+                    .collect(),
+            ),
+        };
+        obj.insert(field.name().to_string(), value);
+    }
+
+    Value::Object(obj)
+}
@@ -2,6 +2,9 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
+#![allow(renamed_and_removed_lints)]
+
 pub mod empty;
+pub mod json;
 pub mod uksmd_ctl;
 pub mod uksmd_ctl_ttrpc;
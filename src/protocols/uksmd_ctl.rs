@@ -171,8 +171,22 @@ pub struct AddRequest {
     // message fields
     // @@protoc_insertion_point(field:MemAgent.AddRequest.pid)
     pub pid: u64,
+    // @@protoc_insertion_point(field:MemAgent.AddRequest.include_vma)
+    pub include_vma: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.AddRequest.exclude_vma)
+    pub exclude_vma: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.AddRequest.follow_children)
+    pub follow_children: bool,
+    // @@protoc_insertion_point(field:MemAgent.AddRequest.align)
+    pub align: bool,
+    // @@protoc_insertion_point(field:MemAgent.AddRequest.force)
+    pub force: bool,
     // message oneof groups
     pub OptAddr: ::std::option::Option<add_request::OptAddr>,
+    pub OptThp: ::std::option::Option<add_request::OptThp>,
+    pub OptMinVmaSize: ::std::option::Option<add_request::OptMinVmaSize>,
+    pub OptPriority: ::std::option::Option<add_request::OptPriority>,
+    pub OptIncludeShmem: ::std::option::Option<add_request::OptIncludeShmem>,
     // special fields
     // @@protoc_insertion_point(special_field:MemAgent.AddRequest.special_fields)
     pub special_fields: ::protobuf::SpecialFields,
@@ -238,9 +252,133 @@ impl AddRequest {
         }
     }
 
+    // string thp = 3;
+
+    pub fn thp(&self) -> &str {
+        match self.OptThp {
+            ::std::option::Option::Some(add_request::OptThp::Thp(ref v)) => v,
+            _ => "",
+        }
+    }
+
+    pub fn clear_thp(&mut self) {
+        self.OptThp = ::std::option::Option::None;
+    }
+
+    pub fn has_thp(&self) -> bool {
+        match self.OptThp {
+            ::std::option::Option::Some(add_request::OptThp::Thp(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_thp(&mut self, v: ::std::string::String) {
+        self.OptThp = ::std::option::Option::Some(add_request::OptThp::Thp(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_thp(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(add_request::OptThp::Thp(_)) = self.OptThp {
+        } else {
+            self.OptThp = ::std::option::Option::Some(add_request::OptThp::Thp(::std::string::String::new()));
+        }
+        match self.OptThp {
+            ::std::option::Option::Some(add_request::OptThp::Thp(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_thp(&mut self) -> ::std::string::String {
+        if self.has_thp() {
+            match self.OptThp.take() {
+                ::std::option::Option::Some(add_request::OptThp::Thp(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
+
+    // uint64 min_vma_size = 6;
+
+    pub fn min_vma_size(&self) -> u64 {
+        match self.OptMinVmaSize {
+            ::std::option::Option::Some(add_request::OptMinVmaSize::MinVmaSize(v)) => v,
+            _ => 0,
+        }
+    }
+
+    pub fn clear_min_vma_size(&mut self) {
+        self.OptMinVmaSize = ::std::option::Option::None;
+    }
+
+    pub fn has_min_vma_size(&self) -> bool {
+        match self.OptMinVmaSize {
+            ::std::option::Option::Some(add_request::OptMinVmaSize::MinVmaSize(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_min_vma_size(&mut self, v: u64) {
+        self.OptMinVmaSize = ::std::option::Option::Some(add_request::OptMinVmaSize::MinVmaSize(v))
+    }
+
+    // int32 priority = 7;
+
+    pub fn priority(&self) -> i32 {
+        match self.OptPriority {
+            ::std::option::Option::Some(add_request::OptPriority::Priority(v)) => v,
+            _ => 0,
+        }
+    }
+
+    pub fn clear_priority(&mut self) {
+        self.OptPriority = ::std::option::Option::None;
+    }
+
+    pub fn has_priority(&self) -> bool {
+        match self.OptPriority {
+            ::std::option::Option::Some(add_request::OptPriority::Priority(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_priority(&mut self, v: i32) {
+        self.OptPriority = ::std::option::Option::Some(add_request::OptPriority::Priority(v))
+    }
+
+    // bool include_shmem = 11;
+
+    pub fn include_shmem(&self) -> bool {
+        match self.OptIncludeShmem {
+            ::std::option::Option::Some(add_request::OptIncludeShmem::IncludeShmem(v)) => v,
+            _ => false,
+        }
+    }
+
+    pub fn clear_include_shmem(&mut self) {
+        self.OptIncludeShmem = ::std::option::Option::None;
+    }
+
+    pub fn has_include_shmem(&self) -> bool {
+        match self.OptIncludeShmem {
+            ::std::option::Option::Some(add_request::OptIncludeShmem::IncludeShmem(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_include_shmem(&mut self, v: bool) {
+        self.OptIncludeShmem = ::std::option::Option::Some(add_request::OptIncludeShmem::IncludeShmem(v))
+    }
+
     fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
-        let mut fields = ::std::vec::Vec::with_capacity(2);
-        let mut oneofs = ::std::vec::Vec::with_capacity(1);
+        let mut fields = ::std::vec::Vec::with_capacity(11);
+        let mut oneofs = ::std::vec::Vec::with_capacity(5);
         fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
             "pid",
             |m: &AddRequest| { &m.pid },
@@ -253,7 +391,60 @@ impl AddRequest {
             AddRequest::mut_addr,
             AddRequest::set_addr,
         ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_deref_has_get_set_simpler_accessor::<_, _>(
+            "thp",
+            AddRequest::has_thp,
+            AddRequest::thp,
+            AddRequest::set_thp,
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "include_vma",
+            |m: &AddRequest| { &m.include_vma },
+            |m: &mut AddRequest| { &mut m.include_vma },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "exclude_vma",
+            |m: &AddRequest| { &m.exclude_vma },
+            |m: &mut AddRequest| { &mut m.exclude_vma },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_copy_has_get_set_simpler_accessors::<_, _>(
+            "min_vma_size",
+            AddRequest::has_min_vma_size,
+            AddRequest::min_vma_size,
+            AddRequest::set_min_vma_size,
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_copy_has_get_set_simpler_accessors::<_, _>(
+            "priority",
+            AddRequest::has_priority,
+            AddRequest::priority,
+            AddRequest::set_priority,
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "follow_children",
+            |m: &AddRequest| { &m.follow_children },
+            |m: &mut AddRequest| { &mut m.follow_children },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "align",
+            |m: &AddRequest| { &m.align },
+            |m: &mut AddRequest| { &mut m.align },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "force",
+            |m: &AddRequest| { &m.force },
+            |m: &mut AddRequest| { &mut m.force },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_copy_has_get_set_simpler_accessors::<_, _>(
+            "include_shmem",
+            AddRequest::has_include_shmem,
+            AddRequest::include_shmem,
+            AddRequest::set_include_shmem,
+        ));
         oneofs.push(add_request::OptAddr::generated_oneof_descriptor_data());
+        oneofs.push(add_request::OptThp::generated_oneof_descriptor_data());
+        oneofs.push(add_request::OptMinVmaSize::generated_oneof_descriptor_data());
+        oneofs.push(add_request::OptPriority::generated_oneof_descriptor_data());
+        oneofs.push(add_request::OptIncludeShmem::generated_oneof_descriptor_data());
         ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<AddRequest>(
             "AddRequest",
             fields,
@@ -278,6 +469,33 @@ impl ::protobuf::Message for AddRequest {
                 18 => {
                     self.OptAddr = ::std::option::Option::Some(add_request::OptAddr::Addr(is.read_message()?));
                 },
+                26 => {
+                    self.OptThp = ::std::option::Option::Some(add_request::OptThp::Thp(is.read_string()?));
+                },
+                34 => {
+                    self.include_vma.push(is.read_string()?);
+                },
+                42 => {
+                    self.exclude_vma.push(is.read_string()?);
+                },
+                48 => {
+                    self.OptMinVmaSize = ::std::option::Option::Some(add_request::OptMinVmaSize::MinVmaSize(is.read_uint64()?));
+                },
+                56 => {
+                    self.OptPriority = ::std::option::Option::Some(add_request::OptPriority::Priority(is.read_int32()?));
+                },
+                64 => {
+                    self.follow_children = is.read_bool()?;
+                },
+                72 => {
+                    self.align = is.read_bool()?;
+                },
+                80 => {
+                    self.force = is.read_bool()?;
+                },
+                88 => {
+                    self.OptIncludeShmem = ::std::option::Option::Some(add_request::OptIncludeShmem::IncludeShmem(is.read_bool()?));
+                },
                 tag => {
                     ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
                 },
@@ -293,6 +511,21 @@ impl ::protobuf::Message for AddRequest {
         if self.pid != 0 {
             my_size += ::protobuf::rt::uint64_size(1, self.pid);
         }
+        for value in &self.include_vma {
+            my_size += ::protobuf::rt::string_size(4, &value);
+        };
+        for value in &self.exclude_vma {
+            my_size += ::protobuf::rt::string_size(5, &value);
+        };
+        if self.follow_children != false {
+            my_size += 1 + 1;
+        }
+        if self.align != false {
+            my_size += 1 + 1;
+        }
+        if self.force != false {
+            my_size += 1 + 1;
+        }
         if let ::std::option::Option::Some(ref v) = self.OptAddr {
             match v {
                 &add_request::OptAddr::Addr(ref v) => {
@@ -301,6 +534,34 @@ impl ::protobuf::Message for AddRequest {
                 },
             };
         }
+        if let ::std::option::Option::Some(ref v) = self.OptThp {
+            match v {
+                &add_request::OptThp::Thp(ref v) => {
+                    my_size += ::protobuf::rt::string_size(3, &v);
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptMinVmaSize {
+            match v {
+                &add_request::OptMinVmaSize::MinVmaSize(v) => {
+                    my_size += ::protobuf::rt::uint64_size(6, v);
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptPriority {
+            match v {
+                &add_request::OptPriority::Priority(v) => {
+                    my_size += ::protobuf::rt::int32_size(7, v);
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptIncludeShmem {
+            match v {
+                &add_request::OptIncludeShmem::IncludeShmem(v) => {
+                    my_size += 1 + 1;
+                },
+            };
+        }
         my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
         self.special_fields.cached_size().set(my_size as u32);
         my_size
@@ -310,6 +571,21 @@ impl ::protobuf::Message for AddRequest {
         if self.pid != 0 {
             os.write_uint64(1, self.pid)?;
         }
+        for v in &self.include_vma {
+            os.write_string(4, &v)?;
+        };
+        for v in &self.exclude_vma {
+            os.write_string(5, &v)?;
+        };
+        if self.follow_children != false {
+            os.write_bool(8, self.follow_children)?;
+        }
+        if self.align != false {
+            os.write_bool(9, self.align)?;
+        }
+        if self.force != false {
+            os.write_bool(10, self.force)?;
+        }
         if let ::std::option::Option::Some(ref v) = self.OptAddr {
             match v {
                 &add_request::OptAddr::Addr(ref v) => {
@@ -317,6 +593,34 @@ impl ::protobuf::Message for AddRequest {
                 },
             };
         }
+        if let ::std::option::Option::Some(ref v) = self.OptThp {
+            match v {
+                &add_request::OptThp::Thp(ref v) => {
+                    os.write_string(3, v)?;
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptMinVmaSize {
+            match v {
+                &add_request::OptMinVmaSize::MinVmaSize(v) => {
+                    os.write_uint64(6, v)?;
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptPriority {
+            match v {
+                &add_request::OptPriority::Priority(v) => {
+                    os.write_int32(7, v)?;
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptIncludeShmem {
+            match v {
+                &add_request::OptIncludeShmem::IncludeShmem(v) => {
+                    os.write_bool(11, v)?;
+                },
+            };
+        }
         os.write_unknown_fields(self.special_fields.unknown_fields())?;
         ::std::result::Result::Ok(())
     }
@@ -336,13 +640,31 @@ impl ::protobuf::Message for AddRequest {
     fn clear(&mut self) {
         self.pid = 0;
         self.OptAddr = ::std::option::Option::None;
+        self.OptThp = ::std::option::Option::None;
+        self.include_vma.clear();
+        self.exclude_vma.clear();
+        self.OptMinVmaSize = ::std::option::Option::None;
+        self.OptPriority = ::std::option::Option::None;
+        self.follow_children = false;
+        self.align = false;
+        self.force = false;
+        self.OptIncludeShmem = ::std::option::Option::None;
         self.special_fields.clear();
     }
 
     fn default_instance() -> &'static AddRequest {
         static instance: AddRequest = AddRequest {
             pid: 0,
+            include_vma: ::std::vec::Vec::new(),
+            exclude_vma: ::std::vec::Vec::new(),
+            follow_children: false,
+            align: false,
+            force: false,
             OptAddr: ::std::option::Option::None,
+            OptThp: ::std::option::Option::None,
+            OptMinVmaSize: ::std::option::Option::None,
+            OptPriority: ::std::option::Option::None,
+            OptIncludeShmem: ::std::option::Option::None,
             special_fields: ::protobuf::SpecialFields::new(),
         };
         &instance
@@ -392,142 +714,6916 @@ pub mod add_request {
             ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptAddr>("OptAddr")
         }
     }
-}
 
-// @@protoc_insertion_point(message:MemAgent.DelRequest)
-#[derive(PartialEq,Clone,Default,Debug)]
-pub struct DelRequest {
-    // message fields
-    // @@protoc_insertion_point(field:MemAgent.DelRequest.pid)
-    pub pid: u64,
-    // special fields
-    // @@protoc_insertion_point(special_field:MemAgent.DelRequest.special_fields)
-    pub special_fields: ::protobuf::SpecialFields,
-}
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.AddRequest.OptThp)
+    pub enum OptThp {
+        // @@protoc_insertion_point(oneof_field:MemAgent.AddRequest.thp)
+        Thp(::std::string::String),
+    }
 
-impl<'a> ::std::default::Default for &'a DelRequest {
-    fn default() -> &'a DelRequest {
-        <DelRequest as ::protobuf::Message>::default_instance()
+    impl ::protobuf::Oneof for OptThp {
     }
-}
 
-impl DelRequest {
-    pub fn new() -> DelRequest {
-        ::std::default::Default::default()
+    impl ::protobuf::OneofFull for OptThp {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::AddRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptThp").unwrap()).clone()
+        }
     }
 
-    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
-        let mut fields = ::std::vec::Vec::with_capacity(1);
-        let mut oneofs = ::std::vec::Vec::with_capacity(0);
-        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
-            "pid",
-            |m: &DelRequest| { &m.pid },
-            |m: &mut DelRequest| { &mut m.pid },
-        ));
-        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<DelRequest>(
-            "DelRequest",
-            fields,
-            oneofs,
-        )
+    impl OptThp {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptThp>("OptThp")
+        }
     }
-}
 
-impl ::protobuf::Message for DelRequest {
-    const NAME: &'static str = "DelRequest";
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.AddRequest.OptMinVmaSize)
+    pub enum OptMinVmaSize {
+        // @@protoc_insertion_point(oneof_field:MemAgent.AddRequest.min_vma_size)
+        MinVmaSize(u64),
+    }
 
-    fn is_initialized(&self) -> bool {
-        true
+    impl ::protobuf::Oneof for OptMinVmaSize {
     }
 
-    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
-        while let Some(tag) = is.read_raw_tag_or_eof()? {
-            match tag {
-                8 => {
-                    self.pid = is.read_uint64()?;
-                },
-                tag => {
-                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
-                },
-            };
+    impl ::protobuf::OneofFull for OptMinVmaSize {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::AddRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptMinVmaSize").unwrap()).clone()
         }
-        ::std::result::Result::Ok(())
     }
 
-    // Compute sizes of nested messages
-    #[allow(unused_variables)]
-    fn compute_size(&self) -> u64 {
-        let mut my_size = 0;
-        if self.pid != 0 {
-            my_size += ::protobuf::rt::uint64_size(1, self.pid);
+    impl OptMinVmaSize {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptMinVmaSize>("OptMinVmaSize")
         }
-        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
-        self.special_fields.cached_size().set(my_size as u32);
-        my_size
     }
 
-    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
-        if self.pid != 0 {
-            os.write_uint64(1, self.pid)?;
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.AddRequest.OptPriority)
+    pub enum OptPriority {
+        // @@protoc_insertion_point(oneof_field:MemAgent.AddRequest.priority)
+        Priority(i32),
+    }
+
+    impl ::protobuf::Oneof for OptPriority {
+    }
+
+    impl ::protobuf::OneofFull for OptPriority {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::AddRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptPriority").unwrap()).clone()
         }
-        os.write_unknown_fields(self.special_fields.unknown_fields())?;
-        ::std::result::Result::Ok(())
     }
 
-    fn special_fields(&self) -> &::protobuf::SpecialFields {
-        &self.special_fields
+    impl OptPriority {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptPriority>("OptPriority")
+        }
     }
 
-    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
-        &mut self.special_fields
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.AddRequest.OptIncludeShmem)
+    pub enum OptIncludeShmem {
+        // @@protoc_insertion_point(oneof_field:MemAgent.AddRequest.include_shmem)
+        IncludeShmem(bool),
     }
 
-    fn new() -> DelRequest {
-        DelRequest::new()
+    impl ::protobuf::Oneof for OptIncludeShmem {
     }
 
-    fn clear(&mut self) {
-        self.pid = 0;
-        self.special_fields.clear();
+    impl ::protobuf::OneofFull for OptIncludeShmem {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::AddRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptIncludeShmem").unwrap()).clone()
+        }
     }
 
-    fn default_instance() -> &'static DelRequest {
-        static instance: DelRequest = DelRequest {
-            pid: 0,
-            special_fields: ::protobuf::SpecialFields::new(),
-        };
-        &instance
+    impl OptIncludeShmem {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptIncludeShmem>("OptIncludeShmem")
+        }
     }
 }
 
-impl ::protobuf::MessageFull for DelRequest {
-    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
-        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
-        descriptor.get(|| file_descriptor().message_by_package_relative_name("DelRequest").unwrap()).clone()
-    }
+// @@protoc_insertion_point(message:MemAgent.AddResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct AddResponse {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.vmas)
+    pub vmas: u64,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.bytes)
+    pub bytes: u64,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.thp)
+    pub thp: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.include_vma)
+    pub include_vma: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.exclude_vma)
+    pub exclude_vma: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.min_vma_size)
+    pub min_vma_size: u64,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.priority)
+    pub priority: i32,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.follow_children)
+    pub follow_children: bool,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.comm)
+    pub comm: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.start_time)
+    pub start_time: u64,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.pid)
+    pub pid: u64,
+    // @@protoc_insertion_point(field:MemAgent.AddResponse.include_shmem)
+    pub include_shmem: bool,
+    // message oneof groups
+    pub OptAddr: ::std::option::Option<add_response::OptAddr>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.AddResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
 }
 
-impl ::std::fmt::Display for DelRequest {
-    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
-        ::protobuf::text_format::fmt(self, f)
+impl<'a> ::std::default::Default for &'a AddResponse {
+    fn default() -> &'a AddResponse {
+        <AddResponse as ::protobuf::Message>::default_instance()
     }
 }
 
-impl ::protobuf::reflect::ProtobufValue for DelRequest {
+impl AddResponse {
+    pub fn new() -> AddResponse {
+        ::std::default::Default::default()
+    }
+
+    // .MemAgent.Addr addr = 1;
+
+    pub fn addr(&self) -> &Addr {
+        match self.OptAddr {
+            ::std::option::Option::Some(add_response::OptAddr::Addr(ref v)) => v,
+            _ => <Addr as ::protobuf::Message>::default_instance(),
+        }
+    }
+
+    pub fn clear_addr(&mut self) {
+        self.OptAddr = ::std::option::Option::None;
+    }
+
+    pub fn has_addr(&self) -> bool {
+        match self.OptAddr {
+            ::std::option::Option::Some(add_response::OptAddr::Addr(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_addr(&mut self, v: Addr) {
+        self.OptAddr = ::std::option::Option::Some(add_response::OptAddr::Addr(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_addr(&mut self) -> &mut Addr {
+        if let ::std::option::Option::Some(add_response::OptAddr::Addr(_)) = self.OptAddr {
+        } else {
+            self.OptAddr = ::std::option::Option::Some(add_response::OptAddr::Addr(Addr::new()));
+        }
+        match self.OptAddr {
+            ::std::option::Option::Some(add_response::OptAddr::Addr(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_addr(&mut self) -> Addr {
+        if self.has_addr() {
+            match self.OptAddr.take() {
+                ::std::option::Option::Some(add_response::OptAddr::Addr(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            Addr::new()
+        }
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(13);
+        let mut oneofs = ::std::vec::Vec::with_capacity(1);
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_message_has_get_mut_set_accessor::<_, Addr>(
+            "addr",
+            AddResponse::has_addr,
+            AddResponse::addr,
+            AddResponse::mut_addr,
+            AddResponse::set_addr,
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "vmas",
+            |m: &AddResponse| { &m.vmas },
+            |m: &mut AddResponse| { &mut m.vmas },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "bytes",
+            |m: &AddResponse| { &m.bytes },
+            |m: &mut AddResponse| { &mut m.bytes },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "thp",
+            |m: &AddResponse| { &m.thp },
+            |m: &mut AddResponse| { &mut m.thp },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "include_vma",
+            |m: &AddResponse| { &m.include_vma },
+            |m: &mut AddResponse| { &mut m.include_vma },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "exclude_vma",
+            |m: &AddResponse| { &m.exclude_vma },
+            |m: &mut AddResponse| { &mut m.exclude_vma },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "min_vma_size",
+            |m: &AddResponse| { &m.min_vma_size },
+            |m: &mut AddResponse| { &mut m.min_vma_size },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "priority",
+            |m: &AddResponse| { &m.priority },
+            |m: &mut AddResponse| { &mut m.priority },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "follow_children",
+            |m: &AddResponse| { &m.follow_children },
+            |m: &mut AddResponse| { &mut m.follow_children },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "comm",
+            |m: &AddResponse| { &m.comm },
+            |m: &mut AddResponse| { &mut m.comm },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "start_time",
+            |m: &AddResponse| { &m.start_time },
+            |m: &mut AddResponse| { &mut m.start_time },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pid",
+            |m: &AddResponse| { &m.pid },
+            |m: &mut AddResponse| { &mut m.pid },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "include_shmem",
+            |m: &AddResponse| { &m.include_shmem },
+            |m: &mut AddResponse| { &mut m.include_shmem },
+        ));
+        oneofs.push(add_response::OptAddr::generated_oneof_descriptor_data());
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<AddResponse>(
+            "AddResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for AddResponse {
+    const NAME: &'static str = "AddResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.OptAddr = ::std::option::Option::Some(add_response::OptAddr::Addr(is.read_message()?));
+                },
+                16 => {
+                    self.vmas = is.read_uint64()?;
+                },
+                24 => {
+                    self.bytes = is.read_uint64()?;
+                },
+                34 => {
+                    self.thp = is.read_string()?;
+                },
+                42 => {
+                    self.include_vma.push(is.read_string()?);
+                },
+                50 => {
+                    self.exclude_vma.push(is.read_string()?);
+                },
+                56 => {
+                    self.min_vma_size = is.read_uint64()?;
+                },
+                64 => {
+                    self.priority = is.read_int32()?;
+                },
+                72 => {
+                    self.follow_children = is.read_bool()?;
+                },
+                82 => {
+                    self.comm = is.read_string()?;
+                },
+                88 => {
+                    self.start_time = is.read_uint64()?;
+                },
+                96 => {
+                    self.pid = is.read_uint64()?;
+                },
+                104 => {
+                    self.include_shmem = is.read_bool()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.vmas != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.vmas);
+        }
+        if self.bytes != 0 {
+            my_size += ::protobuf::rt::uint64_size(3, self.bytes);
+        }
+        if !self.thp.is_empty() {
+            my_size += ::protobuf::rt::string_size(4, &self.thp);
+        }
+        for value in &self.include_vma {
+            my_size += ::protobuf::rt::string_size(5, &value);
+        };
+        for value in &self.exclude_vma {
+            my_size += ::protobuf::rt::string_size(6, &value);
+        };
+        if self.min_vma_size != 0 {
+            my_size += ::protobuf::rt::uint64_size(7, self.min_vma_size);
+        }
+        if self.priority != 0 {
+            my_size += ::protobuf::rt::int32_size(8, self.priority);
+        }
+        if self.follow_children != false {
+            my_size += 1 + 1;
+        }
+        if !self.comm.is_empty() {
+            my_size += ::protobuf::rt::string_size(10, &self.comm);
+        }
+        if self.start_time != 0 {
+            my_size += ::protobuf::rt::uint64_size(11, self.start_time);
+        }
+        if self.pid != 0 {
+            my_size += ::protobuf::rt::uint64_size(12, self.pid);
+        }
+        if self.include_shmem != false {
+            my_size += 1 + 1;
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptAddr {
+            match v {
+                &add_response::OptAddr::Addr(ref v) => {
+                    let len = v.compute_size();
+                    my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+                },
+            };
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.vmas != 0 {
+            os.write_uint64(2, self.vmas)?;
+        }
+        if self.bytes != 0 {
+            os.write_uint64(3, self.bytes)?;
+        }
+        if !self.thp.is_empty() {
+            os.write_string(4, &self.thp)?;
+        }
+        for v in &self.include_vma {
+            os.write_string(5, &v)?;
+        };
+        for v in &self.exclude_vma {
+            os.write_string(6, &v)?;
+        };
+        if self.min_vma_size != 0 {
+            os.write_uint64(7, self.min_vma_size)?;
+        }
+        if self.priority != 0 {
+            os.write_int32(8, self.priority)?;
+        }
+        if self.follow_children != false {
+            os.write_bool(9, self.follow_children)?;
+        }
+        if !self.comm.is_empty() {
+            os.write_string(10, &self.comm)?;
+        }
+        if self.start_time != 0 {
+            os.write_uint64(11, self.start_time)?;
+        }
+        if self.pid != 0 {
+            os.write_uint64(12, self.pid)?;
+        }
+        if self.include_shmem != false {
+            os.write_bool(13, self.include_shmem)?;
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptAddr {
+            match v {
+                &add_response::OptAddr::Addr(ref v) => {
+                    ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+                },
+            };
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> AddResponse {
+        AddResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.OptAddr = ::std::option::Option::None;
+        self.vmas = 0;
+        self.bytes = 0;
+        self.thp.clear();
+        self.include_vma.clear();
+        self.exclude_vma.clear();
+        self.min_vma_size = 0;
+        self.priority = 0;
+        self.follow_children = false;
+        self.comm.clear();
+        self.start_time = 0;
+        self.pid = 0;
+        self.include_shmem = false;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static AddResponse {
+        static instance: AddResponse = AddResponse {
+            vmas: 0,
+            bytes: 0,
+            thp: ::std::string::String::new(),
+            include_vma: ::std::vec::Vec::new(),
+            exclude_vma: ::std::vec::Vec::new(),
+            min_vma_size: 0,
+            priority: 0,
+            follow_children: false,
+            comm: ::std::string::String::new(),
+            start_time: 0,
+            pid: 0,
+            include_shmem: false,
+            OptAddr: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for AddResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("AddResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for AddResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for AddResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+/// Nested message and enums of message `AddResponse`
+pub mod add_response {
+
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.AddResponse.OptAddr)
+    pub enum OptAddr {
+        // @@protoc_insertion_point(oneof_field:MemAgent.AddResponse.addr)
+        Addr(super::Addr),
+    }
+
+    impl ::protobuf::Oneof for OptAddr {
+    }
+
+    impl ::protobuf::OneofFull for OptAddr {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::AddResponse as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptAddr").unwrap()).clone()
+        }
+    }
+
+    impl OptAddr {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptAddr>("OptAddr")
+        }
+    }
+}
+
+// @@protoc_insertion_point(message:MemAgent.AddByContainerRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct AddByContainerRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.AddByContainerRequest.container_id)
+    pub container_id: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.AddByContainerRequest.add)
+    pub add: ::protobuf::MessageField<AddRequest>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.AddByContainerRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a AddByContainerRequest {
+    fn default() -> &'a AddByContainerRequest {
+        <AddByContainerRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl AddByContainerRequest {
+    pub fn new() -> AddByContainerRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "container_id",
+            |m: &AddByContainerRequest| { &m.container_id },
+            |m: &mut AddByContainerRequest| { &mut m.container_id },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_message_field_accessor::<_, AddRequest>(
+            "add",
+            |m: &AddByContainerRequest| { &m.add },
+            |m: &mut AddByContainerRequest| { &mut m.add },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<AddByContainerRequest>(
+            "AddByContainerRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for AddByContainerRequest {
+    const NAME: &'static str = "AddByContainerRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.container_id = is.read_string()?;
+                },
+                18 => {
+                    ::protobuf::rt::read_singular_message_into_field(is, &mut self.add)?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.container_id.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.container_id);
+        }
+        if let Some(v) = self.add.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.container_id.is_empty() {
+            os.write_string(1, &self.container_id)?;
+        }
+        if let Some(v) = self.add.as_ref() {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> AddByContainerRequest {
+        AddByContainerRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.container_id.clear();
+        self.add.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static AddByContainerRequest {
+        static instance: AddByContainerRequest = AddByContainerRequest {
+            container_id: ::std::string::String::new(),
+            add: ::protobuf::MessageField::none(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for AddByContainerRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("AddByContainerRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for AddByContainerRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for AddByContainerRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.MergeRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct MergeRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.MergeRequest.force)
+    pub force: bool,
+    // @@protoc_insertion_point(field:MemAgent.MergeRequest.pid)
+    pub pid: u64,
+    // message oneof groups
+    pub OptAddr: ::std::option::Option<merge_request::OptAddr>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.MergeRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a MergeRequest {
+    fn default() -> &'a MergeRequest {
+        <MergeRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl MergeRequest {
+    pub fn new() -> MergeRequest {
+        ::std::default::Default::default()
+    }
+
+    // .MemAgent.Addr addr = 3;
+
+    pub fn addr(&self) -> &Addr {
+        match self.OptAddr {
+            ::std::option::Option::Some(merge_request::OptAddr::Addr(ref v)) => v,
+            _ => <Addr as ::protobuf::Message>::default_instance(),
+        }
+    }
+
+    pub fn clear_addr(&mut self) {
+        self.OptAddr = ::std::option::Option::None;
+    }
+
+    pub fn has_addr(&self) -> bool {
+        match self.OptAddr {
+            ::std::option::Option::Some(merge_request::OptAddr::Addr(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_addr(&mut self, v: Addr) {
+        self.OptAddr = ::std::option::Option::Some(merge_request::OptAddr::Addr(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_addr(&mut self) -> &mut Addr {
+        if let ::std::option::Option::Some(merge_request::OptAddr::Addr(_)) = self.OptAddr {
+        } else {
+            self.OptAddr = ::std::option::Option::Some(merge_request::OptAddr::Addr(Addr::new()));
+        }
+        match self.OptAddr {
+            ::std::option::Option::Some(merge_request::OptAddr::Addr(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_addr(&mut self) -> Addr {
+        if self.has_addr() {
+            match self.OptAddr.take() {
+                ::std::option::Option::Some(merge_request::OptAddr::Addr(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            Addr::new()
+        }
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(1);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "force",
+            |m: &MergeRequest| { &m.force },
+            |m: &mut MergeRequest| { &mut m.force },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pid",
+            |m: &MergeRequest| { &m.pid },
+            |m: &mut MergeRequest| { &mut m.pid },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_message_has_get_mut_set_accessor::<_, Addr>(
+            "addr",
+            MergeRequest::has_addr,
+            MergeRequest::addr,
+            MergeRequest::mut_addr,
+            MergeRequest::set_addr,
+        ));
+        oneofs.push(merge_request::OptAddr::generated_oneof_descriptor_data());
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<MergeRequest>(
+            "MergeRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for MergeRequest {
+    const NAME: &'static str = "MergeRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.force = is.read_bool()?;
+                },
+                16 => {
+                    self.pid = is.read_uint64()?;
+                },
+                26 => {
+                    self.OptAddr = ::std::option::Option::Some(merge_request::OptAddr::Addr(is.read_message()?));
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.force != false {
+            my_size += 1 + 1;
+        }
+        if self.pid != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.pid);
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptAddr {
+            match v {
+                &merge_request::OptAddr::Addr(ref v) => {
+                    let len = v.compute_size();
+                    my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+                },
+            };
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.force != false {
+            os.write_bool(1, self.force)?;
+        }
+        if self.pid != 0 {
+            os.write_uint64(2, self.pid)?;
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptAddr {
+            match v {
+                &merge_request::OptAddr::Addr(ref v) => {
+                    ::protobuf::rt::write_message_field_with_cached_size(3, v, os)?;
+                },
+            };
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> MergeRequest {
+        MergeRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.force = false;
+        self.pid = 0;
+        self.OptAddr = ::std::option::Option::None;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static MergeRequest {
+        static instance: MergeRequest = MergeRequest {
+            force: false,
+            pid: 0,
+            OptAddr: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for MergeRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("MergeRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for MergeRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for MergeRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+/// Nested message and enums of message `MergeRequest`
+pub mod merge_request {
+
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.MergeRequest.OptAddr)
+    pub enum OptAddr {
+        // @@protoc_insertion_point(oneof_field:MemAgent.MergeRequest.addr)
+        Addr(super::Addr),
+    }
+
+    impl ::protobuf::Oneof for OptAddr {
+    }
+
+    impl ::protobuf::OneofFull for OptAddr {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::MergeRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptAddr").unwrap()).clone()
+        }
+    }
+
+    impl OptAddr {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptAddr>("OptAddr")
+        }
+    }
+}
+
+// @@protoc_insertion_point(message:MemAgent.DelRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct DelRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.DelRequest.pid)
+    pub pid: u64,
+    // @@protoc_insertion_point(field:MemAgent.DelRequest.force)
+    pub force: bool,
+    // @@protoc_insertion_point(field:MemAgent.DelRequest.wait)
+    pub wait: bool,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.DelRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a DelRequest {
+    fn default() -> &'a DelRequest {
+        <DelRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl DelRequest {
+    pub fn new() -> DelRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pid",
+            |m: &DelRequest| { &m.pid },
+            |m: &mut DelRequest| { &mut m.pid },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "force",
+            |m: &DelRequest| { &m.force },
+            |m: &mut DelRequest| { &mut m.force },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "wait",
+            |m: &DelRequest| { &m.wait },
+            |m: &mut DelRequest| { &mut m.wait },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<DelRequest>(
+            "DelRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for DelRequest {
+    const NAME: &'static str = "DelRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.pid = is.read_uint64()?;
+                },
+                16 => {
+                    self.force = is.read_bool()?;
+                },
+                24 => {
+                    self.wait = is.read_bool()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.pid != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.pid);
+        }
+        if self.force != false {
+            my_size += 1 + 1;
+        }
+        if self.wait != false {
+            my_size += 1 + 1;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.pid != 0 {
+            os.write_uint64(1, self.pid)?;
+        }
+        if self.force != false {
+            os.write_bool(2, self.force)?;
+        }
+        if self.wait != false {
+            os.write_bool(3, self.wait)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> DelRequest {
+        DelRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.pid = 0;
+        self.force = false;
+        self.wait = false;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static DelRequest {
+        static instance: DelRequest = DelRequest {
+            pid: 0,
+            force: false,
+            wait: false,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for DelRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("DelRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for DelRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for DelRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.PauseTaskRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct PauseTaskRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.PauseTaskRequest.pid)
+    pub pid: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.PauseTaskRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a PauseTaskRequest {
+    fn default() -> &'a PauseTaskRequest {
+        <PauseTaskRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl PauseTaskRequest {
+    pub fn new() -> PauseTaskRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pid",
+            |m: &PauseTaskRequest| { &m.pid },
+            |m: &mut PauseTaskRequest| { &mut m.pid },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<PauseTaskRequest>(
+            "PauseTaskRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for PauseTaskRequest {
+    const NAME: &'static str = "PauseTaskRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.pid = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.pid != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.pid);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.pid != 0 {
+            os.write_uint64(1, self.pid)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> PauseTaskRequest {
+        PauseTaskRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.pid = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static PauseTaskRequest {
+        static instance: PauseTaskRequest = PauseTaskRequest {
+            pid: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for PauseTaskRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("PauseTaskRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for PauseTaskRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for PauseTaskRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.ResumeTaskRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct ResumeTaskRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.ResumeTaskRequest.pid)
+    pub pid: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.ResumeTaskRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a ResumeTaskRequest {
+    fn default() -> &'a ResumeTaskRequest {
+        <ResumeTaskRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl ResumeTaskRequest {
+    pub fn new() -> ResumeTaskRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pid",
+            |m: &ResumeTaskRequest| { &m.pid },
+            |m: &mut ResumeTaskRequest| { &mut m.pid },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<ResumeTaskRequest>(
+            "ResumeTaskRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for ResumeTaskRequest {
+    const NAME: &'static str = "ResumeTaskRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.pid = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.pid != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.pid);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.pid != 0 {
+            os.write_uint64(1, self.pid)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> ResumeTaskRequest {
+        ResumeTaskRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.pid = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static ResumeTaskRequest {
+        static instance: ResumeTaskRequest = ResumeTaskRequest {
+            pid: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for ResumeTaskRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("ResumeTaskRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for ResumeTaskRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for ResumeTaskRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.UpdateRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct UpdateRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.UpdateRequest.pid)
+    pub pid: u64,
+    // message oneof groups
+    pub OptPriority: ::std::option::Option<update_request::OptPriority>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.UpdateRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UpdateRequest {
+    fn default() -> &'a UpdateRequest {
+        <UpdateRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UpdateRequest {
+    pub fn new() -> UpdateRequest {
+        ::std::default::Default::default()
+    }
+
+    // int32 priority = 2;
+
+    pub fn priority(&self) -> i32 {
+        match self.OptPriority {
+            ::std::option::Option::Some(update_request::OptPriority::Priority(v)) => v,
+            _ => 0,
+        }
+    }
+
+    pub fn clear_priority(&mut self) {
+        self.OptPriority = ::std::option::Option::None;
+    }
+
+    pub fn has_priority(&self) -> bool {
+        match self.OptPriority {
+            ::std::option::Option::Some(update_request::OptPriority::Priority(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_priority(&mut self, v: i32) {
+        self.OptPriority = ::std::option::Option::Some(update_request::OptPriority::Priority(v))
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(1);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pid",
+            |m: &UpdateRequest| { &m.pid },
+            |m: &mut UpdateRequest| { &mut m.pid },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_copy_has_get_set_simpler_accessors::<_, _>(
+            "priority",
+            UpdateRequest::has_priority,
+            UpdateRequest::priority,
+            UpdateRequest::set_priority,
+        ));
+        oneofs.push(update_request::OptPriority::generated_oneof_descriptor_data());
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UpdateRequest>(
+            "UpdateRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UpdateRequest {
+    const NAME: &'static str = "UpdateRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.pid = is.read_uint64()?;
+                },
+                16 => {
+                    self.OptPriority = ::std::option::Option::Some(update_request::OptPriority::Priority(is.read_int32()?));
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.pid != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.pid);
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptPriority {
+            match v {
+                &update_request::OptPriority::Priority(v) => {
+                    my_size += ::protobuf::rt::int32_size(2, v);
+                },
+            };
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.pid != 0 {
+            os.write_uint64(1, self.pid)?;
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptPriority {
+            match v {
+                &update_request::OptPriority::Priority(v) => {
+                    os.write_int32(2, v)?;
+                },
+            };
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UpdateRequest {
+        UpdateRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.pid = 0;
+        self.OptPriority = ::std::option::Option::None;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UpdateRequest {
+        static instance: UpdateRequest = UpdateRequest {
+            pid: 0,
+            OptPriority: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UpdateRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UpdateRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UpdateRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UpdateRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+/// Nested message and enums of message `UpdateRequest`
+pub mod update_request {
+
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.UpdateRequest.OptPriority)
+    pub enum OptPriority {
+        // @@protoc_insertion_point(oneof_field:MemAgent.UpdateRequest.priority)
+        Priority(i32),
+    }
+
+    impl ::protobuf::Oneof for OptPriority {
+    }
+
+    impl ::protobuf::OneofFull for OptPriority {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::UpdateRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptPriority").unwrap()).clone()
+        }
+    }
+
+    impl OptPriority {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptPriority>("OptPriority")
+        }
+    }
+}
+
+// @@protoc_insertion_point(message:MemAgent.UnmergeRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct UnmergeRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.UnmergeRequest.budget)
+    pub budget: u64,
+    // @@protoc_insertion_point(field:MemAgent.UnmergeRequest.pid)
+    pub pid: u64,
+    // message oneof groups
+    pub OptAddr: ::std::option::Option<unmerge_request::OptAddr>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.UnmergeRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UnmergeRequest {
+    fn default() -> &'a UnmergeRequest {
+        <UnmergeRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UnmergeRequest {
+    pub fn new() -> UnmergeRequest {
+        ::std::default::Default::default()
+    }
+
+    // .MemAgent.Addr addr = 3;
+
+    pub fn addr(&self) -> &Addr {
+        match self.OptAddr {
+            ::std::option::Option::Some(unmerge_request::OptAddr::Addr(ref v)) => v,
+            _ => <Addr as ::protobuf::Message>::default_instance(),
+        }
+    }
+
+    pub fn clear_addr(&mut self) {
+        self.OptAddr = ::std::option::Option::None;
+    }
+
+    pub fn has_addr(&self) -> bool {
+        match self.OptAddr {
+            ::std::option::Option::Some(unmerge_request::OptAddr::Addr(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_addr(&mut self, v: Addr) {
+        self.OptAddr = ::std::option::Option::Some(unmerge_request::OptAddr::Addr(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_addr(&mut self) -> &mut Addr {
+        if let ::std::option::Option::Some(unmerge_request::OptAddr::Addr(_)) = self.OptAddr {
+        } else {
+            self.OptAddr = ::std::option::Option::Some(unmerge_request::OptAddr::Addr(Addr::new()));
+        }
+        match self.OptAddr {
+            ::std::option::Option::Some(unmerge_request::OptAddr::Addr(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_addr(&mut self) -> Addr {
+        if self.has_addr() {
+            match self.OptAddr.take() {
+                ::std::option::Option::Some(unmerge_request::OptAddr::Addr(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            Addr::new()
+        }
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(1);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "budget",
+            |m: &UnmergeRequest| { &m.budget },
+            |m: &mut UnmergeRequest| { &mut m.budget },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pid",
+            |m: &UnmergeRequest| { &m.pid },
+            |m: &mut UnmergeRequest| { &mut m.pid },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_message_has_get_mut_set_accessor::<_, Addr>(
+            "addr",
+            UnmergeRequest::has_addr,
+            UnmergeRequest::addr,
+            UnmergeRequest::mut_addr,
+            UnmergeRequest::set_addr,
+        ));
+        oneofs.push(unmerge_request::OptAddr::generated_oneof_descriptor_data());
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UnmergeRequest>(
+            "UnmergeRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UnmergeRequest {
+    const NAME: &'static str = "UnmergeRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.budget = is.read_uint64()?;
+                },
+                16 => {
+                    self.pid = is.read_uint64()?;
+                },
+                26 => {
+                    self.OptAddr = ::std::option::Option::Some(unmerge_request::OptAddr::Addr(is.read_message()?));
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.budget != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.budget);
+        }
+        if self.pid != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.pid);
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptAddr {
+            match v {
+                &unmerge_request::OptAddr::Addr(ref v) => {
+                    let len = v.compute_size();
+                    my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+                },
+            };
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.budget != 0 {
+            os.write_uint64(1, self.budget)?;
+        }
+        if self.pid != 0 {
+            os.write_uint64(2, self.pid)?;
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptAddr {
+            match v {
+                &unmerge_request::OptAddr::Addr(ref v) => {
+                    ::protobuf::rt::write_message_field_with_cached_size(3, v, os)?;
+                },
+            };
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UnmergeRequest {
+        UnmergeRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.budget = 0;
+        self.pid = 0;
+        self.OptAddr = ::std::option::Option::None;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UnmergeRequest {
+        static instance: UnmergeRequest = UnmergeRequest {
+            budget: 0,
+            pid: 0,
+            OptAddr: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UnmergeRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UnmergeRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UnmergeRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UnmergeRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+/// Nested message and enums of message `UnmergeRequest`
+pub mod unmerge_request {
+
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.UnmergeRequest.OptAddr)
+    pub enum OptAddr {
+        // @@protoc_insertion_point(oneof_field:MemAgent.UnmergeRequest.addr)
+        Addr(super::Addr),
+    }
+
+    impl ::protobuf::Oneof for OptAddr {
+    }
+
+    impl ::protobuf::OneofFull for OptAddr {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::UnmergeRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptAddr").unwrap()).clone()
+        }
+    }
+
+    impl OptAddr {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptAddr>("OptAddr")
+        }
+    }
+}
+
+// @@protoc_insertion_point(message:MemAgent.StatusResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct StatusResponse {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.worker_nice)
+    pub worker_nice: i32,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.worker_sched_idle)
+    pub worker_sched_idle: bool,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.worker_ioprio_idle)
+    pub worker_ioprio_idle: bool,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.skipped_pages)
+    pub skipped_pages: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.merge_batched)
+    pub merge_batched: bool,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.backend)
+    pub backend: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.kernel_caps)
+    pub kernel_caps: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.kernel_version)
+    pub kernel_version: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.zero_pages)
+    pub zero_pages: ::std::collections::HashMap<u64, u64>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.thp_pages)
+    pub thp_pages: ::std::collections::HashMap<u64, u64>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.comm)
+    pub comm: ::std::collections::HashMap<u64, ::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.cmdline)
+    pub cmdline: ::std::collections::HashMap<u64, ::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.paused_pids)
+    pub paused_pids: ::std::vec::Vec<u64>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.priority)
+    pub priority: ::std::collections::HashMap<u64, i32>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.cross_uid_blocked)
+    pub cross_uid_blocked: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.parent)
+    pub parent: ::std::collections::HashMap<u64, u64>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.merge_window_open)
+    pub merge_window_open: bool,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.next_merge_window)
+    pub next_merge_window: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.merge_deferred)
+    pub merge_deferred: bool,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.load_throttle)
+    pub load_throttle: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.watch_cgroup)
+    pub watch_cgroup: ::std::collections::HashMap<u64, ::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.labels)
+    pub labels: ::std::collections::HashMap<u64, ::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.watched_cgroups)
+    pub watched_cgroups: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.discovered)
+    pub discovered: ::std::vec::Vec<u64>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.cmd_queue_depth)
+    pub cmd_queue_depth: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.cmd_queue_oldest_ms)
+    pub cmd_queue_oldest_ms: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.task_state)
+    pub task_state: ::std::collections::HashMap<u64, ::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.kernel_lost)
+    pub kernel_lost: bool,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.merge_remaining)
+    pub merge_remaining: ::std::collections::HashMap<u64, u64>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.last_merge_drained)
+    pub last_merge_drained: bool,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.last_merge_drain_us)
+    pub last_merge_drain_us: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.stale_pages)
+    pub stale_pages: ::std::collections::HashMap<u64, u64>,
+    // @@protoc_insertion_point(field:MemAgent.StatusResponse.foreign_ksm_pages)
+    pub foreign_ksm_pages: ::std::collections::HashMap<u64, u64>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.StatusResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a StatusResponse {
+    fn default() -> &'a StatusResponse {
+        <StatusResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl StatusResponse {
+    pub fn new() -> StatusResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(33);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "worker_nice",
+            |m: &StatusResponse| { &m.worker_nice },
+            |m: &mut StatusResponse| { &mut m.worker_nice },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "worker_sched_idle",
+            |m: &StatusResponse| { &m.worker_sched_idle },
+            |m: &mut StatusResponse| { &mut m.worker_sched_idle },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "worker_ioprio_idle",
+            |m: &StatusResponse| { &m.worker_ioprio_idle },
+            |m: &mut StatusResponse| { &mut m.worker_ioprio_idle },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "skipped_pages",
+            |m: &StatusResponse| { &m.skipped_pages },
+            |m: &mut StatusResponse| { &mut m.skipped_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_batched",
+            |m: &StatusResponse| { &m.merge_batched },
+            |m: &mut StatusResponse| { &mut m.merge_batched },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "backend",
+            |m: &StatusResponse| { &m.backend },
+            |m: &mut StatusResponse| { &mut m.backend },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "kernel_caps",
+            |m: &StatusResponse| { &m.kernel_caps },
+            |m: &mut StatusResponse| { &mut m.kernel_caps },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "kernel_version",
+            |m: &StatusResponse| { &m.kernel_version },
+            |m: &mut StatusResponse| { &mut m.kernel_version },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "zero_pages",
+            |m: &StatusResponse| { &m.zero_pages },
+            |m: &mut StatusResponse| { &mut m.zero_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "thp_pages",
+            |m: &StatusResponse| { &m.thp_pages },
+            |m: &mut StatusResponse| { &mut m.thp_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "comm",
+            |m: &StatusResponse| { &m.comm },
+            |m: &mut StatusResponse| { &mut m.comm },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "cmdline",
+            |m: &StatusResponse| { &m.cmdline },
+            |m: &mut StatusResponse| { &mut m.cmdline },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "paused_pids",
+            |m: &StatusResponse| { &m.paused_pids },
+            |m: &mut StatusResponse| { &mut m.paused_pids },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "priority",
+            |m: &StatusResponse| { &m.priority },
+            |m: &mut StatusResponse| { &mut m.priority },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "cross_uid_blocked",
+            |m: &StatusResponse| { &m.cross_uid_blocked },
+            |m: &mut StatusResponse| { &mut m.cross_uid_blocked },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "parent",
+            |m: &StatusResponse| { &m.parent },
+            |m: &mut StatusResponse| { &mut m.parent },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_window_open",
+            |m: &StatusResponse| { &m.merge_window_open },
+            |m: &mut StatusResponse| { &mut m.merge_window_open },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "next_merge_window",
+            |m: &StatusResponse| { &m.next_merge_window },
+            |m: &mut StatusResponse| { &mut m.next_merge_window },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_deferred",
+            |m: &StatusResponse| { &m.merge_deferred },
+            |m: &mut StatusResponse| { &mut m.merge_deferred },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "load_throttle",
+            |m: &StatusResponse| { &m.load_throttle },
+            |m: &mut StatusResponse| { &mut m.load_throttle },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "watch_cgroup",
+            |m: &StatusResponse| { &m.watch_cgroup },
+            |m: &mut StatusResponse| { &mut m.watch_cgroup },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "labels",
+            |m: &StatusResponse| { &m.labels },
+            |m: &mut StatusResponse| { &mut m.labels },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "watched_cgroups",
+            |m: &StatusResponse| { &m.watched_cgroups },
+            |m: &mut StatusResponse| { &mut m.watched_cgroups },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "discovered",
+            |m: &StatusResponse| { &m.discovered },
+            |m: &mut StatusResponse| { &mut m.discovered },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "cmd_queue_depth",
+            |m: &StatusResponse| { &m.cmd_queue_depth },
+            |m: &mut StatusResponse| { &mut m.cmd_queue_depth },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "cmd_queue_oldest_ms",
+            |m: &StatusResponse| { &m.cmd_queue_oldest_ms },
+            |m: &mut StatusResponse| { &mut m.cmd_queue_oldest_ms },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "task_state",
+            |m: &StatusResponse| { &m.task_state },
+            |m: &mut StatusResponse| { &mut m.task_state },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "kernel_lost",
+            |m: &StatusResponse| { &m.kernel_lost },
+            |m: &mut StatusResponse| { &mut m.kernel_lost },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "merge_remaining",
+            |m: &StatusResponse| { &m.merge_remaining },
+            |m: &mut StatusResponse| { &mut m.merge_remaining },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "last_merge_drained",
+            |m: &StatusResponse| { &m.last_merge_drained },
+            |m: &mut StatusResponse| { &mut m.last_merge_drained },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "last_merge_drain_us",
+            |m: &StatusResponse| { &m.last_merge_drain_us },
+            |m: &mut StatusResponse| { &mut m.last_merge_drain_us },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "stale_pages",
+            |m: &StatusResponse| { &m.stale_pages },
+            |m: &mut StatusResponse| { &mut m.stale_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "foreign_ksm_pages",
+            |m: &StatusResponse| { &m.foreign_ksm_pages },
+            |m: &mut StatusResponse| { &mut m.foreign_ksm_pages },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<StatusResponse>(
+            "StatusResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for StatusResponse {
+    const NAME: &'static str = "StatusResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.worker_nice = is.read_int32()?;
+                },
+                16 => {
+                    self.worker_sched_idle = is.read_bool()?;
+                },
+                24 => {
+                    self.worker_ioprio_idle = is.read_bool()?;
+                },
+                32 => {
+                    self.skipped_pages = is.read_uint64()?;
+                },
+                40 => {
+                    self.merge_batched = is.read_bool()?;
+                },
+                50 => {
+                    self.backend = is.read_string()?;
+                },
+                58 => {
+                    self.kernel_caps.push(is.read_string()?);
+                },
+                66 => {
+                    self.kernel_version = is.read_string()?;
+                },
+                74 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            16 => value = is.read_uint64()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.zero_pages.insert(key, value);
+                },
+                82 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            16 => value = is.read_uint64()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.thp_pages.insert(key, value);
+                },
+                90 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            18 => value = is.read_string()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.comm.insert(key, value);
+                },
+                98 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            18 => value = is.read_string()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.cmdline.insert(key, value);
+                },
+                106 => {
+                    is.read_repeated_packed_uint64_into(&mut self.paused_pids)?;
+                },
+                104 => {
+                    self.paused_pids.push(is.read_uint64()?);
+                },
+                114 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            16 => value = is.read_int32()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.priority.insert(key, value);
+                },
+                120 => {
+                    self.cross_uid_blocked = is.read_uint64()?;
+                },
+                130 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            16 => value = is.read_uint64()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.parent.insert(key, value);
+                },
+                136 => {
+                    self.merge_window_open = is.read_bool()?;
+                },
+                146 => {
+                    self.next_merge_window = is.read_string()?;
+                },
+                152 => {
+                    self.merge_deferred = is.read_bool()?;
+                },
+                162 => {
+                    self.load_throttle = is.read_string()?;
+                },
+                170 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            18 => value = is.read_string()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.watch_cgroup.insert(key, value);
+                },
+                178 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            18 => value = is.read_string()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.labels.insert(key, value);
+                },
+                186 => {
+                    self.watched_cgroups.push(is.read_string()?);
+                },
+                194 => {
+                    is.read_repeated_packed_uint64_into(&mut self.discovered)?;
+                },
+                192 => {
+                    self.discovered.push(is.read_uint64()?);
+                },
+                200 => {
+                    self.cmd_queue_depth = is.read_uint64()?;
+                },
+                208 => {
+                    self.cmd_queue_oldest_ms = is.read_uint64()?;
+                },
+                218 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            18 => value = is.read_string()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.task_state.insert(key, value);
+                },
+                224 => {
+                    self.kernel_lost = is.read_bool()?;
+                },
+                234 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            16 => value = is.read_uint64()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.merge_remaining.insert(key, value);
+                },
+                240 => {
+                    self.last_merge_drained = is.read_bool()?;
+                },
+                248 => {
+                    self.last_merge_drain_us = is.read_uint64()?;
+                },
+                258 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            16 => value = is.read_uint64()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.stale_pages.insert(key, value);
+                },
+                266 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            8 => key = is.read_uint64()?,
+                            16 => value = is.read_uint64()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.foreign_ksm_pages.insert(key, value);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.worker_nice != 0 {
+            my_size += ::protobuf::rt::int32_size(1, self.worker_nice);
+        }
+        if self.worker_sched_idle != false {
+            my_size += 1 + 1;
+        }
+        if self.worker_ioprio_idle != false {
+            my_size += 1 + 1;
+        }
+        if self.skipped_pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(4, self.skipped_pages);
+        }
+        if self.merge_batched != false {
+            my_size += 1 + 1;
+        }
+        if !self.backend.is_empty() {
+            my_size += ::protobuf::rt::string_size(6, &self.backend);
+        }
+        for value in &self.kernel_caps {
+            my_size += ::protobuf::rt::string_size(7, &value);
+        };
+        if !self.kernel_version.is_empty() {
+            my_size += ::protobuf::rt::string_size(8, &self.kernel_version);
+        }
+        for (k, v) in &self.zero_pages {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        for (k, v) in &self.thp_pages {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        for (k, v) in &self.comm {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        for (k, v) in &self.cmdline {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        for value in &self.paused_pids {
+            my_size += ::protobuf::rt::uint64_size(13, *value);
+        };
+        for (k, v) in &self.priority {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::int32_size(2, *v);
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        if self.cross_uid_blocked != 0 {
+            my_size += ::protobuf::rt::uint64_size(15, self.cross_uid_blocked);
+        }
+        for (k, v) in &self.parent {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            my_size += 2 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        if self.merge_window_open != false {
+            my_size += 2 + 1;
+        }
+        if !self.next_merge_window.is_empty() {
+            my_size += ::protobuf::rt::string_size(18, &self.next_merge_window);
+        }
+        if self.merge_deferred != false {
+            my_size += 2 + 1;
+        }
+        if !self.load_throttle.is_empty() {
+            my_size += ::protobuf::rt::string_size(20, &self.load_throttle);
+        }
+        for (k, v) in &self.watch_cgroup {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            my_size += 2 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        for (k, v) in &self.labels {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            my_size += 2 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        for value in &self.watched_cgroups {
+            my_size += ::protobuf::rt::string_size(23, &value);
+        };
+        for value in &self.discovered {
+            my_size += ::protobuf::rt::uint64_size(24, *value);
+        };
+        if self.cmd_queue_depth != 0 {
+            my_size += ::protobuf::rt::uint64_size(25, self.cmd_queue_depth);
+        }
+        if self.cmd_queue_oldest_ms != 0 {
+            my_size += ::protobuf::rt::uint64_size(26, self.cmd_queue_oldest_ms);
+        }
+        for (k, v) in &self.task_state {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            my_size += 2 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        if self.kernel_lost != false {
+            my_size += 2 + 1;
+        }
+        for (k, v) in &self.merge_remaining {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            my_size += 2 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        if self.last_merge_drained != false {
+            my_size += 2 + 1;
+        }
+        if self.last_merge_drain_us != 0 {
+            my_size += ::protobuf::rt::uint64_size(31, self.last_merge_drain_us);
+        }
+        for (k, v) in &self.stale_pages {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            my_size += 2 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        for (k, v) in &self.foreign_ksm_pages {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            my_size += 2 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.worker_nice != 0 {
+            os.write_int32(1, self.worker_nice)?;
+        }
+        if self.worker_sched_idle != false {
+            os.write_bool(2, self.worker_sched_idle)?;
+        }
+        if self.worker_ioprio_idle != false {
+            os.write_bool(3, self.worker_ioprio_idle)?;
+        }
+        if self.skipped_pages != 0 {
+            os.write_uint64(4, self.skipped_pages)?;
+        }
+        if self.merge_batched != false {
+            os.write_bool(5, self.merge_batched)?;
+        }
+        if !self.backend.is_empty() {
+            os.write_string(6, &self.backend)?;
+        }
+        for v in &self.kernel_caps {
+            os.write_string(7, &v)?;
+        };
+        if !self.kernel_version.is_empty() {
+            os.write_string(8, &self.kernel_version)?;
+        }
+        for (k, v) in &self.zero_pages {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            os.write_raw_varint32(74)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_uint64(2, *v)?;
+        };
+        for (k, v) in &self.thp_pages {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            os.write_raw_varint32(82)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_uint64(2, *v)?;
+        };
+        for (k, v) in &self.comm {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            os.write_raw_varint32(90)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_string(2, &v)?;
+        };
+        for (k, v) in &self.cmdline {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            os.write_raw_varint32(98)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_string(2, &v)?;
+        };
+        for v in &self.paused_pids {
+            os.write_uint64(13, *v)?;
+        };
+        for (k, v) in &self.priority {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::int32_size(2, *v);
+            os.write_raw_varint32(114)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_int32(2, *v)?;
+        };
+        if self.cross_uid_blocked != 0 {
+            os.write_uint64(15, self.cross_uid_blocked)?;
+        }
+        for (k, v) in &self.parent {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            os.write_raw_varint32(130)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_uint64(2, *v)?;
+        };
+        if self.merge_window_open != false {
+            os.write_bool(17, self.merge_window_open)?;
+        }
+        if !self.next_merge_window.is_empty() {
+            os.write_string(18, &self.next_merge_window)?;
+        }
+        if self.merge_deferred != false {
+            os.write_bool(19, self.merge_deferred)?;
+        }
+        if !self.load_throttle.is_empty() {
+            os.write_string(20, &self.load_throttle)?;
+        }
+        for (k, v) in &self.watch_cgroup {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            os.write_raw_varint32(170)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_string(2, &v)?;
+        };
+        for (k, v) in &self.labels {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            os.write_raw_varint32(178)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_string(2, &v)?;
+        };
+        for v in &self.watched_cgroups {
+            os.write_string(23, &v)?;
+        };
+        for v in &self.discovered {
+            os.write_uint64(24, *v)?;
+        };
+        if self.cmd_queue_depth != 0 {
+            os.write_uint64(25, self.cmd_queue_depth)?;
+        }
+        if self.cmd_queue_oldest_ms != 0 {
+            os.write_uint64(26, self.cmd_queue_oldest_ms)?;
+        }
+        for (k, v) in &self.task_state {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::string_size(2, &v);
+            os.write_raw_varint32(218)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_string(2, &v)?;
+        };
+        if self.kernel_lost != false {
+            os.write_bool(28, self.kernel_lost)?;
+        }
+        for (k, v) in &self.merge_remaining {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            os.write_raw_varint32(234)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_uint64(2, *v)?;
+        };
+        if self.last_merge_drained != false {
+            os.write_bool(30, self.last_merge_drained)?;
+        }
+        if self.last_merge_drain_us != 0 {
+            os.write_uint64(31, self.last_merge_drain_us)?;
+        }
+        for (k, v) in &self.stale_pages {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            os.write_raw_varint32(258)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_uint64(2, *v)?;
+        };
+        for (k, v) in &self.foreign_ksm_pages {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::uint64_size(1, *k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            os.write_raw_varint32(266)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_uint64(1, *k)?;
+            os.write_uint64(2, *v)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> StatusResponse {
+        StatusResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.worker_nice = 0;
+        self.worker_sched_idle = false;
+        self.worker_ioprio_idle = false;
+        self.skipped_pages = 0;
+        self.merge_batched = false;
+        self.backend.clear();
+        self.kernel_caps.clear();
+        self.kernel_version.clear();
+        self.zero_pages.clear();
+        self.thp_pages.clear();
+        self.comm.clear();
+        self.cmdline.clear();
+        self.paused_pids.clear();
+        self.priority.clear();
+        self.cross_uid_blocked = 0;
+        self.parent.clear();
+        self.merge_window_open = false;
+        self.next_merge_window.clear();
+        self.merge_deferred = false;
+        self.load_throttle.clear();
+        self.watch_cgroup.clear();
+        self.labels.clear();
+        self.watched_cgroups.clear();
+        self.discovered.clear();
+        self.cmd_queue_depth = 0;
+        self.cmd_queue_oldest_ms = 0;
+        self.task_state.clear();
+        self.kernel_lost = false;
+        self.merge_remaining.clear();
+        self.last_merge_drained = false;
+        self.last_merge_drain_us = 0;
+        self.stale_pages.clear();
+        self.foreign_ksm_pages.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static StatusResponse {
+        static instance: ::protobuf::rt::Lazy<StatusResponse> = ::protobuf::rt::Lazy::new();
+        instance.get(StatusResponse::new)
+    }
+}
+
+impl ::protobuf::MessageFull for StatusResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("StatusResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for StatusResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for StatusResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.StatsResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct StatsResponse {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.refresh_pages_per_sec_limit)
+    pub refresh_pages_per_sec_limit: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.refresh_pages_per_sec)
+    pub refresh_pages_per_sec: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.merge_pages_per_sec_limit)
+    pub merge_pages_per_sec_limit: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.merge_pages_per_sec)
+    pub merge_pages_per_sec: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.tasks)
+    pub tasks: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.max_tasks)
+    pub max_tasks: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.tracked_pages)
+    pub tracked_pages: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.max_tracked_pages)
+    pub max_tracked_pages: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.merge_give_ups)
+    pub merge_give_ups: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.kernel_retries)
+    pub kernel_retries: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.merge_deferred)
+    pub merge_deferred: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.kernel_stats)
+    pub kernel_stats: ::std::collections::HashMap<::std::string::String, u64>,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.verify_broken_pages)
+    pub verify_broken_pages: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.min_dup_count)
+    pub min_dup_count: u32,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.merge_dup_skipped_pages)
+    pub merge_dup_skipped_pages: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.zero_pages)
+    pub zero_pages: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.merge_scope)
+    pub merge_scope: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.merge_scope_skipped)
+    pub merge_scope_skipped: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.merged_pages)
+    pub merged_pages: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.unmerged_pages)
+    pub unmerged_pages: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.failed_tasks)
+    pub failed_tasks: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.memory_pressure)
+    pub memory_pressure: f64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.pressure_merging)
+    pub pressure_merging: bool,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.mem_available)
+    pub mem_available: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.free_mem_merging)
+    pub free_mem_merging: bool,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.unmerged_by_request)
+    pub unmerged_by_request: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.unmerged_by_policy)
+    pub unmerged_by_policy: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.cpu_pressure)
+    pub cpu_pressure: f64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.op_timings)
+    pub op_timings: ::std::vec::Vec<OpTiming>,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.stale_pages)
+    pub stale_pages: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.merge_shared_frames)
+    pub merge_shared_frames: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.rss_bytes)
+    pub rss_bytes: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.peak_rss_bytes)
+    pub peak_rss_bytes: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.internal_bytes)
+    pub internal_bytes: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.worker_cpu_us)
+    pub worker_cpu_us: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.saved_bytes)
+    pub saved_bytes: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.saved_rss_ratio)
+    pub saved_rss_ratio: f64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.last_merge_crc_matched)
+    pub last_merge_crc_matched: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.last_merge_first_probe)
+    pub last_merge_first_probe: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.last_merge_multi_probe)
+    pub last_merge_multi_probe: u64,
+    // @@protoc_insertion_point(field:MemAgent.StatsResponse.last_merge_crc_collisions)
+    pub last_merge_crc_collisions: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.StatsResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a StatsResponse {
+    fn default() -> &'a StatsResponse {
+        <StatsResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl StatsResponse {
+    pub fn new() -> StatsResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(41);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "refresh_pages_per_sec_limit",
+            |m: &StatsResponse| { &m.refresh_pages_per_sec_limit },
+            |m: &mut StatsResponse| { &mut m.refresh_pages_per_sec_limit },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "refresh_pages_per_sec",
+            |m: &StatsResponse| { &m.refresh_pages_per_sec },
+            |m: &mut StatsResponse| { &mut m.refresh_pages_per_sec },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_pages_per_sec_limit",
+            |m: &StatsResponse| { &m.merge_pages_per_sec_limit },
+            |m: &mut StatsResponse| { &mut m.merge_pages_per_sec_limit },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_pages_per_sec",
+            |m: &StatsResponse| { &m.merge_pages_per_sec },
+            |m: &mut StatsResponse| { &mut m.merge_pages_per_sec },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "tasks",
+            |m: &StatsResponse| { &m.tasks },
+            |m: &mut StatsResponse| { &mut m.tasks },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "max_tasks",
+            |m: &StatsResponse| { &m.max_tasks },
+            |m: &mut StatsResponse| { &mut m.max_tasks },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "tracked_pages",
+            |m: &StatsResponse| { &m.tracked_pages },
+            |m: &mut StatsResponse| { &mut m.tracked_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "max_tracked_pages",
+            |m: &StatsResponse| { &m.max_tracked_pages },
+            |m: &mut StatsResponse| { &mut m.max_tracked_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_give_ups",
+            |m: &StatsResponse| { &m.merge_give_ups },
+            |m: &mut StatsResponse| { &mut m.merge_give_ups },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "kernel_retries",
+            |m: &StatsResponse| { &m.kernel_retries },
+            |m: &mut StatsResponse| { &mut m.kernel_retries },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_deferred",
+            |m: &StatsResponse| { &m.merge_deferred },
+            |m: &mut StatsResponse| { &mut m.merge_deferred },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_map_simpler_accessor::<_, _, _>(
+            "kernel_stats",
+            |m: &StatsResponse| { &m.kernel_stats },
+            |m: &mut StatsResponse| { &mut m.kernel_stats },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "verify_broken_pages",
+            |m: &StatsResponse| { &m.verify_broken_pages },
+            |m: &mut StatsResponse| { &mut m.verify_broken_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "min_dup_count",
+            |m: &StatsResponse| { &m.min_dup_count },
+            |m: &mut StatsResponse| { &mut m.min_dup_count },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_dup_skipped_pages",
+            |m: &StatsResponse| { &m.merge_dup_skipped_pages },
+            |m: &mut StatsResponse| { &mut m.merge_dup_skipped_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "zero_pages",
+            |m: &StatsResponse| { &m.zero_pages },
+            |m: &mut StatsResponse| { &mut m.zero_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_scope",
+            |m: &StatsResponse| { &m.merge_scope },
+            |m: &mut StatsResponse| { &mut m.merge_scope },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_scope_skipped",
+            |m: &StatsResponse| { &m.merge_scope_skipped },
+            |m: &mut StatsResponse| { &mut m.merge_scope_skipped },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merged_pages",
+            |m: &StatsResponse| { &m.merged_pages },
+            |m: &mut StatsResponse| { &mut m.merged_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "unmerged_pages",
+            |m: &StatsResponse| { &m.unmerged_pages },
+            |m: &mut StatsResponse| { &mut m.unmerged_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "failed_tasks",
+            |m: &StatsResponse| { &m.failed_tasks },
+            |m: &mut StatsResponse| { &mut m.failed_tasks },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "memory_pressure",
+            |m: &StatsResponse| { &m.memory_pressure },
+            |m: &mut StatsResponse| { &mut m.memory_pressure },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pressure_merging",
+            |m: &StatsResponse| { &m.pressure_merging },
+            |m: &mut StatsResponse| { &mut m.pressure_merging },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "mem_available",
+            |m: &StatsResponse| { &m.mem_available },
+            |m: &mut StatsResponse| { &mut m.mem_available },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "free_mem_merging",
+            |m: &StatsResponse| { &m.free_mem_merging },
+            |m: &mut StatsResponse| { &mut m.free_mem_merging },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "unmerged_by_request",
+            |m: &StatsResponse| { &m.unmerged_by_request },
+            |m: &mut StatsResponse| { &mut m.unmerged_by_request },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "unmerged_by_policy",
+            |m: &StatsResponse| { &m.unmerged_by_policy },
+            |m: &mut StatsResponse| { &mut m.unmerged_by_policy },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "cpu_pressure",
+            |m: &StatsResponse| { &m.cpu_pressure },
+            |m: &mut StatsResponse| { &mut m.cpu_pressure },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "op_timings",
+            |m: &StatsResponse| { &m.op_timings },
+            |m: &mut StatsResponse| { &mut m.op_timings },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "stale_pages",
+            |m: &StatsResponse| { &m.stale_pages },
+            |m: &mut StatsResponse| { &mut m.stale_pages },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "merge_shared_frames",
+            |m: &StatsResponse| { &m.merge_shared_frames },
+            |m: &mut StatsResponse| { &mut m.merge_shared_frames },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "rss_bytes",
+            |m: &StatsResponse| { &m.rss_bytes },
+            |m: &mut StatsResponse| { &mut m.rss_bytes },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "peak_rss_bytes",
+            |m: &StatsResponse| { &m.peak_rss_bytes },
+            |m: &mut StatsResponse| { &mut m.peak_rss_bytes },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "internal_bytes",
+            |m: &StatsResponse| { &m.internal_bytes },
+            |m: &mut StatsResponse| { &mut m.internal_bytes },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "worker_cpu_us",
+            |m: &StatsResponse| { &m.worker_cpu_us },
+            |m: &mut StatsResponse| { &mut m.worker_cpu_us },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "saved_bytes",
+            |m: &StatsResponse| { &m.saved_bytes },
+            |m: &mut StatsResponse| { &mut m.saved_bytes },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "saved_rss_ratio",
+            |m: &StatsResponse| { &m.saved_rss_ratio },
+            |m: &mut StatsResponse| { &mut m.saved_rss_ratio },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "last_merge_crc_matched",
+            |m: &StatsResponse| { &m.last_merge_crc_matched },
+            |m: &mut StatsResponse| { &mut m.last_merge_crc_matched },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "last_merge_first_probe",
+            |m: &StatsResponse| { &m.last_merge_first_probe },
+            |m: &mut StatsResponse| { &mut m.last_merge_first_probe },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "last_merge_multi_probe",
+            |m: &StatsResponse| { &m.last_merge_multi_probe },
+            |m: &mut StatsResponse| { &mut m.last_merge_multi_probe },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "last_merge_crc_collisions",
+            |m: &StatsResponse| { &m.last_merge_crc_collisions },
+            |m: &mut StatsResponse| { &mut m.last_merge_crc_collisions },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<StatsResponse>(
+            "StatsResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for StatsResponse {
+    const NAME: &'static str = "StatsResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.refresh_pages_per_sec_limit = is.read_uint64()?;
+                },
+                16 => {
+                    self.refresh_pages_per_sec = is.read_uint64()?;
+                },
+                24 => {
+                    self.merge_pages_per_sec_limit = is.read_uint64()?;
+                },
+                32 => {
+                    self.merge_pages_per_sec = is.read_uint64()?;
+                },
+                40 => {
+                    self.tasks = is.read_uint64()?;
+                },
+                48 => {
+                    self.max_tasks = is.read_uint64()?;
+                },
+                56 => {
+                    self.tracked_pages = is.read_uint64()?;
+                },
+                64 => {
+                    self.max_tracked_pages = is.read_uint64()?;
+                },
+                72 => {
+                    self.merge_give_ups = is.read_uint64()?;
+                },
+                80 => {
+                    self.kernel_retries = is.read_uint64()?;
+                },
+                88 => {
+                    self.merge_deferred = is.read_uint64()?;
+                },
+                98 => {
+                    let len = is.read_raw_varint32()?;
+                    let old_limit = is.push_limit(len as u64)?;
+                    let mut key = ::std::default::Default::default();
+                    let mut value = ::std::default::Default::default();
+                    while let Some(tag) = is.read_raw_tag_or_eof()? {
+                        match tag {
+                            10 => key = is.read_string()?,
+                            16 => value = is.read_uint64()?,
+                            _ => ::protobuf::rt::skip_field_for_tag(tag, is)?,
+                        };
+                    }
+                    is.pop_limit(old_limit);
+                    self.kernel_stats.insert(key, value);
+                },
+                104 => {
+                    self.verify_broken_pages = is.read_uint64()?;
+                },
+                112 => {
+                    self.min_dup_count = is.read_uint32()?;
+                },
+                120 => {
+                    self.merge_dup_skipped_pages = is.read_uint64()?;
+                },
+                128 => {
+                    self.zero_pages = is.read_uint64()?;
+                },
+                138 => {
+                    self.merge_scope = is.read_string()?;
+                },
+                144 => {
+                    self.merge_scope_skipped = is.read_uint64()?;
+                },
+                152 => {
+                    self.merged_pages = is.read_uint64()?;
+                },
+                160 => {
+                    self.unmerged_pages = is.read_uint64()?;
+                },
+                168 => {
+                    self.failed_tasks = is.read_uint64()?;
+                },
+                177 => {
+                    self.memory_pressure = is.read_double()?;
+                },
+                184 => {
+                    self.pressure_merging = is.read_bool()?;
+                },
+                192 => {
+                    self.mem_available = is.read_uint64()?;
+                },
+                200 => {
+                    self.free_mem_merging = is.read_bool()?;
+                },
+                208 => {
+                    self.unmerged_by_request = is.read_uint64()?;
+                },
+                216 => {
+                    self.unmerged_by_policy = is.read_uint64()?;
+                },
+                225 => {
+                    self.cpu_pressure = is.read_double()?;
+                },
+                234 => {
+                    self.op_timings.push(is.read_message()?);
+                },
+                240 => {
+                    self.stale_pages = is.read_uint64()?;
+                },
+                248 => {
+                    self.merge_shared_frames = is.read_uint64()?;
+                },
+                256 => {
+                    self.rss_bytes = is.read_uint64()?;
+                },
+                264 => {
+                    self.peak_rss_bytes = is.read_uint64()?;
+                },
+                272 => {
+                    self.internal_bytes = is.read_uint64()?;
+                },
+                280 => {
+                    self.worker_cpu_us = is.read_uint64()?;
+                },
+                288 => {
+                    self.saved_bytes = is.read_uint64()?;
+                },
+                297 => {
+                    self.saved_rss_ratio = is.read_double()?;
+                },
+                304 => {
+                    self.last_merge_crc_matched = is.read_uint64()?;
+                },
+                312 => {
+                    self.last_merge_first_probe = is.read_uint64()?;
+                },
+                320 => {
+                    self.last_merge_multi_probe = is.read_uint64()?;
+                },
+                328 => {
+                    self.last_merge_crc_collisions = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.refresh_pages_per_sec_limit != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.refresh_pages_per_sec_limit);
+        }
+        if self.refresh_pages_per_sec != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.refresh_pages_per_sec);
+        }
+        if self.merge_pages_per_sec_limit != 0 {
+            my_size += ::protobuf::rt::uint64_size(3, self.merge_pages_per_sec_limit);
+        }
+        if self.merge_pages_per_sec != 0 {
+            my_size += ::protobuf::rt::uint64_size(4, self.merge_pages_per_sec);
+        }
+        if self.tasks != 0 {
+            my_size += ::protobuf::rt::uint64_size(5, self.tasks);
+        }
+        if self.max_tasks != 0 {
+            my_size += ::protobuf::rt::uint64_size(6, self.max_tasks);
+        }
+        if self.tracked_pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(7, self.tracked_pages);
+        }
+        if self.max_tracked_pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(8, self.max_tracked_pages);
+        }
+        if self.merge_give_ups != 0 {
+            my_size += ::protobuf::rt::uint64_size(9, self.merge_give_ups);
+        }
+        if self.kernel_retries != 0 {
+            my_size += ::protobuf::rt::uint64_size(10, self.kernel_retries);
+        }
+        if self.merge_deferred != 0 {
+            my_size += ::protobuf::rt::uint64_size(11, self.merge_deferred);
+        }
+        for (k, v) in &self.kernel_stats {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::string_size(1, &k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(entry_size) + entry_size
+        };
+        if self.verify_broken_pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(13, self.verify_broken_pages);
+        }
+        if self.min_dup_count != 0 {
+            my_size += ::protobuf::rt::uint32_size(14, self.min_dup_count);
+        }
+        if self.merge_dup_skipped_pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(15, self.merge_dup_skipped_pages);
+        }
+        if self.zero_pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(16, self.zero_pages);
+        }
+        if !self.merge_scope.is_empty() {
+            my_size += ::protobuf::rt::string_size(17, &self.merge_scope);
+        }
+        if self.merge_scope_skipped != 0 {
+            my_size += ::protobuf::rt::uint64_size(18, self.merge_scope_skipped);
+        }
+        if self.merged_pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(19, self.merged_pages);
+        }
+        if self.unmerged_pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(20, self.unmerged_pages);
+        }
+        if self.failed_tasks != 0 {
+            my_size += ::protobuf::rt::uint64_size(21, self.failed_tasks);
+        }
+        if self.memory_pressure != 0. {
+            my_size += 2 + 8;
+        }
+        if self.pressure_merging != false {
+            my_size += 2 + 1;
+        }
+        if self.mem_available != 0 {
+            my_size += ::protobuf::rt::uint64_size(24, self.mem_available);
+        }
+        if self.free_mem_merging != false {
+            my_size += 2 + 1;
+        }
+        if self.unmerged_by_request != 0 {
+            my_size += ::protobuf::rt::uint64_size(26, self.unmerged_by_request);
+        }
+        if self.unmerged_by_policy != 0 {
+            my_size += ::protobuf::rt::uint64_size(27, self.unmerged_by_policy);
+        }
+        if self.cpu_pressure != 0. {
+            my_size += 2 + 8;
+        }
+        for value in &self.op_timings {
+            let len = value.compute_size();
+            my_size += 2 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        if self.stale_pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(30, self.stale_pages);
+        }
+        if self.merge_shared_frames != 0 {
+            my_size += ::protobuf::rt::uint64_size(31, self.merge_shared_frames);
+        }
+        if self.rss_bytes != 0 {
+            my_size += ::protobuf::rt::uint64_size(32, self.rss_bytes);
+        }
+        if self.peak_rss_bytes != 0 {
+            my_size += ::protobuf::rt::uint64_size(33, self.peak_rss_bytes);
+        }
+        if self.internal_bytes != 0 {
+            my_size += ::protobuf::rt::uint64_size(34, self.internal_bytes);
+        }
+        if self.worker_cpu_us != 0 {
+            my_size += ::protobuf::rt::uint64_size(35, self.worker_cpu_us);
+        }
+        if self.saved_bytes != 0 {
+            my_size += ::protobuf::rt::uint64_size(36, self.saved_bytes);
+        }
+        if self.saved_rss_ratio != 0. {
+            my_size += 2 + 8;
+        }
+        if self.last_merge_crc_matched != 0 {
+            my_size += ::protobuf::rt::uint64_size(38, self.last_merge_crc_matched);
+        }
+        if self.last_merge_first_probe != 0 {
+            my_size += ::protobuf::rt::uint64_size(39, self.last_merge_first_probe);
+        }
+        if self.last_merge_multi_probe != 0 {
+            my_size += ::protobuf::rt::uint64_size(40, self.last_merge_multi_probe);
+        }
+        if self.last_merge_crc_collisions != 0 {
+            my_size += ::protobuf::rt::uint64_size(41, self.last_merge_crc_collisions);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.refresh_pages_per_sec_limit != 0 {
+            os.write_uint64(1, self.refresh_pages_per_sec_limit)?;
+        }
+        if self.refresh_pages_per_sec != 0 {
+            os.write_uint64(2, self.refresh_pages_per_sec)?;
+        }
+        if self.merge_pages_per_sec_limit != 0 {
+            os.write_uint64(3, self.merge_pages_per_sec_limit)?;
+        }
+        if self.merge_pages_per_sec != 0 {
+            os.write_uint64(4, self.merge_pages_per_sec)?;
+        }
+        if self.tasks != 0 {
+            os.write_uint64(5, self.tasks)?;
+        }
+        if self.max_tasks != 0 {
+            os.write_uint64(6, self.max_tasks)?;
+        }
+        if self.tracked_pages != 0 {
+            os.write_uint64(7, self.tracked_pages)?;
+        }
+        if self.max_tracked_pages != 0 {
+            os.write_uint64(8, self.max_tracked_pages)?;
+        }
+        if self.merge_give_ups != 0 {
+            os.write_uint64(9, self.merge_give_ups)?;
+        }
+        if self.kernel_retries != 0 {
+            os.write_uint64(10, self.kernel_retries)?;
+        }
+        if self.merge_deferred != 0 {
+            os.write_uint64(11, self.merge_deferred)?;
+        }
+        for (k, v) in &self.kernel_stats {
+            let mut entry_size = 0;
+            entry_size += ::protobuf::rt::string_size(1, &k);
+            entry_size += ::protobuf::rt::uint64_size(2, *v);
+            os.write_raw_varint32(98)?; // Tag.
+            os.write_raw_varint32(entry_size as u32)?;
+            os.write_string(1, &k)?;
+            os.write_uint64(2, *v)?;
+        };
+        if self.verify_broken_pages != 0 {
+            os.write_uint64(13, self.verify_broken_pages)?;
+        }
+        if self.min_dup_count != 0 {
+            os.write_uint32(14, self.min_dup_count)?;
+        }
+        if self.merge_dup_skipped_pages != 0 {
+            os.write_uint64(15, self.merge_dup_skipped_pages)?;
+        }
+        if self.zero_pages != 0 {
+            os.write_uint64(16, self.zero_pages)?;
+        }
+        if !self.merge_scope.is_empty() {
+            os.write_string(17, &self.merge_scope)?;
+        }
+        if self.merge_scope_skipped != 0 {
+            os.write_uint64(18, self.merge_scope_skipped)?;
+        }
+        if self.merged_pages != 0 {
+            os.write_uint64(19, self.merged_pages)?;
+        }
+        if self.unmerged_pages != 0 {
+            os.write_uint64(20, self.unmerged_pages)?;
+        }
+        if self.failed_tasks != 0 {
+            os.write_uint64(21, self.failed_tasks)?;
+        }
+        if self.memory_pressure != 0. {
+            os.write_double(22, self.memory_pressure)?;
+        }
+        if self.pressure_merging != false {
+            os.write_bool(23, self.pressure_merging)?;
+        }
+        if self.mem_available != 0 {
+            os.write_uint64(24, self.mem_available)?;
+        }
+        if self.free_mem_merging != false {
+            os.write_bool(25, self.free_mem_merging)?;
+        }
+        if self.unmerged_by_request != 0 {
+            os.write_uint64(26, self.unmerged_by_request)?;
+        }
+        if self.unmerged_by_policy != 0 {
+            os.write_uint64(27, self.unmerged_by_policy)?;
+        }
+        if self.cpu_pressure != 0. {
+            os.write_double(28, self.cpu_pressure)?;
+        }
+        for v in &self.op_timings {
+            ::protobuf::rt::write_message_field_with_cached_size(29, v, os)?;
+        };
+        if self.stale_pages != 0 {
+            os.write_uint64(30, self.stale_pages)?;
+        }
+        if self.merge_shared_frames != 0 {
+            os.write_uint64(31, self.merge_shared_frames)?;
+        }
+        if self.rss_bytes != 0 {
+            os.write_uint64(32, self.rss_bytes)?;
+        }
+        if self.peak_rss_bytes != 0 {
+            os.write_uint64(33, self.peak_rss_bytes)?;
+        }
+        if self.internal_bytes != 0 {
+            os.write_uint64(34, self.internal_bytes)?;
+        }
+        if self.worker_cpu_us != 0 {
+            os.write_uint64(35, self.worker_cpu_us)?;
+        }
+        if self.saved_bytes != 0 {
+            os.write_uint64(36, self.saved_bytes)?;
+        }
+        if self.saved_rss_ratio != 0. {
+            os.write_double(37, self.saved_rss_ratio)?;
+        }
+        if self.last_merge_crc_matched != 0 {
+            os.write_uint64(38, self.last_merge_crc_matched)?;
+        }
+        if self.last_merge_first_probe != 0 {
+            os.write_uint64(39, self.last_merge_first_probe)?;
+        }
+        if self.last_merge_multi_probe != 0 {
+            os.write_uint64(40, self.last_merge_multi_probe)?;
+        }
+        if self.last_merge_crc_collisions != 0 {
+            os.write_uint64(41, self.last_merge_crc_collisions)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> StatsResponse {
+        StatsResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.refresh_pages_per_sec_limit = 0;
+        self.refresh_pages_per_sec = 0;
+        self.merge_pages_per_sec_limit = 0;
+        self.merge_pages_per_sec = 0;
+        self.tasks = 0;
+        self.max_tasks = 0;
+        self.tracked_pages = 0;
+        self.max_tracked_pages = 0;
+        self.merge_give_ups = 0;
+        self.kernel_retries = 0;
+        self.merge_deferred = 0;
+        self.kernel_stats.clear();
+        self.verify_broken_pages = 0;
+        self.min_dup_count = 0;
+        self.merge_dup_skipped_pages = 0;
+        self.zero_pages = 0;
+        self.merge_scope.clear();
+        self.merge_scope_skipped = 0;
+        self.merged_pages = 0;
+        self.unmerged_pages = 0;
+        self.failed_tasks = 0;
+        self.memory_pressure = 0.;
+        self.pressure_merging = false;
+        self.mem_available = 0;
+        self.free_mem_merging = false;
+        self.unmerged_by_request = 0;
+        self.unmerged_by_policy = 0;
+        self.cpu_pressure = 0.;
+        self.op_timings.clear();
+        self.stale_pages = 0;
+        self.merge_shared_frames = 0;
+        self.rss_bytes = 0;
+        self.peak_rss_bytes = 0;
+        self.internal_bytes = 0;
+        self.worker_cpu_us = 0;
+        self.saved_bytes = 0;
+        self.saved_rss_ratio = 0.;
+        self.last_merge_crc_matched = 0;
+        self.last_merge_first_probe = 0;
+        self.last_merge_multi_probe = 0;
+        self.last_merge_crc_collisions = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static StatsResponse {
+        static instance: ::protobuf::rt::Lazy<StatsResponse> = ::protobuf::rt::Lazy::new();
+        instance.get(StatsResponse::new)
+    }
+}
+
+impl ::protobuf::MessageFull for StatsResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("StatsResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for StatsResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for StatsResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.SetConfigRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct SetConfigRequest {
+    // message oneof groups
+    pub OptRefreshRate: ::std::option::Option<set_config_request::OptRefreshRate>,
+    pub OptMergeRate: ::std::option::Option<set_config_request::OptMergeRate>,
+    pub OptMinDupCount: ::std::option::Option<set_config_request::OptMinDupCount>,
+    pub OptMergeScope: ::std::option::Option<set_config_request::OptMergeScope>,
+    pub OptMaxLoad: ::std::option::Option<set_config_request::OptMaxLoad>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.SetConfigRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a SetConfigRequest {
+    fn default() -> &'a SetConfigRequest {
+        <SetConfigRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl SetConfigRequest {
+    pub fn new() -> SetConfigRequest {
+        ::std::default::Default::default()
+    }
+
+    // uint64 refresh_pages_per_sec = 1;
+
+    pub fn refresh_pages_per_sec(&self) -> u64 {
+        match self.OptRefreshRate {
+            ::std::option::Option::Some(set_config_request::OptRefreshRate::RefreshPagesPerSec(v)) => v,
+            _ => 0,
+        }
+    }
+
+    pub fn clear_refresh_pages_per_sec(&mut self) {
+        self.OptRefreshRate = ::std::option::Option::None;
+    }
+
+    pub fn has_refresh_pages_per_sec(&self) -> bool {
+        match self.OptRefreshRate {
+            ::std::option::Option::Some(set_config_request::OptRefreshRate::RefreshPagesPerSec(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_refresh_pages_per_sec(&mut self, v: u64) {
+        self.OptRefreshRate = ::std::option::Option::Some(set_config_request::OptRefreshRate::RefreshPagesPerSec(v))
+    }
+
+    // uint64 merge_pages_per_sec = 2;
+
+    pub fn merge_pages_per_sec(&self) -> u64 {
+        match self.OptMergeRate {
+            ::std::option::Option::Some(set_config_request::OptMergeRate::MergePagesPerSec(v)) => v,
+            _ => 0,
+        }
+    }
+
+    pub fn clear_merge_pages_per_sec(&mut self) {
+        self.OptMergeRate = ::std::option::Option::None;
+    }
+
+    pub fn has_merge_pages_per_sec(&self) -> bool {
+        match self.OptMergeRate {
+            ::std::option::Option::Some(set_config_request::OptMergeRate::MergePagesPerSec(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_merge_pages_per_sec(&mut self, v: u64) {
+        self.OptMergeRate = ::std::option::Option::Some(set_config_request::OptMergeRate::MergePagesPerSec(v))
+    }
+
+    // uint32 min_dup_count = 3;
+
+    pub fn min_dup_count(&self) -> u32 {
+        match self.OptMinDupCount {
+            ::std::option::Option::Some(set_config_request::OptMinDupCount::MinDupCount(v)) => v,
+            _ => 0,
+        }
+    }
+
+    pub fn clear_min_dup_count(&mut self) {
+        self.OptMinDupCount = ::std::option::Option::None;
+    }
+
+    pub fn has_min_dup_count(&self) -> bool {
+        match self.OptMinDupCount {
+            ::std::option::Option::Some(set_config_request::OptMinDupCount::MinDupCount(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_min_dup_count(&mut self, v: u32) {
+        self.OptMinDupCount = ::std::option::Option::Some(set_config_request::OptMinDupCount::MinDupCount(v))
+    }
+
+    // string merge_scope = 4;
+
+    pub fn merge_scope(&self) -> &str {
+        match self.OptMergeScope {
+            ::std::option::Option::Some(set_config_request::OptMergeScope::MergeScope(ref v)) => v,
+            _ => "",
+        }
+    }
+
+    pub fn clear_merge_scope(&mut self) {
+        self.OptMergeScope = ::std::option::Option::None;
+    }
+
+    pub fn has_merge_scope(&self) -> bool {
+        match self.OptMergeScope {
+            ::std::option::Option::Some(set_config_request::OptMergeScope::MergeScope(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_merge_scope(&mut self, v: ::std::string::String) {
+        self.OptMergeScope = ::std::option::Option::Some(set_config_request::OptMergeScope::MergeScope(v))
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_merge_scope(&mut self) -> &mut ::std::string::String {
+        if let ::std::option::Option::Some(set_config_request::OptMergeScope::MergeScope(_)) = self.OptMergeScope {
+        } else {
+            self.OptMergeScope = ::std::option::Option::Some(set_config_request::OptMergeScope::MergeScope(::std::string::String::new()));
+        }
+        match self.OptMergeScope {
+            ::std::option::Option::Some(set_config_request::OptMergeScope::MergeScope(ref mut v)) => v,
+            _ => panic!(),
+        }
+    }
+
+    // Take field
+    pub fn take_merge_scope(&mut self) -> ::std::string::String {
+        if self.has_merge_scope() {
+            match self.OptMergeScope.take() {
+                ::std::option::Option::Some(set_config_request::OptMergeScope::MergeScope(v)) => v,
+                _ => panic!(),
+            }
+        } else {
+            ::std::string::String::new()
+        }
+    }
+
+    // double max_load = 5;
+
+    pub fn max_load(&self) -> f64 {
+        match self.OptMaxLoad {
+            ::std::option::Option::Some(set_config_request::OptMaxLoad::MaxLoad(v)) => v,
+            _ => 0.,
+        }
+    }
+
+    pub fn clear_max_load(&mut self) {
+        self.OptMaxLoad = ::std::option::Option::None;
+    }
+
+    pub fn has_max_load(&self) -> bool {
+        match self.OptMaxLoad {
+            ::std::option::Option::Some(set_config_request::OptMaxLoad::MaxLoad(..)) => true,
+            _ => false,
+        }
+    }
+
+    // Param is passed by value, moved
+    pub fn set_max_load(&mut self, v: f64) {
+        self.OptMaxLoad = ::std::option::Option::Some(set_config_request::OptMaxLoad::MaxLoad(v))
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(5);
+        let mut oneofs = ::std::vec::Vec::with_capacity(5);
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_copy_has_get_set_simpler_accessors::<_, _>(
+            "refresh_pages_per_sec",
+            SetConfigRequest::has_refresh_pages_per_sec,
+            SetConfigRequest::refresh_pages_per_sec,
+            SetConfigRequest::set_refresh_pages_per_sec,
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_copy_has_get_set_simpler_accessors::<_, _>(
+            "merge_pages_per_sec",
+            SetConfigRequest::has_merge_pages_per_sec,
+            SetConfigRequest::merge_pages_per_sec,
+            SetConfigRequest::set_merge_pages_per_sec,
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_copy_has_get_set_simpler_accessors::<_, _>(
+            "min_dup_count",
+            SetConfigRequest::has_min_dup_count,
+            SetConfigRequest::min_dup_count,
+            SetConfigRequest::set_min_dup_count,
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_deref_has_get_set_simpler_accessor::<_, _>(
+            "merge_scope",
+            SetConfigRequest::has_merge_scope,
+            SetConfigRequest::merge_scope,
+            SetConfigRequest::set_merge_scope,
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_oneof_copy_has_get_set_simpler_accessors::<_, _>(
+            "max_load",
+            SetConfigRequest::has_max_load,
+            SetConfigRequest::max_load,
+            SetConfigRequest::set_max_load,
+        ));
+        oneofs.push(set_config_request::OptRefreshRate::generated_oneof_descriptor_data());
+        oneofs.push(set_config_request::OptMergeRate::generated_oneof_descriptor_data());
+        oneofs.push(set_config_request::OptMinDupCount::generated_oneof_descriptor_data());
+        oneofs.push(set_config_request::OptMergeScope::generated_oneof_descriptor_data());
+        oneofs.push(set_config_request::OptMaxLoad::generated_oneof_descriptor_data());
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<SetConfigRequest>(
+            "SetConfigRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for SetConfigRequest {
+    const NAME: &'static str = "SetConfigRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.OptRefreshRate = ::std::option::Option::Some(set_config_request::OptRefreshRate::RefreshPagesPerSec(is.read_uint64()?));
+                },
+                16 => {
+                    self.OptMergeRate = ::std::option::Option::Some(set_config_request::OptMergeRate::MergePagesPerSec(is.read_uint64()?));
+                },
+                24 => {
+                    self.OptMinDupCount = ::std::option::Option::Some(set_config_request::OptMinDupCount::MinDupCount(is.read_uint32()?));
+                },
+                34 => {
+                    self.OptMergeScope = ::std::option::Option::Some(set_config_request::OptMergeScope::MergeScope(is.read_string()?));
+                },
+                41 => {
+                    self.OptMaxLoad = ::std::option::Option::Some(set_config_request::OptMaxLoad::MaxLoad(is.read_double()?));
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if let ::std::option::Option::Some(ref v) = self.OptRefreshRate {
+            match v {
+                &set_config_request::OptRefreshRate::RefreshPagesPerSec(v) => {
+                    my_size += ::protobuf::rt::uint64_size(1, v);
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptMergeRate {
+            match v {
+                &set_config_request::OptMergeRate::MergePagesPerSec(v) => {
+                    my_size += ::protobuf::rt::uint64_size(2, v);
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptMinDupCount {
+            match v {
+                &set_config_request::OptMinDupCount::MinDupCount(v) => {
+                    my_size += ::protobuf::rt::uint32_size(3, v);
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptMergeScope {
+            match v {
+                &set_config_request::OptMergeScope::MergeScope(ref v) => {
+                    my_size += ::protobuf::rt::string_size(4, &v);
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptMaxLoad {
+            match v {
+                &set_config_request::OptMaxLoad::MaxLoad(v) => {
+                    my_size += 1 + 8;
+                },
+            };
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if let ::std::option::Option::Some(ref v) = self.OptRefreshRate {
+            match v {
+                &set_config_request::OptRefreshRate::RefreshPagesPerSec(v) => {
+                    os.write_uint64(1, v)?;
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptMergeRate {
+            match v {
+                &set_config_request::OptMergeRate::MergePagesPerSec(v) => {
+                    os.write_uint64(2, v)?;
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptMinDupCount {
+            match v {
+                &set_config_request::OptMinDupCount::MinDupCount(v) => {
+                    os.write_uint32(3, v)?;
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptMergeScope {
+            match v {
+                &set_config_request::OptMergeScope::MergeScope(ref v) => {
+                    os.write_string(4, v)?;
+                },
+            };
+        }
+        if let ::std::option::Option::Some(ref v) = self.OptMaxLoad {
+            match v {
+                &set_config_request::OptMaxLoad::MaxLoad(v) => {
+                    os.write_double(5, v)?;
+                },
+            };
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> SetConfigRequest {
+        SetConfigRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.OptRefreshRate = ::std::option::Option::None;
+        self.OptMergeRate = ::std::option::Option::None;
+        self.OptMinDupCount = ::std::option::Option::None;
+        self.OptMergeScope = ::std::option::Option::None;
+        self.OptMaxLoad = ::std::option::Option::None;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static SetConfigRequest {
+        static instance: SetConfigRequest = SetConfigRequest {
+            OptRefreshRate: ::std::option::Option::None,
+            OptMergeRate: ::std::option::Option::None,
+            OptMinDupCount: ::std::option::Option::None,
+            OptMergeScope: ::std::option::Option::None,
+            OptMaxLoad: ::std::option::Option::None,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for SetConfigRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("SetConfigRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for SetConfigRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for SetConfigRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+/// Nested message and enums of message `SetConfigRequest`
+pub mod set_config_request {
+
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.SetConfigRequest.OptRefreshRate)
+    pub enum OptRefreshRate {
+        // @@protoc_insertion_point(oneof_field:MemAgent.SetConfigRequest.refresh_pages_per_sec)
+        RefreshPagesPerSec(u64),
+    }
+
+    impl ::protobuf::Oneof for OptRefreshRate {
+    }
+
+    impl ::protobuf::OneofFull for OptRefreshRate {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::SetConfigRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptRefreshRate").unwrap()).clone()
+        }
+    }
+
+    impl OptRefreshRate {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptRefreshRate>("OptRefreshRate")
+        }
+    }
+
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.SetConfigRequest.OptMergeRate)
+    pub enum OptMergeRate {
+        // @@protoc_insertion_point(oneof_field:MemAgent.SetConfigRequest.merge_pages_per_sec)
+        MergePagesPerSec(u64),
+    }
+
+    impl ::protobuf::Oneof for OptMergeRate {
+    }
+
+    impl ::protobuf::OneofFull for OptMergeRate {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::SetConfigRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptMergeRate").unwrap()).clone()
+        }
+    }
+
+    impl OptMergeRate {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptMergeRate>("OptMergeRate")
+        }
+    }
+
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.SetConfigRequest.OptMinDupCount)
+    pub enum OptMinDupCount {
+        // @@protoc_insertion_point(oneof_field:MemAgent.SetConfigRequest.min_dup_count)
+        MinDupCount(u32),
+    }
+
+    impl ::protobuf::Oneof for OptMinDupCount {
+    }
+
+    impl ::protobuf::OneofFull for OptMinDupCount {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::SetConfigRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptMinDupCount").unwrap()).clone()
+        }
+    }
+
+    impl OptMinDupCount {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptMinDupCount>("OptMinDupCount")
+        }
+    }
+
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.SetConfigRequest.OptMergeScope)
+    pub enum OptMergeScope {
+        // @@protoc_insertion_point(oneof_field:MemAgent.SetConfigRequest.merge_scope)
+        MergeScope(::std::string::String),
+    }
+
+    impl ::protobuf::Oneof for OptMergeScope {
+    }
+
+    impl ::protobuf::OneofFull for OptMergeScope {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::SetConfigRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptMergeScope").unwrap()).clone()
+        }
+    }
+
+    impl OptMergeScope {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptMergeScope>("OptMergeScope")
+        }
+    }
+
+    #[derive(Clone,PartialEq,Debug)]
+    #[non_exhaustive]
+    // @@protoc_insertion_point(oneof:MemAgent.SetConfigRequest.OptMaxLoad)
+    pub enum OptMaxLoad {
+        // @@protoc_insertion_point(oneof_field:MemAgent.SetConfigRequest.max_load)
+        MaxLoad(f64),
+    }
+
+    impl ::protobuf::Oneof for OptMaxLoad {
+    }
+
+    impl ::protobuf::OneofFull for OptMaxLoad {
+        fn descriptor() -> ::protobuf::reflect::OneofDescriptor {
+            static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::OneofDescriptor> = ::protobuf::rt::Lazy::new();
+            descriptor.get(|| <super::SetConfigRequest as ::protobuf::MessageFull>::descriptor().oneof_by_name("OptMaxLoad").unwrap()).clone()
+        }
+    }
+
+    impl OptMaxLoad {
+        pub(in super) fn generated_oneof_descriptor_data() -> ::protobuf::reflect::GeneratedOneofDescriptorData {
+            ::protobuf::reflect::GeneratedOneofDescriptorData::new::<OptMaxLoad>("OptMaxLoad")
+        }
+    }
+}
+
+// @@protoc_insertion_point(message:MemAgent.HistoryRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct HistoryRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.HistoryRequest.pid)
+    pub pid: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.HistoryRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a HistoryRequest {
+    fn default() -> &'a HistoryRequest {
+        <HistoryRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl HistoryRequest {
+    pub fn new() -> HistoryRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pid",
+            |m: &HistoryRequest| { &m.pid },
+            |m: &mut HistoryRequest| { &mut m.pid },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<HistoryRequest>(
+            "HistoryRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for HistoryRequest {
+    const NAME: &'static str = "HistoryRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.pid = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.pid != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.pid);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.pid != 0 {
+            os.write_uint64(1, self.pid)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> HistoryRequest {
+        HistoryRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.pid = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static HistoryRequest {
+        static instance: HistoryRequest = HistoryRequest {
+            pid: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for HistoryRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("HistoryRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for HistoryRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for HistoryRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.HistorySample)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct HistorySample {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.HistorySample.time)
+    pub time: u64,
+    // @@protoc_insertion_point(field:MemAgent.HistorySample.uksm_count)
+    pub uksm_count: u64,
+    // @@protoc_insertion_point(field:MemAgent.HistorySample.saved_bytes)
+    pub saved_bytes: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.HistorySample.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a HistorySample {
+    fn default() -> &'a HistorySample {
+        <HistorySample as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl HistorySample {
+    pub fn new() -> HistorySample {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "time",
+            |m: &HistorySample| { &m.time },
+            |m: &mut HistorySample| { &mut m.time },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "uksm_count",
+            |m: &HistorySample| { &m.uksm_count },
+            |m: &mut HistorySample| { &mut m.uksm_count },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "saved_bytes",
+            |m: &HistorySample| { &m.saved_bytes },
+            |m: &mut HistorySample| { &mut m.saved_bytes },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<HistorySample>(
+            "HistorySample",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for HistorySample {
+    const NAME: &'static str = "HistorySample";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.time = is.read_uint64()?;
+                },
+                16 => {
+                    self.uksm_count = is.read_uint64()?;
+                },
+                24 => {
+                    self.saved_bytes = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.time != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.time);
+        }
+        if self.uksm_count != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.uksm_count);
+        }
+        if self.saved_bytes != 0 {
+            my_size += ::protobuf::rt::uint64_size(3, self.saved_bytes);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.time != 0 {
+            os.write_uint64(1, self.time)?;
+        }
+        if self.uksm_count != 0 {
+            os.write_uint64(2, self.uksm_count)?;
+        }
+        if self.saved_bytes != 0 {
+            os.write_uint64(3, self.saved_bytes)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> HistorySample {
+        HistorySample::new()
+    }
+
+    fn clear(&mut self) {
+        self.time = 0;
+        self.uksm_count = 0;
+        self.saved_bytes = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static HistorySample {
+        static instance: HistorySample = HistorySample {
+            time: 0,
+            uksm_count: 0,
+            saved_bytes: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for HistorySample {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("HistorySample").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for HistorySample {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for HistorySample {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.HistoryResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct HistoryResponse {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.HistoryResponse.samples)
+    pub samples: ::std::vec::Vec<HistorySample>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.HistoryResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a HistoryResponse {
+    fn default() -> &'a HistoryResponse {
+        <HistoryResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl HistoryResponse {
+    pub fn new() -> HistoryResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "samples",
+            |m: &HistoryResponse| { &m.samples },
+            |m: &mut HistoryResponse| { &mut m.samples },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<HistoryResponse>(
+            "HistoryResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for HistoryResponse {
+    const NAME: &'static str = "HistoryResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.samples.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        for value in &self.samples {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.samples {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> HistoryResponse {
+        HistoryResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.samples.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static HistoryResponse {
+        static instance: HistoryResponse = HistoryResponse {
+            samples: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for HistoryResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("HistoryResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for HistoryResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for HistoryResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.OpTiming)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct OpTiming {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.OpTiming.name)
+    pub name: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.OpTiming.calls)
+    pub calls: u64,
+    // @@protoc_insertion_point(field:MemAgent.OpTiming.total_us)
+    pub total_us: u64,
+    // @@protoc_insertion_point(field:MemAgent.OpTiming.max_us)
+    pub max_us: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.OpTiming.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a OpTiming {
+    fn default() -> &'a OpTiming {
+        <OpTiming as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl OpTiming {
+    pub fn new() -> OpTiming {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "name",
+            |m: &OpTiming| { &m.name },
+            |m: &mut OpTiming| { &mut m.name },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "calls",
+            |m: &OpTiming| { &m.calls },
+            |m: &mut OpTiming| { &mut m.calls },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "total_us",
+            |m: &OpTiming| { &m.total_us },
+            |m: &mut OpTiming| { &mut m.total_us },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "max_us",
+            |m: &OpTiming| { &m.max_us },
+            |m: &mut OpTiming| { &mut m.max_us },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<OpTiming>(
+            "OpTiming",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for OpTiming {
+    const NAME: &'static str = "OpTiming";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.name = is.read_string()?;
+                },
+                16 => {
+                    self.calls = is.read_uint64()?;
+                },
+                24 => {
+                    self.total_us = is.read_uint64()?;
+                },
+                32 => {
+                    self.max_us = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.name.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.name);
+        }
+        if self.calls != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.calls);
+        }
+        if self.total_us != 0 {
+            my_size += ::protobuf::rt::uint64_size(3, self.total_us);
+        }
+        if self.max_us != 0 {
+            my_size += ::protobuf::rt::uint64_size(4, self.max_us);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.name.is_empty() {
+            os.write_string(1, &self.name)?;
+        }
+        if self.calls != 0 {
+            os.write_uint64(2, self.calls)?;
+        }
+        if self.total_us != 0 {
+            os.write_uint64(3, self.total_us)?;
+        }
+        if self.max_us != 0 {
+            os.write_uint64(4, self.max_us)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> OpTiming {
+        OpTiming::new()
+    }
+
+    fn clear(&mut self) {
+        self.name.clear();
+        self.calls = 0;
+        self.total_us = 0;
+        self.max_us = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static OpTiming {
+        static instance: OpTiming = OpTiming {
+            name: ::std::string::String::new(),
+            calls: 0,
+            total_us: 0,
+            max_us: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for OpTiming {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("OpTiming").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for OpTiming {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for OpTiming {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.RecentEventsRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct RecentEventsRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.RecentEventsRequest.count)
+    pub count: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.RecentEventsRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RecentEventsRequest {
+    fn default() -> &'a RecentEventsRequest {
+        <RecentEventsRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RecentEventsRequest {
+    pub fn new() -> RecentEventsRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "count",
+            |m: &RecentEventsRequest| { &m.count },
+            |m: &mut RecentEventsRequest| { &mut m.count },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RecentEventsRequest>(
+            "RecentEventsRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RecentEventsRequest {
+    const NAME: &'static str = "RecentEventsRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.count = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.count != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.count);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.count != 0 {
+            os.write_uint64(1, self.count)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RecentEventsRequest {
+        RecentEventsRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.count = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RecentEventsRequest {
+        static instance: RecentEventsRequest = RecentEventsRequest {
+            count: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RecentEventsRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RecentEventsRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RecentEventsRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RecentEventsRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.RecentEvent)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct RecentEvent {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.RecentEvent.time)
+    pub time: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.RecentEvent.severity)
+    pub severity: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.RecentEvent.message)
+    pub message: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.RecentEvent.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RecentEvent {
+    fn default() -> &'a RecentEvent {
+        <RecentEvent as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RecentEvent {
+    pub fn new() -> RecentEvent {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "time",
+            |m: &RecentEvent| { &m.time },
+            |m: &mut RecentEvent| { &mut m.time },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "severity",
+            |m: &RecentEvent| { &m.severity },
+            |m: &mut RecentEvent| { &mut m.severity },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "message",
+            |m: &RecentEvent| { &m.message },
+            |m: &mut RecentEvent| { &mut m.message },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RecentEvent>(
+            "RecentEvent",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RecentEvent {
+    const NAME: &'static str = "RecentEvent";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.time = is.read_string()?;
+                },
+                18 => {
+                    self.severity = is.read_string()?;
+                },
+                26 => {
+                    self.message = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.time.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.time);
+        }
+        if !self.severity.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.severity);
+        }
+        if !self.message.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.message);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.time.is_empty() {
+            os.write_string(1, &self.time)?;
+        }
+        if !self.severity.is_empty() {
+            os.write_string(2, &self.severity)?;
+        }
+        if !self.message.is_empty() {
+            os.write_string(3, &self.message)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RecentEvent {
+        RecentEvent::new()
+    }
+
+    fn clear(&mut self) {
+        self.time.clear();
+        self.severity.clear();
+        self.message.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RecentEvent {
+        static instance: RecentEvent = RecentEvent {
+            time: ::std::string::String::new(),
+            severity: ::std::string::String::new(),
+            message: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RecentEvent {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RecentEvent").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RecentEvent {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RecentEvent {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.RecentEventsResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct RecentEventsResponse {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.RecentEventsResponse.events)
+    pub events: ::std::vec::Vec<RecentEvent>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.RecentEventsResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RecentEventsResponse {
+    fn default() -> &'a RecentEventsResponse {
+        <RecentEventsResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RecentEventsResponse {
+    pub fn new() -> RecentEventsResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "events",
+            |m: &RecentEventsResponse| { &m.events },
+            |m: &mut RecentEventsResponse| { &mut m.events },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RecentEventsResponse>(
+            "RecentEventsResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RecentEventsResponse {
+    const NAME: &'static str = "RecentEventsResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.events.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        for value in &self.events {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.events {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RecentEventsResponse {
+        RecentEventsResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.events.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RecentEventsResponse {
+        static instance: RecentEventsResponse = RecentEventsResponse {
+            events: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RecentEventsResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RecentEventsResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RecentEventsResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RecentEventsResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.GetMapsRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct GetMapsRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.GetMapsRequest.pid)
+    pub pid: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.GetMapsRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a GetMapsRequest {
+    fn default() -> &'a GetMapsRequest {
+        <GetMapsRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl GetMapsRequest {
+    pub fn new() -> GetMapsRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pid",
+            |m: &GetMapsRequest| { &m.pid },
+            |m: &mut GetMapsRequest| { &mut m.pid },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<GetMapsRequest>(
+            "GetMapsRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for GetMapsRequest {
+    const NAME: &'static str = "GetMapsRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.pid = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.pid != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.pid);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.pid != 0 {
+            os.write_uint64(1, self.pid)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> GetMapsRequest {
+        GetMapsRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.pid = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static GetMapsRequest {
+        static instance: GetMapsRequest = GetMapsRequest {
+            pid: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for GetMapsRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("GetMapsRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for GetMapsRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for GetMapsRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.RejectedMap)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct RejectedMap {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.RejectedMap.start)
+    pub start: u64,
+    // @@protoc_insertion_point(field:MemAgent.RejectedMap.end)
+    pub end: u64,
+    // @@protoc_insertion_point(field:MemAgent.RejectedMap.reason)
+    pub reason: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.RejectedMap.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a RejectedMap {
+    fn default() -> &'a RejectedMap {
+        <RejectedMap as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl RejectedMap {
+    pub fn new() -> RejectedMap {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "start",
+            |m: &RejectedMap| { &m.start },
+            |m: &mut RejectedMap| { &mut m.start },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "end",
+            |m: &RejectedMap| { &m.end },
+            |m: &mut RejectedMap| { &mut m.end },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "reason",
+            |m: &RejectedMap| { &m.reason },
+            |m: &mut RejectedMap| { &mut m.reason },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<RejectedMap>(
+            "RejectedMap",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for RejectedMap {
+    const NAME: &'static str = "RejectedMap";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.start = is.read_uint64()?;
+                },
+                16 => {
+                    self.end = is.read_uint64()?;
+                },
+                26 => {
+                    self.reason = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.start != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.start);
+        }
+        if self.end != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.end);
+        }
+        if !self.reason.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.reason);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.start != 0 {
+            os.write_uint64(1, self.start)?;
+        }
+        if self.end != 0 {
+            os.write_uint64(2, self.end)?;
+        }
+        if !self.reason.is_empty() {
+            os.write_string(3, &self.reason)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> RejectedMap {
+        RejectedMap::new()
+    }
+
+    fn clear(&mut self) {
+        self.start = 0;
+        self.end = 0;
+        self.reason.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static RejectedMap {
+        static instance: RejectedMap = RejectedMap {
+            start: 0,
+            end: 0,
+            reason: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for RejectedMap {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("RejectedMap").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for RejectedMap {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for RejectedMap {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.GetMapsResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct GetMapsResponse {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.GetMapsResponse.maps)
+    pub maps: ::std::vec::Vec<Addr>,
+    // @@protoc_insertion_point(field:MemAgent.GetMapsResponse.rejected)
+    pub rejected: ::std::vec::Vec<RejectedMap>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.GetMapsResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a GetMapsResponse {
+    fn default() -> &'a GetMapsResponse {
+        <GetMapsResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl GetMapsResponse {
+    pub fn new() -> GetMapsResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "maps",
+            |m: &GetMapsResponse| { &m.maps },
+            |m: &mut GetMapsResponse| { &mut m.maps },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "rejected",
+            |m: &GetMapsResponse| { &m.rejected },
+            |m: &mut GetMapsResponse| { &mut m.rejected },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<GetMapsResponse>(
+            "GetMapsResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for GetMapsResponse {
+    const NAME: &'static str = "GetMapsResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.maps.push(is.read_message()?);
+                },
+                18 => {
+                    self.rejected.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        for value in &self.maps {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        for value in &self.rejected {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.maps {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        };
+        for v in &self.rejected {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> GetMapsResponse {
+        GetMapsResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.maps.clear();
+        self.rejected.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static GetMapsResponse {
+        static instance: GetMapsResponse = GetMapsResponse {
+            maps: ::std::vec::Vec::new(),
+            rejected: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for GetMapsResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("GetMapsResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for GetMapsResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for GetMapsResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.CrcHistogramRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct CrcHistogramRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.CrcHistogramRequest.top)
+    pub top: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.CrcHistogramRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a CrcHistogramRequest {
+    fn default() -> &'a CrcHistogramRequest {
+        <CrcHistogramRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl CrcHistogramRequest {
+    pub fn new() -> CrcHistogramRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "top",
+            |m: &CrcHistogramRequest| { &m.top },
+            |m: &mut CrcHistogramRequest| { &mut m.top },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<CrcHistogramRequest>(
+            "CrcHistogramRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for CrcHistogramRequest {
+    const NAME: &'static str = "CrcHistogramRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.top = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.top != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.top);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.top != 0 {
+            os.write_uint64(1, self.top)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> CrcHistogramRequest {
+        CrcHistogramRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.top = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static CrcHistogramRequest {
+        static instance: CrcHistogramRequest = CrcHistogramRequest {
+            top: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for CrcHistogramRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("CrcHistogramRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for CrcHistogramRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for CrcHistogramRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.CrcBucket)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct CrcBucket {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.CrcBucket.range)
+    pub range: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.CrcBucket.crcs)
+    pub crcs: u64,
+    // @@protoc_insertion_point(field:MemAgent.CrcBucket.pages)
+    pub pages: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.CrcBucket.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a CrcBucket {
+    fn default() -> &'a CrcBucket {
+        <CrcBucket as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl CrcBucket {
+    pub fn new() -> CrcBucket {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(3);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "range",
+            |m: &CrcBucket| { &m.range },
+            |m: &mut CrcBucket| { &mut m.range },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "crcs",
+            |m: &CrcBucket| { &m.crcs },
+            |m: &mut CrcBucket| { &mut m.crcs },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pages",
+            |m: &CrcBucket| { &m.pages },
+            |m: &mut CrcBucket| { &mut m.pages },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<CrcBucket>(
+            "CrcBucket",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for CrcBucket {
+    const NAME: &'static str = "CrcBucket";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.range = is.read_string()?;
+                },
+                16 => {
+                    self.crcs = is.read_uint64()?;
+                },
+                24 => {
+                    self.pages = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.range.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.range);
+        }
+        if self.crcs != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.crcs);
+        }
+        if self.pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(3, self.pages);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.range.is_empty() {
+            os.write_string(1, &self.range)?;
+        }
+        if self.crcs != 0 {
+            os.write_uint64(2, self.crcs)?;
+        }
+        if self.pages != 0 {
+            os.write_uint64(3, self.pages)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> CrcBucket {
+        CrcBucket::new()
+    }
+
+    fn clear(&mut self) {
+        self.range.clear();
+        self.crcs = 0;
+        self.pages = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static CrcBucket {
+        static instance: CrcBucket = CrcBucket {
+            range: ::std::string::String::new(),
+            crcs: 0,
+            pages: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for CrcBucket {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("CrcBucket").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for CrcBucket {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for CrcBucket {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.CrcCount)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct CrcCount {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.CrcCount.crc)
+    pub crc: u32,
+    // @@protoc_insertion_point(field:MemAgent.CrcCount.pages)
+    pub pages: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.CrcCount.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a CrcCount {
+    fn default() -> &'a CrcCount {
+        <CrcCount as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl CrcCount {
+    pub fn new() -> CrcCount {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "crc",
+            |m: &CrcCount| { &m.crc },
+            |m: &mut CrcCount| { &mut m.crc },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "pages",
+            |m: &CrcCount| { &m.pages },
+            |m: &mut CrcCount| { &mut m.pages },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<CrcCount>(
+            "CrcCount",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for CrcCount {
+    const NAME: &'static str = "CrcCount";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.crc = is.read_uint32()?;
+                },
+                16 => {
+                    self.pages = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.crc != 0 {
+            my_size += ::protobuf::rt::uint32_size(1, self.crc);
+        }
+        if self.pages != 0 {
+            my_size += ::protobuf::rt::uint64_size(2, self.pages);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.crc != 0 {
+            os.write_uint32(1, self.crc)?;
+        }
+        if self.pages != 0 {
+            os.write_uint64(2, self.pages)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> CrcCount {
+        CrcCount::new()
+    }
+
+    fn clear(&mut self) {
+        self.crc = 0;
+        self.pages = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static CrcCount {
+        static instance: CrcCount = CrcCount {
+            crc: 0,
+            pages: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for CrcCount {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("CrcCount").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for CrcCount {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for CrcCount {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.CrcHistogramResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct CrcHistogramResponse {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.CrcHistogramResponse.merged)
+    pub merged: ::std::vec::Vec<CrcBucket>,
+    // @@protoc_insertion_point(field:MemAgent.CrcHistogramResponse.candidates)
+    pub candidates: ::std::vec::Vec<CrcBucket>,
+    // @@protoc_insertion_point(field:MemAgent.CrcHistogramResponse.top_merged)
+    pub top_merged: ::std::vec::Vec<CrcCount>,
+    // @@protoc_insertion_point(field:MemAgent.CrcHistogramResponse.top_candidates)
+    pub top_candidates: ::std::vec::Vec<CrcCount>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.CrcHistogramResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a CrcHistogramResponse {
+    fn default() -> &'a CrcHistogramResponse {
+        <CrcHistogramResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl CrcHistogramResponse {
+    pub fn new() -> CrcHistogramResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "merged",
+            |m: &CrcHistogramResponse| { &m.merged },
+            |m: &mut CrcHistogramResponse| { &mut m.merged },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "candidates",
+            |m: &CrcHistogramResponse| { &m.candidates },
+            |m: &mut CrcHistogramResponse| { &mut m.candidates },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "top_merged",
+            |m: &CrcHistogramResponse| { &m.top_merged },
+            |m: &mut CrcHistogramResponse| { &mut m.top_merged },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "top_candidates",
+            |m: &CrcHistogramResponse| { &m.top_candidates },
+            |m: &mut CrcHistogramResponse| { &mut m.top_candidates },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<CrcHistogramResponse>(
+            "CrcHistogramResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for CrcHistogramResponse {
+    const NAME: &'static str = "CrcHistogramResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.merged.push(is.read_message()?);
+                },
+                18 => {
+                    self.candidates.push(is.read_message()?);
+                },
+                26 => {
+                    self.top_merged.push(is.read_message()?);
+                },
+                34 => {
+                    self.top_candidates.push(is.read_message()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        for value in &self.merged {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        for value in &self.candidates {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        for value in &self.top_merged {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        for value in &self.top_candidates {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint64_size(len) + len;
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        for v in &self.merged {
+            ::protobuf::rt::write_message_field_with_cached_size(1, v, os)?;
+        };
+        for v in &self.candidates {
+            ::protobuf::rt::write_message_field_with_cached_size(2, v, os)?;
+        };
+        for v in &self.top_merged {
+            ::protobuf::rt::write_message_field_with_cached_size(3, v, os)?;
+        };
+        for v in &self.top_candidates {
+            ::protobuf::rt::write_message_field_with_cached_size(4, v, os)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> CrcHistogramResponse {
+        CrcHistogramResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.merged.clear();
+        self.candidates.clear();
+        self.top_merged.clear();
+        self.top_candidates.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static CrcHistogramResponse {
+        static instance: CrcHistogramResponse = CrcHistogramResponse {
+            merged: ::std::vec::Vec::new(),
+            candidates: ::std::vec::Vec::new(),
+            top_merged: ::std::vec::Vec::new(),
+            top_candidates: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for CrcHistogramResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("CrcHistogramResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for CrcHistogramResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for CrcHistogramResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.WatchCgroupRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct WatchCgroupRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.WatchCgroupRequest.path)
+    pub path: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.WatchCgroupRequest.labels)
+    pub labels: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.WatchCgroupRequest.include_vma)
+    pub include_vma: ::std::vec::Vec<::std::string::String>,
+    // @@protoc_insertion_point(field:MemAgent.WatchCgroupRequest.exclude_vma)
+    pub exclude_vma: ::std::vec::Vec<::std::string::String>,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.WatchCgroupRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a WatchCgroupRequest {
+    fn default() -> &'a WatchCgroupRequest {
+        <WatchCgroupRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl WatchCgroupRequest {
+    pub fn new() -> WatchCgroupRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(4);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "path",
+            |m: &WatchCgroupRequest| { &m.path },
+            |m: &mut WatchCgroupRequest| { &mut m.path },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "labels",
+            |m: &WatchCgroupRequest| { &m.labels },
+            |m: &mut WatchCgroupRequest| { &mut m.labels },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "include_vma",
+            |m: &WatchCgroupRequest| { &m.include_vma },
+            |m: &mut WatchCgroupRequest| { &mut m.include_vma },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_vec_simpler_accessor::<_, _>(
+            "exclude_vma",
+            |m: &WatchCgroupRequest| { &m.exclude_vma },
+            |m: &mut WatchCgroupRequest| { &mut m.exclude_vma },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<WatchCgroupRequest>(
+            "WatchCgroupRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for WatchCgroupRequest {
+    const NAME: &'static str = "WatchCgroupRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.path = is.read_string()?;
+                },
+                18 => {
+                    self.labels.push(is.read_string()?);
+                },
+                26 => {
+                    self.include_vma.push(is.read_string()?);
+                },
+                34 => {
+                    self.exclude_vma.push(is.read_string()?);
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.path);
+        }
+        for value in &self.labels {
+            my_size += ::protobuf::rt::string_size(2, &value);
+        };
+        for value in &self.include_vma {
+            my_size += ::protobuf::rt::string_size(3, &value);
+        };
+        for value in &self.exclude_vma {
+            my_size += ::protobuf::rt::string_size(4, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.path.is_empty() {
+            os.write_string(1, &self.path)?;
+        }
+        for v in &self.labels {
+            os.write_string(2, &v)?;
+        };
+        for v in &self.include_vma {
+            os.write_string(3, &v)?;
+        };
+        for v in &self.exclude_vma {
+            os.write_string(4, &v)?;
+        };
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> WatchCgroupRequest {
+        WatchCgroupRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.path.clear();
+        self.labels.clear();
+        self.include_vma.clear();
+        self.exclude_vma.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static WatchCgroupRequest {
+        static instance: WatchCgroupRequest = WatchCgroupRequest {
+            path: ::std::string::String::new(),
+            labels: ::std::vec::Vec::new(),
+            include_vma: ::std::vec::Vec::new(),
+            exclude_vma: ::std::vec::Vec::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for WatchCgroupRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("WatchCgroupRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for WatchCgroupRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for WatchCgroupRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.UnwatchCgroupRequest)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct UnwatchCgroupRequest {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.UnwatchCgroupRequest.path)
+    pub path: ::std::string::String,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.UnwatchCgroupRequest.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UnwatchCgroupRequest {
+    fn default() -> &'a UnwatchCgroupRequest {
+        <UnwatchCgroupRequest as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UnwatchCgroupRequest {
+    pub fn new() -> UnwatchCgroupRequest {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "path",
+            |m: &UnwatchCgroupRequest| { &m.path },
+            |m: &mut UnwatchCgroupRequest| { &mut m.path },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UnwatchCgroupRequest>(
+            "UnwatchCgroupRequest",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UnwatchCgroupRequest {
+    const NAME: &'static str = "UnwatchCgroupRequest";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.path = is.read_string()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.path.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.path);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.path.is_empty() {
+            os.write_string(1, &self.path)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UnwatchCgroupRequest {
+        UnwatchCgroupRequest::new()
+    }
+
+    fn clear(&mut self) {
+        self.path.clear();
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UnwatchCgroupRequest {
+        static instance: UnwatchCgroupRequest = UnwatchCgroupRequest {
+            path: ::std::string::String::new(),
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UnwatchCgroupRequest {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UnwatchCgroupRequest").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UnwatchCgroupRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UnwatchCgroupRequest {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.VersionResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct VersionResponse {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.VersionResponse.version)
+    pub version: ::std::string::String,
+    // @@protoc_insertion_point(field:MemAgent.VersionResponse.api_level)
+    pub api_level: u32,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.VersionResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a VersionResponse {
+    fn default() -> &'a VersionResponse {
+        <VersionResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl VersionResponse {
+    pub fn new() -> VersionResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(2);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "version",
+            |m: &VersionResponse| { &m.version },
+            |m: &mut VersionResponse| { &mut m.version },
+        ));
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "api_level",
+            |m: &VersionResponse| { &m.api_level },
+            |m: &mut VersionResponse| { &mut m.api_level },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<VersionResponse>(
+            "VersionResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for VersionResponse {
+    const NAME: &'static str = "VersionResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                10 => {
+                    self.version = is.read_string()?;
+                },
+                16 => {
+                    self.api_level = is.read_uint32()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if !self.version.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.version);
+        }
+        if self.api_level != 0 {
+            my_size += ::protobuf::rt::uint32_size(2, self.api_level);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if !self.version.is_empty() {
+            os.write_string(1, &self.version)?;
+        }
+        if self.api_level != 0 {
+            os.write_uint32(2, self.api_level)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> VersionResponse {
+        VersionResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.version.clear();
+        self.api_level = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static VersionResponse {
+        static instance: VersionResponse = VersionResponse {
+            version: ::std::string::String::new(),
+            api_level: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for VersionResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("VersionResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for VersionResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for VersionResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.DrainResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct DrainResponse {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.DrainResponse.elapsed_us)
+    pub elapsed_us: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.DrainResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a DrainResponse {
+    fn default() -> &'a DrainResponse {
+        <DrainResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl DrainResponse {
+    pub fn new() -> DrainResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "elapsed_us",
+            |m: &DrainResponse| { &m.elapsed_us },
+            |m: &mut DrainResponse| { &mut m.elapsed_us },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<DrainResponse>(
+            "DrainResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for DrainResponse {
+    const NAME: &'static str = "DrainResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.elapsed_us = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.elapsed_us != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.elapsed_us);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.elapsed_us != 0 {
+            os.write_uint64(1, self.elapsed_us)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> DrainResponse {
+        DrainResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.elapsed_us = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static DrainResponse {
+        static instance: DrainResponse = DrainResponse {
+            elapsed_us: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for DrainResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("DrainResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for DrainResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for DrainResponse {
+    type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
+}
+
+// @@protoc_insertion_point(message:MemAgent.UnmergeAllResponse)
+#[derive(PartialEq,Clone,Default,Debug)]
+pub struct UnmergeAllResponse {
+    // message fields
+    // @@protoc_insertion_point(field:MemAgent.UnmergeAllResponse.tasks)
+    pub tasks: u64,
+    // special fields
+    // @@protoc_insertion_point(special_field:MemAgent.UnmergeAllResponse.special_fields)
+    pub special_fields: ::protobuf::SpecialFields,
+}
+
+impl<'a> ::std::default::Default for &'a UnmergeAllResponse {
+    fn default() -> &'a UnmergeAllResponse {
+        <UnmergeAllResponse as ::protobuf::Message>::default_instance()
+    }
+}
+
+impl UnmergeAllResponse {
+    pub fn new() -> UnmergeAllResponse {
+        ::std::default::Default::default()
+    }
+
+    fn generated_message_descriptor_data() -> ::protobuf::reflect::GeneratedMessageDescriptorData {
+        let mut fields = ::std::vec::Vec::with_capacity(1);
+        let mut oneofs = ::std::vec::Vec::with_capacity(0);
+        fields.push(::protobuf::reflect::rt::v2::make_simpler_field_accessor::<_, _>(
+            "tasks",
+            |m: &UnmergeAllResponse| { &m.tasks },
+            |m: &mut UnmergeAllResponse| { &mut m.tasks },
+        ));
+        ::protobuf::reflect::GeneratedMessageDescriptorData::new_2::<UnmergeAllResponse>(
+            "UnmergeAllResponse",
+            fields,
+            oneofs,
+        )
+    }
+}
+
+impl ::protobuf::Message for UnmergeAllResponse {
+    const NAME: &'static str = "UnmergeAllResponse";
+
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream<'_>) -> ::protobuf::Result<()> {
+        while let Some(tag) = is.read_raw_tag_or_eof()? {
+            match tag {
+                8 => {
+                    self.tasks = is.read_uint64()?;
+                },
+                tag => {
+                    ::protobuf::rt::read_unknown_or_skip_group(tag, is, self.special_fields.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u64 {
+        let mut my_size = 0;
+        if self.tasks != 0 {
+            my_size += ::protobuf::rt::uint64_size(1, self.tasks);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.special_fields.unknown_fields());
+        self.special_fields.cached_size().set(my_size as u32);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream<'_>) -> ::protobuf::Result<()> {
+        if self.tasks != 0 {
+            os.write_uint64(1, self.tasks)?;
+        }
+        os.write_unknown_fields(self.special_fields.unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn special_fields(&self) -> &::protobuf::SpecialFields {
+        &self.special_fields
+    }
+
+    fn mut_special_fields(&mut self) -> &mut ::protobuf::SpecialFields {
+        &mut self.special_fields
+    }
+
+    fn new() -> UnmergeAllResponse {
+        UnmergeAllResponse::new()
+    }
+
+    fn clear(&mut self) {
+        self.tasks = 0;
+        self.special_fields.clear();
+    }
+
+    fn default_instance() -> &'static UnmergeAllResponse {
+        static instance: UnmergeAllResponse = UnmergeAllResponse {
+            tasks: 0,
+            special_fields: ::protobuf::SpecialFields::new(),
+        };
+        &instance
+    }
+}
+
+impl ::protobuf::MessageFull for UnmergeAllResponse {
+    fn descriptor() -> ::protobuf::reflect::MessageDescriptor {
+        static descriptor: ::protobuf::rt::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::rt::Lazy::new();
+        descriptor.get(|| file_descriptor().message_by_package_relative_name("UnmergeAllResponse").unwrap()).clone()
+    }
+}
+
+impl ::std::fmt::Display for UnmergeAllResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UnmergeAllResponse {
     type RuntimeType = ::protobuf::reflect::rt::RuntimeTypeMessage<Self>;
 }
 
 static file_descriptor_proto_data: &'static [u8] = b"\
     \n\x0fuksmd_ctl.proto\x12\x08MemAgent\x1a\x1bgoogle/protobuf/empty.proto\
     \".\n\x04Addr\x12\x14\n\x05start\x18\x01\x20\x01(\x04R\x05start\x12\x10\
-    \n\x03end\x18\x02\x20\x01(\x04R\x03end\"O\n\nAddRequest\x12\x10\n\x03pid\
-    \x18\x01\x20\x01(\x04R\x03pid\x12$\n\x04addr\x18\x02\x20\x01(\x0b2\x0e.M\
-    emAgent.AddrH\0R\x04addrB\t\n\x07OptAddr\"\x1e\n\nDelRequest\x12\x10\n\
-    \x03pid\x18\x01\x20\x01(\x04R\x03pid2\xe7\x01\n\x07Control\x123\n\x03Add\
-    \x12\x14.MemAgent.AddRequest\x1a\x16.google.protobuf.Empty\x123\n\x03Del\
-    \x12\x14.MemAgent.DelRequest\x1a\x16.google.protobuf.Empty\x129\n\x07Ref\
-    resh\x12\x16.google.protobuf.Empty\x1a\x16.google.protobuf.Empty\x127\n\
-    \x05Merge\x12\x16.google.protobuf.Empty\x1a\x16.google.protobuf.Emptyb\
-    \x06proto3\
+    \n\x03end\x18\x02\x20\x01(\x04R\x03end\"\xa0\x03\n\nAddRequest\x12\x10\n\
+    \x03pid\x18\x01\x20\x01(\x04R\x03pid\x12$\n\x04addr\x18\x02\x20\x01(\x0b\
+    2\x0e.MemAgent.AddrH\0R\x04addr\x12\x12\n\x03thp\x18\x03\x20\x01(\tH\x01\
+    R\x03thp\x12\x1f\n\x0binclude_vma\x18\x04\x20\x03(\tR\nincludeVma\x12\
+    \x1f\n\x0bexclude_vma\x18\x05\x20\x03(\tR\nexcludeVma\x12\"\n\x0cmin_vma\
+    _size\x18\x06\x20\x01(\x04H\x02R\nminVmaSize\x12\x1c\n\x08priority\x18\
+    \x07\x20\x01(\x05H\x03R\x08priority\x12'\n\x0ffollow_children\x18\x08\
+    \x20\x01(\x08R\x0efollowChildren\x12\x14\n\x05align\x18\t\x20\x01(\x08R\
+    \x05align\x12\x14\n\x05force\x18\n\x20\x01(\x08R\x05force\x12%\n\rinclud\
+    e_shmem\x18\x0b\x20\x01(\x08H\x04R\x0cincludeShmemB\t\n\x07OptAddrB\x08\
+    \n\x06OptThpB\x0f\n\rOptMinVmaSizeB\r\n\x0bOptPriorityB\x11\n\x0fOptIncl\
+    udeShmem\"\x8d\x03\n\x0bAddResponse\x12$\n\x04addr\x18\x01\x20\x01(\x0b2\
+    \x0e.MemAgent.AddrH\0R\x04addr\x12\x12\n\x04vmas\x18\x02\x20\x01(\x04R\
+    \x04vmas\x12\x14\n\x05bytes\x18\x03\x20\x01(\x04R\x05bytes\x12\x10\n\x03\
+    thp\x18\x04\x20\x01(\tR\x03thp\x12\x1f\n\x0binclude_vma\x18\x05\x20\x03(\
+    \tR\nincludeVma\x12\x1f\n\x0bexclude_vma\x18\x06\x20\x03(\tR\nexcludeVma\
+    \x12\x20\n\x0cmin_vma_size\x18\x07\x20\x01(\x04R\nminVmaSize\x12\x1a\n\
+    \x08priority\x18\x08\x20\x01(\x05R\x08priority\x12'\n\x0ffollow_children\
+    \x18\t\x20\x01(\x08R\x0efollowChildren\x12\x12\n\x04comm\x18\n\x20\x01(\
+    \tR\x04comm\x12\x1d\n\nstart_time\x18\x0b\x20\x01(\x04R\tstartTime\x12\
+    \x10\n\x03pid\x18\x0c\x20\x01(\x04R\x03pid\x12#\n\rinclude_shmem\x18\r\
+    \x20\x01(\x08R\x0cincludeShmemB\t\n\x07OptAddr\"b\n\x15AddByContainerReq\
+    uest\x12!\n\x0ccontainer_id\x18\x01\x20\x01(\tR\x0bcontainerId\x12&\n\
+    \x03add\x18\x02\x20\x01(\x0b2\x14.MemAgent.AddRequestR\x03add\"g\n\x0cMe\
+    rgeRequest\x12\x14\n\x05force\x18\x01\x20\x01(\x08R\x05force\x12\x10\n\
+    \x03pid\x18\x02\x20\x01(\x04R\x03pid\x12$\n\x04addr\x18\x03\x20\x01(\x0b\
+    2\x0e.MemAgent.AddrH\0R\x04addrB\t\n\x07OptAddr\"H\n\nDelRequest\x12\x10\
+    \n\x03pid\x18\x01\x20\x01(\x04R\x03pid\x12\x14\n\x05force\x18\x02\x20\
+    \x01(\x08R\x05force\x12\x12\n\x04wait\x18\x03\x20\x01(\x08R\x04wait\"$\n\
+    \x10PauseTaskRequest\x12\x10\n\x03pid\x18\x01\x20\x01(\x04R\x03pid\"%\n\
+    \x11ResumeTaskRequest\x12\x10\n\x03pid\x18\x01\x20\x01(\x04R\x03pid\"N\n\
+    \rUpdateRequest\x12\x10\n\x03pid\x18\x01\x20\x01(\x04R\x03pid\x12\x1c\n\
+    \x08priority\x18\x02\x20\x01(\x05H\0R\x08priorityB\r\n\x0bOptPriority\"k\
+    \n\x0eUnmergeRequest\x12\x16\n\x06budget\x18\x01\x20\x01(\x04R\x06budget\
+    \x12\x10\n\x03pid\x18\x02\x20\x01(\x04R\x03pid\x12$\n\x04addr\x18\x03\
+    \x20\x01(\x0b2\x0e.MemAgent.AddrH\0R\x04addrB\t\n\x07OptAddr\"\x86\x13\n\
+    \x0eStatusResponse\x12\x1f\n\x0bworker_nice\x18\x01\x20\x01(\x05R\nworke\
+    rNice\x12*\n\x11worker_sched_idle\x18\x02\x20\x01(\x08R\x0fworkerSchedId\
+    le\x12,\n\x12worker_ioprio_idle\x18\x03\x20\x01(\x08R\x10workerIoprioIdl\
+    e\x12#\n\rskipped_pages\x18\x04\x20\x01(\x04R\x0cskippedPages\x12#\n\rme\
+    rge_batched\x18\x05\x20\x01(\x08R\x0cmergeBatched\x12\x18\n\x07backend\
+    \x18\x06\x20\x01(\tR\x07backend\x12\x1f\n\x0bkernel_caps\x18\x07\x20\x03\
+    (\tR\nkernelCaps\x12%\n\x0ekernel_version\x18\x08\x20\x01(\tR\rkernelVer\
+    sion\x12F\n\nzero_pages\x18\t\x20\x03(\x0b2'.MemAgent.StatusResponse.Zer\
+    oPagesEntryR\tzeroPages\x12C\n\tthp_pages\x18\n\x20\x03(\x0b2&.MemAgent.\
+    StatusResponse.ThpPagesEntryR\x08thpPages\x126\n\x04comm\x18\x0b\x20\x03\
+    (\x0b2\".MemAgent.StatusResponse.CommEntryR\x04comm\x12?\n\x07cmdline\
+    \x18\x0c\x20\x03(\x0b2%.MemAgent.StatusResponse.CmdlineEntryR\x07cmdline\
+    \x12\x1f\n\x0bpaused_pids\x18\r\x20\x03(\x04R\npausedPids\x12B\n\x08prio\
+    rity\x18\x0e\x20\x03(\x0b2&.MemAgent.StatusResponse.PriorityEntryR\x08pr\
+    iority\x12*\n\x11cross_uid_blocked\x18\x0f\x20\x01(\x04R\x0fcrossUidBloc\
+    ked\x12<\n\x06parent\x18\x10\x20\x03(\x0b2$.MemAgent.StatusResponse.Pare\
+    ntEntryR\x06parent\x12*\n\x11merge_window_open\x18\x11\x20\x01(\x08R\x0f\
+    mergeWindowOpen\x12*\n\x11next_merge_window\x18\x12\x20\x01(\tR\x0fnextM\
+    ergeWindow\x12%\n\x0emerge_deferred\x18\x13\x20\x01(\x08R\rmergeDeferred\
+    \x12#\n\rload_throttle\x18\x14\x20\x01(\tR\x0cloadThrottle\x12L\n\x0cwat\
+    ch_cgroup\x18\x15\x20\x03(\x0b2).MemAgent.StatusResponse.WatchCgroupEntr\
+    yR\x0bwatchCgroup\x12<\n\x06labels\x18\x16\x20\x03(\x0b2$.MemAgent.Statu\
+    sResponse.LabelsEntryR\x06labels\x12'\n\x0fwatched_cgroups\x18\x17\x20\
+    \x03(\tR\x0ewatchedCgroups\x12\x1e\n\ndiscovered\x18\x18\x20\x03(\x04R\n\
+    discovered\x12&\n\x0fcmd_queue_depth\x18\x19\x20\x01(\x04R\rcmdQueueDept\
+    h\x12-\n\x13cmd_queue_oldest_ms\x18\x1a\x20\x01(\x04R\x10cmdQueueOldestM\
+    s\x12F\n\ntask_state\x18\x1b\x20\x03(\x0b2'.MemAgent.StatusResponse.Task\
+    StateEntryR\ttaskState\x12\x1f\n\x0bkernel_lost\x18\x1c\x20\x01(\x08R\nk\
+    ernelLost\x12U\n\x0fmerge_remaining\x18\x1d\x20\x03(\x0b2,.MemAgent.Stat\
+    usResponse.MergeRemainingEntryR\x0emergeRemaining\x12,\n\x12last_merge_d\
+    rained\x18\x1e\x20\x01(\x08R\x10lastMergeDrained\x12-\n\x13last_merge_dr\
+    ain_us\x18\x1f\x20\x01(\x04R\x10lastMergeDrainUs\x12I\n\x0bstale_pages\
+    \x18\x20\x20\x03(\x0b2(.MemAgent.StatusResponse.StalePagesEntryR\nstaleP\
+    ages\x12Y\n\x11foreign_ksm_pages\x18!\x20\x03(\x0b2-.MemAgent.StatusResp\
+    onse.ForeignKsmPagesEntryR\x0fforeignKsmPages\x1a<\n\x0eZeroPagesEntry\
+    \x12\x10\n\x03key\x18\x01\x20\x01(\x04R\x03key\x12\x14\n\x05value\x18\
+    \x02\x20\x01(\x04R\x05value:\x028\x01\x1a;\n\rThpPagesEntry\x12\x10\n\
+    \x03key\x18\x01\x20\x01(\x04R\x03key\x12\x14\n\x05value\x18\x02\x20\x01(\
+    \x04R\x05value:\x028\x01\x1a7\n\tCommEntry\x12\x10\n\x03key\x18\x01\x20\
+    \x01(\x04R\x03key\x12\x14\n\x05value\x18\x02\x20\x01(\tR\x05value:\x028\
+    \x01\x1a:\n\x0cCmdlineEntry\x12\x10\n\x03key\x18\x01\x20\x01(\x04R\x03ke\
+    y\x12\x14\n\x05value\x18\x02\x20\x01(\tR\x05value:\x028\x01\x1a;\n\rPrio\
+    rityEntry\x12\x10\n\x03key\x18\x01\x20\x01(\x04R\x03key\x12\x14\n\x05val\
+    ue\x18\x02\x20\x01(\x05R\x05value:\x028\x01\x1a9\n\x0bParentEntry\x12\
+    \x10\n\x03key\x18\x01\x20\x01(\x04R\x03key\x12\x14\n\x05value\x18\x02\
+    \x20\x01(\x04R\x05value:\x028\x01\x1a>\n\x10WatchCgroupEntry\x12\x10\n\
+    \x03key\x18\x01\x20\x01(\x04R\x03key\x12\x14\n\x05value\x18\x02\x20\x01(\
+    \tR\x05value:\x028\x01\x1a9\n\x0bLabelsEntry\x12\x10\n\x03key\x18\x01\
+    \x20\x01(\x04R\x03key\x12\x14\n\x05value\x18\x02\x20\x01(\tR\x05value:\
+    \x028\x01\x1a<\n\x0eTaskStateEntry\x12\x10\n\x03key\x18\x01\x20\x01(\x04\
+    R\x03key\x12\x14\n\x05value\x18\x02\x20\x01(\tR\x05value:\x028\x01\x1aA\
+    \n\x13MergeRemainingEntry\x12\x10\n\x03key\x18\x01\x20\x01(\x04R\x03key\
+    \x12\x14\n\x05value\x18\x02\x20\x01(\x04R\x05value:\x028\x01\x1a=\n\x0fS\
+    talePagesEntry\x12\x10\n\x03key\x18\x01\x20\x01(\x04R\x03key\x12\x14\n\
+    \x05value\x18\x02\x20\x01(\x04R\x05value:\x028\x01\x1aB\n\x14ForeignKsmP\
+    agesEntry\x12\x10\n\x03key\x18\x01\x20\x01(\x04R\x03key\x12\x14\n\x05val\
+    ue\x18\x02\x20\x01(\x04R\x05value:\x028\x01\"\xaf\x0e\n\rStatsResponse\
+    \x12<\n\x1brefresh_pages_per_sec_limit\x18\x01\x20\x01(\x04R\x17refreshP\
+    agesPerSecLimit\x121\n\x15refresh_pages_per_sec\x18\x02\x20\x01(\x04R\
+    \x12refreshPagesPerSec\x128\n\x19merge_pages_per_sec_limit\x18\x03\x20\
+    \x01(\x04R\x15mergePagesPerSecLimit\x12-\n\x13merge_pages_per_sec\x18\
+    \x04\x20\x01(\x04R\x10mergePagesPerSec\x12\x14\n\x05tasks\x18\x05\x20\
+    \x01(\x04R\x05tasks\x12\x1b\n\tmax_tasks\x18\x06\x20\x01(\x04R\x08maxTas\
+    ks\x12#\n\rtracked_pages\x18\x07\x20\x01(\x04R\x0ctrackedPages\x12*\n\
+    \x11max_tracked_pages\x18\x08\x20\x01(\x04R\x0fmaxTrackedPages\x12$\n\
+    \x0emerge_give_ups\x18\t\x20\x01(\x04R\x0cmergeGiveUps\x12%\n\x0ekernel_\
+    retries\x18\n\x20\x01(\x04R\rkernelRetries\x12%\n\x0emerge_deferred\x18\
+    \x0b\x20\x01(\x04R\rmergeDeferred\x12K\n\x0ckernel_stats\x18\x0c\x20\x03\
+    (\x0b2(.MemAgent.StatsResponse.KernelStatsEntryR\x0bkernelStats\x12.\n\
+    \x13verify_broken_pages\x18\r\x20\x01(\x04R\x11verifyBrokenPages\x12\"\n\
+    \rmin_dup_count\x18\x0e\x20\x01(\rR\x0bminDupCount\x125\n\x17merge_dup_s\
+    kipped_pages\x18\x0f\x20\x01(\x04R\x14mergeDupSkippedPages\x12\x1d\n\nze\
+    ro_pages\x18\x10\x20\x01(\x04R\tzeroPages\x12\x1f\n\x0bmerge_scope\x18\
+    \x11\x20\x01(\tR\nmergeScope\x12.\n\x13merge_scope_skipped\x18\x12\x20\
+    \x01(\x04R\x11mergeScopeSkipped\x12!\n\x0cmerged_pages\x18\x13\x20\x01(\
+    \x04R\x0bmergedPages\x12%\n\x0eunmerged_pages\x18\x14\x20\x01(\x04R\runm\
+    ergedPages\x12!\n\x0cfailed_tasks\x18\x15\x20\x01(\x04R\x0bfailedTasks\
+    \x12'\n\x0fmemory_pressure\x18\x16\x20\x01(\x01R\x0ememoryPressure\x12)\
+    \n\x10pressure_merging\x18\x17\x20\x01(\x08R\x0fpressureMerging\x12#\n\r\
+    mem_available\x18\x18\x20\x01(\x04R\x0cmemAvailable\x12(\n\x10free_mem_m\
+    erging\x18\x19\x20\x01(\x08R\x0efreeMemMerging\x12.\n\x13unmerged_by_req\
+    uest\x18\x1a\x20\x01(\x04R\x11unmergedByRequest\x12,\n\x12unmerged_by_po\
+    licy\x18\x1b\x20\x01(\x04R\x10unmergedByPolicy\x12!\n\x0ccpu_pressure\
+    \x18\x1c\x20\x01(\x01R\x0bcpuPressure\x121\n\nop_timings\x18\x1d\x20\x03\
+    (\x0b2\x12.MemAgent.OpTimingR\topTimings\x12\x1f\n\x0bstale_pages\x18\
+    \x1e\x20\x01(\x04R\nstalePages\x12.\n\x13merge_shared_frames\x18\x1f\x20\
+    \x01(\x04R\x11mergeSharedFrames\x12\x1b\n\trss_bytes\x18\x20\x20\x01(\
+    \x04R\x08rssBytes\x12$\n\x0epeak_rss_bytes\x18!\x20\x01(\x04R\x0cpeakRss\
+    Bytes\x12%\n\x0einternal_bytes\x18\"\x20\x01(\x04R\rinternalBytes\x12\"\
+    \n\rworker_cpu_us\x18#\x20\x01(\x04R\x0bworkerCpuUs\x12\x1f\n\x0bsaved_b\
+    ytes\x18$\x20\x01(\x04R\nsavedBytes\x12&\n\x0fsaved_rss_ratio\x18%\x20\
+    \x01(\x01R\rsavedRssRatio\x123\n\x16last_merge_crc_matched\x18&\x20\x01(\
+    \x04R\x13lastMergeCrcMatched\x123\n\x16last_merge_first_probe\x18'\x20\
+    \x01(\x04R\x13lastMergeFirstProbe\x123\n\x16last_merge_multi_probe\x18(\
+    \x20\x01(\x04R\x13lastMergeMultiProbe\x129\n\x19last_merge_crc_collision\
+    s\x18)\x20\x01(\x04R\x16lastMergeCrcCollisions\x1a>\n\x10KernelStatsEntr\
+    y\x12\x10\n\x03key\x18\x01\x20\x01(\tR\x03key\x12\x14\n\x05value\x18\x02\
+    \x20\x01(\x04R\x05value:\x028\x01\"\xb1\x02\n\x10SetConfigRequest\x123\n\
+    \x15refresh_pages_per_sec\x18\x01\x20\x01(\x04H\0R\x12refreshPagesPerSec\
+    \x12/\n\x13merge_pages_per_sec\x18\x02\x20\x01(\x04H\x01R\x10mergePagesP\
+    erSec\x12$\n\rmin_dup_count\x18\x03\x20\x01(\rH\x02R\x0bminDupCount\x12!\
+    \n\x0bmerge_scope\x18\x04\x20\x01(\tH\x03R\nmergeScope\x12\x1b\n\x08max_\
+    load\x18\x05\x20\x01(\x01H\x04R\x07maxLoadB\x10\n\x0eOptRefreshRateB\x0e\
+    \n\x0cOptMergeRateB\x10\n\x0eOptMinDupCountB\x0f\n\rOptMergeScopeB\x0c\n\
+    \nOptMaxLoad\"\"\n\x0eHistoryRequest\x12\x10\n\x03pid\x18\x01\x20\x01(\
+    \x04R\x03pid\"c\n\rHistorySample\x12\x12\n\x04time\x18\x01\x20\x01(\x04R\
+    \x04time\x12\x1d\n\nuksm_count\x18\x02\x20\x01(\x04R\tuksmCount\x12\x1f\
+    \n\x0bsaved_bytes\x18\x03\x20\x01(\x04R\nsavedBytes\"D\n\x0fHistoryRespo\
+    nse\x121\n\x07samples\x18\x01\x20\x03(\x0b2\x17.MemAgent.HistorySampleR\
+    \x07samples\"f\n\x08OpTiming\x12\x12\n\x04name\x18\x01\x20\x01(\tR\x04na\
+    me\x12\x14\n\x05calls\x18\x02\x20\x01(\x04R\x05calls\x12\x19\n\x08total_\
+    us\x18\x03\x20\x01(\x04R\x07totalUs\x12\x15\n\x06max_us\x18\x04\x20\x01(\
+    \x04R\x05maxUs\"+\n\x13RecentEventsRequest\x12\x14\n\x05count\x18\x01\
+    \x20\x01(\x04R\x05count\"W\n\x0bRecentEvent\x12\x12\n\x04time\x18\x01\
+    \x20\x01(\tR\x04time\x12\x1a\n\x08severity\x18\x02\x20\x01(\tR\x08severi\
+    ty\x12\x18\n\x07message\x18\x03\x20\x01(\tR\x07message\"E\n\x14RecentEve\
+    ntsResponse\x12-\n\x06events\x18\x01\x20\x03(\x0b2\x15.MemAgent.RecentEv\
+    entR\x06events\"\"\n\x0eGetMapsRequest\x12\x10\n\x03pid\x18\x01\x20\x01(\
+    \x04R\x03pid\"M\n\x0bRejectedMap\x12\x14\n\x05start\x18\x01\x20\x01(\x04\
+    R\x05start\x12\x10\n\x03end\x18\x02\x20\x01(\x04R\x03end\x12\x16\n\x06re\
+    ason\x18\x03\x20\x01(\tR\x06reason\"h\n\x0fGetMapsResponse\x12\"\n\x04ma\
+    ps\x18\x01\x20\x03(\x0b2\x0e.MemAgent.AddrR\x04maps\x121\n\x08rejected\
+    \x18\x02\x20\x03(\x0b2\x15.MemAgent.RejectedMapR\x08rejected\"'\n\x13Crc\
+    HistogramRequest\x12\x10\n\x03top\x18\x01\x20\x01(\x04R\x03top\"K\n\tCrc\
+    Bucket\x12\x14\n\x05range\x18\x01\x20\x01(\tR\x05range\x12\x12\n\x04crcs\
+    \x18\x02\x20\x01(\x04R\x04crcs\x12\x14\n\x05pages\x18\x03\x20\x01(\x04R\
+    \x05pages\"2\n\x08CrcCount\x12\x10\n\x03crc\x18\x01\x20\x01(\rR\x03crc\
+    \x12\x14\n\x05pages\x18\x02\x20\x01(\x04R\x05pages\"\xe6\x01\n\x14CrcHis\
+    togramResponse\x12+\n\x06merged\x18\x01\x20\x03(\x0b2\x13.MemAgent.CrcBu\
+    cketR\x06merged\x123\n\ncandidates\x18\x02\x20\x03(\x0b2\x13.MemAgent.Cr\
+    cBucketR\ncandidates\x121\n\ntop_merged\x18\x03\x20\x03(\x0b2\x12.MemAge\
+    nt.CrcCountR\ttopMerged\x129\n\x0etop_candidates\x18\x04\x20\x03(\x0b2\
+    \x12.MemAgent.CrcCountR\rtopCandidates\"\x82\x01\n\x12WatchCgroupRequest\
+    \x12\x12\n\x04path\x18\x01\x20\x01(\tR\x04path\x12\x16\n\x06labels\x18\
+    \x02\x20\x03(\tR\x06labels\x12\x1f\n\x0binclude_vma\x18\x03\x20\x03(\tR\
+    \nincludeVma\x12\x1f\n\x0bexclude_vma\x18\x04\x20\x03(\tR\nexcludeVma\"*\
+    \n\x14UnwatchCgroupRequest\x12\x12\n\x04path\x18\x01\x20\x01(\tR\x04path\
+    \"H\n\x0fVersionResponse\x12\x18\n\x07version\x18\x01\x20\x01(\tR\x07ver\
+    sion\x12\x1b\n\tapi_level\x18\x02\x20\x01(\rR\x08apiLevel\".\n\rDrainRes\
+    ponse\x12\x1d\n\nelapsed_us\x18\x01\x20\x01(\x04R\telapsedUs\"*\n\x12Unm\
+    ergeAllResponse\x12\x14\n\x05tasks\x18\x01\x20\x01(\x04R\x05tasks2\xce\n\
+    \n\x07Control\x122\n\x03Add\x12\x14.MemAgent.AddRequest\x1a\x15.MemAgent\
+    .AddResponse\x12H\n\x0eAddByContainer\x12\x1f.MemAgent.AddByContainerReq\
+    uest\x1a\x15.MemAgent.AddResponse\x123\n\x03Del\x12\x14.MemAgent.DelRequ\
+    est\x1a\x16.google.protobuf.Empty\x129\n\x07Refresh\x12\x16.google.proto\
+    buf.Empty\x1a\x16.google.protobuf.Empty\x127\n\x05Merge\x12\x16.MemAgent\
+    .MergeRequest\x1a\x16.google.protobuf.Empty\x12:\n\x06Status\x12\x16.goo\
+    gle.protobuf.Empty\x1a\x18.MemAgent.StatusResponse\x128\n\x05Stats\x12\
+    \x16.google.protobuf.Empty\x1a\x17.MemAgent.StatsResponse\x12?\n\tSetCon\
+    fig\x12\x1a.MemAgent.SetConfigRequest\x1a\x16.google.protobuf.Empty\x12?\
+    \n\tPauseTask\x12\x1a.MemAgent.PauseTaskRequest\x1a\x16.google.protobuf.\
+    Empty\x12A\n\nResumeTask\x12\x1b.MemAgent.ResumeTaskRequest\x1a\x16.goog\
+    le.protobuf.Empty\x12;\n\x07Unmerge\x12\x18.MemAgent.UnmergeRequest\x1a\
+    \x16.google.protobuf.Empty\x12>\n\x07History\x12\x18.MemAgent.HistoryReq\
+    uest\x1a\x19.MemAgent.HistoryResponse\x12M\n\x0cRecentEvents\x12\x1d.Mem\
+    Agent.RecentEventsRequest\x1a\x1e.MemAgent.RecentEventsResponse\x12>\n\
+    \x07GetMaps\x12\x18.MemAgent.GetMapsRequest\x1a\x19.MemAgent.GetMapsResp\
+    onse\x12M\n\x0cCrcHistogram\x12\x1d.MemAgent.CrcHistogramRequest\x1a\x1e\
+    .MemAgent.CrcHistogramResponse\x12C\n\x0bWatchCgroup\x12\x1c.MemAgent.Wa\
+    tchCgroupRequest\x1a\x16.google.protobuf.Empty\x12G\n\rUnwatchCgroup\x12\
+    \x1e.MemAgent.UnwatchCgroupRequest\x1a\x16.google.protobuf.Empty\x12?\n\
+    \nGetVersion\x12\x16.google.protobuf.Empty\x1a\x19.MemAgent.VersionRespo\
+    nse\x128\n\x05Drain\x12\x16.google.protobuf.Empty\x1a\x17.MemAgent.Drain\
+    Response\x12B\n\nUnmergeAll\x12\x16.google.protobuf.Empty\x1a\x1c.MemAge\
+    nt.UnmergeAllResponse\x129\n\x06Update\x12\x17.MemAgent.UpdateRequest\
+    \x1a\x16.google.protobuf.Emptyb\x06proto3\
 ";
 
 /// `FileDescriptorProto` object which was a source for this generated file
@@ -546,10 +7642,39 @@ pub fn file_descriptor() -> &'static ::protobuf::reflect::FileDescriptor {
         let generated_file_descriptor = generated_file_descriptor_lazy.get(|| {
             let mut deps = ::std::vec::Vec::with_capacity(1);
             deps.push(::protobuf::well_known_types::empty::file_descriptor().clone());
-            let mut messages = ::std::vec::Vec::with_capacity(3);
+            let mut messages = ::std::vec::Vec::with_capacity(32);
             messages.push(Addr::generated_message_descriptor_data());
             messages.push(AddRequest::generated_message_descriptor_data());
+            messages.push(AddResponse::generated_message_descriptor_data());
+            messages.push(AddByContainerRequest::generated_message_descriptor_data());
+            messages.push(MergeRequest::generated_message_descriptor_data());
             messages.push(DelRequest::generated_message_descriptor_data());
+            messages.push(PauseTaskRequest::generated_message_descriptor_data());
+            messages.push(ResumeTaskRequest::generated_message_descriptor_data());
+            messages.push(UpdateRequest::generated_message_descriptor_data());
+            messages.push(UnmergeRequest::generated_message_descriptor_data());
+            messages.push(StatusResponse::generated_message_descriptor_data());
+            messages.push(StatsResponse::generated_message_descriptor_data());
+            messages.push(SetConfigRequest::generated_message_descriptor_data());
+            messages.push(HistoryRequest::generated_message_descriptor_data());
+            messages.push(HistorySample::generated_message_descriptor_data());
+            messages.push(HistoryResponse::generated_message_descriptor_data());
+            messages.push(OpTiming::generated_message_descriptor_data());
+            messages.push(RecentEventsRequest::generated_message_descriptor_data());
+            messages.push(RecentEvent::generated_message_descriptor_data());
+            messages.push(RecentEventsResponse::generated_message_descriptor_data());
+            messages.push(GetMapsRequest::generated_message_descriptor_data());
+            messages.push(RejectedMap::generated_message_descriptor_data());
+            messages.push(GetMapsResponse::generated_message_descriptor_data());
+            messages.push(CrcHistogramRequest::generated_message_descriptor_data());
+            messages.push(CrcBucket::generated_message_descriptor_data());
+            messages.push(CrcCount::generated_message_descriptor_data());
+            messages.push(CrcHistogramResponse::generated_message_descriptor_data());
+            messages.push(WatchCgroupRequest::generated_message_descriptor_data());
+            messages.push(UnwatchCgroupRequest::generated_message_descriptor_data());
+            messages.push(VersionResponse::generated_message_descriptor_data());
+            messages.push(DrainResponse::generated_message_descriptor_data());
+            messages.push(UnmergeAllResponse::generated_message_descriptor_data());
             let mut enums = ::std::vec::Vec::with_capacity(0);
             ::protobuf::reflect::GeneratedFileDescriptor::new_generated(
                 file_descriptor_proto(),
@@ -32,11 +32,16 @@ impl ControlClient {
         }
     }
 
-    pub async fn add(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::AddRequest) -> ::ttrpc::Result<super::empty::Empty> {
-        let mut cres = super::empty::Empty::new();
+    pub async fn add(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::AddRequest) -> ::ttrpc::Result<super::uksmd_ctl::AddResponse> {
+        let mut cres = super::uksmd_ctl::AddResponse::new();
         ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "Add", cres);
     }
 
+    pub async fn add_by_container(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::AddByContainerRequest) -> ::ttrpc::Result<super::uksmd_ctl::AddResponse> {
+        let mut cres = super::uksmd_ctl::AddResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "AddByContainer", cres);
+    }
+
     pub async fn del(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::DelRequest) -> ::ttrpc::Result<super::empty::Empty> {
         let mut cres = super::empty::Empty::new();
         ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "Del", cres);
@@ -47,10 +52,90 @@ impl ControlClient {
         ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "Refresh", cres);
     }
 
-    pub async fn merge(&self, ctx: ttrpc::context::Context, req: &super::empty::Empty) -> ::ttrpc::Result<super::empty::Empty> {
+    pub async fn merge(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::MergeRequest) -> ::ttrpc::Result<super::empty::Empty> {
         let mut cres = super::empty::Empty::new();
         ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "Merge", cres);
     }
+
+    pub async fn status(&self, ctx: ttrpc::context::Context, req: &super::empty::Empty) -> ::ttrpc::Result<super::uksmd_ctl::StatusResponse> {
+        let mut cres = super::uksmd_ctl::StatusResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "Status", cres);
+    }
+
+    pub async fn stats(&self, ctx: ttrpc::context::Context, req: &super::empty::Empty) -> ::ttrpc::Result<super::uksmd_ctl::StatsResponse> {
+        let mut cres = super::uksmd_ctl::StatsResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "Stats", cres);
+    }
+
+    pub async fn set_config(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::SetConfigRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        let mut cres = super::empty::Empty::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "SetConfig", cres);
+    }
+
+    pub async fn pause_task(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::PauseTaskRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        let mut cres = super::empty::Empty::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "PauseTask", cres);
+    }
+
+    pub async fn resume_task(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::ResumeTaskRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        let mut cres = super::empty::Empty::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "ResumeTask", cres);
+    }
+
+    pub async fn unmerge(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::UnmergeRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        let mut cres = super::empty::Empty::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "Unmerge", cres);
+    }
+
+    pub async fn history(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::HistoryRequest) -> ::ttrpc::Result<super::uksmd_ctl::HistoryResponse> {
+        let mut cres = super::uksmd_ctl::HistoryResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "History", cres);
+    }
+
+    pub async fn recent_events(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::RecentEventsRequest) -> ::ttrpc::Result<super::uksmd_ctl::RecentEventsResponse> {
+        let mut cres = super::uksmd_ctl::RecentEventsResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "RecentEvents", cres);
+    }
+
+    pub async fn get_maps(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::GetMapsRequest) -> ::ttrpc::Result<super::uksmd_ctl::GetMapsResponse> {
+        let mut cres = super::uksmd_ctl::GetMapsResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "GetMaps", cres);
+    }
+
+    pub async fn crc_histogram(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::CrcHistogramRequest) -> ::ttrpc::Result<super::uksmd_ctl::CrcHistogramResponse> {
+        let mut cres = super::uksmd_ctl::CrcHistogramResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "CrcHistogram", cres);
+    }
+
+    pub async fn watch_cgroup(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::WatchCgroupRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        let mut cres = super::empty::Empty::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "WatchCgroup", cres);
+    }
+
+    pub async fn unwatch_cgroup(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::UnwatchCgroupRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        let mut cres = super::empty::Empty::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "UnwatchCgroup", cres);
+    }
+
+    pub async fn get_version(&self, ctx: ttrpc::context::Context, req: &super::empty::Empty) -> ::ttrpc::Result<super::uksmd_ctl::VersionResponse> {
+        let mut cres = super::uksmd_ctl::VersionResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "GetVersion", cres);
+    }
+
+    pub async fn drain(&self, ctx: ttrpc::context::Context, req: &super::empty::Empty) -> ::ttrpc::Result<super::uksmd_ctl::DrainResponse> {
+        let mut cres = super::uksmd_ctl::DrainResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "Drain", cres);
+    }
+
+    pub async fn unmerge_all(&self, ctx: ttrpc::context::Context, req: &super::empty::Empty) -> ::ttrpc::Result<super::uksmd_ctl::UnmergeAllResponse> {
+        let mut cres = super::uksmd_ctl::UnmergeAllResponse::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "UnmergeAll", cres);
+    }
+
+    pub async fn update(&self, ctx: ttrpc::context::Context, req: &super::uksmd_ctl::UpdateRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        let mut cres = super::empty::Empty::new();
+        ::ttrpc::async_client_request!(self, ctx, req, "MemAgent.Control", "Update", cres);
+    }
 }
 
 struct AddMethod {
@@ -64,6 +149,17 @@ impl ::ttrpc::r#async::MethodHandler for AddMethod {
     }
 }
 
+struct AddByContainerMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for AddByContainerMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, AddByContainerRequest, add_by_container);
+    }
+}
+
 struct DelMethod {
     service: Arc<Box<dyn Control + Send + Sync>>,
 }
@@ -93,24 +189,251 @@ struct MergeMethod {
 #[async_trait]
 impl ::ttrpc::r#async::MethodHandler for MergeMethod {
     async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
-        ::ttrpc::async_request_handler!(self, ctx, req, empty, Empty, merge);
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, MergeRequest, merge);
+    }
+}
+
+struct StatusMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for StatusMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, empty, Empty, status);
+    }
+}
+
+struct StatsMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for StatsMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, empty, Empty, stats);
+    }
+}
+
+struct SetConfigMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for SetConfigMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, SetConfigRequest, set_config);
+    }
+}
+
+struct PauseTaskMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for PauseTaskMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, PauseTaskRequest, pause_task);
+    }
+}
+
+struct ResumeTaskMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for ResumeTaskMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, ResumeTaskRequest, resume_task);
+    }
+}
+
+struct UnmergeMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for UnmergeMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, UnmergeRequest, unmerge);
+    }
+}
+
+struct HistoryMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for HistoryMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, HistoryRequest, history);
+    }
+}
+
+struct RecentEventsMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for RecentEventsMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, RecentEventsRequest, recent_events);
+    }
+}
+
+struct GetMapsMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for GetMapsMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, GetMapsRequest, get_maps);
+    }
+}
+
+struct CrcHistogramMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for CrcHistogramMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, CrcHistogramRequest, crc_histogram);
+    }
+}
+
+struct WatchCgroupMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for WatchCgroupMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, WatchCgroupRequest, watch_cgroup);
+    }
+}
+
+struct UnwatchCgroupMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for UnwatchCgroupMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, UnwatchCgroupRequest, unwatch_cgroup);
+    }
+}
+
+struct GetVersionMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for GetVersionMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, empty, Empty, get_version);
+    }
+}
+
+struct DrainMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for DrainMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, empty, Empty, drain);
+    }
+}
+
+struct UnmergeAllMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for UnmergeAllMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, empty, Empty, unmerge_all);
+    }
+}
+
+struct UpdateMethod {
+    service: Arc<Box<dyn Control + Send + Sync>>,
+}
+
+#[async_trait]
+impl ::ttrpc::r#async::MethodHandler for UpdateMethod {
+    async fn handler(&self, ctx: ::ttrpc::r#async::TtrpcContext, req: ::ttrpc::Request) -> ::ttrpc::Result<::ttrpc::Response> {
+        ::ttrpc::async_request_handler!(self, ctx, req, uksmd_ctl, UpdateRequest, update);
     }
 }
 
 #[async_trait]
 pub trait Control: Sync {
-    async fn add(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::AddRequest) -> ::ttrpc::Result<super::empty::Empty> {
+    async fn add(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::AddRequest) -> ::ttrpc::Result<super::uksmd_ctl::AddResponse> {
         Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/Add is not supported".to_string())))
     }
+    async fn add_by_container(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::AddByContainerRequest) -> ::ttrpc::Result<super::uksmd_ctl::AddResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/AddByContainer is not supported".to_string())))
+    }
     async fn del(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::DelRequest) -> ::ttrpc::Result<super::empty::Empty> {
         Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/Del is not supported".to_string())))
     }
     async fn refresh(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::empty::Empty) -> ::ttrpc::Result<super::empty::Empty> {
         Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/Refresh is not supported".to_string())))
     }
-    async fn merge(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::empty::Empty) -> ::ttrpc::Result<super::empty::Empty> {
+    async fn merge(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::MergeRequest) -> ::ttrpc::Result<super::empty::Empty> {
         Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/Merge is not supported".to_string())))
     }
+    async fn status(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::empty::Empty) -> ::ttrpc::Result<super::uksmd_ctl::StatusResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/Status is not supported".to_string())))
+    }
+    async fn stats(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::empty::Empty) -> ::ttrpc::Result<super::uksmd_ctl::StatsResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/Stats is not supported".to_string())))
+    }
+    async fn set_config(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::SetConfigRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/SetConfig is not supported".to_string())))
+    }
+    async fn pause_task(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::PauseTaskRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/PauseTask is not supported".to_string())))
+    }
+    async fn resume_task(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::ResumeTaskRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/ResumeTask is not supported".to_string())))
+    }
+    async fn unmerge(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::UnmergeRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/Unmerge is not supported".to_string())))
+    }
+    async fn history(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::HistoryRequest) -> ::ttrpc::Result<super::uksmd_ctl::HistoryResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/History is not supported".to_string())))
+    }
+    async fn recent_events(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::RecentEventsRequest) -> ::ttrpc::Result<super::uksmd_ctl::RecentEventsResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/RecentEvents is not supported".to_string())))
+    }
+    async fn get_maps(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::GetMapsRequest) -> ::ttrpc::Result<super::uksmd_ctl::GetMapsResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/GetMaps is not supported".to_string())))
+    }
+    async fn crc_histogram(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::CrcHistogramRequest) -> ::ttrpc::Result<super::uksmd_ctl::CrcHistogramResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/CrcHistogram is not supported".to_string())))
+    }
+    async fn watch_cgroup(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::WatchCgroupRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/WatchCgroup is not supported".to_string())))
+    }
+    async fn unwatch_cgroup(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::UnwatchCgroupRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/UnwatchCgroup is not supported".to_string())))
+    }
+    async fn get_version(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::empty::Empty) -> ::ttrpc::Result<super::uksmd_ctl::VersionResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/GetVersion is not supported".to_string())))
+    }
+    async fn drain(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::empty::Empty) -> ::ttrpc::Result<super::uksmd_ctl::DrainResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/Drain is not supported".to_string())))
+    }
+    async fn unmerge_all(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::empty::Empty) -> ::ttrpc::Result<super::uksmd_ctl::UnmergeAllResponse> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/UnmergeAll is not supported".to_string())))
+    }
+    async fn update(&self, _ctx: &::ttrpc::r#async::TtrpcContext, _: super::uksmd_ctl::UpdateRequest) -> ::ttrpc::Result<super::empty::Empty> {
+        Err(::ttrpc::Error::RpcStatus(::ttrpc::get_status(::ttrpc::Code::NOT_FOUND, "/MemAgent.Control/Update is not supported".to_string())))
+    }
 }
 
 pub fn create_control(service: Arc<Box<dyn Control + Send + Sync>>) -> HashMap<String, ::ttrpc::r#async::Service> {
@@ -121,6 +444,9 @@ pub fn create_control(service: Arc<Box<dyn Control + Send + Sync>>) -> HashMap<S
     methods.insert("Add".to_string(),
                     Box::new(AddMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
 
+    methods.insert("AddByContainer".to_string(),
+                    Box::new(AddByContainerMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
     methods.insert("Del".to_string(),
                     Box::new(DelMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
 
@@ -130,6 +456,54 @@ pub fn create_control(service: Arc<Box<dyn Control + Send + Sync>>) -> HashMap<S
     methods.insert("Merge".to_string(),
                     Box::new(MergeMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
 
+    methods.insert("Status".to_string(),
+                    Box::new(StatusMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("Stats".to_string(),
+                    Box::new(StatsMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("SetConfig".to_string(),
+                    Box::new(SetConfigMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("PauseTask".to_string(),
+                    Box::new(PauseTaskMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("ResumeTask".to_string(),
+                    Box::new(ResumeTaskMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("Unmerge".to_string(),
+                    Box::new(UnmergeMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("History".to_string(),
+                    Box::new(HistoryMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("RecentEvents".to_string(),
+                    Box::new(RecentEventsMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("GetMaps".to_string(),
+                    Box::new(GetMapsMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("CrcHistogram".to_string(),
+                    Box::new(CrcHistogramMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("WatchCgroup".to_string(),
+                    Box::new(WatchCgroupMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("UnwatchCgroup".to_string(),
+                    Box::new(UnwatchCgroupMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("GetVersion".to_string(),
+                    Box::new(GetVersionMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("Drain".to_string(),
+                    Box::new(DrainMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("UnmergeAll".to_string(),
+                    Box::new(UnmergeAllMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
+    methods.insert("Update".to_string(),
+                    Box::new(UpdateMethod{service: service.clone()}) as Box<dyn ::ttrpc::r#async::MethodHandler + Send + Sync>);
+
     ret.insert("MemAgent.Control".to_string(), ::ttrpc::r#async::Service{ methods, streams });
     ret
 }
@@ -0,0 +1,232 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::task::{RangeOp, TaskInfo};
+use std::collections::{HashMap, HashSet};
+
+pub trait QueueKey {
+    fn key(&self) -> u64;
+}
+
+impl QueueKey for u64 {
+    fn key(&self) -> u64 {
+        *self
+    }
+}
+
+impl QueueKey for TaskInfo {
+    fn key(&self) -> u64 {
+        self.pid
+    }
+}
+
+impl QueueKey for RangeOp {
+    fn key(&self) -> u64 {
+        self.pid
+    }
+}
+
+// Work queue that keeps each key (pid) at most once.
+#[derive(Debug, Clone)]
+pub struct WorkQueue<T> {
+    items: Vec<T>,
+    keys: HashSet<u64>,
+}
+
+impl<T: QueueKey> WorkQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            keys: HashSet::new(),
+        }
+    }
+
+    // If the key of item is in the queue, replace the old item with it
+    // and keep the position.
+    pub fn push(&mut self, item: T) {
+        let key = item.key();
+        if self.keys.contains(&key) {
+            if let Some(old) = self.items.iter_mut().find(|i| i.key() == key) {
+                *old = item;
+            }
+        } else {
+            self.keys.insert(key);
+            self.items.push(item);
+        }
+    }
+
+    // Push all the items with one pass over the queue, instead of the
+    // one pass of push for each item whose key is already in the queue.
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        let mut replace: HashMap<u64, T> = HashMap::new();
+        for item in items {
+            let key = item.key();
+            if self.keys.contains(&key) {
+                replace.insert(key, item);
+            } else {
+                self.keys.insert(key);
+                self.items.push(item);
+            }
+        }
+
+        if !replace.is_empty() {
+            for old in self.items.iter_mut() {
+                if let Some(item) = replace.remove(&old.key()) {
+                    *old = item;
+                }
+            }
+        }
+    }
+
+    // Add item to the head to pop it after all the others. If its key is
+    // in the queue, replace the old item as push does.
+    pub fn push_front(&mut self, item: T) {
+        let key = item.key();
+        if self.keys.contains(&key) {
+            if let Some(old) = self.items.iter_mut().find(|i| i.key() == key) {
+                *old = item;
+            }
+        } else {
+            self.keys.insert(key);
+            self.items.insert(0, item);
+        }
+    }
+
+    // Pop the last item that f returns true.
+    pub fn pop_if<F: FnMut(&T) -> bool>(&mut self, f: F) -> Option<T> {
+        let i = self.items.iter().rposition(f)?;
+        let item = self.items.remove(i);
+        self.keys.remove(&item.key());
+        Some(item)
+    }
+
+    // Pop at most n items that f returns true.
+    pub fn pop_n_if<F: FnMut(&T) -> bool>(&mut self, n: usize, mut f: F) -> Vec<T> {
+        let mut items = Vec::new();
+        while items.len() < n {
+            if let Some(item) = self.pop_if(&mut f) {
+                items.push(item);
+            } else {
+                break;
+            }
+        }
+        items
+    }
+
+    pub fn remove(&mut self, key: u64) {
+        if self.keys.remove(&key) {
+            self.items.retain(|i| i.key() != key);
+        }
+    }
+
+    // Keep the items that f returns true.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let keys = &mut self.keys;
+        self.items.retain(|i| {
+            let keep = f(i);
+            if !keep {
+                keys.remove(&i.key());
+            }
+            keep
+        });
+    }
+
+    // Sort the items by the key that f returns, the items of the same key
+    // keep their order.
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, f: F) {
+        self.items.sort_by_key(f);
+    }
+
+    pub fn contains(&self, key: u64) -> bool {
+        self.keys.contains(&key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &u64> {
+        self.keys.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.keys.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An item of pid with a value to tell the pushes of the same pid apart.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Item(u64, u64);
+
+    impl QueueKey for Item {
+        fn key(&self) -> u64 {
+            self.0
+        }
+    }
+
+    fn pop_all(queue: &mut WorkQueue<Item>) -> Vec<Item> {
+        std::iter::from_fn(|| queue.pop_if(|_| true)).collect()
+    }
+
+    #[test]
+    fn test_push_keeps_each_key_once() {
+        let mut queue = WorkQueue::new();
+        queue.push(Item(1, 0));
+        queue.push(Item(2, 0));
+        queue.push(Item(1, 1));
+        queue.extend([Item(2, 1), Item(3, 0), Item(3, 1)]);
+        queue.push_front(Item(1, 2));
+
+        // The newest item of a key wins and keeps the position of the
+        // first one.
+        assert_eq!(pop_all(&mut queue), [Item(3, 1), Item(2, 1), Item(1, 2)]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.keys().count(), 0);
+    }
+
+    #[test]
+    fn test_push_front_is_popped_last() {
+        let mut queue = WorkQueue::new();
+        queue.push(Item(1, 0));
+        queue.push_front(Item(2, 0));
+        queue.push(Item(3, 0));
+        assert_eq!(
+            queue.pop_n_if(3, |item| item.0 != 3),
+            [Item(1, 0), Item(2, 0)]
+        );
+        assert_eq!(pop_all(&mut queue), [Item(3, 0)]);
+    }
+
+    #[test]
+    fn test_removed_key_is_pushed_again() {
+        let mut queue = WorkQueue::new();
+        queue.extend([Item(1, 0), Item(2, 0), Item(3, 0)]);
+        queue.remove(1);
+        queue.retain(|item| item.0 != 2);
+        assert!(!queue.contains(1));
+        assert!(!queue.contains(2));
+        assert!(queue.contains(3));
+
+        queue.push(Item(1, 1));
+        queue.push(Item(2, 1));
+        assert_eq!(pop_all(&mut queue), [Item(2, 1), Item(1, 1), Item(3, 0)]);
+    }
+
+    #[test]
+    fn test_sort_keeps_the_order_of_a_key() {
+        let mut queue = WorkQueue::new();
+        queue.extend([Item(1, 1), Item(2, 0), Item(3, 1), Item(4, 0)]);
+        queue.sort_by_key(|item| item.1);
+        assert!(queue.contains(3));
+        assert_eq!(
+            pop_all(&mut queue),
+            [Item(3, 1), Item(1, 1), Item(4, 0), Item(2, 0)]
+        );
+    }
+}
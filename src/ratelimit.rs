@@ -0,0 +1,117 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last: Instant,
+
+    // To get the achieved pages per second.
+    window_start: Instant,
+    window_pages: u64,
+}
+
+// Token bucket that limits the pages handled per second.
+// The bucket holds at most one second of tokens to allow short bursts.
+#[derive(Debug)]
+pub struct TokenBucket {
+    // 0 means unlimited
+    rate: AtomicU64,
+    // pages per second of the last full window
+    achieved: AtomicU64,
+    // pages that are acquired since it is created
+    total: AtomicU64,
+    state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    pub fn new(rate: u64) -> Self {
+        let now = Instant::now();
+        Self {
+            rate: AtomicU64::new(rate),
+            achieved: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            state: Mutex::new(BucketState {
+                tokens: rate as f64,
+                last: now,
+                window_start: now,
+                window_pages: 0,
+            }),
+        }
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.rate.load(Ordering::Relaxed)
+    }
+
+    // The new rate affects the work that is running.
+    pub fn set_rate(&self, rate: u64) {
+        self.rate.store(rate, Ordering::Relaxed);
+    }
+
+    pub fn achieved(&self) -> u64 {
+        self.achieved.load(Ordering::Relaxed)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    // Take pages tokens from the bucket, block the current thread until
+    // the tokens are enough.
+    pub fn acquire(&self, pages: u64) {
+        self.total.fetch_add(pages, Ordering::Relaxed);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let now = Instant::now();
+        state.window_pages += pages;
+        let window = now.duration_since(state.window_start);
+        if window >= Duration::from_secs(1) {
+            self.achieved.store(
+                (state.window_pages as f64 / window.as_secs_f64()) as u64,
+                Ordering::Relaxed,
+            );
+            state.window_start = now;
+            state.window_pages = 0;
+        }
+
+        let rate = self.rate();
+        if rate == 0 {
+            state.last = now;
+            return;
+        }
+
+        let rate = rate as f64;
+        state.tokens =
+            (state.tokens + now.duration_since(state.last).as_secs_f64() * rate).min(rate);
+        state.last = now;
+        state.tokens -= pages as f64;
+
+        if state.tokens < 0.0 {
+            let wait = Duration::from_secs_f64(-state.tokens / rate);
+            drop(state);
+            thread::sleep(wait);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RateLimits {
+    pub refresh: TokenBucket,
+    pub merge: TokenBucket,
+}
+
+impl RateLimits {
+    pub fn new(refresh_rate: u64, merge_rate: u64) -> Self {
+        Self {
+            refresh: TokenBucket::new(refresh_rate),
+            merge: TokenBucket::new(merge_rate),
+        }
+    }
+}
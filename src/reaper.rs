@@ -0,0 +1,212 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// Reap the tasks that exited without waiting for the refresh to notice them.
+// The exit events come from the netlink proc connector, which needs
+// CAP_NET_ADMIN, the tasks are polled periodically if it cannot be used.
+
+use crate::agent::{AgentCmd, AgentReturn};
+use crate::cmdqueue::CmdQueue;
+use anyhow::{anyhow, Result};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::thread;
+use std::time::Duration;
+
+const NETLINK_CONNECTOR: i32 = 11;
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+const PROC_EVENT_EXIT: u32 = 0x80000000;
+
+const NLMSG_HDRLEN: usize = 16;
+const CN_MSG_LEN: usize = 20;
+// what, cpu and timestamp before the event data in proc_event
+const PROC_EVENT_HDRLEN: usize = 16;
+
+const RECV_BUF_LEN: usize = 4096;
+
+// Send cmd to agent_loop and wait for it to be handled.
+// Return false if the agent is stopped.
+fn send_cmd(cmd_tx: &CmdQueue, cmd: AgentCmd) -> bool {
+    let Ok(ret_rx) = cmd_tx.blocking_send(cmd) else {
+        return false;
+    };
+    match ret_rx.blocking_recv() {
+        Ok(AgentReturn::Err(e)) => {
+            error!("reaper cmd failed: {}", e);
+            true
+        }
+        ret => ret.is_ok(),
+    }
+}
+
+fn read_u32(buf: &[u8], off: usize) -> Option<u32> {
+    buf.get(off..off + 4)
+        .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+struct ProcConnector {
+    fd: OwnedFd,
+}
+
+impl ProcConnector {
+    fn open() -> Result<Self> {
+        let raw = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                NETLINK_CONNECTOR,
+            )
+        };
+        if raw < 0 {
+            return Err(anyhow!("socket failed: {}", io::Error::last_os_error()));
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = CN_IDX_PROC;
+        let ret = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(anyhow!("bind failed: {}", io::Error::last_os_error()));
+        }
+
+        // nlmsghdr, cn_msg and the listen op
+        let len = NLMSG_HDRLEN + CN_MSG_LEN + 4;
+        let mut msg = Vec::with_capacity(len);
+        msg.extend_from_slice(&(len as u32).to_ne_bytes());
+        msg.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+        msg.extend_from_slice(&0u16.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+        msg.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&0u32.to_ne_bytes());
+        msg.extend_from_slice(&4u16.to_ne_bytes());
+        msg.extend_from_slice(&0u16.to_ne_bytes());
+        msg.extend_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+        let ret =
+            unsafe { libc::send(fd.as_raw_fd(), msg.as_ptr() as *const libc::c_void, len, 0) };
+        if ret < 0 {
+            return Err(anyhow!("send failed: {}", io::Error::last_os_error()));
+        }
+
+        Ok(Self { fd })
+    }
+
+    // Receive the pids of the processes that exited.
+    // Return None if some events are lost.
+    fn recv_exits(&self, buf: &mut [u8]) -> Result<Option<Vec<u64>>> {
+        let len = unsafe {
+            libc::recv(
+                self.fd.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                0,
+            )
+        };
+        if len < 0 {
+            let e = io::Error::last_os_error();
+            return match e.raw_os_error() {
+                Some(libc::ENOBUFS) => Ok(None),
+                Some(libc::EINTR) => Ok(Some(Vec::new())),
+                _ => Err(anyhow!("recv failed: {}", e)),
+            };
+        }
+        let buf = &buf[..len as usize];
+
+        let mut pids = Vec::new();
+        let mut off = 0;
+        while let Some(msg_len) = read_u32(buf, off) {
+            let msg_len = msg_len as usize;
+            if msg_len < NLMSG_HDRLEN || off + msg_len > buf.len() {
+                break;
+            }
+            let event = off + NLMSG_HDRLEN + CN_MSG_LEN;
+            if read_u32(buf, event) == Some(PROC_EVENT_EXIT) {
+                let pid = read_u32(buf, event + PROC_EVENT_HDRLEN);
+                let tgid = read_u32(buf, event + PROC_EVENT_HDRLEN + 4);
+                // Only the exit of the main thread is the exit of a process.
+                if let (Some(pid), Some(tgid)) = (pid, tgid) {
+                    if pid == tgid {
+                        pids.push(pid as u64);
+                    }
+                }
+            }
+            off += (msg_len + 3) & !3;
+        }
+
+        Ok(Some(pids))
+    }
+}
+
+// Send the exited pids to agent_loop until the agent stops.
+fn listen(conn: ProcConnector, cmd_tx: &CmdQueue) -> Result<()> {
+    let mut buf = vec![0u8; RECV_BUF_LEN];
+
+    loop {
+        match conn.recv_exits(&mut buf)? {
+            Some(pids) => {
+                for pid in pids {
+                    if !send_cmd(cmd_tx, AgentCmd::Reap(pid)) {
+                        return Ok(());
+                    }
+                }
+            }
+            None => {
+                warn!("reaper lost some exit events, check all the tasks");
+                if !send_cmd(cmd_tx, AgentCmd::ReapGone) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn poll(cmd_tx: &CmdQueue, interval: Duration) {
+    loop {
+        thread::sleep(interval);
+        if !send_cmd(cmd_tx, AgentCmd::ReapGone) {
+            return;
+        }
+    }
+}
+
+// Start the reaper thread.
+// It is a thread instead of a task of the agent runtime because the recv of
+// netlink blocks and the runtime cannot wait for it when shutting down.
+pub fn start(cmd_tx: CmdQueue, interval: Duration) -> Result<()> {
+    thread::Builder::new()
+        .name("uksmd-reaper".to_string())
+        .spawn(move || {
+            match ProcConnector::open() {
+                Ok(conn) => {
+                    info!("reaper listens to the exit events of the proc connector");
+                    match listen(conn, &cmd_tx) {
+                        Ok(()) => return,
+                        Err(e) => error!("reaper listen failed: {}", e),
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "reaper open the proc connector failed: {} (need CAP_NET_ADMIN)",
+                        e
+                    );
+                }
+            }
+            info!("reaper polls the tasks every {:?}", interval);
+            poll(&cmd_tx, interval);
+        })
+        .map_err(|e| anyhow!("spawn reaper thread failed: {}", e))?;
+
+    Ok(())
+}
@@ -2,26 +2,144 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::agent;
-use crate::protocols::{empty, uksmd_ctl, uksmd_ctl_ttrpc};
+use crate::auth;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use std::fs;
-use std::os::unix::fs::PermissionsExt;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
 use std::sync::Arc;
-use tokio::signal::unix::{signal, SignalKind};
+use std::time::Duration;
+use structopt::StructOpt;
+use tokio::signal::unix::{signal, Signal, SignalKind};
 use ttrpc::asynchronous::Server;
 use ttrpc::error::Error;
 use ttrpc::proto::Code;
+use uksmd::agent;
+use uksmd::config;
+use uksmd::protocols::{empty, uksmd_ctl, uksmd_ctl_ttrpc};
+use uksmd::version;
+use uksmd::{Engine, TaskError};
+
+// The options of the grpc server, which is started if grpc_addr is set.
+#[derive(StructOpt, Debug, Clone, Default)]
+#[cfg_attr(not(feature = "grpc"), allow(dead_code))]
+pub struct GrpcOpt {
+    /// host:port that the grpc server listens on
+    #[structopt(long)]
+    pub grpc_addr: Option<String>,
+    /// PEM certificate of the grpc server
+    #[structopt(long)]
+    pub grpc_tls_cert: Option<String>,
+    /// PEM private key of the grpc server
+    #[structopt(long)]
+    pub grpc_tls_key: Option<String>,
+    /// PEM CA certificate to verify the grpc clients
+    #[structopt(long)]
+    pub grpc_tls_ca: Option<String>,
+}
+
+// The options of the read-only http endpoints, which are started if
+// http_port is set.
+#[derive(StructOpt, Debug, Clone, Default)]
+pub struct HttpOpt {
+    /// port of the read-only http endpoints of the status
+    #[structopt(long)]
+    pub http_port: Option<u16>,
+    /// host that the http endpoints listen on
+    #[structopt(long, default_value = "127.0.0.1")]
+    pub http_host: String,
+    /// allow the http endpoints to listen on a non-loopback host
+    #[structopt(long)]
+    pub http_allow_remote: bool,
+}
 
 #[derive(Debug)]
 pub struct MyControl {
-    agent: agent::Agent,
+    engine: Arc<Engine>,
+    policy: auth::AuthPolicy,
+    creds: Box<dyn auth::CredSource>,
 }
 
 impl MyControl {
-    pub fn new(agent: agent::Agent) -> Self {
-        Self { agent }
+    pub fn new(
+        engine: Arc<Engine>,
+        policy: auth::AuthPolicy,
+        creds: Box<dyn auth::CredSource>,
+    ) -> Self {
+        Self {
+            engine,
+            policy,
+            creds,
+        }
+    }
+
+    fn check_permission(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        method: &str,
+        mutating: bool,
+    ) -> ::ttrpc::Result<()> {
+        let cred = self.creds.peer_cred(ctx.fd).map_err(|e| {
+            let estr = format!("{} get peer credentials fail: {}", method, e);
+            error!("{}", estr);
+            Error::RpcStatus(ttrpc::get_status(Code::PERMISSION_DENIED, estr))
+        })?;
+
+        if !self.policy.allow(&cred, mutating) {
+            let estr = format!(
+                "{} permission denied for pid {} uid {} gid {}",
+                method, cred.pid, cred.uid, cred.gid
+            );
+            warn!("{}", estr);
+            return Err(Error::RpcStatus(ttrpc::get_status(
+                Code::PERMISSION_DENIED,
+                estr,
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn agent_error_status(e: anyhow::Error) -> Error {
+    let code = match e.downcast_ref::<TaskError>() {
+        Some(TaskError::Limit(_)) => Code::RESOURCE_EXHAUSTED,
+        Some(TaskError::InvalidArg(_)) => Code::INVALID_ARGUMENT,
+        Some(TaskError::Unavailable(_)) => Code::UNAVAILABLE,
+        Some(TaskError::FailedPrecondition(_)) => Code::FAILED_PRECONDITION,
+        _ => Code::INTERNAL,
+    };
+    let estr = format!("{}", e);
+    error!("agent return error: {}", estr);
+
+    Error::RpcStatus(ttrpc::get_status(code, estr))
+}
+
+// The queue of the agent is full if send_cmd returns TaskError::Unavailable.
+fn send_error_code(e: &anyhow::Error) -> Code {
+    match e.downcast_ref::<TaskError>() {
+        Some(TaskError::Unavailable(_)) => Code::UNAVAILABLE,
+        Some(TaskError::Deadline(_)) => Code::DEADLINE_EXCEEDED,
+        Some(TaskError::FailedPrecondition(_)) => Code::FAILED_PRECONDITION,
+        _ => Code::INTERNAL,
+    }
+}
+
+// The time that is left of the timeout of the client.
+fn ctx_deadline(ctx: &::ttrpc::r#async::TtrpcContext) -> Option<Duration> {
+    if ctx.timeout_nano > 0 {
+        Some(Duration::from_nanos(ctx.timeout_nano as u64))
+    } else {
+        None
+    }
+}
+
+fn check_agent_return(ret: agent::AgentReturn) -> ::ttrpc::Result<()> {
+    match ret {
+        agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+        _ => Ok(()),
     }
 }
 
@@ -29,101 +147,691 @@ impl MyControl {
 impl uksmd_ctl_ttrpc::Control for MyControl {
     async fn add(
         &self,
-        _ctx: &::ttrpc::r#async::TtrpcContext,
+        ctx: &::ttrpc::r#async::TtrpcContext,
         req: uksmd_ctl::AddRequest,
-    ) -> ::ttrpc::Result<empty::Empty> {
-        self.agent
-            .send_cmd_async(agent::AgentCmd::Add(req.clone()))
+    ) -> ::ttrpc::Result<uksmd_ctl::AddResponse> {
+        self.check_permission(ctx, "Add", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::Add(req.clone()), ctx_deadline(ctx))
             .await
             .map_err(|e| {
                 let estr = format!(
-                    "agent.send_cmd_async {:?} fail: {}",
+                    "engine.send_cmd {:?} fail: {}",
                     agent::AgentCmd::Add(req),
                     e
                 );
                 error!("{}", estr);
-                Error::RpcStatus(ttrpc::get_status(Code::INTERNAL, estr))
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
             })?;
 
-        Ok(empty::Empty::new())
+        match ret {
+            agent::AgentReturn::Add(resp) => Ok(resp),
+            agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+            _ => Err(Error::RpcStatus(ttrpc::get_status(
+                Code::INTERNAL,
+                format!("add get unexpected return {:?}", ret),
+            ))),
+        }
+    }
+
+    async fn add_by_container(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::AddByContainerRequest,
+    ) -> ::ttrpc::Result<uksmd_ctl::AddResponse> {
+        self.check_permission(ctx, "AddByContainer", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(
+                agent::AgentCmd::AddByContainer(req.clone()),
+                ctx_deadline(ctx),
+            )
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::AddByContainer(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+
+        match ret {
+            agent::AgentReturn::Add(resp) => Ok(resp),
+            agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+            _ => Err(Error::RpcStatus(ttrpc::get_status(
+                Code::INTERNAL,
+                format!("add_by_container get unexpected return {:?}", ret),
+            ))),
+        }
     }
 
     async fn del(
         &self,
-        _ctx: &::ttrpc::r#async::TtrpcContext,
+        ctx: &::ttrpc::r#async::TtrpcContext,
         req: uksmd_ctl::DelRequest,
     ) -> ::ttrpc::Result<empty::Empty> {
-        self.agent
-            .send_cmd_async(agent::AgentCmd::Del(req.clone()))
+        self.check_permission(ctx, "Del", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::Del(req.clone()), ctx_deadline(ctx))
             .await
             .map_err(|e| {
                 let estr = format!(
-                    "agent.send_cmd_async {:?} fail: {}",
+                    "engine.send_cmd {:?} fail: {}",
                     agent::AgentCmd::Del(req),
                     e
                 );
                 error!("{}", estr);
-                Error::RpcStatus(ttrpc::get_status(Code::INTERNAL, estr))
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
             })?;
+        check_agent_return(ret)?;
 
         Ok(empty::Empty::new())
     }
 
     async fn refresh(
         &self,
-        _ctx: &::ttrpc::r#async::TtrpcContext,
+        ctx: &::ttrpc::r#async::TtrpcContext,
         _: empty::Empty,
     ) -> ::ttrpc::Result<empty::Empty> {
-        self.agent
-            .send_cmd_async(agent::AgentCmd::Refresh)
+        self.check_permission(ctx, "Refresh", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::Refresh, ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!("engine.send_cmd {:?} fail: {}", agent::AgentCmd::Refresh, e);
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+        check_agent_return(ret)?;
+
+        Ok(empty::Empty::new())
+    }
+
+    async fn merge(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::MergeRequest,
+    ) -> ::ttrpc::Result<empty::Empty> {
+        self.check_permission(ctx, "Merge", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::Merge(req.clone()), ctx_deadline(ctx))
             .await
             .map_err(|e| {
                 let estr = format!(
-                    "agent.send_cmd_async {:?} fail: {}",
-                    agent::AgentCmd::Refresh,
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::Merge(req),
                     e
                 );
                 error!("{}", estr);
-                Error::RpcStatus(ttrpc::get_status(Code::INTERNAL, estr))
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
             })?;
+        check_agent_return(ret)?;
 
         Ok(empty::Empty::new())
     }
 
-    async fn merge(
+    async fn status(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        _: empty::Empty,
+    ) -> ::ttrpc::Result<uksmd_ctl::StatusResponse> {
+        self.check_permission(ctx, "Status", false)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::Status, ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!("engine.send_cmd {:?} fail: {}", agent::AgentCmd::Status, e);
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+
+        match ret {
+            agent::AgentReturn::Status(resp) => Ok(*resp),
+            agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+            _ => Err(Error::RpcStatus(ttrpc::get_status(
+                Code::INTERNAL,
+                format!("status get unexpected return {:?}", ret),
+            ))),
+        }
+    }
+
+    async fn history(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::HistoryRequest,
+    ) -> ::ttrpc::Result<uksmd_ctl::HistoryResponse> {
+        self.check_permission(ctx, "History", false)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::History(req.clone()), ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::History(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+
+        match ret {
+            agent::AgentReturn::History(resp) => Ok(resp),
+            agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+            _ => Err(Error::RpcStatus(ttrpc::get_status(
+                Code::INTERNAL,
+                format!("history get unexpected return {:?}", ret),
+            ))),
+        }
+    }
+
+    async fn recent_events(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::RecentEventsRequest,
+    ) -> ::ttrpc::Result<uksmd_ctl::RecentEventsResponse> {
+        self.check_permission(ctx, "RecentEvents", false)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(
+                agent::AgentCmd::RecentEvents(req.clone()),
+                ctx_deadline(ctx),
+            )
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::RecentEvents(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+
+        match ret {
+            agent::AgentReturn::RecentEvents(resp) => Ok(resp),
+            agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+            _ => Err(Error::RpcStatus(ttrpc::get_status(
+                Code::INTERNAL,
+                format!("recent_events get unexpected return {:?}", ret),
+            ))),
+        }
+    }
+
+    async fn get_maps(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::GetMapsRequest,
+    ) -> ::ttrpc::Result<uksmd_ctl::GetMapsResponse> {
+        self.check_permission(ctx, "GetMaps", false)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::GetMaps(req.clone()), ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::GetMaps(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+
+        match ret {
+            agent::AgentReturn::GetMaps(resp) => Ok(resp),
+            agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+            _ => Err(Error::RpcStatus(ttrpc::get_status(
+                Code::INTERNAL,
+                format!("get_maps get unexpected return {:?}", ret),
+            ))),
+        }
+    }
+
+    async fn crc_histogram(
         &self,
-        _ctx: &::ttrpc::r#async::TtrpcContext,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::CrcHistogramRequest,
+    ) -> ::ttrpc::Result<uksmd_ctl::CrcHistogramResponse> {
+        self.check_permission(ctx, "CrcHistogram", false)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(
+                agent::AgentCmd::CrcHistogram(req.clone()),
+                ctx_deadline(ctx),
+            )
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::CrcHistogram(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+
+        match ret {
+            agent::AgentReturn::CrcHistogram(resp) => Ok(resp),
+            agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+            _ => Err(Error::RpcStatus(ttrpc::get_status(
+                Code::INTERNAL,
+                format!("crc_histogram get unexpected return {:?}", ret),
+            ))),
+        }
+    }
+
+    async fn stats(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
         _: empty::Empty,
+    ) -> ::ttrpc::Result<uksmd_ctl::StatsResponse> {
+        self.check_permission(ctx, "Stats", false)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::Stats, ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!("engine.send_cmd {:?} fail: {}", agent::AgentCmd::Stats, e);
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+
+        match ret {
+            agent::AgentReturn::Stats(resp) => Ok(*resp),
+            agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+            _ => Err(Error::RpcStatus(ttrpc::get_status(
+                Code::INTERNAL,
+                format!("stats get unexpected return {:?}", ret),
+            ))),
+        }
+    }
+
+    async fn watch_cgroup(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::WatchCgroupRequest,
     ) -> ::ttrpc::Result<empty::Empty> {
-        self.agent
-            .send_cmd_async(agent::AgentCmd::Merge)
+        self.check_permission(ctx, "WatchCgroup", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::WatchCgroup(req.clone()), ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::WatchCgroup(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+        check_agent_return(ret)?;
+
+        Ok(empty::Empty::new())
+    }
+
+    async fn unwatch_cgroup(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::UnwatchCgroupRequest,
+    ) -> ::ttrpc::Result<empty::Empty> {
+        self.check_permission(ctx, "UnwatchCgroup", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(
+                agent::AgentCmd::UnwatchCgroup(req.clone()),
+                ctx_deadline(ctx),
+            )
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::UnwatchCgroup(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+        check_agent_return(ret)?;
+
+        Ok(empty::Empty::new())
+    }
+
+    async fn pause_task(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::PauseTaskRequest,
+    ) -> ::ttrpc::Result<empty::Empty> {
+        self.check_permission(ctx, "PauseTask", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::PauseTask(req.clone()), ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::PauseTask(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+        check_agent_return(ret)?;
+
+        Ok(empty::Empty::new())
+    }
+
+    async fn resume_task(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::ResumeTaskRequest,
+    ) -> ::ttrpc::Result<empty::Empty> {
+        self.check_permission(ctx, "ResumeTask", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::ResumeTask(req.clone()), ctx_deadline(ctx))
             .await
             .map_err(|e| {
                 let estr = format!(
-                    "agent.send_cmd_async {:?} fail: {}",
-                    agent::AgentCmd::Merge,
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::ResumeTask(req),
                     e
                 );
                 error!("{}", estr);
-                Error::RpcStatus(ttrpc::get_status(Code::INTERNAL, estr))
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
             })?;
+        check_agent_return(ret)?;
+
+        Ok(empty::Empty::new())
+    }
+
+    async fn update(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::UpdateRequest,
+    ) -> ::ttrpc::Result<empty::Empty> {
+        self.check_permission(ctx, "Update", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::Update(req.clone()), ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::Update(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+        check_agent_return(ret)?;
+
+        Ok(empty::Empty::new())
+    }
+
+    async fn unmerge(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::UnmergeRequest,
+    ) -> ::ttrpc::Result<empty::Empty> {
+        self.check_permission(ctx, "Unmerge", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::UnMerge(req.clone()), ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::UnMerge(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+        check_agent_return(ret)?;
+
+        Ok(empty::Empty::new())
+    }
+
+    async fn unmerge_all(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        _: empty::Empty,
+    ) -> ::ttrpc::Result<uksmd_ctl::UnmergeAllResponse> {
+        self.check_permission(ctx, "UnmergeAll", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::UnmergeAll, ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::UnmergeAll,
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+
+        match ret {
+            agent::AgentReturn::UnmergeAll(resp) => Ok(resp),
+            agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+            _ => Err(Error::RpcStatus(ttrpc::get_status(
+                Code::INTERNAL,
+                format!("unmerge_all get unexpected return {:?}", ret),
+            ))),
+        }
+    }
+
+    async fn drain(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        _: empty::Empty,
+    ) -> ::ttrpc::Result<uksmd_ctl::DrainResponse> {
+        self.check_permission(ctx, "Drain", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::Drain, ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!("engine.send_cmd {:?} fail: {}", agent::AgentCmd::Drain, e);
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+
+        match ret {
+            agent::AgentReturn::Drain(resp) => Ok(resp),
+            agent::AgentReturn::Err(e) => Err(agent_error_status(e)),
+            _ => Err(Error::RpcStatus(ttrpc::get_status(
+                Code::INTERNAL,
+                format!("drain get unexpected return {:?}", ret),
+            ))),
+        }
+    }
+
+    async fn get_version(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        _: empty::Empty,
+    ) -> ::ttrpc::Result<uksmd_ctl::VersionResponse> {
+        self.check_permission(ctx, "GetVersion", false)?;
+
+        Ok(uksmd_ctl::VersionResponse {
+            version: version::VERSION.to_string(),
+            api_level: version::API_LEVEL,
+            ..Default::default()
+        })
+    }
+
+    async fn set_config(
+        &self,
+        ctx: &::ttrpc::r#async::TtrpcContext,
+        req: uksmd_ctl::SetConfigRequest,
+    ) -> ::ttrpc::Result<empty::Empty> {
+        self.check_permission(ctx, "SetConfig", true)?;
+
+        let ret = self
+            .engine
+            .send_cmd_deadline(agent::AgentCmd::SetConfig(req.clone()), ctx_deadline(ctx))
+            .await
+            .map_err(|e| {
+                let estr = format!(
+                    "engine.send_cmd {:?} fail: {}",
+                    agent::AgentCmd::SetConfig(req),
+                    e
+                );
+                error!("{}", estr);
+                Error::RpcStatus(ttrpc::get_status(send_error_code(&e), estr))
+            })?;
+        check_agent_return(ret)?;
 
         Ok(empty::Empty::new())
     }
 }
 
-#[tokio::main]
-pub async fn rpc_loop(addr: String) -> Result<()> {
+// Take a flock on the companion lock file of path to make sure only
+// one daemon uses it.
+// The lock is released when the returned file is dropped.
+fn lock_addr(path: &str) -> Result<fs::File> {
+    let lock_path = format!("{}.lock", path);
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .mode(0o600)
+        .open(&lock_path)
+        .map_err(|e| anyhow!("open file {} fail: {}", lock_path, e))?;
+
+    let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if ret != 0 {
+        let e = std::io::Error::last_os_error();
+        if e.raw_os_error() == Some(libc::EWOULDBLOCK) {
+            return Err(anyhow!("{} is locked by another uksmd", lock_path));
+        }
+        return Err(anyhow!("flock {} fail: {}", lock_path, e));
+    }
+
+    Ok(file)
+}
+
+// Remove the socket file left by a crashed daemon.
+fn clean_stale_addr(path: &str) -> Result<()> {
+    if !std::path::Path::new(path).exists() {
+        return Ok(());
+    }
+
+    match UnixStream::connect(path) {
+        Ok(_) => Err(anyhow!("{} is used by another live uksmd", path)),
+        Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+            warn!("{} is stale, remove it", path);
+            fs::remove_file(path).map_err(|e| anyhow!("fs::remove_file {} fail: {}", path, e))
+        }
+        Err(e) => Err(anyhow!("UnixStream::connect {} fail: {}", path, e)),
+    }
+}
+
+// Log the stats of the daemon and the kernel when get SIGUSR1.
+async fn dump_stats(engine: &Engine) {
+    match engine.send_cmd(agent::AgentCmd::Stats).await {
+        Ok(agent::AgentReturn::Stats(resp)) => info!("uksmd: stats {}", resp),
+        Ok(_) => error!("uksmd: get stats fail: wrong return"),
+        Err(e) => error!("uksmd: get stats fail: {}", e),
+    }
+}
+
+// Reload the discovery rules when get SIGHUP.
+async fn reload_discovery(engine: &Engine) {
+    match engine.send_cmd(agent::AgentCmd::ReloadDiscovery).await {
+        Ok(agent::AgentReturn::Err(e)) => error!("uksmd: reload discovery rules fail: {}", e),
+        Ok(_) => {}
+        Err(e) => error!("uksmd: reload discovery rules fail: {}", e),
+    }
+}
+
+// The signals that uksmd handles.
+struct Signals {
+    interrupt: Signal,
+    quit: Signal,
+    terminate: Signal,
+    user_defined1: Signal,
+    hangup: Signal,
+}
+
+impl Signals {
+    fn new() -> Result<Self> {
+        let listen = |kind: SignalKind, name: &str| {
+            signal(kind).map_err(|e| anyhow!("signal(SignalKind::{}()) fail: {}", name, e))
+        };
+
+        Ok(Self {
+            interrupt: listen(SignalKind::interrupt(), "interrupt")?,
+            quit: listen(SignalKind::quit(), "quit")?,
+            terminate: listen(SignalKind::terminate(), "terminate")?,
+            user_defined1: listen(SignalKind::user_defined1(), "user_defined1")?,
+            hangup: listen(SignalKind::hangup(), "hangup")?,
+        })
+    }
+
+    // Handle SIGUSR1 and SIGHUP until a signal to shutdown and return its
+    // name.
+    async fn wait_shutdown(&mut self, engine: &Engine) -> &'static str {
+        loop {
+            tokio::select! {
+                _ = self.interrupt.recv() => return "interrupt",
+                _ = self.quit.recv() => return "quit",
+                _ = self.terminate.recv() => return "terminate",
+                _ = self.user_defined1.recv() => dump_stats(engine).await,
+                _ = self.hangup.recv() => reload_discovery(engine).await,
+            }
+        }
+    }
+}
+
+pub async fn rpc_loop(
+    addr: String,
+    grpc: GrpcOpt,
+    http: HttpOpt,
+    config: config::Config,
+) -> Result<()> {
     let path = addr
         .strip_prefix("unix://")
         .ok_or(anyhow!("format of addr {} is not right", addr))?;
-    if std::path::Path::new(path).exists() {
-        return Err(anyhow!("addr {} is exist", addr));
-    }
+    let _lock = lock_addr(path).map_err(|e| anyhow!("lock_addr {} fail: {}", path, e))?;
+    clean_stale_addr(path).map_err(|e| anyhow!("clean_stale_addr {} fail: {}", path, e))?;
 
-    let agent = agent::Agent::new().map_err(|e| anyhow!("agent::Agent::new fail: {}", e))?;
+    let engine = Engine::new(config.clone()).map_err(|e| anyhow!("Engine::new fail: {}", e))?;
+    let engine = Arc::new(engine);
 
-    let control = MyControl::new(agent);
+    let policy = auth::AuthPolicy::load(config.auth_policy.as_deref(), &config.read_gid)
+        .map_err(|e| anyhow!("AuthPolicy::load fail: {}", e))?;
+    let control = MyControl::new(engine.clone(), policy, Box::new(auth::SockCredSource));
     let c = Box::new(control) as Box<dyn uksmd_ctl_ttrpc::Control + Send + Sync>;
     let c = Arc::new(c);
     let service = uksmd_ctl_ttrpc::create_control(c);
@@ -136,36 +844,405 @@ pub async fn rpc_loop(addr: String) -> Result<()> {
     fs::set_permissions(path, permissions)
         .map_err(|e| anyhow!("fs::set_permissions {} fail: {}", path, e))?;
 
-    let mut interrupt = signal(SignalKind::interrupt())
-        .map_err(|e| anyhow!("signal(SignalKind::interrupt()) fail: {}", e))?;
-    let mut quit = signal(SignalKind::quit())
-        .map_err(|e| anyhow!("signal(SignalKind::quit()) fail: {}", e))?;
-    let mut terminate = signal(SignalKind::terminate())
-        .map_err(|e| anyhow!("signal(SignalKind::terminate()) fail: {}", e))?;
+    let mut signals = Signals::new()?;
     server
         .start()
         .await
         .map_err(|e| anyhow!("server.start() fail: {}", e))?;
 
-    tokio::select! {
-        _ = interrupt.recv() => {
-            info!("uksmd: interrupt shutdown");
-        }
-
-        _ = quit.recv() => {
-            info!("uksmd: quit shutdown");
-        }
+    #[cfg(feature = "grpc")]
+    let grpc_server = match grpc.grpc_addr {
+        Some(_) => Some(
+            crate::grpc::GrpcServer::start(&grpc, engine.clone())
+                .map_err(|e| anyhow!("GrpcServer::start fail: {}", e))?,
+        ),
+        None => None,
+    };
+    #[cfg(not(feature = "grpc"))]
+    let _ = grpc;
 
-        _ = terminate.recv() => {
-            info!("uksmd: terminate shutdown");
-        }
+    let http_server = match http.http_port {
+        Some(_) => Some(
+            crate::http::HttpServer::start(&http, engine.clone())
+                .await
+                .map_err(|e| anyhow!("HttpServer::start fail: {}", e))?,
+        ),
+        None => None,
     };
 
+    let reason = signals.wait_shutdown(&engine).await;
+    info!("uksmd: {} shutdown", reason);
+
     server
         .shutdown()
         .await
         .map_err(|e| anyhow!("server.shutdown() fail: {}", e))?;
-    fs::remove_file(&path).map_err(|e| anyhow!("fs::remove_file {} fail: {}", path, e))?;
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_server) = grpc_server {
+        grpc_server.stop().await;
+    }
+    if let Some(http_server) = http_server {
+        http_server.stop().await;
+    }
+    if let Err(e) = engine
+        .shutdown(Duration::from_secs(config.shutdown_timeout))
+        .await
+    {
+        error!("engine.shutdown fail: {}", e);
+    }
+    fs::remove_file(path).map_err(|e| anyhow!("fs::remove_file {} fail: {}", path, e))?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{fake_config, fake_root};
+    use std::path::PathBuf;
+    use tokio::time;
+    use ttrpc::r#async::TtrpcContext;
+    use uksmd_ctl_ttrpc::Control;
+
+    fn new_engine() -> Arc<Engine> {
+        Arc::new(Engine::new(fake_config(&[])).unwrap())
+    }
+
+    // The credentials of all the connections, or an error if it is None.
+    #[derive(Debug)]
+    struct FakeCreds(Option<auth::PeerCred>);
+
+    impl auth::CredSource for FakeCreds {
+        fn peer_cred(&self, fd: std::os::unix::io::RawFd) -> Result<auth::PeerCred> {
+            self.0.ok_or_else(|| anyhow!("fd {} has no peer", fd))
+        }
+    }
+
+    fn cred(uid: u32, gid: u32) -> Option<auth::PeerCred> {
+        Some(auth::PeerCred { pid: 100, uid, gid })
+    }
+
+    fn ctx() -> TtrpcContext {
+        TtrpcContext {
+            fd: -1,
+            mh: Default::default(),
+            metadata: Default::default(),
+            timeout_nano: 0,
+        }
+    }
+
+    fn code<T>(ret: ::ttrpc::Result<T>) -> Code {
+        match ret {
+            Ok(_) => Code::OK,
+            Err(Error::RpcStatus(status)) => status.code(),
+            Err(e) => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_permission() {
+        let engine = new_engine();
+        let policy = auth::AuthPolicy::new(vec![50]);
+        let control = |creds| MyControl::new(engine.clone(), policy.clone(), Box::new(creds));
+        let ctx = ctx();
+
+        // root can call all the methods
+        let root = control(FakeCreds(cred(0, 0)));
+        assert_eq!(code(root.status(&ctx, empty::Empty::new()).await), Code::OK);
+        assert_eq!(
+            code(root.refresh(&ctx, empty::Empty::new()).await),
+            Code::OK
+        );
+
+        // a user of read_gid can only call the read-only methods
+        let reader = control(FakeCreds(cred(1000, 50)));
+        assert_eq!(
+            code(reader.status(&ctx, empty::Empty::new()).await),
+            Code::OK
+        );
+        assert_eq!(
+            code(reader.get_version(&ctx, empty::Empty::new()).await),
+            Code::OK
+        );
+        assert_eq!(
+            code(reader.refresh(&ctx, empty::Empty::new()).await),
+            Code::PERMISSION_DENIED
+        );
+        assert_eq!(
+            code(reader.del(&ctx, uksmd_ctl::DelRequest::default()).await),
+            Code::PERMISSION_DENIED
+        );
+        assert_eq!(
+            code(reader.add(&ctx, uksmd_ctl::AddRequest::default()).await),
+            Code::PERMISSION_DENIED
+        );
+
+        // the other users cannot call any method
+        let other = control(FakeCreds(cred(1000, 1000)));
+        assert_eq!(
+            code(other.status(&ctx, empty::Empty::new()).await),
+            Code::PERMISSION_DENIED
+        );
+        assert_eq!(
+            code(other.merge(&ctx, uksmd_ctl::MergeRequest::default()).await),
+            Code::PERMISSION_DENIED
+        );
+
+        // the caller is denied if its credentials cannot be got
+        let unknown = control(FakeCreds(None));
+        assert_eq!(
+            code(unknown.status(&ctx, empty::Empty::new()).await),
+            Code::PERMISSION_DENIED
+        );
+
+        engine.shutdown(Duration::from_secs(10)).await.unwrap();
+    }
+
+    // A scratch dir of a test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = fake_root().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_agent_error_codes() {
+        let code_of = |e: anyhow::Error| match agent_error_status(e) {
+            Error::RpcStatus(status) => status.code(),
+            e => panic!("get {:?}", e),
+        };
+        let status_code = |e: TaskError| code_of(anyhow::Error::new(e));
+        let s = || "e".to_string();
+        assert_eq!(status_code(TaskError::Limit(s())), Code::RESOURCE_EXHAUSTED);
+        assert_eq!(
+            status_code(TaskError::InvalidArg(s())),
+            Code::INVALID_ARGUMENT
+        );
+        assert_eq!(status_code(TaskError::Unavailable(s())), Code::UNAVAILABLE);
+        assert_eq!(
+            status_code(TaskError::FailedPrecondition(s())),
+            Code::FAILED_PRECONDITION
+        );
+        assert_eq!(code_of(anyhow!("e")), Code::INTERNAL);
+        assert_eq!(
+            send_error_code(&anyhow::Error::new(TaskError::FailedPrecondition(s()))),
+            Code::FAILED_PRECONDITION
+        );
+        assert_eq!(
+            send_error_code(&anyhow::Error::new(TaskError::Deadline(s()))),
+            Code::DEADLINE_EXCEEDED
+        );
+    }
+
+    #[test]
+    fn test_clean_stale_addr() {
+        let dir = scratch("stale");
+        let path = dir.join("uksmd.sock");
+        let path_str = path.to_str().unwrap();
+
+        // nothing to clean
+        clean_stale_addr(path_str).unwrap();
+
+        // The socket of a live daemon is kept.
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        assert!(clean_stale_addr(path_str).is_err());
+        assert!(path.exists());
+
+        // The socket that is left by a crashed daemon is removed.
+        drop(listener);
+        assert!(path.exists());
+        clean_stale_addr(path_str).unwrap();
+        assert!(!path.exists());
+        std::os::unix::net::UnixListener::bind(&path).unwrap();
+    }
+
+    #[test]
+    fn test_lock_addr() {
+        let dir = scratch("lock");
+        let path = dir.join("uksmd.sock");
+        let path_str = path.to_str().unwrap();
+
+        let lock = lock_addr(path_str).unwrap();
+        assert!(lock_addr(path_str).is_err());
+        drop(lock);
+        lock_addr(path_str).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_add_uksmd_is_invalid_arg() {
+        let engine = new_engine();
+        let policy = auth::AuthPolicy::new(vec![]);
+        let control = MyControl::new(engine.clone(), policy, Box::new(FakeCreds(cred(0, 0))));
+        let ctx = ctx();
+
+        // A thread of uksmd that only shows its tgid in the status file.
+        let pid = std::process::id() as u64;
+        let thread = pid + 1;
+        let status = fake_root().join(format!("proc/{}/status", thread));
+        fs::create_dir_all(status.parent().unwrap()).unwrap();
+        fs::write(
+            &status,
+            format!("Name:\tuksmd\nTgid:\t{}\nPid:\t{}\n", pid, thread),
+        )
+        .unwrap();
+
+        for pid in [pid, thread] {
+            let ret = control
+                .add(
+                    &ctx,
+                    uksmd_ctl::AddRequest {
+                        pid,
+                        ..Default::default()
+                    },
+                )
+                .await;
+            match ret {
+                Err(Error::RpcStatus(status)) => {
+                    assert_eq!(status.code(), Code::INVALID_ARGUMENT);
+                    assert!(status
+                        .message()
+                        .contains(&format!("pid {} is uksmd itself", pid)));
+                }
+                ret => panic!("add uksmd pid {} get {:?}", pid, ret),
+            }
+        }
+
+        engine.shutdown(Duration::from_secs(10)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_add_init_is_invalid_arg() {
+        let engine = new_engine();
+        let policy = auth::AuthPolicy::new(vec![]);
+        let control = MyControl::new(engine.clone(), policy, Box::new(FakeCreds(cred(0, 0))));
+
+        let ret = control
+            .add(
+                &ctx(),
+                uksmd_ctl::AddRequest {
+                    pid: 1,
+                    ..Default::default()
+                },
+            )
+            .await;
+        match ret {
+            Err(Error::RpcStatus(status)) => {
+                assert_eq!(status.code(), Code::INVALID_ARGUMENT);
+                assert!(status.message().contains("is init"));
+            }
+            ret => panic!("add init get {:?}", ret),
+        }
+
+        engine.shutdown(Duration::from_secs(10)).await.unwrap();
+    }
+
+    fn raise(sig: libc::c_int) {
+        assert_eq!(
+            unsafe { libc::kill(std::process::id() as libc::pid_t, sig) },
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_signal_shutdown() {
+        let engine = new_engine();
+
+        for (sig, name) in [
+            (libc::SIGINT, "interrupt"),
+            (libc::SIGQUIT, "quit"),
+            (libc::SIGTERM, "terminate"),
+        ] {
+            let mut signals = Signals::new().unwrap();
+            let wait = signals.wait_shutdown(&engine);
+            tokio::pin!(wait);
+
+            // The stats are dumped and the rules are reloaded, uksmd goes on.
+            raise(libc::SIGUSR1);
+            raise(libc::SIGHUP);
+            assert!(time::timeout(Duration::from_millis(200), &mut wait)
+                .await
+                .is_err());
+
+            raise(sig);
+            let reason = time::timeout(Duration::from_secs(5), &mut wait)
+                .await
+                .unwrap();
+            assert_eq!(reason, name);
+        }
+
+        // The shutdown joins the agent, it doesn't take commands after it.
+        assert!(engine.send_cmd(agent::AgentCmd::Status).await.is_ok());
+        engine.shutdown(Duration::from_secs(10)).await.unwrap();
+        assert!(engine.send_cmd(agent::AgentCmd::Status).await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_deadline_and_abandoned_return() {
+        let engine = new_engine();
+        let policy = auth::AuthPolicy::new(vec![]);
+        let control = MyControl::new(engine.clone(), policy, Box::new(FakeCreds(cred(0, 0))));
+
+        // The agent is stuck in the read of the status of a task that is a
+        // fifo, until the fifo is written.
+        let pid = (1 << 23) + 100;
+        let status = fake_root().join(format!("proc/{}/status", pid));
+        fs::create_dir_all(status.parent().unwrap()).unwrap();
+        let _ = fs::remove_file(&status);
+        let c_status = std::ffi::CString::new(status.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(c_status.as_ptr(), 0o600) }, 0);
+        let add_engine = engine.clone();
+        let add = tokio::spawn(async move {
+            add_engine
+                .send_cmd(agent::AgentCmd::Add(uksmd_ctl::AddRequest {
+                    pid,
+                    ..Default::default()
+                }))
+                .await
+        });
+        // The open for write fails until the agent opens the fifo to read.
+        let mut writer = loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(&status)
+            {
+                Ok(writer) => break writer,
+                Err(_) => time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+
+        let mut ctx = ctx();
+        ctx.timeout_nano = Duration::from_millis(100).as_nanos() as i64;
+        assert_eq!(
+            code(control.status(&ctx, empty::Empty::new()).await),
+            Code::DEADLINE_EXCEEDED
+        );
+        let ret = engine
+            .send_cmd_deadline(agent::AgentCmd::Stats, Some(Duration::from_millis(100)))
+            .await;
+        assert!(matches!(
+            ret.unwrap_err().downcast_ref::<TaskError>(),
+            Some(TaskError::Deadline(_))
+        ));
+
+        // The agent discards the returns of the abandoned commands and
+        // goes on.
+        let tmp = status.with_extension("tmp");
+        fs::write(&tmp, "Name:\tfifo\nTgid:\t1\n").unwrap();
+        fs::rename(&tmp, &status).unwrap();
+        std::io::Write::write_all(&mut writer, b"Name:\tfifo\nTgid:\t1\n").unwrap();
+        drop(writer);
+        assert!(add.await.unwrap().is_ok());
+        assert_eq!(
+            code(control.status(&self::ctx(), empty::Empty::new()).await),
+            Code::OK
+        );
+        assert!(matches!(
+            engine.send_cmd(agent::AgentCmd::Stats).await.unwrap(),
+            agent::AgentReturn::Stats(_)
+        ));
+
+        engine.shutdown(Duration::from_secs(10)).await.unwrap();
+    }
+}
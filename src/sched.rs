@@ -0,0 +1,157 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{config, proc};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const IOPRIO_CLASS_SHIFT: i32 = 13;
+const IOPRIO_CLASS_IDLE: i32 = 3;
+const IOPRIO_WHO_PROCESS: i32 = 1;
+
+// The interval to check the load when the work is throttled.
+const LOAD_RECHECK: Duration = Duration::from_secs(1);
+
+fn os_error(op: &str) -> anyhow::Error {
+    let e = io::Error::last_os_error();
+    if e.raw_os_error() == Some(libc::EPERM) {
+        anyhow!("{} failed: {} (need CAP_SYS_NICE)", op, e)
+    } else {
+        anyhow!("{} failed: {}", op, e)
+    }
+}
+
+// Set the scheduling priority of the current thread to the worker
+// settings in config.
+pub fn setup_worker_thread(config: &config::Config) -> Result<()> {
+    if let Some(nice) = config.worker_nice {
+        let tid = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, nice) } != 0 {
+            return Err(os_error(&format!("setpriority {}", nice)));
+        }
+    }
+
+    if config.worker_sched_idle {
+        let param = libc::sched_param { sched_priority: 0 };
+        if unsafe { libc::sched_setscheduler(0, libc::SCHED_IDLE, &param) } != 0 {
+            return Err(os_error("sched_setscheduler SCHED_IDLE"));
+        }
+    }
+
+    if config.worker_ioprio_idle {
+        let ioprio = IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT;
+        if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) } != 0 {
+            return Err(os_error("ioprio_set IOPRIO_CLASS_IDLE"));
+        }
+    }
+
+    Ok(())
+}
+
+// Check the worker settings in a temporary thread to report the
+// error when uksmd starts.
+pub fn check_worker_settings(config: &config::Config) -> Result<()> {
+    let config = config.clone();
+
+    thread::spawn(move || setup_worker_thread(&config))
+        .join()
+        .map_err(|_| anyhow!("check thread panic"))?
+}
+
+// Throttle the background work when the load average per cpu is higher
+// than max_load.
+#[derive(Debug)]
+pub struct LoadThrottle {
+    // the bits of f64, 0 means disabled
+    max_load: AtomicU64,
+    load: AtomicU64,
+    // the work goes on after it waited so long even if the load is high
+    max_delay: Duration,
+    throttled_since: Mutex<Option<DateTime<Local>>>,
+}
+
+impl LoadThrottle {
+    pub fn new(config: &config::Config) -> Self {
+        Self {
+            max_load: AtomicU64::new(config.max_load.to_bits()),
+            load: AtomicU64::new(0f64.to_bits()),
+            max_delay: Duration::from_secs(config.max_load_delay),
+            throttled_since: Mutex::new(None),
+        }
+    }
+
+    pub fn max_load(&self) -> f64 {
+        f64::from_bits(self.max_load.load(Ordering::Relaxed))
+    }
+
+    pub fn set_max_load(&self, max_load: f64) {
+        self.max_load.store(max_load.to_bits(), Ordering::Relaxed);
+        self.update();
+    }
+
+    pub fn load(&self) -> f64 {
+        f64::from_bits(self.load.load(Ordering::Relaxed))
+    }
+
+    // Read the load average and update the throttle state.
+    pub fn update(&self) {
+        let load = match proc::read_loadavg() {
+            Ok(load) => {
+                let cpus = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) }.max(1);
+                load / cpus as f64
+            }
+            Err(e) => {
+                error!("proc::read_loadavg failed: {}", e);
+                return;
+            }
+        };
+        self.load.store(load.to_bits(), Ordering::Relaxed);
+
+        let max_load = self.max_load();
+        let mut since = self.throttled_since.lock().unwrap();
+        if max_load > 0.0 && load > max_load {
+            if since.is_none() {
+                info!(
+                    "load {:.2} is higher than {}, throttle the work",
+                    load, max_load
+                );
+                *since = Some(Local::now());
+            }
+        } else if since.take().is_some() {
+            info!("load {:.2} is low, stop throttling the work", load);
+        }
+    }
+
+    pub fn is_throttled(&self) -> bool {
+        self.throttled_since.lock().unwrap().is_some()
+    }
+
+    pub fn state(&self) -> String {
+        match *self.throttled_since.lock().unwrap() {
+            Some(since) => format!("throttled since {}", since.format("%Y-%m-%d %H:%M:%S")),
+            None => "running".to_string(),
+        }
+    }
+
+    // Wait until the load is low or max_delay passed.
+    pub fn wait(&self) {
+        let start = Instant::now();
+        while self.is_throttled() && start.elapsed() < self.max_delay {
+            thread::sleep(LOAD_RECHECK);
+            self.update();
+        }
+        if self.is_throttled() {
+            warn!(
+                "load {:.2} is still high after {:?}, the work goes on",
+                self.load(),
+                self.max_delay
+            );
+        }
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{page, task, uksm};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const STATE_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct State {
+    pub version: u32,
+    pub tasks: Vec<task::TaskInfo>,
+    pub pages_info: Vec<page::InfoState>,
+    pub uksm: uksm::UksmState,
+}
+
+pub fn save(path: &str, state: &State) -> Result<()> {
+    let buf = serde_json::to_vec(state).map_err(|e| anyhow!("serde_json::to_vec failed: {}", e))?;
+
+    // Write to a temporary file and rename it to make sure the state
+    // file is always complete.
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, buf).map_err(|e| anyhow!("write file {} failed: {}", tmp_path, e))?;
+    fs::rename(&tmp_path, path)
+        .map_err(|e| anyhow!("rename {} to {} failed: {}", tmp_path, path, e))?;
+
+    Ok(())
+}
+
+// Return None if the state file doesn't exist or cannot be used.
+pub fn load(path: &str) -> Result<Option<State>> {
+    if !Path::new(path).exists() {
+        return Ok(None);
+    }
+
+    let buf = fs::read(path).map_err(|e| anyhow!("read file {} failed: {}", path, e))?;
+
+    let estr = match serde_json::from_slice::<State>(&buf) {
+        Ok(state) if state.version == STATE_VERSION => return Ok(Some(state)),
+        Ok(state) => format!("version {} is not {}", state.version, STATE_VERSION),
+        Err(e) => format!("parse failed: {}", e),
+    };
+
+    let bad_path = format!("{}.bad", path);
+    warn!(
+        "state file {} {}, move it to {} and ignore it",
+        path, estr, bad_path
+    );
+    fs::rename(path, &bad_path)
+        .map_err(|e| anyhow!("rename {} to {} failed: {}", path, bad_path, e))?;
+
+    Ok(None)
+}
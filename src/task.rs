@@ -3,94 +3,417 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::protocols::uksmd_ctl;
-use crate::{page, proc, uksm};
+use crate::{backend, config, events, page, proc, queue, ratelimit, sched, state, timing, uksm};
 use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc as std_mpsc;
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::sync::{Mutex, RwLock};
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub struct TaskInfo {
     pub pid: u64,
     pub addr: Option<(u64, u64)>,
+    pub start_time: u64,
+    // the thp policy of the task, the one of config is used if it is None
+    #[serde(default)]
+    pub thp: Option<page::ThpPolicy>,
+    // the regexes of the map names of the task, the ones of config are used
+    // if both of them are empty
+    #[serde(default)]
+    pub include_vma: Vec<String>,
+    #[serde(default)]
+    pub exclude_vma: Vec<String>,
+    // the min_vma_size of the task, the one of config is used if it is None
+    #[serde(default)]
+    pub min_vma_size: Option<u64>,
+    // the include_shmem of the task, the one of config is used if it is None
+    #[serde(default)]
+    pub include_shmem: Option<bool>,
+    // the comm and the head of the cmdline of the task
+    #[serde(default)]
+    pub comm: String,
+    #[serde(default)]
+    pub cmdline: String,
+    // the paused task is skipped by the refresh and merge of all the tasks
+    #[serde(default)]
+    pub paused: bool,
+    // the task with a higher priority is merged first
+    #[serde(default)]
+    pub priority: i32,
+    // the real uid of the task
+    #[serde(default)]
+    pub uid: Option<u32>,
+    // add the children of the task automatically
+    #[serde(default)]
+    pub follow_children: bool,
+    // the task that this task is added as a child of
+    #[serde(default)]
+    pub parent: Option<u64>,
+    // the parent process of the task when it is added
+    #[serde(default)]
+    pub ppid: Option<u64>,
+    // the watched cgroup that the task is added from and its labels
+    #[serde(default)]
+    pub watch: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    // the task is added by a discovery rule, del needs force
+    #[serde(default)]
+    pub discovered: bool,
 }
 
 impl TaskInfo {
-    fn new(pid: u64, addr: Option<(u64, u64)>) -> Self {
-        Self { pid, addr }
+    pub(crate) fn new(
+        pid: u64,
+        addr: Option<(u64, u64)>,
+        start_time: u64,
+        thp: Option<page::ThpPolicy>,
+    ) -> Self {
+        Self {
+            pid,
+            addr,
+            start_time,
+            thp,
+            include_vma: Vec::new(),
+            exclude_vma: Vec::new(),
+            min_vma_size: None,
+            include_shmem: None,
+            comm: String::new(),
+            cmdline: String::new(),
+            paused: false,
+            priority: 0,
+            uid: None,
+            follow_children: false,
+            parent: None,
+            ppid: None,
+            watch: None,
+            labels: Vec::new(),
+            discovered: false,
+        }
+    }
+
+    // Update the comm, cmdline and uid of the task.
+    fn update_proc_info(&mut self) {
+        self.comm = proc::pid_comm(self.pid).unwrap_or_default();
+        self.cmdline = proc::pid_cmdline(self.pid).unwrap_or_default();
+        self.uid = proc::pid_uid(self.pid).ok();
+        self.ppid = proc::pid_ppid(self.pid).ok();
+    }
+}
+
+/// The errors of the tasks that the callers tell apart.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TaskError {
+    /// A limit is reached.
+    Limit(String),
+    /// An argument is not right.
+    InvalidArg(String),
+    /// A service that uksmd depends on cannot be used.
+    Unavailable(String),
+    /// The state of a task doesn't allow the operation.
+    FailedPrecondition(String),
+    /// The deadline of a command passes before the agent returns.
+    Deadline(String),
+}
+
+impl std::fmt::Display for TaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TaskError::Limit(estr)
+            | TaskError::InvalidArg(estr)
+            | TaskError::Unavailable(estr)
+            | TaskError::FailedPrecondition(estr)
+            | TaskError::Deadline(estr) => write!(f, "{}", estr),
+        }
     }
 }
 
+impl std::error::Error for TaskError {}
+
+// An added task with its range after it is aligned, and the vmas in the
+// range that are selected by the filters of the task.
+#[derive(Debug, Clone)]
+pub struct AddScope {
+    pub task: TaskInfo,
+    pub vmas: u64,
+    pub bytes: u64,
+}
+
+// A merge or unmerge of the pages of a task in [start, end).
+#[derive(Debug, Clone)]
+pub(crate) struct RangeOp {
+    pub pid: u64,
+    pub start: u64,
+    pub end: u64,
+    pub unmerge: bool,
+}
+
 #[derive(Debug, Clone)]
 enum HandleTask {
     Del(u64),
     UnMerge(u64),
-    Refresh(TaskInfo),
     Merge(u64),
+    Verify(u64),
+    Range(RangeOp),
 }
 
-#[derive(Debug, Clone)]
+impl HandleTask {
+    fn pid(&self) -> u64 {
+        match self {
+            HandleTask::Del(pid)
+            | HandleTask::UnMerge(pid)
+            | HandleTask::Merge(pid)
+            | HandleTask::Verify(pid) => *pid,
+            HandleTask::Range(op) => op.pid,
+        }
+    }
+}
+
+// The operation that is running on the Info of a pid, a pid without one
+// is idle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PidState {
+    Refreshing,
+    Merging,
+    Unmerging,
+    Verifying,
+    Deleting,
+}
+
+impl std::fmt::Display for PidState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            PidState::Refreshing => "refreshing",
+            PidState::Merging => "merging",
+            PidState::Unmerging => "unmerging",
+            PidState::Verifying => "verifying",
+            PidState::Deleting => "deleting",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl HandleTask {
+    fn state(&self) -> PidState {
+        match self {
+            HandleTask::Del(_) => PidState::Deleting,
+            HandleTask::UnMerge(_) => PidState::Unmerging,
+            HandleTask::Merge(_) => PidState::Merging,
+            HandleTask::Verify(_) => PidState::Verifying,
+            HandleTask::Range(op) if op.unmerge => PidState::Unmerging,
+            HandleTask::Range(_) => PidState::Merging,
+        }
+    }
+}
+
+#[derive(Debug)]
 struct TasksPages {
     pages_info: HashMap<u64, page::Info>,
+    // The pids that have an operation running, the worker does not start
+    // a second one on them until the first is done.
+    // The operations read the pagemap without tasks_pages locked, and they
+    // can run in the other threads than the worker, for example run_work of
+    // a clone of Tasks in an embedder.
+    busy: HashMap<u64, PidState>,
     uksm: uksm::Uksm,
+    stable_cycles: u8,
+    thp: page::ThpPolicy,
+    max_merge_failures: u8,
+    adopt_foreign_ksm: bool,
 }
 
 impl TasksPages {
-    fn new() -> Self {
+    fn new(config: &config::Config, backend: Arc<dyn backend::KernelOps>) -> Self {
         Self {
             pages_info: HashMap::new(),
-            uksm: uksm::Uksm::new(),
+            busy: HashMap::new(),
+            uksm: uksm::Uksm::new(config, backend),
+            stable_cycles: config.stable_cycles,
+            thp: config.thp,
+            max_merge_failures: config.max_merge_failures,
+            adopt_foreign_ksm: config.adopt_foreign_ksm,
         }
     }
 
-    fn handle_task(&mut self, ht: HandleTask) -> Result<()> {
-        let mut is = page::InfoStatus::default();
-        match ht.clone() {
-            HandleTask::UnMerge(pid) => {
-                if let Some(p) = self.pages_info.get_mut(&pid) {
-                    p.unmerge(&mut self.uksm)
-                        .map_err(|e| anyhow!("p.unmerge failed: {}", e))?;
-                    is = p.get_status();
-                }
-            }
-            HandleTask::Del(pid) => {
-                self.pages_info.remove(&pid);
-            }
-            HandleTask::Refresh(task) => {
-                if !self.pages_info.contains_key(&task.pid) {
-                    self.pages_info.insert(task.pid, page::Info::new(task.pid));
-                }
-
-                if let Some(p) = self.pages_info.get_mut(&task.pid) {
-                    p.refresh(&mut self.uksm, task)
-                        .map_err(|e| anyhow!("p.refresh failed: {}", e))?;
-                    is = p.get_status();
+    // Count the merge failures of the pages that the last merges compared,
+    // park the ones that fail max_merge_failures times in a row and the
+    // maps of the pages that the kernel rejected.
+    // Return the number of the parked pages.
+    fn record_merge_failures(&mut self) -> u64 {
+        let mut parked = 0;
+        for (page, crc) in self.uksm.take_not_same() {
+            if let Some(p) = self.pages_info.get_mut(&page.pid) {
+                if p.merge_failed(&mut self.uksm, page.addr, crc, self.max_merge_failures) {
+                    parked += 1;
                 }
             }
-            HandleTask::Merge(pid) => {
-                if let Some(p) = self.pages_info.get_mut(&pid) {
-                    p.merge(&mut self.uksm)
-                        .map_err(|e| anyhow!("p.merge failed: {}", e))?;
-                    is = p.get_status();
-                }
+        }
+        for page in self.uksm.take_rejected() {
+            if let Some(p) = self.pages_info.get_mut(&page.pid) {
+                parked += p.merge_rejected(&mut self.uksm, page.addr);
             }
         }
 
-        trace!("handle_task {:?} result {:?}", ht, is);
+        parked
+    }
 
-        Ok(())
+    fn get_status(&self, pid: u64) -> page::InfoStatus {
+        self.pages_info
+            .get(&pid)
+            .map(|p| p.get_status())
+            .unwrap_or_default()
     }
 }
 
-#[derive(Debug, Clone)]
-enum AsyncWork {
+// Mark pid busy with state until it is dropped.
+struct BusyGuard<'a> {
+    tasks_pages: &'a Mutex<TasksPages>,
+    pid: u64,
+}
+
+impl<'a> BusyGuard<'a> {
+    // Return None if pid is busy with another operation.
+    fn new(tasks_pages: &'a Mutex<TasksPages>, pid: u64, state: PidState) -> Option<Self> {
+        let mut tp = tasks_pages.blocking_lock();
+        if let Some(running) = tp.busy.get(&pid) {
+            debug!("task {} is {}, defer {}", pid, running, state);
+            return None;
+        }
+        tp.busy.insert(pid, state);
+        Some(Self { tasks_pages, pid })
+    }
+}
+
+impl Drop for BusyGuard<'_> {
+    fn drop(&mut self) {
+        self.tasks_pages.blocking_lock().busy.remove(&self.pid);
+    }
+}
+
+// The max pages handled in a chunk with tasks_pages locked.
+const WORK_CHUNK_PAGES: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsyncWork {
     UnMerge,
     Del,
     Refresh,
     Merge,
+    Verify,
+    // unmerge the pages that are merged last
+    UnMergeRecent,
+    // merge or unmerge the pages in the ranges of the tasks
+    Range,
+}
+
+// The result of an async work that is sent back to agent_loop.
+#[derive(Debug)]
+pub struct WorkReport {
+    pub work: AsyncWork,
+    // number of the handled tasks and the ones that failed
+    pub tasks: u64,
+    pub failed: u64,
+    // pid -> the change of its page counts
+    pub deltas: HashMap<u64, page::InfoDelta>,
+    pub merged_pages: u64,
+    pub unmerged_pages: u64,
+    pub elapsed: Duration,
+    // the duration of lru_add_drain_all if the merge work ran it
+    pub drain: Option<Duration>,
+    // the durations of the operations if the timings are enabled
+    pub timings: Vec<timing::OpTiming>,
+    // the outcomes of the adds of the merged pages
+    pub probes: uksm::ProbeCounts,
+}
+
+impl WorkReport {
+    fn new(work: AsyncWork) -> Self {
+        Self {
+            work,
+            tasks: 0,
+            failed: 0,
+            deltas: HashMap::new(),
+            merged_pages: 0,
+            unmerged_pages: 0,
+            elapsed: Duration::ZERO,
+            drain: None,
+            timings: Vec::new(),
+            probes: uksm::ProbeCounts::default(),
+        }
+    }
+
+    fn record(&mut self, pid: u64, before: &page::InfoStatus, after: &page::InfoStatus, ok: bool) {
+        self.tasks += 1;
+        if !ok {
+            self.failed += 1;
+        }
+
+        let delta = after.delta(before);
+        match self.work {
+            AsyncWork::Merge | AsyncWork::Range if delta.uksm_count > 0 => {
+                self.merged_pages += delta.uksm_count as u64;
+            }
+            AsyncWork::UnMerge | AsyncWork::UnMergeRecent | AsyncWork::Range
+                if delta.uksm_count < 0 =>
+            {
+                self.unmerged_pages += delta.uksm_count.unsigned_abs();
+            }
+            _ => {}
+        }
+        self.probes.add(&after.probes.since(&before.probes));
+
+        let d = self.deltas.entry(pid).or_default();
+        d.new_count += delta.new_count;
+        d.old_count += delta.old_count;
+        d.uksm_count += delta.uksm_count;
+    }
+
+    // If the work changed the merged pages or the tasks that the state file
+    // keeps.
+    pub fn changes_state(&self) -> bool {
+        matches!(self.work, AsyncWork::Del) || self.deltas.values().any(|d| d.uksm_count != 0)
+    }
+}
+
+impl std::fmt::Display for WorkReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (new, old, uksm) = self.deltas.values().fold((0, 0, 0), |(n, o, u), d| {
+            (n + d.new_count, o + d.old_count, u + d.uksm_count)
+        });
+        write!(
+            f,
+            "work {:?} handled {} tasks ({} failed) in {:?}, pages new {:+} old {:+} uksm {:+}, merged {} unmerged {}",
+            self.work,
+            self.tasks,
+            self.failed,
+            self.elapsed,
+            new,
+            old,
+            uksm,
+            self.merged_pages,
+            self.unmerged_pages
+        )?;
+        if self.probes.matched > 0 {
+            write!(f, ", {}", self.probes)?;
+        }
+        if let Some(drain) = self.drain {
+            write!(f, ", drained lru in {:?}", drain)?;
+        }
+        for t in self.timings.iter() {
+            write!(f, ", {}", t)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -99,33 +422,272 @@ pub struct Tasks {
     map: Arc<RwLock<HashMap<u64, TaskInfo>>>,
 
     // tasks should refresh
-    refresh_target: Arc<Mutex<Vec<TaskInfo>>>,
+    refresh_target: Arc<Mutex<queue::WorkQueue<TaskInfo>>>,
 
     // tasks should add to uksm
-    merge_target: Arc<Mutex<Vec<u64>>>,
+    merge_target: Arc<Mutex<queue::WorkQueue<u64>>>,
 
     // tasks should unmerge
-    unmerge_target: Arc<Mutex<Vec<u64>>>,
+    unmerge_target: Arc<Mutex<queue::WorkQueue<u64>>>,
 
     // tasks should del from tasks_pages
-    del_target: Arc<Mutex<Vec<u64>>>,
+    del_target: Arc<Mutex<queue::WorkQueue<u64>>>,
+
+    // tasks whose merged pages should be checked
+    verify_target: Arc<Mutex<queue::WorkQueue<u64>>>,
+
+    // the ranges of the tasks that should merge or unmerge
+    range_target: Arc<Mutex<queue::WorkQueue<RangeOp>>>,
+
+    // merged pages that the kernel broke in the current and the last
+    // verify cycle
+    verify_broken: Arc<AtomicU64>,
+    last_verify_broken: Arc<AtomicU64>,
+
+    // old pages that the last merge cycle skipped because of min_dup_count
+    last_dup_skipped: Arc<AtomicU64>,
+
+    // pages that should be unmerged by request and by the cpu pressure
+    unmerge_request_budget: Arc<AtomicU64>,
+    unmerge_policy_budget: Arc<AtomicU64>,
+    unmerged_by_request: Arc<AtomicU64>,
+    unmerged_by_policy: Arc<AtomicU64>,
+
+    // the totals of the work reports
+    merged_pages: Arc<AtomicU64>,
+    unmerged_pages: Arc<AtomicU64>,
+    failed_tasks: Arc<AtomicU64>,
 
     tasks_pages: Arc<Mutex<TasksPages>>,
+
+    // Set when uksmd is shutting down, refresh and merge work will stop.
+    stopping: Arc<AtomicBool>,
+
+    // Set when the kernel interfaces are gone, only del work runs until
+    // recover_kernel finds them again.
+    kernel_lost: Arc<AtomicBool>,
+
+    // Whether the last merge work drained the lru lists and how long it
+    // took in microseconds.
+    last_merge_drained: Arc<AtomicBool>,
+    last_merge_drain_us: Arc<AtomicU64>,
+    // The outcomes of the adds of the pages in the last merge cycle, it is
+    // reset when add_merge_all starts a cycle.
+    last_merge_probes: Arc<std::sync::Mutex<uksm::ProbeCounts>>,
+    // Set after the missing lru_add_drain_all is warned.
+    lru_drain_warned: Arc<AtomicBool>,
+
+    // The cpu time of the worker thread in microseconds, it is sampled
+    // after each work.
+    worker_cpu_us: Arc<AtomicU64>,
+
+    limits: Arc<ratelimit::RateLimits>,
+
+    load_throttle: Arc<sched::LoadThrottle>,
+
+    events: Arc<events::Events>,
+
+    // op name -> the timing of the last work cycle that called it
+    timings: Arc<std::sync::Mutex<HashMap<&'static str, timing::OpTiming>>>,
+
+    // max samples of the merge savings of each task
+    history_samples: usize,
+
+    // number of the tasks that are read concurrently in refresh
+    refresh_workers: usize,
+
+    // Set if the last Refresh or Merge work is Merge.
+    last_is_merge: bool,
+
+    // The merge work only runs in the merge windows unless it is forced.
+    // The forced merge lasts until merge_target is empty.
+    merge_window_open: bool,
+    merge_forced: bool,
+
+    max_tasks: usize,
+    max_addr_range: u64,
+    max_children: usize,
+    merge_chunk_pages: usize,
+    deterministic_order: bool,
+    lru_drain_before_merge: bool,
+    allow_dangerous_pids: bool,
+    skip_vm_flags: Arc<Vec<String>>,
+    vma_filter: Arc<proc::VmaFilter>,
+    min_vma_size: u64,
+    include_shmem: bool,
+    fast_maps: bool,
+    idle_filter: bool,
+    soft_dirty: bool,
+    // the tasks whose soft-dirty bits are cleared by the last refresh
+    soft_dirty_tasks: Arc<Mutex<HashSet<(u64, u64)>>>,
+    budget: Arc<page::PageBudget>,
+    crcs: Arc<page::CrcCounts>,
+    backend: Arc<dyn backend::KernelOps>,
+}
+
+// The VmFlags of the maps that are not refreshed.
+fn skip_vm_flags(config: &config::Config) -> Vec<String> {
+    let mut flags = config.skip_vm_flags.clone();
+    if !config.include_shared_anon {
+        flags.push("sh".to_string());
+    }
+    flags
 }
 
 impl Tasks {
-    pub fn new() -> Self {
+    pub fn new(config: &config::Config, events: Arc<events::Events>) -> Self {
+        Self::with_backend(config, events, backend::new(config))
+    }
+
+    // The tasks of the config of args on the fake kernel.
+    #[cfg(test)]
+    pub(crate) fn with_fake_kernel(args: &[&str], kernel: &Arc<backend::fake::FakeKernel>) -> Self {
+        use structopt::StructOpt;
+
+        proc::fake::root();
+        let config = config::Config::from_iter(["uksmd"].iter().chain(args).copied());
+        Self::with_backend(&config, Arc::new(events::Events::new(16)), kernel.clone())
+    }
+
+    pub(crate) fn with_backend(
+        config: &config::Config,
+        events: Arc<events::Events>,
+        backend: Arc<dyn backend::KernelOps>,
+    ) -> Self {
+        timing::set_enabled(config.timings);
+        // The regexes are checked when uksmd starts.
+        let vma_filter = proc::VmaFilter::new(&config.include_vma, &config.exclude_vma)
+            .unwrap_or_else(|e| {
+                error!("proc::VmaFilter::new failed: {}", e);
+                proc::VmaFilter::default()
+            });
+
         Self {
             map: Arc::new(RwLock::new(HashMap::new())),
-            refresh_target: Arc::new(Mutex::new(Vec::new())),
-            merge_target: Arc::new(Mutex::new(Vec::new())),
-            unmerge_target: Arc::new(Mutex::new(Vec::new())),
-            del_target: Arc::new(Mutex::new(Vec::new())),
-            tasks_pages: Arc::new(Mutex::new(TasksPages::new())),
+            refresh_target: Arc::new(Mutex::new(queue::WorkQueue::new())),
+            merge_target: Arc::new(Mutex::new(queue::WorkQueue::new())),
+            unmerge_target: Arc::new(Mutex::new(queue::WorkQueue::new())),
+            del_target: Arc::new(Mutex::new(queue::WorkQueue::new())),
+            verify_target: Arc::new(Mutex::new(queue::WorkQueue::new())),
+            range_target: Arc::new(Mutex::new(queue::WorkQueue::new())),
+            verify_broken: Arc::new(AtomicU64::new(0)),
+            last_verify_broken: Arc::new(AtomicU64::new(0)),
+            last_dup_skipped: Arc::new(AtomicU64::new(0)),
+            unmerge_request_budget: Arc::new(AtomicU64::new(0)),
+            unmerge_policy_budget: Arc::new(AtomicU64::new(0)),
+            unmerged_by_request: Arc::new(AtomicU64::new(0)),
+            unmerged_by_policy: Arc::new(AtomicU64::new(0)),
+            merged_pages: Arc::new(AtomicU64::new(0)),
+            unmerged_pages: Arc::new(AtomicU64::new(0)),
+            failed_tasks: Arc::new(AtomicU64::new(0)),
+            tasks_pages: Arc::new(Mutex::new(TasksPages::new(config, backend.clone()))),
+            stopping: Arc::new(AtomicBool::new(false)),
+            kernel_lost: Arc::new(AtomicBool::new(false)),
+            last_merge_drained: Arc::new(AtomicBool::new(false)),
+            last_merge_drain_us: Arc::new(AtomicU64::new(0)),
+            last_merge_probes: Arc::new(std::sync::Mutex::new(uksm::ProbeCounts::default())),
+            lru_drain_warned: Arc::new(AtomicBool::new(false)),
+            worker_cpu_us: Arc::new(AtomicU64::new(0)),
+            limits: Arc::new(ratelimit::RateLimits::new(
+                config.refresh_pages_per_sec,
+                config.merge_pages_per_sec,
+            )),
+            load_throttle: Arc::new(sched::LoadThrottle::new(config)),
+            events,
+            timings: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            history_samples: config.history_samples,
+            refresh_workers: config.refresh_workers.max(1),
+            last_is_merge: true,
+            merge_window_open: true,
+            merge_forced: false,
+            max_tasks: config.max_tasks,
+            max_addr_range: config.max_addr_range,
+            max_children: config.max_children,
+            merge_chunk_pages: config.merge_chunk_pages,
+            deterministic_order: config.deterministic_order,
+            lru_drain_before_merge: config.lru_drain_before_merge,
+            allow_dangerous_pids: config.allow_dangerous_pids,
+            skip_vm_flags: Arc::new(skip_vm_flags(config)),
+            vma_filter: Arc::new(vma_filter),
+            min_vma_size: config.min_vma_size,
+            include_shmem: config.include_shmem,
+            // The shared anonymous maps can only be found by smaps.
+            fast_maps: config.fast_maps && !config.include_shared_anon,
+            idle_filter: config.idle_filter,
+            soft_dirty: config.soft_dirty,
+            soft_dirty_tasks: Arc::new(Mutex::new(HashSet::new())),
+            budget: Arc::new(page::PageBudget::new(config.max_tracked_pages)),
+            crcs: Arc::new(page::CrcCounts::new(
+                config,
+                config.zero_page_crc.or_else(|| backend.zero_crc()),
+            )),
+            backend,
+        }
+    }
+
+    // Check if pid can be added and return its start time.
+    fn check_pid(&self, pid: u64) -> Result<u64> {
+        // Merging the pages of uksmd itself might deadlock the worker.
+        if proc::pid_is_self(pid) {
+            return Err(anyhow::Error::new(TaskError::InvalidArg(format!(
+                "pid {} is uksmd itself",
+                pid
+            ))));
+        }
+        if !self.allow_dangerous_pids && proc::pid_is_dangerous(pid) {
+            return Err(anyhow::Error::new(TaskError::InvalidArg(format!(
+                "pid {} ({}) is init, set allow_dangerous_pids to add it",
+                pid,
+                proc::pid_comm(pid).unwrap_or_default()
+            ))));
+        }
+        proc::pid_is_available(pid)
+            .map_err(|e| anyhow!("proc::pid_is_available {} failed: {}", pid, e))?;
+
+        proc::pid_start_time(pid).map_err(|e| anyhow!("proc::pid_start_time {} failed: {}", pid, e))
+    }
+
+    // Check the address range of a task.
+    // A range that is larger than max_addr_range is only accepted with force.
+    // u64::is_multiple_of needs rust 1.87, keep building with the older ones.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn check_addr(&self, start: u64, end: u64, force: bool) -> Result<()> {
+        let invalid = |estr: String| Err(anyhow::Error::new(TaskError::InvalidArg(estr)));
+
+        if start % *page::PAGE_SIZE != 0 {
+            return invalid(format!("start 0x{:x} is not aligned to the page", start));
         }
+        if end % *page::PAGE_SIZE != 0 {
+            return invalid(format!("end 0x{:x} is not aligned to the page", end));
+        }
+        if start >= end {
+            return invalid(format!("start 0x{:x} is not below end 0x{:x}", start, end));
+        }
+        if end > proc::USER_ADDR_END {
+            return invalid(format!(
+                "end 0x{:x} is beyond the user address space 0x{:x}",
+                end,
+                proc::USER_ADDR_END
+            ));
+        }
+        if self.max_addr_range != 0 && end - start > self.max_addr_range {
+            if !force {
+                return invalid(format!(
+                    "range 0x{:x}-0x{:x} is larger than max_addr_range {}, add it with force",
+                    start, end, self.max_addr_range
+                ));
+            }
+            warn!(
+                "range 0x{:x}-0x{:x} is larger than max_addr_range {}, accept it with force",
+                start, end, self.max_addr_range
+            );
+        }
+
+        Ok(())
     }
 
-    pub async fn add(&mut self, req: uksmd_ctl::AddRequest) -> Result<()> {
+    // Add a task and return it and the vmas that are selected in its range.
+    pub async fn add(&mut self, req: uksmd_ctl::AddRequest) -> Result<AddScope> {
         let mut addr = None;
         if let Some(oaddr) = req.OptAddr {
             match oaddr {
@@ -135,27 +697,101 @@ impl Tasks {
             }
         }
 
-        proc::pid_is_available(req.pid)
-            .map_err(|e| anyhow!("proc::pid_is_available {} failed: {}", req.pid, e))?;
+        let thp = match req.OptThp {
+            Some(uksmd_ctl::add_request::OptThp::Thp(thp)) => Some(thp.parse()?),
+            None => None,
+        };
+
+        // Check the regexes.
+        proc::VmaFilter::new(&req.include_vma, &req.exclude_vma)?;
+
+        if req.align {
+            addr = addr
+                .map(|(start, end)| {
+                    let mask = *page::PAGE_SIZE - 1;
+                    end.checked_add(mask)
+                        .map(|end| (start & !mask, end & !mask))
+                        .ok_or_else(|| {
+                            anyhow::Error::new(TaskError::InvalidArg(format!(
+                                "end 0x{:x} cannot be aligned to the page",
+                                end
+                            )))
+                        })
+                })
+                .transpose()?;
+        }
         if let Some((start, end)) = addr {
-            if start % *page::PAGE_SIZE != 0 || end % *page::PAGE_SIZE != 0 {
-                return Err(anyhow!("start {} or end {} is not right", start, end));
+            self.check_addr(start, end, req.force)?;
+        }
+        // Check the map before the task is parsed, it is checked again when
+        // the task is inserted.
+        self.check_insert(&*self.map.read().await, req.pid)?;
+        let start_time = self.check_pid(req.pid)?;
+        let mut task = TaskInfo::new(req.pid, addr, start_time, thp);
+        task.include_vma = req.include_vma;
+        task.exclude_vma = req.exclude_vma;
+        if let Some(uksmd_ctl::add_request::OptMinVmaSize::MinVmaSize(size)) = req.OptMinVmaSize {
+            task.min_vma_size = Some(size);
+        }
+        if let Some(uksmd_ctl::add_request::OptPriority::Priority(priority)) = req.OptPriority {
+            task.priority = priority;
+        }
+        if let Some(uksmd_ctl::add_request::OptIncludeShmem::IncludeShmem(include)) =
+            req.OptIncludeShmem
+        {
+            task.include_shmem = Some(include);
+        }
+        task.follow_children = req.follow_children;
+        task.update_proc_info();
+
+        // The smaps of the task are parsed again by the refresh, the scope
+        // is only for the users to see what the range covers.
+        let mut scope = AddScope {
+            task: task.clone(),
+            vmas: 0,
+            bytes: 0,
+        };
+        match proc::parse_task_smaps(
+            &task,
+            &self.skip_vm_flags,
+            &self.task_vma_filter(&task),
+            task.min_vma_size.unwrap_or(self.min_vma_size),
+            task.include_shmem.unwrap_or(self.include_shmem),
+        ) {
+            Ok(maps) => {
+                scope.vmas = maps.len() as u64;
+                scope.bytes = maps.iter().map(|m| m.end - m.start).sum();
             }
+            Err(e) => warn!("get the scope of task {} failed: {}", req.pid, e),
         }
 
         {
             let mut map = self.map.write().await;
-            if map.contains_key(&req.pid) {
-                return Err(anyhow!("pid {} exists", req.pid));
-            }
-
-            map.insert(req.pid, TaskInfo::new(req.pid, addr));
+            self.check_insert(&map, req.pid)?;
+            map.insert(req.pid, task.clone());
         }
 
-        self.refresh_target
-            .lock()
-            .await
-            .push(TaskInfo::new(req.pid, addr));
+        self.refresh_target.lock().await.push(task);
+
+        info!(
+            "add task {} with {} vmas {} bytes",
+            req.pid, scope.vmas, scope.bytes
+        );
+
+        Ok(scope)
+    }
+
+    // If pid can be inserted into map.
+    fn check_insert(&self, map: &HashMap<u64, TaskInfo>, pid: u64) -> Result<()> {
+        if map.contains_key(&pid) {
+            return Err(anyhow!("pid {} exists", pid));
+        }
+        if map.len() >= self.max_tasks {
+            return Err(anyhow::Error::new(TaskError::Limit(format!(
+                "tasks number reaches the limit {}",
+                self.max_tasks
+            ))));
+        }
 
         Ok(())
     }
@@ -163,16 +799,17 @@ impl Tasks {
     pub async fn del(&mut self, req: uksmd_ctl::DelRequest) -> Result<()> {
         let mut map = self.map.write().await;
 
-        if let Some(_) = map.remove(&req.pid) {
-            self.refresh_target
-                .lock()
-                .await
-                .retain(|task| task.pid != req.pid);
-            self.merge_target.lock().await.retain(|pid| *pid != req.pid);
-            self.unmerge_target
-                .lock()
-                .await
-                .retain(|pid| *pid != req.pid);
+        if map.get(&req.pid).is_some_and(|t| t.discovered) && !req.force {
+            return Err(anyhow::Error::new(TaskError::InvalidArg(format!(
+                "pid {} is added by the discovery rules, del it with force",
+                req.pid
+            ))));
+        }
+        if map.remove(&req.pid).is_some() {
+            self.refresh_target.lock().await.remove(req.pid);
+            self.merge_target.lock().await.remove(req.pid);
+            self.range_target.lock().await.remove(req.pid);
+            self.verify_target.lock().await.remove(req.pid);
 
             self.unmerge_target.lock().await.push(req.pid);
             self.del_target.lock().await.push(req.pid);
@@ -183,123 +820,3429 @@ impl Tasks {
         Ok(())
     }
 
-    pub async fn add_refresh_all(&mut self) {
-        let mut set: HashSet<TaskInfo> = self
-            .map
-            .write()
+    // Unmerge the pages of all the tasks, they are still tracked and the
+    // later merges merge them again. The queued merges are dropped.
+    // Return the number of the tasks.
+    pub async fn add_unmerge_all(&self) -> usize {
+        let pids: Vec<u64> = self.map.read().await.keys().cloned().collect();
+        self.merge_target.lock().await.clear();
+        self.range_target.lock().await.retain(|op| op.unmerge);
+        self.unmerge_target
+            .lock()
             .await
-            .clone()
-            .into_iter()
-            .map(|(_, v)| v)
-            .collect();
+            .extend(pids.iter().cloned());
+
+        pids.len()
+    }
+
+    // Merge or unmerge the pages of pid in addr, all the pages of pid if
+    // addr is None. A pending range of pid is replaced.
+    pub async fn add_range(&self, pid: u64, addr: Option<(u64, u64)>, unmerge: bool) -> Result<()> {
+        if let Some((start, end)) = addr {
+            self.check_addr(start, end, false)?;
+        }
+        match self.map.read().await.get(&pid) {
+            Some(task) if task.paused && !unmerge => {
+                return Err(anyhow::Error::new(TaskError::FailedPrecondition(format!(
+                    "pid {} is paused",
+                    pid
+                ))));
+            }
+            Some(_) => {}
+            None => return Err(anyhow!("pid {} does not exist", pid)),
+        }
+
+        let (start, end) = addr.unwrap_or((0, proc::USER_ADDR_END));
+        self.range_target.lock().await.push(RangeOp {
+            pid,
+            start,
+            end,
+            unmerge,
+        });
 
-        let mut target = self.refresh_target.lock().await;
+        Ok(())
+    }
+
+    // Pause or resume the refresh and merge of a task.
+    // The unmerge and del of the task still work.
+    pub async fn set_paused(&mut self, pid: u64, paused: bool) -> Result<()> {
+        let mut map = self.map.write().await;
 
-        for t in target.clone() {
-            set.insert(t);
+        if let Some(task) = map.get_mut(&pid) {
+            task.paused = paused;
+            if paused {
+                self.refresh_target.lock().await.remove(pid);
+                self.merge_target.lock().await.remove(pid);
+                self.range_target
+                    .lock()
+                    .await
+                    .retain(|op| op.pid != pid || op.unmerge);
+            }
+        } else {
+            return Err(anyhow!("pid {} does not exist", pid));
         }
 
-        *target = set.into_iter().collect();
+        Ok(())
     }
 
-    pub async fn add_merge_all(&mut self) {
-        let mut set: HashSet<u64> = self
-            .map
-            .write()
+    // Change the options of a task that are set in req. The pending merge
+    // of a task whose priority is changed is moved to the place of the new
+    // priority as add_merge_all orders them.
+    pub async fn update(&mut self, req: &uksmd_ctl::UpdateRequest) -> Result<()> {
+        let pid = req.pid;
+        let mut map = self.map.write().await;
+        let task = map
+            .get_mut(&pid)
+            .ok_or_else(|| anyhow!("pid {} does not exist", pid))?;
+
+        if let Some(uksmd_ctl::update_request::OptPriority::Priority(priority)) = req.OptPriority {
+            task.priority = priority;
+            let mut merge_target = self.merge_target.lock().await;
+            if merge_target.contains(pid) {
+                merge_target.sort_by_key(|pid| map.get(pid).map_or(0, |t| t.priority));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn priorities(&self) -> HashMap<u64, i32> {
+        self.map
+            .read()
             .await
-            .clone()
-            .into_iter()
-            .map(|(k, _)| k)
-            .collect();
+            .values()
+            .map(|t| (t.pid, t.priority))
+            .collect()
+    }
+
+    // The Old pages that are left to the next chunks of the merge of the
+    // tasks whose merge is split.
+    pub async fn merge_remaining(&self) -> HashMap<u64, u64> {
+        self.tasks_pages
+            .lock()
+            .await
+            .pages_info
+            .iter()
+            .filter_map(|(pid, p)| p.merge_remaining().map(|r| (*pid, r)))
+            .collect()
+    }
+
+    // The operation that is running on each task, or idle.
+    pub async fn task_states(&self) -> HashMap<u64, String> {
+        let pids: Vec<u64> = self.map.read().await.keys().cloned().collect();
+        let tasks_pages = self.tasks_pages.lock().await;
+        pids.into_iter()
+            .map(|pid| {
+                let state = tasks_pages
+                    .busy
+                    .get(&pid)
+                    .map_or("idle".to_string(), |s| s.to_string());
+                (pid, state)
+            })
+            .collect()
+    }
+
+    pub async fn paused_pids(&self) -> Vec<u64> {
+        self.map
+            .read()
+            .await
+            .values()
+            .filter(|t| t.paused)
+            .map(|t| t.pid)
+            .collect()
+    }
+
+    fn is_gone(task: &TaskInfo) -> bool {
+        proc::pid_start_time(task.pid).ok() != Some(task.start_time)
+            || proc::pid_is_available(task.pid).is_err()
+    }
 
-        let mut target = self.merge_target.lock().await;
+    // Del the task that exited as the del request does, but its pages are
+    // gone and don't need unmerge.
+    pub async fn reap(&mut self, pid: u64) {
+        let mut map = self.map.write().await;
 
-        for t in target.clone() {
-            set.insert(t);
+        if !map.get(&pid).is_some_and(Self::is_gone) {
+            return;
         }
+        map.remove(&pid);
+        self.refresh_target.lock().await.remove(pid);
+        self.merge_target.lock().await.remove(pid);
+        self.range_target.lock().await.remove(pid);
+        self.verify_target.lock().await.remove(pid);
+        self.del_target.lock().await.push(pid);
 
-        *target = set.into_iter().collect();
+        info!("reap task {} because it exited", pid);
     }
 
-    fn async_work_thread(&mut self, work: AsyncWork) -> Result<()> {
-        if let AsyncWork::Merge = work {
-            uksm::lru_add_drain_all()?;
+    // Reap all the tasks that exited.
+    pub async fn reap_gone(&mut self) {
+        let gone: Vec<u64> = self
+            .map
+            .read()
+            .await
+            .values()
+            .filter(|t| Self::is_gone(t))
+            .map(|t| t.pid)
+            .collect();
+
+        for pid in gone {
+            self.reap(pid).await;
         }
+    }
 
-        loop {
-            let ht = {
-                match work {
-                    AsyncWork::UnMerge => {
-                        if let Some(pid) = self.unmerge_target.blocking_lock().pop() {
-                            HandleTask::UnMerge(pid)
-                        } else {
-                            break;
-                        }
-                    }
-                    AsyncWork::Del => {
-                        if let Some(pid) = self.del_target.blocking_lock().pop() {
-                            HandleTask::Del(pid)
-                        } else {
-                            break;
-                        }
-                    }
-                    AsyncWork::Refresh => {
-                        if let Some(t) = self.refresh_target.blocking_lock().pop() {
-                            HandleTask::Refresh(t)
-                        } else {
-                            break;
-                        }
-                    }
-                    AsyncWork::Merge => {
-                        if let Some(pid) = self.merge_target.blocking_lock().pop() {
-                            HandleTask::Merge(pid)
-                        } else {
-                            break;
-                        }
-                    }
+    // Add the new children of the tasks that follow their children with
+    // the settings of their parents.
+    // The children that exit are del as the other tasks.
+    async fn add_children(&mut self) {
+        let parents: Vec<TaskInfo> = self
+            .map
+            .read()
+            .await
+            .values()
+            .filter(|t| t.follow_children && !t.paused)
+            .cloned()
+            .collect();
+
+        for parent in parents {
+            let children = match proc::pid_children(parent.pid) {
+                Ok(children) => children,
+                Err(e) => {
+                    trace!("proc::pid_children {} failed: {}", parent.pid, e);
+                    continue;
                 }
             };
 
-            if let Err(e) = self.tasks_pages.blocking_lock().handle_task(ht.clone()) {
-                error!("handle_task {:?} failed: {}", ht, e)
+            let mut map = self.map.write().await;
+            let mut count = map
+                .values()
+                .filter(|t| t.parent == Some(parent.pid))
+                .count();
+            for pid in children {
+                if map.contains_key(&pid) {
+                    continue;
+                }
+                // Guard against the fork bombs.
+                if count >= self.max_children {
+                    warn!(
+                        "children of task {} reach the limit {}",
+                        parent.pid, self.max_children
+                    );
+                    break;
+                }
+                if map.len() >= self.max_tasks {
+                    warn!("tasks number reaches the limit {}", self.max_tasks);
+                    return;
+                }
+                let start_time = match self.check_pid(pid) {
+                    Ok(start_time) => start_time,
+                    Err(e) => {
+                        trace!("child {} of task {} is skipped: {}", pid, parent.pid, e);
+                        continue;
+                    }
+                };
+
+                let mut task = parent.clone();
+                task.pid = pid;
+                task.start_time = start_time;
+                task.parent = Some(parent.pid);
+                task.update_proc_info();
+                info!("add child {} ({}) of task {}", pid, task.comm, parent.pid);
+                map.insert(pid, task);
+                count += 1;
             }
         }
+    }
 
-        Ok(())
+    // Return the parent of the tasks that are added as children.
+    pub async fn parents(&self) -> HashMap<u64, u64> {
+        self.map
+            .read()
+            .await
+            .values()
+            .filter_map(|t| t.parent.map(|parent| (t.pid, parent)))
+            .collect()
     }
 
-    //merge: true is merge, false is refresh
-    pub async fn async_work(&mut self, ret_tx: mpsc::Sender<Result<()>>) -> bool {
-        let work = if self.unmerge_target.lock().await.len() > 0 {
-            AsyncWork::UnMerge
-        } else if self.del_target.lock().await.len() > 0 {
-            AsyncWork::Del
-        } else if self.refresh_target.lock().await.len() > 0 {
-            AsyncWork::Refresh
-        } else if self.merge_target.lock().await.len() > 0 {
-            AsyncWork::Merge
-        } else {
-            return false;
-        };
+    // Mark the task as added from the watched cgroup.
+    pub async fn set_watch(&mut self, pid: u64, cgroup: &str, labels: &[String]) {
+        if let Some(task) = self.map.write().await.get_mut(&pid) {
+            task.watch = Some(cgroup.to_string());
+            task.labels = labels.to_vec();
+        }
+    }
 
-        let mut tasks = self.clone();
+    // Mark the task as added by a discovery rule.
+    pub async fn set_discovered(&mut self, pid: u64, labels: &[String]) {
+        if let Some(task) = self.map.write().await.get_mut(&pid) {
+            task.discovered = true;
+            task.labels = labels.to_vec();
+        }
+    }
 
-        thread::spawn(move || {
-            info!("async_work_thread {:?} start", work);
+    pub async fn discovered_pids(&self) -> HashSet<u64> {
+        self.map
+            .read()
+            .await
+            .values()
+            .filter(|t| t.discovered)
+            .map(|t| t.pid)
+            .collect()
+    }
 
-            let ret = tasks.async_work_thread(work.clone());
+    // The pids of all the tasks.
+    pub async fn pids(&self) -> HashSet<u64> {
+        self.map.read().await.keys().copied().collect()
+    }
 
-            if let Err(e) = ret_tx.blocking_send(ret) {
-                error!(
-                    "async_work_thread {:?} ret_tx.blocking_send failed: {}",
-                    work, e
-                );
-                return;
+    // The tasks that are added from each watched cgroup.
+    pub async fn watched_pids(&self) -> HashMap<String, HashSet<u64>> {
+        let mut watched: HashMap<String, HashSet<u64>> = HashMap::new();
+        for task in self.map.read().await.values() {
+            if let Some(cgroup) = &task.watch {
+                watched.entry(cgroup.clone()).or_default().insert(task.pid);
             }
+        }
 
-            info!("async_work_thread {:?} stop", work);
-        });
+        watched
+    }
+
+    // Return the watched cgroups that the tasks are added from.
+    pub async fn watches(&self) -> HashMap<u64, String> {
+        self.map
+            .read()
+            .await
+            .values()
+            .filter_map(|t| t.watch.as_ref().map(|cgroup| (t.pid, cgroup.clone())))
+            .collect()
+    }
+
+    // Return the labels of the tasks joined by ",".
+    pub async fn labels(&self) -> HashMap<u64, String> {
+        self.map
+            .read()
+            .await
+            .values()
+            .filter(|t| !t.labels.is_empty())
+            .map(|t| (t.pid, t.labels.join(",")))
+            .collect()
+    }
+
+    pub async fn add_refresh_all(&mut self) {
+        self.add_children().await;
+
+        // Lock map before refresh_target as load_state does.
+        let map = self.map.read().await;
+        self.refresh_target
+            .lock()
+            .await
+            .extend(map.values().filter(|t| !t.paused).cloned());
+    }
+
+    pub async fn add_merge_all(&mut self) {
+        let tasks: Vec<(i32, u64)> = self
+            .map
+            .read()
+            .await
+            .values()
+            .filter(|t| !t.paused)
+            .map(|t| (t.priority, t.pid))
+            .collect();
+        let mut pids: Vec<(i32, u64, u64)> = {
+            let tasks_pages = self.tasks_pages.lock().await;
+            tasks
+                .into_iter()
+                .map(|(priority, pid)| {
+                    let pages = tasks_pages.pages_info.get(&pid).map_or(0, |p| {
+                        let status = p.get_status();
+                        status.new_count + status.old_count + status.uksm_count
+                    });
+                    (priority, pages, pid)
+                })
+                .collect()
+        };
+
+        self.last_dup_skipped
+            .store(self.crcs.take_skipped(), Ordering::SeqCst);
+        *self.last_merge_probes.lock().unwrap() = uksm::ProbeCounts::default();
+
+        // The worker pops the pids from the tail of merge_target, push the
+        // task with the highest priority and the most pages last.
+        // The page counts change from run to run, so deterministic_order
+        // uses the lowest pid instead of the most pages.
+        if self.deterministic_order {
+            pids.sort_unstable_by_key(|(priority, _, pid)| (*priority, std::cmp::Reverse(*pid)));
+        } else {
+            pids.sort_unstable();
+        }
+        self.merge_target
+            .lock()
+            .await
+            .extend(pids.into_iter().map(|(_, _, pid)| pid));
+    }
+
+    // Start a verify cycle of all the tasks.
+    pub async fn add_verify_all(&mut self) {
+        let pids: Vec<u64> = self.map.read().await.keys().cloned().collect();
+
+        self.last_verify_broken.store(
+            self.verify_broken.swap(0, Ordering::SeqCst),
+            Ordering::SeqCst,
+        );
+
+        let mut target = self.verify_target.lock().await;
+        for pid in pids {
+            target.push(pid);
+        }
+    }
+
+    pub async fn save_state(&self, path: &str) -> Result<()> {
+        let tasks = self.map.read().await.values().cloned().collect();
+
+        let (pages_info, uksm) = {
+            let tasks_pages = self.tasks_pages.lock().await;
+            (
+                tasks_pages
+                    .pages_info
+                    .values()
+                    .map(|p| p.to_state())
+                    .collect(),
+                tasks_pages.uksm.to_state(),
+            )
+        };
+
+        state::save(
+            path,
+            &state::State {
+                version: state::STATE_VERSION,
+                tasks,
+                pages_info,
+                uksm,
+            },
+        )
+    }
+
+    // Load the tasks and pages from the state file and refresh the tasks
+    // that are still alive to reconcile with the kernel.
+    pub async fn load_state(&mut self, path: &str) -> Result<()> {
+        let state = if let Some(state) = state::load(path)? {
+            state
+        } else {
+            return Ok(());
+        };
+
+        let mut alive = HashSet::new();
+        {
+            let mut map = self.map.write().await;
+            let mut refresh_target = self.refresh_target.lock().await;
+            for mut task in state.tasks {
+                match proc::pid_start_time(task.pid) {
+                    Ok(start_time) if start_time == task.start_time => {
+                        task.update_proc_info();
+                        alive.insert(task.pid);
+                        map.insert(task.pid, task.clone());
+                        refresh_target.push(task);
+                    }
+                    _ => {
+                        info!("state task {:?} is gone, discard it", task);
+                    }
+                }
+            }
+        }
+
+        let mut tasks_pages = self.tasks_pages.lock().await;
+        for p in state.pages_info {
+            if alive.contains(&p.pid) {
+                tasks_pages.pages_info.insert(
+                    p.pid,
+                    page::Info::from_state(p, self.budget.clone(), self.crcs.clone()),
+                );
+            }
+        }
+        tasks_pages.uksm.load_state(state.uksm, &alive);
+
+        info!("load {} tasks from state file {}", alive.len(), path);
+
+        Ok(())
+    }
+
+    pub async fn set_config(&self, req: &uksmd_ctl::SetConfigRequest) -> Result<()> {
+        let merge_scope = match &req.OptMergeScope {
+            Some(uksmd_ctl::set_config_request::OptMergeScope::MergeScope(scope)) => {
+                Some(scope.parse::<uksm::MergeScope>()?)
+            }
+            None => None,
+        };
+
+        if let Some(uksmd_ctl::set_config_request::OptRefreshRate::RefreshPagesPerSec(rate)) =
+            req.OptRefreshRate
+        {
+            self.limits.refresh.set_rate(rate);
+        }
+        if let Some(uksmd_ctl::set_config_request::OptMergeRate::MergePagesPerSec(rate)) =
+            req.OptMergeRate
+        {
+            self.limits.merge.set_rate(rate);
+        }
+        if let Some(uksmd_ctl::set_config_request::OptMinDupCount::MinDupCount(count)) =
+            req.OptMinDupCount
+        {
+            self.crcs.set_min_dup_count(count);
+        }
+        if let Some(uksmd_ctl::set_config_request::OptMaxLoad::MaxLoad(max_load)) = req.OptMaxLoad {
+            self.load_throttle.set_max_load(max_load);
+        }
+        if let Some(scope) = merge_scope {
+            self.tasks_pages.lock().await.uksm.set_merge_scope(scope);
+        }
+
+        Ok(())
+    }
+
+    pub async fn stats(&self) -> uksmd_ctl::StatsResponse {
+        let tasks_pages = self.tasks_pages.lock().await;
+
+        let rss_bytes = proc::self_rss().unwrap_or_default();
+        let saved_bytes = tasks_pages.uksm.saved_total() * *page::PAGE_SIZE;
+        let internal_bytes = tasks_pages.uksm.mem_bytes()
+            + tasks_pages
+                .pages_info
+                .values()
+                .map(|p| p.mem_bytes())
+                .sum::<u64>()
+            + self.crcs.mem_bytes();
+        let probes = *self.last_merge_probes.lock().unwrap();
+
+        uksmd_ctl::StatsResponse {
+            tasks: self.map.read().await.len() as u64,
+            max_tasks: self.max_tasks as u64,
+            tracked_pages: self.budget.used(),
+            max_tracked_pages: self.budget.limit(),
+            refresh_pages_per_sec_limit: self.limits.refresh.rate(),
+            refresh_pages_per_sec: self.limits.refresh.achieved(),
+            merge_pages_per_sec_limit: self.limits.merge.rate(),
+            merge_pages_per_sec: self.limits.merge.achieved(),
+            merge_give_ups: tasks_pages.uksm.give_ups(),
+            merge_shared_frames: tasks_pages.uksm.shared_frames(),
+            kernel_retries: tasks_pages.uksm.retried(),
+            merge_deferred: tasks_pages.uksm.deferred(),
+            kernel_stats: uksm::kernel_stats(),
+            verify_broken_pages: self.last_verify_broken.load(Ordering::SeqCst),
+            merged_pages: self.merged_pages.load(Ordering::SeqCst),
+            unmerged_pages: self.unmerged_pages.load(Ordering::SeqCst),
+            failed_tasks: self.failed_tasks.load(Ordering::SeqCst),
+            unmerged_by_request: self.unmerged_by_request.load(Ordering::SeqCst),
+            unmerged_by_policy: self.unmerged_by_policy.load(Ordering::SeqCst),
+            min_dup_count: self.crcs.min_dup_count(),
+            merge_scope: tasks_pages.uksm.merge_scope().to_string(),
+            merge_scope_skipped: tasks_pages.uksm.scope_skipped(),
+            merge_dup_skipped_pages: self.last_dup_skipped.load(Ordering::SeqCst),
+            zero_pages: tasks_pages
+                .pages_info
+                .values()
+                .map(|p| p.get_status().zero_count)
+                .sum(),
+            stale_pages: tasks_pages
+                .pages_info
+                .values()
+                .map(|p| p.get_status().stale_count)
+                .sum(),
+            rss_bytes,
+            peak_rss_bytes: proc::self_peak_rss().unwrap_or_default(),
+            internal_bytes,
+            worker_cpu_us: self.worker_cpu_us.load(Ordering::SeqCst),
+            saved_bytes,
+            saved_rss_ratio: if rss_bytes > 0 {
+                saved_bytes as f64 / rss_bytes as f64
+            } else {
+                0.0
+            },
+            last_merge_crc_matched: probes.matched,
+            last_merge_first_probe: probes.first_probe,
+            last_merge_multi_probe: probes.multi_probe,
+            last_merge_crc_collisions: probes.exhausted,
+            ..Default::default()
+        }
+    }
+
+    pub fn events(&self) -> &events::Events {
+        &self.events
+    }
+
+    pub fn kernel_lost(&self) -> bool {
+        self.kernel_lost.load(Ordering::SeqCst)
+    }
+
+    fn set_kernel_lost(&self, reason: &dyn std::fmt::Display) {
+        if !self.kernel_lost.swap(true, Ordering::SeqCst) {
+            let msg = format!("kernel interfaces are lost, pause the work: {}", reason);
+            warn!("{}", msg);
+            self.events.push(events::Severity::Warn, msg);
+        }
+    }
+
+    // Probe the kernel interfaces that are lost. If they are back, the
+    // kernel has unmerged all the pages, make them New and refresh all the
+    // tasks to merge them again.
+    pub async fn recover_kernel(&mut self) {
+        if !self.kernel_lost() || !self.backend.available() {
+            return;
+        }
+
+        let pages = {
+            let mut tasks_pages = self.tasks_pages.lock().await;
+            tasks_pages.uksm.reset();
+            tasks_pages
+                .pages_info
+                .values_mut()
+                .map(|p| p.reset_merged())
+                .sum::<u64>()
+        };
+        self.kernel_lost.store(false, Ordering::SeqCst);
+
+        let msg = format!(
+            "kernel interfaces are back, {} merged pages will be merged again",
+            pages
+        );
+        info!("{}", msg);
+        self.events.push(events::Severity::Info, msg);
+
+        self.add_refresh_all().await;
+    }
+
+    // The pages that the refresh read since uksmd starts.
+    pub fn refreshed_pages(&self) -> u64 {
+        self.limits.refresh.total()
+    }
+
+    pub async fn merge_compares(&self) -> (u64, u64) {
+        self.tasks_pages.lock().await.uksm.compares()
+    }
+
+    pub fn load_throttle(&self) -> &sched::LoadThrottle {
+        &self.load_throttle
+    }
+
+    pub fn set_merge_window_open(&mut self, open: bool) {
+        if self.merge_window_open != open {
+            info!("merge window is {}", if open { "open" } else { "closed" });
+        }
+        self.merge_window_open = open;
+    }
+
+    pub fn merge_window_open(&self) -> bool {
+        self.merge_window_open
+    }
+
+    // Run the queued merge work even if the merge window is closed.
+    pub fn force_merge(&mut self) {
+        self.merge_forced = true;
+    }
+
+    // If the queued merge work waits for the merge window.
+    pub async fn merge_deferred(&self) -> bool {
+        !self.merge_window_open && !self.merge_forced && !self.merge_target.lock().await.is_empty()
+    }
+
+    // Unmerge at most budget pages that are merged last.
+    pub fn add_unmerge_recent(&self, budget: u64, by_request: bool) {
+        let target = if by_request {
+            &self.unmerge_request_budget
+        } else {
+            &self.unmerge_policy_budget
+        };
+        target.fetch_add(budget, Ordering::SeqCst);
+    }
+
+    // Add the numbers of a work report to the stats.
+    pub fn record_report(&self, report: &WorkReport) {
+        self.merged_pages
+            .fetch_add(report.merged_pages, Ordering::SeqCst);
+        self.unmerged_pages
+            .fetch_add(report.unmerged_pages, Ordering::SeqCst);
+        self.failed_tasks.fetch_add(report.failed, Ordering::SeqCst);
+        if let AsyncWork::Merge = report.work {
+            self.last_merge_drained
+                .store(report.drain.is_some(), Ordering::SeqCst);
+            self.last_merge_drain_us.store(
+                report.drain.map_or(0, |d| d.as_micros() as u64),
+                Ordering::SeqCst,
+            );
+            self.last_merge_probes.lock().unwrap().add(&report.probes);
+        }
+
+        let mut timings = self.timings.lock().unwrap();
+        for t in report.timings.iter() {
+            timings.insert(t.name, t.clone());
+        }
+    }
+
+    // The timings of the operations in the last work cycles.
+    pub fn op_timings(&self) -> Vec<uksmd_ctl::OpTiming> {
+        let mut timings: Vec<uksmd_ctl::OpTiming> = self
+            .timings
+            .lock()
+            .unwrap()
+            .values()
+            .map(|t| uksmd_ctl::OpTiming {
+                name: t.name.to_string(),
+                calls: t.calls,
+                total_us: t.total.as_micros() as u64,
+                max_us: t.max.as_micros() as u64,
+                ..Default::default()
+            })
+            .collect();
+        timings.sort_by(|a, b| a.name.cmp(&b.name));
+
+        timings
+    }
+
+    pub fn skipped_pages(&self) -> u64 {
+        self.budget.skipped()
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    pub fn kernel_caps(&self) -> Vec<String> {
+        self.backend.caps()
+    }
+
+    // Drain the lru lists and return how long it took, None if the backend
+    // can't drain them.
+    fn lru_drain(&self) -> Result<Option<Duration>> {
+        if !self.backend.can_lru_drain() {
+            if !self.lru_drain_warned.swap(true, Ordering::SeqCst) {
+                warn!("lru_add_drain_all is not supported, skip the lru drain before the merge");
+            }
+            return Ok(None);
+        }
+
+        let now = Instant::now();
+        self.backend.lru_drain()?;
+        Ok(Some(now.elapsed()))
+    }
+
+    // Drain the lru lists for the Drain RPC.
+    pub async fn drain(&self) -> Result<Duration> {
+        if self.kernel_lost() {
+            return Err(anyhow::Error::new(TaskError::Unavailable(
+                "the kernel interfaces are lost".to_string(),
+            )));
+        }
+        if !self.backend.can_lru_drain() {
+            return Err(anyhow::Error::new(TaskError::Unavailable(format!(
+                "backend {} doesn't support lru_add_drain_all",
+                self.backend.name()
+            ))));
+        }
+
+        let backend = self.backend.clone();
+        let now = Instant::now();
+        match tokio::task::spawn_blocking(move || backend.lru_drain())
+            .await
+            .map_err(|e| anyhow!("join lru_drain failed: {}", e))?
+        {
+            Err(e) if uksm::is_kernel_lost(&e) => {
+                self.set_kernel_lost(&e);
+                Err(anyhow::Error::new(TaskError::Unavailable(e.to_string())))
+            }
+            ret => ret.map(|_| now.elapsed()),
+        }
+    }
+
+    // Whether the last merge work drained the lru lists and how long it
+    // took in microseconds.
+    pub fn last_merge_drain(&self) -> (bool, u64) {
+        (
+            self.last_merge_drained.load(Ordering::SeqCst),
+            self.last_merge_drain_us.load(Ordering::SeqCst),
+        )
+    }
+
+    pub fn kernel_version(&self) -> String {
+        self.backend.version().unwrap_or_default()
+    }
+
+    // The page status of each task.
+    pub async fn info_status(&self) -> HashMap<u64, page::InfoStatus> {
+        self.tasks_pages
+            .lock()
+            .await
+            .pages_info
+            .iter()
+            .map(|(pid, p)| (*pid, p.get_status()))
+            .collect()
+    }
+
+    // Parse the maps of pid like the refresh does and return the maps and the
+    // skipped vmas. The task that is not added is checked like add and
+    // parsed with the config.
+    pub async fn get_maps(&self, pid: u64) -> Result<(Vec<proc::MapRange>, Vec<proc::MapReject>)> {
+        let task = match self.map.read().await.get(&pid) {
+            Some(task) => task.clone(),
+            None => {
+                let start_time = self.check_pid(pid)?;
+                TaskInfo::new(pid, None, start_time, None)
+            }
+        };
+
+        let mut rejects = Vec::new();
+        let maps = proc::parse_task_smaps_rejects(
+            &task,
+            &self.skip_vm_flags,
+            &self.task_vma_filter(&task),
+            task.min_vma_size.unwrap_or(self.min_vma_size),
+            task.include_shmem.unwrap_or(self.include_shmem),
+            Some(&mut rejects),
+        )
+        .map_err(|e| anyhow!("proc::parse_task_smaps_rejects failed: {}", e))?;
+
+        Ok((maps, rejects))
+    }
+
+    // The histograms of the crcs of the merged pages and of the pages that
+    // can be merged.
+    pub async fn crc_histogram(&self, top: usize) -> (page::CrcHistogram, page::CrcHistogram) {
+        let merged = self.tasks_pages.lock().await.uksm.crc_pages();
+        let candidates = self.crcs.snapshot();
+
+        (
+            page::CrcHistogram::new(merged, top),
+            page::CrcHistogram::new(candidates, top),
+        )
+    }
+
+    // The samples of the merge savings of a task.
+    pub async fn history(&self, pid: u64) -> Result<Vec<page::HistorySample>> {
+        if !self.map.read().await.contains_key(&pid) {
+            return Err(anyhow!("pid {} does not exist", pid));
+        }
+
+        Ok(self
+            .tasks_pages
+            .lock()
+            .await
+            .pages_info
+            .get(&pid)
+            .map(|p| p.history())
+            .unwrap_or_default())
+    }
+
+    // Return the comm and cmdline of the tasks.
+    pub async fn task_names(&self) -> HashMap<u64, (String, String)> {
+        self.map
+            .read()
+            .await
+            .iter()
+            .map(|(pid, t)| (*pid, (t.comm.clone(), t.cmdline.clone())))
+            .collect()
+    }
+
+    // Return the comm of pid for the logs.
+    fn task_comm(&self, pid: u64) -> String {
+        self.map
+            .blocking_read()
+            .get(&pid)
+            .map(|t| t.comm.clone())
+            .unwrap_or_default()
+    }
+
+    pub async fn cross_uid_blocked(&self) -> u64 {
+        self.tasks_pages.lock().await.uksm.cross_uid_blocked()
+    }
+
+    pub async fn merge_batched(&self) -> bool {
+        self.tasks_pages.lock().await.uksm.merge_batched()
+    }
+
+    // Drop the refresh and merge work.
+    // The queued unmerge and del work is kept because they affect correctness.
+    pub async fn stop(&mut self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.refresh_target.lock().await.clear();
+        self.merge_target.lock().await.clear();
+        self.verify_target.lock().await.clear();
+        self.range_target.lock().await.retain(|op| op.unmerge);
+    }
+
+    fn apply_pages(&self, pid: u64, start: u64, entries: &[Option<uksm::UKSMPagemapEntry>]) {
+        let mut tasks_pages = self.tasks_pages.blocking_lock();
+        let tp = &mut *tasks_pages;
+        if let Some(p) = tp.pages_info.get_mut(&pid) {
+            p.refresh_pages(&mut tp.uksm, start, entries, tp.stable_cycles);
+        }
+    }
+
+    // Read the runs of data and apply them to the Info of task chunk by
+    // chunk to not block the others that want tasks_pages.
+    fn refresh_task(
+        &self,
+        task: &TaskInfo,
+        data: page::RefreshData,
+        opts: &page::RefreshOpts,
+    ) -> Result<()> {
+        {
+            let mut tasks_pages = self.tasks_pages.blocking_lock();
+            let tp = &mut *tasks_pages;
+            let p = tp.pages_info.entry(task.pid).or_insert_with(|| {
+                page::Info::new(
+                    task.pid,
+                    task.start_time,
+                    self.budget.clone(),
+                    self.crcs.clone(),
+                )
+            });
+            // The Info belongs to an old task with the same pid.
+            if p.start_time() != task.start_time {
+                p.clear(&mut tp.uksm);
+                *p = page::Info::new(
+                    task.pid,
+                    task.start_time,
+                    self.budget.clone(),
+                    self.crcs.clone(),
+                );
+            }
+            // The task execed a new program after the last refresh, the
+            // old pages are gone.
+            if p.exec_id().is_some() && data.exec_id.is_some() && p.exec_id() != data.exec_id {
+                info!("task {} execed, drop its pages", task.pid);
+                p.clear(&mut tp.uksm);
+            }
+            p.set_thp(task.thp.unwrap_or(tp.thp));
+            p.set_adopt_foreign_ksm(tp.adopt_foreign_ksm);
+            p.set_track_cow(opts.track_cow);
+            tp.uksm.set_uid(task.pid, task.uid);
+            p.refresh_remove_maps(&mut tp.uksm, &data.maps);
+        }
+
+        let holes = vec![None; WORK_CHUNK_PAGES];
+        for (start, end, change) in data.runs {
+            match change {
+                page::PageChange::Absent => {
+                    let mut addr = start;
+                    while addr < end {
+                        let count =
+                            std::cmp::min(WORK_CHUNK_PAGES as u64, (end - addr) / *page::PAGE_SIZE);
+                        self.apply_pages(task.pid, addr, &holes[..count as usize]);
+                        addr += count * *page::PAGE_SIZE;
+                    }
+                }
+                page::PageChange::Dirty => page::read_run(
+                    task,
+                    &*self.backend,
+                    &self.limits.refresh,
+                    opts,
+                    start,
+                    end,
+                    &mut |addr, entries| {
+                        self.apply_pages(task.pid, addr, entries);
+                        Ok(())
+                    },
+                )?,
+                page::PageChange::Clean => {}
+            }
+        }
+
+        let exec_id = proc::pid_exec_id(task.pid).ok();
+        let mut tasks_pages = self.tasks_pages.blocking_lock();
+        let tp = &mut *tasks_pages;
+        if let Some(p) = tp.pages_info.get_mut(&task.pid) {
+            // The task execed a new program in the refresh, the pages
+            // might be from the both programs, refresh it again.
+            if exec_id.is_some() && data.exec_id.is_some() && exec_id != data.exec_id {
+                info!("task {} execed in refresh, refresh it again", task.pid);
+                p.clear(&mut tp.uksm);
+                p.set_exec_id(None);
+                drop(tasks_pages);
+                self.refresh_target.blocking_lock().push(task.clone());
+                return Ok(());
+            }
+            p.set_exec_id(exec_id);
+            p.drop_empty_maps();
+            trace!("refresh {:?} result {:?}", task, p.get_status());
+        }
+
+        Ok(())
+    }
+
+    // Handle the addrs that got from the Info of pid chunk by chunk.
+    fn handle_chunks<A, F>(
+        &self,
+        pid: u64,
+        get_addrs: A,
+        limit: Option<&ratelimit::TokenBucket>,
+        mut f: F,
+    ) -> Result<()>
+    where
+        A: FnOnce(&page::Info) -> Vec<u64>,
+        F: FnMut(&mut page::Info, &mut uksm::Uksm, &[u64]) -> Result<()>,
+    {
+        let addrs = if let Some(p) = self.tasks_pages.blocking_lock().pages_info.get(&pid) {
+            get_addrs(p)
+        } else {
+            return Ok(());
+        };
+
+        for chunk in addrs.chunks(WORK_CHUNK_PAGES) {
+            if let Some(limit) = limit {
+                limit.acquire(chunk.len() as u64);
+            }
+
+            let mut tasks_pages = self.tasks_pages.blocking_lock();
+            let tp = &mut *tasks_pages;
+            if let Some(p) = tp.pages_info.get_mut(&pid) {
+                f(p, &mut tp.uksm, chunk)?;
+            } else {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Merge or unmerge the pages of op.pid in its range, the tasks_pages is
+    // locked for a window of WORK_CHUNK_PAGES pages at a time.
+    fn handle_range(&self, op: &RangeOp) -> Result<()> {
+        let window = WORK_CHUNK_PAGES as u64 * *page::PAGE_SIZE;
+        let mut start = op.start;
+        while start < op.end {
+            let end = op.end.min(start.saturating_add(window));
+            let count = {
+                let mut tasks_pages = self.tasks_pages.blocking_lock();
+                let tp = &mut *tasks_pages;
+                let Some(p) = tp.pages_info.get_mut(&op.pid) else {
+                    break;
+                };
+                if op.unmerge {
+                    p.unmerge_range(&mut tp.uksm, start, end)?;
+                    0
+                } else {
+                    let ret = p.merge_range(&mut tp.uksm, start, end);
+                    tp.record_merge_failures();
+                    ret?
+                }
+            };
+            if count > 0 {
+                self.limits.merge.acquire(count as u64);
+            }
+            start = end;
+        }
+
+        Ok(())
+    }
+
+    // Read the merged pages of pid and move the pages that the kernel broke
+    // back to new. Return the number of the broken pages.
+    fn verify_task(&self, pid: u64) -> Result<u64> {
+        let addrs = match self.tasks_pages.blocking_lock().pages_info.get(&pid) {
+            Some(p) => p.uksm_addrs(),
+            None => return Ok(0),
+        };
+
+        // Read the continuous addrs together.
+        let mut runs: Vec<(u64, u64)> = Vec::new();
+        for addr in addrs {
+            match runs.last_mut() {
+                Some((start, end))
+                    if *end == addr
+                        && (*end - *start) / *page::PAGE_SIZE < WORK_CHUNK_PAGES as u64 =>
+                {
+                    *end += *page::PAGE_SIZE
+                }
+                _ => runs.push((addr, addr + *page::PAGE_SIZE)),
+            }
+        }
+
+        let mut broken = 0;
+        for (start, end) in runs {
+            self.limits
+                .refresh
+                .acquire((end - start) / *page::PAGE_SIZE);
+            let entries = self.backend.read_pagemap(pid, start, end).map_err(|e| {
+                anyhow!(
+                    "backend.read_pagemap {} 0x{:x}-0x{:x} failed: {}",
+                    pid,
+                    start,
+                    end,
+                    e
+                )
+            })?;
+
+            let mut tasks_pages = self.tasks_pages.blocking_lock();
+            let tp = &mut *tasks_pages;
+            if let Some(p) = tp.pages_info.get_mut(&pid) {
+                broken += p.verify(&mut tp.uksm, start, &entries);
+            } else {
+                break;
+            }
+        }
+
+        Ok(broken)
+    }
+
+    // Del the task that is gone as the del request does.
+    fn del_gone_task(&self, pid: u64) {
+        let mut map = self.map.blocking_write();
+
+        if map.remove(&pid).is_some() {
+            self.refresh_target.blocking_lock().remove(pid);
+            self.merge_target.blocking_lock().remove(pid);
+        }
+        // Its pages cannot be unmerged, just del them.
+        self.del_target.blocking_lock().push(pid);
+    }
+
+    // Del the task instead of reporting the error if the task is gone.
+    fn handle_gone(&self, ret: Result<()>) -> Result<()> {
+        match ret {
+            Err(e) => match uksm::is_task_gone(&e) {
+                Some(pid) => {
+                    info!("del task {} because {}", pid, e);
+                    self.del_gone_task(pid);
+                    Ok(())
+                }
+                None => Err(e),
+            },
+            ret => ret,
+        }
+    }
+
+    fn handle_task(&self, ht: HandleTask) -> Result<()> {
+        match ht.clone() {
+            HandleTask::UnMerge(pid) => {
+                match self.handle_gone(self.handle_chunks(
+                    pid,
+                    |p| p.uksm_addrs(),
+                    None,
+                    |p, uksm, addrs| p.unmerge(uksm, addrs, None).map(|_| ()),
+                )) {
+                    // The kernel unmerged the pages itself.
+                    Err(e) if uksm::is_kernel_lost(&e) => self.set_kernel_lost(&e),
+                    ret => ret.map_err(|e| anyhow!("p.unmerge failed: {}", e))?,
+                }
+            }
+            HandleTask::Range(op) => {
+                let action = if op.unmerge { "unmerge" } else { "merge" };
+                match self.handle_gone(self.handle_range(&op)) {
+                    Err(e) if uksm::is_no_mem(&e) => {
+                        info!("defer the {} of task {}: {}", action, op.pid, e);
+                    }
+                    Err(e) if uksm::is_kernel_lost(&e) => self.set_kernel_lost(&e),
+                    ret => ret.map_err(|e| anyhow!("p.{}_range failed: {}", action, e))?,
+                }
+            }
+            HandleTask::Verify(pid) => {
+                let broken = self
+                    .verify_task(pid)
+                    .map_err(|e| anyhow!("verify_task failed: {}", e))?;
+                if broken > 0 {
+                    info!("{} merged pages of task {} are broken", broken, pid);
+                    self.verify_broken.fetch_add(broken, Ordering::SeqCst);
+                }
+            }
+            HandleTask::Del(pid) => {
+                let mut tasks_pages = self.tasks_pages.blocking_lock();
+                tasks_pages.pages_info.remove(&pid);
+                // The unmerge of a dead pid fails, drop its left merged pages.
+                let count = tasks_pages.uksm.remove_pid(pid);
+                tasks_pages.uksm.del_uid(pid);
+                if count > 0 {
+                    info!("drop {} merged pages of del pid {}", count, pid);
+                }
+                self.soft_dirty_tasks
+                    .blocking_lock()
+                    .retain(|(p, _)| *p != pid);
+            }
+            HandleTask::Merge(pid) => {
+                // The last addr of the chunk if the chunk is full, the
+                // number of the pages in it and the Old pages before it.
+                let mut chunk = (None, 0, 0);
+                let ret = self.handle_gone(self.handle_chunks(
+                    pid,
+                    |p| {
+                        let addrs = p.merge_chunk_addrs(self.merge_chunk_pages);
+                        if self.merge_chunk_pages != 0 && addrs.len() >= self.merge_chunk_pages {
+                            chunk.0 = addrs.last().cloned();
+                        }
+                        chunk.1 = addrs.len() as u64;
+                        chunk.2 = p.get_status().old_count;
+                        addrs
+                    },
+                    Some(&self.limits.merge),
+                    |p, uksm, addrs| p.merge(uksm, addrs),
+                ));
+                let parked = self.tasks_pages.blocking_lock().record_merge_failures();
+                if parked > 0 {
+                    debug!("park {} pages that cannot be merged", parked);
+                }
+                if ret.is_ok() {
+                    let more = {
+                        let mut tasks_pages = self.tasks_pages.blocking_lock();
+                        match tasks_pages.pages_info.get_mut(&pid) {
+                            Some(p) => {
+                                p.advance_merge_cursor(chunk.0, chunk.1, chunk.2);
+                                chunk.0.is_some()
+                            }
+                            None => false,
+                        }
+                    };
+                    // Let the other tasks go before the next chunk.
+                    if more {
+                        self.merge_target.blocking_lock().push_front(pid);
+                    }
+                }
+                match ret {
+                    // Keep the left old pages to the next merge.
+                    Err(e) if uksm::is_no_mem(&e) => {
+                        info!("defer the merge of task {}: {}", pid, e);
+                    }
+                    // The merge is scheduled again when the kernel is back.
+                    Err(e) if uksm::is_kernel_lost(&e) => self.set_kernel_lost(&e),
+                    ret => ret.map_err(|e| anyhow!("p.merge failed: {}", e))?,
+                }
+            }
+        }
+
+        if let HandleTask::Merge(pid) | HandleTask::Verify(pid) = ht {
+            self.record_history(pid);
+        }
+
+        trace!(
+            "handle_task {:?} result {:?}",
+            ht,
+            self.tasks_pages.blocking_lock().get_status(ht.pid())
+        );
+
+        Ok(())
+    }
+
+    fn record_history(&self, pid: u64) {
+        if self.history_samples == 0 {
+            return;
+        }
+
+        // Sum the saving chunk by chunk to not block the others that want
+        // tasks_pages.
+        let addrs = self.tasks_pages.blocking_lock().uksm.pid_addrs(pid);
+        let mut saved = 0.0;
+        for chunk in addrs.chunks(WORK_CHUNK_PAGES) {
+            saved += self
+                .tasks_pages
+                .blocking_lock()
+                .uksm
+                .saved_pages(pid, chunk);
+        }
+
+        let saved_bytes = (saved * *page::PAGE_SIZE as f64) as u64;
+        if let Some(p) = self.tasks_pages.blocking_lock().pages_info.get_mut(&pid) {
+            p.record_history(saved_bytes, self.history_samples);
+        }
+    }
+
+    fn task_vma_filter(&self, task: &TaskInfo) -> Arc<proc::VmaFilter> {
+        if task.include_vma.is_empty() && task.exclude_vma.is_empty() {
+            return self.vma_filter.clone();
+        }
+
+        match proc::VmaFilter::new(&task.include_vma, &task.exclude_vma) {
+            Ok(filter) => Arc::new(filter),
+            Err(e) => {
+                error!("vma filter of task {} is not right: {}", task.pid, e);
+                self.vma_filter.clone()
+            }
+        }
+    }
+
+    // The pages of the task might share the frames with another tracked task
+    // if one of them forked the other.
+    fn forked_tracked(&self, task: &TaskInfo) -> bool {
+        if task.follow_children || task.parent.is_some() {
+            return true;
+        }
+        let map = self.map.blocking_read();
+        task.ppid.is_some_and(|ppid| map.contains_key(&ppid))
+            || map.values().any(|t| t.ppid == Some(task.pid))
+    }
+
+    fn refresh_opts(&self, task: &TaskInfo) -> page::RefreshOpts {
+        let include_shmem = task.include_shmem.unwrap_or(self.include_shmem);
+        page::RefreshOpts {
+            skip_vm_flags: self.skip_vm_flags.clone(),
+            vma_filter: self.task_vma_filter(task),
+            min_vma_size: task.min_vma_size.unwrap_or(self.min_vma_size),
+            include_shmem,
+            // The shmem maps can only be found by smaps.
+            fast_maps: self.fast_maps && !include_shmem,
+            track_cow: self.forked_tracked(task),
+            idle_filter: self.idle_filter,
+            soft_dirty: self.soft_dirty,
+            incremental: self.soft_dirty
+                && self
+                    .soft_dirty_tasks
+                    .blocking_lock()
+                    .contains(&(task.pid, task.start_time)),
+        }
+    }
+
+    // Return false if the refresh failed.
+    fn refresh_one(&self, task: TaskInfo) -> bool {
+        let _busy = match BusyGuard::new(&self.tasks_pages, task.pid, PidState::Refreshing) {
+            Some(guard) => guard,
+            None => {
+                self.refresh_target.blocking_lock().push(task);
+                return true;
+            }
+        };
+        let opts = self.refresh_opts(&task);
+        let ret = page::read_refresh_data(&task, &opts)
+            .map_err(|e| anyhow!("page::read_refresh_data failed: {}", e))
+            .and_then(|data| {
+                let cleared = data.cleared;
+                self.refresh_task(&task, data, &opts)?;
+                Ok(cleared)
+            });
+
+        // The next refresh is incremental only if all the pages are read
+        // after the soft-dirty bits are cleared.
+        let mut soft_dirty_tasks = self.soft_dirty_tasks.blocking_lock();
+        match ret {
+            Ok(true) => {
+                soft_dirty_tasks.insert((task.pid, task.start_time));
+                true
+            }
+            Ok(false) => {
+                soft_dirty_tasks.remove(&(task.pid, task.start_time));
+                true
+            }
+            Err(e) => {
+                soft_dirty_tasks.remove(&(task.pid, task.start_time));
+                drop(soft_dirty_tasks);
+                // uksm_pagemap of all the tasks is gone with the kernel
+                // interfaces, don't del the task.
+                if !self.backend.available() {
+                    self.set_kernel_lost(&e);
+                    return false;
+                }
+                // The task exited in the refresh.
+                if uksm::is_task_gone(&e).is_some()
+                    || proc::pid_start_time(task.pid).ok() != Some(task.start_time)
+                {
+                    info!("del task {} because it is gone: {}", task.pid, e);
+                    self.del_gone_task(task.pid);
+                    true
+                } else {
+                    error!("refresh {:?} failed: {}", task, e);
+                    false
+                }
+            }
+        }
+    }
+
+    // Refresh the tasks in batch concurrently, each of them reads its
+    // pages and applies them to tasks_pages chunk by chunk.
+    // Replace the popped tasks with their entries in map. A task that is
+    // queued again by the worker might be older than the one that
+    // add_refresh_all or an update queued, and the one in map is the
+    // current range and options of the task. The deleted tasks are dropped.
+    fn current_tasks(&self, batch: Vec<TaskInfo>) -> Vec<TaskInfo> {
+        let map = self.map.blocking_read();
+        batch
+            .into_iter()
+            .filter_map(|task| map.get(&task.pid).cloned())
+            .collect()
+    }
+
+    fn refresh_batch(&self, batch: Vec<TaskInfo>, report: &mut WorkReport) {
+        let now = Instant::now();
+        let count = batch.len();
+
+        let pids: Vec<u64> = batch.iter().map(|t| t.pid).collect();
+        let befores: Vec<page::InfoStatus> = {
+            let tasks_pages = self.tasks_pages.blocking_lock();
+            pids.iter()
+                .map(|pid| tasks_pages.get_status(*pid))
+                .collect()
+        };
+
+        let oks: Vec<bool> = if count == 1 {
+            batch
+                .into_iter()
+                .map(|task| self.refresh_one(task))
+                .collect()
+        } else {
+            thread::scope(|s| {
+                let handles: Vec<_> = batch
+                    .into_iter()
+                    .map(|task| s.spawn(move || self.refresh_one(task)))
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .map(|h| {
+                        h.join().unwrap_or_else(|_| {
+                            error!("refresh thread panic");
+                            false
+                        })
+                    })
+                    .collect()
+            })
+        };
+
+        let tasks_pages = self.tasks_pages.blocking_lock();
+        for ((pid, before), ok) in pids.iter().zip(befores.iter()).zip(oks) {
+            report.record(*pid, before, &tasks_pages.get_status(*pid), ok);
+        }
+        drop(tasks_pages);
+
+        trace!("refresh_batch {} tasks took {:?}", count, now.elapsed());
+    }
+
+    // If the unmerge or del of pid is queued or running. The unmerge is not
+    // done when the kernel interfaces are lost, it is not waited for then.
+    pub async fn del_pending(&self, pid: u64) -> bool {
+        (!self.kernel_lost() && self.unmerge_target.lock().await.contains(pid))
+            || self.del_target.lock().await.contains(pid)
+            || self.tasks_pages.lock().await.busy.contains_key(&pid)
+    }
+
+    // The pids that have queued unmerge or del work or a running
+    // operation.
+    fn pending_del_pids(&self) -> HashSet<u64> {
+        let unmerge_target = self.unmerge_target.blocking_lock();
+        let del_target = self.del_target.blocking_lock();
+        unmerge_target
+            .keys()
+            .chain(del_target.keys())
+            .cloned()
+            .chain(self.busy_pids())
+            .collect()
+    }
+
+    fn busy_pids(&self) -> HashSet<u64> {
+        self.tasks_pages
+            .blocking_lock()
+            .busy
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    // UnMerge and Del work handles all the targets because they affect
+    // correctness.
+    // Refresh and Merge work only handles one batch to let async_work
+    // switch between them.
+    // Unmerge at most budget pages that are merged last.
+    // Return the number of the unmerged pages.
+    // The pages of a busy pid are left to the next unmerge with their budget.
+    fn unmerge_recent(&self, budget: u64, by_request: bool, report: &mut WorkReport) -> u64 {
+        let pages = self
+            .tasks_pages
+            .blocking_lock()
+            .uksm
+            .recent_merged(budget as usize);
+        let mut pids: HashMap<u64, Vec<u64>> = HashMap::new();
+        for page in pages {
+            pids.entry(page.pid).or_default().push(page.addr);
+        }
+
+        let mut unmerged = 0;
+        let mut deferred = 0;
+        for (pid, addrs) in pids {
+            let _busy = match BusyGuard::new(&self.tasks_pages, pid, PidState::Unmerging) {
+                Some(guard) => guard,
+                None => {
+                    deferred += addrs.len() as u64;
+                    continue;
+                }
+            };
+            let before = self.tasks_pages.blocking_lock().get_status(pid);
+            let ret = self.handle_gone(self.handle_chunks(
+                pid,
+                |_| addrs,
+                None,
+                |p, uksm, addrs| {
+                    let left = budget.saturating_sub(unmerged) as usize;
+                    unmerged += p.unmerge(uksm, addrs, Some(left))? as u64;
+                    Ok(())
+                },
+            ));
+            if let Err(e) = &ret {
+                if uksm::is_kernel_lost(e) {
+                    self.set_kernel_lost(e);
+                } else {
+                    error!("unmerge the recent pages of task {} failed: {}", pid, e);
+                }
+            }
+            let after = self.tasks_pages.blocking_lock().get_status(pid);
+            report.record(pid, &before, &after, ret.is_ok());
+        }
+
+        if deferred > 0 {
+            self.add_unmerge_recent(deferred, by_request);
+        }
+
+        unmerged
+    }
+
+    // Queue ht again to handle it after the running operation of its pid.
+    fn defer_task(&self, ht: HandleTask) {
+        match ht {
+            HandleTask::Del(pid) => self.del_target.blocking_lock().push(pid),
+            HandleTask::UnMerge(pid) => self.unmerge_target.blocking_lock().push(pid),
+            HandleTask::Merge(pid) => self.merge_target.blocking_lock().push(pid),
+            HandleTask::Verify(pid) => self.verify_target.blocking_lock().push(pid),
+            HandleTask::Range(op) => self.range_target.blocking_lock().push(op),
+        }
+    }
+
+    // Handle a task and record its result to report.
+    fn handle_task_report(&self, ht: HandleTask, report: &mut WorkReport) {
+        let pid = ht.pid();
+        let _busy = match BusyGuard::new(&self.tasks_pages, pid, ht.state()) {
+            Some(guard) => guard,
+            None => {
+                self.defer_task(ht);
+                return;
+            }
+        };
+        let before = self.tasks_pages.blocking_lock().get_status(pid);
+        let ret = self.handle_task(ht.clone());
+        if let Err(e) = &ret {
+            let msg = format!(
+                "handle_task {:?} ({}) failed: {}",
+                ht,
+                self.task_comm(pid),
+                e
+            );
+            error!("{}", msg);
+            self.events.push(events::Severity::Error, msg);
+        }
+        let after = self.tasks_pages.blocking_lock().get_status(pid);
+        report.record(pid, &before, &after, ret.is_ok());
+    }
+
+    fn async_work_thread(&mut self, work: AsyncWork) -> Result<WorkReport> {
+        let now = Instant::now();
+        let mut report = WorkReport::new(work);
+        // Drop the timings of the operations out of the work cycles.
+        timing::take();
+
+        if let AsyncWork::Merge = work {
+            if self.lru_drain_before_merge {
+                match self.lru_drain() {
+                    Ok(drain) => report.drain = drain,
+                    Err(e) if uksm::is_kernel_lost(&e) => self.set_kernel_lost(&e),
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        loop {
+            if !matches!(work, AsyncWork::Del) && self.kernel_lost() {
+                break;
+            }
+            if matches!(
+                work,
+                AsyncWork::Refresh | AsyncWork::Merge | AsyncWork::Verify
+            ) && self.stopping.load(Ordering::SeqCst)
+            {
+                break;
+            }
+            if matches!(
+                work,
+                AsyncWork::Refresh | AsyncWork::Merge | AsyncWork::Verify
+            ) {
+                self.load_throttle.wait();
+            }
+
+            let ht = {
+                match work {
+                    AsyncWork::UnMerge => {
+                        let busy = self.busy_pids();
+                        if let Some(pid) = self
+                            .unmerge_target
+                            .blocking_lock()
+                            .pop_if(|pid| !busy.contains(pid))
+                        {
+                            HandleTask::UnMerge(pid)
+                        } else {
+                            break;
+                        }
+                    }
+                    AsyncWork::Del => {
+                        let busy = self.busy_pids();
+                        if let Some(pid) = self
+                            .del_target
+                            .blocking_lock()
+                            .pop_if(|pid| !busy.contains(pid))
+                        {
+                            HandleTask::Del(pid)
+                        } else {
+                            break;
+                        }
+                    }
+                    AsyncWork::UnMergeRecent => {
+                        let budget = self.unmerge_request_budget.swap(0, Ordering::SeqCst);
+                        if budget > 0 {
+                            let count = self.unmerge_recent(budget, true, &mut report);
+                            self.unmerged_by_request.fetch_add(count, Ordering::SeqCst);
+                        }
+                        let budget = self.unmerge_policy_budget.swap(0, Ordering::SeqCst);
+                        if budget > 0 {
+                            let count = self.unmerge_recent(budget, false, &mut report);
+                            self.unmerged_by_policy.fetch_add(count, Ordering::SeqCst);
+                        }
+                        break;
+                    }
+                    AsyncWork::Refresh => {
+                        // Don't refresh a new task before the unmerge and del
+                        // of the old task with the same pid are done.
+                        let batch = {
+                            let pending = self.pending_del_pids();
+                            self.refresh_target
+                                .blocking_lock()
+                                .pop_n_if(self.refresh_workers, |t| !pending.contains(&t.pid))
+                        };
+                        let batch = self.current_tasks(batch);
+                        if !batch.is_empty() {
+                            self.refresh_batch(batch, &mut report);
+                        }
+                        break;
+                    }
+                    AsyncWork::Range => {
+                        let op = {
+                            let pending = self.pending_del_pids();
+                            self.range_target
+                                .blocking_lock()
+                                .pop_if(|op| !pending.contains(&op.pid))
+                        };
+                        if let Some(op) = op {
+                            HandleTask::Range(op)
+                        } else {
+                            break;
+                        }
+                    }
+                    AsyncWork::Verify => {
+                        let pid = {
+                            let pending = self.pending_del_pids();
+                            self.verify_target
+                                .blocking_lock()
+                                .pop_if(|pid| !pending.contains(pid))
+                        };
+                        if let Some(pid) = pid {
+                            HandleTask::Verify(pid)
+                        } else {
+                            break;
+                        }
+                    }
+                    AsyncWork::Merge => {
+                        // Refresh a task before merge it.
+                        let pid = {
+                            let pending = self.pending_del_pids();
+                            let refresh_target = self.refresh_target.blocking_lock();
+                            self.merge_target.blocking_lock().pop_if(|pid| {
+                                !refresh_target.contains(*pid) && !pending.contains(pid)
+                            })
+                        };
+                        if let Some(pid) = pid {
+                            self.handle_task_report(HandleTask::Merge(pid), &mut report);
+                        }
+                        break;
+                    }
+                }
+            };
+
+            self.handle_task_report(ht, &mut report);
+        }
+
+        report.elapsed = now.elapsed();
+        report.timings = timing::take();
+
+        Ok(report)
+    }
+
+    // Run work in the current thread until its targets are empty, for the
+    // callers that don't have a worker.
+    pub fn run_work(&mut self, work: AsyncWork) -> Result<Vec<WorkReport>> {
+        let mut reports = Vec::new();
+
+        loop {
+            let empty = match work {
+                AsyncWork::UnMerge => self.unmerge_target.blocking_lock().is_empty(),
+                AsyncWork::Del => self.del_target.blocking_lock().is_empty(),
+                AsyncWork::Refresh => self.refresh_target.blocking_lock().is_empty(),
+                AsyncWork::Merge => self.merge_target.blocking_lock().is_empty(),
+                AsyncWork::Verify => self.verify_target.blocking_lock().is_empty(),
+                AsyncWork::Range => self.range_target.blocking_lock().is_empty(),
+                AsyncWork::UnMergeRecent => {
+                    self.unmerge_request_budget.load(Ordering::SeqCst) == 0
+                        && self.unmerge_policy_budget.load(Ordering::SeqCst) == 0
+                }
+            };
+            if empty {
+                break;
+            }
+            if self.kernel_lost() {
+                return Err(anyhow!("kernel interfaces are lost"));
+            }
+
+            let report = self.async_work_thread(work)?;
+            self.record_report(&report);
+            reports.push(report);
+        }
+
+        Ok(reports)
+    }
+
+    // Start the long-lived worker thread that handles the AsyncWork
+    // sent by async_work and reports the result to ret_tx.
+    pub fn start_worker(
+        &self,
+        ret_tx: mpsc::Sender<Result<WorkReport>>,
+        config: &config::Config,
+    ) -> Result<Worker> {
+        let (work_tx, work_rx) = std_mpsc::channel::<AsyncWork>();
+        let mut tasks = self.clone();
+        let config = config.clone();
+
+        let handle = thread::Builder::new()
+            .name("uksmd-worker".to_string())
+            .spawn(move || {
+                if let Err(e) = sched::setup_worker_thread(&config) {
+                    error!("sched::setup_worker_thread failed: {}", e);
+                }
+
+                while let Ok(work) = work_rx.recv() {
+                    info!("async_work_thread {:?} start", work);
+                    tasks
+                        .events
+                        .push(events::Severity::Info, format!("work {:?} start", work));
+
+                    // Convert the panic to an error to make sure agent_loop always
+                    // gets the result and can go on with the next work.
+                    let ret =
+                        panic::catch_unwind(AssertUnwindSafe(|| tasks.async_work_thread(work)))
+                            .unwrap_or_else(|e| {
+                                let msg = if let Some(s) = e.downcast_ref::<&str>() {
+                                    s.to_string()
+                                } else if let Some(s) = e.downcast_ref::<String>() {
+                                    s.clone()
+                                } else {
+                                    "unknown".to_string()
+                                };
+                                Err(anyhow!("async_work_thread {:?} panic: {}", work, msg))
+                            });
+
+                    match proc::thread_cpu_time() {
+                        Ok(time) => tasks
+                            .worker_cpu_us
+                            .store(time.as_micros() as u64, Ordering::SeqCst),
+                        Err(e) => debug!("proc::thread_cpu_time failed: {}", e),
+                    }
+
+                    if let Err(e) = ret_tx.blocking_send(ret) {
+                        error!(
+                            "async_work_thread {:?} ret_tx.blocking_send failed: {}",
+                            work, e
+                        );
+                        return;
+                    }
+
+                    info!("async_work_thread {:?} stop", work);
+                }
+            })
+            .map_err(|e| anyhow!("spawn uksmd-worker failed: {}", e))?;
+
+        Ok(Worker {
+            work_tx,
+            handle: Some(handle),
+        })
+    }
+
+    // Send the next work to worker.
+    // Return true if a work is sent.
+    pub async fn async_work(&mut self, worker: &Worker) -> bool {
+        let work = if self.kernel_lost() {
+            // Del work doesn't use the kernel interfaces.
+            if self.del_target.lock().await.is_empty() {
+                return false;
+            }
+            AsyncWork::Del
+        } else if !self.unmerge_target.lock().await.is_empty() {
+            AsyncWork::UnMerge
+        } else if !self.del_target.lock().await.is_empty() {
+            AsyncWork::Del
+        } else if self.unmerge_request_budget.load(Ordering::SeqCst) > 0
+            || self.unmerge_policy_budget.load(Ordering::SeqCst) > 0
+        {
+            AsyncWork::UnMergeRecent
+        } else if !self.range_target.lock().await.is_empty() {
+            AsyncWork::Range
+        } else if !self.verify_target.lock().await.is_empty() {
+            AsyncWork::Verify
+        } else {
+            let refresh = !self.refresh_target.lock().await.is_empty();
+            let merge = !self.merge_target.lock().await.is_empty();
+            if !merge {
+                self.merge_forced = false;
+            }
+            let merge = merge && (self.merge_window_open || self.merge_forced);
+            match (refresh, merge) {
+                // Switch between Refresh and Merge to not starve merge.
+                (true, true) if !self.last_is_merge => AsyncWork::Merge,
+                (true, _) => AsyncWork::Refresh,
+                (false, true) => AsyncWork::Merge,
+                (false, false) => return false,
+            }
+        };
+        match work {
+            AsyncWork::Refresh => self.last_is_merge = false,
+            AsyncWork::Merge => self.last_is_merge = true,
+            _ => {}
+        }
+
+        if let Err(e) = worker.work_tx.send(work) {
+            error!("worker.work_tx.send failed: {}", e);
+            return false;
+        }
+
+        true
+    }
+}
+
+#[derive(Debug)]
+pub struct Worker {
+    work_tx: std_mpsc::Sender<AsyncWork>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Worker {
+    // Stop the worker thread after the current work is done.
+    pub fn join(mut self) {
+        drop(self.work_tx);
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                error!("uksmd-worker join failed");
+            }
+        }
+    }
+}
 
-        true
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::{FakeKernel, Fault};
+    use crate::proc::fake::{self, FakeTask};
+    use std::os::unix::fs::FileExt;
+    use structopt::StructOpt;
+
+    fn block_on<F: std::future::Future>(f: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(f)
+    }
+
+    fn page(i: u64) -> u64 {
+        fake::MAP_START + i * *page::PAGE_SIZE
+    }
+
+    fn add(tasks: &mut Tasks, pid: u64) {
+        block_on(tasks.add(uksmd_ctl::AddRequest {
+            pid,
+            ..Default::default()
+        }))
+        .unwrap();
+    }
+
+    fn refresh(tasks: &mut Tasks) -> Vec<WorkReport> {
+        block_on(tasks.add_refresh_all());
+        tasks.run_work(AsyncWork::Refresh).unwrap()
+    }
+
+    fn merge(tasks: &mut Tasks) -> Vec<WorkReport> {
+        block_on(tasks.add_merge_all());
+        tasks.run_work(AsyncWork::Merge).unwrap()
+    }
+
+    // Refresh the tasks until their pages are Old and merge them until the
+    // pages that the later tasks merged with are Uksm too.
+    fn settle(tasks: &mut Tasks) {
+        refresh(tasks);
+        refresh(tasks);
+        merge(tasks);
+        merge(tasks);
+    }
+
+    fn status(tasks: &Tasks, pid: u64) -> page::InfoStatus {
+        tasks.tasks_pages.blocking_lock().get_status(pid)
+    }
+
+    #[test]
+    fn test_refresh_merge_change_unmerge() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2, 3, 4]);
+        let b = kernel.spawn(&[1, 2, 3, 5]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+
+        refresh(&mut tasks);
+        assert_eq!(status(&tasks, a).new_count, 4);
+        refresh(&mut tasks);
+        assert_eq!(status(&tasks, a).old_count, 4);
+        assert_eq!(status(&tasks, b).old_count, 4);
+
+        merge(&mut tasks);
+        merge(&mut tasks);
+        for pid in [a, b] {
+            let s = status(&tasks, pid);
+            assert_eq!((s.uksm_count, s.old_count), (3, 1));
+        }
+        // The pages whose crc has one page are not compared.
+        assert_eq!(kernel.merges(), 3);
+        for i in 0..3 {
+            assert_eq!(kernel.frame(a, page(i)), kernel.frame(b, page(i)));
+            assert!(kernel.is_ksm(b, page(i)));
+        }
+        assert_eq!(tasks.tasks_pages.blocking_lock().uksm.saved_total(), 3);
+
+        // The write breaks the merged page, the refresh finds it New.
+        kernel.write(b, page(0), 6);
+        refresh(&mut tasks);
+        let s = status(&tasks, b);
+        assert_eq!((s.new_count, s.uksm_count), (1, 2));
+        assert_eq!(tasks.tasks_pages.blocking_lock().uksm.saved_total(), 2);
+
+        block_on(tasks.add_unmerge_all());
+        tasks.run_work(AsyncWork::UnMerge).unwrap();
+        assert_eq!(kernel.unmerges(), 5);
+        for pid in [a, b] {
+            assert_eq!(status(&tasks, pid).uksm_count, 0);
+            for i in 0..4 {
+                assert!(!kernel.is_ksm(pid, page(i)));
+            }
+        }
+        assert_eq!(tasks.tasks_pages.blocking_lock().uksm.saved_total(), 0);
+
+        // The unmerged pages are merged again by the next merge.
+        merge(&mut tasks);
+        merge(&mut tasks);
+        assert_eq!(status(&tasks, a).uksm_count, 2);
+        assert_eq!(kernel.frame(a, page(1)), kernel.frame(b, page(1)));
+    }
+
+    #[test]
+    fn test_gone_task_is_deleted() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+        assert_eq!(status(&tasks, b).uksm_count, 2);
+
+        kernel.exit(a);
+        FakeTask::exit(a);
+        refresh(&mut tasks);
+        tasks.run_work(AsyncWork::Del).unwrap();
+        assert!(!block_on(tasks.pids()).contains(&a));
+        let tp = tasks.tasks_pages.blocking_lock();
+        assert!(!tp.pages_info.contains_key(&a));
+        assert!(!tp.uksm.contains(a, page(0)));
+    }
+
+    #[test]
+    fn test_gone_in_merge_and_unmerge() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&["--deterministic-order"], &kernel);
+        let a = kernel.spawn(&[1, 2, 3]);
+        let b = kernel.spawn(&[1, 2, 3]);
+        let c = kernel.spawn(&[1, 2, 3]);
+        for pid in [a, b, c] {
+            add(&mut tasks, pid);
+        }
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+
+        // a exits and a page of c is unmapped after a is merged, the merge
+        // of the others goes on.
+        block_on(tasks.add_merge_all());
+        assert_eq!(tasks.async_work_thread(AsyncWork::Merge).unwrap().tasks, 1);
+        assert!(tasks.tasks_pages.blocking_lock().uksm.contains(a, page(0)));
+        kernel.exit(a);
+        FakeTask::exit(a);
+        kernel.drop_page(c, page(1));
+        let reports = tasks.run_work(AsyncWork::Merge).unwrap();
+        assert_eq!(reports.iter().map(|r| r.tasks).sum::<u64>(), 2);
+        assert!(reports.iter().all(|r| r.failed == 0));
+        {
+            let tp = tasks.tasks_pages.blocking_lock();
+            assert!(!tp.uksm.contains(a, page(0)));
+            assert!(!tp.uksm.contains(c, page(1)));
+        }
+        for i in [0, 2] {
+            assert!(kernel.is_ksm(c, page(i)));
+            assert_eq!(kernel.frame(b, page(i)), kernel.frame(c, page(i)));
+        }
+
+        // The del of the task that is gone still unmerges and deletes it.
+        block_on(tasks.del(uksmd_ctl::DelRequest {
+            pid: a,
+            ..Default::default()
+        }))
+        .unwrap();
+        tasks.run_work(AsyncWork::UnMerge).unwrap();
+        tasks.run_work(AsyncWork::Del).unwrap();
+        assert!(!block_on(tasks.pids()).contains(&a));
+        assert!(!tasks
+            .tasks_pages
+            .blocking_lock()
+            .pages_info
+            .contains_key(&a));
+    }
+
+    #[test]
+    fn test_batch_merge() {
+        let kernel = Arc::new(FakeKernel::with_batch());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        assert!(block_on(tasks.merge_batched()));
+        let a = kernel.spawn(&[1, 2, 3]);
+        let b = kernel.spawn(&[1, 2, 4]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+
+        assert_eq!(status(&tasks, a).uksm_count, 2);
+        assert_eq!(status(&tasks, b).uksm_count, 2);
+        assert_eq!(kernel.merges(), 2);
+    }
+
+    #[test]
+    fn test_dropped_page_is_removed() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+
+        kernel.drop_page(b, page(0));
+        refresh(&mut tasks);
+        assert_eq!(status(&tasks, b).uksm_count, 1);
+        let tp = tasks.tasks_pages.blocking_lock();
+        assert!(!tp.uksm.contains(b, page(0)));
+        assert_eq!(tp.uksm.saved_total(), 1);
+    }
+
+    #[test]
+    fn test_foreign_ksm_pages() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 3]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        kernel.merge_behind(
+            &uksm::PidAddr {
+                pid: a,
+                addr: page(0),
+            },
+            &uksm::PidAddr {
+                pid: b,
+                addr: page(0),
+            },
+        );
+        settle(&mut tasks);
+
+        assert_eq!(status(&tasks, a).foreign_count, 1);
+        assert_eq!(status(&tasks, b).foreign_count, 1);
+        assert_eq!(kernel.merges(), 0);
+
+        // The unmerge leaves the foreign pages merged.
+        block_on(tasks.add_unmerge_all());
+        tasks.run_work(AsyncWork::UnMerge).unwrap();
+        assert_eq!(kernel.unmerges(), 0);
+        assert!(kernel.is_ksm(b, page(0)));
+
+        // The write breaks the ksm page, it is New again.
+        kernel.write(b, page(0), 2);
+        refresh(&mut tasks);
+        let s = status(&tasks, b);
+        assert_eq!((s.foreign_count, s.new_count), (0, 1));
+        assert_eq!(status(&tasks, a).foreign_count, 1);
+    }
+
+    #[test]
+    fn test_adopt_foreign_ksm_pages() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&["--adopt-foreign-ksm"], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 3]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        kernel.merge_behind(
+            &uksm::PidAddr {
+                pid: a,
+                addr: page(0),
+            },
+            &uksm::PidAddr {
+                pid: b,
+                addr: page(0),
+            },
+        );
+        settle(&mut tasks);
+
+        // The foreign pages are merged and unmerged as the other pages,
+        // the page in the same frame joins the group without a merge.
+        assert_eq!(status(&tasks, a).foreign_count, 0);
+        assert_eq!(status(&tasks, b).uksm_count, 1);
+        assert_eq!(kernel.merges(), 0);
+
+        block_on(tasks.add_unmerge_all());
+        tasks.run_work(AsyncWork::UnMerge).unwrap();
+        assert!(kernel.unmerges() > 0);
+        assert!(!kernel.is_ksm(b, page(0)));
+        assert_eq!(status(&tasks, b).uksm_count, 0);
+    }
+
+    #[test]
+    fn test_shared_frames_are_not_merged_again() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+        assert_eq!(kernel.merges(), 2);
+
+        // A new uksmd adopts the ksm pages that the old one merged, the pages
+        // of a frame join its group without the merge writes.
+        let mut tasks = Tasks::with_fake_kernel(&["--adopt-foreign-ksm"], &kernel);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+        assert_eq!(kernel.merges(), 2);
+        let tp = tasks.tasks_pages.blocking_lock();
+        assert_eq!(tp.uksm.shared_frames(), 2);
+        assert_eq!(tp.uksm.saved_total(), 2);
+    }
+
+    #[test]
+    fn test_forked_pages_are_not_merged() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2, 3]);
+        let c = fake::new_pid();
+        let mut child = FakeTask::new(c).map(fake::MAP_START, 3);
+        child.ppid = a;
+        child.write();
+        kernel.fork(a, c);
+        add(&mut tasks, a);
+        add(&mut tasks, c);
+        settle(&mut tasks);
+
+        // The pages of the child join the groups of the parent without the
+        // merge writes, and a shared frame saves nothing.
+        assert_eq!(kernel.merges(), 0);
+        assert_eq!(status(&tasks, c).uksm_count, 3);
+        assert_eq!(tasks.tasks_pages.blocking_lock().uksm.saved_total(), 0);
+
+        // The frames are kept in the state file.
+        let path = fake::root().join(format!("state-{}", a));
+        let path = path.to_str().unwrap();
+        block_on(tasks.save_state(path)).unwrap();
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        block_on(tasks.load_state(path)).unwrap();
+        assert_eq!(tasks.tasks_pages.blocking_lock().uksm.saved_total(), 0);
+        settle(&mut tasks);
+        assert_eq!(kernel.merges(), 0);
+        assert_eq!(status(&tasks, c).uksm_count, 3);
+        assert_eq!(tasks.tasks_pages.blocking_lock().uksm.saved_total(), 0);
+
+        // A merge of a forked page with another task leaves the other forked
+        // page in the old frame, a refresh after the one of the merged page
+        // finds it New and it is merged again.
+        let b = kernel.spawn(&[1, 4, 5]);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+        assert_ne!(kernel.frame(a, page(0)), kernel.frame(c, page(0)));
+        settle(&mut tasks);
+        settle(&mut tasks);
+        assert_eq!(kernel.merges(), 2);
+        assert_eq!(kernel.frame(a, page(0)), kernel.frame(c, page(0)));
+        assert_eq!(kernel.frame(b, page(0)), kernel.frame(c, page(0)));
+    }
+
+    #[test]
+    fn test_reap_drops_queued_work() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        block_on(tasks.add_range(a, None, false)).unwrap();
+        block_on(tasks.add_verify_all());
+
+        kernel.exit(a);
+        FakeTask::exit(a);
+        block_on(tasks.reap(a));
+        assert!(!block_on(tasks.pids()).contains(&a));
+        assert!(tasks.range_target.blocking_lock().is_empty());
+        let mut verify = tasks.verify_target.blocking_lock();
+        assert_eq!(verify.pop_if(|_| true), Some(b));
+        assert!(verify.is_empty());
+    }
+
+    #[test]
+    fn test_check_addr() {
+        let kernel = Arc::new(FakeKernel::new());
+        let tasks = Tasks::with_fake_kernel(&["--max-addr-range", "1048576"], &kernel);
+        let invalid = |ret: Result<()>| {
+            matches!(
+                ret.unwrap_err().downcast_ref::<TaskError>(),
+                Some(TaskError::InvalidArg(_))
+            )
+        };
+        let size = *page::PAGE_SIZE;
+        let end = proc::USER_ADDR_END;
+
+        assert!(invalid(tasks.check_addr(1, size, false)));
+        assert!(invalid(tasks.check_addr(0, size + 1, false)));
+        assert!(invalid(tasks.check_addr(size, size, false)));
+        assert!(invalid(tasks.check_addr(2 * size, size, false)));
+        assert!(invalid(tasks.check_addr(end - size, end + size, true)));
+        assert!(invalid(tasks.check_addr(0, (1 << 20) + size, false)));
+
+        // The largest ranges that are accepted.
+        tasks.check_addr(0, 1 << 20, false).unwrap();
+        tasks.check_addr(end - (1 << 20), end, false).unwrap();
+        tasks.check_addr(0, end, true).unwrap();
+        let tasks = Tasks::with_fake_kernel(&["--max-addr-range", "0"], &kernel);
+        tasks.check_addr(0, end, false).unwrap();
+    }
+
+    #[test]
+    fn test_add_checks_map_first() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&["--max-tasks", "1"], &kernel);
+        let a = kernel.spawn(&[1]);
+        add(&mut tasks, a);
+        let add_err = |tasks: &mut Tasks, pid: u64| {
+            block_on(tasks.add(uksmd_ctl::AddRequest {
+                pid,
+                ..Default::default()
+            }))
+            .unwrap_err()
+        };
+
+        // The files of the tasks are not read.
+        FakeTask::exit(a);
+        assert_eq!(
+            add_err(&mut tasks, a).to_string(),
+            format!("pid {} exists", a)
+        );
+        let err = add_err(&mut tasks, fake::new_pid());
+        assert!(matches!(
+            err.downcast_ref::<TaskError>(),
+            Some(TaskError::Limit(_))
+        ));
+    }
+
+    #[test]
+    fn test_reports_change_state() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 3]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+
+        let changed = |reports: Vec<WorkReport>| reports.iter().any(|r| r.changes_state());
+        assert!(!changed(refresh(&mut tasks)));
+        assert!(!changed(refresh(&mut tasks)));
+        merge(&mut tasks);
+        assert!(changed(merge(&mut tasks)));
+        assert!(!changed(merge(&mut tasks)));
+
+        kernel.write(b, page(0), 4);
+        assert!(changed(refresh(&mut tasks)));
+        assert!(!changed(refresh(&mut tasks)));
+    }
+
+    #[test]
+    fn test_bad_state_file_is_moved_aside() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 3]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+
+        let path = fake::root().join(format!("state-bad-{}", a));
+        let path = path.to_str().unwrap();
+        let bad_path = format!("{}.bad", path);
+        block_on(tasks.save_state(path)).unwrap();
+        let good = std::fs::read(path).unwrap();
+        let mut old: serde_json::Value = serde_json::from_slice(&good).unwrap();
+        old["version"] = (state::STATE_VERSION - 1).into();
+        let old = serde_json::to_vec(&old).unwrap();
+
+        // The file that cannot be used is moved aside, the tasks start empty.
+        for buf in [&good[..good.len() / 2], &old[..]] {
+            std::fs::write(path, buf).unwrap();
+            let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+            block_on(tasks.load_state(path)).unwrap();
+            assert!(block_on(tasks.map.read()).is_empty());
+            assert!(block_on(tasks.info_status()).is_empty());
+            assert!(!std::path::Path::new(path).exists());
+            assert_eq!(std::fs::read(&bad_path).unwrap(), buf);
+        }
+
+        std::fs::write(path, &good).unwrap();
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        block_on(tasks.load_state(path)).unwrap();
+        assert_eq!(block_on(tasks.map.read()).len(), 2);
+    }
+
+    #[test]
+    fn test_work_report_aggregation() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2, 3, 4]);
+        let b = kernel.spawn(&[1, 2, 3, 5]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+
+        // The sum of the reports of a work: handled tasks, merged and
+        // unmerged pages, and the (new, old, uksm) deltas of a pid.
+        let sum = |reports: &[WorkReport]| {
+            let tasks: u64 = reports.iter().map(|r| r.tasks).sum();
+            let merged: u64 = reports.iter().map(|r| r.merged_pages).sum();
+            let unmerged: u64 = reports.iter().map(|r| r.unmerged_pages).sum();
+            assert!(reports.iter().all(|r| r.failed == 0));
+            (tasks, merged, unmerged)
+        };
+        let delta = |reports: &[WorkReport], pid: u64| {
+            reports
+                .iter()
+                .filter_map(|r| r.deltas.get(&pid))
+                .fold((0, 0, 0), |(n, o, u), d| {
+                    (n + d.new_count, o + d.old_count, u + d.uksm_count)
+                })
+        };
+
+        let reports = refresh(&mut tasks);
+        assert_eq!(sum(&reports), (2, 0, 0));
+        assert_eq!(delta(&reports, a), (4, 0, 0));
+        let reports = refresh(&mut tasks);
+        assert_eq!(delta(&reports, b), (-4, 4, 0));
+
+        let mut reports = merge(&mut tasks);
+        reports.extend(merge(&mut tasks));
+        assert_eq!(sum(&reports), (4, 6, 0));
+        for pid in [a, b] {
+            assert_eq!(delta(&reports, pid), (0, -3, 3));
+        }
+
+        block_on(tasks.add_unmerge_all());
+        let reports = tasks.run_work(AsyncWork::UnMerge).unwrap();
+        assert_eq!(sum(&reports), (2, 0, 6));
+        for pid in [a, b] {
+            assert_eq!(delta(&reports, pid).2, -3);
+        }
+
+        // The stats add up the reports of all the works.
+        let stats = block_on(tasks.stats());
+        assert_eq!((stats.merged_pages, stats.unmerged_pages), (6, 6));
+        assert_eq!(stats.failed_tasks, 0);
+    }
+
+    #[test]
+    fn test_busy_task_is_deferred() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+
+        // A refresh in another thread, as the one of an embedder or a
+        // refresh worker, reads the pagemap of a task without tasks_pages
+        // locked.
+        kernel.hold_reads();
+        block_on(tasks.add_refresh_all());
+        let mut other = tasks.clone();
+        let refresh = std::thread::spawn(move || other.run_work(AsyncWork::Refresh));
+        kernel.wait_held();
+        let busy = tasks.busy_pids();
+        assert_eq!(busy.len(), 1);
+        let x = *busy.iter().next().unwrap();
+        let y = if x == a { b } else { a };
+        let states = block_on(tasks.task_states());
+        assert_eq!(
+            (states[&x].as_str(), states[&y].as_str()),
+            ("refreshing", "idle")
+        );
+
+        // The unmerge of the busy task waits for the refresh, the other task
+        // goes on.
+        block_on(tasks.add_unmerge_all());
+        let report = tasks.async_work_thread(AsyncWork::UnMerge).unwrap();
+        assert_eq!(report.deltas.keys().collect::<Vec<_>>(), vec![&y]);
+        assert_eq!(status(&tasks, y).uksm_count, 0);
+        assert_eq!(status(&tasks, x).uksm_count, 2);
+        assert!(tasks.unmerge_target.blocking_lock().contains(x));
+
+        kernel.release_reads();
+        refresh.join().unwrap().unwrap();
+        tasks.run_work(AsyncWork::UnMerge).unwrap();
+        assert_eq!(status(&tasks, x).uksm_count, 0);
+        assert!(tasks.busy_pids().is_empty());
+    }
+
+    #[test]
+    fn test_worker_panic_is_an_error() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+
+        let config = config::Config::from_iter(["uksmd"]);
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let (ret_tx, mut ret_rx) = mpsc::channel(2);
+        let worker = tasks.start_worker(ret_tx, &config).unwrap();
+
+        // The panic of the work is got as an error, the task that merges
+        // first has nothing to write.
+        kernel.inject_panic();
+        rt.block_on(tasks.add_merge_all());
+        let mut errs = Vec::new();
+        while rt.block_on(tasks.async_work(&worker)) {
+            if let Err(e) = rt.block_on(ret_rx.recv()).unwrap() {
+                errs.push(e.to_string());
+            }
+        }
+        assert_eq!(errs.len(), 1);
+        assert!(errs[0].contains("panic"), "{}", errs[0]);
+        assert!(tasks.busy_pids().is_empty());
+        assert!(!kernel.is_ksm(a, page(0)));
+
+        // The worker goes on with the next work.
+        for _ in 0..2 {
+            rt.block_on(tasks.add_refresh_all());
+            rt.block_on(tasks.add_merge_all());
+            while rt.block_on(tasks.async_work(&worker)) {
+                rt.block_on(ret_rx.recv()).unwrap().unwrap();
+            }
+        }
+        for i in 0..2 {
+            assert!(kernel.is_ksm(a, page(i)));
+            assert_eq!(kernel.frame(a, page(i)), kernel.frame(b, page(i)));
+        }
+        worker.join();
+    }
+
+    #[test]
+    fn test_status_in_long_merge() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let contents: Vec<u64> = (0..32 * WORK_CHUNK_PAGES as u64)
+            .map(|i| i % 64 + 1)
+            .collect();
+        let a = kernel.spawn(&contents);
+        let b = kernel.spawn(&contents);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+
+        block_on(tasks.add_merge_all());
+        let mut other = tasks.clone();
+        let start = Instant::now();
+        let merge = std::thread::spawn(move || other.run_work(AsyncWork::Merge));
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let mut slowest = Duration::ZERO;
+        let mut samples = 0;
+        while !merge.is_finished() {
+            let now = Instant::now();
+            rt.block_on(tasks.info_status());
+            slowest = slowest.max(now.elapsed());
+            samples += 1;
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        merge.join().unwrap().unwrap();
+        let total = start.elapsed();
+
+        // The status waits for a chunk at most, not the whole merge.
+        assert!(samples > 1);
+        assert!(slowest < total / 4, "{:?} of {:?}", slowest, total);
+        assert_eq!(
+            status(&tasks, a).uksm_count + status(&tasks, b).uksm_count,
+            2 * contents.len() as u64
+        );
+    }
+
+    #[test]
+    fn test_merge_is_not_starved_by_refresh() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        let c = kernel.spawn(&[3]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        add(&mut tasks, c);
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+        let task_c = block_on(tasks.map.read()).get(&c).cloned().unwrap();
+
+        let config = config::Config::from_iter(["uksmd"]);
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let (ret_tx, mut ret_rx) = mpsc::channel(2);
+        let worker = tasks.start_worker(ret_tx, &config).unwrap();
+
+        // A refresh arrives before each work, the merges go on between
+        // the refreshes.
+        let mut works = Vec::new();
+        for _ in 0..2 {
+            rt.block_on(tasks.merge_target.lock()).extend([a, b]);
+            while !rt.block_on(tasks.merge_target.lock()).is_empty() {
+                assert!(works.len() < 16, "{:?}", works);
+                rt.block_on(tasks.refresh_target.lock())
+                    .push(task_c.clone());
+                assert!(rt.block_on(tasks.async_work(&worker)));
+                works.push(rt.block_on(ret_rx.recv()).unwrap().unwrap().work);
+            }
+        }
+        let merges = works.iter().filter(|w| **w == AsyncWork::Merge).count();
+        assert_eq!(merges, 4, "{:?}", works);
+        assert!(works.windows(2).all(|w| w[0] != w[1]), "{:?}", works);
+        for i in 0..2 {
+            assert!(kernel.is_ksm(a, page(i)));
+            assert_eq!(kernel.frame(a, page(i)), kernel.frame(b, page(i)));
+        }
+        worker.join();
+    }
+
+    #[test]
+    fn test_duplicate_works_run_once() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        let handled = |reports: Vec<WorkReport>| reports.iter().map(|r| r.tasks).sum::<u64>();
+
+        block_on(tasks.add_refresh_all());
+        assert_eq!(handled(refresh(&mut tasks)), 2);
+        refresh(&mut tasks);
+        block_on(tasks.add_merge_all());
+        block_on(tasks.merge_target.lock()).push(a);
+        assert_eq!(handled(merge(&mut tasks)), 2);
+        merge(&mut tasks);
+        assert_eq!(status(&tasks, b).uksm_count, 2);
+
+        block_on(tasks.add_unmerge_all());
+        block_on(tasks.add_unmerge_all());
+        block_on(tasks.del(uksmd_ctl::DelRequest {
+            pid: a,
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_eq!(handled(tasks.run_work(AsyncWork::UnMerge).unwrap()), 2);
+        assert_eq!(handled(tasks.run_work(AsyncWork::Del).unwrap()), 1);
+        assert!(!tasks
+            .tasks_pages
+            .blocking_lock()
+            .pages_info
+            .contains_key(&a));
+        assert_eq!(status(&tasks, b).uksm_count, 0);
+    }
+
+    #[test]
+    fn test_readd_waits_for_del() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+        assert_eq!(status(&tasks, a).uksm_count, 2);
+
+        // The task restarts with the same pid and other pages right after
+        // its del.
+        block_on(tasks.del(uksmd_ctl::DelRequest {
+            pid: a,
+            ..Default::default()
+        }))
+        .unwrap();
+        kernel.exit(a);
+        let mut task = FakeTask::new(a).map(fake::MAP_START, 3);
+        task.start_time = 200;
+        task.write();
+        kernel.add_task(a);
+        kernel.write_pages(a, fake::MAP_START, &[7, 8, 9]);
+        add(&mut tasks, a);
+
+        // The refresh of the new task waits for the unmerge and del of the
+        // old one.
+        assert_eq!(
+            tasks.async_work_thread(AsyncWork::Refresh).unwrap().tasks,
+            0
+        );
+        assert!(block_on(tasks.refresh_target.lock()).contains(a));
+        tasks.run_work(AsyncWork::UnMerge).unwrap();
+        tasks.run_work(AsyncWork::Del).unwrap();
+        assert!(!block_on(tasks.del_pending(a)));
+
+        tasks.run_work(AsyncWork::Refresh).unwrap();
+        refresh(&mut tasks);
+        let status = status(&tasks, a);
+        assert_eq!((status.old_count, status.uksm_count), (3, 0));
+        assert_eq!(block_on(tasks.map.read())[&a].start_time, 200);
+    }
+
+    #[test]
+    fn test_singleton_stays_old() {
+        let kernel = Arc::new(FakeKernel::new());
+        // Add the singletons to uksm, not skip them by the crc counts.
+        let mut tasks = Tasks::with_fake_kernel(&["--min-dup-count", "1"], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 3]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+
+        // The pages whose crc is the only one are not merged and wait for
+        // the same pages as the old pages.
+        for pid in [a, b] {
+            let status = status(&tasks, pid);
+            assert_eq!((status.old_count, status.uksm_count), (1, 1));
+        }
+        assert!(!kernel.is_ksm(a, page(1)));
+        assert_eq!(kernel.merges(), 1);
+
+        // The page is merged when the same page comes.
+        let c = kernel.spawn(&[2]);
+        add(&mut tasks, c);
+        settle(&mut tasks);
+        let status = status(&tasks, a);
+        assert_eq!((status.old_count, status.uksm_count), (0, 2));
+        assert_eq!(kernel.frame(a, page(1)), kernel.frame(c, page(0)));
+        assert!(kernel.is_ksm(a, page(1)));
+    }
+
+    #[test]
+    fn test_refresh_skips_holes() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        // A map of 4 GiB that has only a few pages.
+        let count = (4 << 30) / *page::PAGE_SIZE;
+        let present = [0, 1, count / 2, count - 1];
+        let pid = fake::new_pid();
+        FakeTask::new(pid).map(fake::MAP_START, count).write();
+        kernel.add_task(pid);
+        for i in present {
+            kernel.write(pid, page(i), i + 1);
+        }
+        add(&mut tasks, pid);
+
+        // All the pages are read from the backend without pagemap.
+        let now = Instant::now();
+        refresh(&mut tasks);
+        let read_all = now.elapsed();
+        assert_eq!(kernel.read_pages(), count);
+        assert_eq!(status(&tasks, pid).new_count, 4);
+
+        // The present bits of pagemap, the file is sparse.
+        let pagemap = std::fs::File::create(fake::root().join(format!("{}/pagemap", pid))).unwrap();
+        pagemap
+            .set_len((fake::MAP_START / *page::PAGE_SIZE + count) * 8)
+            .unwrap();
+        for i in present {
+            let offset = (fake::MAP_START / *page::PAGE_SIZE + i) * 8;
+            pagemap
+                .write_all_at(&(1u64 << 63).to_ne_bytes(), offset)
+                .unwrap();
+        }
+
+        // Only the present pages are read from the backend.
+        let now = Instant::now();
+        refresh(&mut tasks);
+        let read_present = now.elapsed();
+        assert_eq!(kernel.read_pages(), count + 4);
+        assert_eq!(status(&tasks, pid).old_count, 4);
+        assert!(
+            read_present < read_all / 2,
+            "{:?} {:?}",
+            read_present,
+            read_all
+        );
+        FakeTask::exit(pid);
+    }
+
+    #[test]
+    fn test_exit_in_refresh() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+        block_on(tasks.add_merge_all());
+
+        // a exits after its maps are read, the refresh doesn't fail and a
+        // is deleted.
+        kernel.hold_reads();
+        let task = tasks.map.blocking_read()[&a].clone();
+        tasks.refresh_target.blocking_lock().push(task);
+        let mut other = tasks.clone();
+        let worker = std::thread::spawn(move || other.run_work(AsyncWork::Refresh));
+        kernel.wait_held();
+        kernel.exit(a);
+        FakeTask::exit(a);
+        kernel.release_reads();
+        worker.join().unwrap().unwrap();
+        assert!(!block_on(tasks.pids()).contains(&a));
+        assert!(!tasks.merge_target.blocking_lock().contains(a));
+        tasks.run_work(AsyncWork::Del).unwrap();
+        let tp = tasks.tasks_pages.blocking_lock();
+        assert!(!tp.pages_info.contains_key(&a));
+        assert!(!tp.uksm.contains(a, page(0)));
+    }
+
+    #[test]
+    fn test_exec_in_refresh() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+        assert_eq!(status(&tasks, a).uksm_count, 2);
+
+        // The new program of a has its pages in another map.
+        let exec = |exec_id: (u64, u64)| {
+            let mut task = FakeTask::new(a).map(page(0x100), 2);
+            task.exec_id = exec_id;
+            task.write();
+            kernel.drop_page(a, page(0));
+            kernel.drop_page(a, page(1));
+            kernel.write_pages(a, page(0x100), &[7, 8]);
+        };
+
+        // a execs after its maps are read, the pages of the old program
+        // are dropped and a is refreshed again with the new maps.
+        kernel.hold_reads();
+        let task = tasks.map.blocking_read()[&a].clone();
+        tasks.refresh_target.blocking_lock().push(task);
+        let mut other = tasks.clone();
+        let worker = std::thread::spawn(move || other.run_work(AsyncWork::Refresh));
+        kernel.wait_held();
+        exec((0x500000, 0x7ff000000000));
+        kernel.release_reads();
+        worker.join().unwrap().unwrap();
+        let s = status(&tasks, a);
+        assert_eq!((s.new_count, s.uksm_count), (2, 0));
+        assert!(!tasks.tasks_pages.blocking_lock().uksm.contains(a, page(0)));
+        assert!(block_on(tasks.pids()).contains(&a));
+
+        // a execs between the refreshes, the pages of the same crcs are not
+        // aged from the old ones.
+        refresh(&mut tasks);
+        assert_eq!(status(&tasks, a).old_count, 2);
+        exec((0x600000, 0x7ff000000000));
+        refresh(&mut tasks);
+        let s = status(&tasks, a);
+        assert_eq!((s.new_count, s.old_count), (2, 0));
+    }
+
+    #[test]
+    fn test_vma_filter_of_task() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&["--exclude-vma", "^\\[heap\\]$"], &kernel);
+        // A task that has a heap and a guest ram named with PR_SET_VMA.
+        let spawn_named = || {
+            let pid = fake::new_pid();
+            let named = |start: u64, name: &str| {
+                FakeTask::new(pid).map(start, 2).smaps().replacen(
+                    " 0\n",
+                    &format!(" 0    {}\n", name),
+                    1,
+                )
+            };
+            let smaps = named(page(0), "[heap]") + &named(page(0x10), "[anon:guest-ram]");
+            FakeTask::new(pid).write_files(&smaps);
+            kernel.add_task(pid);
+            kernel.write_pages(pid, page(0), &[1, 2]);
+            kernel.write_pages(pid, page(0x10), &[3, 4]);
+            pid
+        };
+        let (a, b) = (spawn_named(), spawn_named());
+        add(&mut tasks, a);
+        // The filter of b is used instead of the one of config.
+        block_on(tasks.add(uksmd_ctl::AddRequest {
+            pid: b,
+            include_vma: vec!["^\\[heap\\]$".to_string()],
+            ..Default::default()
+        }))
+        .unwrap();
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+
+        let tp = tasks.tasks_pages.blocking_lock();
+        let addrs = |pid| tp.pages_info[&pid].merge_chunk_addrs(0);
+        assert_eq!(addrs(a), vec![page(0x10), page(0x11)]);
+        assert_eq!(addrs(b), vec![page(0), page(1)]);
+        drop(tp);
+
+        // The regexes are checked when the task is added.
+        let c = kernel.spawn(&[1]);
+        assert!(block_on(tasks.add(uksmd_ctl::AddRequest {
+            pid: c,
+            exclude_vma: vec!["[anon".to_string()],
+            ..Default::default()
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_dangerous_pids_are_rejected() {
+        let kernel = Arc::new(FakeKernel::new());
+        let add_err = |tasks: &mut Tasks, pid: u64| {
+            block_on(tasks.add(uksmd_ctl::AddRequest {
+                pid,
+                ..Default::default()
+            }))
+            .unwrap_err()
+            .to_string()
+        };
+
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        assert!(add_err(&mut tasks, 1).contains("is init"));
+        assert!(add_err(&mut tasks, std::process::id() as u64).contains("is uksmd itself"));
+        // The fake procfs doesn't have init, it is checked as the others
+        // with allow_dangerous_pids.
+        let mut tasks = Tasks::with_fake_kernel(&["--allow-dangerous-pids"], &kernel);
+        assert!(add_err(&mut tasks, 1).contains("proc::pid_is_available 1 failed"));
+        assert!(add_err(&mut tasks, std::process::id() as u64).contains("is uksmd itself"));
+    }
+
+    #[test]
+    fn test_paused_task_is_skipped() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        let c = kernel.spawn(&[1, 2]);
+        for pid in [a, b, c] {
+            add(&mut tasks, pid);
+        }
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+        assert!(block_on(tasks.set_paused(fake::new_pid(), true)).is_err());
+        block_on(tasks.set_paused(b, true)).unwrap();
+        assert_eq!(block_on(tasks.paused_pids()), vec![b]);
+
+        // The pages of b are not moved by the refresh and merge of all the
+        // tasks, the others are merged.
+        let counts = |tasks: &Tasks, pid| {
+            let s = status(tasks, pid);
+            (s.new_count, s.old_count, s.uksm_count)
+        };
+        let before = counts(&tasks, b);
+        kernel.write(b, page(0), 3);
+        for _ in 0..2 {
+            settle(&mut tasks);
+            assert_eq!(counts(&tasks, b), before);
+        }
+        assert_eq!(status(&tasks, c).uksm_count, 2);
+        assert!(!tasks.tasks_pages.blocking_lock().uksm.contains(b, page(1)));
+        assert_ne!(kernel.frame(a, page(1)), kernel.frame(b, page(1)));
+
+        // The merge of b is refused, its unmerge is not.
+        let e = block_on(tasks.add_range(b, None, false)).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<TaskError>(),
+            Some(TaskError::FailedPrecondition(_))
+        ));
+        block_on(tasks.add_range(b, None, true)).unwrap();
+
+        // b is merged after it is resumed.
+        block_on(tasks.set_paused(b, false)).unwrap();
+        assert!(block_on(tasks.paused_pids()).is_empty());
+        settle(&mut tasks);
+        assert_eq!(status(&tasks, b).uksm_count, 1);
+        assert_eq!(kernel.frame(a, page(1)), kernel.frame(b, page(1)));
+    }
+
+    #[test]
+    fn test_merge_order_of_priorities() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        // The priority and the pages of each task in the merge order.
+        let want = [(5, 1), (0, 4), (0, 2), (-1, 3)];
+        let mut pids = Vec::new();
+        for (priority, pages) in want.iter().rev() {
+            let contents: Vec<u64> = (0..*pages).collect();
+            let pid = kernel.spawn(&contents);
+            block_on(tasks.add(uksmd_ctl::AddRequest {
+                pid,
+                OptPriority: Some(uksmd_ctl::add_request::OptPriority::Priority(*priority)),
+                ..Default::default()
+            }))
+            .unwrap();
+            pids.insert(0, pid);
+        }
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+        let priorities = block_on(tasks.priorities());
+        assert_eq!(priorities[&pids[0]], 5);
+
+        // The worker merges a task each time.
+        let merge_order = |tasks: &mut Tasks| {
+            let mut order = Vec::new();
+            while !tasks.merge_target.blocking_lock().is_empty() {
+                let report = tasks.async_work_thread(AsyncWork::Merge).unwrap();
+                assert_eq!(report.tasks, 1);
+                order.extend(report.deltas.keys().copied());
+            }
+            order
+        };
+        block_on(tasks.add_merge_all());
+        assert_eq!(merge_order(&mut tasks), pids);
+
+        // The pending merges are moved to the places of the new priorities,
+        // the tasks of the same priority keep their order.
+        let update = |tasks: &mut Tasks, pid, priority| {
+            block_on(tasks.update(&uksmd_ctl::UpdateRequest {
+                pid,
+                OptPriority: Some(uksmd_ctl::update_request::OptPriority::Priority(priority)),
+                ..Default::default()
+            }))
+        };
+        block_on(tasks.add_merge_all());
+        update(&mut tasks, pids[3], 10).unwrap();
+        update(&mut tasks, pids[0], -2).unwrap();
+        let want = vec![pids[3], pids[1], pids[2], pids[0]];
+        assert_eq!(merge_order(&mut tasks), want);
+        assert_eq!(block_on(tasks.priorities())[&pids[3]], 10);
+
+        // The next merges follow the new priorities, and an update without
+        // a priority keeps it.
+        block_on(tasks.update(&uksmd_ctl::UpdateRequest {
+            pid: pids[3],
+            ..Default::default()
+        }))
+        .unwrap();
+        block_on(tasks.add_merge_all());
+        assert_eq!(merge_order(&mut tasks), want);
+        assert!(update(&mut tasks, fake::new_pid(), 1).is_err());
+    }
+
+    #[test]
+    fn test_merge_window_defers_merge() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+
+        tasks.set_merge_window_open(false);
+        assert!(!block_on(tasks.merge_deferred()));
+        block_on(tasks.add_merge_all());
+        assert!(block_on(tasks.merge_deferred()));
+
+        // The forced merge runs in the closed window.
+        tasks.force_merge();
+        assert!(!block_on(tasks.merge_deferred()));
+
+        tasks.set_merge_window_open(true);
+        assert!(!block_on(tasks.merge_deferred()));
+        assert!(tasks.merge_window_open());
+    }
+
+    #[test]
+    fn test_add_all_with_many_tasks() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        {
+            let mut map = tasks.map.blocking_write();
+            for pid in 1..=50_000u64 {
+                let mut task = TaskInfo::new(pid, None, 0, None);
+                task.paused = pid % 10 == 0;
+                task.priority = (pid % 3) as i32;
+                map.insert(pid, task);
+            }
+        }
+
+        // The target set that one push for each task makes.
+        let mut expected = queue::WorkQueue::new();
+        for task in tasks.map.blocking_read().values().filter(|t| !t.paused) {
+            expected.push(task.clone());
+        }
+        let mut expected: Vec<u64> = expected.keys().cloned().collect();
+        expected.sort_unstable();
+        assert_eq!(expected.len(), 45_000);
+
+        // The tasks are still queued from the last cycle, a push for each
+        // of them scans the whole queue.
+        block_on(tasks.add_refresh_all());
+        let now = Instant::now();
+        block_on(tasks.add_refresh_all());
+        let elapsed = now.elapsed();
+        assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+
+        let mut target = tasks.refresh_target.blocking_lock();
+        let mut pids: Vec<u64> = std::iter::from_fn(|| target.pop_if(|_| true))
+            .map(|t| t.pid)
+            .collect();
+        drop(target);
+        pids.sort_unstable();
+        assert_eq!(pids, expected);
+
+        block_on(tasks.add_merge_all());
+        let now = Instant::now();
+        block_on(tasks.add_merge_all());
+        let elapsed = now.elapsed();
+        assert!(elapsed < Duration::from_secs(2), "{:?}", elapsed);
+
+        // The worker pops the highest priority first.
+        let mut target = tasks.merge_target.blocking_lock();
+        let pids: Vec<u64> = std::iter::from_fn(|| target.pop_if(|_| true)).collect();
+        let mut order = expected.clone();
+        order.sort_unstable_by_key(|pid| std::cmp::Reverse(((pid % 3) as i32, *pid)));
+        assert_eq!(pids, order);
+    }
+
+    #[test]
+    fn test_chunked_merge() {
+        let contents = [1, 2, 3, 4, 5];
+        let run = |args: &[&str]| {
+            let kernel = Arc::new(FakeKernel::new());
+            let mut tasks = Tasks::with_fake_kernel(args, &kernel);
+            let a = kernel.spawn(&contents);
+            let b = kernel.spawn(&contents);
+            add(&mut tasks, a);
+            add(&mut tasks, b);
+            refresh(&mut tasks);
+            refresh(&mut tasks);
+            (kernel, tasks, a, b)
+        };
+
+        let (kernel, mut tasks, a, b) = run(&["--deterministic-order", "--merge-chunk-pages", "2"]);
+        block_on(tasks.add_merge_all());
+        // Each step merges a chunk of a task, the next chunk of the task
+        // goes after the other task.
+        let mut remaining = Vec::new();
+        while !tasks.merge_target.blocking_lock().is_empty() {
+            tasks.async_work_thread(AsyncWork::Merge).unwrap();
+            let r = block_on(tasks.merge_remaining());
+            remaining.push((r.get(&a).cloned(), r.get(&b).cloned()));
+        }
+        assert_eq!(
+            remaining,
+            [
+                (Some(3), None),
+                (Some(3), Some(3)),
+                (Some(1), Some(3)),
+                (Some(1), Some(1)),
+                (None, Some(1)),
+                (None, None),
+            ]
+        );
+        merge(&mut tasks);
+
+        // The chunked merges end the same as the merges of the whole tasks.
+        let (whole_kernel, mut whole, wa, wb) = run(&["--deterministic-order"]);
+        merge(&mut whole);
+        merge(&mut whole);
+        let counts = |tasks: &Tasks, pid| {
+            let s = status(tasks, pid);
+            (s.new_count, s.old_count, s.uksm_count, s.stale_count)
+        };
+        assert_eq!(counts(&tasks, a), counts(&whole, wa));
+        assert_eq!(counts(&tasks, b), counts(&whole, wb));
+        assert_eq!(status(&tasks, b).uksm_count, 5);
+        assert_eq!(kernel.merges(), whole_kernel.merges());
+        assert_eq!(
+            tasks.tasks_pages.blocking_lock().uksm.saved_total(),
+            whole.tasks_pages.blocking_lock().uksm.saved_total()
+        );
+        assert!(block_on(tasks.merge_remaining()).is_empty());
+    }
+
+    #[test]
+    fn test_deterministic_order() {
+        let pids: Vec<u64> = (0..3).map(|_| fake::new_pid()).collect();
+        // The tasks share 3 pages and have extra pages of their own, the
+        // extra pages change the order of the merge by pages.
+        let run = |args: &[&str], extra: [u64; 3]| {
+            let kernel = Arc::new(FakeKernel::new());
+            let mut tasks = Tasks::with_fake_kernel(args, &kernel);
+            for (i, pid) in pids.iter().enumerate() {
+                let mut contents = vec![1, 2, 3];
+                contents.extend((0..extra[i]).map(|j| 100 + i as u64 * 10 + j));
+                kernel.spawn_pid(*pid, &contents);
+                add(&mut tasks, *pid);
+            }
+            settle(&mut tasks);
+            let state = tasks.tasks_pages.blocking_lock().uksm.to_state();
+            serde_json::to_value(state).unwrap()["pages"].take()
+        };
+
+        // The groups of the merged pages are the same in the runs.
+        let groups = run(&["--deterministic-order"], [0, 0, 2]);
+        assert_eq!(groups.as_object().unwrap().len(), 3);
+        assert_eq!(groups, run(&["--deterministic-order"], [2, 0, 0]));
+
+        // They are not without the option.
+        assert_ne!(run(&[], [0, 0, 2]), run(&[], [2, 0, 0]));
+    }
+
+    #[test]
+    fn test_failing_page_is_parked() {
+        // Each cycle adds a task whose page has the same crc as the pages
+        // of the tasks before it but not the same content.
+        let run = |args: &[&str]| {
+            let kernel = Arc::new(FakeKernel::new());
+            let mut tasks = Tasks::with_fake_kernel(args, &kernel);
+            let mut pids = Vec::new();
+            let mut cycles = Vec::new();
+            for k in 0..5u64 {
+                let pid = kernel.spawn(&[2 + (k << 32)]);
+                add(&mut tasks, pid);
+                pids.push(pid);
+                refresh(&mut tasks);
+                refresh(&mut tasks);
+                let merges = kernel.merges();
+                merge(&mut tasks);
+                let stale: u64 = pids
+                    .iter()
+                    .map(|pid| status(&tasks, *pid).stale_count)
+                    .sum();
+                cycles.push((kernel.merges() - merges, stale));
+            }
+            (kernel, tasks, pids, cycles)
+        };
+
+        // The pages that failed twice are parked and the later pages are
+        // not compared with them.
+        let (kernel, mut tasks, pids, cycles) =
+            run(&["--deterministic-order", "--max-merge-failures", "2"]);
+        assert_eq!(cycles, [(0, 0), (1, 0), (2, 2), (1, 3), (1, 4)]);
+        assert_eq!(block_on(tasks.stats()).stale_pages, 4);
+
+        // A parked page is merged again after its crc changes.
+        kernel.write(pids[0], page(0), 7);
+        kernel.write(pids[1], page(0), 7);
+        refresh(&mut tasks);
+        assert_eq!(status(&tasks, pids[0]).new_count, 1);
+        refresh(&mut tasks);
+        merge(&mut tasks);
+        merge(&mut tasks);
+        assert_eq!(status(&tasks, pids[1]).uksm_count, 1);
+        assert_eq!(block_on(tasks.stats()).stale_pages, 2);
+
+        // 0 never parks the pages, each page is compared with all the
+        // pages before it.
+        let (_, _, _, cycles) = run(&["--deterministic-order", "--max-merge-failures", "0"]);
+        assert_eq!(cycles, [(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]);
+    }
+
+    #[test]
+    fn test_range_merge_unmerge() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&["--deterministic-order"], &kernel);
+        let a = kernel.spawn(&[1, 2, 3, 4, 5, 6]);
+        let b = kernel.spawn(&[1, 2, 3, 4, 5, 6]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+        let range = |tasks: &mut Tasks, pid, start, end, unmerge| {
+            block_on(tasks.add_range(pid, Some((page(start), page(end))), unmerge)).unwrap();
+            tasks.run_work(AsyncWork::Range).unwrap()
+        };
+
+        // Only the pages in both ranges are merged.
+        range(&mut tasks, a, 0, 4, false);
+        range(&mut tasks, b, 2, 6, false);
+        let s = status(&tasks, b);
+        assert_eq!((s.uksm_count, s.old_count), (2, 4));
+        for i in 0..6 {
+            assert_eq!(kernel.is_ksm(b, page(i)), i == 2 || i == 3, "page {}", i);
+        }
+
+        // A range with no tracked pages does nothing.
+        let merges = kernel.merges();
+        range(&mut tasks, b, 100, 110, false);
+        range(&mut tasks, b, 100, 110, true);
+        assert_eq!(kernel.merges(), merges);
+        assert_eq!(status(&tasks, b).uksm_count, 2);
+
+        // The unmerge range overlaps one of the merged pages.
+        range(&mut tasks, b, 1, 3, true);
+        assert!(!kernel.is_ksm(b, page(2)));
+        assert!(kernel.is_ksm(b, page(3)));
+        assert_eq!(status(&tasks, b).uksm_count, 1);
+
+        // The full merge merges the pages out of the ranges.
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+        merge(&mut tasks);
+        merge(&mut tasks);
+        assert_eq!(status(&tasks, b).uksm_count, 6);
+
+        // The range is checked as add does.
+        assert!(block_on(tasks.add_range(b, Some((page(1) + 1, page(2))), false)).is_err());
+        assert!(block_on(tasks.add_range(b, Some((page(2), page(1))), true)).is_err());
+    }
+
+    #[test]
+    fn test_refresh_uses_task_in_map() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2, 3, 4, 5, 6]);
+        add(&mut tasks, a);
+
+        // An outdated entry of the task with a smaller range is queued
+        // after the one of add_refresh_all.
+        let mut old = tasks.map.blocking_read()[&a].clone();
+        old.addr = Some((page(0), page(2)));
+        block_on(tasks.add_refresh_all());
+        tasks.refresh_target.blocking_lock().push(old.clone());
+        assert_eq!(tasks.refresh_target.blocking_lock().keys().count(), 1);
+
+        // The task is refreshed once with the range in map.
+        let reports = tasks.run_work(AsyncWork::Refresh).unwrap();
+        assert_eq!(reports.iter().map(|r| r.tasks).sum::<u64>(), 1);
+        assert_eq!(kernel.read_pages(), 6);
+        assert_eq!(status(&tasks, a).new_count, 6);
+
+        // A deleted task is not refreshed.
+        tasks.refresh_target.blocking_lock().push(old);
+        tasks.map.blocking_write().remove(&a);
+        let reports = tasks.run_work(AsyncWork::Refresh).unwrap();
+        assert_eq!(reports.iter().map(|r| r.tasks).sum::<u64>(), 0);
+        assert_eq!(kernel.read_pages(), 6);
+    }
+
+    #[test]
+    fn test_merge_probe_counts() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&["--deterministic-order"], &kernel);
+        // The crc of 7 and collide is the same.
+        let collide = 7 + (1 << 32);
+        let a = kernel.spawn(&[5, 7]);
+        let b = kernel.spawn(&[collide]);
+        let c = kernel.spawn(&[5, collide]);
+        for pid in [a, b, c] {
+            add(&mut tasks, pid);
+        }
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+
+        // a starts the groups. The page of b is compared with the one of a
+        // only, 5 of c is merged with the first group and the other page of
+        // c with the second group.
+        let reports = merge(&mut tasks);
+        let stats = block_on(tasks.stats());
+        assert_eq!(
+            (
+                stats.last_merge_crc_matched,
+                stats.last_merge_first_probe,
+                stats.last_merge_multi_probe,
+                stats.last_merge_crc_collisions
+            ),
+            (3, 1, 1, 1)
+        );
+        assert_eq!(status(&tasks, b).probes.exhausted, 1);
+        assert_eq!(status(&tasks, c).probes.multi_probe, 1);
+        let summary: Vec<String> = reports.iter().map(|r| r.to_string()).collect();
+        assert!(
+            summary
+                .iter()
+                .any(|s| s.contains("crc matched 2 (first probe 1 more probes 1 collisions 0)")),
+            "{:?}",
+            summary
+        );
+
+        // The pages in the groups are not compared again.
+        merge(&mut tasks);
+        let stats = block_on(tasks.stats());
+        assert_eq!(stats.last_merge_crc_matched, 0);
+        assert_eq!(stats.last_merge_crc_collisions, 0);
+    }
+
+    #[test]
+    fn test_no_mem_defers_merge() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&["--deterministic-order"], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+
+        kernel.inject(Fault::NoMem);
+        merge(&mut tasks);
+        assert_eq!(status(&tasks, b).uksm_count, 0);
+        assert_eq!(block_on(tasks.stats()).merge_deferred, 1);
+
+        merge(&mut tasks);
+        assert_eq!(status(&tasks, b).uksm_count, 2);
+    }
+
+    #[test]
+    fn test_rejected_map_is_parked() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&["--deterministic-order"], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        refresh(&mut tasks);
+        refresh(&mut tasks);
+
+        kernel.inject(Fault::Rejected);
+        merge(&mut tasks);
+        let s = status(&tasks, b);
+        assert_eq!((s.stale_count, s.uksm_count), (1, 1));
+    }
+
+    #[test]
+    fn test_kernel_lost_and_back() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+
+        // The module is unloaded, the kernel unmerges all the pages.
+        kernel.set_lost(true);
+        kernel.unmerge_all();
+        block_on(tasks.add_refresh_all());
+        assert!(tasks.run_work(AsyncWork::Refresh).is_err());
+        assert!(tasks.kernel_lost());
+        assert!(block_on(tasks.pids()).contains(&a));
+
+        kernel.set_lost(false);
+        block_on(tasks.recover_kernel());
+        assert!(!tasks.kernel_lost());
+        assert_eq!(status(&tasks, a).uksm_count, 0);
+        assert_eq!(tasks.tasks_pages.blocking_lock().uksm.saved_total(), 0);
+
+        settle(&mut tasks);
+        assert_eq!(status(&tasks, a).uksm_count, 2);
+        assert!(kernel.is_ksm(b, page(1)));
+    }
+
+    #[test]
+    fn test_kernel_lost_in_unmerge() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+
+        kernel.inject(Fault::Lost);
+        block_on(tasks.add_unmerge_all());
+        assert!(tasks.run_work(AsyncWork::UnMerge).is_err());
+        assert!(tasks.kernel_lost());
+        // The unmerge is not waited for when the kernel is lost.
+        assert!(!block_on(tasks.del_pending(a)));
+    }
+
+    #[test]
+    fn test_kernel_lost_events() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut tasks = Tasks::with_fake_kernel(&[], &kernel);
+        let a = kernel.spawn(&[1, 2]);
+        let b = kernel.spawn(&[1, 2]);
+        add(&mut tasks, a);
+        add(&mut tasks, b);
+        settle(&mut tasks);
+        let merged = status(&tasks, a).uksm_count + status(&tasks, b).uksm_count;
+        assert!(merged > 0);
+        let events = tasks.events.recent(0).len();
+
+        // The lost transition is reported once.
+        kernel.set_lost(true);
+        kernel.unmerge_all();
+        for _ in 0..2 {
+            block_on(tasks.add_refresh_all());
+            assert!(tasks.run_work(AsyncWork::Refresh).is_err());
+        }
+        let recent = tasks.events.recent(0);
+        assert_eq!(recent.len(), events + 1);
+        assert_eq!(recent[0].severity, events::Severity::Warn);
+        assert!(recent[0].message.contains("kernel interfaces are lost"));
+        let e = block_on(tasks.drain()).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<TaskError>(),
+            Some(TaskError::Unavailable(_))
+        ));
+
+        // The probe fails while the kernel is still lost.
+        block_on(tasks.recover_kernel());
+        assert!(tasks.kernel_lost());
+        assert_eq!(tasks.events.recent(0).len(), events + 1);
+
+        kernel.set_lost(false);
+        block_on(tasks.recover_kernel());
+        assert!(!tasks.kernel_lost());
+        let recent = tasks.events.recent(0);
+        assert_eq!(recent.len(), events + 2);
+        assert_eq!(recent[0].severity, events::Severity::Info);
+        assert_eq!(
+            recent[0].message,
+            format!(
+                "kernel interfaces are back, {} merged pages will be merged again",
+                merged
+            )
+        );
+        // The merged pages are New and all the tasks are refreshed again.
+        assert_eq!(status(&tasks, b).new_count, 2);
+        let target = tasks.refresh_target.blocking_lock();
+        assert!(target.contains(a) && target.contains(b));
+        drop(target);
+
+        // The recovery is done once.
+        block_on(tasks.recover_kernel());
+        assert_eq!(tasks.events.recent(0).len(), events + 2);
     }
 }
@@ -0,0 +1,141 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The durations of the major operations of each work cycle.
+// The counters are global because the operations are deep in the call
+// chains of the worker and the refresh threads. They are only updated when
+// the timings are enabled, otherwise a timer doesn't even read the clock.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    ParseSmaps,
+    ReadPagemap,
+    RefreshPages,
+    Merge,
+    UksmAdd,
+    UksmAddBatch,
+    MergePages,
+}
+
+const OPS: [Op; 7] = [
+    Op::ParseSmaps,
+    Op::ReadPagemap,
+    Op::RefreshPages,
+    Op::Merge,
+    Op::UksmAdd,
+    Op::UksmAddBatch,
+    Op::MergePages,
+];
+
+impl Op {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Op::ParseSmaps => "parse_task_smaps",
+            Op::ReadPagemap => "read_pagemap",
+            Op::RefreshPages => "refresh_pages",
+            Op::Merge => "merge",
+            Op::UksmAdd => "uksm_add",
+            Op::UksmAddBatch => "uksm_add_batch",
+            Op::MergePages => "merge_pages",
+        }
+    }
+}
+
+struct Counter {
+    calls: AtomicU64,
+    total_ns: AtomicU64,
+    max_ns: AtomicU64,
+}
+
+impl Counter {
+    const fn new() -> Self {
+        Self {
+            calls: AtomicU64::new(0),
+            total_ns: AtomicU64::new(0),
+            max_ns: AtomicU64::new(0),
+        }
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// indexed by Op
+static COUNTERS: [Counter; OPS.len()] = [
+    Counter::new(),
+    Counter::new(),
+    Counter::new(),
+    Counter::new(),
+    Counter::new(),
+    Counter::new(),
+    Counter::new(),
+];
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+// Record the duration of an operation when it is dropped.
+pub struct Timer {
+    op: Op,
+    start: Option<Instant>,
+}
+
+pub fn start(op: Op) -> Timer {
+    Timer {
+        op,
+        start: ENABLED.load(Ordering::Relaxed).then(Instant::now),
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            let ns = start.elapsed().as_nanos() as u64;
+            let c = &COUNTERS[self.op as usize];
+            c.calls.fetch_add(1, Ordering::Relaxed);
+            c.total_ns.fetch_add(ns, Ordering::Relaxed);
+            c.max_ns.fetch_max(ns, Ordering::Relaxed);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpTiming {
+    pub name: &'static str,
+    pub calls: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl std::fmt::Display for OpTiming {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} calls total {:?} max {:?}",
+            self.name, self.calls, self.total, self.max
+        )
+    }
+}
+
+// Return the timings of the operations that are called since the last
+// take and reset the counters.
+pub fn take() -> Vec<OpTiming> {
+    OPS.iter()
+        .filter_map(|op| {
+            let c = &COUNTERS[*op as usize];
+            let calls = c.calls.swap(0, Ordering::Relaxed);
+            let total = c.total_ns.swap(0, Ordering::Relaxed);
+            let max = c.max_ns.swap(0, Ordering::Relaxed);
+            (calls > 0).then(|| OpTiming {
+                name: op.name(),
+                calls,
+                total: Duration::from_nanos(total),
+                max: Duration::from_nanos(max),
+            })
+        })
+        .collect()
+}
@@ -2,39 +2,297 @@
 //
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::page;
+use crate::{backend, config, page, paths, proc, timing};
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
-const MERGE_PATH: &str = "/proc/uksm/merge";
-const UNMERGE_PATH: &str = "/proc/uksm/unmerge";
-const CMP_PATH: &str = "/proc/uksm/cmp";
-const LRU_ADD_DRAIN_ALL_PATH: &str = "/proc/uksm/lru_add_drain_all";
+lazy_static! {
+    static ref MERGE_PATH: String = paths::uksm("merge");
+    static ref UNMERGE_PATH: String = paths::uksm("unmerge");
+    static ref CMP_PATH: String = paths::uksm("cmp");
+    static ref LRU_ADD_DRAIN_ALL_PATH: String = paths::uksm("lru_add_drain_all");
+    static ref VERSION_PATH: String = paths::uksm("version");
+    static ref SELF_UKSM_PAGEMAP_PATH: String = paths::proc("self/uksm_pagemap");
+}
 const EPAGESNOTSAME: i32 = 541;
 
-pub fn check_kernel() -> Result<()> {
+// The dirs of the counters of the uksm patches and the standard KSM.
+const KERNEL_STATS_DIRS: [&str; 2] = ["/sys/kernel/mm/uksm", "/sys/kernel/mm/ksm"];
+
+// The interfaces that the uksm kernel patches provide.
+#[derive(Debug, Clone, Default)]
+pub struct KernelCaps {
+    pub merge: bool,
+    pub unmerge: bool,
+    pub cmp: bool,
+    pub lru_add_drain_all: bool,
+    pub uksm_pagemap: bool,
+    pub version: Option<String>,
+}
+
+impl KernelCaps {
+    pub fn probe() -> Self {
+        let exists = |path: &str| Path::new(path).exists();
+
+        Self {
+            merge: exists(&MERGE_PATH),
+            unmerge: exists(&UNMERGE_PATH),
+            cmp: exists(&CMP_PATH),
+            lru_add_drain_all: exists(&LRU_ADD_DRAIN_ALL_PATH),
+            uksm_pagemap: exists(&SELF_UKSM_PAGEMAP_PATH),
+            version: std::fs::read_to_string(&*VERSION_PATH)
+                .ok()
+                .map(|v| v.trim().to_string()),
+        }
+    }
+
+    // The names of the interfaces that exist.
+    pub fn names(&self) -> Vec<String> {
+        [
+            ("merge", self.merge),
+            ("unmerge", self.unmerge),
+            ("cmp", self.cmp),
+            ("lru_add_drain_all", self.lru_add_drain_all),
+            ("uksm_pagemap", self.uksm_pagemap),
+        ]
+        .iter()
+        .filter(|(_, exists)| *exists)
+        .map(|(name, _)| name.to_string())
+        .collect()
+    }
+}
+
+// merge, unmerge and uksm_pagemap are necessary, the others are optional.
+pub fn check_kernel() -> Result<KernelCaps> {
     OpenOptions::new()
         .write(true)
-        .open(MERGE_PATH)
-        .map_err(|e| anyhow!("open file {} failed: {}", MERGE_PATH, e))?;
+        .open(&*MERGE_PATH)
+        .map_err(|e| anyhow!("open file {} failed: {}", *MERGE_PATH, e))?;
 
-    Ok(())
+    let caps = KernelCaps::probe();
+    if !caps.unmerge {
+        return Err(anyhow!("file {} does not exist", *UNMERGE_PATH));
+    }
+    if !caps.uksm_pagemap {
+        return Err(anyhow!("file {} does not exist", *SELF_UKSM_PAGEMAP_PATH));
+    }
+
+    Ok(caps)
+}
+
+// The write that no kernel handles as a command, the parser of the kernel
+// rejects it with EINVAL.
+const PROBE_CMD: &str = "uksmd-probe";
+
+// Check that the kernel parses the writes to path. The file of a kernel
+// that only has a part of the patches exists but fails the writes with
+// another errno.
+fn probe_write(path: &str) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| anyhow!("open file {} for write failed: {}", path, e))?;
+
+    match file.write(PROBE_CMD.as_bytes()) {
+        Ok(_) => Ok(()),
+        Err(e) if e.raw_os_error() == Some(libc::EINVAL) => Ok(()),
+        Err(e) => Err(anyhow!("file {} does not handle the writes: {}", path, e)),
+    }
+}
+
+// Check that the uksm_pagemap of uksmd can be read at a page that is mapped.
+fn probe_pagemap() -> Result<()> {
+    let file = File::open(&*SELF_UKSM_PAGEMAP_PATH)
+        .map_err(|e| anyhow!("open file {} failed: {}", *SELF_UKSM_PAGEMAP_PATH, e))?;
+
+    let addr = &file as *const File as u64;
+    let mut buf = [0u8; UKSM_PAGEMAP_ENTRY_SIZE as usize];
+    file.read_exact_at(&mut buf, addr / *page::PAGE_SIZE * UKSM_PAGEMAP_ENTRY_SIZE)
+        .map_err(|e| anyhow!("read file {} failed: {}", *SELF_UKSM_PAGEMAP_PATH, e))
+}
+
+// check_kernel that also probes the writes of the interfaces and the read
+// of uksm_pagemap. The probes are only done at startup.
+pub fn probe_kernel() -> Result<KernelCaps> {
+    let caps = check_kernel()?;
+
+    probe_write(&MERGE_PATH)?;
+    probe_write(&UNMERGE_PATH)?;
+    if caps.cmp {
+        probe_write(&CMP_PATH)?;
+    }
+    probe_pagemap()?;
+
+    Ok(caps)
 }
 
-pub fn lru_add_drain_all() -> Result<()> {
+// Read the counters in dir to stats with the name "<prefix>/<file>".
+// The files that cannot be read or are not a number are ignored because
+// they are different across the kernel versions.
+fn read_stats_dir(dir: &Path, prefix: &str, stats: &mut HashMap<String, u64>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Ok(content) = std::fs::read_to_string(entry.path()) {
+            if let Ok(value) = content.trim().parse::<u64>() {
+                stats.insert(format!("{}/{}", prefix, name), value);
+            }
+        }
+    }
+}
+
+pub fn kernel_stats() -> HashMap<String, u64> {
+    read_stats_dirs(&KERNEL_STATS_DIRS)
+}
+
+// Read the counters in dirs, the prefix of each one is the name of its dir.
+fn read_stats_dirs<P: AsRef<Path>>(dirs: &[P]) -> HashMap<String, u64> {
+    let mut stats = HashMap::new();
+
+    for dir in dirs {
+        let path = dir.as_ref();
+        let prefix = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        read_stats_dir(path, &prefix, &mut stats);
+    }
+
+    stats
+}
+
+fn lru_add_drain_all() -> Result<()> {
     let mut file = OpenOptions::new()
         .write(true)
-        .open(LRU_ADD_DRAIN_ALL_PATH)
-        .map_err(|e| anyhow!("open file {} failed: {}", LRU_ADD_DRAIN_ALL_PATH, e))?;
+        .open(&*LRU_ADD_DRAIN_ALL_PATH)
+        .map_err(|e| {
+            let estr = format!("open file {} failed: {}", *LRU_ADD_DRAIN_ALL_PATH, e);
+            if e.raw_os_error() == Some(libc::ENOENT) {
+                anyhow::Error::new(KernelError::Lost(estr))
+            } else {
+                anyhow!(estr)
+            }
+        })?;
 
     write!(file, "1")
-        .map_err(|e| anyhow!("write file {} failed: {}", LRU_ADD_DRAIN_ALL_PATH, e))?;
+        .map_err(|e| anyhow!("write file {} failed: {}", *LRU_ADD_DRAIN_ALL_PATH, e))?;
 
     Ok(())
 }
 
+// The errors of the kernel operations that the callers handle.
+#[derive(Debug)]
+pub enum KernelError {
+    // The task or the page is gone when the kernel handles it.
+    TaskGone(u64),
+    PageGone(u64, u64),
+    // The kernel does not merge the page, for example a shmem page of a
+    // kernel that only merges the anonymous pages.
+    Rejected(u64, u64),
+    // The kernel is short of memory, the merge should be deferred to the
+    // next time.
+    NoMem(String),
+    // The kernel interface is gone, for example the uksm module is
+    // unloaded. The merged pages are unmerged by the kernel then.
+    Lost(String),
+}
+
+impl std::fmt::Display for KernelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            KernelError::TaskGone(pid) => write!(f, "task {} is gone", pid),
+            KernelError::PageGone(pid, addr) => write!(f, "page {} 0x{:x} is gone", pid, addr),
+            KernelError::Rejected(pid, addr) => {
+                write!(f, "page {} 0x{:x} is rejected by the kernel", pid, addr)
+            }
+            KernelError::NoMem(estr) | KernelError::Lost(estr) => write!(f, "{}", estr),
+        }
+    }
+}
+
+impl std::error::Error for KernelError {}
+
+pub fn is_page_rejected(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<KernelError>(),
+        Some(KernelError::Rejected(_, _))
+    )
+}
+
+pub fn is_no_mem(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<KernelError>(), Some(KernelError::NoMem(_)))
+}
+
+pub fn is_kernel_lost(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<KernelError>(), Some(KernelError::Lost(_)))
+}
+
+pub fn is_task_gone(e: &anyhow::Error) -> Option<u64> {
+    match e.downcast_ref::<KernelError>() {
+        Some(KernelError::TaskGone(pid)) => Some(*pid),
+        _ => None,
+    }
+}
+
+pub fn is_page_gone(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<KernelError>(),
+        Some(KernelError::PageGone(_, _))
+    )
+}
+
+// Convert the error of a write of cmd about pas to path.
+// ESRCH means the task is gone and EFAULT means the page is gone.
+// ENOMEM means the kernel is short of memory.
+fn write_error(e: std::io::Error, path: &str, cmd: &str, pas: &[&PidAddr]) -> anyhow::Error {
+    match e.raw_os_error() {
+        Some(libc::ESRCH) => {
+            if let Some(pa) = pas
+                .iter()
+                .rev()
+                .find(|pa| proc::pid_is_available(pa.pid).is_err())
+            {
+                return anyhow::Error::new(KernelError::TaskGone(pa.pid));
+            }
+        }
+        Some(libc::EFAULT) => {
+            if let Some(pa) = pas.last() {
+                return anyhow::Error::new(KernelError::PageGone(pa.pid, pa.addr));
+            }
+        }
+        Some(libc::ENOMEM) => {
+            return anyhow::Error::new(KernelError::NoMem(format!(
+                "write file {} {} failed: {}",
+                path, cmd, e
+            )));
+        }
+        // The file is gone, write_reopen failed to open it again.
+        Some(libc::ENOENT) => {
+            return anyhow::Error::new(KernelError::Lost(format!(
+                "write file {} {} failed: {}",
+                path, cmd, e
+            )));
+        }
+        _ => {}
+    }
+
+    anyhow!("write file {} {} failed: {}", path, cmd, e)
+}
+
 #[repr(C)]
 struct KerneluKSMPagemapEntry {
     pme: u64,
@@ -50,26 +308,71 @@ const UKSM_CRC_PRESENT: u64 = 1 << 63;
 const UKSM_PM_THP: u64 = 1 << 62;
 const UKSM_PM_KSM: u64 = 1 << 61;
 
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct UKSMPagemapEntry {
     pub pfn: u64,
     pub crc: u32,
     pub is_thp: bool,
     pub is_ksm: bool,
+    // If the page is not accessed since the last refresh, None if unknown.
+    pub idle: Option<bool>,
 }
 
-pub fn read_uksm_pagemap(pid: u64, start: u64, end: u64) -> Result<Vec<Option<UKSMPagemapEntry>>> {
-    let mut file = File::open(format!("/proc/{}/uksm_pagemap", pid))
-        .map_err(|e| anyhow!("File::open failed: {}", e))?;
+// Read buf from file until it is full or EOF, a short read is continued
+// so that a torn entry is read again. Return the bytes read.
+fn read_full<R: Read>(file: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match file.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+// Read the entries of [start, end) chunk_pages by chunk_pages and call f
+// with the address and the entries of each chunk.
+// The map might be shrunk after it is got from smaps, so the pages after
+// EOF are handled as absent.
+fn read_uksm_pagemap_chunked(
+    pid: u64,
+    start: u64,
+    end: u64,
+    chunk_pages: u64,
+    f: &mut backend::PagemapChunkFn,
+) -> Result<()> {
+    let mut file =
+        File::open(paths::proc(&format!("{}/uksm_pagemap", pid))).map_err(|e| {
+            match e.raw_os_error() {
+                Some(libc::ENOENT) | Some(libc::ESRCH) => {
+                    anyhow::Error::new(KernelError::TaskGone(pid))
+                }
+                _ => anyhow!("File::open failed: {}", e),
+            }
+        })?;
 
     let start_page_index = start / *page::PAGE_SIZE;
     let end_page_index = end / *page::PAGE_SIZE;
     let mut current_page_index = start_page_index;
 
-    let mut buffer = vec![0; (256 * UKSM_PAGEMAP_ENTRY_SIZE) as usize];
+    let mut buffer = vec![0; (chunk_pages * UKSM_PAGEMAP_ENTRY_SIZE) as usize];
 
-    let mut entries = Vec::new();
+    let mut entries = Vec::with_capacity(chunk_pages as usize);
+    let mut eof = false;
     while current_page_index < end_page_index {
-        let entries_to_read = std::cmp::min(256, end_page_index - current_page_index);
+        let entries_to_read = std::cmp::min(chunk_pages, end_page_index - current_page_index);
+        entries.clear();
+        if eof {
+            entries.resize(entries_to_read as usize, None);
+            f(current_page_index * *page::PAGE_SIZE, &mut entries)?;
+            current_page_index += entries_to_read;
+            continue;
+        }
+
         let bytes_to_read = entries_to_read * UKSM_PAGEMAP_ENTRY_SIZE;
         file.seek(SeekFrom::Start(
             current_page_index * UKSM_PAGEMAP_ENTRY_SIZE,
@@ -81,18 +384,23 @@ pub fn read_uksm_pagemap(pid: u64, start: u64, end: u64) -> Result<Vec<Option<UK
                 e
             )
         })?;
-        file.read_exact(&mut buffer[0..(entries_to_read * UKSM_PAGEMAP_ENTRY_SIZE) as usize])
-            .map_err(|e| {
+        let bytes_read =
+            read_full(&mut file, &mut buffer[0..bytes_to_read as usize]).map_err(|e| {
                 anyhow!(
-                    "file.read_exact {} {} failed: {}",
+                    "read_full {} {} failed: {}",
                     current_page_index * UKSM_PAGEMAP_ENTRY_SIZE,
-                    entries_to_read * UKSM_PAGEMAP_ENTRY_SIZE,
+                    bytes_to_read,
                     e
                 )
             })?;
+        if bytes_read < bytes_to_read as usize {
+            eof = true;
+        }
+        // Drop the torn entry at EOF.
+        let bytes_read = bytes_read - bytes_read % UKSM_PAGEMAP_ENTRY_SIZE as usize;
 
         let mut index: usize = 0;
-        while index < bytes_to_read as usize {
+        while index < bytes_read {
             let pme_bytes: [u8; 8] = buffer[index..(index + 8)]
                 .try_into()
                 .expect("Expected 8 bytes");
@@ -110,158 +418,1874 @@ pub fn read_uksm_pagemap(pid: u64, start: u64, end: u64) -> Result<Vec<Option<UK
                     crc: (uksm_pme & UKSM_CRC_MASK) as u32,
                     is_thp: uksm_pme & UKSM_PM_THP != 0,
                     is_ksm: uksm_pme & UKSM_PM_KSM != 0,
+                    idle: None,
                 }));
             }
 
             index += UKSM_PAGEMAP_ENTRY_SIZE as usize;
         }
+        entries.resize(entries_to_read as usize, None);
+        f(current_page_index * *page::PAGE_SIZE, &mut entries)?;
         current_page_index += entries_to_read;
     }
 
-    Ok(entries)
+    Ok(())
 }
 
-fn merge_pages(pa1: &PidAddr, pa2: &PidAddr) -> Result<bool> {
-    let cmd = format!("{} 0x{:x} {} 0x{:x}", pa1.pid, pa1.addr, pa2.pid, pa2.addr);
+// How to retry the writes that fail with EAGAIN or EBUSY.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    // The delay before the first retry, it is doubled for each retry.
+    pub delay: Duration,
+}
 
-    let mut cmp_file = OpenOptions::new()
-        .write(true)
-        .open(CMP_PATH)
-        .map_err(|e| anyhow!("open file {} failed: {}", CMP_PATH, e))?;
+impl RetryPolicy {
+    // Retry write with backoff if the kernel is busy, the retries are
+    // counted to retried.
+    fn run<F>(&self, retried: &AtomicU64, mut write: F) -> std::io::Result<usize>
+    where
+        F: FnMut() -> std::io::Result<usize>,
+    {
+        let mut delay = self.delay;
+        let mut tries = 0;
 
-    if let Err(e) = cmp_file.write_all(cmd.as_bytes()) {
-        if let Some(errno) = e.raw_os_error() {
-            if errno == EPAGESNOTSAME {
-                return Ok(false);
+        loop {
+            match write() {
+                Err(e)
+                    if matches!(e.raw_os_error(), Some(libc::EAGAIN) | Some(libc::EBUSY))
+                        && tries < self.retries =>
+                {
+                    tries += 1;
+                    retried.fetch_add(1, Ordering::Relaxed);
+                    thread::sleep(delay);
+                    delay *= 2;
+                }
+                ret => return ret,
             }
         }
-        return Err(anyhow!("cmp_file.write_all {} failed: {}", cmd, e));
     }
+}
 
-    drop(cmp_file);
+// A /proc/uksm file that is kept open across the writes.
+#[derive(Debug)]
+struct KernelFile {
+    path: &'static str,
+    file: Mutex<Option<File>>,
+    retry: RetryPolicy,
+    // number of the retried writes
+    retried: AtomicU64,
+}
 
-    let mut merge_file = OpenOptions::new()
-        .write(true)
-        .open(MERGE_PATH)
-        .map_err(|e| anyhow!("open file {} failed: {}", MERGE_PATH, e))?;
+impl KernelFile {
+    fn new(path: &'static str, retry: RetryPolicy) -> Self {
+        Self {
+            path,
+            file: Mutex::new(None),
+            retry,
+            retried: AtomicU64::new(0),
+        }
+    }
+
+    fn write_once(&self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut file = self.file.lock().unwrap();
+        let file = match file.as_mut() {
+            Some(file) => file,
+            None => file.insert(OpenOptions::new().write(true).open(self.path)?),
+        };
+
+        file.write_at(buf, 0)
+    }
 
-    if let Err(e) = merge_file.write_all(cmd.as_bytes()) {
-        if let Some(errno) = e.raw_os_error() {
-            if errno == EPAGESNOTSAME {
-                return Ok(false);
+    // Open the file when it is first used. Reopen it and try again if the
+    // old handle is not usable, for example the kernel module was reloaded.
+    fn write_reopen(&self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.write_once(buf) {
+            Err(e) if matches!(e.raw_os_error(), Some(libc::EBADF) | Some(libc::ENOENT)) => {
+                warn!("reopen file {} because write failed: {}", self.path, e);
+                *self.file.lock().unwrap() = None;
+                self.write_once(buf)
             }
+            ret => ret,
         }
-        return Err(anyhow!("merge_file.write_all {} failed: {}", cmd, e));
     }
 
-    Ok(true)
+    // Retry the write with backoff if the kernel is busy.
+    // Return the number of the bytes that the kernel handled.
+    fn write_some(&self, buf: &[u8]) -> std::io::Result<usize> {
+        self.retry.run(&self.retried, || self.write_reopen(buf))
+    }
+
+    fn write(&self, cmd: &str) -> std::io::Result<()> {
+        let n = self.write_some(cmd.as_bytes())?;
+        if n != cmd.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::WriteZero,
+                format!("short write {} of {}", n, cmd.len()),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// The backend that uses the interface of the uksm kernel patches.
+#[derive(Debug)]
+pub struct UksmBackend {
+    caps: KernelCaps,
+    cmp: KernelFile,
+    merge: KernelFile,
+    unmerge: KernelFile,
+    // pages read from uksm_pagemap in a read
+    chunk_pages: u64,
 }
 
-fn unmerge_pages(pa: &PidAddr) -> Result<()> {
-    let cmd = format!("{} 0x{:x}", pa.pid, pa.addr);
+impl UksmBackend {
+    pub fn new(retry: RetryPolicy, chunk_pages: u64) -> Self {
+        let caps = KernelCaps::probe();
+        info!(
+            "uksm kernel version {} interfaces {:?}",
+            caps.version.as_deref().unwrap_or("unknown"),
+            caps.names()
+        );
 
-    let mut file = OpenOptions::new()
-        .write(true)
-        .open(UNMERGE_PATH)
-        .map_err(|e| anyhow!("open file {} failed: {}", UNMERGE_PATH, e))?;
+        Self {
+            caps,
+            cmp: KernelFile::new(&CMP_PATH, retry),
+            merge: KernelFile::new(&MERGE_PATH, retry),
+            unmerge: KernelFile::new(&UNMERGE_PATH, retry),
+            chunk_pages: chunk_pages.max(1),
+        }
+    }
+}
 
-    file.write_all(cmd.as_bytes())
-        .map_err(|e| anyhow!("write_all file {} {} failed: {}", UNMERGE_PATH, cmd, e))?;
+impl backend::KernelOps for UksmBackend {
+    fn name(&self) -> &'static str {
+        "uksm"
+    }
 
-    Ok(())
+    fn read_pagemap_chunked(
+        &self,
+        pid: u64,
+        start: u64,
+        end: u64,
+        f: &mut backend::PagemapChunkFn,
+    ) -> Result<()> {
+        read_uksm_pagemap_chunked(pid, start, end, self.chunk_pages, f)
+    }
+
+    fn retried(&self) -> u64 {
+        self.cmp.retried.load(Ordering::Relaxed)
+            + self.merge.retried.load(Ordering::Relaxed)
+            + self.unmerge.retried.load(Ordering::Relaxed)
+    }
+
+    fn caps(&self) -> Vec<String> {
+        self.caps.names()
+    }
+
+    fn available(&self) -> bool {
+        check_kernel().is_ok()
+    }
+
+    fn version(&self) -> Option<String> {
+        self.caps.version.clone()
+    }
+
+    // Some versions of the kernel patches don't have lru_add_drain_all.
+    fn lru_drain(&self) -> Result<()> {
+        if !self.caps.lru_add_drain_all {
+            return Ok(());
+        }
+
+        lru_add_drain_all()
+    }
+
+    fn can_lru_drain(&self) -> bool {
+        self.caps.lru_add_drain_all
+    }
+
+    // Merge also compares the pages, cmp just avoids the merge of the
+    // pages that are not the same if it exists.
+    fn cmp_merge(&self, pa1: &PidAddr, pa2: &PidAddr) -> Result<bool> {
+        let cmd = format!("{} 0x{:x} {} 0x{:x}", pa1.pid, pa1.addr, pa2.pid, pa2.addr);
+
+        // The command is well formed, EINVAL means the kernel doesn't merge
+        // the new page.
+        if self.caps.cmp {
+            if let Err(e) = self.cmp.write(&cmd) {
+                match e.raw_os_error() {
+                    Some(EPAGESNOTSAME) => return Ok(false),
+                    Some(libc::EINVAL) => {
+                        return Err(anyhow::Error::new(KernelError::Rejected(pa2.pid, pa2.addr)))
+                    }
+                    _ => {}
+                }
+                return Err(write_error(e, &CMP_PATH, &cmd, &[pa1, pa2]));
+            }
+        }
+
+        if let Err(e) = self.merge.write(&cmd) {
+            match e.raw_os_error() {
+                Some(EPAGESNOTSAME) => return Ok(false),
+                Some(libc::EINVAL) => {
+                    return Err(anyhow::Error::new(KernelError::Rejected(pa2.pid, pa2.addr)))
+                }
+                _ => {}
+            }
+            return Err(write_error(e, &MERGE_PATH, &cmd, &[pa1, pa2]));
+        }
+
+        Ok(true)
+    }
+
+    // The kernel that supports batch merge accepts the empty commands.
+    fn probe_batch(&self) -> bool {
+        self.merge.write("\n\n").is_ok()
+    }
+
+    // Write the merge commands of pairs in one write.
+    // The kernel handles the commands in order and stops at the first
+    // one that fails, so the handled bytes tell which pairs are merged.
+    // Return if each pair is merged.
+    fn cmp_merge_batch(&self, pairs: &[(PidAddr, PidAddr)]) -> Result<Vec<bool>> {
+        let mut buf = String::new();
+        let mut ends = Vec::with_capacity(pairs.len());
+        for (pa1, pa2) in pairs {
+            buf.push_str(&format!(
+                "{} 0x{:x} {} 0x{:x}\n",
+                pa1.pid, pa1.addr, pa2.pid, pa2.addr
+            ));
+            ends.push(buf.len());
+        }
+
+        let mut merged = Vec::with_capacity(pairs.len());
+        let mut pos = 0;
+        while merged.len() < pairs.len() {
+            let index = merged.len();
+            match self.merge.write_some(&buf.as_bytes()[pos..]) {
+                Ok(n) => {
+                    let done = ends[index..]
+                        .iter()
+                        .take_while(|end| **end <= pos + n)
+                        .count();
+                    if done == 0 {
+                        return Err(anyhow!(
+                            "write file {} handled {} bytes of {}",
+                            *MERGE_PATH,
+                            n,
+                            &buf[pos..ends[index]]
+                        ));
+                    }
+                    merged.resize(index + done, true);
+                    pos = ends[index + done - 1];
+                }
+                Err(e) => {
+                    // Let the failed pair be merged again one by one to
+                    // handle the task or page that is gone.
+                    if !matches!(
+                        e.raw_os_error(),
+                        Some(EPAGESNOTSAME)
+                            | Some(libc::ESRCH)
+                            | Some(libc::EFAULT)
+                            | Some(libc::EINVAL)
+                    ) {
+                        return Err(write_error(e, &MERGE_PATH, &buf[pos..ends[index]], &[]));
+                    }
+                    // Skip the command that failed.
+                    merged.push(false);
+                    pos = ends[index];
+                }
+            }
+        }
+
+        Ok(merged)
+    }
+
+    fn unmerge(&self, pa: &PidAddr) -> Result<()> {
+        let cmd = format!("{} 0x{:x}", pa.pid, pa.addr);
+
+        self.unmerge
+            .write(&cmd)
+            .map_err(|e| write_error(e, &UNMERGE_PATH, &cmd, &[pa]))?;
+
+        Ok(())
+    }
 }
 
-#[derive(Debug, Clone)]
-struct PidAddr {
-    pid: u64,
-    addr: u64,
+// Which pages can be merged.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MergeScope {
+    #[default]
+    Any,
+    // Only merge the pages of the different tasks.
+    CrossTaskOnly,
+    // Only merge the pages of the same task.
+    SameTaskOnly,
+}
+
+impl FromStr for MergeScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "any" => Ok(MergeScope::Any),
+            "cross_task_only" => Ok(MergeScope::CrossTaskOnly),
+            "same_task_only" => Ok(MergeScope::SameTaskOnly),
+            _ => Err(anyhow!("merge scope {} is not supported", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for MergeScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            MergeScope::Any => "any",
+            MergeScope::CrossTaskOnly => "cross_task_only",
+            MergeScope::SameTaskOnly => "same_task_only",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// If the page of pid can be merged with the group pages by scope.
+fn scope_allowed(scope: MergeScope, pages: &[PidAddr], pid: u64) -> bool {
+    match scope {
+        MergeScope::Any => true,
+        MergeScope::CrossTaskOnly => pages.iter().any(|page| page.pid != pid),
+        MergeScope::SameTaskOnly => pages.iter().all(|page| page.pid == pid),
+    }
+}
+
+// If the pages of pid1 and pid2 can be merged by their uids.
+// The pages of a task that has unknown uid are not blocked.
+fn uid_allowed(uids: &HashMap<u64, u32>, pid1: u64, pid2: u64) -> bool {
+    match (uids.get(&pid1), uids.get(&pid2)) {
+        (Some(uid1), Some(uid2)) => uid1 == uid2,
+        _ => true,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PidAddr {
+    pub pid: u64,
+    pub addr: u64,
+}
+
+// Where a merged page is in Uksm.pages.
+#[derive(Debug, Clone, Copy)]
+struct PageLoc {
+    crc: u32,
+    group: usize,
+    pos: usize,
+    // the sequence number of the add of the page, it is kept when the page
+    // is moved
+    seq: u64,
+    // the frame of the page if the kernel reports it as a ksm page or the
+    // page may share its frame with a forked task, 0 if unknown, it is kept
+    // when the page is moved
+    pfn: u64,
+    // the page joins the group without a merge and its frame is not a ksm
+    // page, the frame is shared by the forked tasks, it is kept when the
+    // page is moved
+    cow: bool,
+}
+
+// How an add of a page went.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AddOutcome {
+    pub merged: bool,
+    // the crc of the page has the groups already
+    pub matched: bool,
+    // number of the groups that the page is compared with
+    pub probes: usize,
+}
+
+// The outcomes of the adds of the pages whose crc has the groups already.
+// The pages that are not compared because of the uid, the scope or a shared
+// frame are only counted in matched.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProbeCounts {
+    pub matched: u64,
+    // merged with the first group that is compared
+    pub first_probe: u64,
+    // merged after the other groups are compared
+    pub multi_probe: u64,
+    // not the same as any group that is compared, a crc collision
+    pub exhausted: u64,
 }
 
-#[derive(Debug, Clone)]
+impl ProbeCounts {
+    pub fn record(&mut self, outcome: &AddOutcome) {
+        if !outcome.matched {
+            return;
+        }
+        self.matched += 1;
+        match (outcome.merged, outcome.probes) {
+            (_, 0) => {}
+            (true, 1) => self.first_probe += 1,
+            (true, _) => self.multi_probe += 1,
+            (false, _) => self.exhausted += 1,
+        }
+    }
+
+    pub fn add(&mut self, other: &ProbeCounts) {
+        self.matched += other.matched;
+        self.first_probe += other.first_probe;
+        self.multi_probe += other.multi_probe;
+        self.exhausted += other.exhausted;
+    }
+
+    pub fn since(&self, before: &ProbeCounts) -> ProbeCounts {
+        ProbeCounts {
+            matched: self.matched.saturating_sub(before.matched),
+            first_probe: self.first_probe.saturating_sub(before.first_probe),
+            multi_probe: self.multi_probe.saturating_sub(before.multi_probe),
+            exhausted: self.exhausted.saturating_sub(before.exhausted),
+        }
+    }
+}
+
+impl std::fmt::Display for ProbeCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "crc matched {} (first probe {} more probes {} collisions {})",
+            self.matched, self.first_probe, self.multi_probe, self.exhausted
+        )
+    }
+}
+
+#[derive(Debug)]
 pub struct Uksm {
     pages: HashMap<u32, Vec<Vec<PidAddr>>>,
+
+    // pid -> addr -> location in pages, to remove a page without scanning
+    // the groups of its crc.
+    index: HashMap<u64, HashMap<u64, PageLoc>>,
+
+    // (crc, pfn) -> the groups of crc that have the pages in the frame pfn
+    // and the number of the pages of each, to find the group of a frame
+    // without scanning the groups. The pages of an unknown frame are not
+    // in it.
+    frames: HashMap<(u32, u64), Vec<(usize, usize)>>,
+
+    // (crc, group) -> number of the cow pages of the group, to get the
+    // saving of a group without scanning it.
+    cows: HashMap<(u32, usize), usize>,
+
+    // max groups that are tried to merge a page, 0 means unlimited
+    merge_attempts: usize,
+
+    // number of the adds that stopped trying after merge_attempts groups
+    give_ups: u64,
+
+    // max merge commands in a write, 1 means one write per pair
+    merge_batch: usize,
+
+    // number of the merges that are deferred because of ENOMEM
+    deferred: u64,
+
+    // pid -> uid, the page is not merged with the pages of another uid
+    // unless allow_cross_uid_merge is set
+    uids: HashMap<u64, u32>,
+    allow_cross_uid_merge: bool,
+
+    // number of the merges that are blocked because of the different uids
+    cross_uid_blocked: u64,
+
+    merge_scope: MergeScope,
+
+    // number of the groups that are not tried because of merge_scope
+    scope_skipped: u64,
+
+    // the last sequence number of the added pages
+    seq: u64,
+
+    // number of the pairs of pages that are written to the kernel to merge
+    // and the ones that are merged
+    compares: u64,
+    compares_merged: u64,
+
+    // number of the pages that are added to a group without a merge because
+    // the kernel merged them into the frame of the group already
+    shared_frames: u64,
+
+    // the pages that are compared with a page of the same crc and are not
+    // the same, recorded if max_merge_failures is set
+    not_same: Vec<(PidAddr, u32)>,
+    track_not_same: bool,
+
+    // the pages that the kernel rejects to merge
+    rejected: Vec<PidAddr>,
+
+    backend: Arc<dyn backend::KernelOps>,
+}
+
+// The merged pages that are kept in the state file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UksmState {
+    pages: HashMap<u32, Vec<Vec<PidAddr>>>,
+    // the pages whose frames are known, their frames and if they share the
+    // frames with the forked tasks
+    #[serde(default)]
+    frames: Vec<(PidAddr, u64, bool)>,
 }
 
 impl Uksm {
-    pub fn new() -> Self {
+    pub fn new(config: &config::Config, backend: Arc<dyn backend::KernelOps>) -> Self {
+        let merge_batch = if config.merge_batch > 1 && backend.probe_batch() {
+            info!("merge {} pages in a write", config.merge_batch);
+            config.merge_batch
+        } else {
+            1
+        };
+
         Self {
             pages: HashMap::new(),
+            index: HashMap::new(),
+            frames: HashMap::new(),
+            cows: HashMap::new(),
+            merge_attempts: config.merge_attempts,
+            give_ups: 0,
+            merge_batch,
+            deferred: 0,
+            uids: HashMap::new(),
+            allow_cross_uid_merge: config.allow_cross_uid_merge,
+            cross_uid_blocked: 0,
+            merge_scope: config.merge_scope,
+            scope_skipped: 0,
+            seq: 0,
+            compares: 0,
+            compares_merged: 0,
+            shared_frames: 0,
+            not_same: Vec::new(),
+            track_not_same: config.max_merge_failures != 0,
+            rejected: Vec::new(),
+            backend,
+        }
+    }
+
+    // Only keep the pages of the pids in alive.
+    pub fn load_state(&mut self, state: UksmState, alive: &HashSet<u64>) {
+        let mut pages = state.pages;
+
+        for pagesvec in pages.values_mut() {
+            for pages in pagesvec.iter_mut() {
+                pages.retain(|page| alive.contains(&page.pid));
+            }
+            pagesvec.retain(|pages| !pages.is_empty());
+        }
+        pages.retain(|_, pagesvec| !pagesvec.is_empty());
+
+        self.index.clear();
+        self.frames.clear();
+        self.cows.clear();
+        for (crc, pagesvec) in pages.iter() {
+            for (group, pages) in pagesvec.iter().enumerate() {
+                for (pos, page) in pages.iter().enumerate() {
+                    self.index.entry(page.pid).or_default().insert(
+                        page.addr,
+                        PageLoc {
+                            crc: *crc,
+                            group,
+                            pos,
+                            seq: 0,
+                            pfn: 0,
+                            cow: false,
+                        },
+                    );
+                }
+            }
+        }
+        self.pages = pages;
+
+        for (page, pfn, cow) in state.frames {
+            if let Some(loc) = self
+                .index
+                .get_mut(&page.pid)
+                .and_then(|addrs| addrs.get_mut(&page.addr))
+            {
+                loc.pfn = pfn;
+                loc.cow = cow;
+                let loc = *loc;
+                self.link_frame(&loc);
+            }
         }
     }
 
-    pub fn add(&mut self, pid: u64, addr: u64, entry: &page::PageEntry) -> Result<()> {
+    pub fn retried(&self) -> u64 {
+        self.backend.retried()
+    }
+
+    pub fn deferred(&self) -> u64 {
+        self.deferred
+    }
+
+    pub fn cross_uid_blocked(&self) -> u64 {
+        self.cross_uid_blocked
+    }
+
+    pub fn merge_scope(&self) -> MergeScope {
+        self.merge_scope
+    }
+
+    pub fn set_merge_scope(&mut self, scope: MergeScope) {
+        self.merge_scope = scope;
+    }
+
+    pub fn scope_skipped(&self) -> u64 {
+        self.scope_skipped
+    }
+
+    pub fn set_uid(&mut self, pid: u64, uid: Option<u32>) {
+        match uid {
+            Some(uid) => self.uids.insert(pid, uid),
+            None => self.uids.remove(&pid),
+        };
+    }
+
+    pub fn del_uid(&mut self, pid: u64) {
+        self.uids.remove(&pid);
+    }
+
+    pub fn merge_batched(&self) -> bool {
+        self.merge_batch > 1
+    }
+
+    // If the page is in a group with the other pages.
+    pub fn is_merged(&self, pid: u64, addr: u64) -> bool {
+        let loc = match self.index.get(&pid).and_then(|addrs| addrs.get(&addr)) {
+            Some(loc) => loc,
+            None => return false,
+        };
+
+        self.pages
+            .get(&loc.crc)
+            .and_then(|pagesvec| pagesvec.get(loc.group))
+            .map(|pages| pages.len() > 1)
+            .unwrap_or(false)
+    }
+
+    pub fn contains(&self, pid: u64, addr: u64) -> bool {
+        self.index
+            .get(&pid)
+            .map(|addrs| addrs.contains_key(&addr))
+            .unwrap_or(false)
+    }
+
+    pub fn give_ups(&self) -> u64 {
+        self.give_ups
+    }
+
+    // The groups that only have one page are not kept because the page
+    // is not merged.
+    pub fn to_state(&self) -> UksmState {
+        let mut pages = self.pages.clone();
+        for pagesvec in pages.values_mut() {
+            pagesvec.retain(|pages| pages.len() > 1);
+        }
+        pages.retain(|_, pagesvec| !pagesvec.is_empty());
+
+        let frames = self
+            .index
+            .iter()
+            .flat_map(|(pid, addrs)| {
+                addrs
+                    .iter()
+                    .filter(|(_, loc)| loc.pfn != 0)
+                    .map(|(addr, loc)| {
+                        (
+                            PidAddr {
+                                pid: *pid,
+                                addr: *addr,
+                            },
+                            loc.pfn,
+                            loc.cow,
+                        )
+                    })
+            })
+            .collect();
+
+        UksmState { pages, frames }
+    }
+
+    // Count a page of loc in its frame and the cow pages of its group.
+    fn link_frame(&mut self, loc: &PageLoc) {
+        if loc.cow {
+            *self.cows.entry((loc.crc, loc.group)).or_default() += 1;
+        }
+        if loc.pfn == 0 {
+            return;
+        }
+        let groups = self.frames.entry((loc.crc, loc.pfn)).or_default();
+        match groups.iter_mut().find(|(group, _)| *group == loc.group) {
+            Some((_, count)) => *count += 1,
+            None => groups.push((loc.group, 1)),
+        }
+    }
+
+    fn unlink_frame(&mut self, loc: &PageLoc) {
+        if loc.cow {
+            let key = (loc.crc, loc.group);
+            if let Some(count) = self.cows.get_mut(&key) {
+                *count -= 1;
+                if *count == 0 {
+                    self.cows.remove(&key);
+                }
+            }
+        }
+        if loc.pfn == 0 {
+            return;
+        }
+        let key = (loc.crc, loc.pfn);
+        if let Some(groups) = self.frames.get_mut(&key) {
+            if let Some(i) = groups.iter().position(|(group, _)| *group == loc.group) {
+                groups[i].1 -= 1;
+                if groups[i].1 == 0 {
+                    groups.swap_remove(i);
+                }
+            }
+            if groups.is_empty() {
+                self.frames.remove(&key);
+            }
+        }
+    }
+
+    // Set the location of page, a new page gets a new sequence number.
+    // A moved page keeps its sequence number and frame.
+    fn set_loc(&mut self, page: &PidAddr, mut loc: PageLoc) {
+        let old = self
+            .index
+            .get(&page.pid)
+            .and_then(|addrs| addrs.get(&page.addr))
+            .copied();
+        match old {
+            Some(old) => {
+                loc.seq = old.seq;
+                loc.pfn = old.pfn;
+                loc.cow = old.cow;
+                self.unlink_frame(&old);
+            }
+            None => {
+                self.seq += 1;
+                loc.seq = self.seq;
+            }
+        }
+        self.link_frame(&loc);
+        self.index
+            .entry(page.pid)
+            .or_default()
+            .insert(page.addr, loc);
+    }
+
+    // Record the frame of a page in uksm, 0 if it is unknown. A page whose
+    // frame is a ksm page doesn't share a frame of the forked tasks.
+    pub fn set_pfn(&mut self, pid: u64, addr: u64, pfn: u64, ksm: bool) {
+        let (old, new) = match self
+            .index
+            .get_mut(&pid)
+            .and_then(|addrs| addrs.get_mut(&addr))
+        {
+            Some(loc) => {
+                let old = *loc;
+                loc.pfn = pfn;
+                if ksm {
+                    loc.cow = false;
+                }
+                (old, *loc)
+            }
+            None => return,
+        };
+        self.unlink_frame(&old);
+        self.link_frame(&new);
+    }
+
+    // If the page that is not a ksm page is still in the frame pfn that
+    // another page of its group is in, for example the forked tasks share
+    // the frame.
+    pub fn frame_shared(&self, pid: u64, addr: u64, pfn: u64) -> bool {
+        let loc = match self.index.get(&pid).and_then(|addrs| addrs.get(&addr)) {
+            Some(loc) if pfn != 0 && loc.pfn == pfn => loc,
+            _ => return false,
+        };
+
+        self.frames
+            .get(&(loc.crc, pfn))
+            .and_then(|groups| groups.iter().find(|(group, _)| *group == loc.group))
+            .is_some_and(|(_, count)| *count > 1)
+    }
+
+    // The pages that the merges of the group of crc save, the pages that
+    // share a frame of the forked tasks save nothing.
+    fn group_saved(&self, crc: u32, group: usize, len: usize) -> usize {
+        let cows = self.cows.get(&(crc, group)).copied().unwrap_or(0);
+        len.saturating_sub(1).saturating_sub(cows)
+    }
+
+    // The group of crc that has a page in the frame pfn, a page in the
+    // frame is merged with the group already.
+    fn shared_group(&self, crc: u32, pfn: u64) -> Option<usize> {
+        if pfn == 0 {
+            return None;
+        }
+        self.frames
+            .get(&(crc, pfn))?
+            .iter()
+            .map(|(group, _)| *group)
+            .min()
+    }
+
+    // Add the page that shares the frame of group to it without a merge.
+    fn add_shared(&mut self, page: PidAddr, entry: &page::PageEntry, group: usize) {
+        if let Some(pages) = self
+            .pages
+            .get_mut(&entry.crc)
+            .and_then(|pagesvec| pagesvec.get_mut(group))
+        {
+            let loc = PageLoc {
+                crc: entry.crc,
+                group,
+                pos: pages.len(),
+                seq: 0,
+                pfn: entry.pfn,
+                cow: entry.cow,
+            };
+            pages.push(page.clone());
+            self.set_loc(&page, loc);
+            self.shared_frames += 1;
+        }
+    }
+
+    // Number of the pages that are added without a merge because they
+    // share the frame of their groups.
+    pub fn shared_frames(&self) -> u64 {
+        self.shared_frames
+    }
+
+    // The approximate bytes of the groups and the index of the merged pages.
+    pub fn mem_bytes(&self) -> u64 {
+        let pages: usize = self.index.values().map(|addrs| addrs.len()).sum();
+        let entry = 2 * std::mem::size_of::<u64>()
+            + std::mem::size_of::<PidAddr>()
+            + std::mem::size_of::<PageLoc>();
+        let frame = std::mem::size_of::<(u32, u64)>() + std::mem::size_of::<(usize, usize)>();
+        (pages * entry + self.frames.len() * frame) as u64
+    }
+
+    // The pages that the merges save, a group of n pages saves n - 1 pages
+    // and a frame that the forked tasks share is counted once.
+    pub fn saved_total(&self) -> u64 {
+        self.pages
+            .iter()
+            .flat_map(|(crc, pagesvec)| {
+                pagesvec
+                    .iter()
+                    .enumerate()
+                    .map(|(group, pages)| self.group_saved(*crc, group, pages.len()) as u64)
+            })
+            .sum()
+    }
+
+    // The merged pages of each crc.
+    pub fn crc_pages(&self) -> Vec<(u32, u64)> {
+        self.pages
+            .iter()
+            .map(|(crc, groups)| (*crc, groups.iter().map(|g| g.len() as u64).sum()))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    // The addrs of the pages of pid.
+    pub fn pid_addrs(&self, pid: u64) -> Vec<u64> {
+        self.index
+            .get(&pid)
+            .map_or_else(Vec::new, |addrs| addrs.keys().copied().collect())
+    }
+
+    // Estimate the pages of pid in addrs that are saved by the merges, a
+    // group of n pages saves n - 1 pages less its shared frames of the
+    // forked tasks and each page of it shares the saving.
+    // The saving of a group is counted once for all the pages of pid in it.
+    pub fn saved_pages(&self, pid: u64, addrs: &[u64]) -> f64 {
+        let index = match self.index.get(&pid) {
+            Some(index) => index,
+            None => return 0.0,
+        };
+        // (crc, group) -> number of the pages of pid in the group
+        let mut counts: HashMap<(u32, usize), usize> = HashMap::new();
+        for loc in addrs.iter().filter_map(|addr| index.get(addr)) {
+            *counts.entry((loc.crc, loc.group)).or_default() += 1;
+        }
+
+        counts
+            .iter()
+            .filter_map(|((crc, group), count)| {
+                let len = self.pages.get(crc)?.get(*group)?.len();
+                (len > 1).then(|| (self.group_saved(*crc, *group, len) * count) as f64 / len as f64)
+            })
+            .sum()
+    }
+
+    // Return the merged pages that are added last, at most n.
+    pub fn recent_merged(&self, n: usize) -> Vec<PidAddr> {
+        let mut pages: Vec<(u64, PidAddr)> = self
+            .index
+            .iter()
+            .flat_map(|(pid, addrs)| {
+                addrs.iter().map(move |(addr, loc)| {
+                    (
+                        loc.seq,
+                        PidAddr {
+                            pid: *pid,
+                            addr: *addr,
+                        },
+                    )
+                })
+            })
+            .filter(|(_, page)| self.is_merged(page.pid, page.addr))
+            .collect();
+        pages.sort_unstable_by_key(|(seq, _)| std::cmp::Reverse(*seq));
+        pages.truncate(n);
+
+        pages.into_iter().map(|(_, page)| page).collect()
+    }
+
+    // Return if the page is merged with another page and how many groups it
+    // is compared with.
+    // A page that doesn't match any group starts a new group and waits
+    // for the same pages.
+    pub fn add(&mut self, pid: u64, addr: u64, entry: &page::PageEntry) -> Result<AddOutcome> {
+        let _timer = timing::start(timing::Op::UksmAdd);
+        loop {
+            match self.try_add(pid, addr, entry) {
+                Err(e) => match is_task_gone(&e) {
+                    // A task that has pages in uksm is gone, drop its pages
+                    // and try again.
+                    Some(gone) if gone != pid => {
+                        // Drop the pages before info, its args are not
+                        // evaluated if the level is off.
+                        let count = self.remove_pid(gone);
+                        info!("drop {} merged pages of gone task {}", count, gone);
+                    }
+                    _ => return Err(e),
+                },
+                ret => return ret,
+            }
+        }
+    }
+
+    // Add a page and record the page that is gone to gone. The page that
+    // the kernel rejects is recorded to rejected.
+    // Return the outcome of the add if the page is added.
+    fn add_or_gone(
+        &mut self,
+        pid: u64,
+        addr: u64,
+        entry: &page::PageEntry,
+        gone: &mut Vec<u64>,
+    ) -> Result<Option<AddOutcome>> {
+        match self.add(pid, addr, entry) {
+            Err(e) if is_page_gone(&e) => {
+                gone.push(addr);
+                Ok(None)
+            }
+            Err(e) if is_page_rejected(&e) => {
+                self.rejected.push(PidAddr { pid, addr });
+                Ok(None)
+            }
+            ret => ret.map(Some),
+        }
+    }
+
+    fn try_add(&mut self, pid: u64, addr: u64, entry: &page::PageEntry) -> Result<AddOutcome> {
+        // The page started a group before.
+        if self.contains(pid, addr) {
+            return Ok(AddOutcome {
+                merged: self.is_merged(pid, addr),
+                ..Default::default()
+            });
+        }
+
         let new_page = PidAddr { pid, addr };
+        if let Some(group) = self.shared_group(entry.crc, entry.pfn) {
+            self.add_shared(new_page, entry, group);
+            return Ok(AddOutcome {
+                merged: true,
+                matched: true,
+                probes: 0,
+            });
+        }
 
+        let mut loc = PageLoc {
+            crc: entry.crc,
+            group: 0,
+            pos: 0,
+            seq: 0,
+            pfn: entry.pfn,
+            cow: false,
+        };
+
+        let mut merged = false;
+        let mut blocked = false;
+        let mut attempts = 0;
+        let matched = self.pages.contains_key(&entry.crc);
         if let Some(pagesvec) = self.pages.get_mut(&entry.crc) {
-            let mut merged = false;
-
-            'pagesvec: for pages in pagesvec.iter_mut() {
-                'pages: for page in pages.iter_mut() {
-                    // try to merge each pages because maybe a page in pages is updated after refresh
-                    let merge_ret = merge_pages(page, &new_page)
-                        .map_err(|e| anyhow!("merge_pages failed: {}", e))?;
-                    if merge_ret {
-                        merged = true;
-                        break 'pages;
-                    }
+            // The pages in a group are merged into the same page, so just
+            // try the first page of each group.
+            for (group, pages) in pagesvec.iter_mut().enumerate() {
+                if !self.allow_cross_uid_merge && !uid_allowed(&self.uids, pages[0].pid, pid) {
+                    blocked = true;
+                    continue;
                 }
-                if merged {
+                if !scope_allowed(self.merge_scope, pages, pid) {
+                    self.scope_skipped += 1;
+                    continue;
+                }
+                if self.merge_attempts != 0 && attempts >= self.merge_attempts {
+                    self.give_ups += 1;
+                    break;
+                }
+                attempts += 1;
+                let merge_ret = {
+                    let _timer = timing::start(timing::Op::MergePages);
+                    self.backend.cmp_merge(&pages[0], &new_page)?
+                };
+                self.compares += 1;
+                if merge_ret {
+                    self.compares_merged += 1;
+                    loc.group = group;
+                    loc.pos = pages.len();
                     pages.push(new_page.clone());
-                    break 'pagesvec;
+                    merged = true;
+                    break;
+                }
+                // The page that waits alone for the same pages failed too.
+                if self.track_not_same && pages.len() == 1 {
+                    self.not_same.push((pages[0].clone(), entry.crc));
                 }
             }
             if !merged {
-                pagesvec.push(vec![new_page]);
+                if self.track_not_same && attempts > 0 {
+                    self.not_same.push((new_page.clone(), entry.crc));
+                }
+                if blocked {
+                    self.cross_uid_blocked += 1;
+                }
+                loc.group = pagesvec.len();
+                pagesvec.push(vec![new_page.clone()]);
             }
         } else {
-            let mut pagevecs = Vec::new();
-            pagevecs.push(vec![new_page]);
-            self.pages.insert(entry.crc, pagevecs);
+            self.pages.insert(entry.crc, vec![vec![new_page.clone()]]);
         }
 
-        Ok(())
+        self.set_loc(&new_page, loc);
+
+        Ok(AddOutcome {
+            merged,
+            matched,
+            probes: attempts,
+        })
     }
 
-    pub fn remove(&mut self, pid: u64, addr: u64, crc: u32) {
-        let mut removed = false;
-        let mut should_remove_crc = false;
+    // Add the pages (addr, entry) of pid.
+    // A page that shares the frame of a group joins it without a merge.
+    // Each page is merged with the first group of its crc in a batch write,
+    // the pages that fail are added one by one.
+    // The outcomes of the adds are recorded to probes.
+    // Return the addrs of the pages that are gone.
+    pub fn add_batch(
+        &mut self,
+        pid: u64,
+        pages: &[(u64, page::PageEntry)],
+        probes: &mut ProbeCounts,
+    ) -> Result<Vec<u64>> {
+        let _timer = timing::start(timing::Op::UksmAddBatch);
+        let ret = self.try_add_batch(pid, pages, probes);
+        if let Err(e) = &ret {
+            if is_no_mem(e) {
+                self.deferred += 1;
+            }
+        }
 
-        if let Some(pagesvec) = self.pages.get_mut(&crc) {
-            let mut should_remove_empty_pages = false;
-            for pages in pagesvec.iter_mut() {
-                let origin_len = pages.len();
-                pages.retain(|page| page.pid != pid || page.addr != addr);
-                if origin_len != pages.len() {
-                    if pages.is_empty() {
-                        should_remove_empty_pages = true;
+        ret
+    }
+
+    fn try_add_batch(
+        &mut self,
+        pid: u64,
+        pages: &[(u64, page::PageEntry)],
+        probes: &mut ProbeCounts,
+    ) -> Result<Vec<u64>> {
+        let mut gone = Vec::new();
+
+        if self.merge_batch <= 1 {
+            for (addr, entry) in pages {
+                if let Some(outcome) = self.add_or_gone(pid, *addr, entry, &mut gone)? {
+                    probes.record(&outcome);
+                }
+            }
+            return Ok(gone);
+        }
+
+        for chunk in pages.chunks(self.merge_batch) {
+            let mut pairs = Vec::new();
+            let mut entries = Vec::new();
+            for (addr, entry) in chunk {
+                if self.contains(pid, *addr) {
+                    continue;
+                }
+                let new_page = PidAddr { pid, addr: *addr };
+                if let Some(group) = self.shared_group(entry.crc, entry.pfn) {
+                    self.add_shared(new_page, entry, group);
+                    probes.record(&AddOutcome {
+                        merged: true,
+                        matched: true,
+                        probes: 0,
+                    });
+                    continue;
+                }
+                match self.pages.get(&entry.crc) {
+                    // Add the page that cannot be merged with the first group
+                    // because of the uid or the scope one by one.
+                    Some(pagesvec)
+                        if (!self.allow_cross_uid_merge
+                            && !uid_allowed(&self.uids, pagesvec[0][0].pid, pid))
+                            || !scope_allowed(self.merge_scope, &pagesvec[0], pid) =>
+                    {
+                        if let Some(outcome) = self.add_or_gone(pid, *addr, entry, &mut gone)? {
+                            probes.record(&outcome);
+                        }
+                    }
+                    Some(pagesvec) => {
+                        pairs.push((pagesvec[0][0].clone(), new_page));
+                        entries.push(entry);
+                    }
+                    None => {
+                        self.pages.insert(entry.crc, vec![vec![new_page.clone()]]);
+                        self.set_loc(
+                            &new_page,
+                            PageLoc {
+                                crc: entry.crc,
+                                group: 0,
+                                pos: 0,
+                                seq: 0,
+                                pfn: entry.pfn,
+                                cow: false,
+                            },
+                        );
                     }
-                    removed = true;
-                    break;
                 }
             }
-            if should_remove_empty_pages {
-                pagesvec.retain(|pa| !pa.is_empty());
-                if pagesvec.is_empty() {
-                    should_remove_crc = true;
+            if pairs.is_empty() {
+                continue;
+            }
+
+            let merged = {
+                let _timer = timing::start(timing::Op::MergePages);
+                self.backend.cmp_merge_batch(&pairs)?
+            };
+            self.compares += merged.len() as u64;
+            self.compares_merged += merged.iter().filter(|m| **m).count() as u64;
+            for (((_, new_page), entry), merged) in pairs.into_iter().zip(entries).zip(merged) {
+                if !merged {
+                    // The first group is compared in the batch already.
+                    if let Some(mut outcome) =
+                        self.add_or_gone(pid, new_page.addr, entry, &mut gone)?
+                    {
+                        outcome.matched = true;
+                        outcome.probes += 1;
+                        probes.record(&outcome);
+                    }
+                    continue;
+                }
+                probes.record(&AddOutcome {
+                    merged: true,
+                    matched: true,
+                    probes: 1,
+                });
+                if let Some(pagesvec) = self.pages.get_mut(&entry.crc) {
+                    let loc = PageLoc {
+                        crc: entry.crc,
+                        group: 0,
+                        pos: pagesvec[0].len(),
+                        seq: 0,
+                        pfn: entry.pfn,
+                        cow: false,
+                    };
+                    pagesvec[0].push(new_page.clone());
+                    self.set_loc(&new_page, loc);
                 }
             }
         }
 
-        if should_remove_crc {
-            self.pages.remove(&crc);
+        Ok(gone)
+    }
+
+    pub fn remove(&mut self, pid: u64, addr: u64) {
+        let loc = match self.index.get_mut(&pid) {
+            Some(addrs) => {
+                let loc = addrs.remove(&addr);
+                if addrs.is_empty() {
+                    self.index.remove(&pid);
+                }
+                loc
+            }
+            None => None,
+        };
+        let loc = match loc {
+            Some(loc) => loc,
+            None => {
+                error!("uksm.remove cannot get {} 0x{:x}", pid, addr);
+                return;
+            }
+        };
+        self.unlink_frame(&loc);
+
+        let pagesvec = match self.pages.get_mut(&loc.crc) {
+            Some(pagesvec) => pagesvec,
+            None => {
+                error!(
+                    "uksm.remove cannot get crc {} of {} 0x{:x}",
+                    loc.crc, pid, addr
+                );
+                return;
+            }
+        };
+
+        // swap_remove the page and the group, then fix the locations of
+        // the page and the group that were moved into their places.
+        let pages = &mut pagesvec[loc.group];
+        pages.swap_remove(loc.pos);
+        let mut moved = Vec::new();
+        if let Some(page) = pages.get(loc.pos) {
+            moved.push((page.clone(), loc));
         }
+        if pages.is_empty() {
+            pagesvec.swap_remove(loc.group);
+            if let Some(pages) = pagesvec.get(loc.group) {
+                for (pos, page) in pages.iter().enumerate() {
+                    moved.push((
+                        page.clone(),
+                        PageLoc {
+                            crc: loc.crc,
+                            group: loc.group,
+                            pos,
+                            seq: 0,
+                            pfn: 0,
+                            cow: false,
+                        },
+                    ));
+                }
+            }
+            if pagesvec.is_empty() {
+                self.pages.remove(&loc.crc);
+            }
+        }
+
+        for (page, loc) in moved {
+            self.set_loc(&page, loc);
+        }
+    }
 
-        if !removed {
-            error!("uksm.remove cannot get {} 0x{:x} {}", pid, addr, crc);
+    // Remove all the pages of pid, for example when pid is gone.
+    // Return the number of the removed pages.
+    pub fn remove_pid(&mut self, pid: u64) -> usize {
+        let addrs: Vec<u64> = match self.index.get(&pid) {
+            Some(addrs) => addrs.keys().copied().collect(),
+            None => return 0,
+        };
+
+        for addr in addrs.iter() {
+            self.remove(pid, *addr);
         }
+
+        addrs.len()
+    }
+
+    // Disable the transparent huge page of the page before merging it.
+    pub fn split_thp(&self, pid: u64, addr: u64) -> Result<()> {
+        self.backend.split_thp(&PidAddr { pid, addr })
+    }
+
+    // The pairs of pages that are written to the kernel and the ones that
+    // are merged.
+    pub fn compares(&self) -> (u64, u64) {
+        (self.compares, self.compares_merged)
+    }
+
+    // Get the pages that are not the same as the pages of their crcs since
+    // the last call.
+    pub fn take_not_same(&mut self) -> Vec<(PidAddr, u32)> {
+        std::mem::take(&mut self.not_same)
+    }
+
+    // Get the pages that the kernel rejected to merge since the last call.
+    pub fn take_rejected(&mut self) -> Vec<PidAddr> {
+        std::mem::take(&mut self.rejected)
+    }
+
+    // The kernel unmerged all the pages, for example the uksm module was
+    // reloaded. Forget them.
+    pub fn reset(&mut self) {
+        self.pages.clear();
+        self.index.clear();
+        self.frames.clear();
+        self.cows.clear();
+        self.not_same.clear();
+        self.rejected.clear();
     }
 
-    pub fn unmerge(&mut self, pid: u64, addr: u64, entry: &page::PageEntry) -> Result<()> {
-        unmerge_pages(&PidAddr { pid, addr })
-            .map_err(|e| anyhow!("unmerge_pages failed: {}", e))?;
+    pub fn unmerge(&mut self, pid: u64, addr: u64) -> Result<()> {
+        self.backend.unmerge(&PidAddr { pid, addr })?;
 
-        self.remove(pid, addr, entry.crc);
+        self.remove(pid, addr);
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::fake::FakeKernel;
+    use std::time::Instant;
+    use structopt::StructOpt;
+
+    // The xorshift64 generator, the tests are the same in each run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    fn new_uksm(args: &[&str], kernel: &Arc<FakeKernel>) -> Uksm {
+        let config = config::Config::from_iter(["uksmd"].iter().chain(args).copied());
+        Uksm::new(&config, kernel.clone())
+    }
+
+    fn entry(data: u64, pfn: u64) -> page::PageEntry {
+        page::PageEntry {
+            crc: data as u32,
+            pfn,
+            cow: false,
+        }
+    }
+
+    fn addr(i: u64) -> u64 {
+        0x1000_0000 + i * *page::PAGE_SIZE
+    }
+
+    fn is_cow(uksm: &Uksm, page: &PidAddr) -> bool {
+        uksm.index
+            .get(&page.pid)
+            .and_then(|addrs| addrs.get(&page.addr))
+            .is_some_and(|loc| loc.cow)
+    }
+
+    // Check frames and cows with the ones that are built from the groups.
+    fn check_frames(uksm: &Uksm) {
+        let mut want: HashMap<(u32, u64, usize), usize> = HashMap::new();
+        let mut cows: HashMap<(u32, usize), usize> = HashMap::new();
+        for (crc, pagesvec) in uksm.pages.iter() {
+            for (group, pages) in pagesvec.iter().enumerate() {
+                for (pos, page) in pages.iter().enumerate() {
+                    let loc = uksm.index[&page.pid][&page.addr];
+                    assert_eq!((loc.crc, loc.group, loc.pos), (*crc, group, pos));
+                    if loc.pfn != 0 {
+                        *want.entry((*crc, loc.pfn, group)).or_default() += 1;
+                    }
+                    if loc.cow {
+                        *cows.entry((*crc, group)).or_default() += 1;
+                    }
+                }
+            }
+        }
+        let mut got: HashMap<(u32, u64, usize), usize> = HashMap::new();
+        for ((crc, pfn), groups) in uksm.frames.iter() {
+            assert!(!groups.is_empty());
+            for (group, count) in groups {
+                got.insert((*crc, *pfn, *group), *count);
+            }
+        }
+        assert_eq!(got, want);
+        assert_eq!(uksm.cows, cows);
+    }
+
+    #[test]
+    fn test_frames_follow_the_groups() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut uksm = new_uksm(&["--merge-attempts", "0"], &kernel);
+        // Two contents of each crc, the pages of them are in two groups.
+        let contents = [1, 1 | 1 << 32, 2, 2 | 1 << 32];
+        let pids = [1 << 23, (1 << 23) + 1, (1 << 23) + 2];
+        for pid in pids {
+            kernel.add_task(pid);
+        }
+
+        let mut rng = Rng(0x9e3779b97f4a7c15);
+        let mut data: HashMap<(u64, u64), u64> = HashMap::new();
+        for _ in 0..2000 {
+            let pid = pids[rng.below(3) as usize];
+            let a = addr(rng.below(16));
+            match rng.below(4) {
+                0 | 1 if !uksm.contains(pid, a) => {
+                    let d = contents[rng.below(4) as usize];
+                    kernel.write(pid, a, d);
+                    data.insert((pid, a), d);
+                    uksm.add(pid, a, &entry(d, rng.below(4))).unwrap();
+                }
+                2 if uksm.contains(pid, a) => uksm.remove(pid, a),
+                3 if uksm.contains(pid, a) => {
+                    uksm.set_pfn(pid, a, rng.below(4), rng.below(2) == 0);
+                }
+                _ => continue,
+            }
+            check_frames(&uksm);
+
+            // The group of each frame is the first one that has a page in it.
+            for d in contents {
+                for pfn in 1..4 {
+                    let want = uksm.pages.get(&(d as u32)).and_then(|pagesvec| {
+                        pagesvec.iter().position(|pages| {
+                            pages
+                                .iter()
+                                .any(|page| uksm.index[&page.pid][&page.addr].pfn == pfn)
+                        })
+                    });
+                    assert_eq!(uksm.shared_group(d as u32, pfn), want);
+                }
+            }
+        }
+
+        for pid in pids {
+            uksm.remove_pid(pid);
+        }
+        assert!(uksm.frames.is_empty());
+    }
+
+    #[test]
+    fn test_shared_frame_joins_without_merge() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut uksm = new_uksm(&[], &kernel);
+        let (a, b, c) = (1 << 23, (1 << 23) + 1, (1 << 23) + 2);
+        for pid in [a, b, c] {
+            kernel.add_task(pid);
+            kernel.write(pid, addr(0), 7);
+        }
+
+        uksm.add(a, addr(0), &entry(7, 10)).unwrap();
+        let outcome = uksm.add(b, addr(0), &entry(7, 10)).unwrap();
+        assert!(outcome.merged);
+        assert_eq!(outcome.probes, 0);
+        assert_eq!(kernel.merges(), 0);
+        assert_eq!(uksm.shared_frames(), 1);
+        assert!(uksm.frame_shared(a, addr(0), 10));
+        assert!(!uksm.frame_shared(a, addr(0), 11));
+
+        // The page in another frame is merged by the kernel.
+        let outcome = uksm.add(c, addr(0), &entry(7, 11)).unwrap();
+        assert_eq!((outcome.merged, outcome.probes), (true, 1));
+        assert_eq!(kernel.merges(), 1);
+
+        // The frame is not shared after one of its pages moves away.
+        uksm.set_pfn(b, addr(0), 12, true);
+        assert!(!uksm.frame_shared(a, addr(0), 10));
+        check_frames(&uksm);
+    }
+
+    #[test]
+    fn test_cow_is_kept() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut uksm = new_uksm(&[], &kernel);
+        let (a, b, c) = (1 << 23, (1 << 23) + 1, (1 << 23) + 2);
+        for pid in [a, b, c] {
+            kernel.add_task(pid);
+        }
+        kernel.write(a, addr(0), 7);
+        kernel.fork(a, c);
+        kernel.write(b, addr(0), 7);
+        kernel.write(b, addr(1), 7);
+
+        let cow = page::PageEntry {
+            crc: 7,
+            pfn: 10,
+            cow: true,
+        };
+        uksm.add(a, addr(0), &entry(7, 10)).unwrap();
+        uksm.add(b, addr(1), &entry(7, 0)).unwrap();
+        uksm.add(b, addr(0), &entry(7, 0)).unwrap();
+        uksm.add(c, addr(0), &cow).unwrap();
+        let pc = PidAddr {
+            pid: c,
+            addr: addr(0),
+        };
+        assert!(is_cow(&uksm, &pc));
+        assert_eq!(uksm.saved_total(), 2);
+        // The saving of the group is shared by its pages.
+        assert_eq!(
+            [a, b, c].map(|pid| uksm.saved_pages(pid, &uksm.pid_addrs(pid))),
+            [0.5, 1.0, 0.5]
+        );
+
+        // c is moved to the place of the removed page.
+        uksm.remove(b, addr(1));
+        assert_eq!(uksm.index[&c][&addr(0)].pos, 1);
+        assert!(is_cow(&uksm, &pc));
+        assert_eq!(uksm.saved_total(), 1);
+
+        let mut loaded = new_uksm(&[], &kernel);
+        loaded.load_state(uksm.to_state(), &HashSet::from([a, b, c]));
+        assert!(is_cow(&loaded, &pc));
+        assert!(loaded.frame_shared(c, addr(0), 10));
+        assert_eq!(loaded.saved_total(), 1);
+        check_frames(&loaded);
+    }
+
+    #[test]
+    fn test_remove_does_not_scan_the_bucket() {
+        let kernel = Arc::new(FakeKernel::new());
+        let mut uksm = new_uksm(&["--merge-attempts", "0"], &kernel);
+
+        // The pages of the pids are the groups of the same crc, each group
+        // has a page of every pid.
+        let pids: Vec<u64> = (0..4).map(|i| (1 << 23) + i).collect();
+        let n = 64;
+        for pid in pids.iter() {
+            kernel.add_task(*pid);
+            for i in 0..n {
+                let data = 7 | i << 32;
+                kernel.write(*pid, addr(i), data);
+                uksm.add(*pid, addr(i), &entry(data, 0)).unwrap();
+            }
+        }
+        assert_eq!(uksm.pages[&7].len(), n as usize);
+        check_frames(&uksm);
+
+        // A remove takes the page from its place in the index and moves the
+        // last page and the last group into the holes, the index keeps the
+        // places of the moved ones without a scan of the bucket.
+        let mut left = pids.len() * n as usize;
+        for i in (0..n).map(|i| i * 37 % n) {
+            for j in 0..pids.len() {
+                let pid = &pids[(i as usize + j) % pids.len()];
+                uksm.remove(*pid, addr(i));
+                left -= 1;
+                assert!(!uksm
+                    .index
+                    .get(pid)
+                    .is_some_and(|m| m.contains_key(&addr(i))));
+                let total: usize = uksm.index.values().map(|m| m.len()).sum();
+                assert_eq!(total, left);
+                check_frames(&uksm);
+            }
+        }
+        assert!(uksm.pages.is_empty());
+        assert!(uksm.index.is_empty());
+    }
+
+    #[test]
+    fn test_merge_attempts_are_capped() {
+        let kernel = Arc::new(FakeKernel::new());
+        let (a, b) = (1 << 23, (1 << 23) + 1);
+        for pid in [a, b] {
+            kernel.add_task(pid);
+        }
+        // The pages of a are the groups of the same crc.
+        for i in 0..20 {
+            kernel.write(a, addr(i), 7 | i << 32);
+        }
+
+        for (attempts, probes) in [(8, 124), (0, 190)] {
+            let mut uksm = new_uksm(&["--merge-attempts", &attempts.to_string()], &kernel);
+            let merges = kernel.merges();
+            for i in 0..20 {
+                let outcome = uksm.add(a, addr(i), &entry(7 | i << 32, 0)).unwrap();
+                let i = i as usize;
+                let want = if attempts == 0 { i } else { i.min(attempts) };
+                assert_eq!((outcome.merged, outcome.probes), (false, want));
+            }
+            assert_eq!(kernel.merges() - merges, probes);
+            assert_eq!(uksm.give_ups(), if attempts == 0 { 0 } else { 11 });
+            assert_eq!(uksm.pages[&7].len(), 20);
+
+            // Only the first page of a group is tried.
+            for i in 0..3 {
+                kernel.write(b, addr(i), 7);
+                let outcome = uksm.add(b, addr(i), &entry(7, 0)).unwrap();
+                assert_eq!((outcome.merged, outcome.probes), (true, 1));
+            }
+            assert_eq!(uksm.pages[&7][0].len(), 4);
+            uksm.remove_pid(b);
+        }
+    }
+
+    // A KernelFile of a temp file.
+    fn temp_kernel_file(name: &str) -> KernelFile {
+        let path = std::env::temp_dir().join(format!("uksmd-{}-{}", name, std::process::id()));
+        std::fs::write(&path, "").unwrap();
+        let path: &'static str = Box::leak(path.to_str().unwrap().to_string().into_boxed_str());
+        KernelFile::new(
+            path,
+            RetryPolicy {
+                retries: 0,
+                delay: Duration::ZERO,
+            },
+        )
+    }
+
+    #[test]
+    fn test_kernel_file_is_kept_open() {
+        let file = temp_kernel_file("kept-open");
+        file.write("1 0x1000 2 0x1000").unwrap();
+
+        // The writes of a merge of 10k pages go to the file that is opened
+        // once, an open of each write fails after the file is removed.
+        std::fs::remove_file(file.path).unwrap();
+        for i in 0..10000 {
+            file.write(&format!("1 0x{:x} 2 0x{:x}", i << 12, i << 12))
+                .unwrap();
+        }
+        assert!(file.file.lock().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_kernel_file_is_reopened() {
+        let file = temp_kernel_file("reopened");
+        // The old handle cannot be written, for example the kernel module
+        // was reloaded.
+        *file.file.lock().unwrap() = Some(File::open(file.path).unwrap());
+        file.write("1 0x1000").unwrap();
+        assert_eq!(std::fs::read_to_string(file.path).unwrap(), "1 0x1000");
+
+        std::fs::remove_file(file.path).unwrap();
+        *file.file.lock().unwrap() = None;
+        let e = file.write("1 0x1000").unwrap_err();
+        assert_eq!(e.raw_os_error(), Some(libc::ENOENT));
+        assert_eq!(file.retried.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_write_error() {
+        let alive = crate::proc::fake::new_pid();
+        crate::proc::fake::FakeTask::new(alive).write();
+        let gone = crate::proc::fake::new_pid();
+        let pa1 = PidAddr {
+            pid: gone,
+            addr: addr(0),
+        };
+        let pa2 = PidAddr {
+            pid: alive,
+            addr: addr(1),
+        };
+        let error = |errno: i32, pas: &[&PidAddr]| {
+            let e = write_error(
+                std::io::Error::from_raw_os_error(errno),
+                "merge",
+                "cmd",
+                pas,
+            );
+            e.downcast::<KernelError>().map_err(|e| e.to_string())
+        };
+
+        // ESRCH is the task that is gone, not the last one.
+        assert!(
+            matches!(error(libc::ESRCH, &[&pa1, &pa2]), Ok(KernelError::TaskGone(pid)) if pid == gone)
+        );
+        assert!(error(libc::ESRCH, &[&pa2]).is_err());
+        assert!(matches!(
+            error(libc::EFAULT, &[&pa1, &pa2]),
+            Ok(KernelError::PageGone(pid, a)) if (pid, a) == (alive, addr(1))
+        ));
+        assert!(matches!(
+            error(libc::ENOMEM, &[&pa2]),
+            Ok(KernelError::NoMem(_))
+        ));
+        assert!(matches!(
+            error(libc::ENOENT, &[&pa2]),
+            Ok(KernelError::Lost(_))
+        ));
+        assert!(error(libc::EIO, &[&pa2]).is_err_and(|e| e.contains("merge cmd")));
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let retry = RetryPolicy {
+            retries: 3,
+            delay: Duration::from_millis(1),
+        };
+        let retried = AtomicU64::new(0);
+        // The write fails with errno for fails times.
+        let run = |errno: i32, fails: u32| {
+            let mut calls = 0;
+            let now = Instant::now();
+            let ret = retry.run(&retried, || {
+                calls += 1;
+                if calls <= fails {
+                    Err(std::io::Error::from_raw_os_error(errno))
+                } else {
+                    Ok(8)
+                }
+            });
+            (ret.map_err(|e| e.raw_os_error()), calls, now.elapsed())
+        };
+
+        // The delay is doubled for each retry.
+        let (ret, calls, elapsed) = run(libc::EAGAIN, 2);
+        assert_eq!((ret, calls), (Ok(8), 3));
+        assert!(elapsed >= Duration::from_millis(3));
+        assert_eq!(retried.load(Ordering::Relaxed), 2);
+
+        let (ret, calls, _) = run(libc::EBUSY, 10);
+        assert_eq!((ret, calls), (Err(Some(libc::EBUSY)), 4));
+        assert_eq!(retried.load(Ordering::Relaxed), 5);
+
+        // ENOMEM is not retried, the merge is deferred.
+        let (ret, calls, _) = run(libc::ENOMEM, 1);
+        assert_eq!((ret, calls), (Err(Some(libc::ENOMEM)), 1));
+        assert_eq!(retried.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_kernel_stats() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/uksm/testdata");
+        let stats = read_stats_dirs(&[
+            testdata.join("sysfs-uksm/uksm"),
+            testdata.join("sysfs-ksm/ksm"),
+            testdata.join("sysfs-none/ksm"),
+        ]);
+
+        let mut names: Vec<&str> = stats.keys().map(|name| name.as_str()).collect();
+        names.sort_unstable();
+        // The files that are not a number and the dirs are skipped.
+        assert_eq!(
+            names,
+            [
+                "ksm/full_scans",
+                "ksm/ksm_zero_pages",
+                "ksm/pages_shared",
+                "ksm/pages_sharing",
+                "ksm/pages_unshared",
+                "ksm/pages_volatile",
+                "ksm/stable_node_chains",
+                "uksm/full_scans",
+                "uksm/pages_shared",
+                "uksm/pages_sharing",
+                "uksm/run",
+                "uksm/sleep_millisecs",
+            ]
+        );
+        assert_eq!(stats["uksm/pages_sharing"], 8192);
+        assert_eq!(stats["ksm/pages_shared"], 512);
+    }
+
+    #[test]
+    fn test_read_uksm_pagemap_chunked() {
+        let pid = crate::proc::fake::new_pid();
+        let start = crate::proc::fake::MAP_START;
+        let first = start / *page::PAGE_SIZE;
+        // The entries of 1000 pages, the file ends in the middle of the
+        // last entry, so the pages after it are absent.
+        let count = 1000;
+        let mut want = Vec::new();
+        let mut pagemap = vec![0u8; (first * UKSM_PAGEMAP_ENTRY_SIZE) as usize];
+        for i in 0..count {
+            let (pfn, mut uksm_pme) = (0x1000 + i, i & UKSM_CRC_MASK);
+            let entry = match i % 5 {
+                0 => None,
+                _ => {
+                    uksm_pme |= UKSM_CRC_PRESENT;
+                    if i % 5 == 3 {
+                        uksm_pme |= UKSM_PM_THP;
+                    }
+                    if i % 5 == 4 {
+                        uksm_pme |= UKSM_PM_KSM;
+                    }
+                    Some(UKSMPagemapEntry {
+                        pfn,
+                        crc: i as u32,
+                        is_thp: i % 5 == 3,
+                        is_ksm: i % 5 == 4,
+                        idle: None,
+                    })
+                }
+            };
+            want.push(entry);
+            pagemap.extend_from_slice(&pfn.to_ne_bytes());
+            pagemap.extend_from_slice(&uksm_pme.to_ne_bytes());
+        }
+        pagemap.extend_from_slice(&[0xff; 12]);
+        want.resize(count as usize + 100, None);
+        crate::proc::fake::FakeTask::new(pid).write();
+        let path = crate::proc::fake::root().join(format!("{}/uksm_pagemap", pid));
+        std::fs::write(path, pagemap).unwrap();
+
+        // The entries are the same with any chunk size, and each chunk is
+        // no larger than the chunk size.
+        let end = start + want.len() as u64 * *page::PAGE_SIZE;
+        for chunk_pages in [1, 7, 256, 4096] {
+            let mut got = Vec::new();
+            read_uksm_pagemap_chunked(pid, start, end, chunk_pages, &mut |addr, entries| {
+                assert_eq!(addr, start + got.len() as u64 * *page::PAGE_SIZE);
+                assert!(entries.len() as u64 <= chunk_pages);
+                got.extend_from_slice(entries);
+                Ok(())
+            })
+            .unwrap();
+            assert_eq!(got, want, "{}", chunk_pages);
+        }
+        crate::proc::fake::FakeTask::exit(pid);
+    }
+
+    // A reader that returns at most step bytes in a read, and is
+    // interrupted before each read.
+    struct ShortReader {
+        data: Vec<u8>,
+        pos: usize,
+        step: usize,
+        interrupted: bool,
+    }
+
+    impl Read for ShortReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.interrupted = !self.interrupted;
+            if self.interrupted {
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            let n = buf.len().min(self.step).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_read_full() {
+        let data: Vec<u8> = (0..100).collect();
+        // The reads are torn in the middle of the entries.
+        for step in [1, 5, 16, 33, 200] {
+            let mut reader = ShortReader {
+                data: data.clone(),
+                pos: 0,
+                step,
+                interrupted: false,
+            };
+            let mut buf = vec![0u8; 64];
+            assert_eq!(read_full(&mut reader, &mut buf).unwrap(), 64);
+            assert_eq!(buf, data[..64]);
+            // The rest is short at EOF.
+            assert_eq!(read_full(&mut reader, &mut buf).unwrap(), 36);
+            assert_eq!(buf[..36], data[64..]);
+            assert_eq!(read_full(&mut reader, &mut buf).unwrap(), 0);
+        }
+    }
+}
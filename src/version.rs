@@ -0,0 +1,53 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+//! The version of uksmd and the level of its api, which uksmd-ctl checks
+//! against the daemon.
+
+/// The semantic version of uksmd.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The level of the api, it is increased when an rpc is added or its
+/// response reports more.
+pub const API_LEVEL: u32 = 6;
+
+/// The api level that AddResponse reports the options of the task in.
+pub const ADD_SUMMARY_LEVEL: u32 = 4;
+
+/// The api level that Del handles wait in, the older ones return at once.
+pub const DEL_WAIT_LEVEL: u32 = 5;
+
+/// The rpcs and the api levels that they are added in.
+/// The daemons that do not have GetVersion are older than level 1.
+pub const RPC_LEVELS: &[(&str, u32)] = &[
+    ("Add", 1),
+    ("AddByContainer", 1),
+    ("Del", 1),
+    ("Refresh", 1),
+    ("Merge", 1),
+    ("Status", 1),
+    ("Stats", 1),
+    ("SetConfig", 1),
+    ("PauseTask", 1),
+    ("ResumeTask", 1),
+    ("Unmerge", 1),
+    ("History", 1),
+    ("RecentEvents", 1),
+    ("GetMaps", 1),
+    ("CrcHistogram", 1),
+    ("WatchCgroup", 1),
+    ("UnwatchCgroup", 1),
+    ("GetVersion", 1),
+    ("Drain", 2),
+    ("UnmergeAll", 3),
+    ("Update", 6),
+];
+
+/// The api level that rpc is added in.
+pub fn rpc_level(rpc: &str) -> Option<u32> {
+    RPC_LEVELS
+        .iter()
+        .find(|(name, _)| *name == rpc)
+        .map(|(_, level)| *level)
+}
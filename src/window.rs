@@ -0,0 +1,211 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The time windows of the local time in which the merges run, for example
+// "01:00-05:00" or "sat,sun@00:00-08:00".
+// A window that ends before its start wraps around midnight, its days are
+// the days it starts.
+
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Weekday};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeWindow {
+    // empty means every day
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl FromStr for MergeWindow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (days, range) = match s.split_once('@') {
+            Some((days, range)) => (Some(days), range),
+            None => (None, s),
+        };
+
+        let days = match days {
+            Some(days) => days
+                .split(',')
+                .map(|day| {
+                    day.trim()
+                        .parse::<Weekday>()
+                        .map_err(|_| anyhow!("day {} of merge window {} is not right", day, s))
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+
+        let (start, end) = range
+            .split_once('-')
+            .ok_or(anyhow!("merge window {} is not right", s))?;
+        let parse = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|e| anyhow!("time {} of merge window {} is not right: {}", time, s, e))
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start == end {
+            return Err(anyhow!("merge window {} is empty", s));
+        }
+
+        Ok(Self { days, start, end })
+    }
+}
+
+impl MergeWindow {
+    fn on_day(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let time = now.time();
+        let today = now.weekday();
+
+        if self.start < self.end {
+            self.on_day(today) && self.start <= time && time < self.end
+        } else {
+            // The window that started yesterday is still open.
+            (self.on_day(today) && time >= self.start)
+                || (self.on_day(today.pred()) && time < self.end)
+        }
+    }
+
+    // The next start of the window after now.
+    pub fn next_start(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        (0..=7)
+            .map(|days| now.date() + Duration::days(days))
+            .filter(|date| self.on_day(date.weekday()))
+            .map(|date| date.and_time(self.start))
+            .find(|start| *start > now)
+    }
+}
+
+// The merges can run at any time if there is no window.
+pub fn in_windows(windows: &[MergeWindow], now: NaiveDateTime) -> bool {
+    windows.is_empty() || windows.iter().any(|w| w.contains(now))
+}
+
+pub fn next_window(windows: &[MergeWindow], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    windows.iter().filter_map(|w| w.next_start(now)).min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    // The time of a day of the week of 2024-01-01, a Monday.
+    fn at(day: Weekday, time: &str) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1 + day.num_days_from_monday())
+            .unwrap()
+            .and_time(NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+    }
+
+    fn window(s: &str) -> MergeWindow {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(
+            window("sat, sun@00:00-08:30"),
+            MergeWindow {
+                days: vec![Weekday::Sat, Weekday::Sun],
+                start: NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(8, 30, 0).unwrap(),
+            }
+        );
+        assert!(window("22:00-02:00").days.is_empty());
+
+        for s in [
+            "",
+            "01:00",
+            "01:00-01:00",
+            "25:00-02:00",
+            "foo@01:00-02:00",
+            "01:00-02:00-03:00",
+        ] {
+            assert!(s.parse::<MergeWindow>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_window_contains() {
+        let w = window("01:00-05:00");
+        assert!(!w.contains(at(Weekday::Mon, "00:59")));
+        assert!(w.contains(at(Weekday::Mon, "01:00")));
+        assert!(w.contains(at(Weekday::Mon, "04:59")));
+        assert!(!w.contains(at(Weekday::Mon, "05:00")));
+
+        let w = window("fri@01:00-05:00");
+        assert!(w.contains(at(Weekday::Fri, "02:00")));
+        assert!(!w.contains(at(Weekday::Sat, "02:00")));
+    }
+
+    #[test]
+    fn test_window_wraps_around_midnight() {
+        let w = window("22:00-02:00");
+        assert!(!w.contains(at(Weekday::Mon, "21:59")));
+        assert!(w.contains(at(Weekday::Mon, "22:00")));
+        assert!(w.contains(at(Weekday::Mon, "23:59")));
+        assert!(w.contains(at(Weekday::Tue, "00:00")));
+        assert!(w.contains(at(Weekday::Tue, "01:59")));
+        assert!(!w.contains(at(Weekday::Tue, "02:00")));
+
+        // The days are the ones the window starts, the end of the window
+        // of Sunday is on Monday.
+        let w = window("sun@22:00-02:00");
+        assert!(w.contains(at(Weekday::Sun, "23:00")));
+        assert!(w.contains(at(Weekday::Mon, "01:00")));
+        assert!(!w.contains(at(Weekday::Mon, "23:00")));
+        assert!(!w.contains(at(Weekday::Sun, "01:00")));
+    }
+
+    #[test]
+    fn test_next_start() {
+        let w = window("22:00-02:00");
+        assert_eq!(
+            w.next_start(at(Weekday::Mon, "12:00")),
+            Some(at(Weekday::Mon, "22:00"))
+        );
+        // The window is open, the next start is the one of tomorrow.
+        assert_eq!(
+            w.next_start(at(Weekday::Mon, "22:00")),
+            Some(at(Weekday::Tue, "22:00"))
+        );
+        assert_eq!(
+            w.next_start(at(Weekday::Tue, "01:00")),
+            Some(at(Weekday::Tue, "22:00"))
+        );
+
+        // The window of a day of the next week.
+        let w = window("mon@01:00-05:00");
+        assert_eq!(
+            w.next_start(at(Weekday::Mon, "01:00")),
+            Some(at(Weekday::Mon, "01:00") + Duration::days(7))
+        );
+    }
+
+    #[test]
+    fn test_windows() {
+        let windows = [window("sat,sun@00:00-08:00"), window("01:00-05:00")];
+        assert!(in_windows(&[], at(Weekday::Wed, "12:00")));
+        assert!(in_windows(&windows, at(Weekday::Sat, "07:00")));
+        assert!(in_windows(&windows, at(Weekday::Wed, "04:00")));
+        assert!(!in_windows(&windows, at(Weekday::Wed, "07:00")));
+
+        assert_eq!(next_window(&[], at(Weekday::Wed, "12:00")), None);
+        assert_eq!(
+            next_window(&windows, at(Weekday::Fri, "12:00")),
+            Some(at(Weekday::Sat, "00:00"))
+        );
+        assert_eq!(
+            next_window(&windows, at(Weekday::Sat, "00:30")),
+            Some(at(Weekday::Sat, "01:00"))
+        );
+    }
+}
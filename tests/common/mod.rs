@@ -0,0 +1,128 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// A fake procfs and uksm interface under a temp dir for the tests that drive
+// the engine with the uksm backend.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+use uksmd::agent::{AgentCmd, AgentReturn};
+use uksmd::protocols::uksmd_ctl;
+use uksmd::{Config, Engine};
+
+pub const MAP_START: u64 = 0x1000_0000;
+const UKSM_CRC_PRESENT: u64 = 1 << 63;
+
+fn page_size() -> u64 {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as u64 }
+}
+
+pub fn write(path: PathBuf, content: &[u8]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+// Write the files of a task that has a map of the pages of crcs.
+pub fn write_task(proc: &Path, pid: u64, crcs: &[u32]) {
+    let dir = proc.join(pid.to_string());
+    let end = MAP_START + crcs.len() as u64 * page_size();
+    let kb = (end - MAP_START) / 1024;
+    let map = format!("{:x}-{:x} rw-p 00000000 00:00 0\n", MAP_START, end);
+
+    write(
+        dir.join("status"),
+        format!(
+            "Name:\tfake\nState:\tS (sleeping)\nTgid:\t{pid}\nPPid:\t1\nUid:\t0\t0\t0\t0\n\
+             VmSize:\t  102400 kB\nVmRSS:\t    4096 kB\n"
+        )
+        .as_bytes(),
+    );
+    write(dir.join("comm"), b"fake\n");
+    write(dir.join("cmdline"), b"fake\0");
+    // ppid, starttime, startcode and startstack are the 4th, 22nd, 26th
+    // and 28th fields.
+    let mut fields = vec!["0"; 26];
+    fields[0] = "S";
+    fields[1] = "1";
+    fields[19] = "100";
+    fields[23] = "4194304";
+    fields[25] = "140668768878592";
+    write(
+        dir.join("stat"),
+        format!("{} (fake) {}\n", pid, fields.join(" ")).as_bytes(),
+    );
+    write(dir.join("maps"), map.as_bytes());
+    write(
+        dir.join("smaps"),
+        format!(
+            "{map}Size:           {kb} kB\nRss:            {kb} kB\nAnonymous:      {kb} kB\n\
+             Shared_Hugetlb:        0 kB\nPrivate_Hugetlb:       0 kB\n\
+             VmFlags: rd wr mr mw me ac\n"
+        )
+        .as_bytes(),
+    );
+    write(dir.join(format!("task/{}/children", pid)), b" ");
+
+    // The entries of uksm_pagemap are indexed by the page number, each one
+    // is the pfn and the crc. The pages of the tasks are in their own frames
+    // and are not shared.
+    let mut pagemap = vec![0u8; (MAP_START / page_size() * 16) as usize];
+    for (i, crc) in crcs.iter().enumerate() {
+        pagemap.extend_from_slice(&(pid << 8 | i as u64).to_ne_bytes());
+        pagemap.extend_from_slice(&(UKSM_CRC_PRESENT | *crc as u64).to_ne_bytes());
+    }
+    write(dir.join("uksm_pagemap"), &pagemap);
+}
+
+pub async fn stats(engine: &Engine) -> uksmd_ctl::StatsResponse {
+    match engine.send_cmd(AgentCmd::Stats).await.unwrap() {
+        AgentReturn::Stats(stats) => *stats,
+        ret => panic!("agent return for Stats is {:?}", ret),
+    }
+}
+
+// Wait until f returns true.
+pub async fn wait_for<F: std::future::Future<Output = bool>>(mut f: impl FnMut() -> F) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !f().await {
+        assert!(Instant::now() < deadline, "timeout");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+// The fake procfs and uksm interface of the test process, all the tests of
+// the process share them because the roots of the engine are global.
+pub fn fake_root() -> &'static Path {
+    ROOT.get_or_init(|| {
+        let root = std::env::temp_dir().join(format!("uksmd-fake-{}", std::process::id()));
+        write(root.join("proc/self/uksm_pagemap"), b"");
+        write(root.join("uksm/merge"), b"");
+        write(root.join("uksm/unmerge"), b"");
+        root
+    })
+}
+
+// The config of an engine that uses the fake root.
+pub fn fake_config(args: &[&str]) -> Config {
+    let root = fake_root();
+    let (proc, uksm) = (root.join("proc"), root.join("uksm"));
+    let mut argv = vec![
+        "uksmd",
+        "--backend",
+        "uksm",
+        "--skip-kernel-check",
+        "--procfs-root",
+        proc.to_str().unwrap(),
+        "--uksmfs-root",
+        uksm.to_str().unwrap(),
+    ];
+    argv.extend_from_slice(args);
+
+    Config::from_iter(argv)
+}
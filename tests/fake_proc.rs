@@ -0,0 +1,57 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// Drive the engine with the uksm backend against a fake procfs and uksm
+// interface under a temp dir.
+
+mod common;
+
+use common::{fake_config, fake_root, stats, wait_for, write_task, MAP_START};
+use std::fs;
+use std::time::Duration;
+use uksmd::protocols::uksmd_ctl;
+use uksmd::Engine;
+
+#[tokio::test]
+async fn test_refresh_and_merge_with_fake_proc() {
+    // The crcs of a cycle are counted by its refreshes, merge the pages of a
+    // task that is refreshed before the other one too.
+    let root = fake_root();
+    let config = fake_config(&["--min-dup-count", "1"]);
+    let (proc, uksm) = (root.join("proc"), root.join("uksm"));
+    // The pids are larger than the max pid of the kernel.
+    let (a, b) = (1 << 23, (1 << 23) + 1);
+    write_task(&proc, a, &[7, 8]);
+    write_task(&proc, b, &[7, 9]);
+
+    let engine = Engine::new(config).unwrap();
+    for pid in [a, b] {
+        engine
+            .add_task(uksmd_ctl::AddRequest {
+                pid,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+    }
+
+    // The pages are read from uksm_pagemap of the fake procfs.
+    engine.refresh().await.unwrap();
+    wait_for(|| async { stats(&engine).await.tracked_pages == 4 }).await;
+    engine.refresh().await.unwrap();
+
+    // The pages of the same crc are merged by the merge file of the fake
+    // uksm interface, the task that merges first is the first one in the
+    // command.
+    engine.merge(Default::default()).await.unwrap();
+    let cmds = [(a, b), (b, a)]
+        .map(|(p1, p2)| format!("{} 0x{:x} {} 0x{:x}", p1, MAP_START, p2, MAP_START));
+    wait_for(|| async {
+        let merge = fs::read_to_string(uksm.join("merge")).unwrap();
+        cmds.iter().any(|cmd| merge.contains(cmd))
+    })
+    .await;
+
+    engine.shutdown(Duration::from_secs(5)).await.unwrap();
+}
@@ -0,0 +1,67 @@
+// Copyright (C) 2024 Ant group. All rights reserved.
+//
+// SPDX-License-Identifier: Apache-2.0
+
+// The timings of the operations are global, check them in their own test
+// process so that the work cycles of the other tests don't take them.
+
+mod common;
+
+use common::{fake_config, fake_root, stats, wait_for, write_task, MAP_START};
+use std::fs;
+use std::time::Duration;
+use uksmd::protocols::uksmd_ctl;
+use uksmd::Engine;
+
+#[tokio::test]
+async fn test_timings_of_merge_cycle() {
+    let root = fake_root();
+    let config = fake_config(&["--min-dup-count", "1", "--timings"]);
+    let (proc, uksm) = (root.join("proc"), root.join("uksm"));
+    let (a, b) = (1 << 23, (1 << 23) + 1);
+    write_task(&proc, a, &[7, 8]);
+    write_task(&proc, b, &[7, 9]);
+
+    let engine = Engine::new(config).unwrap();
+    for pid in [a, b] {
+        engine
+            .add_task(uksmd_ctl::AddRequest {
+                pid,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+    }
+    engine.refresh().await.unwrap();
+    wait_for(|| async { stats(&engine).await.tracked_pages == 4 }).await;
+    engine.refresh().await.unwrap();
+    engine.merge(Default::default()).await.unwrap();
+    wait_for(|| async {
+        fs::read_to_string(uksm.join("merge"))
+            .unwrap()
+            .contains(&format!("0x{:x}", MAP_START))
+    })
+    .await;
+
+    // The timings of the last cycle of each operation are kept by the stats.
+    let names = [
+        "parse_task_smaps",
+        "read_pagemap",
+        "refresh_pages",
+        "merge",
+        "merge_pages",
+    ];
+    wait_for(|| async {
+        let timings = stats(&engine).await.op_timings;
+        names
+            .iter()
+            .all(|name| timings.iter().any(|t| t.name == *name))
+    })
+    .await;
+    for t in stats(&engine).await.op_timings {
+        assert!(t.calls > 0, "{:?}", t);
+        assert!(t.max_us <= t.total_us, "{:?}", t);
+    }
+
+    engine.shutdown(Duration::from_secs(5)).await.unwrap();
+}